@@ -0,0 +1,119 @@
+//! Compliance Alert Delivery
+//!
+//! The kernel's alert monitor raises alerts for overdrawn balances, passed
+//! annual limits, and module crash loops. One task receives them and, for
+//! each, emits an `alert://raised` event to the webview, shows an OS
+//! notification, and posts the alert as JSON to the webhooks configured for
+//! its tenant. The event and notification are skipped unless the signed-in
+//! user may see the alert's tenant (see [`SessionStore::may_receive`]), so
+//! they stop when an admin signs out.
+//!
+//! Webhooks go through Tauri's HTTP client, not the webview, and must be on
+//! a host in `ESTA_WEBHOOK_HOSTS` (checked when they are configured).
+//! Redirects are not followed, so a webhook cannot bounce an alert to a host
+//! off that list. A failed post is logged and not retried.
+
+use crate::session::SessionStore;
+use esta_kernel::{Alert, AlertDelivery, AlertKind};
+use std::time::Duration;
+use tauri::api::http::{Body, Client, ClientBuilder, HttpRequestBuilder};
+use tauri::api::notification::Notification;
+use tauri::Manager;
+use tokio::sync::broadcast;
+
+/// Event carrying a raised alert
+pub const ALERT_EVENT: &str = "alert://raised";
+
+/// Longest a webhook may take to accept an alert
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliver alerts until the kernel's alert monitor is dropped
+pub async fn forward_alerts(app: tauri::AppHandle, mut alerts: broadcast::Receiver<AlertDelivery>) {
+    let client = match ClientBuilder::new().max_redirections(0).connect_timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(client) => Some(client),
+        Err(e) => {
+            log::error!("Failed to create webhook client; alerts will not be posted: {}", e);
+            None
+        }
+    };
+    loop {
+        let delivery = match alerts.recv().await {
+            Ok(delivery) => delivery,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                log::warn!("Alert delivery skipped {} alerts", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        if app.state::<SessionStore>().may_receive(delivery.alert.tenant_id.as_deref()) {
+            if let Err(e) = app.emit_all(ALERT_EVENT, &delivery.alert) {
+                log::error!("Failed to emit alert: {}", e);
+            }
+            let (title, body) = notification(&delivery.alert);
+            let identifier = app.config().tauri.bundle.identifier.clone();
+            if let Err(e) = Notification::new(&identifier).title(title).body(body).show() {
+                log::warn!("Failed to show alert notification: {}", e);
+            }
+        }
+        if let Some(client) = &client {
+            for url in delivery.webhooks {
+                tauri::async_runtime::spawn(post(client.clone(), url, delivery.alert.clone()));
+            }
+        }
+    }
+}
+
+async fn post(client: Client, url: String, alert: Alert) {
+    let payload = match serde_json::to_value(&alert) {
+        Ok(payload) => payload,
+        Err(e) => return log::error!("Failed to encode alert for {}: {}", url, e),
+    };
+    let request = match HttpRequestBuilder::new("POST", &url) {
+        Ok(request) => request.body(Body::Json(payload)).timeout(WEBHOOK_TIMEOUT),
+        Err(e) => return log::error!("Invalid webhook {}: {}", url, e),
+    };
+    match client.send(request).await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => log::warn!("Webhook {} refused alert: HTTP {}", url, response.status()),
+        Err(e) => log::warn!("Failed to post alert to {}: {}", url, e),
+    }
+}
+
+/// Title and body of the notification for an alert
+fn notification(alert: &Alert) -> (&'static str, String) {
+    let title = match alert.kind {
+        AlertKind::NegativeBalanceAttempt => "Sick time over balance",
+        AlertKind::CapExceeded => "Annual sick time limit passed",
+        AlertKind::ModuleCrashLoop => "Module keeps crashing",
+    };
+    let subject = match (&alert.tenant_id, &alert.employee_id) {
+        (Some(tenant), Some(employee)) => format!("Employee {} of {}: ", employee, tenant),
+        (Some(tenant), None) => format!("{}: ", tenant),
+        _ => String::new(),
+    };
+    (title, format!("{}{}", subject, alert.detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_names_the_employee() {
+        let alert = Alert {
+            kind: AlertKind::NegativeBalanceAttempt,
+            tenant_id: Some("acme".into()),
+            employee_id: Some("e1".into()),
+            module_name: None,
+            detail: "used 70 minutes with 50 available".into(),
+            raised_at: 0,
+        };
+        let (title, body) = notification(&alert);
+        assert_eq!(title, "Sick time over balance");
+        assert_eq!(body, "Employee e1 of acme: used 70 minutes with 50 available");
+
+        let crash = Alert { kind: AlertKind::ModuleCrashLoop, tenant_id: None, employee_id: None, ..alert };
+        assert_eq!(notification(&crash).1, "used 70 minutes with 50 available");
+    }
+}
@@ -0,0 +1,163 @@
+//! Live Audit Stream
+//!
+//! The live audit view subscribes instead of polling `kernel_get_logs`. One
+//! task follows the kernel's audit log and, for every new entry, emits an
+//! `audit://entry` event per subscription whose filter matches it. The
+//! payload names the subscription, so several views can share the webview's
+//! event channel. If the task falls behind it emits `audit://lagged` with the
+//! number of entries skipped, which the view can fetch with `kernel_get_logs`.
+//! Subscriptions belong to the signed-in user and are closed when anyone
+//! signs in or out.
+//!
+//! A second task follows the audit log's write health: every change is
+//! emitted as `audit://health`, and an OS notification is shown when segment
+//! writes start failing and when they recover, since privileged operations
+//! may be refused in between.
+
+use esta_kernel::security::audit::AuditEntry;
+use esta_kernel::{AuditFilter, AuditHealth, AuditLog, AuditSubscription, MissedEntries};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tauri::api::notification::Notification;
+use tauri::Manager;
+
+/// Event carrying a new audit entry
+pub const AUDIT_ENTRY_EVENT: &str = "audit://entry";
+
+/// Event reporting entries the stream skipped
+pub const AUDIT_LAGGED_EVENT: &str = "audit://lagged";
+
+/// Event carrying the audit log's write health whenever it changes
+pub const AUDIT_HEALTH_EVENT: &str = "audit://health";
+
+/// Most subscriptions open at once
+const MAX_SUBSCRIPTIONS: usize = 32;
+
+/// Payload of [`AUDIT_ENTRY_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntryEvent {
+    pub subscription_id: u64,
+    pub entry: AuditEntry,
+}
+
+/// Open subscriptions and their filters
+#[derive(Default)]
+pub struct AuditStreams {
+    subscriptions: Mutex<(u64, BTreeMap<u64, AuditFilter>)>,
+}
+
+impl AuditStreams {
+    /// Open a subscription; `None` when too many are open
+    pub fn subscribe(&self, filter: AuditFilter) -> Option<u64> {
+        let mut guard = self.lock();
+        let (next_id, subscriptions) = &mut *guard;
+        if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+            return None;
+        }
+        *next_id += 1;
+        subscriptions.insert(*next_id, filter);
+        Some(*next_id)
+    }
+
+    /// Close a subscription; returns whether it was open
+    pub fn unsubscribe(&self, id: u64) -> bool {
+        self.lock().1.remove(&id).is_some()
+    }
+
+    /// Close every subscription (e.g. on sign out); returns how many were open
+    pub fn close_all(&self) -> usize {
+        let mut guard = self.lock();
+        let closed = guard.1.len();
+        guard.1.clear();
+        closed
+    }
+
+    /// Subscriptions whose filter matches `entry`
+    pub fn matching(&self, entry: &AuditEntry) -> Vec<u64> {
+        self.lock().1.iter().filter(|(_, filter)| filter.matches(entry)).map(|(id, _)| *id).collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (u64, BTreeMap<u64, AuditFilter>)> {
+        self.subscriptions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Emit new audit entries to the webview until the audit log is dropped
+pub async fn forward_entries(app: tauri::AppHandle, mut feed: AuditSubscription) {
+    while let Some(next) = feed.recv().await {
+        let result = match next {
+            Ok(entry) => app.state::<AuditStreams>().matching(&entry).into_iter().try_for_each(|subscription_id| {
+                app.emit_all(AUDIT_ENTRY_EVENT, AuditEntryEvent { subscription_id, entry: entry.clone() })
+            }),
+            Err(MissedEntries(missed)) => {
+                log::warn!("Live audit stream skipped {} entries", missed);
+                app.emit_all(AUDIT_LAGGED_EVENT, serde_json::json!({ "missed": missed }))
+            }
+        };
+        if let Err(e) = result {
+            log::error!("Failed to emit audit event: {}", e);
+        }
+    }
+}
+
+/// Emit audit write health changes, notifying when writes fail or recover
+pub async fn forward_health(app: tauri::AppHandle, audit_log: Arc<AuditLog>) {
+    let mut health = audit_log.watch_health();
+    let mut was_healthy = health.borrow().is_healthy();
+    while health.changed().await.is_ok() {
+        let current = health.borrow_and_update().clone();
+        if let Err(e) = app.emit_all(AUDIT_HEALTH_EVENT, &current) {
+            log::error!("Failed to emit audit health: {}", e);
+        }
+        if current.is_healthy() != was_healthy {
+            was_healthy = current.is_healthy();
+            let (title, body) = health_notification(&current);
+            let identifier = app.config().tauri.bundle.identifier.clone();
+            if let Err(e) = Notification::new(&identifier).title(title).body(body).show() {
+                log::warn!("Failed to show audit health notification: {}", e);
+            }
+        }
+    }
+}
+
+/// Title and body of the notification for a change in write health
+fn health_notification(health: &AuditHealth) -> (&'static str, String) {
+    if health.is_healthy() {
+        return ("Compliance log restored", "Audit entries are being saved again.".to_string());
+    }
+    let error = health.last_error.as_deref().unwrap_or("unknown error");
+    let body = if health.blocks_privileged {
+        format!("Audit entries cannot be saved ({}). Module and policy changes are paused until this is fixed.", error)
+    } else {
+        format!("Audit entries cannot be saved ({}). They are held in memory until this is fixed.", error)
+    };
+    ("Compliance log failing", body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use esta_kernel::AuditLog;
+
+    #[tokio::test]
+    async fn test_entries_reach_matching_subscriptions() {
+        let streams = AuditStreams::default();
+        let everything = streams.subscribe(AuditFilter::default()).unwrap();
+        let ipc_only = streams.subscribe(AuditFilter { sources: vec!["ipc".to_string()], ..Default::default() }).unwrap();
+        let crashes =
+            streams.subscribe(AuditFilter { event_types: vec!["ModuleCrashed".to_string()], ..Default::default() }).unwrap();
+
+        let log = AuditLog::with_defaults();
+        let ipc = log.log_custom("ipc", "{}", "ipc").await;
+        let crash = log.log_module_crashed("accrual", "trap", "kernel").await;
+        assert_eq!(streams.matching(&ipc), [everything, ipc_only]);
+        assert_eq!(streams.matching(&crash), [everything, crashes]);
+
+        assert!(streams.unsubscribe(everything));
+        assert!(!streams.unsubscribe(everything));
+        assert_eq!(streams.matching(&ipc), [ipc_only]);
+        while streams.subscribe(AuditFilter::default()).is_some() {}
+        assert_eq!(streams.lock().1.len(), MAX_SUBSCRIPTIONS);
+    }
+}
@@ -0,0 +1,568 @@
+//! Payroll CSV Import
+//!
+//! Employers export hours from their payroll systems as CSV. This module maps
+//! the export's columns onto timesheet rows, validates each row, runs accrual
+//! for the valid rows through the kernel's accrual module (using the tenant
+//! policy and the statute in force on each work date), and records the
+//! results in the ledger.
+//!
+//! Rows are independent: a bad row is reported with its line number and
+//! skipped, and the remaining rows are still imported. Ledger events for the
+//! whole file are written in a single batch.
+//!
+//! A dry-run import appends to an in-memory copy of the ledger instead and
+//! reports the balance each employee would have afterwards.
+
+use crate::{execute_json, ACCRUAL_MODULE};
+use anyhow::Result;
+use esta_kernel::report::generate_compliance_report;
+use esta_kernel::{Date, Kernel, KernelError, Ledger, LedgerEventKind, NewLedgerEvent, StorageError};
+use serde::{Deserialize, Serialize};
+
+/// Maximum accepted CSV size (10MB)
+const MAX_CSV_BYTES: usize = 10 * 1_048_576;
+
+/// Maximum minutes that can be recorded for one row (one day)
+const MAX_MINUTES_PER_ROW: u64 = 24 * 60;
+
+/// Header names of the columns to read
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub employee_id: String,
+    pub work_date: String,
+    /// Hours worked as a decimal (e.g. `7.5`)
+    pub hours_worked: Option<String>,
+    /// Minutes worked; used instead of `hours_worked` when set
+    pub minutes_worked: Option<String>,
+    /// Sick hours used (optional)
+    pub hours_used: Option<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            employee_id: "employee_id".to_string(),
+            work_date: "work_date".to_string(),
+            hours_worked: Some("hours_worked".to_string()),
+            minutes_worked: None,
+            hours_used: None,
+        }
+    }
+}
+
+/// Request to import a payroll CSV export
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportTimesheetRequest {
+    pub tenant_id: String,
+    /// CSV contents, including a header row
+    pub csv: String,
+    #[serde(default)]
+    pub mapping: ColumnMapping,
+    /// Field delimiter (default `,`)
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Name recorded as the source of the ledger events (e.g. the file name)
+    #[serde(default)]
+    pub source_name: Option<String>,
+    /// Preview the import without recording anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A row that could not be imported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowError {
+    /// Line number in the CSV (the header is line 1)
+    pub line: u64,
+    pub employee_id: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub tenant_id: String,
+    pub rows_total: usize,
+    pub rows_imported: usize,
+    pub minutes_worked_total: u64,
+    pub accrued_minutes_total: u64,
+    pub ledger_events: usize,
+    pub errors: Vec<RowError>,
+    pub dry_run: bool,
+    /// Balances after the import, for a dry run
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub balances: Vec<HypotheticalBalance>,
+}
+
+/// Balance an employee would have if a dry-run import were recorded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HypotheticalBalance {
+    pub employee_id: String,
+    /// Calendar year of the balance (the year of the latest imported row)
+    pub year: i32,
+    pub balance_minutes: i64,
+}
+
+/// A validated timesheet row
+#[derive(Debug, PartialEq)]
+struct TimesheetRow {
+    line: u64,
+    employee_id: String,
+    work_date: Date,
+    minutes_worked: u64,
+    minutes_used: u64,
+}
+
+/// Column positions resolved from the header row
+struct ColumnIndexes {
+    employee_id: usize,
+    work_date: usize,
+    worked: usize,
+    worked_in_minutes: bool,
+    used: Option<usize>,
+}
+
+impl ColumnIndexes {
+    fn resolve(headers: &csv::StringRecord, mapping: &ColumnMapping) -> Result<Self, String> {
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Column '{}' not found in CSV header", name))
+        };
+
+        let (worked, worked_in_minutes) = match (&mapping.minutes_worked, &mapping.hours_worked) {
+            (Some(minutes), _) => (find(minutes)?, true),
+            (None, Some(hours)) => (find(hours)?, false),
+            (None, None) => {
+                return Err("Mapping must name an hours_worked or minutes_worked column".to_string())
+            }
+        };
+
+        Ok(Self {
+            employee_id: find(&mapping.employee_id)?,
+            work_date: find(&mapping.work_date)?,
+            worked,
+            worked_in_minutes,
+            used: mapping.hours_used.as_deref().map(find).transpose()?,
+        })
+    }
+}
+
+/// Parse a work date as `YYYY-MM-DD` or `MM/DD/YYYY`
+fn parse_work_date(value: &str) -> Result<Date, String> {
+    let value = value.trim();
+    if let Ok(date) = value.parse::<Date>() {
+        return Ok(date);
+    }
+
+    let parts: Vec<&str> = value.split('/').collect();
+    if let [month, day, year] = parts[..] {
+        if let (Ok(month), Ok(day), Ok(year)) = (month.parse(), day.parse(), year.parse()) {
+            if year >= 1000 {
+                return Date::new(year, month, day).map_err(|e| e.to_string());
+            }
+        }
+    }
+    Err(format!("Invalid work date '{}': expected YYYY-MM-DD or MM/DD/YYYY", value))
+}
+
+/// Parse a non-negative duration cell into whole minutes; empty cells are zero
+fn parse_minutes(value: &str, in_minutes: bool, column: &str) -> Result<u64, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    let number: f64 = value
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}': not a number", column, value))?;
+    if !number.is_finite() || number < 0.0 {
+        return Err(format!("Invalid {} '{}': must be zero or more", column, value));
+    }
+
+    let minutes = if in_minutes { number } else { number * 60.0 }.round() as u64;
+    if minutes > MAX_MINUTES_PER_ROW {
+        return Err(format!("Invalid {} '{}': exceeds 24 hours", column, value));
+    }
+    Ok(minutes)
+}
+
+fn parse_row(record: &csv::StringRecord, line: u64, columns: &ColumnIndexes) -> Result<TimesheetRow, RowError> {
+    let cell = |index: usize| record.get(index).unwrap_or("");
+    let employee_id = cell(columns.employee_id).trim().to_string();
+    let row_error = |message: String| RowError {
+        line,
+        employee_id: Some(employee_id.clone()).filter(|id| !id.is_empty()),
+        message,
+    };
+
+    if employee_id.is_empty() {
+        return Err(row_error("Missing employee id".to_string()));
+    }
+
+    let work_date = parse_work_date(cell(columns.work_date)).map_err(row_error)?;
+    let worked_column = if columns.worked_in_minutes { "minutes worked" } else { "hours worked" };
+    let minutes_worked = parse_minutes(cell(columns.worked), columns.worked_in_minutes, worked_column)
+        .map_err(row_error)?;
+    let minutes_used = match columns.used {
+        Some(index) => parse_minutes(cell(index), false, "hours used").map_err(row_error)?,
+        None => 0,
+    };
+
+    Ok(TimesheetRow {
+        line,
+        employee_id,
+        work_date,
+        minutes_worked,
+        minutes_used,
+    })
+}
+
+/// Parse and validate every row of a CSV export
+fn parse_rows(request: &ImportTimesheetRequest) -> Result<Vec<Result<TimesheetRow, RowError>>, String> {
+    let delimiter = request.delimiter.unwrap_or(',');
+    if !delimiter.is_ascii() {
+        return Err(format!("Delimiter '{}' must be an ASCII character", delimiter));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .flexible(true)
+        .from_reader(request.csv.as_bytes());
+
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV header: {}", e))?.clone();
+    let columns = ColumnIndexes::resolve(&headers, &request.mapping)?;
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        // Header is line 1; fall back to the record index if the position is unknown
+        let fallback_line = i as u64 + 2;
+        rows.push(match record {
+            Ok(record) => {
+                let line = record.position().map(|p| p.line()).unwrap_or(fallback_line);
+                parse_row(&record, line, &columns)
+            }
+            Err(e) => Err(RowError {
+                line: e.position().map(|p| p.line()).unwrap_or(fallback_line),
+                employee_id: None,
+                message: format!("Malformed CSV row: {}", e),
+            }),
+        });
+    }
+    Ok(rows)
+}
+
+/// Run accrual for a row and build its ledger events
+async fn accrue_row(
+    kernel: &Kernel,
+    tenant_id: &str,
+    source: &str,
+    row: &TimesheetRow,
+    dry_run: bool,
+) -> Result<Vec<NewLedgerEvent>, String> {
+    let version = kernel
+        .tenants()
+        .policy_at(tenant_id, row.work_date)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No policy in force for tenant on {}", row.work_date))?;
+    // Without a statute in force the module falls back to its built-in rate
+    let jurisdiction = kernel.policy_jurisdiction(&version.policy);
+    let statutory = kernel.guest_statute(jurisdiction, row.work_date).await;
+
+    let input = serde_json::json!({
+        "employee_id": row.employee_id,
+        "minutes_worked": row.minutes_worked,
+        "employer_policy": {
+            "employer_size": version.policy.employer_size,
+            "accrual_rate": version.policy.accrual_rate,
+            "policy_version": version.version,
+            "statutory": statutory
+        }
+    });
+    let (output, _) = execute_json(kernel, Some(tenant_id), ACCRUAL_MODULE, "accrue_json", &input, dry_run, false, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let accrued_minutes = output["accrued_minutes"]
+        .as_u64()
+        .ok_or_else(|| "Accrual module returned no accrued_minutes".to_string())?;
+
+    let event = |kind| NewLedgerEvent {
+        tenant_id: tenant_id.to_string(),
+        employee_id: row.employee_id.clone(),
+        work_date: row.work_date,
+        kind,
+        policy_version: Some(version.version),
+        source: format!("import:{}#{}", source, row.line),
+    };
+
+    let mut events = vec![event(LedgerEventKind::Accrued {
+        minutes_worked: row.minutes_worked,
+        accrued_minutes,
+    })];
+    if row.minutes_used > 0 {
+        events.push(event(LedgerEventKind::Used { minutes: row.minutes_used }));
+    }
+    Ok(events)
+}
+
+/// Import a payroll CSV export into a tenant's ledger
+///
+/// Returns an error only if the import cannot start (unknown tenant, bad
+/// header or mapping); problems with individual rows are listed in the report.
+pub async fn import_timesheet(kernel: &Kernel, request: ImportTimesheetRequest) -> Result<ImportReport> {
+    if request.csv.len() > MAX_CSV_BYTES {
+        return Err(KernelError::InputTooLarge(request.csv.len()).into());
+    }
+    if kernel.is_read_only() && !request.dry_run {
+        return Err(StorageError::ReadOnly("Read replica".to_string()).into());
+    }
+    kernel.tenants().ensure_exists(&request.tenant_id).await?;
+
+    let rows = parse_rows(&request).map_err(KernelError::InvalidRequest)?;
+    let source = request.source_name.as_deref().unwrap_or("csv");
+
+    let mut report = ImportReport {
+        tenant_id: request.tenant_id.clone(),
+        rows_total: rows.len(),
+        rows_imported: 0,
+        minutes_worked_total: 0,
+        accrued_minutes_total: 0,
+        ledger_events: 0,
+        errors: Vec::new(),
+        dry_run: request.dry_run,
+        balances: Vec::new(),
+    };
+    let mut events = Vec::new();
+    let mut employees = Vec::new();
+    let mut latest_date: Option<Date> = None;
+
+    for row in rows {
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                report.errors.push(e);
+                continue;
+            }
+        };
+
+        match accrue_row(kernel, &request.tenant_id, source, &row, request.dry_run).await {
+            Ok(row_events) => {
+                for event in &row_events {
+                    if let LedgerEventKind::Accrued { accrued_minutes, .. } = event.kind {
+                        report.accrued_minutes_total += accrued_minutes;
+                    }
+                }
+                report.rows_imported += 1;
+                report.minutes_worked_total += row.minutes_worked;
+                latest_date = latest_date.max(Some(row.work_date));
+                employees.push(row.employee_id.clone());
+                events.extend(row_events);
+            }
+            Err(message) => report.errors.push(RowError {
+                line: row.line,
+                employee_id: Some(row.employee_id.clone()),
+                message,
+            }),
+        }
+    }
+
+    if request.dry_run {
+        let ledger = kernel.ledger().dry_run().await;
+        report.ledger_events = ledger.append_batch(events).await?.len();
+        if let Some(date) = latest_date {
+            report.balances = hypothetical_balances(kernel, &ledger, &request.tenant_id, date.year(), employees).await?;
+        }
+        return Ok(report);
+    }
+
+    report.ledger_events = kernel
+        .append_ledger_events(events)
+        .await
+        .map_err(|e| e.context("Failed to write ledger events"))?
+        .len();
+
+    employees.sort();
+    employees.dedup();
+    kernel.add_employees(&request.tenant_id, &employees).await?;
+
+    Ok(report)
+}
+
+/// Year-end balances of the given employees in a dry-run ledger
+async fn hypothetical_balances(
+    kernel: &Kernel,
+    ledger: &Ledger,
+    tenant_id: &str,
+    year: i32,
+    mut employees: Vec<String>,
+) -> Result<Vec<HypotheticalBalance>> {
+    employees.sort();
+    employees.dedup();
+
+    let summary = generate_compliance_report(&kernel.tenants(), ledger, tenant_id, year).await?;
+    Ok(summary
+        .employees
+        .into_iter()
+        .filter(|e| employees.binary_search(&e.employee_id).is_ok())
+        .map(|e| HypotheticalBalance {
+            employee_id: e.employee_id,
+            year,
+            balance_minutes: e.ending_balance_minutes,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(csv: &str) -> ImportTimesheetRequest {
+        ImportTimesheetRequest {
+            tenant_id: "acme".to_string(),
+            csv: csv.to_string(),
+            mapping: ColumnMapping::default(),
+            delimiter: None,
+            source_name: None,
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_rows_reports_bad_rows() {
+        let csv = "employee_id,work_date,hours_worked\n\
+                   e1,2025-03-03,8\n\
+                   ,2025-03-03,8\n\
+                   e2,03/04/2025,7.5\n\
+                   e3,2025-02-30,8\n\
+                   e4,2025-03-03,-1\n\
+                   e5,2025-03-03,25\n";
+        let rows = parse_rows(&request(csv)).unwrap();
+        assert_eq!(rows.len(), 6);
+
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!((first.line, first.minutes_worked), (2, 480));
+        let us_date = rows[2].as_ref().unwrap();
+        assert_eq!(us_date.work_date.to_string(), "2025-03-04");
+        assert_eq!(us_date.minutes_worked, 450);
+
+        let lines: Vec<u64> = rows.iter().filter_map(|r| r.as_ref().err()).map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 5, 6, 7]);
+        assert_eq!(rows[1].as_ref().unwrap_err().employee_id, None);
+        assert_eq!(rows[3].as_ref().unwrap_err().employee_id.as_deref(), Some("e3"));
+    }
+
+    #[test]
+    fn test_custom_mapping_and_delimiter() {
+        let mut req = request("Emp;Date;Mins;Sick\ne1;2025-03-03;300;2\n");
+        req.delimiter = Some(';');
+        req.mapping = ColumnMapping {
+            employee_id: "Emp".to_string(),
+            work_date: "Date".to_string(),
+            hours_worked: None,
+            minutes_worked: Some("Mins".to_string()),
+            hours_used: Some("sick".to_string()),
+        };
+
+        let rows = parse_rows(&req).unwrap();
+        let row = rows[0].as_ref().unwrap();
+        assert_eq!((row.minutes_worked, row.minutes_used), (300, 120));
+    }
+
+    #[test]
+    fn test_missing_column_fails_import() {
+        let err = parse_rows(&request("employee,work_date,hours_worked\ne1,2025-03-03,8\n")).unwrap_err();
+        assert!(err.contains("employee_id"));
+    }
+
+    #[tokio::test]
+    async fn test_import_requires_known_tenant() {
+        let kernel = Kernel::new().unwrap();
+        let result = import_timesheet(&kernel, request("employee_id,work_date,hours_worked\n")).await;
+        assert!(result.is_err());
+    }
+
+    /// Accrual module that credits 16 minutes for any row
+    const ACCRUAL_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 16) "\16\00\00\00{\"accrued_minutes\":16}")
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "__abi_version") (result i32)
+            (i32.const 1))
+          (func (export "alloc") (param $size i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $size)))
+            (local.get $ptr))
+          (func (export "accrue_json") (param $ptr i32) (param $len i32) (result i32)
+            (i32.const 16)))
+    "#;
+
+    #[tokio::test]
+    async fn test_import_records_events_and_roster() {
+        let dir = tempfile::tempdir().unwrap();
+        let module_path = dir.path().join("accrual.wat");
+        std::fs::write(&module_path, ACCRUAL_WAT).unwrap();
+        let manifest = serde_json::json!({
+            "name": ACCRUAL_MODULE,
+            "path": module_path,
+            "checksum": hex::encode(ring::digest::digest(&ring::digest::SHA256, ACCRUAL_WAT.as_bytes())),
+            "capabilities": []
+        });
+        let manifest_path = dir.path().join("accrual.json");
+        std::fs::write(&manifest_path, manifest.to_string()).unwrap();
+
+        let kernel = Kernel::new().unwrap();
+        kernel.launch_module(manifest_path.to_str().unwrap()).await.unwrap();
+        let policy = esta_kernel::TenantPolicy {
+            employer_size: "large".to_string(),
+            accrual_rate: 1.0 / 30.0,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        kernel.tenants().set_policy("acme", policy, "2025-01-01".parse().unwrap()).await.unwrap();
+
+        let mut req = request("employee_id,work_date,hours_worked,sick\ne2,2025-03-03,8,0\ne1,2025-03-04,7.5,2\n");
+        req.mapping.hours_used = Some("sick".to_string());
+        req.source_name = Some("payroll.csv".to_string());
+        let report = import_timesheet(&kernel, req).await.unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.rows_total, report.rows_imported, report.ledger_events), (2, 2, 3));
+        assert_eq!((report.minutes_worked_total, report.accrued_minutes_total), (930, 32));
+
+        let events = kernel.ledger().events_for_tenant("acme").await;
+        let recorded: Vec<_> = events
+            .iter()
+            .map(|e| (e.event.employee_id.as_str(), e.event.kind.clone(), e.event.source.as_str()))
+            .collect();
+        assert_eq!(
+            recorded,
+            vec![
+                ("e2", LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 }, "import:payroll.csv#2"),
+                ("e1", LedgerEventKind::Accrued { minutes_worked: 450, accrued_minutes: 16 }, "import:payroll.csv#3"),
+                ("e1", LedgerEventKind::Used { minutes: 120 }, "import:payroll.csv#3"),
+            ]
+        );
+        assert!(events.iter().all(|e| e.event.policy_version == Some(1)));
+        assert_eq!(kernel.tenants().list_employees("acme").await.unwrap(), vec!["e1", "e2"]);
+    }
+
+    #[tokio::test]
+    async fn test_rows_without_policy_are_reported() {
+        let kernel = Kernel::new().unwrap();
+        kernel.tenants().register("acme").await.unwrap();
+
+        let report = import_timesheet(&kernel, request("employee_id,work_date,hours_worked\ne1,2025-03-03,8\n"))
+            .await
+            .unwrap();
+        assert_eq!((report.rows_total, report.rows_imported), (1, 0));
+        assert!(report.errors[0].message.contains("No policy"));
+        assert!(kernel.ledger().is_empty().await);
+    }
+}
@@ -0,0 +1,127 @@
+//! IPC Audit
+//!
+//! The kernel audits what modules and storage do; this records what users
+//! asked for. Every Tauri command is written to the audit log as a `Custom`
+//! event in the `ipc` category, with the command name, caller role,
+//! correlation ID, outcome, and duration.
+//!
+//! Failed commands and commands that change state are always recorded. Other
+//! commands (status polls, log reads) are sampled at `ESTA_IPC_AUDIT_SAMPLE_RATE`
+//! (default 1.0, every call), keyed on the correlation ID so the decision is
+//! the same for every retry of a request.
+
+use esta_kernel::AuditLog;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// Audit category of IPC events
+pub const IPC_CATEGORY: &str = "ipc";
+
+/// Commands that change state, recorded regardless of sampling
+pub const STATE_CHANGING_COMMANDS: &[&str] = &[
+    "invoke_kernel",
+    "kernel_load_module",
+    "kernel_install_module",
+    "kernel_rollback_module",
+    "kernel_execute",
+    "kernel_cancel",
+    "storage_vacuum",
+    "kernel_rotate_capability_secret",
+    "database_rotate_key",
+    "backup_create",
+    "backup_restore",
+    "kernel_rotate_signing_key",
+    "kernel_reload_config",
+    "tenant_set_policy",
+    "tenant_apply_retention",
+    "report_template_save",
+    "import_timesheet_csv",
+    "reminder_add",
+    "reminder_remove",
+    "alerts_set_config",
+];
+
+/// Which commands are recorded
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpcAuditConfig {
+    /// Fraction (0.0-1.0) of other successful commands to record
+    pub sample_rate: f64,
+    /// Further commands recorded on every call
+    pub always: Vec<String>,
+}
+
+impl Default for IpcAuditConfig {
+    fn default() -> Self {
+        Self { sample_rate: 1.0, always: Vec::new() }
+    }
+}
+
+impl IpcAuditConfig {
+    /// Whether a command's call should be recorded
+    pub fn should_record(&self, command: &str, correlation_id: &str, success: bool) -> bool {
+        if !success || STATE_CHANGING_COMMANDS.contains(&command) || self.always.iter().any(|c| c == command) {
+            return true;
+        }
+        if self.sample_rate <= 0.0 {
+            return false;
+        }
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        let mut hasher = DefaultHasher::new();
+        correlation_id.hash(&mut hasher);
+        (hasher.finish() as f64 / u64::MAX as f64) < self.sample_rate
+    }
+}
+
+/// One recorded command call, serialized as the audit event's message
+#[derive(Debug, Serialize)]
+pub struct IpcCall<'a> {
+    pub command: &'a str,
+    pub role: &'a str,
+    pub correlation_id: &'a str,
+    /// `ok`, or the failure's error code
+    pub outcome: &'a str,
+    pub duration_ms: u64,
+}
+
+impl IpcCall<'_> {
+    /// Append the call to the audit log if the configuration selects it
+    pub async fn record(&self, audit_log: &AuditLog, config: &IpcAuditConfig) {
+        if !config.should_record(self.command, self.correlation_id, self.outcome == "ok") {
+            return;
+        }
+        let message = serde_json::to_string(self).unwrap_or_default();
+        audit_log.log_custom(IPC_CATEGORY, &message, "ipc").await;
+    }
+}
+
+/// Milliseconds in a duration, for [`IpcCall::duration_ms`]
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_spares_writes_and_failures() {
+        let config = IpcAuditConfig { sample_rate: 0.25, always: vec!["kernel_get_logs".to_string()] };
+        let ids: Vec<String> = (0..400).map(|i| format!("req-{}", i)).collect();
+        let sampled = ids.iter().filter(|id| config.should_record("kernel_get_status", id, true)).count();
+        assert!((50..150).contains(&sampled), "sampled {} of 400", sampled);
+        assert_eq!(
+            config.should_record("kernel_get_status", "req-1", true),
+            config.should_record("kernel_get_status", "req-1", true)
+        );
+
+        assert!(ids.iter().all(|id| config.should_record("tenant_set_policy", id, true)));
+        assert!(ids.iter().all(|id| config.should_record("kernel_get_logs", id, true)));
+        assert!(ids.iter().all(|id| config.should_record("kernel_get_status", id, false)));
+        let off = IpcAuditConfig { sample_rate: 0.0, always: Vec::new() };
+        assert!(!off.should_record("kernel_get_status", "req-1", true));
+    }
+}
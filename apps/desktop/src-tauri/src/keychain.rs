@@ -0,0 +1,46 @@
+//! OS Keychain Master Key
+//!
+//! With `ESTA_SECRET_KEYCHAIN` set, the secret store is unlocked with a
+//! 32-byte master key held in the OS keychain (macOS Keychain, Windows
+//! Credential Manager, or the Secret Service on Linux) instead of a
+//! passphrase. The key is generated and saved on first run and stored
+//! hex-encoded under the service [`KEYCHAIN_SERVICE`]. The account is the
+//! secret store's path, so stores in different data directories get
+//! different keys.
+
+use esta_kernel::MasterKey;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::path::Path;
+
+/// Keychain service the master key is saved under
+pub const KEYCHAIN_SERVICE: &str = "esta-rainforest.secret-store";
+
+/// Master key for the secret store at `path`, generated on first run
+pub fn master_key(path: &Path) -> Result<MasterKey, String> {
+    let account = path.display().to_string();
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, &account).map_err(|e| format!("OS keychain unavailable: {}", e))?;
+
+    match entry.get_password() {
+        Ok(encoded) => parse_key(&encoded),
+        Err(keyring::Error::NoEntry) => {
+            let mut key = [0u8; 32];
+            SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| "System RNG failed; no master key generated".to_string())?;
+            entry
+                .set_password(&hex::encode(key))
+                .map_err(|e| format!("Failed to save the master key in the OS keychain: {}", e))?;
+            Ok(MasterKey::from_bytes(key))
+        }
+        Err(e) => Err(format!("Failed to read the master key from the OS keychain: {}", e)),
+    }
+}
+
+/// Decode a hex-encoded 32-byte key
+fn parse_key(encoded: &str) -> Result<MasterKey, String> {
+    let bytes = hex::decode(encoded.trim()).map_err(|_| "OS keychain master key is not hex".to_string())?;
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "OS keychain master key is not 32 bytes".to_string())?;
+    Ok(MasterKey::from_bytes(key))
+}
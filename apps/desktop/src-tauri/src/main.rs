@@ -6,23 +6,471 @@
 //! ## Command Handlers
 //!
 //! - `invoke_kernel` - General kernel invocation for accrual/validation
-//! - `kernel_get_status` - Get kernel status and loaded modules
+//! - `kernel_get_status` - Get kernel status, loaded modules, and security profile
+//! - `supervisor_get_status` - State, restarts, and heartbeat age of each supervised module
 //! - `kernel_load_module` - Load a WASM module by manifest path
+//! - `kernel_list_available_modules` - List modules in the modules directory with verification status
+//! - `kernel_install_module` - Verify, load, and record a module from the modules directory
+//! - `kernel_rollback_module` - Swap a module back to a previously installed version
+//! - `kernel_get_module_info` - Manifest, checksum, signature verification, grants, and statistics of a loaded module
+//! - `kernel_unload_module` - Shut one module down, revoke its capabilities, and stop supervising it
 //! - `kernel_execute` - Execute a function on a loaded module
-//! - `kernel_get_logs` - Get recent audit log entries
-//! - `tenant_set_policy` - Set tenant policy configuration
+//! - `kernel_cancel` - Cancel a running execution by its invocation ID
+//! - `kernel_replay` - Re-execute recorded invocations and report any divergence
+//! - `kernel_get_logs` - Get audit log entries by sequence, time range, or source
+//! - `kernel_audit_subscribe` - Receive new audit entries as `audit://entry` events
+//! - `kernel_audit_unsubscribe` - Stop an audit subscription
+//! - `kernel_verify_audit` - Verify new audit entries, or start a full verification in the background
+//! - `audit_verify_export` - Verify an exported audit log's chain, anchored checkpoints, and their signatures
+//! - `storage_usage_report` - Disk space used by the ledger, policies, archive, and modules
+//! - `storage_vacuum` - Reclaim disk space (temp files, old module versions, expired archives)
+//! - `kernel_get_stats_history` - Hourly invocation, error, and fuel counts per module
+//! - `kernel_export_stats_history` - Hash-chained statistics records for a time range, with a digest
+//! - `kernel_rotate_capability_secret` - Replace the capability secret and re-issue live tokens
+//! - `database_rotate_key` - Re-seal the database's employee records under a new key
+//! - `backup_create` - Write a signed archive of tenants, policies, the ledger, and the audit log
+//! - `backup_restore` - Verify a backup archive's signature and format version and merge it in
+//! - `kernel_rotate_signing_key` - Trust a new module signing key, retiring the current one after a grace period
+//! - `kernel_export_capabilities` - Signed snapshot of active capabilities for security review
+//! - `kernel_reload_config` - Re-read `esta-kernel.toml` and apply its execution limits
+//! - `tenant_create` - Create a tenant, its capability namespace, and its yearly carryover reminder
+//! - `tenant_archive` - Close a tenant to new work, revoking its capabilities and reminders
+//! - `tenant_purge` - Permanently remove an archived tenant and its ledger events
+//! - `tenant_apply_retention` - Redact employee data past a tenant's retention period
+//! - `tenant_set_policy` - Record a new tenant policy version
+//! - `tenant_get_policy_history` - Get every policy version recorded for a tenant
+//! - `tenant_get_usage` - Fuel a tenant has used, per module and against its ceiling
+//! - `statute_get_parameters` - Statutory parameters in force on a date
 //! - `tenant_get_accruals` - Get accrual data for tenant
 //! - `employee_view_accruals` - Get accrual data for employee
+//! - `tenant_usage_insights` - Informational usage pattern insights (tenant opt-in)
+//! - `import_timesheet_csv` - Import hours from a payroll CSV export into the ledger
+//! - `generate_compliance_report` - Annual compliance report for a tenant (JSON or PDF)
+//! - `generate_liability_report` - Dollar value of unused sick time by GL account (JSON or CSV)
+//! - `report_template_save` - Save a new version of a tenant's report template
+//! - `report_template_list` - Latest version of each of a tenant's report templates
+//! - `generate_custom_report` - Compliance report laid out by a saved template
+//! - `reminder_add` - Register a recurring compliance reminder
+//! - `reminder_list` - List registered reminders and when each is next due
+//! - `reminder_remove` - Remove a reminder
+//! - `alerts_get_config` - Alert kinds switched off and webhooks for a tenant, or the default
+//! - `alerts_set_config` - Replace a tenant's alert configuration, or the default
+//! - `session_login` - Sign in, establishing the caller's role
+//! - `session_logout` - Sign out
+//! - `session_current` - The signed-in user, if any
+//! - `session_add_account` - Create a user account
+//!
+//! ## Calculations
+//!
+//! Accrual and balance math lives in the accrual WASM module and runs through
+//! the kernel. The `accrue`, `validate`, and `calculate` actions of
+//! `invoke_kernel` are legacy request shapes kept only as a compatibility
+//! shim: they are translated into `accrual` module calls. Set
+//! `ESTA_FAIL_ON_LEGACY_REQUESTS=1` to reject them instead.
+//!
+//! ## Policies
+//!
+//! Tenant policies are versioned with effective dates. Legacy accrual requests
+//! that name a `tenant_id` use the policy version in force on the payload's
+//! `work_date` (today if omitted). Set `ESTA_POLICY_FILE` to persist policy
+//! history across restarts.
+//!
+//! ## Tenants
+//!
+//! Each employer is a tenant with an explicit lifecycle. `tenant_create`
+//! registers it, issues the root capability of its namespace, and schedules
+//! its yearly carryover reminder. `tenant_archive` keeps its records readable
+//! but refuses new invocations and policy changes, revoking its capabilities
+//! and reminders. Only an archived tenant can be removed with `tenant_purge`,
+//! which deletes its policies and ledger events; the audit log keeps its
+//! trail. Audit entries written on a tenant's behalf carry its ID.
+//!
+//! A policy's `retention_days` (at least three years) limits how long
+//! employee data is kept: at startup, and on `tenant_apply_retention`,
+//! employee IDs in older ledger events and the events of older audit
+//! entries are redacted. The audit chain still verifies afterwards.
+//!
+//! ## Listings
+//!
+//! Lists come back in a fixed order: audit entries by sequence number,
+//! employees by ID, reminders by due time and then ID. `kernel_get_logs` and
+//! `tenant_get_accruals` return one page at a time; pass a response's
+//! `next_cursor` as `cursor` to get the next page, which stays stable while
+//! new entries are added. `next_cursor` is null on the last page.
+//!
+//! ## Report Templates
+//!
+//! Employers lay out compliance reports their own way with templates saved
+//! by `report_template_save`: named columns computed by small expressions
+//! over each employee's summary (`hours(ending_balance_minutes)`), and an
+//! optional filter (`violations > 0`). Saving under an existing name adds a
+//! version; `generate_custom_report` uses the latest unless `version` is
+//! given. Templates are evaluated under the kernel's fuel limit.
+//!
+//! ## Usage Insights
+//!
+//! Tenants whose policy sets `usage_insights` can request informational
+//! insights about unusual sick time usage patterns. The analysis runs in the
+//! `analytics` module (loaded like any other module) and is not reachable
+//! through `kernel_execute`, so the opt-in cannot be bypassed.
+//!
+//! ## Policy Simulation
+//!
+//! The `simulate` action of `invoke_kernel` plays a year of projected hours
+//! (`periods` of `minutes_worked` and planned `minutes_used`) through both
+//! frontloading and accrual in the `policy-sim` module, so employers can
+//! compare the methods before choosing one. The statute in force supplies
+//! the rate and usage limits; nothing is recorded in the ledger.
+//!
+//! ## Ledger
+//!
+//! Imported hours and the sick time they accrue are recorded in the kernel's
+//! append-only ledger. Set `ESTA_LEDGER_FILE` to persist it across restarts.
+//! `ESTA_DATA_DIR` supplies default locations for both files
+//! (`policies.json` and `ledger.jsonl`).
+//!
+//! Tenants, employee rosters, policy versions, and ledger events are also
+//! written through to a SQLite database, `ESTA_DATABASE_FILE` (default
+//! `esta.db` in the data directory), and restored from it at startup, so
+//! rosters built by imports survive restarts. With `ESTA_ENCRYPT_DATABASE=1`,
+//! employee IDs and hour records in it are encrypted under a key kept in the
+//! secret store (so the store must be unlocked; see Secrets);
+//! `database_rotate_key` replaces that key. An existing plaintext database
+//! must first be converted with `esta-kernel-cli database encrypt`.
+//!
+//! ## Backups
+//!
+//! `backup_create` writes one signed, versioned JSON archive of every
+//! tenant's policies and roster, the ledger, and the audit log, into
+//! `backups/` under `ESTA_DATA_DIR`.
+//! `backup_restore` accepts archives signed by this installation's backup
+//! key (kept in the secret store) or by a `trusted_key` from the machine that
+//! wrote it, and refuses unknown format versions and archives whose ledger
+//! does not continue this installation's (e.g. another installation's, once
+//! this one has recorded events). Restored audit entries are kept in
+//! `restored-audit/` under `ESTA_DATA_DIR`.
+//!
+//! ## Access Control
+//!
+//! Each command requires one of the roles `employer-admin`, `manager`, or
+//! `employee`, established by `session_login`; see `session.rs` for the
+//! table. Employees may only view their own accruals. Accounts are kept in
+//! `ESTA_ACCOUNTS_FILE` (default `accounts.json` in `ESTA_DATA_DIR`). Until
+//! the first account is created every command is open to the local user,
+//! except under the production security profile.
+//!
+//! ## Request Tracing
+//!
+//! Every command accepts an optional `correlation_id` argument (generated
+//! when omitted) and returns it in the response. Audit entries written while
+//! the command runs carry the same ID, so `kernel_get_logs` with
+//! `correlation_id` returns everything one frontend action caused.
+//!
+//! Each command call is itself audited (source `ipc`, category `ipc`) with
+//! its caller role, outcome, and duration. Reads can be sampled with
+//! `ESTA_IPC_AUDIT_SAMPLE_RATE`; commands listed in `ESTA_IPC_AUDIT_ALWAYS`,
+//! state-changing commands, and failures are always recorded.
+//!
+//! ## Replay
+//!
+//! Every successful execution is recorded in the audit log with the module
+//! checksum and input/output hashes. `kernel_replay` re-runs the invocations
+//! in a range of audit sequence numbers against the same module versions and
+//! reports any output that differs. Inputs are taken from the invocation
+//! archive (`ESTA_ARCHIVE_DIR`), or from the audit log itself for inputs up to
+//! `ESTA_RECORD_INPUT_BYTES` bytes (default 0: hashes only).
+//!
+//! ## Crash Dumps
+//!
+//! With `ESTA_CRASH_DUMP_DIR` set, a module trap saves the guest's memory
+//! (first 1 MiB), its remaining fuel, and the failing function to that
+//! directory, encrypted under a key in the secret store (so the store must
+//! be unlocked). The dump's ID is recorded as
+//! `crash_dump` in the `ModuleCrashed` audit entry; the newest 32 are kept.
+//!
+//! ## Audit Log
+//!
+//! The audit log is persisted as segment files in `ESTA_AUDIT_DIR` (default
+//! `audit/` in the data directory), continuing its hash chain across
+//! restarts; read replicas keep theirs in memory. `kernel_verify_audit`
+//! checks the entries appended since its last call. With `full: true` it
+//! starts re-verifying every segment from the first entry in the background;
+//! later calls report that verification's progress and result.
+//! `kernel_get_logs` accepts `after_sequence` and `from_timestamp` /
+//! `to_timestamp` filters; entries older than those kept in memory are found
+//! in the segments by binary search over memory-mapped files.
+//!
+//! `audit_verify_export` checks an exported log file offline, this
+//! installation's or another's: entry hashes, chain continuity, anchored
+//! checkpoints, and checkpoint signatures against the trusted signing keys
+//! (or `trusted_key`). It reports the first invalid sequence number.
+//!
+//! Live views call `kernel_audit_subscribe` with optional `sources` and
+//! `event_types` filters instead of polling, and receive each new matching
+//! entry as an `audit://entry` event tagged with their subscription ID.
+//!
+//! If segment writes start failing (disk full, permissions), entries wait in
+//! memory and are retried every minute. `ESTA_AUDIT_FAILURE_POLICY` decides
+//! what stops meanwhile: `block` (the default) refuses module loads,
+//! installs, capability grants, key rotations, and policy changes with
+//! `AUDIT_UNAVAILABLE` until the backlog is written; `buffer` refuses them
+//! only once the buffer is full and entries are being lost. The failure is
+//! shown as an OS notification, reported in `kernel_get_status` under
+//! `audit.health`, and emitted as `audit://health` whenever it changes.
+//!
+//! ## Dry Runs
+//!
+//! `kernel_execute` and `import_timesheet_csv` accept `dry_run: true` to
+//! preview a calculation, such as accrual under a proposed policy, or an
+//! import's effect on balances. The modules run exactly as they would for
+//! real, but nothing is written to the ledger, the roster, the invocation
+//! archive, or the audit log beyond a `DryRunExecuted` marker. A dry-run
+//! import returns each affected employee's hypothetical balance. Dry runs
+//! are allowed on read replicas.
+//!
+//! ## Cancellation
+//!
+//! A long-running execution, such as a report module working through a year
+//! of records, can be stopped. The frontend passes an `invocation_id` of its
+//! choosing to `kernel_execute` and calls `kernel_cancel` with the same ID;
+//! the module is interrupted wherever it is, and the execution fails with
+//! `CANCELLED`. Cancellations are audited as `InvocationCancelled`, apart
+//! from module failures, and do not count as module errors.
+//!
+//! ## Modules
+//!
+//! Rule modules (a `.wasm` file plus its JSON manifest) placed in
+//! `ESTA_MODULES_DIR` (default `modules/` in the data directory) can be listed
+//! and installed by name. Installed modules are loaded again at startup.
+//! Each install is pinned to a copy kept under `.versions/` in the modules
+//! directory, along with up to `ESTA_MODULE_VERSIONS_KEPT` (default 3)
+//! previous versions that can be rolled back to.
+//!
+//! Compiled modules are cached in `ESTA_MODULE_CACHE_DIR` (default
+//! `module-cache/` in the data directory) so later launches skip compilation.
+//! Entries are authenticated with a key from the secret store, so the cache
+//! is only used while the store is unlocked.
+//!
+//! ## Signing Keys
+//!
+//! Set `ESTA_SIGNING_PUBLIC_KEY` (hex Ed25519) to verify module and statute
+//! signatures. The trusted keys are kept in `ESTA_TRUST_FILE` (default
+//! `trust.json` in the data directory), seeded with that key on first run.
+//! `kernel_rotate_signing_key` makes a new key current; modules signed by the
+//! previous key keep loading for `grace_days` (default 30), then are
+//! rejected. The audit log records which key verified each module.
+//!
+//! ## Statutes
+//!
+//! The accrual rate, the annual usage employers must allow, and the waiting
+//! period come from versioned statute files with effective dates. The kernel
+//! bundles a baseline; rule packs can ship amendments as
+//! `<jurisdiction>.json` files (with a `.json.sig` signature) in
+//! `ESTA_STATUTES_DIR` (default `statutes/` in the modules directory), loaded
+//! at startup. New tenant policies must be at least as generous as the
+//! statute in force on their effective date. A policy's `jurisdiction`
+//! (Michigan `US-MI` or Minnesota `US-MN`) picks the statute used to check
+//! it and to accrue its tenant's time; without one the kernel's applies.
+//!
+//! ## Storage
+//!
+//! `storage_usage_report` measures each data location and flags totals over
+//! `ESTA_STORAGE_ALERT_MB` with a `StorageLimitExceeded` audit event.
+//! `storage_vacuum` removes temp files left by interrupted writes, module
+//! versions beyond the retention limit, and, when
+//! `ESTA_ARCHIVE_RETENTION_DAYS` is set, archived invocations older than that.
+//! The ledger and policy files are never compacted. Set
+//! `ESTA_VACUUM_INTERVAL_HOURS` to vacuum and check usage on a schedule.
+//!
+//! ## File Output
+//!
+//! Commands that write a file the caller names take a path relative to an
+//! output directory in the data directory: `reports/` for exported reports,
+//! `backups/` for backups. Absolute paths, `..`, and symlinks leading
+//! elsewhere are refused with `INVALID_REQUEST`; without a data directory
+//! nothing is written.
+//!
+//! ## Supervision
+//!
+//! The accrual module runs under a supervisor that relaunches it when it
+//! hangs (see `supervision.rs`). `supervisor_get_status` returns each child's
+//! state as JSON tagged by `kind` (`starting`, `running`, `crashed`,
+//! `restarting`, `circuit_open`, `stopped`, `terminated`), with
+//! `restart_in_ms` counting down to a scheduled restart, and every
+//! transition is emitted as a `supervisor://event` with the child's ID,
+//! previous and new state, and time.
+//! Crash histories persist in `ESTA_SUPERVISOR_FILE` (default
+//! `supervisor.json` in the data directory), so restart budgets and escalation
+//! levels carry over when the app is reopened.
+//!
+//! ## Fuel Accounting
+//!
+//! Fuel used by each tenant's invocations is totalled per module and reported
+//! by `tenant_get_usage`, priced at `ESTA_FUEL_PRICE_CENTS_PER_BILLION` when
+//! set. `ESTA_TENANT_FUEL_CEILING` limits the fuel any tenant may use per hour,
+//! and `ESTA_TENANT_FUEL_CEILINGS` (`tenant=fuel,...`) sets individual
+//! tenants' limits; a tenant at its ceiling gets `FUEL_CEILING` errors until
+//! the next hour.
+//!
+//! ## Statistics History
+//!
+//! Module statistics are snapshotted hourly into `ESTA_STATS_FILE` (default
+//! `stats.jsonl` in the data directory) and kept for
+//! `ESTA_STATS_RETENTION_DAYS` (default 90). `kernel_get_stats_history`
+//! answers "how much fuel did this module use per hour last month";
+//! `kernel_export_stats_history` returns the underlying records, each hashed
+//! over its predecessor, with a digest that recipients can recompute.
+//!
+//! ## Secrets
+//!
+//! The capability secret is kept encrypted in `ESTA_SECRETS_FILE` (default
+//! `secrets.json` in the data directory) once the store is unlocked, either
+//! with `ESTA_SECRET_PASSPHRASE` or, with `ESTA_SECRET_KEYCHAIN=1`, by a
+//! master key the app generates and keeps in the OS keychain (the passphrase
+//! wins when both are set). Otherwise the secret is generated at startup and
+//! lives only in memory. The same holds for the secret behind the pseudonyms
+//! modules see in place of employee IDs, so they stay stable across restarts
+//! only with a store. Capability tokens never outlive the process: the
+//! capabilities themselves are held in memory and granted afresh at launch.
+//! `kernel_rotate_capability_secret` replaces the secret and re-issues tokens
+//! for capabilities that are still live.
+//!
+//! `kernel_get_status` reports capability validation and denial rates over
+//! the last minute, with denials by reason. With
+//! `ESTA_CAPABILITY_DENIAL_ALERT` set, that many denials within a minute are
+//! recorded as a `Custom` audit entry in category `security` naming the
+//! module refused most often, a sign of a misbehaving or hostile module.
+//!
+//! ## Concurrency
+//!
+//! Each module runs up to `ESTA_MAX_CONCURRENT_INVOCATIONS` (default 4)
+//! invocations at once. Further calls wait in a per-module queue of up to
+//! `ESTA_MAX_QUEUED_INVOCATIONS` (default 64), served round-robin across
+//! tenants. When the queue is full, calls fail with error code `BUSY` and can
+//! be retried.
+//!
+//! On exit the kernel stops admitting calls, signals modules that export
+//! `__shutdown`, and waits up to `ESTA_SHUTDOWN_DRAIN_SECS` (default 10) for
+//! running and queued calls before cancelling them.
+//!
+//! With `ESTA_ENFORCE_RESOURCE_PROFILES=1`, once a module has 100 successful
+//! calls, a call using more than 10× its usual (99th percentile) fuel or
+//! memory is stopped with error code `RESOURCE_LIMIT` and audited as a
+//! `ResourceAnomaly`.
+//!
+//! With `ESTA_RESULT_CACHE=1`, a call repeating an earlier successful one
+//! (same module version, function, input, tenant, and tenant policy) is
+//! answered from the kernel's result cache for `ESTA_RESULT_CACHE_TTL_SECS`
+//! (default 300) without running the module; `kernel_execute` reports
+//! `cached: true`, and a request can set `bypass_cache` to run the module
+//! anyway. `kernel_get_status` reports cache hits and misses.
+//!
+//! ## Security Profiles
+//!
+//! `ESTA_SECURITY_PROFILE` selects `development` (the default), `staging`, or
+//! `production`. Staging and production require signed modules, a persisted
+//! audit log, and known capabilities, and cap each call at 30 seconds;
+//! production also loads modules only through the module catalog, so set
+//! `ESTA_ACCRUAL_MANIFEST` only in development and staging. Outside
+//! development the application refuses to start when a requirement is not
+//! met (e.g. no `ESTA_SIGNING_PUBLIC_KEY`), and an unrecognized profile name
+//! is an error rather than a fallback to development. `kernel_get_status`
+//! reports the profile in effect.
+//!
+//! `ESTA_SANDBOX_PROFILE` sets module limits in one value: `strict` (signed
+//! modules, 5M fuel per call, 16 MiB, a 10 second timeout, no file or
+//! network access), `standard` (the defaults), or `dev` (generous limits).
+//! A module's manifest can name a stricter `sandbox` for itself; the profile
+//! each module runs under is recorded in its `ModuleLoaded` audit entry.
+//!
+//! ## Kernel Configuration File
+//!
+//! `ESTA_KERNEL_CONFIG` (default `esta-kernel.toml` in the data directory)
+//! names a TOML file holding the sandbox profile, module and audit
+//! directories, trusted signing keys, and execution limits, read at startup
+//! when it exists. Environment variables win over the file. A malformed file
+//! stops the application from starting.
+//!
+//! `kernel_reload_config` re-reads the file and applies its execution limits
+//! to the running kernel. Limits that are fixed at startup (the invocation
+//! scheduler, and the directories and keys) are reported as needing a
+//! restart; the reload is audited as `ConfigReloaded`.
+//!
+//! ## Reminders
+//!
+//! Recurring compliance reminders ("annual carryover processing due") are
+//! kept in `ESTA_REMINDERS_FILE` (default `reminders.json` in the data
+//! directory). Due reminders are shown as OS notifications and emitted to the
+//! frontend as `reminder-due` events, checked at startup and every hour.
+//! Read replicas neither fire nor change reminders.
+//!
+//! ## Alerts
+//!
+//! Sick time used beyond an employee's balance or past the annual limit, and
+//! a module crashing three times within ten minutes, raise alerts. Each is
+//! emitted to the frontend as an `alert://raised` event, shown as an OS
+//! notification, and posted as JSON to its tenant's webhooks. Kinds can be
+//! switched off and webhooks set per tenant with `alerts_set_config`; tenants
+//! without their own configuration use the default (`tenant_id` omitted).
+//! The configuration is kept in `ESTA_ALERTS_FILE` (default `alerts.json` in
+//! the data directory). Webhooks must be HTTPS URLs on a host listed in
+//! `ESTA_WEBHOOK_HOSTS` (comma-separated); without it, none can be set.
+//! Read replicas raise no alerts.
+//!
+//! ## Read Replica Mode
+//!
+//! With `ESTA_READ_REPLICA=1` the application opens the primary's data files
+//! as read-only snapshots, refreshed before each report. Heavy reporting can
+//! then run in a separate process without contending with interactive accrual
+//! processing; every write (policy changes, imports) is rejected.
+//!
+//! ## Errors
+//!
+//! Failed responses carry a localized, non-technical `error` message, a stable
+//! `error_code`, and a suggested `remediation`; the raw error text is kept in
+//! `error_detail` for logs and support. Set `ESTA_LOCALE` (e.g. `es-MX`) to
+//! choose the message language. When a module declined a request through its
+//! response envelope, the module's own `code` and `message` are passed on as
+//! `module_error`; when it trapped, `trap` says how (`fuel_exhausted`,
+//! `memory_limit`, `unreachable`, `host_error`, or `timeout`).
 
 #![cfg_attr(
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
 
+mod alerts;
+mod audit_stream;
+mod import;
+mod ipc_audit;
+mod keychain;
+mod reminders;
+mod session;
+mod supervision;
+
+use esta_kernel::{clock, correlation};
+use esta_kernel::user_errors::{self, ErrorCode, Locale, UserError, UserFacing};
+use esta_kernel::stats_history::DEFAULT_RETENTION as DEFAULT_STATS_RETENTION;
+use esta_kernel::security::audit::{AuditEntry, AuditFailurePolicy, AuditLogConfig, UnknownFailurePolicy};
+use esta_kernel::{
+    AlertConfig, AlertMonitor, ArchiveConfig, AuditFilter, AuditLog, AuditQuery, CapabilityMetricsConfig, CrashDumps, Date, ExecutionConfig, ExecutionReport, GlAccountMapping, InvocationArchive, Kernel,
+    Database, KernelError, Ledger, ModuleCatalog, ModuleError, OutputPathError, OutputRoot, Page, PageRequest, PolicyFile, PolicyVersion, ReportTemplate,
+    ChildSpec, FieldCipher, KernelConfig, ResourceProfileConfig, ResultCacheConfig, SecretStore, SecurityProfile, StatsHistory, StorageLimits, TenantFuelConfig, TenantRegistry,
+    SandboxProfile, Supervisor, TrapKind, TrustStore, UnknownProfile, UnknownSandbox, WageRate,
+};
+use audit_stream::AuditStreams;
+use import::ImportTimesheetRequest;
+use ipc_audit::{IpcAuditConfig, IpcCall};
+use reminders::{NewReminder, Recurrence, ReminderStore};
+use session::{NewAccount, SessionStore};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use tauri::api::notification::Notification;
+use tauri::{command, Manager, State};
 use log::{info, error, warn};
-use std::collections::HashMap;
 
 /// Request payload for kernel invocation
 #[derive(Debug, Deserialize)]
@@ -40,7 +488,114 @@ pub struct KernelRequest {
 pub struct KernelResponse {
     pub success: bool,
     pub data: Option<serde_json::Value>,
+    /// Localized, non-technical message for users
     pub error: Option<String>,
+    /// Stable error code (see `esta_kernel::user_errors`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'static str>,
+    /// Suggested next step for users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Technical error text for logs and support
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<String>,
+    /// Error the module reported in its response envelope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_error: Option<ModuleError>,
+    /// How the module call failed, if it trapped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trap: Option<TrapKind>,
+    /// Correlation ID of the request; audit entries it caused carry the same ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl KernelResponse {
+    /// Successful response carrying `data`
+    pub fn ok(data: serde_json::Value) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            error: None,
+            error_code: None,
+            remediation: None,
+            error_detail: None,
+            module_error: None,
+            trap: None,
+            correlation_id: None,
+        }
+    }
+
+    /// Failed response describing a user error
+    pub fn failure(error: UserError) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error.message),
+            error_code: Some(error.code),
+            remediation: Some(error.remediation),
+            error_detail: error.detail,
+            module_error: None,
+            trap: error.trap,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Run a command's handler under a correlation ID, check the caller's role, and audit the call
+///
+/// The frontend may supply the ID; otherwise one is generated. Every audit
+/// entry written while the handler runs carries it, and so does the response.
+/// Callers without a role allowed to run the command (see [`session`]) are
+/// refused before the handler runs. The call itself is recorded as an `ipc`
+/// audit event (see [`ipc_audit`]).
+async fn traced(
+    state: &AppState,
+    sessions: &SessionStore,
+    command: &str,
+    correlation_id: Option<String>,
+    handler: impl std::future::Future<Output = KernelResponse>,
+) -> KernelResponse {
+    let correlation_id = correlation::accept(correlation_id.as_deref());
+    let started = std::time::Instant::now();
+    let mut response = correlation::scope(correlation_id.clone(), async {
+        let (role, response) = match sessions.authorize(command) {
+            Ok(role) => (role, handler.await),
+            Err(denied) => {
+                warn!("Refused {}: {}", command, denied.detail);
+                let role = sessions.current().map_or(session::ANONYMOUS_ROLE, |s| s.role.as_str());
+                (role, state.rejection(denied.code, denied.detail))
+            }
+        };
+        let call = IpcCall {
+            command,
+            role,
+            correlation_id: &correlation_id,
+            outcome: response.error_code.unwrap_or(if response.success { "ok" } else { "INTERNAL" }),
+            duration_ms: ipc_audit::millis(started.elapsed()),
+        };
+        call.record(&state.kernel.audit_log(), &state.config.ipc_audit()).await;
+        response
+    })
+    .await;
+    if !response.success {
+        warn!("Request {} failed: {}", correlation_id, response.error_detail.as_deref().unwrap_or("no detail"));
+    }
+    response.correlation_id = Some(correlation_id);
+    response
+}
+
+/// Run `handler` if the signed-in user may act on `tenant_id` (`None` names no tenant)
+async fn for_tenant(
+    state: &AppState,
+    sessions: &SessionStore,
+    tenant_id: Option<&str>,
+    handler: impl std::future::Future<Output = KernelResponse>,
+) -> KernelResponse {
+    match tenant_id.map(|tenant_id| sessions.authorize_tenant(tenant_id)) {
+        Some(Err(denied)) => state.rejection(denied.code, denied.detail),
+        _ => handler.await,
+    }
 }
 
 /// Request to load a module
@@ -50,6 +605,35 @@ pub struct LoadModuleRequest {
     pub manifest_path: String,
 }
 
+/// Request to install a module from the modules directory
+#[derive(Debug, Deserialize)]
+pub struct InstallModuleRequest {
+    /// Module name from its manifest
+    pub name: String,
+    /// Version to install; the highest available version when omitted
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// Request to roll a module back to a stored version
+#[derive(Debug, Deserialize)]
+pub struct RollbackModuleRequest {
+    /// Module name from its manifest
+    pub name: String,
+    /// Stored version label, as listed in `stored_versions`
+    pub version: String,
+}
+
+/// Request to unload a running module
+#[derive(Debug, Deserialize)]
+pub struct UnloadModuleRequest {
+    /// Module name from its manifest
+    pub name: String,
+    /// Write the module's final statistics to the stats history first
+    #[serde(default)]
+    pub flush: bool,
+}
+
 /// Request to execute a module function
 #[derive(Debug, Deserialize)]
 pub struct ExecuteRequest {
@@ -59,6 +643,47 @@ pub struct ExecuteRequest {
     pub function: String,
     /// Input data as JSON
     pub input: serde_json::Value,
+    /// Tenant the execution runs on behalf of; its input may name no other
+    pub tenant_id: String,
+    /// Run the calculation without recording anything but a dry-run audit marker
+    #[serde(default)]
+    pub dry_run: bool,
+    /// ID the frontend picks so it can stop the execution with `kernel_cancel`
+    #[serde(default)]
+    pub invocation_id: Option<String>,
+    /// Run the module even if the result cache holds an output for this call
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+/// Request to cancel a running execution
+#[derive(Debug, Deserialize)]
+pub struct CancelRequest {
+    /// `invocation_id` given to `kernel_execute`
+    pub invocation_id: String,
+}
+
+/// Request to replay recorded invocations
+#[derive(Debug, Deserialize)]
+pub struct ReplayRequest {
+    /// First audit log sequence number to replay
+    pub first_sequence: u64,
+    /// Last audit log sequence number to replay; the end of the log when omitted
+    #[serde(default)]
+    pub last_sequence: Option<u64>,
+}
+
+/// Request for hourly module statistics
+#[derive(Debug, Deserialize)]
+pub struct StatsHistoryRequest {
+    /// Only this module; every module when omitted
+    #[serde(default)]
+    pub module: Option<String>,
+    /// Start of the range (ms since Unix epoch, inclusive)
+    pub from: u64,
+    /// End of the range (ms since Unix epoch, exclusive); now when omitted
+    #[serde(default)]
+    pub to: Option<u64>,
 }
 
 /// Request for log entries
@@ -66,10 +691,21 @@ pub struct ExecuteRequest {
 pub struct GetLogsRequest {
     /// Number of entries to retrieve
     pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
     /// Filter by source
     pub source: Option<String>,
     /// Get entries after this sequence number
     pub after_sequence: Option<u64>,
+    /// Get entries at or after this time (ms since Unix epoch)
+    pub from_timestamp: Option<u64>,
+    /// Get entries at or before this time (ms since Unix epoch)
+    pub to_timestamp: Option<u64>,
+    /// Get entries written while handling this request
+    pub correlation_id: Option<String>,
+    /// Get entries written on behalf of this tenant
+    pub tenant_id: Option<String>,
 }
 
 /// Tenant policy configuration
@@ -80,6 +716,90 @@ pub struct TenantPolicy {
     pub accrual_rate: f64,     // Default 1:30 (1 minute per 30 minutes worked)
     pub max_carryover_hours: u32,
     pub max_usage_hours: u32,
+    /// Opt in to informational usage pattern insights
+    #[serde(default)]
+    pub usage_insights: bool,
+    /// First day the policy applies (YYYY-MM-DD); defaults to today
+    #[serde(default)]
+    pub effective_from: Option<String>,
+    /// Jurisdiction whose statute applies, e.g. "US-MN"; defaults to the kernel's
+    #[serde(default)]
+    pub jurisdiction: Option<String>,
+    /// Days employee data is kept in the ledger and audit log before redaction
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+}
+
+/// Output format of a compliance report
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Pdf,
+}
+
+/// Request for a tenant's annual compliance report
+#[derive(Debug, Deserialize)]
+pub struct GenerateReportRequest {
+    pub tenant_id: String,
+    /// Calendar year to report on
+    pub year: i32,
+    #[serde(default)]
+    pub format: ReportFormat,
+    /// File to write the PDF to, relative to the reports directory (required for PDF output)
+    #[serde(default)]
+    pub output_path: Option<String>,
+}
+
+/// Request for a compliance report laid out by a saved template
+#[derive(Debug, Deserialize)]
+pub struct CustomReportRequest {
+    pub tenant_id: String,
+    /// Calendar year to report on
+    pub year: i32,
+    /// Name of the saved template
+    pub template: String,
+    /// Template version; defaults to the latest
+    #[serde(default)]
+    pub version: Option<u32>,
+}
+
+/// Output format of an accrued liability report
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LiabilityFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Request for a tenant's accrued sick-leave liability
+#[derive(Debug, Deserialize)]
+pub struct LiabilityReportRequest {
+    pub tenant_id: String,
+    /// Balance date (YYYY-MM-DD); defaults to today
+    #[serde(default)]
+    pub as_of: Option<String>,
+    /// Wage rates from payroll; employees without one are listed but not valued
+    #[serde(default)]
+    pub wages: Vec<WageRate>,
+    #[serde(default)]
+    pub gl_accounts: GlAccountMapping,
+    #[serde(default)]
+    pub format: LiabilityFormat,
+    /// File to write the CSV to, relative to the reports directory (required for CSV output)
+    #[serde(default)]
+    pub output_path: Option<String>,
+}
+
+/// Request for a tenant's usage pattern insights
+#[derive(Debug, Deserialize)]
+pub struct UsageInsightsRequest {
+    pub tenant_id: String,
+    /// Last day analyzed (YYYY-MM-DD); defaults to today
+    #[serde(default)]
+    pub as_of: Option<String>,
 }
 
 /// Employee accrual query
@@ -89,6 +809,651 @@ pub struct EmployeeAccrualQuery {
     pub employee_id: String,
 }
 
+/// Runtime configuration for the desktop shell
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    /// Manifest of the accrual module loaded at startup
+    pub accrual_manifest: Option<String>,
+    /// Reject legacy calculation requests instead of routing them to the kernel
+    pub fail_on_legacy_requests: bool,
+    /// Directory for archived invocation inputs/outputs; archival is off when unset
+    pub archive_dir: Option<String>,
+    /// Modules whose invocations are always archived
+    pub archive_modules: Vec<String>,
+    /// Fraction of other invocations to archive
+    pub archive_sample_rate: f64,
+    /// Largest invocation input recorded verbatim in the audit log
+    pub recorded_input_bytes: usize,
+    /// Directory for encrypted crash dumps of trapped modules; off when unset
+    pub crash_dump_dir: Option<String>,
+    /// File holding tenant policy history; history is kept in memory when unset
+    pub policy_file: Option<String>,
+    /// JSON Lines file holding the accrual ledger; the ledger is kept in memory when unset
+    pub ledger_file: Option<String>,
+    /// Directory of installable rule modules and their manifests
+    pub modules_dir: Option<String>,
+    /// Previous module versions kept for rollback; the catalog default when unset
+    pub module_versions_kept: Option<usize>,
+    /// Directory for precompiled modules
+    pub module_cache_dir: Option<String>,
+    /// Directory of statute files shipped with rule packs
+    pub statutes_dir: Option<String>,
+    /// Directory of persisted audit log segments
+    pub audit_dir: Option<String>,
+    /// What to refuse while audit segment writes fail; `block` when unset
+    pub audit_failure_policy: Option<String>,
+    /// Trusted module signing key (hex Ed25519); signatures are not checked when unset
+    pub signing_public_key: Option<String>,
+    /// File holding trusted signing keys across rotations
+    pub trust_file: Option<String>,
+    /// Encrypted secret store file
+    pub secrets_file: Option<String>,
+    /// Passphrase unlocking the secret store; the capability secret stays in memory when unset
+    pub secret_passphrase: Option<String>,
+    /// Unlock the secret store with a master key kept in the OS keychain when no passphrase is set
+    pub secret_keychain: bool,
+    /// Invocations of one module run at once; the kernel default when unset
+    pub max_concurrent_invocations: Option<usize>,
+    /// Invocations of one module allowed to wait; the kernel default when unset
+    pub max_queued_invocations: Option<usize>,
+    /// Seconds shutdown waits for in-flight invocations; the kernel default when unset
+    pub shutdown_drain_secs: Option<u64>,
+    /// Stop invocations far outside their module's resource profile
+    pub enforce_resource_profiles: bool,
+    /// Answer repeated calls from the kernel's result cache
+    pub result_cache: bool,
+    /// Seconds a cached result is served; the kernel default when unset
+    pub result_cache_ttl_secs: Option<u64>,
+    /// Fuel any tenant may use per hour; unlimited when unset
+    pub tenant_fuel_ceiling: Option<u64>,
+    /// Hourly fuel ceilings for individual tenants, overriding `tenant_fuel_ceiling`
+    pub tenant_fuel_ceilings: BTreeMap<String, u64>,
+    /// Price of a billion fuel units in cents; usage is unpriced when unset
+    pub fuel_price_cents_per_billion: Option<u64>,
+    /// Capability denials per minute that raise a security alert; no alerts when unset
+    pub capability_denial_alert: Option<u64>,
+    /// Total storage size (MiB) above which usage reports raise an alert
+    pub storage_alert_mb: Option<u64>,
+    /// Age (days) after which vacuuming removes archived invocations; kept forever when unset
+    pub archive_retention_days: Option<u64>,
+    /// Hours between scheduled vacuums; vacuuming only on request when unset
+    pub vacuum_interval_hours: Option<u64>,
+    /// JSON Lines file holding hourly module statistics
+    pub stats_file: Option<String>,
+    /// Age (days) after which statistics history is dropped; 90 when unset
+    pub stats_retention_days: Option<u64>,
+    /// JSON file of supervised modules' crash histories
+    pub supervisor_file: Option<String>,
+    /// SQLite database tenants, rosters, and the ledger are written through to
+    pub database_file: Option<String>,
+    /// Encrypt employee records in the database under a key in the secret store
+    pub encrypt_database: bool,
+    /// Directory providing default policy, ledger, and modules locations
+    pub data_dir: Option<String>,
+    /// Open storage as read-only snapshots of a running primary
+    pub read_replica: bool,
+    /// Language for user-facing error messages
+    pub locale: Locale,
+    /// Security profile name; development when unset
+    pub security_profile: Option<String>,
+    /// Sandbox profile name setting module limits; the kernel defaults when unset
+    pub sandbox_profile: Option<String>,
+    /// File holding compliance reminders; reminders are kept in memory when unset
+    pub reminders_file: Option<String>,
+    /// File holding user accounts; accounts are kept in memory when unset
+    pub accounts_file: Option<String>,
+    /// Fraction of read-only commands recorded in the audit log; every call when unset
+    pub ipc_audit_sample_rate: Option<f64>,
+    /// Read-only commands recorded on every call regardless of sampling
+    pub ipc_audit_always: Vec<String>,
+    /// File holding alert configuration; kept in memory when unset
+    pub alerts_file: Option<String>,
+    /// Hosts alert webhooks may point at; no webhooks can be set when empty
+    pub webhook_hosts: Vec<String>,
+    /// Kernel configuration file; `esta-kernel.toml` in the data directory when unset
+    pub kernel_config_file: Option<String>,
+    /// Settings read from the kernel configuration file, overridden by the environment
+    pub kernel: KernelConfig,
+}
+
+impl AppConfig {
+    /// Read configuration from `ESTA_*` environment variables
+    pub fn from_env() -> Self {
+        Self {
+            accrual_manifest: std::env::var("ESTA_ACCRUAL_MANIFEST").ok(),
+            fail_on_legacy_requests: std::env::var("ESTA_FAIL_ON_LEGACY_REQUESTS")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            archive_dir: std::env::var("ESTA_ARCHIVE_DIR").ok(),
+            archive_modules: std::env::var("ESTA_ARCHIVE_MODULES")
+                .map(|v| v.split(',').map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).collect())
+                .unwrap_or_default(),
+            archive_sample_rate: std::env::var("ESTA_ARCHIVE_SAMPLE_RATE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.0),
+            crash_dump_dir: std::env::var("ESTA_CRASH_DUMP_DIR").ok(),
+            recorded_input_bytes: std::env::var("ESTA_RECORD_INPUT_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            policy_file: std::env::var("ESTA_POLICY_FILE").ok(),
+            ledger_file: std::env::var("ESTA_LEDGER_FILE").ok(),
+            modules_dir: std::env::var("ESTA_MODULES_DIR").ok(),
+            module_versions_kept: std::env::var("ESTA_MODULE_VERSIONS_KEPT")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            module_cache_dir: std::env::var("ESTA_MODULE_CACHE_DIR").ok(),
+            statutes_dir: std::env::var("ESTA_STATUTES_DIR").ok(),
+            audit_dir: std::env::var("ESTA_AUDIT_DIR").ok(),
+            audit_failure_policy: std::env::var("ESTA_AUDIT_FAILURE_POLICY").ok().filter(|p| !p.is_empty()),
+            signing_public_key: std::env::var("ESTA_SIGNING_PUBLIC_KEY").ok().filter(|k| !k.is_empty()),
+            trust_file: std::env::var("ESTA_TRUST_FILE").ok(),
+            secrets_file: std::env::var("ESTA_SECRETS_FILE").ok(),
+            secret_passphrase: std::env::var("ESTA_SECRET_PASSPHRASE").ok().filter(|p| !p.is_empty()),
+            secret_keychain: std::env::var("ESTA_SECRET_KEYCHAIN")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            max_concurrent_invocations: std::env::var("ESTA_MAX_CONCURRENT_INVOCATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            max_queued_invocations: std::env::var("ESTA_MAX_QUEUED_INVOCATIONS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            shutdown_drain_secs: std::env::var("ESTA_SHUTDOWN_DRAIN_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            enforce_resource_profiles: std::env::var("ESTA_ENFORCE_RESOURCE_PROFILES")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            result_cache: std::env::var("ESTA_RESULT_CACHE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            result_cache_ttl_secs: std::env::var("ESTA_RESULT_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            tenant_fuel_ceiling: std::env::var("ESTA_TENANT_FUEL_CEILING")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            tenant_fuel_ceilings: std::env::var("ESTA_TENANT_FUEL_CEILINGS")
+                .map(|v| parse_fuel_ceilings(&v))
+                .unwrap_or_default(),
+            fuel_price_cents_per_billion: std::env::var("ESTA_FUEL_PRICE_CENTS_PER_BILLION")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            capability_denial_alert: std::env::var("ESTA_CAPABILITY_DENIAL_ALERT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&denials| denials > 0),
+            storage_alert_mb: std::env::var("ESTA_STORAGE_ALERT_MB")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            archive_retention_days: std::env::var("ESTA_ARCHIVE_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            vacuum_interval_hours: std::env::var("ESTA_VACUUM_INTERVAL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&hours| hours > 0),
+            stats_file: std::env::var("ESTA_STATS_FILE").ok(),
+            stats_retention_days: std::env::var("ESTA_STATS_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            supervisor_file: std::env::var("ESTA_SUPERVISOR_FILE").ok(),
+            database_file: std::env::var("ESTA_DATABASE_FILE").ok(),
+            encrypt_database: std::env::var("ESTA_ENCRYPT_DATABASE")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            data_dir: std::env::var("ESTA_DATA_DIR").ok(),
+            read_replica: std::env::var("ESTA_READ_REPLICA")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            locale: std::env::var("ESTA_LOCALE")
+                .map(|tag| Locale::from_tag(&tag))
+                .unwrap_or_default(),
+            security_profile: std::env::var("ESTA_SECURITY_PROFILE").ok().filter(|p| !p.is_empty()),
+            sandbox_profile: std::env::var("ESTA_SANDBOX_PROFILE").ok().filter(|p| !p.is_empty()),
+            reminders_file: std::env::var("ESTA_REMINDERS_FILE").ok(),
+            accounts_file: std::env::var("ESTA_ACCOUNTS_FILE").ok(),
+            ipc_audit_sample_rate: std::env::var("ESTA_IPC_AUDIT_SAMPLE_RATE")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            ipc_audit_always: std::env::var("ESTA_IPC_AUDIT_ALWAYS")
+                .map(|v| v.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect())
+                .unwrap_or_default(),
+            alerts_file: std::env::var("ESTA_ALERTS_FILE").ok(),
+            webhook_hosts: std::env::var("ESTA_WEBHOOK_HOSTS")
+                .map(|v| v.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+                .unwrap_or_default(),
+            kernel_config_file: std::env::var("ESTA_KERNEL_CONFIG").ok(),
+            kernel: KernelConfig::default(),
+        }
+    }
+
+    /// Kernel configuration file: `kernel_config_file`, else `esta-kernel.toml` in the data directory
+    pub fn kernel_config_path(&self) -> Option<PathBuf> {
+        self.kernel_config_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("esta-kernel.toml")))
+    }
+
+    /// Read the kernel configuration file, if there is one
+    pub fn read_kernel_config(&self) -> Result<Option<KernelConfig>, String> {
+        match self.kernel_config_path() {
+            Some(path) if path.exists() => KernelConfig::from_file(path).map(Some).map_err(|e| e.to_string()),
+            _ => Ok(None),
+        }
+    }
+
+    /// This configuration with the kernel configuration file's settings loaded
+    pub fn with_kernel_config(self) -> Result<Self, String> {
+        Ok(match self.read_kernel_config()? {
+            Some(kernel) => Self { kernel, ..self },
+            None => self,
+        })
+    }
+
+    /// The configured security profile
+    ///
+    /// An unrecognized name is an error, never a silent fallback to development.
+    pub fn security_profile(&self) -> Result<SecurityProfile, String> {
+        self.security_profile
+            .as_deref()
+            .map_or(Ok(SecurityProfile::default()), |name| name.parse().map_err(|e: UnknownProfile| e.to_string()))
+    }
+
+    /// The configured sandbox profile, else the kernel configuration file's, if
+    /// any; an unrecognized name is an error
+    pub fn sandbox_profile(&self) -> Result<Option<SandboxProfile>, String> {
+        match self.sandbox_profile.as_deref() {
+            Some(name) => name.parse().map(Some).map_err(|e: UnknownSandbox| e.to_string()),
+            None => Ok(self.kernel.sandbox),
+        }
+    }
+
+    /// Build the invocation archive described by this configuration
+    pub fn invocation_archive(&self) -> Option<InvocationArchive> {
+        let dir = self.archive_dir.as_ref()?;
+        let config = ArchiveConfig {
+            modules: self.archive_modules.iter().cloned().collect(),
+            sample_rate: self.archive_sample_rate,
+        };
+        Some(InvocationArchive::new(config, dir))
+    }
+
+    /// Policy history file: `policy_file`, else `policies.json` in the data directory
+    pub fn policy_path(&self) -> Option<PathBuf> {
+        self.policy_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("policies.json")))
+    }
+
+    /// Ledger file: `ledger_file`, else `ledger.jsonl` in the data directory
+    pub fn ledger_path(&self) -> Option<PathBuf> {
+        self.ledger_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("ledger.jsonl")))
+    }
+
+    /// Modules directory: `modules_dir`, else the kernel configuration file's,
+    /// else `modules/` in the data directory
+    pub fn modules_path(&self) -> Option<PathBuf> {
+        self.modules_dir.as_ref().map(PathBuf::from)
+            .or_else(|| self.kernel.module_dir.clone())
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("modules")))
+    }
+
+    /// Module cache directory: `module_cache_dir`, else `module-cache/` in the data directory
+    pub fn module_cache_path(&self) -> Option<PathBuf> {
+        self.module_cache_dir.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("module-cache")))
+    }
+
+    /// Statutes directory: `statutes_dir`, else `statutes/` in the modules directory
+    pub fn statutes_path(&self) -> Option<PathBuf> {
+        self.statutes_dir.as_ref().map(PathBuf::from)
+            .or_else(|| self.modules_path().map(|dir| dir.join("statutes")))
+    }
+
+    /// Audit segment directory: `audit_dir`, else the kernel configuration
+    /// file's, else `audit/` in the data directory
+    pub fn audit_path(&self) -> Option<PathBuf> {
+        self.audit_dir.as_ref().map(PathBuf::from)
+            .or_else(|| self.kernel.audit_dir.clone())
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("audit")))
+    }
+
+    /// Build the audit log, resuming persisted segments if configured
+    ///
+    /// Read replicas never write to the primary's audit directory. An
+    /// unrecognized failure policy is an error.
+    pub fn audit_log(&self) -> Result<AuditLog, String> {
+        let failure_policy = self
+            .audit_failure_policy
+            .as_deref()
+            .map_or(Ok(AuditFailurePolicy::default()), |name| {
+                name.parse().map_err(|e: UnknownFailurePolicy| e.to_string())
+            })?;
+        let audit_config = AuditLogConfig { failure_policy, ..AuditLogConfig::default() };
+        match (self.audit_path(), self.read_replica) {
+            (Some(dir), false) => AuditLog::with_segments(audit_config, dir).map_err(|e| e.to_string()),
+            _ => Ok(AuditLog::new(audit_config)),
+        }
+    }
+
+    /// Trust store file: `trust_file`, else `trust.json` in the data directory
+    pub fn trust_path(&self) -> Option<PathBuf> {
+        self.trust_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("trust.json")))
+    }
+
+    /// Open the signing key trust store if a signing key is configured
+    ///
+    /// `signing_public_key` wins over the kernel configuration file's
+    /// `trusted_keys`. Without a trust file location the store lives in
+    /// memory and rotations last until restart.
+    pub fn trust_store(&self) -> Result<Option<TrustStore>, String> {
+        let keys = match &self.signing_public_key {
+            Some(key) => std::slice::from_ref(key),
+            None if !self.kernel.trusted_keys.is_empty() => self.kernel.trusted_keys.as_slice(),
+            None => return Ok(None),
+        };
+        match self.trust_path() {
+            Some(path) => TrustStore::open_with_keys(path, keys),
+            None => TrustStore::with_keys(keys),
+        }
+        .map(Some)
+        .map_err(|e| e.to_string())
+    }
+
+    /// Secret store file: `secrets_file`, else `secrets.json` in the data directory
+    pub fn secrets_path(&self) -> Option<PathBuf> {
+        self.secrets_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("secrets.json")))
+    }
+
+    /// Whether a passphrase or the OS keychain is configured to unlock the secret store
+    pub fn secrets_enabled(&self) -> bool {
+        self.secret_passphrase.is_some() || self.secret_keychain
+    }
+
+    /// Unlock the secret store if a location and a passphrase or the keychain are configured
+    pub fn secret_store(&self) -> Result<Option<SecretStore>, String> {
+        match (self.secrets_path(), &self.secret_passphrase) {
+            (Some(path), Some(passphrase)) => SecretStore::open_with_passphrase(path, passphrase)
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            (Some(path), None) if self.secret_keychain => {
+                let key = keychain::master_key(&path)?;
+                SecretStore::open_with_key(path, key).map(Some).map_err(|e| e.to_string())
+            }
+            (None, _) if self.secrets_enabled() => {
+                Err("The secret store requires ESTA_DATA_DIR or ESTA_SECRETS_FILE".to_string())
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Open the module catalog if a modules directory is configured
+    pub fn module_catalog(&self) -> Result<Option<ModuleCatalog>, String> {
+        self.modules_path()
+            .map(|dir| {
+                let catalog = ModuleCatalog::open(dir).map_err(|e| e.to_string())?;
+                Ok(match self.module_versions_kept {
+                    Some(versions) => catalog.with_retained_versions(versions),
+                    None => catalog,
+                })
+            })
+            .transpose()
+    }
+
+    /// Kernel execution limits from the sandbox profile and the kernel
+    /// configuration file, with the IPC payload limit, invocation scheduler,
+    /// shutdown, resource profile, result cache, tenant fuel, and capability
+    /// alert overrides applied
+    pub fn execution_config(&self) -> ExecutionConfig {
+        let defaults = KernelConfig {
+            sandbox: self.sandbox_profile().ok().flatten(),
+            ..self.kernel.clone()
+        }
+        .execution_config();
+        ExecutionConfig {
+            max_input_bytes: defaults.max_input_bytes.min(MAX_PAYLOAD_SIZE),
+            max_concurrent_invocations: self
+                .max_concurrent_invocations
+                .unwrap_or(defaults.max_concurrent_invocations),
+            max_queued_invocations: self
+                .max_queued_invocations
+                .unwrap_or(defaults.max_queued_invocations),
+            shutdown_drain_timeout: self
+                .shutdown_drain_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.shutdown_drain_timeout),
+            resource_profile: ResourceProfileConfig {
+                enforce: self.enforce_resource_profiles,
+                ..defaults.resource_profile.clone()
+            },
+            result_cache: ResultCacheConfig {
+                enabled: self.result_cache || defaults.result_cache.enabled,
+                ttl: self.result_cache_ttl_secs.map(Duration::from_secs).unwrap_or(defaults.result_cache.ttl),
+                ..defaults.result_cache.clone()
+            },
+            tenant_fuel: TenantFuelConfig {
+                default_ceiling: self.tenant_fuel_ceiling,
+                ceilings: self.tenant_fuel_ceilings.clone(),
+                price_cents_per_billion: self.fuel_price_cents_per_billion.unwrap_or(0),
+                ..defaults.tenant_fuel.clone()
+            },
+            capability_metrics: CapabilityMetricsConfig {
+                denial_alert_threshold: self.capability_denial_alert,
+                ..defaults.capability_metrics.clone()
+            },
+            ..defaults
+        }
+    }
+
+    /// Size alert and archive retention settings for storage maintenance
+    pub fn storage_limits(&self) -> StorageLimits {
+        StorageLimits {
+            alert_bytes: self.storage_alert_mb.map(|mb| mb * 1024 * 1024),
+            archive_retention: self.archive_retention_days.map(|days| Duration::from_secs(days * 86_400)),
+        }
+    }
+
+    /// Statistics history file: `stats_file`, else `stats.jsonl` in the data directory
+    pub fn stats_path(&self) -> Option<PathBuf> {
+        self.stats_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("stats.jsonl")))
+    }
+
+    /// Crash history file: `supervisor_file`, else `supervisor.json` in the data
+    /// directory; read replicas keep none, since the primary supervises its own modules
+    pub fn supervisor_path(&self) -> Option<PathBuf> {
+        if self.read_replica {
+            return None;
+        }
+        self.supervisor_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("supervisor.json")))
+    }
+
+    /// Database file: `database_file`, else `esta.db` in the data directory
+    pub fn database_path(&self) -> Option<PathBuf> {
+        self.database_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("esta.db")))
+    }
+
+    /// Directory exported reports are confined to: `reports/` in the data directory
+    pub fn reports_path(&self) -> Option<PathBuf> {
+        self.data_dir.as_ref().map(|dir| Path::new(dir).join("reports"))
+    }
+
+    /// Directory backups are confined to: `backups/` in the data directory
+    pub fn backups_path(&self) -> Option<PathBuf> {
+        self.data_dir.as_ref().map(|dir| Path::new(dir).join("backups"))
+    }
+
+    /// Open the database if a location is configured
+    ///
+    /// With `encrypt_database`, its key is taken from (or generated in) the
+    /// secret store. Read replicas open none; they read the primary's files.
+    pub fn database(&self, secrets: Option<&mut SecretStore>) -> Result<Option<Database>, String> {
+        let Some(path) = self.database_path().filter(|_| !self.read_replica) else {
+            return Ok(None);
+        };
+        let database = if self.encrypt_database {
+            let store = secrets.ok_or("ESTA_ENCRYPT_DATABASE requires ESTA_SECRET_PASSPHRASE or ESTA_SECRET_KEYCHAIN")?;
+            let cipher = FieldCipher::from_store(store).map_err(|e| e.to_string())?;
+            Database::open_encrypted(path, cipher)
+        } else {
+            Database::open(path)
+        };
+        database.map(Some).map_err(|e| format!("{:#}", e))
+    }
+
+    /// Open the module statistics history if a location is configured
+    ///
+    /// Read replicas keep no history; the primary records its own modules.
+    pub fn stats_history(&self) -> Result<Option<StatsHistory>, String> {
+        let retention = self
+            .stats_retention_days
+            .map_or(DEFAULT_STATS_RETENTION, |days| Duration::from_secs(days * 86_400));
+        match (self.stats_path(), self.read_replica) {
+            (Some(path), false) => StatsHistory::with_file(path, retention).map(Some).map_err(|e| e.to_string()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Build the tenant registry, loading persisted policy history if configured
+    pub fn tenant_registry(&self) -> Result<TenantRegistry, String> {
+        match (self.policy_path(), self.read_replica) {
+            (Some(path), false) => TenantRegistry::with_policy_file(PolicyFile::new(path))
+                .map_err(|e| e.to_string()),
+            (Some(path), true) => TenantRegistry::snapshot(PolicyFile::new(path))
+                .map_err(|e| e.to_string()),
+            (None, false) => Ok(TenantRegistry::new()),
+            (None, true) => Err("Read replica mode requires ESTA_DATA_DIR or ESTA_POLICY_FILE".to_string()),
+        }
+    }
+
+    /// Reminders file: `reminders_file`, else `reminders.json` in the data directory
+    pub fn reminders_path(&self) -> Option<PathBuf> {
+        self.reminders_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("reminders.json")))
+    }
+
+    /// Alerts file: `alerts_file`, else `alerts.json` in the data directory
+    pub fn alerts_path(&self) -> Option<PathBuf> {
+        self.alerts_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("alerts.json")))
+    }
+
+    /// Build the alert monitor, loading persisted configuration if configured
+    ///
+    /// Read replicas keep theirs in memory; they raise no alerts.
+    pub fn alert_monitor(&self) -> Result<AlertMonitor, String> {
+        let monitor = AlertMonitor::new().with_webhook_hosts(self.webhook_hosts.iter().cloned());
+        match (self.alerts_path(), self.read_replica) {
+            (Some(path), false) => monitor.with_file(path).map_err(|e| e.to_string()),
+            _ => Ok(monitor),
+        }
+    }
+
+    /// Accounts file: `accounts_file`, else `accounts.json` in the data directory
+    pub fn accounts_path(&self) -> Option<PathBuf> {
+        self.accounts_file.as_ref().map(PathBuf::from)
+            .or_else(|| self.data_dir.as_ref().map(|dir| Path::new(dir).join("accounts.json")))
+    }
+
+    /// Open the account store
+    ///
+    /// Every profile but production runs as the local user until the first
+    /// account is created.
+    pub fn session_store(&self) -> Result<SessionStore, String> {
+        let allow_local = !matches!(self.security_profile()?, SecurityProfile::Production);
+        match self.accounts_path() {
+            Some(path) => SessionStore::open(path, allow_local).map_err(|e| format!("{:#}", e)),
+            None => Ok(SessionStore::in_memory(allow_local)),
+        }
+    }
+
+    /// Which command calls are recorded in the audit log
+    pub fn ipc_audit(&self) -> IpcAuditConfig {
+        IpcAuditConfig {
+            sample_rate: self.ipc_audit_sample_rate.unwrap_or(1.0).clamp(0.0, 1.0),
+            always: self.ipc_audit_always.clone(),
+        }
+    }
+
+    /// Open the reminder store, loading persisted reminders if configured
+    ///
+    /// Read replicas keep an empty store so the primary alone fires reminders.
+    pub fn reminder_store(&self) -> Result<ReminderStore, String> {
+        match (self.reminders_path(), self.read_replica) {
+            (Some(path), false) => ReminderStore::open(path).map_err(|e| format!("{:#}", e)),
+            _ => Ok(ReminderStore::in_memory()),
+        }
+    }
+
+    /// Build the accrual ledger, loading persisted events if configured
+    pub fn ledger(&self) -> Result<Ledger, String> {
+        match (self.ledger_path(), self.read_replica) {
+            (Some(path), false) => Ledger::with_file(path).map_err(|e| e.to_string()),
+            (Some(path), true) => Ledger::snapshot(path).map_err(|e| e.to_string()),
+            (None, false) => Ok(Ledger::new()),
+            (None, true) => Err("Read replica mode requires ESTA_DATA_DIR or ESTA_LEDGER_FILE".to_string()),
+        }
+    }
+}
+
+/// Parse `tenant=fuel` pairs separated by commas, skipping malformed ones
+fn parse_fuel_ceilings(value: &str) -> BTreeMap<String, u64> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (tenant, fuel) = pair.split_once('=')?;
+            Some((tenant.trim().to_string(), fuel.trim().parse().ok()?))
+        })
+        .filter(|(tenant, _)| !tenant.is_empty())
+        .collect()
+}
+
+/// State shared by all command handlers
+pub struct AppState {
+    pub kernel: Kernel,
+    pub config: AppConfig,
+}
+
+impl AppState {
+    /// Failed response for a typed error
+    fn error_response(&self, error: &dyn UserFacing) -> KernelResponse {
+        KernelResponse::failure(error.to_user_error(self.config.locale))
+    }
+
+    /// Failed response for a kernel error, classified by its typed cause
+    fn kernel_error_response(&self, error: &anyhow::Error) -> KernelResponse {
+        let mut response = KernelResponse::failure(user_errors::from_anyhow(error, self.config.locale));
+        response.module_error = error.chain().find_map(|cause| match cause.downcast_ref() {
+            Some(KernelError::ModuleReported { error, .. }) => Some(error.clone()),
+            _ => None,
+        });
+        response
+    }
+
+    /// Failed response for a request rejected before reaching the kernel
+    fn rejection(&self, code: ErrorCode, detail: impl Into<String>) -> KernelResponse {
+        KernelResponse::failure(UserError::new(code, self.config.locale, Some(detail.into())))
+    }
+
+    /// The file a caller named inside an output directory, or the response refusing it
+    fn output_file(&self, dir: Option<PathBuf>, name: &str) -> Result<PathBuf, KernelResponse> {
+        let Some(dir) = dir else {
+            return Err(self.rejection(ErrorCode::StorageUnavailable, "File output needs a data directory (ESTA_DATA_DIR)"));
+        };
+        let root = OutputRoot::new(&dir).map_err(|e| self.error_response(&OutputPathError::from(e)))?;
+        root.resolve(name).map_err(|e| {
+            warn!("Refused output path {:?}: {}", name, e);
+            self.error_response(&e)
+        })
+    }
+}
+
 /// Maximum allowed payload size (1MB)
 const MAX_PAYLOAD_SIZE: usize = 1_048_576;
 
@@ -100,6 +1465,7 @@ const ALLOWED_ACTIONS: &[&str] = &[
     "status", 
     "calculate",
     "report",
+    "simulate",
 ];
 
 /// Allowed modules for kernel invocation
@@ -109,6 +1475,7 @@ const ALLOWED_MODULES: &[&str] = &[
     "audit",
     "policy",
     "reporting",
+    "policy-sim",
 ];
 
 /// Validate the kernel request before processing
@@ -134,222 +1501,579 @@ fn validate_request(request: &KernelRequest) -> Result<(), String> {
     Ok(())
 }
 
-/// MI-ESTA accrual rate: 1 minute per 30 minutes worked
-const ACCRUAL_RATE: u64 = 30;
+/// Kernel module that owns accrual and balance calculations
+const ACCRUAL_MODULE: &str = "accrual";
 
-/// Calculate accrued sick time based on MI-ESTA rules
-fn calculate_accrual(minutes_worked: u64, employer_size: &str) -> u64 {
-    // MI-ESTA: 1 hour of sick time per 30 hours worked
-    // For simplicity, we use 1 minute per 30 minutes
-    // Note: employer_size may affect caps in future, but rate is same for all
-    let _ = employer_size; // Currently unused, rate is the same
-    minutes_worked / ACCRUAL_RATE
+/// Response shape expected by callers of a legacy calculation request
+#[derive(Debug, PartialEq)]
+enum LegacyShape {
+    Accrue { minutes_worked: u64, employer_size: String },
+    Validate,
+    CalculateAccrual,
+    CalculateBalance,
 }
 
-/// Invoke the ESTA kernel with a validated request.
-/// 
-/// This is the primary IPC bridge between the React frontend and the Rust kernel.
-/// All requests are validated before processing to prevent unauthorized operations.
-#[command]
-pub async fn invoke_kernel(request: KernelRequest) -> Result<KernelResponse, String> {
-    info!("Kernel invocation: action={}, module={}", request.action, request.module);
+/// A legacy calculation request translated into an accrual module call
+#[derive(Debug, PartialEq)]
+struct LegacyCall {
+    function: &'static str,
+    input: serde_json::Value,
+    shape: LegacyShape,
+}
 
-    // Validate request before processing
-    if let Err(e) = validate_request(&request) {
-        error!("Request validation failed: {}", e);
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some(e),
-        });
+impl LegacyCall {
+    fn accrue(employee_id: &str, minutes_worked: u64, employer_size: &str, shape: LegacyShape) -> Self {
+        Self {
+            function: "accrue_json",
+            input: serde_json::json!({
+                "employee_id": employee_id,
+                "minutes_worked": minutes_worked,
+                "employer_policy": { "employer_size": employer_size }
+            }),
+            shape,
+        }
     }
 
-    match request.action.as_str() {
-        "status" => Ok(KernelResponse {
-            success: true,
-            data: Some(serde_json::json!({
-                "kernel_version": env!("CARGO_PKG_VERSION"),
-                "status": "running",
-                "modules_loaded": [],
-                "fuel_limit": 20_000_000,
-                "memory_limit_bytes": 33_554_432
-            })),
-            error: None,
-        }),
+    fn validate(employee_id: &str, accrued_minutes: u64, used_minutes: u64, shape: LegacyShape) -> Self {
+        Self {
+            function: "validate_json",
+            input: serde_json::json!({
+                "employee_id": employee_id,
+                "accrued_minutes": accrued_minutes,
+                "used_minutes": used_minutes
+            }),
+            shape,
+        }
+    }
+
+    /// Use a tenant policy version for an accrual call
+    fn apply_policy(&mut self, version: &PolicyVersion) {
+        if self.function != "accrue_json" {
+            return;
+        }
+
+        let employer_size = version.policy.employer_size.clone();
+        self.input["employer_policy"] = serde_json::json!({
+            "employer_size": employer_size,
+            "accrual_rate": version.policy.accrual_rate,
+            "policy_version": version.version
+        });
+        if let LegacyShape::Accrue { employer_size: shape_size, .. } = &mut self.shape {
+            *shape_size = employer_size;
+        }
+    }
+
+    /// Map the module output back onto the response the legacy caller expects
+    fn into_response(self, output: &serde_json::Value) -> serde_json::Value {
+        match self.shape {
+            LegacyShape::Accrue { minutes_worked, employer_size } => serde_json::json!({
+                "accrued_minutes": output["accrued_minutes"],
+                "minutes_worked": minutes_worked,
+                "employer_size": employer_size,
+                "rate": output["metadata"]["calc"],
+                "source": "kernel"
+            }),
+            LegacyShape::Validate => serde_json::json!({
+                "valid": output["valid"],
+                "employee_id": output["employee_id"],
+                "balance": output["balance_minutes"],
+                "validation_errors": output["validation_errors"]
+            }),
+            LegacyShape::CalculateAccrual => serde_json::json!({
+                "result": output["accrued_minutes"],
+                "operation": "accrual"
+            }),
+            LegacyShape::CalculateBalance => serde_json::json!({
+                "result": output["balance_minutes"],
+                "operation": "balance"
+            }),
+        }
+    }
+}
+
+/// Translate a legacy `accrue`/`validate`/`calculate` request into a module call
+fn legacy_call(request: &KernelRequest) -> Result<LegacyCall, String> {
+    let payload = &request.payload;
+    let u64_field = |name: &str| payload.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+    let employee_id = payload.get("employee_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+
+    match request.action.as_str() {
         "accrue" => {
-            let minutes = request.payload.get("minutes_worked")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let employer_size = request.payload.get("employer_size")
+            let minutes = u64_field("minutes_worked");
+            let employer_size = payload.get("employer_size")
                 .and_then(|v| v.as_str())
                 .unwrap_or("small");
-            
-            let accrued = calculate_accrual(minutes, employer_size);
-            
-            Ok(KernelResponse {
-                success: true,
-                data: Some(serde_json::json!({
-                    "accrued_minutes": accrued,
-                    "minutes_worked": minutes,
-                    "employer_size": employer_size,
-                    "rate": "1:30",
-                    "source": "kernel"
-                })),
-                error: None,
-            })
-        },
-        "validate" => {
-            // Validate accrual data
-            let employee_id = request.payload.get("employee_id")
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-            let accrued = request.payload.get("accrued_minutes")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-            let used = request.payload.get("used_minutes")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
-
-            let valid = accrued >= used;
-            
-            Ok(KernelResponse {
-                success: true,
-                data: Some(serde_json::json!({
-                    "valid": valid,
-                    "employee_id": employee_id,
-                    "balance": accrued.saturating_sub(used),
-                    "validation_errors": if valid { vec![] } else { vec!["Used exceeds accrued"] }
-                })),
-                error: None,
-            })
-        },
-        "audit" => {
-            // Return audit information
-            Ok(KernelResponse {
-                success: true,
-                data: Some(serde_json::json!({
-                    "audit_enabled": true,
-                    "log_entries": 0,
-                    "chain_valid": true
-                })),
-                error: None,
-            })
+            Ok(LegacyCall::accrue(employee_id, minutes, employer_size, LegacyShape::Accrue {
+                minutes_worked: minutes,
+                employer_size: employer_size.to_string(),
+            }))
         },
+        "validate" => Ok(LegacyCall::validate(
+            employee_id,
+            u64_field("accrued_minutes"),
+            u64_field("used_minutes"),
+            LegacyShape::Validate,
+        )),
         "calculate" => {
-            // Generic calculation endpoint
-            let operation = request.payload.get("operation")
+            let operation = payload.get("operation")
                 .and_then(|v| v.as_str())
                 .unwrap_or("accrual");
-                
+
             match operation {
-                "accrual" => {
-                    let minutes = request.payload.get("minutes_worked")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    Ok(KernelResponse {
-                        success: true,
-                        data: Some(serde_json::json!({
-                            "result": minutes / 30,
-                            "operation": "accrual"
-                        })),
-                        error: None,
-                    })
-                },
-                "balance" => {
-                    let accrued = request.payload.get("accrued")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    let used = request.payload.get("used")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0);
-                    Ok(KernelResponse {
-                        success: true,
-                        data: Some(serde_json::json!({
-                            "result": accrued.saturating_sub(used),
-                            "operation": "balance"
-                        })),
-                        error: None,
-                    })
-                },
-                _ => Ok(KernelResponse {
-                    success: false,
-                    data: None,
-                    error: Some(format!("Unknown calculation operation: {}", operation)),
-                }),
+                "accrual" => Ok(LegacyCall::accrue(
+                    employee_id,
+                    u64_field("minutes_worked"),
+                    "small",
+                    LegacyShape::CalculateAccrual,
+                )),
+                "balance" => Ok(LegacyCall::validate(
+                    employee_id,
+                    u64_field("accrued"),
+                    u64_field("used"),
+                    LegacyShape::CalculateBalance,
+                )),
+                _ => Err(format!("Unknown calculation operation: {}", operation)),
             }
         },
-        _ => Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Action '{}' not yet implemented", request.action)),
-        }),
+        other => Err(format!("Action '{}' is not a legacy calculation", other)),
     }
 }
 
-/// Get kernel status including loaded modules and configuration
+/// Run a JSON call on a loaded kernel module and parse its output.
+/// Calls made for a tenant are scoped to that tenant by the kernel.
+/// Calls given an invocation ID can be cancelled with `kernel_cancel`.
+/// Returns the parsed output and the execution report.
+#[allow(clippy::too_many_arguments)]
+async fn execute_json(
+    kernel: &Kernel,
+    tenant_id: Option<&str>,
+    module: &str,
+    function: &str,
+    input: &serde_json::Value,
+    dry_run: bool,
+    bypass_cache: bool,
+    invocation_id: Option<&str>,
+) -> anyhow::Result<(serde_json::Value, ExecutionReport)> {
+    let input = serde_json::to_vec(input)?;
+    let invocation = match tenant_id {
+        _ if dry_run => kernel.execute_dry_run(tenant_id, module, function, &input),
+        Some(tenant_id) => kernel.execute_for_tenant(tenant_id, module, function, &input),
+        None => kernel.execute_function(module, function, &input),
+    };
+    let invocation = if bypass_cache { invocation.bypass_cache() } else { invocation };
+    let report = match invocation_id {
+        Some(id) => invocation.with_id(id).await,
+        None => invocation.await,
+    }?;
+    let output = serde_json::from_slice(&report.output)
+        .map_err(|e| anyhow::anyhow!("Module '{}' returned invalid JSON: {}", module, e))?;
+    Ok((output, report))
+}
+
+/// Look up the tenant policy version in force on a request's `work_date` (default today)
+async fn tenant_policy_for_request(
+    state: &AppState,
+    tenant_id: &str,
+    payload: &serde_json::Value,
+) -> anyhow::Result<Option<PolicyVersion>> {
+    let work_date = match payload.get("work_date").and_then(|v| v.as_str()) {
+        Some(date) => date.parse::<Date>()?,
+        None => Date::today(),
+    };
+    Ok(state.kernel.tenants().policy_at(tenant_id, work_date).await?)
+}
+
+/// Compatibility shim: route a legacy calculation request through the accrual module
+async fn route_legacy_request(state: &AppState, request: &KernelRequest) -> KernelResponse {
+    if state.config.fail_on_legacy_requests {
+        error!("Rejected legacy request shape '{}' (legacy routing disabled)", request.action);
+        return state.rejection(ErrorCode::InvalidRequest, format!(
+            "Legacy request shape '{}' is disabled; use kernel_execute on module '{}'",
+            request.action, ACCRUAL_MODULE
+        ));
+    }
+
+    warn!("Legacy request shape '{}' routed through module '{}'", request.action, ACCRUAL_MODULE);
+
+    let mut call = match legacy_call(request) {
+        Ok(call) => call,
+        Err(e) => return state.rejection(ErrorCode::InvalidRequest, e),
+    };
+
+    // Tenant requests use the policy version in force on the work date
+    let tenant_id = request.payload.get("tenant_id").and_then(|v| v.as_str());
+    if let Some(tenant_id) = tenant_id {
+        match tenant_policy_for_request(state, tenant_id, &request.payload).await {
+            Ok(Some(version)) => call.apply_policy(&version),
+            Ok(None) => warn!("Tenant {} has no policy for this work date; using request values", tenant_id),
+            Err(e) => return state.kernel_error_response(&e),
+        }
+    }
+
+    match execute_json(&state.kernel, tenant_id, ACCRUAL_MODULE, call.function, &call.input, false, false, None).await {
+        Ok((output, _)) => KernelResponse::ok(call.into_response(&output)),
+        Err(e) => {
+            error!("Legacy request '{}' failed in kernel: {}", request.action, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Kernel module that compares frontloading with accrual
+const POLICY_SIM_MODULE: &str = "policy-sim";
+
+/// Input for the policy simulation module from a `simulate` payload
+///
+/// The statute in force on `as_of` (default today) supplies the accrual rate
+/// and usage limits. A tenant's policy supplies `employer_size` when the
+/// payload leaves it out, and its jurisdiction picks the statute.
+async fn simulation_input(state: &AppState, payload: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let mut input = payload.clone();
+    let Some(fields) = input.as_object_mut() else {
+        anyhow::bail!("Simulation payload must be an object");
+    };
+    let as_of = match fields.get("as_of").and_then(|v| v.as_str()) {
+        Some(date) => date.parse::<Date>()?,
+        None => Date::today(),
+    };
+
+    let mut jurisdiction = state.kernel.jurisdiction().to_string();
+    if let Some(tenant_id) = fields.get("tenant_id").and_then(|v| v.as_str()).map(str::to_string) {
+        if let Some(version) = state.kernel.tenants().policy_at(&tenant_id, as_of).await? {
+            jurisdiction = state.kernel.policy_jurisdiction(&version.policy).to_string();
+            if !fields.contains_key("employer_size") {
+                fields.insert("employer_size".into(), version.policy.employer_size.into());
+            }
+        }
+    }
+
+    // Without a statute in force the module falls back to Michigan's figures
+    if let Some(statutory) = state.kernel.guest_statute(&jurisdiction, as_of).await {
+        fields.insert("statutory".into(), statutory);
+    }
+    Ok(input)
+}
+
+/// Compare frontloading with accrual over a year of projected hours
+async fn handle_simulate(state: &AppState, request: &KernelRequest) -> KernelResponse {
+    let input = match simulation_input(state, &request.payload).await {
+        Ok(input) => input,
+        Err(e) => return state.rejection(ErrorCode::InvalidRequest, e.to_string()),
+    };
+
+    let tenant_id = request.payload.get("tenant_id").and_then(|v| v.as_str());
+    match execute_json(&state.kernel, tenant_id, POLICY_SIM_MODULE, "simulate_json", &input, false, false, None).await {
+        Ok((output, _)) => KernelResponse::ok(output),
+        Err(e) => {
+            error!("Policy simulation failed in kernel: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Invoke the ESTA kernel with a validated request.
+/// 
+/// This is the primary IPC bridge between the React frontend and the Rust kernel.
+/// All requests are validated before processing to prevent unauthorized operations.
 #[command]
-pub async fn kernel_get_status() -> Result<KernelResponse, String> {
-    info!("Getting kernel status");
-    
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
-            "version": env!("CARGO_PKG_VERSION"),
+pub async fn invoke_kernel(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: KernelRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.payload.get("tenant_id").and_then(|v| v.as_str()).map(str::to_string);
+    let handler = for_tenant(&state, &sessions, tenant_id.as_deref(), handle_invoke_kernel(&state, request));
+    Ok(traced(&state, &sessions, "invoke_kernel", correlation_id, handler).await)
+}
+
+async fn handle_invoke_kernel(state: &AppState, request: KernelRequest) -> KernelResponse {
+    info!("Kernel invocation: action={}, module={}", request.action, request.module);
+
+    // Validate request before processing
+    if let Err(e) = validate_request(&request) {
+        error!("Request validation failed: {}", e);
+        return state.rejection(ErrorCode::InvalidRequest, e);
+    }
+
+    match request.action.as_str() {
+        "status" => KernelResponse::ok(serde_json::json!({
+            "kernel_version": env!("CARGO_PKG_VERSION"),
             "status": "running",
-            "modules": [],
-            "config": {
-                "max_fuel": 20_000_000,
-                "max_memory_bytes": 33_554_432,
-                "require_signatures": false
-            },
-            "audit": {
-                "enabled": true,
-                "entries": 0
-            }
+            "modules_loaded": [],
+            "fuel_limit": 20_000_000,
+            "memory_limit_bytes": 33_554_432
         })),
-        error: None,
-    })
+        "accrue" | "validate" | "calculate" => route_legacy_request(state, &request).await,
+        "simulate" => handle_simulate(state, &request).await,
+        "report" => {
+            let tenant_id = request.payload.get("tenant_id").and_then(|v| v.as_str());
+            let year = request.payload.get("year").and_then(|v| v.as_i64());
+            match (tenant_id, year) {
+                (Some(tenant_id), Some(year)) => handle_generate_report(state, GenerateReportRequest {
+                    tenant_id: tenant_id.to_string(),
+                    year: year as i32,
+                    format: ReportFormat::Json,
+                    output_path: None,
+                }).await,
+                _ => state.rejection(ErrorCode::InvalidRequest, "Report requires 'tenant_id' and 'year'"),
+            }
+        },
+        "audit" => {
+            // Return audit information
+            KernelResponse::ok(serde_json::json!({
+                "audit_enabled": true,
+                "log_entries": 0,
+                "chain_valid": true
+            }))
+        },
+        _ => state.rejection(ErrorCode::InvalidRequest, format!("Action '{}' not yet implemented", request.action)),
+    }
+}
+
+/// State of each supervised module
+#[command]
+pub async fn supervisor_get_status(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    supervisor: State<'_, Arc<Supervisor>>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_supervisor_get_status(&supervisor);
+    Ok(traced(&state, &sessions, "supervisor_get_status", correlation_id, handler).await)
+}
+
+async fn handle_supervisor_get_status(supervisor: &Supervisor) -> KernelResponse {
+    KernelResponse::ok(serde_json::json!({ "children": supervisor.get_status().await }))
+}
+
+/// Get kernel status including loaded modules and configuration
+#[command]
+pub async fn kernel_get_status(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_get_status", correlation_id, handle_get_status(&state)).await)
+}
+
+async fn handle_get_status(state: &AppState) -> KernelResponse {
+    info!("Getting kernel status");
+
+    let status = state.kernel.get_status().await;
+    KernelResponse::ok(serde_json::json!({
+        "version": status.version,
+        "status": "running",
+        "modules": status.module_names,
+        "config": {
+            "max_fuel": status.max_fuel_per_call,
+            "max_memory_bytes": status.max_memory_bytes,
+            "require_signatures": status.require_signatures,
+            "security_profile": status.security_profile,
+            "sandbox_profile": status.sandbox_profile,
+            "profile_violations": status.profile_violations
+        },
+        "audit": {
+            "enabled": true,
+            "entries": status.audit_entries,
+            "archive": status.audit_archive,
+            "health": status.audit_health
+        },
+        "capabilities": status.capabilities,
+        "result_cache": status.result_cache,
+        "heartbeats": status.heartbeats
+    }))
 }
 
 /// Load a WASM module from its manifest
 #[command]
-pub async fn kernel_load_module(request: LoadModuleRequest) -> Result<KernelResponse, String> {
+pub async fn kernel_load_module(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: LoadModuleRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_load_module", correlation_id, handle_load_module(&state, request)).await)
+}
+
+async fn handle_load_module(state: &AppState, request: LoadModuleRequest) -> KernelResponse {
     info!("Loading module from manifest: {}", request.manifest_path);
     
     // Validate manifest path doesn't escape allowed directories
     if request.manifest_path.contains("..") {
         warn!("Attempted path traversal in manifest_path: {}", request.manifest_path);
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some("Invalid manifest path".to_string()),
-        });
+        return state.rejection(ErrorCode::InvalidRequest, "Invalid manifest path");
     }
     
-    // In a full implementation, this would load the actual module
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
+    match state.kernel.launch_module(&request.manifest_path).await {
+        Ok(()) => KernelResponse::ok(serde_json::json!({
             "loaded": true,
             "manifest_path": request.manifest_path,
-            "message": "Module loading not yet implemented in Tauri handler"
+            "modules": state.kernel.list_modules().await
         })),
-        error: None,
-    })
+        Err(e) => {
+            error!("Failed to load module from {}: {}", request.manifest_path, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// List the modules available in the modules directory
+#[command]
+pub async fn kernel_list_available_modules(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_list_available_modules(&state);
+    Ok(traced(&state, &sessions, "kernel_list_available_modules", correlation_id, handler).await)
+}
+
+async fn handle_list_available_modules(state: &AppState) -> KernelResponse {
+    info!("Listing available modules");
+
+    match state.kernel.available_modules().await {
+        Ok(modules) => KernelResponse::ok(serde_json::json!({
+            "modules": modules,
+            "loaded": state.kernel.list_modules().await
+        })),
+        Err(e) => {
+            error!("Failed to list available modules: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Install a module from the modules directory
+#[command]
+pub async fn kernel_install_module(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: InstallModuleRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_install_module", correlation_id, handle_install_module(&state, request)).await)
+}
+
+async fn handle_install_module(state: &AppState, request: InstallModuleRequest) -> KernelResponse {
+    info!("Installing module {} (version {:?})", request.name, request.version);
+
+    match state.kernel.install_module(&request.name, request.version.as_deref()).await {
+        Ok(installed) => KernelResponse::ok(serde_json::json!({
+            "installed": installed,
+            "modules": state.kernel.list_modules().await
+        })),
+        Err(e) => {
+            error!("Failed to install module {}: {}", request.name, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Roll a module back to a previously installed version
+#[command]
+pub async fn kernel_rollback_module(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: RollbackModuleRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_rollback_module(&state, request);
+    Ok(traced(&state, &sessions, "kernel_rollback_module", correlation_id, handler).await)
+}
+
+async fn handle_rollback_module(state: &AppState, request: RollbackModuleRequest) -> KernelResponse {
+    info!("Rolling back module {} to version {}", request.name, request.version);
+
+    match state.kernel.rollback_module(&request.name, &request.version).await {
+        Ok(installed) => KernelResponse::ok(serde_json::json!({
+            "installed": installed,
+            "modules": state.kernel.list_modules().await
+        })),
+        Err(e) => {
+            error!("Failed to roll back module {}: {}", request.name, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Get a loaded module's manifest, signature verification, grants, and statistics
+#[command]
+pub async fn kernel_get_module_info(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    name: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_get_module_info", correlation_id, handle_get_module_info(&state, name)).await)
+}
+
+async fn handle_get_module_info(state: &AppState, name: String) -> KernelResponse {
+    match state.kernel.module_info(&name).await {
+        Some(info) => KernelResponse::ok(serde_json::json!(info)),
+        None => state.kernel_error_response(&anyhow::Error::new(KernelError::ModuleNotLoaded(name))),
+    }
+}
+
+/// Unload a running module, revoking its capabilities
+///
+/// A supervised module is also removed from the supervisor, so it is not
+/// relaunched when its heartbeat stops.
+#[command]
+pub async fn kernel_unload_module(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    supervisor: State<'_, Arc<Supervisor>>,
+    request: UnloadModuleRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_unload_module(&state, &supervisor, request);
+    Ok(traced(&state, &sessions, "kernel_unload_module", correlation_id, handler).await)
+}
+
+async fn handle_unload_module(state: &AppState, supervisor: &Supervisor, request: UnloadModuleRequest) -> KernelResponse {
+    info!("Unloading module {}", request.name);
+
+    match state.kernel.unload_module(&request.name, request.flush).await {
+        Ok(unload) => {
+            if supervisor.get_child_status(&request.name).await.is_some() {
+                if let Err(e) = supervisor.unregister_child(&request.name).await {
+                    warn!("Failed to stop supervising module {}: {}", request.name, e);
+                }
+            }
+            KernelResponse::ok(serde_json::json!({
+                "unloaded": unload,
+                "modules": state.kernel.list_modules().await
+            }))
+        }
+        Err(e) => {
+            error!("Failed to unload module {}: {}", request.name, e);
+            state.kernel_error_response(&e)
+        }
+    }
 }
 
 /// Execute a function on a loaded module
 #[command]
-pub async fn kernel_execute(request: ExecuteRequest) -> Result<KernelResponse, String> {
+pub async fn kernel_execute(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: ExecuteRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_execute(&state, request));
+    Ok(traced(&state, &sessions, "kernel_execute", correlation_id, handler).await)
+}
+
+async fn handle_execute(state: &AppState, request: ExecuteRequest) -> KernelResponse {
     info!("Executing {}::{}", request.module, request.function);
     
     // Validate module name
     if !ALLOWED_MODULES.contains(&request.module.as_str()) {
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some(format!("Module '{}' is not allowed", request.module)),
-        });
+        return state.rejection(ErrorCode::InvalidRequest, format!("Module '{}' is not allowed", request.module));
     }
     
     // Validate payload size
@@ -357,241 +2081,2127 @@ pub async fn kernel_execute(request: ExecuteRequest) -> Result<KernelResponse, S
         .map(|s| s.len())
         .unwrap_or(0);
     if input_size > MAX_PAYLOAD_SIZE {
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some("Input payload too large".to_string()),
-        });
+        return state.rejection(ErrorCode::InputTooLarge, "Input payload too large");
     }
     
-    // In a full implementation, this would execute the module function
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
+    let result = execute_json(
+        &state.kernel,
+        Some(&request.tenant_id),
+        &request.module,
+        &request.function,
+        &request.input,
+        request.dry_run,
+        request.bypass_cache,
+        request.invocation_id.as_deref(),
+    ).await;
+
+    match result {
+        Ok((result, report)) => KernelResponse::ok(serde_json::json!({
             "executed": true,
+            "dry_run": request.dry_run,
             "module": request.module,
             "function": request.function,
-            "result": null,
-            "fuel_consumed": 0,
-            "message": "Execution not yet implemented in Tauri handler"
+            "result": result,
+            "fuel_consumed": report.fuel_consumed,
+            "cached": report.cached
         })),
-        error: None,
-    })
+        Err(e) => {
+            error!("Execution of {}::{} failed: {}", request.module, request.function, e);
+            state.kernel_error_response(&e)
+        }
+    }
 }
 
-/// Get audit log entries
+/// Cancel a running execution
 #[command]
-pub async fn kernel_get_logs(request: GetLogsRequest) -> Result<KernelResponse, String> {
-    info!("Getting audit logs, limit: {:?}, source: {:?}", request.limit, request.source);
-    
-    let limit = request.limit.unwrap_or(100).min(1000); // Cap at 1000
-    
-    // In a full implementation, this would query the actual audit log
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
-            "entries": [],
-            "total": 0,
-            "limit": limit,
-            "source_filter": request.source,
-            "after_sequence": request.after_sequence
-        })),
-        error: None,
-    })
+pub async fn kernel_cancel(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: CancelRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_cancel", correlation_id, handle_cancel(&state, request)).await)
 }
 
-/// Set tenant policy configuration
-#[command]
-pub async fn tenant_set_policy(policy: TenantPolicy) -> Result<KernelResponse, String> {
-    info!("Setting policy for tenant: {}", policy.tenant_id);
-    
-    // Validate employer size
-    if !["small", "large"].contains(&policy.employer_size.as_str()) {
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some("employer_size must be 'small' or 'large'".to_string()),
-        });
+async fn handle_cancel(state: &AppState, request: CancelRequest) -> KernelResponse {
+    // An execution that already finished has nothing to cancel; not an error
+    let cancelled = state.kernel.cancel_invocation(&request.invocation_id);
+    if !cancelled {
+        info!("No running invocation {} to cancel", request.invocation_id);
     }
-    
-    // Validate accrual rate
-    if policy.accrual_rate <= 0.0 || policy.accrual_rate > 1.0 {
-        return Ok(KernelResponse {
-            success: false,
-            data: None,
-            error: Some("accrual_rate must be between 0 and 1".to_string()),
-        });
+    KernelResponse::ok(serde_json::json!({
+        "invocation_id": request.invocation_id,
+        "cancelled": cancelled
+    }))
+}
+
+/// Re-execute recorded invocations and compare their outputs
+#[command]
+pub async fn kernel_replay(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: ReplayRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_replay", correlation_id, handle_replay(&state, request)).await)
+}
+
+async fn handle_replay(state: &AppState, request: ReplayRequest) -> KernelResponse {
+    let last_sequence = request.last_sequence.unwrap_or(u64::MAX);
+    info!("Replaying audit entries {}..={}", request.first_sequence, last_sequence);
+    if last_sequence < request.first_sequence {
+        return state.rejection(ErrorCode::InvalidRequest, "last_sequence is before first_sequence");
     }
-    
-    // In a full implementation, this would persist the policy
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
-            "tenant_id": policy.tenant_id,
-            "policy_set": true,
-            "employer_size": policy.employer_size,
-            "accrual_rate": policy.accrual_rate,
-            "max_carryover_hours": policy.max_carryover_hours,
-            "max_usage_hours": policy.max_usage_hours
+
+    match state.kernel.replay(request.first_sequence..=last_sequence).await {
+        Ok(report) => KernelResponse::ok(serde_json::json!({
+            "deterministic": report.is_deterministic(),
+            "matched": report.matched(),
+            "diverged": report.diverged(),
+            "unavailable": report.unavailable(),
+            "report": report
         })),
-        error: None,
-    })
+        Err(e) => {
+            error!("Replay failed: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
 }
 
-/// Get accrual data for a tenant
+/// Report the disk space used by each data location
 #[command]
-pub async fn tenant_get_accruals(tenant_id: String) -> Result<KernelResponse, String> {
-    info!("Getting accruals for tenant: {}", tenant_id);
-    
-    // In a full implementation, this would query actual accrual data
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
-            "tenant_id": tenant_id,
-            "employees": [],
-            "total_accrued_hours": 0,
-            "total_used_hours": 0,
-            "period": "current"
+pub async fn storage_usage_report(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "storage_usage_report", correlation_id, handle_storage_usage_report(&state)).await)
+}
+
+async fn handle_storage_usage_report(state: &AppState) -> KernelResponse {
+    match state.kernel.storage().usage_report().await {
+        Ok(report) => KernelResponse::ok(serde_json::json!({
+            "over_limit": report.over_limit(),
+            "report": report
         })),
-        error: None,
-    })
+        Err(e) => {
+            error!("Storage usage report failed: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
 }
 
-/// Get accrual data for a specific employee
+/// Reclaim disk space
 #[command]
-pub async fn employee_view_accruals(query: EmployeeAccrualQuery) -> Result<KernelResponse, String> {
-    info!("Getting accruals for employee: {} in tenant: {}", query.employee_id, query.tenant_id);
-    
-    // In a full implementation, this would query actual employee accrual data
-    Ok(KernelResponse {
-        success: true,
-        data: Some(serde_json::json!({
-            "tenant_id": query.tenant_id,
-            "employee_id": query.employee_id,
-            "accrued_minutes": 0,
-            "used_minutes": 0,
-            "balance_minutes": 0,
-            "carryover_minutes": 0,
-            "policy": {
-                "rate": "1:30",
-                "employer_size": "unknown"
-            }
-        })),
-        error: None,
-    })
+pub async fn storage_vacuum(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "storage_vacuum", correlation_id, handle_storage_vacuum(&state)).await)
 }
 
-fn main() {
-    env_logger::init();
-    
-    info!("Starting ESTA Rainforest Desktop Application v{}", env!("CARGO_PKG_VERSION"));
+async fn handle_storage_vacuum(state: &AppState) -> KernelResponse {
+    info!("Vacuuming storage");
+    match state.kernel.storage().vacuum().await {
+        Ok(report) => KernelResponse::ok(serde_json::json!(report)),
+        Err(e) => {
+            error!("Storage vacuum failed: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
 
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            invoke_kernel,
-            kernel_get_status,
-            kernel_load_module,
-            kernel_execute,
-            kernel_get_logs,
-            tenant_set_policy,
-            tenant_get_accruals,
-            employee_view_accruals,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+/// Hourly invocation, error, and fuel counts per module
+#[command]
+pub async fn kernel_get_stats_history(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: StatsHistoryRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_get_stats_history(&state, request);
+    Ok(traced(&state, &sessions, "kernel_get_stats_history", correlation_id, handler).await)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+async fn handle_get_stats_history(state: &AppState, request: StatsHistoryRequest) -> KernelResponse {
+    let Some(history) = state.kernel.stats_history() else {
+        return state.rejection(ErrorCode::InvalidRequest, "statistics history is not kept; set ESTA_DATA_DIR or ESTA_STATS_FILE");
+    };
+    let to = request.to.unwrap_or_else(clock::now_millis);
+    let samples = history.query(request.module.as_deref(), request.from, to).await;
+    KernelResponse::ok(serde_json::json!({ "from": request.from, "to": to, "samples": samples }))
+}
 
-    #[test]
-    fn test_validate_request_valid() {
-        let request = KernelRequest {
-            action: "accrue".to_string(),
-            module: "accrual".to_string(),
-            payload: serde_json::json!({"minutes_worked": 60}),
-        };
-        assert!(validate_request(&request).is_ok());
-    }
+/// Hash-chained statistics records for a time range, with a digest
+#[command]
+pub async fn kernel_export_stats_history(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: StatsHistoryRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_export_stats_history(&state, request);
+    Ok(traced(&state, &sessions, "kernel_export_stats_history", correlation_id, handler).await)
+}
 
-    #[test]
-    fn test_validate_request_invalid_action() {
-        let request = KernelRequest {
-            action: "delete_all".to_string(),
-            module: "accrual".to_string(),
-            payload: serde_json::json!({}),
-        };
-        assert!(validate_request(&request).is_err());
-    }
+async fn handle_export_stats_history(state: &AppState, request: StatsHistoryRequest) -> KernelResponse {
+    let Some(history) = state.kernel.stats_history() else {
+        return state.rejection(ErrorCode::InvalidRequest, "statistics history is not kept; set ESTA_DATA_DIR or ESTA_STATS_FILE");
+    };
+    let to = request.to.unwrap_or_else(clock::now_millis);
+    KernelResponse::ok(serde_json::json!(history.export(request.from, to).await))
+}
 
-    #[test]
-    fn test_validate_request_invalid_module() {
-        let request = KernelRequest {
-            action: "accrue".to_string(),
-            module: "system".to_string(),
-            payload: serde_json::json!({}),
-        };
-        assert!(validate_request(&request).is_err());
-    }
+/// Replace the capability secret and re-issue tokens for live capabilities
+#[command]
+pub async fn kernel_rotate_capability_secret(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_rotate_capability_secret(&state);
+    Ok(traced(&state, &sessions, "kernel_rotate_capability_secret", correlation_id, handler).await)
+}
 
-    #[test]
-    fn test_calculate_accrual() {
-        assert_eq!(calculate_accrual(60, "small"), 2);  // 60/30 = 2
-        assert_eq!(calculate_accrual(120, "large"), 4); // 120/30 = 4
-        assert_eq!(calculate_accrual(0, "small"), 0);
-        assert_eq!(calculate_accrual(29, "small"), 0);  // < 30 = 0
+async fn handle_rotate_capability_secret(state: &AppState) -> KernelResponse {
+    info!("Rotating capability secret");
+    match state.kernel.rotate_capability_secret().await {
+        Ok(reissued) => KernelResponse::ok(serde_json::json!({
+            "reissued": reissued.len(),
+            "persisted": state.config.secrets_enabled() && !state.config.read_replica
+        })),
+        Err(e) => {
+            error!("Capability secret rotation failed: {}", e);
+            state.kernel_error_response(&e)
+        }
     }
+}
 
-    #[tokio::test]
-    async fn test_invoke_kernel_status() {
-        let request = KernelRequest {
-            action: "status".to_string(),
+/// Replace the database key and re-seal the employee records stored under it
+#[command]
+pub async fn database_rotate_key(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_rotate_database_key(&state);
+    Ok(traced(&state, &sessions, "database_rotate_key", correlation_id, handler).await)
+}
+
+async fn handle_rotate_database_key(state: &AppState) -> KernelResponse {
+    if !state.config.encrypt_database {
+        return state.rejection(ErrorCode::InvalidRequest, "the database is not encrypted; set ESTA_ENCRYPT_DATABASE=1");
+    }
+    info!("Rotating database key");
+    match state.kernel.rotate_database_key().await {
+        Ok(resealed) => KernelResponse::ok(serde_json::json!({ "resealed": resealed })),
+        Err(e) => {
+            error!("Database key rotation failed: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Write a signed backup of tenants, policies, the ledger, and the audit log
+///
+/// `path` is relative to the backups directory.
+#[command]
+pub async fn backup_create(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    path: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_backup_create(&state, &path);
+    Ok(traced(&state, &sessions, "backup_create", correlation_id, handler).await)
+}
+
+async fn handle_backup_create(state: &AppState, name: &str) -> KernelResponse {
+    let path = match state.output_file(state.config.backups_path(), name) {
+        Ok(path) => path,
+        Err(response) => return response,
+    };
+    info!("Writing backup to {}", path.display());
+    match state.kernel.create_backup(&path).await {
+        Ok(summary) => KernelResponse::ok(serde_json::to_value(summary).unwrap_or_default()),
+        Err(e) => {
+            error!("Backup failed: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Restore a backup written by this installation or signed by `trusted_key`
+///
+/// The archive's audit entries are kept under `restored-audit` in the data
+/// directory rather than replayed into this installation's log.
+#[command]
+pub async fn backup_restore(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    path: String,
+    trusted_key: Option<String>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_backup_restore(&state, &path, trusted_key);
+    Ok(traced(&state, &sessions, "backup_restore", correlation_id, handler).await)
+}
+
+async fn handle_backup_restore(state: &AppState, path: &str, trusted_key: Option<String>) -> KernelResponse {
+    info!("Restoring backup from {}", path);
+    let trusted: Vec<String> = trusted_key.into_iter().collect();
+    let archive = match state.kernel.open_backup(Path::new(path), &trusted).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            error!("Backup {} was refused: {:#}", path, e);
+            return state.kernel_error_response(&e);
+        }
+    };
+    let report = match state.kernel.restore_backup(&archive).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Backup restore failed: {:#}", e);
+            return state.kernel_error_response(&e);
+        }
+    };
+
+    let audit_file = match &state.config.data_dir {
+        Some(dir) if !archive.contents.audit.is_empty() => {
+            let file = Path::new(dir)
+                .join("restored-audit")
+                .join(format!("audit-{}.jsonl", archive.contents.created_at));
+            match write_restored_audit(&file, &archive.contents.audit) {
+                Ok(()) => Some(file.to_string_lossy().into_owned()),
+                Err(e) => {
+                    warn!("Could not keep restored audit entries in {}: {}", file.display(), e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    KernelResponse::ok(serde_json::json!({
+        "backup": archive.summary(),
+        "tenants_added": report.tenants_added,
+        "employees": report.employees,
+        "ledger_events": report.ledger_events,
+        "audit_file": audit_file,
+    }))
+}
+
+/// Write audit entries from a backup as JSON lines
+fn write_restored_audit(file: &Path, entries: &[AuditEntry]) -> std::io::Result<()> {
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    std::fs::write(file, lines)
+}
+
+/// Default days the previous signing key stays trusted after a rotation
+const DEFAULT_SIGNING_KEY_GRACE_DAYS: u64 = 30;
+
+/// Make a new module signing key current, trusting the previous one for a grace period
+#[command]
+pub async fn kernel_rotate_signing_key(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    new_public_key: String,
+    grace_days: Option<u64>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_rotate_signing_key(&state, new_public_key, grace_days);
+    Ok(traced(&state, &sessions, "kernel_rotate_signing_key", correlation_id, handler).await)
+}
+
+async fn handle_rotate_signing_key(state: &AppState, new_public_key: String, grace_days: Option<u64>) -> KernelResponse {
+    let grace_days = grace_days.unwrap_or(DEFAULT_SIGNING_KEY_GRACE_DAYS);
+    info!("Rotating module signing key (grace period {} days)", grace_days);
+    let grace_period = Duration::from_secs(grace_days * 86_400);
+    match state.kernel.rotate_signing_key(&new_public_key, grace_period).await {
+        Ok(rotation) => KernelResponse::ok(serde_json::json!(rotation)),
+        Err(e) => {
+            error!("Signing key rotation failed: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Export a signed snapshot of the active capabilities for security review
+///
+/// Compare two exports with `esta-kernel-cli capabilities diff`.
+#[command]
+pub async fn kernel_export_capabilities(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_export_capabilities(&state);
+    Ok(traced(&state, &sessions, "kernel_export_capabilities", correlation_id, handler).await)
+}
+
+async fn handle_export_capabilities(state: &AppState) -> KernelResponse {
+    let snapshot = state.kernel.capability_manager().export_state().await;
+    info!("Exported {} active capabilities", snapshot.capabilities.len());
+    KernelResponse::ok(serde_json::json!(snapshot))
+}
+
+/// Re-read the kernel configuration file and apply its execution limits
+#[command]
+pub async fn kernel_reload_config(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_reload_config", correlation_id, handle_reload_config(&state)).await)
+}
+
+async fn handle_reload_config(state: &AppState) -> KernelResponse {
+    let kernel = match state.config.read_kernel_config() {
+        Ok(kernel) => kernel.unwrap_or_default(),
+        Err(e) => {
+            error!("Kernel configuration reload failed: {}", e);
+            return state.rejection(ErrorCode::InvalidRequest, e);
+        }
+    };
+    info!("Reloading kernel configuration");
+    // Directories and keys are opened once at startup
+    let startup = &state.config.kernel;
+    let restart_required: Vec<&str> = [
+        ("module_dir", kernel.module_dir != startup.module_dir),
+        ("audit_dir", kernel.audit_dir != startup.audit_dir),
+        ("trusted_keys", kernel.trusted_keys != startup.trusted_keys),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect();
+    let config = AppConfig { kernel, ..state.config.clone() }.execution_config();
+    let mut reload = state.kernel.reload_config(config).await;
+    reload.restart_required.extend(restart_required);
+    KernelResponse::ok(serde_json::json!(reload))
+}
+
+/// Get audit log entries
+#[command]
+pub async fn kernel_get_logs(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: GetLogsRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    Ok(traced(&state, &sessions, "kernel_get_logs", correlation_id, handle_get_logs(&state, request)).await)
+}
+
+async fn handle_get_logs(state: &AppState, request: GetLogsRequest) -> KernelResponse {
+    info!("Getting audit logs, limit: {:?}, source: {:?}", request.limit, request.source);
+
+    let page = PageRequest { cursor: request.cursor.clone(), limit: request.limit };
+    let query = AuditQuery {
+        after_sequence: request.after_sequence,
+        from_timestamp: request.from_timestamp,
+        to_timestamp: request.to_timestamp,
+        source: request.source.clone(),
+        correlation_id: request.correlation_id.clone(),
+        tenant_id: request.tenant_id.clone(),
+    };
+    let audit_log = state.kernel.audit_log();
+    match audit_log.query_page(&query, &page).await {
+        Ok(Page { items: entries, next_cursor }) => KernelResponse::ok(serde_json::json!({
+            "entries": entries,
+            "next_cursor": next_cursor,
+            "total": audit_log.stats().await.total_entries,
+            "limit": page.limit(),
+            "source_filter": request.source,
+            "after_sequence": request.after_sequence
+        })),
+        Err(e) => {
+            error!("Audit log query failed: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Verify the audit log hash chain
+///
+/// Checks the entries appended since the last call, and reports the progress
+/// of the background full verification, which `full: true` starts.
+#[command]
+pub async fn kernel_verify_audit(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    full: Option<bool>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_verify_audit(&state, full.unwrap_or(false));
+    Ok(traced(&state, &sessions, "kernel_verify_audit", correlation_id, handler).await)
+}
+
+async fn handle_verify_audit(state: &AppState, full: bool) -> KernelResponse {
+    let audit_log = state.kernel.audit_log();
+    let incremental = audit_log.verify_incremental().await;
+    if !incremental.valid {
+        error!("Audit chain broken at sequence {:?}", incremental.first_invalid);
+    }
+    if full {
+        info!("Starting full audit log verification");
+        audit_log.start_full_verification().await;
+    }
+    KernelResponse::ok(serde_json::json!({
+        "incremental": incremental,
+        "full": audit_log.full_verification_progress(),
+    }))
+}
+
+/// Verify an exported audit log file
+///
+/// Checkpoint signatures are checked against `trusted_key` if given, else
+/// the installation's trusted signing keys. Works on exports from other
+/// installations, so employers can check the logs they archive.
+#[command]
+pub async fn audit_verify_export(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    path: String,
+    trusted_key: Option<String>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_audit_verify_export(&state, path, trusted_key);
+    Ok(traced(&state, &sessions, "audit_verify_export", correlation_id, handler).await)
+}
+
+async fn handle_audit_verify_export(state: &AppState, path: String, trusted_key: Option<String>) -> KernelResponse {
+    let trust_store = match trusted_key {
+        Some(key) => match TrustStore::new(&key) {
+            Ok(store) => Some(Arc::new(store)),
+            Err(e) => return state.error_response(&e),
+        },
+        None => state.kernel.trust_store(),
+    };
+    let verified = tokio::task::spawn_blocking(move || {
+        esta_kernel::security::audit_verify_export(Path::new(&path), trust_store.as_deref())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.into()));
+    match verified {
+        Ok(report) => {
+            if !report.valid {
+                warn!("Exported audit log invalid at sequence {:?}: {:?}", report.first_invalid, report.error);
+            }
+            KernelResponse::ok(serde_json::to_value(report).unwrap_or_default())
+        }
+        Err(e) => {
+            error!("Audit export verification failed: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Set tenant policy configuration
+#[command]
+pub async fn tenant_set_policy(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    policy: TenantPolicy,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = policy.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_set_policy(&state, policy));
+    Ok(traced(&state, &sessions, "tenant_set_policy", correlation_id, handler).await)
+}
+
+async fn handle_set_policy(state: &AppState, policy: TenantPolicy) -> KernelResponse {
+    info!("Setting policy for tenant: {}", policy.tenant_id);
+
+    let effective_from = match policy.effective_from.as_deref() {
+        Some(date) => match date.parse::<Date>() {
+            Ok(date) => date,
+            Err(e) => return state.error_response(&e),
+        },
+        None => Date::today(),
+    };
+
+    let tenant_policy = esta_kernel::TenantPolicy {
+        employer_size: policy.employer_size.clone(),
+        accrual_rate: policy.accrual_rate,
+        max_carryover_hours: policy.max_carryover_hours,
+        max_usage_hours: policy.max_usage_hours,
+        usage_insights: policy.usage_insights,
+        jurisdiction: policy.jurisdiction.clone(),
+        retention_days: policy.retention_days,
+    };
+
+    // The registry validates the tenant id, policy values, and effective date
+    let version = match state.kernel.set_tenant_policy(&policy.tenant_id, tenant_policy, effective_from).await {
+        Ok(version) => version,
+        Err(e) => return state.error_response(&e),
+    };
+    
+    KernelResponse::ok(serde_json::json!({
+        "tenant_id": policy.tenant_id,
+        "policy_set": true,
+        "version": version.version,
+        "effective_from": version.effective_from,
+        "employer_size": policy.employer_size,
+        "accrual_rate": policy.accrual_rate,
+        "max_carryover_hours": policy.max_carryover_hours,
+        "max_usage_hours": policy.max_usage_hours,
+        "usage_insights": policy.usage_insights
+    }))
+}
+
+/// Get every policy version recorded for a tenant
+#[command]
+pub async fn tenant_get_policy_history(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_get_policy_history(&state, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_get_policy_history", correlation_id, handler).await)
+}
+
+async fn handle_get_policy_history(state: &AppState, tenant_id: String) -> KernelResponse {
+    info!("Getting policy history for tenant: {}", tenant_id);
+
+    match state.kernel.tenants().policy_history(&tenant_id).await {
+        Ok(versions) => KernelResponse::ok(serde_json::json!({
+            "tenant_id": tenant_id,
+            "versions": versions
+        })),
+        Err(e) => state.error_response(&e),
+    }
+}
+
+/// Fuel a tenant has used, per module and against its ceiling
+#[command]
+pub async fn tenant_get_usage(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_get_tenant_usage(&state, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_get_usage", correlation_id, handler).await)
+}
+
+async fn handle_get_tenant_usage(state: &AppState, tenant_id: String) -> KernelResponse {
+    match state.kernel.tenant_usage(&tenant_id).await {
+        Ok(usage) => KernelResponse::ok(serde_json::json!(usage)),
+        Err(e) => state.error_response(&e),
+    }
+}
+
+/// Create a tenant and provision its namespace and scheduled reminders
+#[command]
+pub async fn tenant_create(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_tenant_create(&state, &reminders, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_create", correlation_id, handler).await)
+}
+
+async fn handle_tenant_create(state: &AppState, reminders: &ReminderStore, tenant_id: String) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Tenants cannot be created on a read replica");
+    }
+    let provisioned = match state.kernel.create_tenant(&tenant_id).await {
+        Ok(provisioned) => provisioned,
+        Err(e) => return state.kernel_error_response(&e),
+    };
+
+    // Carryover is processed at the start of every benefit year
+    let carryover = NewReminder {
+        title: "Annual carryover processing".to_string(),
+        body: format!("Carry over unused sick time for {}", tenant_id),
+        tenant_id: Some(tenant_id.clone()),
+        recurrence: Recurrence::Yearly,
+        first_due: Date::new(Date::today().year() + 1, 1, 1).expect("January 1 is a valid date"),
+    };
+    let reminder = match reminders.add(carryover) {
+        Ok(reminder) => reminder,
+        Err(e) => {
+            error!("Tenant {} created but its reminders could not be saved: {:#}", tenant_id, e);
+            return state.kernel_error_response(&e);
+        }
+    };
+
+    info!("Created tenant {}", tenant_id);
+    KernelResponse::ok(serde_json::json!({
+        "tenant": provisioned.tenant,
+        "root_capability": provisioned.root_capability,
+        "reminders": [reminder],
+    }))
+}
+
+/// Archive a tenant: its records stay readable, but it accepts no new work
+#[command]
+pub async fn tenant_archive(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_tenant_archive(&state, &reminders, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_archive", correlation_id, handler).await)
+}
+
+async fn handle_tenant_archive(state: &AppState, reminders: &ReminderStore, tenant_id: String) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Tenants cannot be archived on a read replica");
+    }
+    let capabilities_revoked = match state.kernel.archive_tenant(&tenant_id).await {
+        Ok(revoked) => revoked,
+        Err(e) => return state.kernel_error_response(&e),
+    };
+    let reminders_removed = match reminders.remove_tenant(&tenant_id) {
+        Ok(removed) => removed,
+        Err(e) => {
+            error!("Tenant {} archived but its reminders could not be removed: {:#}", tenant_id, e);
+            return state.kernel_error_response(&e);
+        }
+    };
+
+    info!("Archived tenant {}", tenant_id);
+    KernelResponse::ok(serde_json::json!({
+        "tenant_id": tenant_id,
+        "archived": true,
+        "capabilities_revoked": capabilities_revoked,
+        "reminders_removed": reminders_removed,
+    }))
+}
+
+/// Permanently remove an archived tenant, its policies, and its ledger events
+#[command]
+pub async fn tenant_purge(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_tenant_purge(&state, &reminders, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_purge", correlation_id, handler).await)
+}
+
+async fn handle_tenant_purge(state: &AppState, reminders: &ReminderStore, tenant_id: String) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Tenants cannot be purged on a read replica");
+    }
+    let report = match state.kernel.purge_tenant(&tenant_id).await {
+        Ok(report) => report,
+        Err(e) => return state.kernel_error_response(&e),
+    };
+    // Reminders added after the tenant was archived
+    if let Err(e) = reminders.remove_tenant(&tenant_id) {
+        error!("Tenant {} purged but its reminders could not be removed: {:#}", tenant_id, e);
+        return state.kernel_error_response(&e);
+    }
+
+    warn!("Purged tenant {}: {} ledger events removed", tenant_id, report.ledger_events);
+    KernelResponse::ok(serde_json::json!(report))
+}
+
+/// Redact employee data past the retention period of one tenant, or of every tenant
+#[command]
+pub async fn tenant_apply_retention(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: Option<String>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, tenant.as_deref(), handle_apply_retention(&state, tenant_id));
+    Ok(traced(&state, &sessions, "tenant_apply_retention", correlation_id, handler).await)
+}
+
+async fn handle_apply_retention(state: &AppState, tenant_id: Option<String>) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Retention cannot be applied on a read replica");
+    }
+    let reports = match &tenant_id {
+        Some(tenant_id) => state.kernel.apply_retention(tenant_id).await.map(|report| vec![report]),
+        None => state.kernel.apply_retention_all().await,
+    };
+    match reports {
+        Ok(reports) => KernelResponse::ok(serde_json::json!({ "tenants": reports })),
+        Err(e) => {
+            error!("Applying retention failed: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Get the statutory parameters in force on a date (default today)
+///
+/// `jurisdiction` defaults to the kernel's.
+#[command]
+pub async fn statute_get_parameters(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    date: Option<String>,
+    jurisdiction: Option<String>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handled = handle_get_statute(&state, date, jurisdiction);
+    Ok(traced(&state, &sessions, "statute_get_parameters", correlation_id, handled).await)
+}
+
+async fn handle_get_statute(state: &AppState, date: Option<String>, jurisdiction: Option<String>) -> KernelResponse {
+    let date = match date.map(|d| d.parse::<Date>()).transpose() {
+        Ok(date) => date.unwrap_or_else(Date::today),
+        Err(e) => return state.error_response(&e),
+    };
+
+    let jurisdiction = jurisdiction.unwrap_or_else(|| state.kernel.jurisdiction().to_string());
+    match state.kernel.statute_for(&jurisdiction, date).await {
+        Ok(statute) => KernelResponse::ok(serde_json::json!({
+            "jurisdiction": jurisdiction,
+            "date": date,
+            "statute": statute
+        })),
+        Err(e) => state.error_response(&e),
+    }
+}
+
+/// Get accrual data for a tenant, one page of employees at a time
+#[command]
+pub async fn tenant_get_accruals(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: String,
+    cursor: Option<String>,
+    limit: Option<usize>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let page = PageRequest { cursor, limit };
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_get_accruals(&state, tenant_id, page));
+    Ok(traced(&state, &sessions, "tenant_get_accruals", correlation_id, handler).await)
+}
+
+async fn handle_get_accruals(state: &AppState, tenant_id: String, page: PageRequest) -> KernelResponse {
+    info!("Getting accruals for tenant: {}", tenant_id);
+
+    let employees = match state.kernel.tenants().list_employees(&tenant_id).await {
+        Ok(employees) => employees,
+        Err(e) => return state.error_response(&e),
+    };
+    let employees = match Page::from_sorted(employees, &page, Clone::clone) {
+        Ok(employees) => employees,
+        Err(e) => return state.error_response(&e),
+    };
+
+    // Balances are not tracked yet; only the tenant's own roster is returned
+    KernelResponse::ok(serde_json::json!({
+        "tenant_id": tenant_id,
+        "employees": employees.items,
+        "next_cursor": employees.next_cursor,
+        "total_accrued_hours": 0,
+        "total_used_hours": 0,
+        "period": "current"
+    }))
+}
+
+/// Get accrual data for a specific employee
+#[command]
+pub async fn employee_view_accruals(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    query: EmployeeAccrualQuery,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = handle_view_own_accruals(&state, &sessions, query);
+    Ok(traced(&state, &sessions, "employee_view_accruals", correlation_id, handler).await)
+}
+
+/// Employees may only look up their own records
+async fn handle_view_own_accruals(state: &AppState, sessions: &SessionStore, query: EmployeeAccrualQuery) -> KernelResponse {
+    match sessions.authorize_employee(&query.tenant_id, &query.employee_id) {
+        Ok(()) => handle_view_accruals(state, query).await,
+        Err(denied) => state.rejection(denied.code, denied.detail),
+    }
+}
+
+async fn handle_view_accruals(state: &AppState, query: EmployeeAccrualQuery) -> KernelResponse {
+    info!("Getting accruals for employee: {} in tenant: {}", query.employee_id, query.tenant_id);
+
+    // Employees are only visible through the tenant they belong to
+    let tenants = state.kernel.tenants();
+    if let Err(e) = tenants.ensure_employee(&query.tenant_id, &query.employee_id).await {
+        warn!("Rejected employee accrual query: {}", e);
+        return state.error_response(&e);
+    }
+    let employer_size = tenants.get_policy(&query.tenant_id).await
+        .ok()
+        .flatten()
+        .map(|p| p.employer_size)
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    // Balances are not tracked yet
+    KernelResponse::ok(serde_json::json!({
+        "tenant_id": query.tenant_id,
+        "employee_id": query.employee_id,
+        "accrued_minutes": 0,
+        "used_minutes": 0,
+        "balance_minutes": 0,
+        "carryover_minutes": 0,
+        "policy": {
+            "rate": "1:30",
+            "employer_size": employer_size
+        }
+    }))
+}
+
+/// Get informational usage pattern insights for a tenant that has opted in
+#[command]
+pub async fn tenant_usage_insights(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: UsageInsightsRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_usage_insights(&state, request));
+    Ok(traced(&state, &sessions, "tenant_usage_insights", correlation_id, handler).await)
+}
+
+async fn handle_usage_insights(state: &AppState, request: UsageInsightsRequest) -> KernelResponse {
+    info!("Computing usage insights for tenant: {}", request.tenant_id);
+
+    let as_of = match request.as_of.as_deref() {
+        Some(date) => match date.parse::<Date>() {
+            Ok(date) => date,
+            Err(e) => return state.error_response(&e),
+        },
+        None => Date::today(),
+    };
+
+    match state.kernel.usage_insights(&request.tenant_id, as_of).await {
+        Ok(report) => KernelResponse::ok(serde_json::to_value(&report).unwrap_or_default()),
+        Err(e) => {
+            warn!("Usage insights unavailable for tenant {}: {}", request.tenant_id, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Import hours from a payroll CSV export
+#[command]
+pub async fn import_timesheet_csv(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: ImportTimesheetRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_import_timesheet(&state, request));
+    Ok(traced(&state, &sessions, "import_timesheet_csv", correlation_id, handler).await)
+}
+
+async fn handle_import_timesheet(state: &AppState, request: ImportTimesheetRequest) -> KernelResponse {
+    info!("Importing timesheet CSV for tenant: {}", request.tenant_id);
+
+    match import::import_timesheet(&state.kernel, request).await {
+        Ok(report) => {
+            if !report.errors.is_empty() {
+                warn!("Timesheet import skipped {} of {} rows", report.errors.len(), report.rows_total);
+            }
+            KernelResponse::ok(serde_json::to_value(&report).unwrap_or_default())
+        }
+        Err(e) => {
+            error!("Timesheet import failed: {}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Generate a tenant's annual compliance report
+#[command]
+pub async fn generate_compliance_report(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: GenerateReportRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_generate_report(&state, request));
+    Ok(traced(&state, &sessions, "generate_compliance_report", correlation_id, handler).await)
+}
+
+async fn handle_generate_report(state: &AppState, request: GenerateReportRequest) -> KernelResponse {
+    info!("Generating {} compliance report for tenant: {}", request.year, request.tenant_id);
+
+    let report = match state.kernel.compliance_report(&request.tenant_id, request.year).await {
+        Ok(report) => report,
+        Err(e) => return state.error_response(&e),
+    };
+
+    match request.format {
+        ReportFormat::Json => KernelResponse::ok(serde_json::to_value(&report).unwrap_or_default()),
+        ReportFormat::Pdf => {
+            let Some(name) = request.output_path else {
+                return state.rejection(ErrorCode::InvalidRequest, "PDF reports require an output_path");
+            };
+            let path = match state.output_file(state.config.reports_path(), &name) {
+                Ok(path) => path,
+                Err(response) => return response,
+            };
+
+            let pdf = esta_kernel::report::render_pdf(&report);
+            match std::fs::write(&path, &pdf) {
+                Ok(()) => KernelResponse::ok(serde_json::json!({
+                    "tenant_id": report.tenant_id,
+                    "year": report.year,
+                    "format": "pdf",
+                    "path": path,
+                    "bytes": pdf.len(),
+                    "totals": report.totals
+                })),
+                Err(e) => {
+                    error!("Failed to write compliance report to {}: {}", path.display(), e);
+                    state.error_response(&e)
+                }
+            }
+        }
+    }
+}
+
+/// Generate a tenant's accrued sick-leave liability for accounting
+#[command]
+pub async fn generate_liability_report(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: LiabilityReportRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_liability_report(&state, request));
+    Ok(traced(&state, &sessions, "generate_liability_report", correlation_id, handler).await)
+}
+
+async fn handle_liability_report(state: &AppState, request: LiabilityReportRequest) -> KernelResponse {
+    info!("Generating liability report for tenant: {}", request.tenant_id);
+
+    let as_of = match request.as_of.as_deref() {
+        Some(date) => match date.parse::<Date>() {
+            Ok(date) => date,
+            Err(e) => return state.error_response(&e),
+        },
+        None => Date::today(),
+    };
+    let report = match state.kernel.liability_report(&request.tenant_id, as_of, &request.wages, &request.gl_accounts).await {
+        Ok(report) => report,
+        Err(e) => return state.error_response(&e),
+    };
+
+    match request.format {
+        LiabilityFormat::Json => KernelResponse::ok(serde_json::to_value(&report).unwrap_or_default()),
+        LiabilityFormat::Csv => {
+            let Some(name) = request.output_path else {
+                return state.rejection(ErrorCode::InvalidRequest, "CSV reports require an output_path");
+            };
+            let path = match state.output_file(state.config.reports_path(), &name) {
+                Ok(path) => path,
+                Err(response) => return response,
+            };
+
+            let csv = report.to_csv();
+            match std::fs::write(&path, &csv) {
+                Ok(()) => KernelResponse::ok(serde_json::json!({
+                    "tenant_id": report.tenant_id,
+                    "as_of": report.as_of,
+                    "format": "csv",
+                    "path": path,
+                    "rows": report.lines.len(),
+                    "accounts": report.accounts,
+                    "total_liability_cents": report.total_liability_cents,
+                    "missing_rates": report.missing_rates
+                })),
+                Err(e) => {
+                    error!("Failed to write liability report to {}: {}", path.display(), e);
+                    state.error_response(&e)
+                }
+            }
+        }
+    }
+}
+
+/// Save a new version of a tenant's report template
+#[command]
+pub async fn report_template_save(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: String,
+    template: ReportTemplate,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_report_template_save(&state, tenant_id, template));
+    Ok(traced(&state, &sessions, "report_template_save", correlation_id, handler).await)
+}
+
+async fn handle_report_template_save(state: &AppState, tenant_id: String, template: ReportTemplate) -> KernelResponse {
+    info!("Saving report template {} for tenant: {}", template.name, tenant_id);
+
+    // The registry checks that every column and the filter parse
+    match state.kernel.save_report_template(&tenant_id, template).await {
+        Ok(saved) => KernelResponse::ok(serde_json::to_value(&saved).unwrap_or_default()),
+        Err(e) => state.error_response(&e),
+    }
+}
+
+/// List the latest version of each of a tenant's report templates
+#[command]
+pub async fn report_template_list(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant), handle_report_template_list(&state, tenant_id));
+    Ok(traced(&state, &sessions, "report_template_list", correlation_id, handler).await)
+}
+
+async fn handle_report_template_list(state: &AppState, tenant_id: String) -> KernelResponse {
+    match state.kernel.tenants().list_report_templates(&tenant_id).await {
+        Ok(templates) => KernelResponse::ok(serde_json::json!({
+            "tenant_id": tenant_id,
+            "templates": templates
+        })),
+        Err(e) => state.error_response(&e),
+    }
+}
+
+/// Generate a tenant's compliance report laid out by a saved template
+#[command]
+pub async fn generate_custom_report(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    request: CustomReportRequest,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = request.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, Some(&tenant_id), handle_custom_report(&state, request));
+    Ok(traced(&state, &sessions, "generate_custom_report", correlation_id, handler).await)
+}
+
+async fn handle_custom_report(state: &AppState, request: CustomReportRequest) -> KernelResponse {
+    info!("Generating {} report {} for tenant: {}", request.year, request.template, request.tenant_id);
+
+    match state.kernel.custom_report(&request.tenant_id, request.year, &request.template, request.version).await {
+        Ok(report) => KernelResponse::ok(serde_json::to_value(&report).unwrap_or_default()),
+        Err(e) => {
+            warn!("Custom report {} failed for tenant {}: {}", request.template, request.tenant_id, e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Subscribe to new audit entries matching a filter
+#[command]
+pub async fn kernel_audit_subscribe(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    streams: State<'_, AuditStreams>,
+    filter: AuditFilter,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async { handle_audit_subscribe(&state, &streams, filter) };
+    Ok(traced(&state, &sessions, "kernel_audit_subscribe", correlation_id, handler).await)
+}
+
+fn handle_audit_subscribe(state: &AppState, streams: &AuditStreams, filter: AuditFilter) -> KernelResponse {
+    match streams.subscribe(filter) {
+        Some(id) => KernelResponse::ok(serde_json::json!({ "subscription_id": id })),
+        None => state.rejection(ErrorCode::ResourceLimit, "too many open audit subscriptions"),
+    }
+}
+
+/// Stop an audit subscription
+#[command]
+pub async fn kernel_audit_unsubscribe(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    streams: State<'_, AuditStreams>,
+    subscription_id: u64,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async { KernelResponse::ok(serde_json::json!({ "unsubscribed": streams.unsubscribe(subscription_id) })) };
+    Ok(traced(&state, &sessions, "kernel_audit_unsubscribe", correlation_id, handler).await)
+}
+
+/// Sign in, establishing the caller's role
+#[command]
+pub async fn session_login(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    streams: State<'_, AuditStreams>,
+    username: String,
+    password: String,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async { handle_session_login(&state, &sessions, &streams, &username, &password) };
+    Ok(traced(&state, &sessions, "session_login", correlation_id, handler).await)
+}
+
+fn handle_session_login(state: &AppState, sessions: &SessionStore, streams: &AuditStreams, username: &str, password: &str) -> KernelResponse {
+    match sessions.login(username, password) {
+        Ok(session) => {
+            info!("{} signed in as {}", session.username, session.role.as_str());
+            // Streams opened by whoever was signed in before are not theirs
+            streams.close_all();
+            KernelResponse::ok(serde_json::json!(session))
+        }
+        Err(denied) => {
+            warn!("Failed sign in for {}", username);
+            state.rejection(denied.code, denied.detail)
+        }
+    }
+}
+
+/// Sign out, closing the user's audit subscriptions
+#[command]
+pub async fn session_logout(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    streams: State<'_, AuditStreams>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async {
+        let closed = streams.close_all();
+        KernelResponse::ok(serde_json::json!({ "signed_out": sessions.logout(), "subscriptions_closed": closed }))
+    };
+    Ok(traced(&state, &sessions, "session_logout", correlation_id, handler).await)
+}
+
+/// The signed-in user, and whether signing in is required
+#[command]
+pub async fn session_current(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async {
+        KernelResponse::ok(serde_json::json!({
+            "session": sessions.current(),
+            "accounts_exist": sessions.has_accounts(),
+        }))
+    };
+    Ok(traced(&state, &sessions, "session_current", correlation_id, handler).await)
+}
+
+/// Create a user account (employer admins; anyone for the first account)
+#[command]
+pub async fn session_add_account(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    account: NewAccount,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async { handle_add_account(&state, &sessions, account) };
+    Ok(traced(&state, &sessions, "session_add_account", correlation_id, handler).await)
+}
+
+fn handle_add_account(state: &AppState, sessions: &SessionStore, account: NewAccount) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "accounts are managed by the primary");
+    }
+    let (username, role) = (account.username.trim().to_string(), account.role);
+    match sessions.add_account(account) {
+        Ok(()) => {
+            info!("Created {} account {}", role.as_str(), username);
+            KernelResponse::ok(serde_json::json!({ "username": username, "role": role }))
+        }
+        Err(denied) => state.rejection(denied.code, denied.detail),
+    }
+}
+
+/// Register a recurring compliance reminder
+#[command]
+pub async fn reminder_add(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    reminder: NewReminder,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant_id = reminder.tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, tenant_id.as_deref(), async { handle_reminder_add(&state, &reminders, reminder) });
+    Ok(traced(&state, &sessions, "reminder_add", correlation_id, handler).await)
+}
+
+fn handle_reminder_add(state: &AppState, reminders: &ReminderStore, reminder: NewReminder) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Reminders cannot be changed on a read replica");
+    }
+    if let Err(e) = reminder.validate() {
+        return state.rejection(ErrorCode::InvalidRequest, e.to_string());
+    }
+    match reminders.add(reminder) {
+        Ok(reminder) => {
+            info!("Registered reminder {} ({:?}, next due {})", reminder.id, reminder.recurrence, reminder.next_due);
+            KernelResponse::ok(serde_json::json!(reminder))
+        }
+        Err(e) => {
+            error!("Failed to save reminder: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// List registered reminders, soonest first
+#[command]
+pub async fn reminder_list(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async {
+        // Managers see reminders for their own tenant and those for no tenant
+        let visible: Vec<_> = reminders
+            .list()
+            .into_iter()
+            .filter(|r| r.tenant_id.as_deref().is_none_or(|tenant_id| sessions.authorize_tenant(tenant_id).is_ok()))
+            .collect();
+        KernelResponse::ok(serde_json::json!({ "reminders": visible }))
+    };
+    Ok(traced(&state, &sessions, "reminder_list", correlation_id, handler).await)
+}
+
+/// Remove a reminder
+#[command]
+pub async fn reminder_remove(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    reminders: State<'_, ReminderStore>,
+    id: u64,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let handler = async { handle_reminder_remove(&state, &reminders, id) };
+    Ok(traced(&state, &sessions, "reminder_remove", correlation_id, handler).await)
+}
+
+fn handle_reminder_remove(state: &AppState, reminders: &ReminderStore, id: u64) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Reminders cannot be changed on a read replica");
+    }
+    match reminders.remove(id) {
+        Ok(removed) => KernelResponse::ok(serde_json::json!({ "id": id, "removed": removed })),
+        Err(e) => {
+            error!("Failed to save reminders: {:#}", e);
+            state.kernel_error_response(&e)
+        }
+    }
+}
+
+/// Get the alert configuration of a tenant, or the default
+#[command]
+pub async fn alerts_get_config(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: Option<String>,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, tenant.as_deref(), async { handle_get_alert_config(&state, tenant_id) });
+    Ok(traced(&state, &sessions, "alerts_get_config", correlation_id, handler).await)
+}
+
+fn handle_get_alert_config(state: &AppState, tenant_id: Option<String>) -> KernelResponse {
+    let config = state.kernel.alerts().config(tenant_id.as_deref());
+    KernelResponse::ok(serde_json::json!({ "tenant_id": tenant_id, "config": config }))
+}
+
+/// Replace the alert configuration of a tenant, or the default
+#[command]
+pub async fn alerts_set_config(
+    state: State<'_, AppState>,
+    sessions: State<'_, SessionStore>,
+    tenant_id: Option<String>,
+    config: AlertConfig,
+    correlation_id: Option<String>,
+) -> Result<KernelResponse, String> {
+    let tenant = tenant_id.clone();
+    let handler = for_tenant(&state, &sessions, tenant.as_deref(), async { handle_set_alert_config(&state, tenant_id, config) });
+    Ok(traced(&state, &sessions, "alerts_set_config", correlation_id, handler).await)
+}
+
+fn handle_set_alert_config(state: &AppState, tenant_id: Option<String>, config: AlertConfig) -> KernelResponse {
+    if state.config.read_replica {
+        return state.rejection(ErrorCode::ReadOnly, "Alerts cannot be configured on a read replica");
+    }
+    match state.kernel.alerts().set_config(tenant_id.as_deref(), config.clone()) {
+        Ok(()) => KernelResponse::ok(serde_json::json!({ "tenant_id": tenant_id, "config": config })),
+        Err(e) => {
+            warn!("Alert configuration refused: {}", e);
+            state.error_response(&e)
+        }
+    }
+}
+
+/// How often the application checks for due reminders
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How often failed audit segment writes are retried
+const AUDIT_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often supervised modules' heartbeats are checked
+const HEARTBEAT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often module statistics are added to the history
+const STATS_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Show an OS notification, and emit `reminder-due`, for each reminder that has come due
+fn notify_due_reminders(app: &tauri::AppHandle) {
+    let reminders = app.state::<ReminderStore>();
+    let due = match reminders.take_due(Date::today()) {
+        Ok(due) => due,
+        Err(e) => {
+            error!("Failed to update reminders: {:#}", e);
+            return;
+        }
+    };
+    let identifier = app.config().tauri.bundle.identifier.clone();
+    for reminder in due {
+        info!("Reminder {} due: {}", reminder.id, reminder.title);
+        let notification = Notification::new(&identifier).title(&reminder.title).body(&reminder.body);
+        if let Err(e) = notification.show() {
+            warn!("Failed to show notification for reminder {}: {}", reminder.id, e);
+        }
+        if let Err(e) = app.emit_all("reminder-due", &reminder) {
+            warn!("Failed to emit reminder {}: {}", reminder.id, e);
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    
+    info!("Starting ESTA Rainforest Desktop Application v{}", env!("CARGO_PKG_VERSION"));
+
+    let config = AppConfig::from_env()
+        .with_kernel_config()
+        .expect("failed to read kernel configuration file");
+    let tenants = config.tenant_registry().expect("failed to load tenant policy history");
+    let ledger = config.ledger().expect("failed to load accrual ledger");
+    let audit_log = config.audit_log().expect("failed to open audit log");
+    if let Some(dir) = audit_log.segment_dir() {
+        info!("Audit log persisted to {}", dir.display());
+    }
+    let profile = config.security_profile().expect("invalid ESTA_SECURITY_PROFILE");
+    info!("Security profile: {}", profile);
+    if let Some(sandbox) = config.sandbox_profile().expect("invalid ESTA_SANDBOX_PROFILE") {
+        info!("Sandbox profile: {}", sandbox);
+    }
+    let mut kernel = Kernel::with_config(config.execution_config())
+        .expect("failed to initialize ESTA kernel")
+        .with_profile(profile)
+        .with_audit_log(audit_log)
+        .with_tenant_registry(tenants)
+        .with_ledger(ledger)
+        .with_recorded_inputs(config.recorded_input_bytes)
+        .with_storage_limits(config.storage_limits());
+    let mut secret_store = if config.read_replica {
+        None
+    } else {
+        config.secret_store().expect("failed to unlock secret store")
+    };
+    if let Some(database) = config.database(secret_store.as_mut()).expect("failed to open database") {
+        info!("Database at {:?} (encrypted: {})", database.path(), config.encrypt_database);
+        kernel = kernel.with_database(database);
+        if let Err(e) = tauri::async_runtime::block_on(kernel.restore_from_database()) {
+            panic!("failed to restore from the database: {:#}", e);
+        }
+    }
+    if let Some(trust_store) = config.trust_store().expect("failed to open signing key trust store") {
+        if let Some(key) = trust_store.current() {
+            info!("Verifying module signatures; current signing key {}", key.key_id);
+        }
+        kernel = kernel.with_trust_store(trust_store);
+    }
+    if config.read_replica {
+        info!("Read replica: capability secret kept in memory");
+    } else if let Some(mut store) = secret_store {
+        if let Some(dir) = &config.crash_dump_dir {
+            info!("Saving crash dumps to {}", dir);
+            let dumps = CrashDumps::from_store(dir, &mut store).expect("failed to load crash dump key");
+            kernel = kernel.with_crash_dumps(dumps);
+        }
+        if let Some(dir) = config.module_cache_path() {
+            info!("Module cache at {}", dir.display());
+            kernel = kernel.with_module_cache(dir, &mut store).expect("failed to open module cache");
+        }
+        info!("Capability secret kept in {}", store.path().display());
+        kernel = kernel.with_secret_store(store).expect("failed to load capability secret");
+    } else {
+        info!("Secret store not unlocked; capability secret kept in memory");
+        if config.crash_dump_dir.is_some() {
+            warn!("ESTA_CRASH_DUMP_DIR needs the secret store for the dump key; crash dumps are off");
+        }
+        if config.module_cache_path().is_some() {
+            warn!("The module cache needs the secret store for its key; compiled modules are not cached");
+        }
+    }
+    if config.read_replica {
+        info!("Running as a read replica of {:?}", config.data_dir);
+    } else if let Some(archive) = config.invocation_archive() {
+        info!("Archiving invocations to {:?}", config.archive_dir);
+        kernel = kernel.with_archive(archive);
+    }
+    if let Some(catalog) = config.module_catalog().expect("failed to open module catalog") {
+        info!("Module catalog at {}", catalog.dir().display());
+        kernel = kernel.with_module_catalog(catalog);
+        match tauri::async_runtime::block_on(kernel.load_installed_modules()) {
+            Ok(loaded) => info!("Loaded installed modules: {:?}", loaded),
+            Err(e) => error!("Failed to load installed modules: {}", e),
+        }
+    }
+
+    let violations = kernel.profile_violations();
+    if profile != SecurityProfile::Development && !violations.is_empty() {
+        panic!("the {} security profile is not satisfied: {}", profile, violations.join("; "));
+    }
+
+    if let Some(dir) = config.statutes_path().filter(|dir| dir.is_dir()) {
+        match tauri::async_runtime::block_on(kernel.load_statutes(&dir)) {
+            Ok(loaded) => info!("Loaded statutes from {}: {:?}", dir.display(), loaded),
+            Err(e) => error!("Failed to load statutes from {}; using bundled statutes: {}", dir.display(), e),
+        }
+    }
+
+    if !config.read_replica {
+        match tauri::async_runtime::block_on(kernel.apply_retention_all()) {
+            Ok(reports) => {
+                for report in reports.iter().filter(|r| r.ledger_events + r.audit_entries > 0) {
+                    info!("Retention period of tenant {} applied: {:?}", report.tenant_id, report);
+                }
+            }
+            Err(e) => error!("Failed to apply retention periods: {:#}", e),
+        }
+    }
+
+    if let Some(hours) = config.vacuum_interval_hours {
+        info!("Vacuuming storage every {} hours", hours);
+        let storage = kernel.storage();
+        tauri::async_runtime::block_on(async move {
+            storage.schedule(Duration::from_secs(hours * 3600));
+        });
+    }
+
+    if let Some(history) = config.stats_history().expect("failed to load module statistics history") {
+        info!("Module statistics history at {:?}", history.path());
+        kernel = kernel.with_stats_history(history);
+        tauri::async_runtime::block_on(async {
+            kernel.schedule_stats_snapshots(STATS_SNAPSHOT_INTERVAL);
+        });
+    }
+
+    let (supervisor, relaunches) =
+        supervision::supervisor(config.supervisor_path()).expect("failed to load supervisor crash history");
+    match &config.accrual_manifest {
+        Some(path) => match tauri::async_runtime::block_on(kernel.launch_module(path)) {
+            Ok(()) => {
+                let spec = ChildSpec { id: ACCRUAL_MODULE.to_string(), manifest_path: path.clone(), ..Default::default() };
+                let supervisor = supervisor.clone();
+                if let Err(e) = tauri::async_runtime::block_on(async move {
+                    supervisor.register_child(spec).await?;
+                    supervisor.report_started(ACCRUAL_MODULE).await
+                }) {
+                    error!("Failed to supervise the accrual module: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to load accrual module from {}: {}", path, e),
+        },
+        None => warn!("ESTA_ACCRUAL_MANIFEST not set; accrual calculations are unavailable"),
+    }
+    tauri::async_runtime::block_on(async {
+        kernel.supervise_heartbeats(supervisor.clone(), HEARTBEAT_CHECK_INTERVAL);
+    });
+    let transitions = supervisor.subscribe();
+
+    kernel = kernel.with_alert_monitor(config.alert_monitor().expect("failed to load alert configuration"));
+    let alert_feed = kernel.alerts().subscribe();
+    if !config.read_replica {
+        tauri::async_runtime::block_on(async {
+            kernel.start_alert_monitor();
+        });
+    }
+
+    let reminders = config.reminder_store().expect("failed to load reminders");
+    let sessions = config.session_store().expect("failed to load accounts");
+    let fire_reminders = !config.read_replica;
+    let audit_feed = kernel.audit_log().subscribe(AuditFilter::default());
+    let audit_log = kernel.audit_log();
+    if audit_log.segment_dir().is_some() {
+        let audit_log = audit_log.clone();
+        tauri::async_runtime::block_on(async move {
+            audit_log.schedule_retries(AUDIT_RETRY_INTERVAL);
+        });
+    }
+
+    tauri::Builder::default()
+        .manage(AppState { kernel, config })
+        .manage(reminders)
+        .manage(sessions)
+        .manage(AuditStreams::default())
+        .manage(supervisor.clone())
+        .setup(move |app| {
+            tauri::async_runtime::spawn(audit_stream::forward_entries(app.handle(), audit_feed));
+            tauri::async_runtime::spawn(audit_stream::forward_health(app.handle(), audit_log));
+            tauri::async_runtime::spawn(alerts::forward_alerts(app.handle(), alert_feed));
+            tauri::async_runtime::spawn(supervision::forward_transitions(app.handle(), transitions));
+            tauri::async_runtime::spawn(supervision::relaunch_children(app.handle(), supervisor, relaunches));
+            if fire_reminders {
+                let handle = app.handle();
+                std::thread::spawn(move || loop {
+                    notify_due_reminders(&handle);
+                    std::thread::sleep(REMINDER_CHECK_INTERVAL);
+                });
+            }
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            invoke_kernel,
+            kernel_get_status,
+            supervisor_get_status,
+            kernel_load_module,
+            kernel_list_available_modules,
+            kernel_install_module,
+            kernel_rollback_module,
+            kernel_get_module_info,
+            kernel_unload_module,
+            kernel_execute,
+            kernel_cancel,
+            kernel_replay,
+            kernel_get_logs,
+            kernel_verify_audit,
+            audit_verify_export,
+            storage_usage_report,
+            storage_vacuum,
+            kernel_get_stats_history,
+            kernel_export_stats_history,
+            kernel_rotate_capability_secret,
+            database_rotate_key,
+            backup_create,
+            backup_restore,
+            kernel_rotate_signing_key,
+            kernel_export_capabilities,
+            kernel_reload_config,
+            tenant_create,
+            tenant_archive,
+            tenant_purge,
+            tenant_apply_retention,
+            tenant_set_policy,
+            tenant_get_policy_history,
+            tenant_get_usage,
+            statute_get_parameters,
+            tenant_get_accruals,
+            employee_view_accruals,
+            tenant_usage_insights,
+            import_timesheet_csv,
+            generate_compliance_report,
+            generate_liability_report,
+            report_template_save,
+            report_template_list,
+            generate_custom_report,
+            kernel_audit_subscribe,
+            kernel_audit_unsubscribe,
+            session_login,
+            session_logout,
+            session_current,
+            session_add_account,
+            reminder_add,
+            reminder_list,
+            reminder_remove,
+            alerts_get_config,
+            alerts_set_config,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let state = app.state::<AppState>();
+                match tauri::async_runtime::block_on(state.kernel.shutdown()) {
+                    Ok(outcomes) => {
+                        for outcome in outcomes.iter().filter(|o| !o.is_graceful()) {
+                            warn!("Module {} did not shut down cleanly: {:?}", outcome.module, outcome);
+                        }
+                    }
+                    Err(e) => error!("Kernel shutdown failed: {}", e),
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state(config: AppConfig) -> AppState {
+        AppState {
+            kernel: Kernel::new().unwrap(),
+            config,
+        }
+    }
+
+    #[test]
+    fn test_validate_request_valid() {
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"minutes_worked": 60}),
+        };
+        assert!(validate_request(&request).is_ok());
+    }
+
+    #[test]
+    fn test_validate_request_invalid_action() {
+        let request = KernelRequest {
+            action: "delete_all".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({}),
+        };
+        assert!(validate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_validate_request_invalid_module() {
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "system".to_string(),
+            payload: serde_json::json!({}),
+        };
+        assert!(validate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_legacy_accrue_translation() {
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"minutes_worked": 120, "employer_size": "large"}),
+        };
+        let call = legacy_call(&request).unwrap();
+        assert_eq!(call.function, "accrue_json");
+        assert_eq!(call.input["minutes_worked"], 120);
+        assert_eq!(call.input["employer_policy"]["employer_size"], "large");
+
+        let data = call.into_response(&serde_json::json!({
+            "employee_id": "",
+            "accrued_minutes": 4,
+            "metadata": {"calc": "1:30"}
+        }));
+        assert_eq!(data["accrued_minutes"], 4);
+        assert_eq!(data["minutes_worked"], 120);
+        assert_eq!(data["rate"], "1:30");
+    }
+
+    #[test]
+    fn test_legacy_calculate_translation() {
+        let request = KernelRequest {
+            action: "calculate".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"operation": "balance", "accrued": 100, "used": 30}),
+        };
+        let call = legacy_call(&request).unwrap();
+        assert_eq!(call.function, "validate_json");
+        assert_eq!(call.input["accrued_minutes"], 100);
+        assert_eq!(call.input["used_minutes"], 30);
+
+        let data = call.into_response(&serde_json::json!({"balance_minutes": 70}));
+        assert_eq!(data["result"], 70);
+        assert_eq!(data["operation"], "balance");
+
+        let request = KernelRequest {
+            action: "calculate".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"operation": "payroll"}),
+        };
+        assert!(legacy_call(&request).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_invoke_kernel_status() {
+        let request = KernelRequest {
+            action: "status".to_string(),
             module: "accrual".to_string(),
             payload: serde_json::json!({}),
         };
-        let response = invoke_kernel(request).await.unwrap();
+        let response = handle_invoke_kernel(&test_state(AppConfig::default()), request).await;
         assert!(response.success);
         assert!(response.data.is_some());
     }
 
     #[tokio::test]
-    async fn test_invoke_kernel_accrue() {
-        let request = KernelRequest {
-            action: "accrue".to_string(),
-            module: "accrual".to_string(),
-            payload: serde_json::json!({"minutes_worked": 120}),
+    async fn test_invoke_kernel_accrue() {
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"minutes_worked": 120}),
+        };
+        // Without the accrual module loaded there is no fallback calculation
+        let response = handle_invoke_kernel(&test_state(AppConfig::default()), request).await;
+        assert!(!response.success);
+        assert_eq!(response.error_code, Some("MODULE_NOT_LOADED"));
+        assert!(response.error_detail.unwrap().contains("not loaded"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_kernel_simulate() {
+        let state = test_state(AppConfig::default());
+        let payload = serde_json::json!({
+            "employer_size": "large",
+            "as_of": "2025-06-01",
+            "periods": [{"minutes_worked": 2400}]
+        });
+        let input = simulation_input(&state, &payload).await.unwrap();
+        assert_eq!(input["statutory"]["accrual_hours_worked"], 30);
+        assert_eq!(input["statutory"]["large_employer_usage_hours"], 72);
+        assert!(simulation_input(&state, &serde_json::json!([])).await.is_err());
+
+        let request = KernelRequest { action: "simulate".to_string(), module: "policy-sim".to_string(), payload };
+        let response = handle_invoke_kernel(&state, request).await;
+        assert_eq!(response.error_code, Some("MODULE_NOT_LOADED"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_kernel_legacy_disabled() {
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"minutes_worked": 120}),
+        };
+        let config = AppConfig {
+            fail_on_legacy_requests: true,
+            ..Default::default()
+        };
+        let response = handle_invoke_kernel(&test_state(config), request).await;
+        assert!(!response.success);
+        assert_eq!(response.error_code, Some("INVALID_REQUEST"));
+        assert!(response.error_detail.unwrap().contains("disabled"));
+    }
+
+    #[test]
+    fn test_legacy_validate_translation() {
+        let request = KernelRequest {
+            action: "validate".to_string(),
+            module: "compliance".to_string(),
+            payload: serde_json::json!({
+                "employee_id": "emp1",
+                "accrued_minutes": 100,
+                "used_minutes": 50
+            }),
+        };
+        let call = legacy_call(&request).unwrap();
+        assert_eq!(call.function, "validate_json");
+        assert_eq!(call.input["employee_id"], "emp1");
+
+        let data = call.into_response(&serde_json::json!({
+            "employee_id": "emp1",
+            "valid": true,
+            "balance_minutes": 50,
+            "validation_errors": []
+        }));
+        assert_eq!(data["valid"], true);
+        assert_eq!(data["balance"], 50);
+    }
+
+    #[tokio::test]
+    async fn test_kernel_get_status() {
+        let response = handle_get_status(&test_state(AppConfig::default())).await;
+        assert!(response.success);
+        let data = response.data.unwrap();
+        assert_eq!(data["status"], "running");
+    }
+
+    #[tokio::test]
+    async fn test_kernel_get_module_info_unknown_module() {
+        let response = handle_get_module_info(&test_state(AppConfig::default()), "missing".into()).await;
+        assert_eq!(response.error_code, Some("MODULE_NOT_LOADED"));
+    }
+
+    #[tokio::test]
+    async fn test_kernel_unload_module_unknown_module() {
+        let (supervisor, _relaunches) = supervision::supervisor(None).unwrap();
+        let request = UnloadModuleRequest { name: "missing".into(), flush: true };
+        let response = handle_unload_module(&test_state(AppConfig::default()), &supervisor, request).await;
+        assert_eq!(response.error_code, Some("MODULE_NOT_LOADED"));
+    }
+
+    #[tokio::test]
+    async fn test_kernel_replay() {
+        let state = test_state(AppConfig::default());
+        let response = handle_replay(&state, ReplayRequest { first_sequence: 0, last_sequence: None }).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["deterministic"], true);
+        assert_eq!(data["report"]["invocations"].as_array().unwrap().len(), 0);
+
+        let response = handle_replay(&state, ReplayRequest { first_sequence: 5, last_sequence: Some(1) }).await;
+        assert_eq!(response.error_code, Some("INVALID_REQUEST"));
+    }
+
+    #[tokio::test]
+    async fn test_kernel_cancel_finished_invocation() {
+        let state = test_state(AppConfig::default());
+        let response = handle_cancel(&state, CancelRequest { invocation_id: "report-2025".into() }).await;
+        assert!(response.success);
+        let data = response.data.unwrap();
+        assert_eq!((data["invocation_id"].as_str(), data["cancelled"].as_bool()), (Some("report-2025"), Some(false)));
+    }
+
+    #[test]
+    fn test_execution_config_overrides() {
+        let config = AppConfig {
+            max_queued_invocations: Some(8),
+            shutdown_drain_secs: Some(3),
+            enforce_resource_profiles: true,
+            result_cache: true,
+            result_cache_ttl_secs: Some(60),
+            tenant_fuel_ceiling: Some(1_000),
+            tenant_fuel_ceilings: parse_fuel_ceilings("acme=5000, bad, globex=x"),
+            capability_denial_alert: Some(20),
+            ..Default::default()
+        };
+        let execution = config.execution_config();
+        assert!(execution.resource_profile.enforce);
+        assert!(execution.result_cache.enabled);
+        assert_eq!(execution.result_cache.ttl, Duration::from_secs(60));
+        assert_eq!(execution.tenant_fuel.ceiling("acme"), Some(5_000));
+        assert_eq!(execution.tenant_fuel.ceiling("globex"), Some(1_000));
+        assert_eq!(execution.max_queued_invocations, 8);
+        assert_eq!(execution.shutdown_drain_timeout, Duration::from_secs(3));
+        assert_eq!(execution.capability_metrics.denial_alert_threshold, Some(20));
+
+        let strict = AppConfig { sandbox_profile: Some("strict".to_string()), ..Default::default() };
+        let execution = strict.execution_config();
+        assert_eq!((execution.sandbox, execution.max_fuel), (Some(SandboxProfile::Strict), 5_000_000));
+        assert!(execution.require_signatures);
+        let typo = AppConfig { sandbox_profile: Some("strcit".to_string()), ..Default::default() };
+        assert!(typo.sandbox_profile().is_err());
+        assert_eq!(execution.max_concurrent_invocations, ExecutionConfig::default().max_concurrent_invocations);
+    }
+
+    #[tokio::test]
+    async fn test_kernel_config_file() {
+        use esta_kernel::security::sig::ModuleSigner;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let key = ModuleSigner::from_seed(&[3u8; 32]).unwrap().public_key_hex();
+        let file = dir.join("esta-kernel.toml");
+        std::fs::write(
+            &file,
+            format!(
+                "sandbox = \"strict\"\nmodule_dir = \"/opt/esta/modules\"\ntrusted_keys = [\"{}\"]\n\n\
+                 [execution]\nmax_fuel = 1000000\nmax_queued_invocations = 2\n",
+                key
+            ),
+        )
+        .unwrap();
+        let config = AppConfig {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            max_queued_invocations: Some(8),
+            ..Default::default()
+        }
+        .with_kernel_config()
+        .unwrap();
+        assert_eq!(config.kernel_config_path(), Some(file.clone()));
+        assert_eq!(config.modules_path(), Some(PathBuf::from("/opt/esta/modules")));
+        assert_eq!(config.trust_store().unwrap().unwrap().current().unwrap().public_key, key);
+        let execution = config.execution_config();
+        assert_eq!((execution.sandbox, execution.max_fuel), (Some(SandboxProfile::Strict), 1_000_000));
+        // The environment wins over the file
+        assert_eq!(execution.max_queued_invocations, 8);
+
+        let state = AppState { kernel: Kernel::with_config(execution).unwrap(), config };
+        std::fs::write(&file, "module_dir = \"/srv/modules\"\n[execution]\nmax_fuel = 2000000\n").unwrap();
+        let data = handle_reload_config(&state).await.data.unwrap();
+        assert!(data["changed"].as_array().unwrap().contains(&serde_json::json!("max_fuel")));
+        let restart_required = data["restart_required"].as_array().unwrap();
+        assert!(restart_required.contains(&serde_json::json!("module_dir")));
+        assert!(restart_required.contains(&serde_json::json!("trusted_keys")));
+        assert_eq!(state.kernel.config().max_fuel, 2_000_000);
+
+        std::fs::write(&file, "max_fule = 1\n").unwrap();
+        assert_eq!(handle_reload_config(&state).await.error_code, Some("INVALID_REQUEST"));
+        assert_eq!(state.kernel.config().max_fuel, 2_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_get_usage() {
+        let state = test_state(AppConfig::default());
+        let response = handle_get_tenant_usage(&state, "acme".into()).await;
+        assert_eq!(response.error_code, Some("TENANT_NOT_FOUND"));
+
+        state.kernel.tenants().register("acme").await.unwrap();
+        let response = handle_get_tenant_usage(&state, "acme".into()).await;
+        let usage = response.data.unwrap();
+        assert_eq!(usage["fuel_consumed"], 0);
+        assert_eq!(usage["modules"], serde_json::json!([]));
+        assert!(usage.get("cost_cents").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_get_status() {
+        let (supervisor, _relaunches) = supervision::supervisor(None).unwrap();
+        let spec = ChildSpec { id: ACCRUAL_MODULE.into(), manifest_path: "accrual.json".into(), ..Default::default() };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started(ACCRUAL_MODULE).await.unwrap();
+
+        let response = handle_supervisor_get_status(&supervisor).await;
+        let children = response.data.unwrap()["children"].clone();
+        assert_eq!(children[0]["id"], ACCRUAL_MODULE);
+        assert_eq!(children[0]["state"], serde_json::json!({ "kind": "running" }));
+
+        // Crash histories are kept by the primary only
+        let config = AppConfig { data_dir: Some("/var/esta".into()), ..Default::default() };
+        assert_eq!(config.supervisor_path(), Some(PathBuf::from("/var/esta/supervisor.json")));
+        assert_eq!(AppConfig { read_replica: true, ..config }.supervisor_path(), None);
+    }
+
+    #[tokio::test]
+    async fn test_database_keeps_rosters_across_restarts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig { data_dir: Some(dir.to_string_lossy().into_owned()), ..Default::default() };
+        assert_eq!(config.database_path(), Some(dir.join("esta.db")));
+        assert!(AppConfig { read_replica: true, ..config.clone() }.database(None).unwrap().is_none());
+        let encrypted = AppConfig { encrypt_database: true, ..config.clone() };
+        assert!(encrypted.database(None).unwrap_err().contains("requires ESTA_SECRET_PASSPHRASE"));
+
+        let kernel = Kernel::new().unwrap().with_database(config.database(None).unwrap().unwrap());
+        kernel.create_tenant("acme").await.unwrap();
+        kernel.add_employees("acme", &["emp1".to_string()]).await.unwrap();
+        drop(kernel);
+
+        let kernel = Kernel::new().unwrap().with_database(config.database(None).unwrap().unwrap());
+        kernel.restore_from_database().await.unwrap();
+        assert_eq!(kernel.tenants().list_employees("acme").await.unwrap(), vec!["emp1"]);
+        let state = AppState { kernel, config };
+        assert_eq!(handle_rotate_database_key(&state).await.error_code, Some("INVALID_REQUEST"));
+    }
+
+    #[tokio::test]
+    async fn test_backup_create_and_restore() {
+        let data_dir = |dir: &tempfile::TempDir| AppConfig {
+            data_dir: Some(dir.path().to_string_lossy().into_owned()),
+            ..Default::default()
         };
-        let response = invoke_kernel(request).await.unwrap();
-        assert!(response.success);
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = AppState { kernel: Kernel::new().unwrap(), config: data_dir(&source_dir) };
+        source.kernel.create_tenant("acme").await.unwrap();
+        source.kernel.add_employees("acme", &["emp1".to_string()]).await.unwrap();
+        let response = handle_backup_create(&source, "backup.json").await;
+        assert!(response.success, "{:?}", response.error_detail);
+        let path = source_dir.path().join("backups/backup.json").to_string_lossy().into_owned();
+
+        // Backups are written only inside the backups directory
+        let outside = source_dir.path().join("outside.json");
+        for escape in [outside.to_string_lossy().into_owned(), "../outside.json".to_string()] {
+            assert_eq!(handle_backup_create(&source, &escape).await.error_code, Some("INVALID_REQUEST"));
+        }
+        assert!(!outside.exists());
+        let no_data_dir = AppState { kernel: Kernel::new().unwrap(), config: AppConfig::default() };
+        assert_eq!(handle_backup_create(&no_data_dir, "backup.json").await.error_code, Some("STORAGE_UNAVAILABLE"));
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let target = AppState { kernel: Kernel::new().unwrap(), config: data_dir(&target_dir) };
+        // Another installation's backup key must be trusted explicitly
+        let refused = handle_backup_restore(&target, &path, None).await;
+        assert_eq!(refused.error_code, Some("SIGNATURE_INVALID"));
+
+        let response = handle_backup_restore(&target, &path, Some(source.kernel.backup_public_key())).await;
         let data = response.data.unwrap();
-        assert_eq!(data["accrued_minutes"], 4); // 120/30 = 4
+        assert_eq!(data["tenants_added"], 1);
+        assert_eq!(target.kernel.tenants().list_employees("acme").await.unwrap(), vec!["emp1"]);
+        let audit_file = data["audit_file"].as_str().unwrap();
+        assert!(std::fs::read_to_string(audit_file).unwrap().lines().count() > 0);
+    }
+
+    #[test]
+    fn test_alert_config_webhooks_must_be_allowed() {
+        let config = AppConfig { webhook_hosts: vec!["hooks.example.com".to_string()], ..Default::default() };
+        let kernel = Kernel::new().unwrap().with_alert_monitor(config.alert_monitor().unwrap());
+        let state = AppState { kernel, config };
+
+        let refused = AlertConfig { webhooks: vec!["https://elsewhere.example.com/".to_string()], ..Default::default() };
+        let response = handle_set_alert_config(&state, Some("acme".to_string()), refused);
+        assert_eq!(response.error_code, Some("INVALID_REQUEST"));
+
+        let allowed = AlertConfig { webhooks: vec!["https://hooks.example.com/esta".to_string()], ..Default::default() };
+        assert!(handle_set_alert_config(&state, Some("acme".to_string()), allowed.clone()).success);
+        let data = handle_get_alert_config(&state, Some("acme".to_string())).data.unwrap();
+        assert_eq!(data["config"]["webhooks"], serde_json::json!(allowed.webhooks));
+        let default = handle_get_alert_config(&state, None).data.unwrap();
+        assert_eq!(default["config"], serde_json::json!({}));
     }
 
     #[tokio::test]
-    async fn test_invoke_kernel_validate() {
-        let request = KernelRequest {
-            action: "validate".to_string(),
-            module: "compliance".to_string(),
-            payload: serde_json::json!({
-                "employee_id": "emp1",
-                "accrued_minutes": 100,
-                "used_minutes": 50
-            }),
+    async fn test_storage_usage_and_vacuum() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            storage_alert_mb: Some(1),
+            ..Default::default()
         };
-        let response = invoke_kernel(request).await.unwrap();
-        assert!(response.success);
+        let state = AppState {
+            kernel: Kernel::new()
+                .unwrap()
+                .with_ledger(config.ledger().unwrap())
+                .with_storage_limits(config.storage_limits()),
+            config,
+        };
+        std::fs::write(dir.join("ledger.jsonl"), "").unwrap();
+
+        let response = handle_storage_usage_report(&state).await;
         let data = response.data.unwrap();
-        assert_eq!(data["valid"], true);
-        assert_eq!(data["balance"], 50);
+        assert_eq!(data["over_limit"], false);
+        assert_eq!(data["report"]["areas"][0]["area"], "ledger");
+
+        let response = handle_storage_vacuum(&state).await;
+        assert_eq!(response.data.unwrap()["temp_files_removed"], 0);
     }
 
     #[tokio::test]
-    async fn test_kernel_get_status() {
-        let response = kernel_get_status().await.unwrap();
-        assert!(response.success);
-        let data = response.data.unwrap();
-        assert_eq!(data["status"], "running");
+    async fn test_stats_history_commands() {
+        let request = || StatsHistoryRequest { module: None, from: 0, to: None };
+        let state = test_state(AppConfig::default());
+        let response = handle_get_stats_history(&state, request()).await;
+        assert_eq!(response.error_code, Some("INVALID_REQUEST"));
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig { data_dir: Some(dir.to_string_lossy().into_owned()), ..Default::default() };
+        assert!(AppConfig { read_replica: true, ..config.clone() }.stats_history().unwrap().is_none());
+        let history = config.stats_history().unwrap().unwrap();
+        assert_eq!(history.path(), Some(dir.join("stats.jsonl").as_path()));
+        let state = AppState { kernel: Kernel::new().unwrap().with_stats_history(history), config };
+
+        let response = handle_get_stats_history(&state, request()).await;
+        assert_eq!(response.data.unwrap()["samples"], serde_json::json!([]));
+        let response = handle_export_stats_history(&state, request()).await;
+        let export: esta_kernel::StatsExport = serde_json::from_value(response.data.unwrap()).unwrap();
+        assert!(export.verify().is_ok());
     }
 
     #[tokio::test]
@@ -602,9 +4212,15 @@ mod tests {
             accrual_rate: 0.0333, // ~1:30
             max_carryover_hours: 40,
             max_usage_hours: 72,
+            usage_insights: false,
+            effective_from: None,
+            jurisdiction: None,
+            retention_days: None,
         };
-        let response = tenant_set_policy(policy).await.unwrap();
+        let state = test_state(AppConfig::default());
+        let response = handle_set_policy(&state, policy).await;
         assert!(response.success);
+        assert!(state.kernel.tenants().get_policy("tenant1").await.unwrap().is_some());
     }
 
     #[tokio::test]
@@ -615,10 +4231,795 @@ mod tests {
             accrual_rate: 0.0333,
             max_carryover_hours: 40,
             max_usage_hours: 72,
+            usage_insights: false,
+            effective_from: None,
+            jurisdiction: None,
+            retention_days: None,
         };
-        let response = tenant_set_policy(policy).await.unwrap();
+        let response = handle_set_policy(&test_state(AppConfig::default()), policy).await;
         assert!(!response.success);
-        assert!(response.error.is_some());
+        assert_eq!(response.error_code, Some("INVALID_POLICY"));
+        assert!(response.remediation.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_statute_parameters_and_policy_minimums() {
+        let config = AppConfig { modules_dir: Some("/srv/esta/modules".to_string()), ..AppConfig::default() };
+        assert_eq!(config.statutes_path(), Some(PathBuf::from("/srv/esta/modules/statutes")));
+
+        let state = test_state(config);
+        let response = handle_get_statute(&state, Some("2025-06-01".to_string()), None).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["jurisdiction"], "US-MI");
+        assert_eq!(data["statute"]["parameters"]["accrual_hours_worked"], 30);
+        assert_eq!(data["statute"]["parameters"]["waiting_period_days"], 90);
+
+        let response = handle_get_statute(&state, Some("2020-01-01".to_string()), None).await;
+        assert_eq!(response.error_code, Some("STATUTE_UNAVAILABLE"));
+
+        let response = handle_get_statute(&state, Some("2025-06-01".to_string()), Some("US-MN".to_string())).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["jurisdiction"], "US-MN");
+        assert_eq!(data["statute"]["parameters"]["waiting_period_days"], 0);
+
+        let policy = TenantPolicy {
+            tenant_id: "acme".to_string(),
+            employer_size: "large".to_string(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 40,
+            usage_insights: false,
+            effective_from: Some("2025-06-01".to_string()),
+            jurisdiction: None,
+            retention_days: None,
+        };
+        let response = handle_set_policy(&state, policy).await;
+        assert_eq!(response.error_code, Some("INVALID_POLICY"));
+        assert!(response.error_detail.unwrap().contains("max_usage_hours"));
+    }
+
+    #[tokio::test]
+    async fn test_capability_secret_kept_in_secret_store() {
+        use esta_kernel::security::secrets::CAPABILITY_SECRET;
+        use esta_kernel::{CapabilityRight, ResourceType};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            secret_passphrase: Some("correct horse".to_string()),
+            ..AppConfig::default()
+        };
+        assert_eq!(config.secrets_path(), Some(dir.join("secrets.json")));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let stored = |config: &AppConfig| {
+            let store = config.secret_store().unwrap().unwrap();
+            (store.generation(CAPABILITY_SECRET), store.get(CAPABILITY_SECRET).unwrap().unwrap().expose().to_vec())
+        };
+        let kernel = Kernel::new().unwrap().with_secret_store(config.secret_store().unwrap().unwrap()).unwrap();
+        let rights = [CapabilityRight::Read].into_iter().collect();
+        kernel
+            .capability_manager()
+            .create_capability(ResourceType::Module, "ledger".to_string(), rights, "shell".to_string(), Default::default())
+            .await
+            .unwrap();
+        let (generation, first) = stored(&config);
+        assert_eq!(generation, Some(1));
+
+        let state = AppState { kernel, config: config.clone() };
+        let response = handle_rotate_capability_secret(&state).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["reissued"], 1);
+        assert_eq!(data["persisted"], true);
+        let (generation, second) = stored(&config);
+        assert_eq!(generation, Some(2));
+        assert_ne!(first, second);
+
+        let wrong = AppConfig { secret_passphrase: Some("wrong".to_string()), ..config };
+        assert!(wrong.secret_store().is_err());
+
+        // The keychain needs a store location just as a passphrase does
+        let keychain = AppConfig { secret_keychain: true, ..AppConfig::default() };
+        assert!(keychain.secrets_enabled() && !AppConfig::default().secrets_enabled());
+        assert!(keychain.secret_store().unwrap_err().contains("ESTA_SECRETS_FILE"));
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_rotation_persists() {
+        use esta_kernel::security::sig::ModuleSigner;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let old = ModuleSigner::from_seed(&[1u8; 32]).unwrap();
+        let new = ModuleSigner::from_seed(&[2u8; 32]).unwrap();
+        let config = AppConfig {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            signing_public_key: Some(old.public_key_hex()),
+            ..AppConfig::default()
+        };
+        assert_eq!(config.trust_path(), Some(dir.join("trust.json")));
+
+        let kernel = Kernel::new().unwrap().with_trust_store(config.trust_store().unwrap().unwrap());
+        let state = AppState { kernel, config: config.clone() };
+        let response = handle_rotate_signing_key(&state, new.public_key_hex(), Some(7)).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["current"]["public_key"], new.public_key_hex());
+        assert_eq!(data["retiring"][0]["public_key"], old.public_key_hex());
+
+        // The rotation outlives a restart configured with the old key
+        let reopened = config.trust_store().unwrap().unwrap();
+        assert_eq!(reopened.current().unwrap().public_key, new.public_key_hex());
+
+        let response = handle_rotate_signing_key(&state, "not-a-key".to_string(), None).await;
+        assert_eq!(response.error_code, Some("SIGNATURE_CONFIG"));
+
+        let unsigned = test_state(AppConfig::default());
+        let response = handle_rotate_signing_key(&unsigned, new.public_key_hex(), None).await;
+        assert_eq!(response.error_code, Some("SIGNATURE_CONFIG"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_persisted_and_verified() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig { data_dir: Some(dir.to_string_lossy().into_owned()), ..AppConfig::default() };
+        assert_eq!(config.audit_path(), Some(dir.join("audit")));
+
+        let kernel = Kernel::new().unwrap().with_audit_log(config.audit_log().unwrap());
+        let state = AppState { kernel, config: config.clone() };
+        state.kernel.audit_log().log_custom("test", "first", "desktop").await;
+        let data = handle_verify_audit(&state, false).await.data.unwrap();
+        assert_eq!(data["incremental"]["entries_checked"], 1);
+        assert_eq!(data["full"]["running"], false);
+
+        handle_verify_audit(&state, true).await;
+        let mut progress = state.kernel.audit_log().start_full_verification().await;
+        progress.wait_for(|p| !p.running).await.unwrap();
+        let data = handle_verify_audit(&state, false).await.data.unwrap();
+        assert_eq!(data["incremental"]["entries_checked"], 0);
+        assert_eq!(data["full"]["result"]["valid"], true);
+        assert_eq!(data["full"]["segments_total"], 1);
+        let status = handle_get_status(&state).await.data.unwrap();
+        assert_eq!(status["audit"]["health"]["failing_since"], serde_json::Value::Null);
+        assert_eq!(status["audit"]["health"]["blocks_privileged"], false);
+        let typo = AppConfig { audit_failure_policy: Some("ignore".to_string()), ..config.clone() };
+        assert!(typo.audit_log().is_err(), "a typo must not fall back to a default policy");
+
+        // The chain continues after a restart
+        let reopened = config.audit_log().unwrap();
+        assert_eq!(reopened.log_custom("test", "second", "desktop").await.sequence, 2);
+    }
+
+    #[tokio::test]
+    async fn test_audit_verify_export() {
+        let state = test_state(AppConfig::default());
+        let audit_log = state.kernel.audit_log();
+        audit_log.log_custom("test", "first", "desktop").await;
+        audit_log.log_custom("test", "second", "desktop").await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit-export.jsonl");
+        let write = |entries: &[AuditEntry]| {
+            let lines: Vec<String> = entries.iter().map(|e| serde_json::to_string(e).unwrap()).collect();
+            std::fs::write(&path, lines.join("\n")).unwrap();
+        };
+        let mut entries = audit_log.get_all_entries().await;
+        write(&entries);
+
+        let export = path.to_string_lossy().into_owned();
+        let data = handle_audit_verify_export(&state, export.clone(), None).await.data.unwrap();
+        assert_eq!((data["valid"].as_bool(), data["entries_checked"].as_u64()), (Some(true), Some(2)));
+
+        entries[1].source = "ipc".to_string();
+        write(&entries);
+        let data = handle_audit_verify_export(&state, export.clone(), None).await.data.unwrap();
+        assert_eq!((data["valid"].as_bool(), data["first_invalid"].as_u64()), (Some(false), Some(2)));
+        std::fs::remove_file(&path).ok();
+        assert!(!handle_audit_verify_export(&state, export, None).await.success);
+    }
+
+    #[tokio::test]
+    async fn test_security_profile_reported_in_status() {
+        assert_eq!(AppConfig::default().security_profile(), Ok(SecurityProfile::Development));
+        let typo = AppConfig { security_profile: Some("prodution".to_string()), ..AppConfig::default() };
+        assert!(typo.security_profile().is_err(), "a typo must not fall back to development");
+
+        let config = AppConfig { security_profile: Some("production".to_string()), ..AppConfig::default() };
+        let profile = config.security_profile().unwrap();
+        let state = AppState { kernel: Kernel::new().unwrap().with_profile(profile), config };
+        let data = handle_get_status(&state).await.data.unwrap();
+        assert_eq!(data["config"]["security_profile"], "production");
+        assert_eq!(data["config"]["require_signatures"], true);
+        assert_eq!(data["config"]["profile_violations"].as_array().unwrap().len(), 3);
+        assert_eq!(data["capabilities"]["window_secs"], 60);
+    }
+
+    #[test]
+    fn test_reminder_commands() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AppConfig { data_dir: Some(dir.to_string_lossy().into_owned()), ..AppConfig::default() };
+        let reminders = config.reminder_store().unwrap();
+        let state = test_state(config);
+        let reminder: NewReminder = serde_json::from_value(serde_json::json!({
+            "title": "Annual carryover processing due",
+            "tenant_id": "acme",
+            "recurrence": "yearly",
+            "first_due": "2026-01-01"
+        }))
+        .unwrap();
+
+        let added = handle_reminder_add(&state, &reminders, reminder.clone());
+        assert!(added.success);
+        assert!(dir.join("reminders.json").exists());
+        let id = added.data.unwrap()["id"].as_u64().unwrap();
+
+        let untitled = NewReminder { title: String::new(), ..reminder.clone() };
+        assert_eq!(handle_reminder_add(&state, &reminders, untitled).error_code, Some("INVALID_REQUEST"));
+
+        let replica = test_state(AppConfig { read_replica: true, ..state.config.clone() });
+        assert_eq!(handle_reminder_add(&replica, &reminders, reminder).error_code, Some("READ_ONLY"));
+        assert_eq!(replica.config.reminder_store().unwrap().list().len(), 0, "replicas never fire the primary's reminders");
+
+        assert_eq!(handle_reminder_remove(&state, &reminders, id).data.unwrap()["removed"], true);
+        assert!(reminders.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_lifecycle_commands() {
+        let state = test_state(AppConfig::default());
+        let reminders = ReminderStore::in_memory();
+
+        let created = handle_tenant_create(&state, &reminders, "acme".into()).await;
+        let data = created.data.unwrap();
+        assert_eq!(data["tenant"]["status"], "active");
+        assert!(data["root_capability"].is_string());
+        let reminder = &reminders.list()[0];
+        assert_eq!((reminder.tenant_id.as_deref(), reminder.next_due.month()), (Some("acme"), 1));
+        let again = handle_tenant_create(&state, &reminders, "acme".into()).await;
+        assert_eq!(again.error_code, Some("TENANT_EXISTS"));
+        assert_eq!(handle_tenant_purge(&state, &reminders, "acme".into()).await.error_code, Some("TENANT_ACTIVE"));
+
+        let archived = handle_tenant_archive(&state, &reminders, "acme".into()).await.data.unwrap();
+        assert_eq!((archived["capabilities_revoked"].as_u64(), archived["reminders_removed"].as_u64()), (Some(1), Some(1)));
+        let archived_again = handle_tenant_archive(&state, &reminders, "acme".into()).await;
+        assert_eq!(archived_again.error_code, Some("TENANT_ARCHIVED"));
+
+        let purged = handle_tenant_purge(&state, &reminders, "acme".into()).await;
+        assert_eq!(purged.data.unwrap()["tenant_id"], "acme");
+        assert!(!state.kernel.tenants().contains("acme").await);
+
+        let replica = test_state(AppConfig { read_replica: true, ..AppConfig::default() });
+        assert_eq!(handle_tenant_create(&replica, &reminders, "globex".into()).await.error_code, Some("READ_ONLY"));
+    }
+
+    #[tokio::test]
+    async fn test_get_logs_reads_persisted_history() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let config = AuditLogConfig { max_entries: 2, ..AuditLogConfig::default() };
+        let audit_log = AuditLog::with_segments(config, &dir).unwrap();
+        let state = AppState { kernel: Kernel::new().unwrap().with_audit_log(audit_log), config: AppConfig::default() };
+        for i in 0..6 {
+            state.kernel.audit_log().log_custom("test", &format!("entry {}", i), "ui").await;
+        }
+
+        let request = GetLogsRequest {
+            limit: Some(2),
+            cursor: None,
+            source: Some("ui".to_string()),
+            after_sequence: Some(1),
+            from_timestamp: None,
+            to_timestamp: None,
+            correlation_id: None,
+            tenant_id: None,
+        };
+        let data = handle_get_logs(&state, request).await.data.unwrap();
+        let sequences: Vec<u64> = data["entries"].as_array().unwrap().iter().map(|e| e["sequence"].as_u64().unwrap()).collect();
+        assert_eq!(sequences, [2, 3], "trimmed from memory, read from the segments");
+        assert_eq!(data["total"], 6);
+        assert_eq!(data["next_cursor"], "3");
+
+        let request = GetLogsRequest {
+            limit: Some(2),
+            cursor: Some("3".to_string()),
+            source: Some("ui".to_string()),
+            after_sequence: Some(1),
+            from_timestamp: None,
+            to_timestamp: None,
+            correlation_id: None,
+            tenant_id: None,
+        };
+        let data = handle_get_logs(&state, request).await.data.unwrap();
+        let sequences: Vec<u64> = data["entries"].as_array().unwrap().iter().map(|e| e["sequence"].as_u64().unwrap()).collect();
+        assert_eq!(sequences, [4, 5]);
+
+        let status = handle_get_status(&state).await.data.unwrap();
+        assert_eq!(status["audit"]["archive"]["last_sequence"], 6);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_reaches_audit_log() {
+        let state = test_state(AppConfig::default());
+        let policy = TenantPolicy {
+            tenant_id: "acme".to_string(),
+            employer_size: "small".to_string(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 72,
+            usage_insights: false,
+            effective_from: Some("2025-01-01".to_string()),
+            jurisdiction: None,
+            retention_days: None,
+        };
+        let sessions = SessionStore::in_memory(true);
+        let id = Some("ui-set-policy-1".to_string());
+        let response = traced(&state, &sessions, "tenant_set_policy", id, handle_set_policy(&state, policy)).await;
+        assert!(response.success);
+        assert_eq!(response.correlation_id.as_deref(), Some("ui-set-policy-1"));
+
+        let generated = traced(&state, &sessions, "kernel_get_status", None, handle_get_status(&state)).await;
+        assert_eq!(generated.correlation_id.unwrap().len(), 32);
+
+        let request = GetLogsRequest {
+            limit: None,
+            cursor: None,
+            source: None,
+            after_sequence: None,
+            from_timestamp: None,
+            to_timestamp: None,
+            correlation_id: Some("ui-set-policy-1".to_string()),
+            tenant_id: None,
+        };
+        let data = handle_get_logs(&state, request).await.data.unwrap();
+        let entries = data["entries"].as_array().unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|e| e["correlation_id"] == "ui-set-policy-1"));
+    }
+
+    #[tokio::test]
+    async fn test_commands_are_audited() {
+        let state = test_state(AppConfig { ipc_audit_sample_rate: Some(0.0), ..Default::default() });
+        let ipc_calls = || async {
+            let query = AuditQuery { source: Some("ipc".to_string()), ..Default::default() };
+            let entries = state.kernel.audit_log().query(&query, 10).await.unwrap();
+            entries
+                .into_iter()
+                .map(|entry| match entry.event {
+                    esta_kernel::AuditEventType::Custom { message, .. } => {
+                        serde_json::from_str::<serde_json::Value>(&message).unwrap()
+                    }
+                    other => panic!("unexpected event {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let sessions = SessionStore::in_memory(true);
+
+        // Reads are sampled out; writes and failures are always recorded
+        traced(&state, &sessions, "kernel_get_status", None, handle_get_status(&state)).await;
+        assert!(ipc_calls().await.is_empty());
+        let id = Some("ui-history-1".to_string());
+        let history = handle_get_policy_history(&state, "globex".to_string());
+        traced(&state, &sessions, "tenant_get_policy_history", id, history).await;
+        let response = traced(&state, &sessions, "storage_vacuum", None, handle_storage_vacuum(&state)).await;
+
+        let calls = ipc_calls().await;
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["command"], "tenant_get_policy_history");
+        assert_eq!(calls[0]["correlation_id"], "ui-history-1");
+        assert_eq!(calls[0]["role"], "local");
+        assert_ne!(calls[0]["outcome"], "ok");
+        assert_eq!(calls[1]["command"], "storage_vacuum");
+        assert_eq!(calls[1]["outcome"], if response.success { "ok" } else { response.error_code.unwrap() });
+        assert_eq!(calls[1]["correlation_id"], response.correlation_id.unwrap().as_str());
+        assert!(calls[1]["duration_ms"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn test_commands_require_roles() {
+        let state = test_state(AppConfig::default());
+        state.kernel.tenants().register("acme").await.unwrap();
+        state.kernel.tenants().add_employee("acme", "emp1").await.unwrap();
+        state.kernel.tenants().add_employee("acme", "emp2").await.unwrap();
+        let sessions = SessionStore::in_memory(true);
+        let streams = AuditStreams::default();
+        let account = |username: &str, role, employee_id: Option<&str>| NewAccount {
+            username: username.to_string(),
+            password: "correct horse".to_string(),
+            role,
+            tenant_id: Some("acme".to_string()),
+            employee_id: employee_id.map(str::to_string),
+        };
+        let accruals = |employee_id: &str| EmployeeAccrualQuery {
+            tenant_id: "acme".to_string(),
+            employee_id: employee_id.to_string(),
+        };
+
+        // Open until the first account exists
+        let status = traced(&state, &sessions, "storage_usage_report", None, handle_storage_usage_report(&state)).await;
+        assert_ne!(status.error_code, Some("NOT_SIGNED_IN"));
+        assert!(handle_add_account(&state, &sessions, account("admin", session::Role::EmployerAdmin, None)).success);
+        let vacuum = traced(&state, &sessions, "storage_vacuum", None, handle_storage_vacuum(&state)).await;
+        assert_eq!(vacuum.error_code, Some("NOT_SIGNED_IN"));
+
+        assert!(handle_session_login(&state, &sessions, &streams, "admin", "correct horse").success);
+        assert!(handle_add_account(&state, &sessions, account("pat", session::Role::Employee, Some("emp1"))).success);
+        assert!(handle_add_account(&state, &sessions, account("morgan", session::Role::Manager, None)).success);
+        streams.subscribe(AuditFilter::default()).unwrap();
+        assert!(handle_session_login(&state, &sessions, &streams, "pat", "correct horse").success);
+        assert_eq!(streams.close_all(), 0, "the admin's audit subscription outlived their session");
+        let vacuum = traced(&state, &sessions, "storage_vacuum", None, handle_storage_vacuum(&state)).await;
+        assert_eq!(vacuum.error_code, Some("PERMISSION_DENIED"));
+
+        let own = handle_view_own_accruals(&state, &sessions, accruals("emp1"));
+        assert!(traced(&state, &sessions, "employee_view_accruals", None, own).await.success);
+        let other = handle_view_own_accruals(&state, &sessions, accruals("emp2"));
+        let other = traced(&state, &sessions, "employee_view_accruals", None, other).await;
+        assert_eq!(other.error_code, Some("PERMISSION_DENIED"));
+
+        // Managers are confined to their own tenant
+        state.kernel.tenants().register("globex").await.unwrap();
+        assert!(handle_session_login(&state, &sessions, &streams, "morgan", "correct horse").success);
+        let report = |tenant_id: &str| GenerateReportRequest {
+            tenant_id: tenant_id.to_string(),
+            year: 2025,
+            format: ReportFormat::Json,
+            output_path: None,
+        };
+        for (tenant_id, allowed) in [("acme", true), ("globex", false)] {
+            let handler = for_tenant(&state, &sessions, Some(tenant_id), handle_generate_report(&state, report(tenant_id)));
+            let response = traced(&state, &sessions, "generate_compliance_report", None, handler).await;
+            assert_eq!(response.success, allowed, "{}: {:?}", tenant_id, response.error_detail);
+        }
+        let handler = for_tenant(&state, &sessions, Some("globex"), handle_get_accruals(&state, "globex".to_string(), PageRequest::default()));
+        let response = traced(&state, &sessions, "tenant_get_accruals", None, handler).await;
+        assert_eq!(response.error_code, Some("PERMISSION_DENIED"));
+
+        let replica = test_state(AppConfig { read_replica: true, ..Default::default() });
+        let added = handle_add_account(&replica, &sessions, account("lee", session::Role::Manager, None));
+        assert_eq!(added.error_code, Some("READ_ONLY"));
+    }
+
+    #[tokio::test]
+    async fn test_export_capabilities() {
+        let state = test_state(AppConfig::default());
+        let manager = state.kernel.capability_manager();
+        manager
+            .create_read_only(esta_kernel::ResourceType::Module, "accrual".into(), "ui".into())
+            .await
+            .unwrap();
+
+        let data = handle_export_capabilities(&state).await.data.unwrap();
+        assert_eq!(data["capabilities"][0]["resource_id"], "accrual");
+        let snapshot: esta_kernel::CapabilitySnapshot = serde_json::from_value(data).unwrap();
+        assert!(manager.verify_snapshot(&snapshot).await);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_policy_history() {
+        let state = test_state(AppConfig::default());
+        for (size, date) in [("small", "2024-01-01"), ("large", "2025-02-21")] {
+            let policy = TenantPolicy {
+                tenant_id: "acme".to_string(),
+                employer_size: size.to_string(),
+                accrual_rate: 0.0333,
+                max_carryover_hours: 72,
+                max_usage_hours: 72,
+                usage_insights: false,
+                effective_from: Some(date.to_string()),
+                jurisdiction: None,
+                retention_days: None,
+            };
+            assert!(handle_set_policy(&state, policy).await.success);
+        }
+
+        let response = handle_get_policy_history(&state, "acme".to_string()).await;
+        let versions = &response.data.unwrap()["versions"];
+        assert_eq!(versions.as_array().unwrap().len(), 2);
+        assert_eq!(versions[0]["effective_to"], "2025-02-21");
+        assert_eq!(versions[1]["policy"]["employer_size"], "large");
+
+        // The policy in force on the work date is applied to legacy accruals
+        let payload = serde_json::json!({"tenant_id": "acme", "work_date": "2024-07-04"});
+        let version = tenant_policy_for_request(&state, "acme", &payload).await.unwrap().unwrap();
+        let request = KernelRequest {
+            action: "accrue".to_string(),
+            module: "accrual".to_string(),
+            payload: serde_json::json!({"minutes_worked": 60, "employer_size": "large"}),
+        };
+        let mut call = legacy_call(&request).unwrap();
+        call.apply_policy(&version);
+        assert_eq!(call.input["employer_policy"]["employer_size"], "small");
+        assert_eq!(call.input["employer_policy"]["policy_version"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_compliance_report() {
+        let state = test_state(AppConfig::default());
+        state.kernel.tenants().register("acme").await.unwrap();
+        state.kernel.tenants().add_employee("acme", "emp1").await.unwrap();
+
+        let request = KernelRequest {
+            action: "report".to_string(),
+            module: "reporting".to_string(),
+            payload: serde_json::json!({"tenant_id": "acme", "year": 2025}),
+        };
+        let response = handle_invoke_kernel(&state, request).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["employees"][0]["employee_id"], "emp1");
+        assert_eq!(data["period_end"], "2025-12-31");
+
+        let pdf = |output_path: &str| GenerateReportRequest {
+            tenant_id: "acme".to_string(),
+            year: 2025,
+            format: ReportFormat::Pdf,
+            output_path: Some(output_path.to_string()),
+        };
+        let no_data_dir = handle_generate_report(&state, pdf("report.pdf")).await;
+        assert_eq!(no_data_dir.error_code, Some("STORAGE_UNAVAILABLE"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState {
+            config: AppConfig { data_dir: Some(dir.path().to_string_lossy().into_owned()), ..Default::default() },
+            ..state
+        };
+        let response = handle_generate_report(&state, pdf("2025/report.pdf")).await;
+        assert!(response.success);
+        assert!(std::fs::read(dir.path().join("reports/2025/report.pdf")).unwrap().starts_with(b"%PDF"));
+
+        let outside = dir.path().join("outside.pdf");
+        for escape in [outside.to_str().unwrap(), "../outside.pdf"] {
+            let refused = handle_generate_report(&state, pdf(escape)).await;
+            assert_eq!(refused.error_code, Some("INVALID_REQUEST"));
+        }
+        assert!(!outside.exists());
+
+        let unknown = handle_generate_report(&state, GenerateReportRequest {
+            tenant_id: "globex".to_string(),
+            year: 2025,
+            format: ReportFormat::Json,
+            output_path: None,
+        }).await;
+        assert!(!unknown.success);
+    }
+
+    #[tokio::test]
+    async fn test_custom_report_templates() {
+        let state = test_state(AppConfig::default());
+        state.kernel.tenants().register("acme").await.unwrap();
+        state.kernel.tenants().add_employee("acme", "emp1").await.unwrap();
+        let template: ReportTemplate = serde_json::from_value(serde_json::json!({
+            "name": "payroll",
+            "columns": [{"header": "Employee", "expression": "employee_id"}],
+            "filter": "minutes_worked == 0"
+        }))
+        .unwrap();
+
+        let saved = handle_report_template_save(&state, "acme".to_string(), template.clone()).await;
+        assert_eq!(saved.data.unwrap()["version"], 1);
+        let list = handle_report_template_list(&state, "acme".to_string()).await;
+        assert_eq!(list.data.unwrap()["templates"][0]["template"]["name"], "payroll");
+
+        let request = |template: &str| CustomReportRequest {
+            tenant_id: "acme".to_string(),
+            year: 2025,
+            template: template.to_string(),
+            version: None,
+        };
+        let report = handle_custom_report(&state, request("payroll")).await.data.unwrap();
+        assert_eq!(report["rows"], serde_json::json!([["emp1"]]));
+
+        let missing = handle_custom_report(&state, request("other")).await;
+        assert_eq!(missing.error_code, Some("TEMPLATE_NOT_FOUND"));
+        let invalid = ReportTemplate { filter: Some("minutes_worked >".into()), ..template };
+        let rejected = handle_report_template_save(&state, "acme".to_string(), invalid).await;
+        assert_eq!(rejected.error_code, Some("INVALID_TEMPLATE"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_liability_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(AppConfig { data_dir: Some(dir.path().to_string_lossy().into_owned()), ..Default::default() });
+        state.kernel.tenants().register("acme").await.unwrap();
+        state.kernel.tenants().add_employee("acme", "emp1").await.unwrap();
+        state.kernel.ledger().append(esta_kernel::NewLedgerEvent {
+            tenant_id: "acme".to_string(),
+            employee_id: "emp1".to_string(),
+            work_date: "2025-03-03".parse().unwrap(),
+            kind: esta_kernel::LedgerEventKind::Accrued { minutes_worked: 7_200, accrued_minutes: 240 },
+            policy_version: None,
+            source: "test".to_string(),
+        }).await.unwrap();
+
+        let request = |format, output_path| LiabilityReportRequest {
+            tenant_id: "acme".to_string(),
+            as_of: Some("2025-06-30".to_string()),
+            wages: vec![WageRate { employee_id: "emp1".to_string(), hourly_rate_cents: 2_000, cost_center: None }],
+            gl_accounts: GlAccountMapping::default(),
+            format,
+            output_path,
+        };
+        let response = handle_liability_report(&state, request(LiabilityFormat::Json, None)).await;
+        let data = response.data.unwrap();
+        assert_eq!(data["total_liability_cents"], 8_000);
+        assert_eq!(data["lines"][0]["provenance"]["ledger_events"], 1);
+
+        let csv = handle_liability_report(&state, request(LiabilityFormat::Csv, None)).await;
+        assert_eq!(csv.error_code, Some("INVALID_REQUEST"));
+
+        let response = handle_liability_report(&state, request(LiabilityFormat::Csv, Some("liability.csv".to_string()))).await;
+        assert!(response.success);
+        let written = std::fs::read_to_string(dir.path().join("reports/liability.csv")).unwrap();
+        assert!(written.contains("emp1,,2150 Accrued Sick Leave,4.00,20.00,80.00"));
+
+        let outside = dir.path().join("outside.csv");
+        for escape in [outside.to_string_lossy().into_owned(), "../outside.csv".to_string()] {
+            let refused = handle_liability_report(&state, request(LiabilityFormat::Csv, Some(escape))).await;
+            assert_eq!(refused.error_code, Some("INVALID_REQUEST"));
+        }
+        assert!(!outside.exists());
+    }
+
+    #[tokio::test]
+    async fn test_read_replica_rejects_writes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let primary_config = AppConfig {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let replica_config = AppConfig { read_replica: true, ..primary_config.clone() };
+        assert!(AppConfig { read_replica: true, ..Default::default() }.ledger().is_err());
+
+        let primary = Kernel::new().unwrap()
+            .with_tenant_registry(primary_config.tenant_registry().unwrap())
+            .with_ledger(primary_config.ledger().unwrap());
+        let policy = |date: &str| TenantPolicy {
+            tenant_id: "acme".to_string(),
+            employer_size: "small".to_string(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 72,
+            usage_insights: false,
+            effective_from: Some(date.to_string()),
+            jurisdiction: None,
+            retention_days: None,
+        };
+        let primary_state = AppState { kernel: primary, config: primary_config };
+        assert!(handle_set_policy(&primary_state, policy("2025-01-01")).await.success);
+
+        let replica = Kernel::new().unwrap()
+            .with_tenant_registry(replica_config.tenant_registry().unwrap())
+            .with_ledger(replica_config.ledger().unwrap());
+        let replica_state = AppState { kernel: replica, config: replica_config };
+        assert!(!handle_set_policy(&replica_state, policy("2026-01-01")).await.success);
+
+        let import = ImportTimesheetRequest {
+            tenant_id: "acme".to_string(),
+            csv: "employee_id,work_date,hours_worked\ne1,2025-03-03,8\n".to_string(),
+            mapping: Default::default(),
+            delimiter: None,
+            source_name: None,
+            dry_run: false,
+        };
+        let dry_run = ImportTimesheetRequest { dry_run: true, ..import.clone() };
+        let response = handle_import_timesheet(&replica_state, import).await;
+        assert_eq!(response.error_code, Some("READ_ONLY"));
+
+        // A dry run writes nothing, so replicas may preview imports
+        let response = handle_import_timesheet(&replica_state, dry_run).await;
+        assert_eq!(response.data.unwrap()["dry_run"], true);
+        assert!(replica_state.kernel.ledger().is_empty().await);
+
+        let report = handle_generate_report(&replica_state, GenerateReportRequest {
+            tenant_id: "acme".to_string(),
+            year: 2025,
+            format: ReportFormat::Json,
+            output_path: None,
+        }).await;
+        assert!(report.success);
+    }
+
+    #[tokio::test]
+    async fn test_employee_accruals_tenant_isolation() {
+        let state = test_state(AppConfig::default());
+        let tenants = state.kernel.tenants();
+        tenants.register("acme").await.unwrap();
+        tenants.register("globex").await.unwrap();
+        tenants.add_employee("acme", "emp1").await.unwrap();
+
+        let own = EmployeeAccrualQuery {
+            tenant_id: "acme".to_string(),
+            employee_id: "emp1".to_string(),
+        };
+        assert!(handle_view_accruals(&state, own).await.success);
+
+        let other = EmployeeAccrualQuery {
+            tenant_id: "globex".to_string(),
+            employee_id: "emp1".to_string(),
+        };
+        assert!(!handle_view_accruals(&state, other).await.success);
+
+        let roster = handle_get_accruals(&state, "acme".to_string(), PageRequest::default()).await;
+        assert_eq!(roster.data.unwrap()["employees"], serde_json::json!(["emp1"]));
+        assert!(!handle_get_accruals(&state, "unknown".to_string(), PageRequest::default()).await.success);
+
+        // Paged by employee ID
+        tenants.add_employee("acme", "emp0").await.unwrap();
+        let first = handle_get_accruals(&state, "acme".to_string(), PageRequest::first(1)).await.data.unwrap();
+        assert_eq!(first["employees"], serde_json::json!(["emp0"]));
+        assert_eq!(first["next_cursor"], "emp0");
+        let second = handle_get_accruals(&state, "acme".to_string(), PageRequest::after("emp0", 1)).await.data.unwrap();
+        assert_eq!(second["employees"], serde_json::json!(["emp1"]));
+        assert!(second["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_usage_insights_require_opt_in() {
+        let state = test_state(AppConfig::default());
+        let policy = TenantPolicy {
+            tenant_id: "acme".to_string(),
+            employer_size: "large".to_string(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            effective_from: Some("2025-01-01".to_string()),
+            jurisdiction: None,
+            retention_days: None,
+        };
+        assert!(handle_set_policy(&state, policy).await.success);
+
+        let request = |as_of: &str| UsageInsightsRequest {
+            tenant_id: "acme".to_string(),
+            as_of: Some(as_of.to_string()),
+        };
+        let response = handle_usage_insights(&state, request("2025-06-30")).await;
+        assert_eq!(response.error_code, Some("INSIGHTS_DISABLED"));
+
+        let response = handle_usage_insights(&state, request("June 30")).await;
+        assert_eq!(response.error_code, Some("INVALID_DATE"));
+    }
+
+    #[tokio::test]
+    async fn test_module_catalog_commands() {
+        let response = handle_list_available_modules(&test_state(AppConfig::default())).await;
+        assert_eq!(response.error_code, Some("CATALOG_UNAVAILABLE"));
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        std::fs::write(
+            dir.join("accrual.json"),
+            r#"{"name":"accrual","version":"1.0.0","path":"accrual.wasm","checksum":"00","capabilities":[]}"#,
+        ).unwrap();
+
+        let config = AppConfig {
+            modules_dir: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let catalog = config.module_catalog().unwrap().unwrap();
+        let state = AppState {
+            kernel: Kernel::new().unwrap().with_module_catalog(catalog),
+            config,
+        };
+
+        let listed = handle_list_available_modules(&state).await;
+        let module = &listed.data.unwrap()["modules"][0];
+        assert_eq!(module["name"], "accrual");
+        assert_eq!(module["verification"]["status"], "rejected");
+
+        let missing = handle_install_module(&state, InstallModuleRequest {
+            name: "overtime".to_string(),
+            version: None,
+        }).await;
+        assert_eq!(missing.error_code, Some("MODULE_NOT_AVAILABLE"));
+
+        let rollback = handle_rollback_module(&state, RollbackModuleRequest {
+            name: "accrual".to_string(),
+            version: "0.9.0".to_string(),
+        }).await;
+        assert_eq!(rollback.error_code, Some("MODULE_NOT_AVAILABLE"));
     }
 
     #[tokio::test]
@@ -626,8 +5027,45 @@ mod tests {
         let request = LoadModuleRequest {
             manifest_path: "../../../etc/passwd".to_string(),
         };
-        let response = kernel_load_module(request).await.unwrap();
+        let response = handle_load_module(&test_state(AppConfig::default()), request).await;
         assert!(!response.success);
-        assert!(response.error.unwrap().contains("Invalid"));
+        assert_eq!(response.error_code, Some("INVALID_REQUEST"));
+        assert!(response.error_detail.unwrap().contains("Invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_localized() {
+        let config = AppConfig {
+            locale: Locale::Spanish,
+            ..Default::default()
+        };
+        let response = handle_view_accruals(&test_state(config), EmployeeAccrualQuery {
+            tenant_id: "acme".to_string(),
+            employee_id: "emp1".to_string(),
+        }).await;
+        assert_eq!(response.error_code, Some("TENANT_NOT_FOUND"));
+        assert_eq!(response.error.as_deref(), Some("No se encontró la cuenta del empleador."));
+        assert!(response.error_detail.unwrap().contains("acme"));
+
+        let json = serde_json::to_value(KernelResponse::ok(serde_json::json!({}))).unwrap();
+        assert!(json.get("error_code").is_none());
+        assert!(json.get("module_error").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_module_errors_are_propagated() {
+        let state = test_state(AppConfig::default());
+        let error = ModuleError { code: "INVALID_INPUT".to_string(), message: "missing field `minutes_worked`".to_string() };
+        let reported = anyhow::Error::new(KernelError::ModuleReported { function: "accrue_json".to_string(), error: error.clone() })
+            .context("Legacy request 'accrue' failed");
+
+        let response = state.kernel_error_response(&reported);
+        assert_eq!(response.error_code, Some("INPUT_REJECTED"));
+        assert_eq!(response.module_error, Some(error));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["module_error"]["code"], "INVALID_INPUT");
+
+        let other = state.kernel_error_response(&anyhow::Error::new(KernelError::ShuttingDown));
+        assert!(other.module_error.is_none());
     }
 }
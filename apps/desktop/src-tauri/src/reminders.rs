@@ -0,0 +1,274 @@
+//! Compliance Reminders
+//!
+//! Employers have recurring obligations (annual carryover processing,
+//! quarterly policy reviews) that are easy to miss. Reminders are registered
+//! with a first due date and a recurrence, and are kept in a JSON file so they
+//! survive restarts. The application checks for due reminders at startup and
+//! every hour and shows each as an OS notification.
+//!
+//! A reminder that came due while the application was closed fires once when
+//! it next runs, then moves to its next future occurrence; missed repeats are
+//! not replayed one by one. One-time reminders are removed once they fire.
+
+use anyhow::{bail, Context, Result};
+use esta_kernel::Date;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Reminder file format version
+const REMINDERS_VERSION: u32 = 1;
+
+/// Longest accepted reminder title
+const MAX_TITLE_CHARS: usize = 200;
+
+/// How often a reminder repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Once,
+    Weekly,
+    /// On the first due date's day of the month (the last day in shorter months)
+    Monthly,
+    /// On the first due date's month and day (February 28 in non-leap years for February 29)
+    Yearly,
+}
+
+/// A reminder to register
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewReminder {
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    /// Tenant the obligation belongs to, if any
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    pub recurrence: Recurrence,
+    pub first_due: Date,
+}
+
+impl NewReminder {
+    /// Check the reminder can be registered
+    pub fn validate(&self) -> Result<()> {
+        let title = self.title.trim();
+        if title.is_empty() || title.chars().count() > MAX_TITLE_CHARS {
+            bail!("Reminder title must be 1 to {} characters", MAX_TITLE_CHARS);
+        }
+        Ok(())
+    }
+}
+
+/// A registered reminder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: u64,
+    pub title: String,
+    pub body: String,
+    pub tenant_id: Option<String>,
+    pub recurrence: Recurrence,
+    pub first_due: Date,
+    /// Next date the reminder fires
+    pub next_due: Date,
+}
+
+impl Reminder {
+    /// The first occurrence after `date`, or `None` for a one-time reminder
+    fn occurrence_after(&self, date: Date) -> Option<Date> {
+        let mut next = self.next_due;
+        while next <= date {
+            next = match self.recurrence {
+                Recurrence::Once => return None,
+                Recurrence::Weekly => next.add_days(7),
+                Recurrence::Monthly => {
+                    let (year, month) = if next.month() == 12 { (next.year() + 1, 1) } else { (next.year(), next.month() + 1) };
+                    clamped_date(year, month, self.first_due.day())
+                }
+                Recurrence::Yearly => clamped_date(next.year() + 1, self.first_due.month(), self.first_due.day()),
+            };
+        }
+        Some(next)
+    }
+}
+
+/// The given day of a month, or the month's last day if it is shorter
+fn clamped_date(year: i32, month: u8, day: u8) -> Date {
+    (28..=day.max(28))
+        .rev()
+        .find_map(|day| Date::new(year, month, day).ok())
+        .expect("every month has 28 days")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReminderFile {
+    version: u32,
+    next_id: u64,
+    reminders: Vec<Reminder>,
+}
+
+/// Registered reminders, saved to a file after every change
+pub struct ReminderStore {
+    path: Option<PathBuf>,
+    file: Mutex<ReminderFile>,
+}
+
+impl ReminderStore {
+    /// Reminders kept in memory only
+    pub fn in_memory() -> Self {
+        Self { path: None, file: Mutex::new(ReminderFile { version: REMINDERS_VERSION, next_id: 1, reminders: Vec::new() }) }
+    }
+
+    /// Open a reminder file, starting empty if it does not exist
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = if path.exists() {
+            let bytes = std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+            let file: ReminderFile =
+                serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))?;
+            if file.version != REMINDERS_VERSION {
+                bail!("Unsupported reminder file version {}", file.version);
+            }
+            file
+        } else {
+            ReminderFile { version: REMINDERS_VERSION, next_id: 1, reminders: Vec::new() }
+        };
+        Ok(Self { path: Some(path), file: Mutex::new(file) })
+    }
+
+    /// Every registered reminder, soonest first
+    pub fn list(&self) -> Vec<Reminder> {
+        let mut reminders = self.lock().reminders.clone();
+        reminders.sort_by_key(|r| (r.next_due, r.id));
+        reminders
+    }
+
+    /// Register a reminder
+    pub fn add(&self, new: NewReminder) -> Result<Reminder> {
+        new.validate()?;
+        let mut file = self.lock();
+        let reminder = Reminder {
+            id: file.next_id,
+            title: new.title.trim().to_string(),
+            body: new.body,
+            tenant_id: new.tenant_id,
+            recurrence: new.recurrence,
+            first_due: new.first_due,
+            next_due: new.first_due,
+        };
+        file.next_id += 1;
+        file.reminders.push(reminder.clone());
+        self.save(&file)?;
+        Ok(reminder)
+    }
+
+    /// Remove a reminder; returns whether it existed
+    pub fn remove(&self, id: u64) -> Result<bool> {
+        let mut file = self.lock();
+        let before = file.reminders.len();
+        file.reminders.retain(|r| r.id != id);
+        if file.reminders.len() == before {
+            return Ok(false);
+        }
+        self.save(&file)?;
+        Ok(true)
+    }
+
+    /// Remove every reminder of a tenant; returns how many were removed
+    pub fn remove_tenant(&self, tenant_id: &str) -> Result<usize> {
+        let mut file = self.lock();
+        let before = file.reminders.len();
+        file.reminders.retain(|r| r.tenant_id.as_deref() != Some(tenant_id));
+        let removed = before - file.reminders.len();
+        if removed > 0 {
+            self.save(&file)?;
+        }
+        Ok(removed)
+    }
+
+    /// Reminders due on or before `today`, moved on to their next occurrence
+    ///
+    /// Returns the reminders as they were when they came due.
+    pub fn take_due(&self, today: Date) -> Result<Vec<Reminder>> {
+        let mut file = self.lock();
+        let due: Vec<Reminder> = file.reminders.iter().filter(|r| r.next_due <= today).cloned().collect();
+        if due.is_empty() {
+            return Ok(due);
+        }
+        file.reminders.retain_mut(|reminder| match reminder.occurrence_after(today) {
+            Some(next) => {
+                reminder.next_due = next;
+                true
+            }
+            None => reminder.next_due > today,
+        });
+        self.save(&file)?;
+        Ok(due)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ReminderFile> {
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, file: &ReminderFile) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(file)?)?;
+        std::fs::rename(&tmp, path).with_context(|| format!("saving {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    fn new_reminder(title: &str, recurrence: Recurrence, first_due: &str) -> NewReminder {
+        NewReminder {
+            title: title.to_string(),
+            body: String::new(),
+            tenant_id: Some("acme".to_string()),
+            recurrence,
+            first_due: date(first_due),
+        }
+    }
+
+    #[test]
+    fn test_recurring_reminders_persist_and_advance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("reminders.json");
+        let store = ReminderStore::open(&path).unwrap();
+        let carryover = store.add(new_reminder("Annual carryover processing due", Recurrence::Yearly, "2024-02-29")).unwrap();
+        store.add(new_reminder("Month-end review", Recurrence::Monthly, "2025-01-31")).unwrap();
+        store.add(new_reminder("Post updated policy", Recurrence::Once, "2025-03-01")).unwrap();
+        assert!(store.add(new_reminder("  ", Recurrence::Once, "2025-03-01")).is_err());
+
+        // Missed repeats fire once, then the reminder moves to its next future date
+        let due = store.take_due(date("2025-02-28")).unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0], carryover);
+        assert!(store.take_due(date("2025-02-28")).unwrap().is_empty());
+
+        // Saved across restarts; February 29 falls back to the 28th, the 31st to the month's end
+        let reopened = ReminderStore::open(&path).unwrap();
+        let next: Vec<String> = reopened.list().iter().map(|r| r.next_due.to_string()).collect();
+        assert_eq!(next, ["2025-03-01", "2025-03-31", "2026-02-28"]);
+
+        // One-time reminders are dropped once they fire
+        assert_eq!(reopened.take_due(date("2025-03-01")).unwrap()[0].title, "Post updated policy");
+        assert_eq!(reopened.list().len(), 2);
+        assert!(reopened.remove(carryover.id).unwrap());
+        assert!(!reopened.remove(carryover.id).unwrap());
+        assert_eq!(ReminderStore::open(&path).unwrap().list().len(), 1);
+        assert_eq!(reopened.remove_tenant("globex").unwrap(), 0);
+        assert_eq!(reopened.remove_tenant("acme").unwrap(), 1);
+        assert!(ReminderStore::open(&path).unwrap().list().is_empty());
+    }
+}
@@ -0,0 +1,448 @@
+//! Sessions and Roles
+//!
+//! Commands are checked against the role of the signed-in user before they
+//! run. Accounts are kept in a JSON file with PBKDF2 password hashes;
+//! signing in establishes a session for the application window until sign
+//! out.
+//!
+//! Roles:
+//! - `employer-admin`: every command, including policy, modules, and keys
+//! - `manager`: reports, accruals, and reminders for their tenant's employees
+//! - `employee`: status, statutes, and their own accruals only
+//!
+//! Managers and employees are also confined to their own tenant: commands
+//! naming a tenant check it with [`SessionStore::authorize_tenant`], and
+//! events about a tenant are only emitted to users who may see it.
+//!
+//! Until the first account is created the shell runs as a single local user
+//! with every permission, as it did before accounts existed; the production
+//! security profile refuses this and requires an account. The first account
+//! must be an employer admin.
+
+use anyhow::{bail, Context, Result};
+use esta_kernel::ErrorCode;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Account file format version
+const ACCOUNTS_VERSION: u32 = 1;
+
+/// PBKDF2-HMAC-SHA256 iterations for new password hashes
+const PBKDF2_ITERATIONS: u32 = 210_000;
+
+/// Shortest accepted password
+const MIN_PASSWORD_CHARS: usize = 8;
+
+/// Role recorded for callers while no accounts exist
+pub const LOCAL_ROLE: &str = "local";
+
+/// Role recorded for callers who have not signed in
+pub const ANONYMOUS_ROLE: &str = "anonymous";
+
+/// What a signed-in user may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    EmployerAdmin,
+    Manager,
+    Employee,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::EmployerAdmin => "employer-admin",
+            Role::Manager => "manager",
+            Role::Employee => "employee",
+        }
+    }
+}
+
+const ADMIN: &[Role] = &[Role::EmployerAdmin];
+const STAFF: &[Role] = &[Role::EmployerAdmin, Role::Manager];
+const EVERYONE: &[Role] = &[Role::EmployerAdmin, Role::Manager, Role::Employee];
+
+/// Roles allowed to run a command; `None` for commands open to everyone,
+/// signed in or not
+///
+/// Commands missing from this table are admin-only.
+pub fn required_roles(command: &str) -> Option<&'static [Role]> {
+    match command {
+        "session_login" | "session_logout" | "session_current" | "session_add_account" => None,
+        "kernel_get_status" | "statute_get_parameters" | "employee_view_accruals" => Some(EVERYONE),
+        "invoke_kernel"
+        | "kernel_list_available_modules"
+        | "storage_usage_report"
+        | "supervisor_get_status"
+        | "tenant_get_policy_history"
+        | "tenant_get_accruals"
+        | "tenant_usage_insights"
+        | "generate_compliance_report"
+        | "generate_custom_report"
+        | "report_template_list"
+        | "reminder_list" => Some(STAFF),
+        _ => Some(ADMIN),
+    }
+}
+
+/// Why a command was refused
+#[derive(Debug, Clone, PartialEq)]
+pub struct Denied {
+    pub code: ErrorCode,
+    pub detail: String,
+}
+
+impl Denied {
+    fn not_signed_in(command: &str) -> Self {
+        Self { code: ErrorCode::NotSignedIn, detail: format!("{} requires a signed-in user", command) }
+    }
+
+    fn permission(detail: String) -> Self {
+        Self { code: ErrorCode::PermissionDenied, detail }
+    }
+}
+
+/// An account to create
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAccount {
+    pub username: String,
+    pub password: String,
+    pub role: Role,
+    /// Tenant a manager or employee belongs to
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+    /// The employee's own ID (employees only)
+    #[serde(default)]
+    pub employee_id: Option<String>,
+}
+
+impl NewAccount {
+    /// Check the account can be created
+    pub fn validate(&self) -> Result<()> {
+        let username = self.username.trim();
+        if username.is_empty() || username.len() > 64 {
+            bail!("User name must be 1 to 64 characters");
+        }
+        if self.password.chars().count() < MIN_PASSWORD_CHARS {
+            bail!("Password must be at least {} characters", MIN_PASSWORD_CHARS);
+        }
+        match self.role {
+            Role::Employee if self.tenant_id.is_none() || self.employee_id.is_none() => {
+                bail!("Employee accounts need a tenant_id and employee_id")
+            }
+            Role::Manager if self.tenant_id.is_none() => bail!("Manager accounts need a tenant_id"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A stored account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Account {
+    username: String,
+    role: Role,
+    tenant_id: Option<String>,
+    employee_id: Option<String>,
+    /// Hex-encoded salt
+    salt: String,
+    iterations: u32,
+    /// Hex-encoded PBKDF2-HMAC-SHA256 output
+    password_hash: String,
+}
+
+impl Account {
+    fn verify(&self, password: &str) -> bool {
+        let (Ok(salt), Ok(hash), Some(iterations)) =
+            (hex::decode(&self.salt), hex::decode(&self.password_hash), NonZeroU32::new(self.iterations))
+        else {
+            return false;
+        };
+        pbkdf2::verify(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, password.as_bytes(), &hash).is_ok()
+    }
+}
+
+/// The signed-in user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Session {
+    pub username: String,
+    pub role: Role,
+    pub tenant_id: Option<String>,
+    pub employee_id: Option<String>,
+    /// When the user signed in (ms since Unix epoch)
+    pub signed_in_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountFile {
+    version: u32,
+    accounts: Vec<Account>,
+}
+
+/// Accounts and the current session
+pub struct SessionStore {
+    path: Option<PathBuf>,
+    /// Whether the shell may run as the local user while no accounts exist
+    allow_local: bool,
+    accounts: Mutex<AccountFile>,
+    current: Mutex<Option<Session>>,
+}
+
+impl SessionStore {
+    /// Accounts kept in memory only
+    pub fn in_memory(allow_local: bool) -> Self {
+        Self {
+            path: None,
+            allow_local,
+            accounts: Mutex::new(AccountFile { version: ACCOUNTS_VERSION, accounts: Vec::new() }),
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Open an account file, starting with no accounts if it does not exist
+    pub fn open(path: impl Into<PathBuf>, allow_local: bool) -> Result<Self> {
+        let path = path.into();
+        let file = if path.exists() {
+            let bytes = std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+            let file: AccountFile = serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))?;
+            if file.version != ACCOUNTS_VERSION {
+                bail!("Unsupported account file version {}", file.version);
+            }
+            file
+        } else {
+            AccountFile { version: ACCOUNTS_VERSION, accounts: Vec::new() }
+        };
+        Ok(Self { path: Some(path), allow_local, accounts: Mutex::new(file), current: Mutex::new(None) })
+    }
+
+    /// Whether any account has been created
+    pub fn has_accounts(&self) -> bool {
+        !self.accounts().accounts.is_empty()
+    }
+
+    /// Create an account
+    ///
+    /// Only an employer admin may create accounts, except the first, which
+    /// anyone may create and which must be an employer admin.
+    pub fn add_account(&self, new: NewAccount) -> Result<(), Denied> {
+        let mut file = self.accounts();
+        if file.accounts.is_empty() {
+            if new.role != Role::EmployerAdmin {
+                return Err(Denied::permission("the first account must be an employer admin".to_string()));
+            }
+        } else {
+            self.require_admin()?;
+        }
+        let invalid = |e: anyhow::Error| Denied { code: ErrorCode::InvalidRequest, detail: format!("{:#}", e) };
+        new.validate().map_err(invalid)?;
+        let username = new.username.trim().to_string();
+        if file.accounts.iter().any(|a| a.username == username) {
+            return Err(invalid(anyhow::anyhow!("User {} already exists", username)));
+        }
+
+        let mut salt = [0u8; 16];
+        SystemRandom::new().fill(&mut salt).map_err(|_| invalid(anyhow::anyhow!("System RNG failed")))?;
+        let mut hash = [0u8; 32];
+        let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations are nonzero");
+        pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, new.password.as_bytes(), &mut hash);
+        file.accounts.push(Account {
+            username,
+            role: new.role,
+            tenant_id: new.tenant_id,
+            employee_id: new.employee_id,
+            salt: hex::encode(salt),
+            iterations: PBKDF2_ITERATIONS,
+            password_hash: hex::encode(hash),
+        });
+        self.save(&file).map_err(|e| Denied { code: ErrorCode::StorageUnavailable, detail: format!("{:#}", e) })
+    }
+
+    /// Sign in, replacing any current session
+    pub fn login(&self, username: &str, password: &str) -> Result<Session, Denied> {
+        let file = self.accounts();
+        let account = file.accounts.iter().find(|a| a.username == username.trim());
+        // Unknown users and wrong passwords are indistinguishable to the caller
+        let Some(account) = account.filter(|a| a.verify(password)) else {
+            return Err(Denied { code: ErrorCode::NotSignedIn, detail: "invalid user name or password".to_string() });
+        };
+        let session = Session {
+            username: account.username.clone(),
+            role: account.role,
+            tenant_id: account.tenant_id.clone(),
+            employee_id: account.employee_id.clone(),
+            signed_in_at: esta_kernel::clock::now_millis(),
+        };
+        *self.lock_current() = Some(session.clone());
+        Ok(session)
+    }
+
+    /// Sign out; returns whether someone was signed in
+    pub fn logout(&self) -> bool {
+        self.lock_current().take().is_some()
+    }
+
+    /// The signed-in user, if any
+    pub fn current(&self) -> Option<Session> {
+        self.lock_current().clone()
+    }
+
+    /// Check the caller may run `command`, returning the role to record for the call
+    pub fn authorize(&self, command: &str) -> Result<&'static str, Denied> {
+        let Some(allowed) = required_roles(command) else {
+            return Ok(self.current().map_or(ANONYMOUS_ROLE, |s| s.role.as_str()));
+        };
+        if !self.has_accounts() && self.allow_local {
+            return Ok(LOCAL_ROLE);
+        }
+        let session = self.current().ok_or_else(|| Denied::not_signed_in(command))?;
+        if !allowed.contains(&session.role) {
+            return Err(Denied::permission(format!("{} is not allowed for role {}", command, session.role.as_str())));
+        }
+        Ok(session.role.as_str())
+    }
+
+    fn require_admin(&self) -> Result<(), Denied> {
+        match self.current() {
+            Some(session) if session.role == Role::EmployerAdmin => Ok(()),
+            Some(session) => Err(Denied::permission(format!("{} may not create accounts", session.username))),
+            None => Err(Denied::not_signed_in("session_add_account")),
+        }
+    }
+
+    /// Check the caller may act on `tenant_id`
+    ///
+    /// Managers and employees are confined to their own tenant; admins and
+    /// the local user may act on any. Roles are checked by [`Self::authorize`].
+    pub fn authorize_tenant(&self, tenant_id: &str) -> Result<(), Denied> {
+        match self.current() {
+            Some(session) if session.role != Role::EmployerAdmin && session.tenant_id.as_deref() != Some(tenant_id) => {
+                Err(Denied::permission(format!("{} may not act on tenant {}", session.username, tenant_id)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the caller may see one employee's records
+    ///
+    /// Employees may only see their own, managers their own tenant's.
+    pub fn authorize_employee(&self, tenant_id: &str, employee_id: &str) -> Result<(), Denied> {
+        self.authorize_tenant(tenant_id)?;
+        match self.current() {
+            Some(session) if session.role == Role::Employee && session.employee_id.as_deref() != Some(employee_id) => {
+                Err(Denied::permission(format!("{} may only view their own accruals", session.username)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the signed-in user may be sent events about `tenant_id`
+    ///
+    /// Admins (and the local user) get every event, managers their own
+    /// tenant's, and employees none; events about no tenant go to admins only.
+    pub fn may_receive(&self, tenant_id: Option<&str>) -> bool {
+        if !self.has_accounts() && self.allow_local {
+            return true;
+        }
+        match (self.current(), tenant_id) {
+            (Some(session), _) if session.role == Role::EmployerAdmin => true,
+            (Some(session), Some(tenant_id)) if session.role == Role::Manager => session.tenant_id.as_deref() == Some(tenant_id),
+            _ => false,
+        }
+    }
+
+    fn accounts(&self) -> std::sync::MutexGuard<'_, AccountFile> {
+        self.accounts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_current(&self) -> std::sync::MutexGuard<'_, Option<Session>> {
+        self.current.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, file: &AccountFile) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(file)?)?;
+        std::fs::rename(&tmp, path).with_context(|| format!("saving {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(username: &str, role: Role, employee_id: Option<&str>) -> NewAccount {
+        NewAccount {
+            username: username.to_string(),
+            password: "correct horse".to_string(),
+            role,
+            tenant_id: Some("acme".to_string()),
+            employee_id: employee_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_managers_are_confined_to_their_tenant() {
+        let store = SessionStore::in_memory(false);
+        store.add_account(account("admin", Role::EmployerAdmin, None)).unwrap();
+        store.login("admin", "correct horse").unwrap();
+        store.add_account(account("morgan", Role::Manager, None)).unwrap();
+        assert!(store.authorize_tenant("globex").is_ok());
+        assert!(store.may_receive(Some("globex")) && store.may_receive(None));
+        store.logout();
+        assert!(!store.may_receive(Some("acme")));
+
+        store.login("morgan", "correct horse").unwrap();
+        assert_eq!(store.authorize("generate_compliance_report"), Ok("manager"));
+        assert!(store.authorize_tenant("acme").is_ok());
+        assert!(store.authorize_employee("acme", "e1").is_ok());
+        assert_eq!(store.authorize_tenant("globex").unwrap_err().code, ErrorCode::PermissionDenied);
+        assert_eq!(store.authorize_employee("globex", "e1").unwrap_err().code, ErrorCode::PermissionDenied);
+        assert!(store.may_receive(Some("acme")));
+        assert!(!store.may_receive(Some("globex")) && !store.may_receive(None));
+    }
+
+    #[test]
+    fn test_roles_gate_commands() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("accounts.json");
+        let store = SessionStore::open(&path, true).unwrap();
+
+        // Open to the local user until the first account exists, which must be an admin
+        assert_eq!(store.authorize("tenant_set_policy"), Ok(LOCAL_ROLE));
+        assert_eq!(store.add_account(account("pat", Role::Employee, Some("e1"))).unwrap_err().code, ErrorCode::PermissionDenied);
+        store.add_account(account("admin", Role::EmployerAdmin, None)).unwrap();
+        assert_eq!(store.authorize("tenant_set_policy").unwrap_err().code, ErrorCode::NotSignedIn);
+        assert_eq!(store.add_account(account("pat", Role::Employee, Some("e1"))).unwrap_err().code, ErrorCode::NotSignedIn);
+
+        assert_eq!(store.login("admin", "wrong password").unwrap_err().code, ErrorCode::NotSignedIn);
+        store.login("admin", "correct horse").unwrap();
+        store.add_account(account("pat", Role::Employee, Some("e1"))).unwrap();
+        assert!(store.add_account(account("pat", Role::Employee, Some("e1"))).is_err());
+        assert_eq!(store.authorize("tenant_set_policy"), Ok("employer-admin"));
+        assert!(store.logout());
+
+        // Accounts persist; employees see only their own accruals
+        let reopened = SessionStore::open(&path, true).unwrap();
+        assert_eq!(reopened.login("pat", "correct horse").unwrap().role, Role::Employee);
+        assert_eq!(reopened.authorize("employee_view_accruals"), Ok("employee"));
+        assert_eq!(reopened.authorize("tenant_get_accruals").unwrap_err().code, ErrorCode::PermissionDenied);
+        assert!(reopened.authorize_employee("acme", "e1").is_ok());
+        assert_eq!(reopened.authorize_employee("acme", "e2").unwrap_err().code, ErrorCode::PermissionDenied);
+        assert_eq!(reopened.authorize_employee("globex", "e1").unwrap_err().code, ErrorCode::PermissionDenied);
+        assert!(!reopened.may_receive(Some("acme")));
+
+        // Without local mode, an empty account file still requires sign in
+        let strict = SessionStore::in_memory(false);
+        assert_eq!(strict.authorize("kernel_get_status").unwrap_err().code, ErrorCode::NotSignedIn);
+        assert_eq!(strict.authorize("session_login"), Ok(ANONYMOUS_ROLE));
+    }
+}
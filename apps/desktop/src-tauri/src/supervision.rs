@@ -0,0 +1,116 @@
+//! Module Supervision
+//!
+//! The accrual module runs as a child of a supervisor. When the supervisor
+//! decides to restart it (after a hang, say), the request is passed to a
+//! task that relaunches the module in the kernel and reports it started.
+//! Crash histories are kept in `ESTA_SUPERVISOR_FILE` (default
+//! `supervisor.json` in the data directory), so a module that was
+//! crash-looping resumes at its escalation level when the app is reopened.
+//!
+//! Every state transition is emitted as `supervisor://event` with a
+//! [`ChildTransition`] payload. If the stream falls behind it emits
+//! `supervisor://lagged`, and the view should refresh with
+//! `supervisor_get_status`.
+
+use esta_kernel::{ChildTransition, Supervisor};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::Manager;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+
+/// Event carrying a child state transition
+pub const SUPERVISOR_EVENT: &str = "supervisor://event";
+
+/// Event reporting transitions the stream skipped
+pub const SUPERVISOR_LAGGED_EVENT: &str = "supervisor://lagged";
+
+/// Restarts waiting for the relaunch task
+const RELAUNCH_QUEUE: usize = 16;
+
+/// A child the supervisor asked to be started again: (child ID, manifest path)
+pub type Relaunch = (String, String);
+
+/// Supervisor whose restarts are queued for [`relaunch_children`], keeping
+/// crash histories in `history` if given
+pub fn supervisor(history: Option<PathBuf>) -> anyhow::Result<(Arc<Supervisor>, mpsc::Receiver<Relaunch>)> {
+    let (sender, receiver) = mpsc::channel(RELAUNCH_QUEUE);
+    let mut supervisor = Supervisor::new(move |id, manifest_path, _| {
+        sender
+            .try_send((id.to_string(), manifest_path.to_string()))
+            .map_err(|e| anyhow::anyhow!("cannot queue restart of {}: {}", id, e))
+    });
+    if let Some(path) = history {
+        supervisor = supervisor.with_history_file(path)?;
+    }
+    Ok((Arc::new(supervisor), receiver))
+}
+
+/// Relaunch children as the supervisor requests, reporting the outcome back
+pub async fn relaunch_children(
+    app: tauri::AppHandle,
+    supervisor: Arc<Supervisor>,
+    mut requests: mpsc::Receiver<Relaunch>,
+) {
+    while let Some((id, manifest_path)) = requests.recv().await {
+        let launched = app.state::<crate::AppState>().kernel.launch_module(&manifest_path).await;
+        let reported = match launched {
+            Ok(()) => supervisor.report_started(&id).await,
+            Err(e) => {
+                log::error!("Failed to relaunch {} from {}: {}", id, manifest_path, e);
+                match supervisor.report_crash(&id, &e.to_string()).await {
+                    Ok(action) => {
+                        let supervisor = supervisor.clone();
+                        tauri::async_runtime::spawn(async move { supervisor.execute_restart(&id, action).await });
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(e) = reported {
+            log::error!("Failed to report relaunch to the supervisor: {}", e);
+        }
+    }
+}
+
+/// Emit child state transitions to the webview until the supervisor is dropped
+pub async fn forward_transitions(app: tauri::AppHandle, mut transitions: broadcast::Receiver<ChildTransition>) {
+    loop {
+        let result = match transitions.recv().await {
+            Ok(transition) => app.emit_all(SUPERVISOR_EVENT, transition),
+            Err(RecvError::Lagged(missed)) => {
+                log::warn!("Supervisor event stream skipped {} transitions", missed);
+                app.emit_all(SUPERVISOR_LAGGED_EVENT, serde_json::json!({ "missed": missed }))
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if let Err(e) = result {
+            log::error!("Failed to emit supervisor event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use esta_kernel::{ChildSpec, ChildState};
+
+    #[tokio::test]
+    async fn test_restarts_are_queued_for_relaunch() {
+        let (supervisor, mut requests) = supervisor(None).unwrap();
+        let spec = ChildSpec {
+            id: "accrual".into(),
+            manifest_path: "accrual.json".into(),
+            base_restart_delay_ms: 0,
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("accrual").await.unwrap();
+
+        let action = supervisor.report_crash("accrual", "hung").await.unwrap();
+        supervisor.execute_restart("accrual", action).await.unwrap();
+        assert_eq!(requests.recv().await, Some(("accrual".to_string(), "accrual.json".to_string())));
+        assert_eq!(supervisor.get_child_status("accrual").await.unwrap().state, ChildState::Starting);
+    }
+}
@@ -39,10 +39,75 @@ export interface KernelRequest {
   payload: Record<string, unknown>;
 }
 
+/**
+ * Stable error codes sent by the kernel (see esta_kernel::user_errors).
+ * Codes never change once released; branch on these, not on messages.
+ */
+export type KernelErrorCode =
+  | 'MODULE_NOT_LOADED'
+  | 'MODULE_NOT_AVAILABLE'
+  | 'CATALOG_UNAVAILABLE'
+  | 'MODULE_INTEGRITY'
+  | 'SIGNATURE_REQUIRED'
+  | 'SIGNATURE_INVALID'
+  | 'SIGNATURE_CONFIG'
+  | 'PROFILE_RESTRICTED'
+  | 'MODULE_INCOMPATIBLE'
+  | 'MODULE_CRASHED'
+  | 'RESOURCE_LIMIT'
+  | 'BUSY'
+  | 'FUEL_CEILING'
+  | 'SHUTTING_DOWN'
+  | 'CANCELLED'
+  | 'INPUT_TOO_LARGE'
+  | 'INPUT_REJECTED'
+  | 'CAPABILITY_DENIED'
+  | 'CAPABILITY_EXPIRED'
+  | 'SECRETS_LOCKED'
+  | 'INSIGHTS_DISABLED'
+  | 'TENANT_ISOLATION'
+  | 'TENANT_NOT_FOUND'
+  | 'TENANT_EXISTS'
+  | 'TENANT_ARCHIVED'
+  | 'TENANT_ACTIVE'
+  | 'EMPLOYEE_NOT_FOUND'
+  | 'INVALID_TENANT_ID'
+  | 'INVALID_POLICY'
+  | 'INVALID_TEMPLATE'
+  | 'TEMPLATE_NOT_FOUND'
+  | 'STATUTE_UNAVAILABLE'
+  | 'INVALID_DATE'
+  | 'INVALID_REQUEST'
+  | 'READ_ONLY'
+  | 'NOT_SIGNED_IN'
+  | 'PERMISSION_DENIED'
+  | 'STORAGE_CORRUPT'
+  | 'STORAGE_UNAVAILABLE'
+  | 'AUDIT_UNAVAILABLE'
+  | 'INTERNAL';
+
+/** Error a WASM module reported instead of a result */
+export interface ModuleError {
+  /** Module-defined code, e.g. `INVALID_INPUT`, `INPUT_TOO_LARGE`, `INTERNAL` */
+  code: string;
+  message: string;
+}
+
 export interface KernelResponse<T = unknown> {
   success: boolean;
   data: T | null;
+  /** Localized, non-technical message for users */
   error: string | null;
+  /** Stable error code, present on failures */
+  error_code?: KernelErrorCode;
+  /** Suggested next step for users */
+  remediation?: string;
+  /** Technical error text for logs and support */
+  error_detail?: string;
+  /** Error the module reported in its response envelope */
+  module_error?: ModuleError;
+  /** Correlation ID of the request, shared by the audit entries it caused */
+  correlation_id?: string;
 }
 
 export interface KernelStatus {
@@ -53,11 +118,26 @@ export interface KernelStatus {
     max_fuel: number;
     max_memory_bytes: number;
     require_signatures: boolean;
+    security_profile: 'development' | 'staging' | 'production';
+    /** Requirements of the security profile the kernel does not meet */
+    profile_violations: string[];
   };
   audit: {
     enabled: boolean;
     entries: number;
+    /** Extent of the persisted audit log, or null when kept in memory */
+    archive: {
+      segments: number;
+      bytes: number;
+      first_sequence: number;
+      last_sequence: number;
+      first_timestamp: number;
+      last_timestamp: number;
+    } | null;
+    health: AuditHealth;
   };
+  /** Last heartbeat (Unix millis) of each resident module that sends them */
+  heartbeats: { module: string; last_heartbeat: number }[];
 }
 
 export interface AccrualResult {
@@ -82,6 +162,37 @@ export interface AuditLogEntry {
   source: string;
   prev_hash: string;
   hash: string;
+  correlation_id?: string;
+  /** Tenant the entry was written on behalf of */
+  tenant_id?: string;
+}
+
+export interface TenantInfo {
+  id: string;
+  status: 'active' | 'archived';
+  /** Unix millis */
+  created_at: number;
+  archived_at?: number;
+  policies: unknown[];
+  employees: string[];
+}
+
+export interface TenantPurgeReport {
+  tenant_id: string;
+  ledger_events: number;
+  policy_versions: number;
+  employees: number;
+  capabilities_revoked: number;
+}
+
+export type UserRole = 'employer-admin' | 'manager' | 'employee';
+
+export interface UserSession {
+  username: string;
+  role: UserRole;
+  tenant_id: string | null;
+  employee_id: string | null;
+  signed_in_at: number;
 }
 
 export interface TenantPolicy {
@@ -110,6 +221,12 @@ const isTauri = (): boolean => {
   return typeof window !== 'undefined' && '__TAURI__' in window;
 };
 
+// Correlation ID sent with each command; the kernel stamps it on the audit
+// entries the command causes
+const newCorrelationId = (): string =>
+  globalThis.crypto?.randomUUID?.() ??
+  `${Date.now().toString(36)}-${Math.random().toString(36).slice(2)}`;
+
 // Type for Tauri invoke function
 type TauriInvokeFn = <T>(
   cmd: string,
@@ -139,6 +256,47 @@ const getTauriInvoke = async (): Promise<TauriInvokeFn | null> => {
   }
 };
 
+// Type for Tauri event listener registration
+type TauriListenFn = <T>(
+  event: string,
+  handler: (event: { payload: T }) => void
+) => Promise<() => void>;
+
+// Dynamic import of the Tauri event API
+const getTauriListen = async (): Promise<TauriListenFn | null> => {
+  if (!isTauri()) {
+    return null;
+  }
+  try {
+    const modulePath = '@tauri-apps/api/event';
+    const eventModule: { listen: TauriListenFn } = await import(
+      /* webpackIgnore: true */ modulePath
+    );
+    return eventModule.listen;
+  } catch {
+    return null;
+  }
+};
+
+/** Whether audit entries are reaching disk (see `audit://health`) */
+export interface AuditHealth {
+  /** When writes started failing (Unix millis), or null while healthy */
+  failing_since: number | null;
+  last_error: string | null;
+  /** Entries held in memory until writes recover */
+  pending_entries: number;
+  /** Entries lost because the in-memory buffer was full */
+  lost_entries: number;
+  /** Module, key, and policy changes are refused (`AUDIT_UNAVAILABLE`) */
+  blocks_privileged: boolean;
+}
+
+/** Filter for live audit entries; empty lists match everything */
+export interface AuditFilter {
+  sources?: string[];
+  event_types?: string[];
+}
+
 /**
  * ESTA Kernel Client
  *
@@ -170,13 +328,33 @@ export class KernelClient {
     const tauriInvoke = await getTauriInvoke();
 
     if (tauriInvoke) {
+      const correlationId = newCorrelationId();
       try {
-        return await tauriInvoke(command, args);
+        const response = await tauriInvoke<KernelResponse<T>>(command, {
+          ...args,
+          correlationId,
+        });
+        if (!response.success) {
+          logger.warn('Kernel command failed', {
+            command,
+            correlationId,
+            errorCode: response.error_code,
+          });
+        }
+        return response;
       } catch (error) {
+        // Commands report failures in the response; a rejected call means
+        // the IPC layer itself failed
+        const detail = error instanceof Error ? error.message : String(error);
+        logger.error('Kernel command failed', { command, correlationId, detail });
         return {
           success: false,
           data: null,
-          error: error instanceof Error ? error.message : String(error),
+          error: 'Something went wrong.',
+          error_code: 'INTERNAL',
+          remediation: 'Try again. If it keeps happening, contact support.',
+          error_detail: detail,
+          correlation_id: correlationId,
         };
       }
     }
@@ -205,11 +383,22 @@ export class KernelClient {
               max_fuel: 20_000_000,
               max_memory_bytes: 33_554_432,
               require_signatures: false,
+              security_profile: 'development',
+              profile_violations: [],
             },
             audit: {
               enabled: true,
               entries: 0,
+              archive: null,
+              health: {
+                failing_since: null,
+                last_error: null,
+                pending_entries: 0,
+                lost_entries: 0,
+                blocks_privileged: false,
+              },
             },
+            heartbeats: [],
           } as T,
           error: null,
         };
@@ -306,39 +495,203 @@ export class KernelClient {
   }
 
   /**
-   * Execute a function on a loaded module
+   * Execute a function on a loaded module on behalf of a tenant
+   *
+   * The input may not name any other tenant. Pass an `invocationId` to be
+   * able to stop the execution with `cancelExecution`; it then fails with
+   * `CANCELLED`.
    */
   async executeFunction(
+    tenantId: string,
     moduleName: string,
     functionName: string,
-    input: Record<string, unknown>
+    input: Record<string, unknown>,
+    invocationId?: string
   ): Promise<KernelResponse<{ result: unknown; fuel_consumed: number }>> {
     return this.invoke('kernel_execute', {
       request: {
         module: moduleName,
         function: functionName,
         input,
+        tenant_id: tenantId,
+        invocation_id: invocationId,
       },
     });
   }
 
   /**
-   * Get audit log entries
+   * Cancel an execution started with `invocationId`
+   *
+   * `cancelled` is false if it had already finished.
+   */
+  async cancelExecution(
+    invocationId: string
+  ): Promise<KernelResponse<{ invocation_id: string; cancelled: boolean }>> {
+    return this.invoke('kernel_cancel', {
+      request: { invocation_id: invocationId },
+    });
+  }
+
+  /**
+   * Get audit log entries, oldest first, one page at a time
    */
   async getAuditLogs(options?: {
     limit?: number;
+    /** `next_cursor` of the previous page */
+    cursor?: string;
     source?: string;
     afterSequence?: number;
-  }): Promise<KernelResponse<{ entries: AuditLogEntry[]; total: number }>> {
+    fromTimestamp?: number;
+    toTimestamp?: number;
+    /** Only entries caused by the request with this correlation ID */
+    correlationId?: string;
+    /** Only entries written on behalf of this tenant */
+    tenantId?: string;
+  }): Promise<
+    KernelResponse<{
+      entries: AuditLogEntry[];
+      next_cursor: string | null;
+      total: number;
+    }>
+  > {
     return this.invoke('kernel_get_logs', {
       request: {
         limit: options?.limit,
+        cursor: options?.cursor,
         source: options?.source,
         after_sequence: options?.afterSequence,
+        from_timestamp: options?.fromTimestamp,
+        to_timestamp: options?.toTimestamp,
+        correlation_id: options?.correlationId,
+        tenant_id: options?.tenantId,
       },
     });
   }
 
+  /**
+   * Sign in, establishing the role commands are checked against
+   */
+  async login(
+    username: string,
+    password: string
+  ): Promise<KernelResponse<UserSession>> {
+    return this.invoke<UserSession>('session_login', { username, password });
+  }
+
+  /**
+   * Sign out
+   */
+  async logout(): Promise<KernelResponse<{ signed_out: boolean }>> {
+    return this.invoke('session_logout');
+  }
+
+  /**
+   * Get the signed-in user, and whether any accounts exist yet
+   */
+  async getSession(): Promise<
+    KernelResponse<{ session: UserSession | null; accounts_exist: boolean }>
+  > {
+    return this.invoke('session_current');
+  }
+
+  /**
+   * Receive new audit entries matching a filter as they are written
+   *
+   * `onLagged` is called with the number of entries skipped if the stream
+   * fell behind; fetch them with `getAuditLogs`. Resolves to a function that
+   * ends the subscription, or null outside Tauri or if subscribing failed.
+   */
+  async subscribeAuditLog(
+    filter: AuditFilter,
+    onEntry: (entry: AuditLogEntry) => void,
+    onLagged?: (missed: number) => void
+  ): Promise<(() => Promise<void>) | null> {
+    const listen = await getTauriListen();
+    if (!listen) {
+      return null;
+    }
+    const response = await this.invoke<{ subscription_id: number }>(
+      'kernel_audit_subscribe',
+      { filter: { sources: [], event_types: [], ...filter } }
+    );
+    if (!response.success || !response.data) {
+      return null;
+    }
+    const subscriptionId = response.data.subscription_id;
+    const stopEntries = await listen<{
+      subscription_id: number;
+      entry: AuditLogEntry;
+    }>('audit://entry', ({ payload }) => {
+      if (payload.subscription_id === subscriptionId) {
+        onEntry(payload.entry);
+      }
+    });
+    const stopLagged = await listen<{ missed: number }>(
+      'audit://lagged',
+      ({ payload }) => onLagged?.(payload.missed)
+    );
+    return async () => {
+      stopEntries();
+      stopLagged();
+      await this.invoke('kernel_audit_unsubscribe', {
+        subscriptionId,
+      });
+    };
+  }
+
+  /**
+   * Follow audit write health; called on every change
+   *
+   * Resolves to a function that stops listening, or null outside Tauri.
+   */
+  async watchAuditHealth(
+    onChange: (health: AuditHealth) => void
+  ): Promise<(() => void) | null> {
+    const listen = await getTauriListen();
+    if (!listen) {
+      return null;
+    }
+    return listen<AuditHealth>('audit://health', ({ payload }) =>
+      onChange(payload)
+    );
+  }
+
+  /**
+   * Create a tenant with its capability namespace and carryover reminder
+   */
+  async createTenant(tenantId: string): Promise<
+    KernelResponse<{
+      tenant: TenantInfo;
+      root_capability: string;
+      reminders: unknown[];
+    }>
+  > {
+    return this.invoke('tenant_create', { tenant_id: tenantId });
+  }
+
+  /**
+   * Archive a tenant; its records stay readable but it accepts no new work
+   */
+  async archiveTenant(tenantId: string): Promise<
+    KernelResponse<{
+      tenant_id: string;
+      archived: boolean;
+      capabilities_revoked: number;
+      reminders_removed: number;
+    }>
+  > {
+    return this.invoke('tenant_archive', { tenant_id: tenantId });
+  }
+
+  /**
+   * Permanently remove an archived tenant and its ledger events
+   */
+  async purgeTenant(
+    tenantId: string
+  ): Promise<KernelResponse<TenantPurgeReport>> {
+    return this.invoke('tenant_purge', { tenant_id: tenantId });
+  }
+
   /**
    * Set tenant policy
    */
@@ -349,12 +702,22 @@ export class KernelClient {
   }
 
   /**
-   * Get tenant accruals
+   * Get tenant accruals, one page of employees (sorted by ID) at a time
    */
   async getTenantAccruals(
-    tenantId: string
-  ): Promise<KernelResponse<{ employees: EmployeeAccruals[] }>> {
-    return this.invoke('tenant_get_accruals', { tenant_id: tenantId });
+    tenantId: string,
+    page?: { cursor?: string; limit?: number }
+  ): Promise<
+    KernelResponse<{
+      employees: EmployeeAccruals[];
+      next_cursor: string | null;
+    }>
+  > {
+    return this.invoke('tenant_get_accruals', {
+      tenant_id: tenantId,
+      cursor: page?.cursor,
+      limit: page?.limit,
+    });
   }
 
   /**
@@ -0,0 +1,105 @@
+//! Capability validation throughput
+//!
+//! Host functions validate a capability on every call, so validation runs far
+//! more often than anything changes the capability table. This measures it
+//! from concurrent readers, alone and while a writer keeps issuing and
+//! revoking capabilities.
+//!
+//! Run with `cargo bench --bench capability_validation`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use esta_kernel::{CapabilityManager, CapabilityRight, CapabilityToken, ResourceType};
+
+/// Capabilities in the table
+const CAPABILITIES: usize = 1_000;
+/// Validations per reader task
+const VALIDATIONS: usize = 50_000;
+/// Concurrent reader tasks
+const READERS: usize = 8;
+
+async fn table() -> (Arc<CapabilityManager>, Vec<CapabilityToken>) {
+    let manager = Arc::new(CapabilityManager::new(CapabilityManager::generate_secret().unwrap()));
+    let mut tokens = Vec::with_capacity(CAPABILITIES);
+    for i in 0..CAPABILITIES {
+        let token = manager
+            .create_read_only(ResourceType::Module, format!("module-{}", i), format!("owner-{}", i))
+            .await
+            .unwrap();
+        tokens.push(token);
+    }
+    (manager, tokens)
+}
+
+/// Time `READERS` tasks each validating `VALIDATIONS` tokens
+async fn readers(manager: &Arc<CapabilityManager>, tokens: &Arc<Vec<CapabilityToken>>) -> Duration {
+    let start = Instant::now();
+    let tasks: Vec<_> = (0..READERS)
+        .map(|reader| {
+            let manager = manager.clone();
+            let tokens = tokens.clone();
+            tokio::spawn(async move {
+                for i in 0..VALIDATIONS {
+                    let token = &tokens[(reader * 7919 + i) % tokens.len()];
+                    manager.validate(token, &[CapabilityRight::Read]).await.unwrap();
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    start.elapsed()
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let validations = (READERS * VALIDATIONS) as f64;
+    println!(
+        "{:<24} {:>10.0} validations/s {:>8.0} ns/validation",
+        name,
+        validations / elapsed.as_secs_f64(),
+        elapsed.as_nanos() as f64 / validations,
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let (manager, tokens) = table().await;
+        let tokens = Arc::new(tokens);
+
+        // Warm up, then measure readers alone
+        readers(&manager, &tokens).await;
+        report("readers only", readers(&manager, &tokens).await);
+
+        // Measure again with a writer issuing and revoking capabilities throughout
+        let done = Arc::new(AtomicBool::new(false));
+        // On its own thread, so readers that never yield cannot starve it
+        let writer = {
+            let manager = manager.clone();
+            let done = done.clone();
+            let runtime = tokio::runtime::Handle::current();
+            std::thread::spawn(move || {
+                runtime.block_on(async {
+                    let mut writes = 0u64;
+                    while !done.load(Ordering::Relaxed) {
+                        let token = manager
+                            .create_read_only(ResourceType::Module, "churn".into(), "writer".into())
+                            .await
+                            .unwrap();
+                        manager.revoke(&token).await.unwrap();
+                        writes += 2;
+                    }
+                    writes
+                })
+            })
+        };
+        let elapsed = readers(&manager, &tokens).await;
+        done.store(true, Ordering::Relaxed);
+        let writes = writer.join().unwrap();
+        report("readers with a writer", elapsed);
+        println!("{:<24} {:>10.0} writes/s", "writer", writes as f64 / elapsed.as_secs_f64());
+    });
+}
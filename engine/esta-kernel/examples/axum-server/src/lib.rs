@@ -0,0 +1,203 @@
+//! esta-axum-server - Reference HTTP server embedding esta-kernel
+//!
+//! Shows a backend service what the desktop app does with the kernel: build
+//! it with its `with_*` methods over a data directory, load the accrual
+//! module, and expose tenant-scoped calculations over HTTP. It is built and
+//! tested with the kernel crate, so the embedding API it uses stays working.
+//!
+//! The data directory holds:
+//!
+//! - `audit/` - the hash-chained audit log, persisted in segments and resumed
+//!   on restart
+//! - `policies.json` - tenants and their policy history
+//!
+//! Endpoints (JSON in and out):
+//!
+//! - `GET /status` - kernel status, loaded modules, and security profile
+//! - `POST /tenants` `{"tenant_id": ...}` - create a tenant; returns it with
+//!   the root capability of its namespace
+//! - `GET /tenants/{tenant_id}/capabilities` - capabilities the tenant owns
+//! - `POST /tenants/{tenant_id}/accrue` - run the accrual module's
+//!   `accrue_json` for the tenant with the request body as input
+//! - `GET /audit` - a page of audit entries, filtered by the fields of
+//!   `AuditQuery` (`tenant_id`, `after_sequence`, ...) and paged with
+//!   `cursor` and `limit`
+//!
+//! Failures are returned as the kernel's `UserError` (stable `code`,
+//! `message`, `remediation`), localized by `Accept-Language`, with an HTTP
+//! status chosen from the code.
+
+use anyhow::{anyhow, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use esta_kernel::catalog::read_manifest;
+use esta_kernel::security::audit::{AuditEntry, AuditLogConfig};
+use esta_kernel::security::capabilities::Capability;
+use esta_kernel::user_errors::from_anyhow;
+use esta_kernel::{
+    AuditLog, AuditQuery, ExecutionConfig, Kernel, KernelStatus, Locale, Page, PageRequest, PolicyFile,
+    TenantProvisioning, TenantRegistry, UserError,
+};
+use log::info;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Name the accrual module's manifest must give it
+pub const ACCRUAL_MODULE: &str = "accrual";
+
+/// Where the server keeps its data and which module it serves
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub data_dir: PathBuf,
+    /// Manifest of the accrual module; accrual requests fail with
+    /// `MODULE_NOT_LOADED` without one
+    pub accrual_manifest: Option<PathBuf>,
+    /// Hex Ed25519 key modules must be signed with; unsigned modules load when unset
+    pub public_key: Option<String>,
+    pub listen: SocketAddr,
+}
+
+impl ServerConfig {
+    /// Read `ESTA_DATA_DIR` (default `./esta-data`), `ESTA_ACCRUAL_MANIFEST`,
+    /// `ESTA_PUBLIC_KEY`, and `ESTA_LISTEN` (default `127.0.0.1:8080`)
+    pub fn from_env() -> Result<Self> {
+        let listen = std::env::var("ESTA_LISTEN").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
+        Ok(Self {
+            data_dir: std::env::var("ESTA_DATA_DIR").unwrap_or_else(|_| "esta-data".to_string()).into(),
+            accrual_manifest: std::env::var("ESTA_ACCRUAL_MANIFEST").ok().map(PathBuf::from),
+            public_key: std::env::var("ESTA_PUBLIC_KEY").ok(),
+            listen: listen.parse().map_err(|e| anyhow!("invalid ESTA_LISTEN {}: {}", listen, e))?,
+        })
+    }
+}
+
+/// Build the kernel over the data directory and load the accrual module
+pub async fn kernel(config: &ServerConfig) -> Result<Kernel> {
+    let dir = &config.data_dir;
+    std::fs::create_dir_all(dir)?;
+
+    let execution = ExecutionConfig { require_signatures: config.public_key.is_some(), ..ExecutionConfig::default() };
+    let mut kernel = Kernel::with_config(execution)?
+        .with_audit_log(AuditLog::with_segments(AuditLogConfig::default(), dir.join("audit"))?)
+        .with_tenant_registry(TenantRegistry::with_policy_file(PolicyFile::new(dir.join("policies.json")))?);
+    if let Some(key) = &config.public_key {
+        kernel = kernel.with_signature_verifier(key)?;
+    }
+
+    if let Some(path) = &config.accrual_manifest {
+        let manifest = read_manifest(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        if manifest.name != ACCRUAL_MODULE {
+            return Err(anyhow!("{} names module {}, expected {}", path.display(), manifest.name, ACCRUAL_MODULE));
+        }
+        kernel.launch_manifest(manifest).await?;
+        info!("Loaded the accrual module from {}", path.display());
+    }
+    Ok(kernel)
+}
+
+/// The REST API over a kernel
+pub fn router(kernel: Arc<Kernel>) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/tenants", post(create_tenant))
+        .route("/tenants/{tenant_id}/capabilities", get(tenant_capabilities))
+        .route("/tenants/{tenant_id}/accrue", post(accrue))
+        .route("/audit", get(audit))
+        .with_state(kernel)
+}
+
+/// A kernel error as an HTTP response
+pub struct ApiError {
+    status: StatusCode,
+    error: UserError,
+}
+
+impl ApiError {
+    fn new(error: anyhow::Error, headers: &HeaderMap) -> Self {
+        let locale = headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(Locale::English, Locale::from_tag);
+        let error = from_anyhow(&error, locale);
+        Self { status: status_for(error.code), error }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.error)).into_response()
+    }
+}
+
+/// HTTP status for a user error code
+fn status_for(code: &str) -> StatusCode {
+    match code {
+        "MODULE_NOT_LOADED" | "TENANT_NOT_FOUND" | "EMPLOYEE_NOT_FOUND" => StatusCode::NOT_FOUND,
+        "TENANT_EXISTS" | "TENANT_ARCHIVED" => StatusCode::CONFLICT,
+        "INVALID_TENANT_ID" | "INVALID_REQUEST" | "INVALID_DATE" | "INPUT_REJECTED" => StatusCode::BAD_REQUEST,
+        "INPUT_TOO_LARGE" => StatusCode::PAYLOAD_TOO_LARGE,
+        "TENANT_ISOLATION" | "CAPABILITY_DENIED" | "CAPABILITY_EXPIRED" => StatusCode::FORBIDDEN,
+        "BUSY" | "FUEL_CEILING" => StatusCode::TOO_MANY_REQUESTS,
+        "SHUTTING_DOWN" | "READ_ONLY" | "AUDIT_UNAVAILABLE" | "STORAGE_UNAVAILABLE" => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+async fn status(State(kernel): State<Arc<Kernel>>) -> Json<KernelStatus> {
+    Json(kernel.get_status().await)
+}
+
+#[derive(Deserialize)]
+struct NewTenant {
+    tenant_id: String,
+}
+
+async fn create_tenant(
+    State(kernel): State<Arc<Kernel>>,
+    headers: HeaderMap,
+    Json(request): Json<NewTenant>,
+) -> Result<(StatusCode, Json<TenantProvisioning>), ApiError> {
+    let provisioned = kernel.create_tenant(&request.tenant_id).await.map_err(|e| ApiError::new(e, &headers))?;
+    Ok((StatusCode::CREATED, Json(provisioned)))
+}
+
+async fn tenant_capabilities(
+    State(kernel): State<Arc<Kernel>>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<Vec<Capability>>, ApiError> {
+    kernel.tenants().get(&tenant_id).await.map_err(|e| ApiError::new(e.into(), &headers))?;
+    Ok(Json(kernel.capability_manager().list_capabilities(&tenant_id).await))
+}
+
+async fn accrue(
+    State(kernel): State<Arc<Kernel>>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Json(input): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let result = async {
+        let report = kernel
+            .execute_for_tenant(&tenant_id, ACCRUAL_MODULE, "accrue_json", &serde_json::to_vec(&input)?)
+            .await?;
+        let output: serde_json::Value = serde_json::from_slice(&report.output)
+            .map_err(|e| anyhow!("Module '{}' returned invalid JSON: {}", ACCRUAL_MODULE, e))?;
+        Ok::<_, anyhow::Error>(output)
+    };
+    result.await.map(Json).map_err(|e| ApiError::new(e, &headers))
+}
+
+async fn audit(
+    State(kernel): State<Arc<Kernel>>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+    Query(page): Query<PageRequest>,
+) -> Result<Json<Page<AuditEntry>>, ApiError> {
+    let entries = kernel.audit_log().query_page(&query, &page).await.map_err(|e| ApiError::new(e, &headers))?;
+    Ok(Json(entries))
+}
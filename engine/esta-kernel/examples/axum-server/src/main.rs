@@ -0,0 +1,28 @@
+//! Serve the kernel's REST API until Ctrl-C
+//!
+//! Configured from the environment (see `ServerConfig::from_env`). Set
+//! `RUST_LOG` for kernel logging.
+
+use anyhow::Result;
+use esta_axum_server::{kernel, router, ServerConfig};
+use log::info;
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let config = ServerConfig::from_env()?;
+    let kernel = Arc::new(kernel(&config).await?);
+
+    let listener = tokio::net::TcpListener::bind(config.listen).await?;
+    info!("Serving {} on {}", config.data_dir.display(), listener.local_addr()?);
+    axum::serve(listener, router(kernel.clone()))
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await?;
+
+    let stopped = kernel.shutdown().await?;
+    info!("Stopped; shut down {} module(s)", stopped.len());
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! The REST API driven in-process against a kernel over a temporary data directory
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use esta_axum_server::{kernel, router, ServerConfig};
+use esta_kernel::security::sig::ModuleSigner;
+use esta_kernel::ModuleManifest;
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// Accrual stand-in whose `accrue_json` always reports 4 minutes accrued
+fn accrual_wat() -> String {
+    let output = r#"{"ok":true,"error":null,"data":{"accrued_minutes":4}}"#;
+    let prefix: String = (output.len() as u32).to_le_bytes().iter().map(|b| format!("\\{:02x}", b)).collect();
+    format!(
+        r#"(module
+          (memory (export "memory") 1)
+          (data (i32.const 512) "{}{}")
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "accrue_json") (param i32 i32) (result i32) (i32.const 512)))"#,
+        prefix,
+        output.replace('"', "\\\"")
+    )
+}
+
+/// Config over `dir`, serving an accrual module signed with a test key
+fn config(dir: &Path) -> ServerConfig {
+    let module = dir.join("accrual.wat");
+    std::fs::write(&module, accrual_wat()).unwrap();
+    let signer = ModuleSigner::from_seed(&[3u8; 32]).unwrap();
+    let manifest = ModuleManifest { path: "accrual.wat".into(), ..ModuleManifest::generate(&module, &signer, vec![]).unwrap() };
+    let manifest_path = dir.join("accrual.json");
+    std::fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+    ServerConfig {
+        data_dir: dir.join("data"),
+        accrual_manifest: Some(manifest_path),
+        public_key: Some(signer.public_key_hex()),
+        listen: "127.0.0.1:0".parse().unwrap(),
+    }
+}
+
+async fn app(config: &ServerConfig) -> Router {
+    router(Arc::new(kernel(config).await.unwrap()))
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>, language: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT_LANGUAGE, language)
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_tenant_accrual() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&config(dir.path())).await;
+
+    let (status, body) = send(&app, "GET", "/status", None, "en").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["module_names"], json!(["accrual"]));
+    assert_eq!(body["require_signatures"], true);
+
+    let (status, body) = send(&app, "POST", "/tenants", Some(json!({ "tenant_id": "acme" })), "en").await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert!(body["root_capability"].is_string());
+    let (_, capabilities) = send(&app, "GET", "/tenants/acme/capabilities", None, "en").await;
+    assert_eq!(capabilities.as_array().unwrap().len(), 1);
+    assert_eq!(capabilities[0]["owner"], "acme");
+
+    let input = json!({ "employee_id": "e1", "minutes_worked": 120, "employer_policy": { "employer_size": "small" } });
+    let (status, body) = send(&app, "POST", "/tenants/acme/accrue", Some(input.clone()), "en").await;
+    assert_eq!((status, body), (StatusCode::OK, json!({ "accrued_minutes": 4 })));
+
+    let (status, body) = send(&app, "POST", "/tenants/globex/accrue", Some(input.clone()), "en").await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::NOT_FOUND, Some("TENANT_NOT_FOUND")));
+    let (_, spanish) = send(&app, "POST", "/tenants/globex/accrue", Some(input), "es-MX").await;
+    assert_ne!(spanish["message"], body["message"]);
+
+    let (status, page) = send(&app, "GET", "/audit?tenant_id=acme&limit=100", None, "en").await;
+    assert_eq!(status, StatusCode::OK);
+    let items = page["items"].as_array().unwrap();
+    assert!(items.iter().any(|e| e["event"].get("TenantCreated").is_some()), "{}", page);
+    assert!(items.iter().all(|e| e["tenant_id"] == "acme"), "{}", page);
+}
+
+#[tokio::test]
+async fn test_audit_and_tenants_survive_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = config(dir.path());
+    let (_, before) = {
+        let app = app(&config).await;
+        send(&app, "POST", "/tenants", Some(json!({ "tenant_id": "acme" })), "en").await;
+        send(&app, "GET", "/audit?limit=100", None, "en").await
+    };
+
+    // Reopened without the module: the tenant and audit history remain
+    let app = app(&ServerConfig { accrual_manifest: None, ..config }).await;
+    let (status, body) = send(&app, "POST", "/tenants", Some(json!({ "tenant_id": "acme" })), "en").await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::CONFLICT, Some("TENANT_EXISTS")));
+
+    let (_, after) = send(&app, "GET", "/audit?limit=100", None, "en").await;
+    let sequences = |page: &Value| page["items"].as_array().unwrap().iter().map(|e| e["sequence"].as_u64()).collect::<Vec<_>>();
+    assert!(!sequences(&before).is_empty());
+    assert_eq!(sequences(&after)[..sequences(&before).len()], sequences(&before)[..]);
+
+    let (status, body) = send(&app, "POST", "/tenants/acme/accrue", Some(json!({})), "en").await;
+    assert_eq!((status, body["code"].as_str()), (StatusCode::NOT_FOUND, Some("MODULE_NOT_LOADED")));
+}
@@ -0,0 +1,391 @@
+//! Compliance Alerts
+//!
+//! The alert monitor watches the ledger and the audit log for conditions an
+//! employer should hear about straight away:
+//!
+//! - an employee used more sick time than they had available
+//!   ([`AlertKind::NegativeBalanceAttempt`]),
+//! - an employee's use in a year went past the policy's annual limit
+//!   ([`AlertKind::CapExceeded`]),
+//! - a module crashed repeatedly within a short window
+//!   ([`AlertKind::ModuleCrashLoop`]).
+//!
+//! Ledger conditions are checked as events are appended
+//! ([`crate::Kernel::append_ledger_events`]); crashes are read from an audit
+//! subscription ([`AlertMonitor::watch`]). Each alert is published to
+//! subscribers with the webhooks configured for it, and the embedding
+//! application delivers it (the desktop app shows a notification and posts
+//! to the webhooks).
+//!
+//! Alerting is configured per tenant: kinds can be switched off and
+//! webhooks added. Alerts not tied to a tenant, such as a crash outside any
+//! tenant's invocation, use the default configuration, as do tenants without
+//! their own. Webhooks must be HTTPS URLs on an allow-listed host.
+
+use crate::report::{EmployeeSummary, ViolationKind};
+use crate::security::audit::{AuditEntry, AuditEventType, AuditSubscription, MissedEntries};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Alerts buffered for each subscriber before it starts missing some
+const ALERT_CAPACITY: usize = 256;
+
+/// Errors from alert configuration
+#[derive(Debug, Error)]
+pub enum AlertError {
+    #[error("Alert settings I/O failed: {0}")]
+    Io(String),
+
+    #[error("Alert settings are corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Webhook {0} is not allowed: {1}")]
+    WebhookNotAllowed(String, String),
+}
+
+/// Result type for alert configuration
+pub type AlertResult<T> = Result<T, AlertError>;
+
+/// Condition an alert reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// Sick time was used beyond the available balance
+    NegativeBalanceAttempt,
+    /// Sick time used in a year passed the policy's annual limit
+    CapExceeded,
+    /// A module crashed repeatedly within the crash loop window
+    ModuleCrashLoop,
+}
+
+/// A condition that needs attention
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub employee_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module_name: Option<String>,
+    pub detail: String,
+    /// When the alert was raised (ms since Unix epoch)
+    pub raised_at: u64,
+}
+
+/// How a tenant (or the default) is alerted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertConfig {
+    /// Kinds that are not raised
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub disabled: BTreeSet<AlertKind>,
+    /// HTTPS URLs each alert is posted to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<String>,
+}
+
+/// An alert and where to deliver it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlertDelivery {
+    pub alert: Alert,
+    pub webhooks: Vec<String>,
+}
+
+/// When repeated crashes count as a crash loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrashLoopConfig {
+    pub crashes: usize,
+    pub window: Duration,
+}
+
+impl Default for CrashLoopConfig {
+    fn default() -> Self {
+        Self { crashes: 3, window: Duration::from_secs(600) }
+    }
+}
+
+/// Alert configuration as saved to a file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AlertSettings {
+    #[serde(default)]
+    default: AlertConfig,
+    #[serde(default)]
+    tenants: BTreeMap<String, AlertConfig>,
+}
+
+/// Raises alerts and publishes them with their delivery targets
+pub struct AlertMonitor {
+    settings: RwLock<AlertSettings>,
+    file: Option<PathBuf>,
+    /// Hosts webhooks may point at (lowercase)
+    webhook_hosts: BTreeSet<String>,
+    crash_loop: CrashLoopConfig,
+    /// Recent crash times per module (ms since Unix epoch)
+    crashes: Mutex<HashMap<String, VecDeque<u64>>>,
+    sender: broadcast::Sender<AlertDelivery>,
+}
+
+impl Default for AlertMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertMonitor {
+    /// A monitor with every kind enabled, no webhooks, and settings kept in memory
+    pub fn new() -> Self {
+        Self {
+            settings: RwLock::new(AlertSettings::default()),
+            file: None,
+            webhook_hosts: BTreeSet::new(),
+            crash_loop: CrashLoopConfig::default(),
+            crashes: Mutex::new(HashMap::new()),
+            sender: broadcast::channel(ALERT_CAPACITY).0,
+        }
+    }
+
+    /// Keep settings in a JSON file, loading it if it exists
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> AlertResult<Self> {
+        let path = path.into();
+        if path.exists() {
+            let bytes = std::fs::read(&path).map_err(|e| AlertError::Io(e.to_string()))?;
+            let settings = serde_json::from_slice(&bytes).map_err(|e| AlertError::Corrupt(e.to_string()))?;
+            self.settings = RwLock::new(settings);
+        }
+        self.file = Some(path);
+        Ok(self)
+    }
+
+    /// Allow webhooks on these hosts
+    pub fn with_webhook_hosts(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.webhook_hosts = hosts.into_iter().map(|host| host.into().to_ascii_lowercase()).collect();
+        self
+    }
+
+    pub fn with_crash_loop(mut self, crash_loop: CrashLoopConfig) -> Self {
+        self.crash_loop = crash_loop;
+        self
+    }
+
+    /// Configuration in effect for a tenant, or the default for `None`
+    pub fn config(&self, tenant_id: Option<&str>) -> AlertConfig {
+        let settings = self.settings.read().unwrap_or_else(|e| e.into_inner());
+        tenant_id
+            .and_then(|tenant| settings.tenants.get(tenant))
+            .unwrap_or(&settings.default)
+            .clone()
+    }
+
+    /// Replace a tenant's configuration, or the default for `None`
+    pub fn set_config(&self, tenant_id: Option<&str>, config: AlertConfig) -> AlertResult<()> {
+        for webhook in &config.webhooks {
+            self.check_webhook(webhook)?;
+        }
+        let mut settings = self.settings.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = settings.clone();
+        match tenant_id {
+            Some(tenant) => {
+                updated.tenants.insert(tenant.to_string(), config);
+            }
+            None => updated.default = config,
+        }
+        if let Some(path) = &self.file {
+            save(path, &updated)?;
+        }
+        *settings = updated;
+        Ok(())
+    }
+
+    /// Check that a webhook is an HTTPS URL on an allowed host
+    pub fn check_webhook(&self, url: &str) -> AlertResult<()> {
+        let refuse = |reason: &str| Err(AlertError::WebhookNotAllowed(url.to_string(), reason.to_string()));
+        let Some(rest) = url.strip_prefix("https://") else {
+            return refuse("only https URLs are allowed");
+        };
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        if authority.contains('@') {
+            return refuse("credentials in the URL are not allowed");
+        }
+        let host = authority.rsplit_once(':').map_or(authority, |(host, _port)| host);
+        if !self.webhook_hosts.contains(&host.to_ascii_lowercase()) {
+            return refuse("the host is not on the webhook allow list");
+        }
+        Ok(())
+    }
+
+    /// Receive alerts raised from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<AlertDelivery> {
+        self.sender.subscribe()
+    }
+
+    /// Publish an alert unless its kind is disabled, returning whether it was
+    pub fn raise(&self, alert: Alert) -> bool {
+        let config = self.config(alert.tenant_id.as_deref());
+        if config.disabled.contains(&alert.kind) {
+            return false;
+        }
+        log::warn!("Alert {:?}: {}", alert.kind, alert.detail);
+        // No subscribers is not an error
+        let _ = self.sender.send(AlertDelivery { alert, webhooks: config.webhooks });
+        true
+    }
+
+    /// Count a crash if the entry records one, raising an alert when a module
+    /// crashes often enough within the window
+    pub fn observe(&self, entry: &AuditEntry) {
+        let AuditEventType::ModuleCrashed { module_name, error, .. } = &entry.event else {
+            return;
+        };
+        let window = self.crash_loop.window.as_millis() as u64;
+        let looping = {
+            let mut crashes = self.crashes.lock().unwrap_or_else(|e| e.into_inner());
+            let recent = crashes.entry(module_name.clone()).or_default();
+            recent.push_back(entry.timestamp);
+            while recent.front().is_some_and(|&t| t + window < entry.timestamp) {
+                recent.pop_front();
+            }
+            let looping = recent.len() >= self.crash_loop.crashes.max(1);
+            if looping {
+                // Start counting afresh so one loop raises one alert
+                recent.clear();
+            }
+            looping
+        };
+        if looping {
+            self.raise(Alert {
+                kind: AlertKind::ModuleCrashLoop,
+                tenant_id: entry.tenant_id.clone(),
+                employee_id: None,
+                module_name: Some(module_name.clone()),
+                detail: format!(
+                    "{} crashed {} times within {} seconds; last error: {}",
+                    module_name,
+                    self.crash_loop.crashes,
+                    self.crash_loop.window.as_secs(),
+                    error
+                ),
+                raised_at: entry.timestamp,
+            });
+        }
+    }
+
+    /// Watch an audit subscription for crash loops until it closes
+    pub fn watch(self: Arc<Self>, mut subscription: AuditSubscription) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            while let Some(received) = subscription.recv().await {
+                match received {
+                    Ok(entry) => self.observe(&entry),
+                    Err(MissedEntries(missed)) => log::warn!("Alert monitor skipped {} audit entries", missed),
+                }
+            }
+        })
+    }
+}
+
+/// Alerts for violations an employee's summary gained from new ledger events
+///
+/// Violations are matched by kind and day, since their detail changes as
+/// more time is used; passing the annual limit alerts once a year.
+pub fn ledger_alerts(tenant_id: &str, before: &EmployeeSummary, after: &EmployeeSummary, now: u64) -> Vec<Alert> {
+    after
+        .violations
+        .iter()
+        .filter(|violation| !before.violations.iter().any(|v| v.kind == violation.kind && v.date == violation.date))
+        .filter_map(|violation| {
+            let kind = match violation.kind {
+                ViolationKind::UsageExceedsBalance => AlertKind::NegativeBalanceAttempt,
+                ViolationKind::UsageExceedsAnnualLimit => AlertKind::CapExceeded,
+                ViolationKind::NoPolicyInForce => return None,
+            };
+            Some(Alert {
+                kind,
+                tenant_id: Some(tenant_id.to_string()),
+                employee_id: Some(after.employee_id.clone()),
+                module_name: None,
+                detail: violation.detail.clone(),
+                raised_at: now,
+            })
+        })
+        .collect()
+}
+
+fn save(path: &Path, settings: &AlertSettings) -> AlertResult<()> {
+    let io = |e: std::io::Error| AlertError::Io(e.to_string());
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(io)?;
+    }
+    let json = serde_json::to_vec_pretty(settings).map_err(|e| AlertError::Corrupt(e.to_string()))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, json).map_err(io)?;
+    std::fs::rename(&tmp, path).map_err(io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::AuditLog;
+
+    #[test]
+    fn test_per_tenant_config_and_webhook_allow_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.json");
+        let monitor = AlertMonitor::new().with_file(&path).unwrap().with_webhook_hosts(["hooks.example.com"]);
+
+        for url in [
+            "http://hooks.example.com/esta",
+            "https://evil.example.com/esta",
+            "https://hooks.example.com@evil.example.com/",
+        ] {
+            let config = AlertConfig { webhooks: vec![url.into()], ..Default::default() };
+            assert!(matches!(monitor.set_config(Some("acme"), config), Err(AlertError::WebhookNotAllowed(..))), "{}", url);
+        }
+        let acme = AlertConfig {
+            disabled: [AlertKind::CapExceeded].into(),
+            webhooks: vec!["https://HOOKS.example.com:8443/esta?tenant=acme".into()],
+        };
+        monitor.set_config(Some("acme"), acme.clone()).unwrap();
+
+        let reopened = AlertMonitor::new().with_file(&path).unwrap();
+        assert_eq!(reopened.config(Some("acme")), acme);
+        assert_eq!(reopened.config(Some("globex")), AlertConfig::default());
+
+        let mut alerts = reopened.subscribe();
+        let alert = |kind| Alert {
+            kind,
+            tenant_id: Some("acme".into()),
+            employee_id: Some("e1".into()),
+            module_name: None,
+            detail: String::new(),
+            raised_at: 0,
+        };
+        assert!(!reopened.raise(alert(AlertKind::CapExceeded)));
+        assert!(reopened.raise(alert(AlertKind::NegativeBalanceAttempt)));
+        let delivery = alerts.try_recv().unwrap();
+        assert_eq!((delivery.alert.kind, delivery.webhooks), (AlertKind::NegativeBalanceAttempt, acme.webhooks));
+    }
+
+    #[tokio::test]
+    async fn test_crash_loop_raises_one_alert() {
+        let log = AuditLog::with_defaults();
+        let monitor = AlertMonitor::new().with_crash_loop(CrashLoopConfig { crashes: 2, window: Duration::from_secs(60) });
+        let mut alerts = monitor.subscribe();
+
+        monitor.observe(&log.log_module_crashed("accrual", "unreachable", "kernel").await);
+        monitor.observe(&log.log_module_loaded("accrual", "abc", "kernel").await);
+        assert!(alerts.try_recv().is_err());
+        monitor.observe(&log.log_module_crashed("accrual", "unreachable", "kernel").await);
+        let delivery = alerts.try_recv().unwrap();
+        assert_eq!(delivery.alert.kind, AlertKind::ModuleCrashLoop);
+        assert_eq!(delivery.alert.module_name.as_deref(), Some("accrual"));
+
+        // The count starts again after an alert
+        monitor.observe(&log.log_module_crashed("accrual", "unreachable", "kernel").await);
+        assert!(alerts.try_recv().is_err());
+    }
+}
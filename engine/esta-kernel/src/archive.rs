@@ -0,0 +1,211 @@
+//! Invocation Input/Output Archival
+//!
+//! This module stores the exact input and output bytes of selected invocations
+//! in content-addressed storage so disputed calculations can be replayed
+//! byte-for-byte long after they ran. The audit log only carries the hashes.
+//!
+//! Selection is deterministic: a module can be archived unconditionally, or a
+//! fraction of invocations can be sampled by input hash, so the same input is
+//! always either archived or not.
+//!
+//! Reference: docs/abi/kernel_contract.md
+
+use crate::error::StorageError;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Selection rules for archiving invocations
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveConfig {
+    /// Modules whose invocations are always archived
+    pub modules: HashSet<String>,
+    /// Fraction (0.0-1.0) of remaining invocations to archive, sampled by input hash
+    pub sample_rate: f64,
+}
+
+/// Hashes of an archived invocation, as referenced from the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveRef {
+    /// SHA-256 of the input bytes (hex)
+    pub input_hash: String,
+    /// SHA-256 of the output bytes (hex)
+    pub output_hash: String,
+}
+
+/// Content-addressed blob store on the local filesystem
+///
+/// Blobs live at `<root>/<first two hex chars>/<full hash>`.
+pub struct ContentStore {
+    root: PathBuf,
+}
+
+impl ContentStore {
+    /// Create a store rooted at the given directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Hash bytes the same way the store addresses them
+    pub fn hash(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    /// Store a blob, returning its hash. Existing blobs are not rewritten.
+    pub async fn put(&self, bytes: &[u8]) -> Result<String> {
+        let hash = Self::hash(bytes);
+        let path = self.blob_path(&hash);
+
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Ok(hash);
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temp file first so a crash never leaves a partial blob
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(hash)
+    }
+
+    /// Load a blob and verify it still matches its hash
+    pub async fn get(&self, hash: &str) -> Result<Vec<u8>> {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(StorageError::NotFound(format!("invalid content hash {}", hash)).into());
+        }
+
+        let bytes = tokio::fs::read(self.blob_path(hash)).await?;
+        let actual = Self::hash(&bytes);
+        if actual != hash {
+            return Err(StorageError::Corrupt(format!("archived blob {} hashes to {}", hash, actual)).into());
+        }
+        Ok(bytes)
+    }
+
+    /// Root directory of the store
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+/// Archives selected invocations into a content store
+pub struct InvocationArchive {
+    config: ArchiveConfig,
+    store: ContentStore,
+}
+
+impl InvocationArchive {
+    /// Create an archive with the given selection rules and storage root
+    pub fn new(config: ArchiveConfig, root: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            store: ContentStore::new(root),
+        }
+    }
+
+    /// Decide whether an invocation should be archived
+    pub fn should_archive(&self, module_name: &str, input: &[u8]) -> bool {
+        if self.config.modules.contains(module_name) {
+            return true;
+        }
+
+        if self.config.sample_rate <= 0.0 {
+            return false;
+        }
+        if self.config.sample_rate >= 1.0 {
+            return true;
+        }
+
+        // Map the first 8 bytes of the input hash onto [0, 1)
+        let digest = Sha256::digest(input);
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        let position = u64::from_le_bytes(prefix) as f64 / u64::MAX as f64;
+        position < self.config.sample_rate
+    }
+
+    /// Store an invocation's input and output
+    pub async fn archive(&self, input: &[u8], output: &[u8]) -> Result<ArchiveRef> {
+        Ok(ArchiveRef {
+            input_hash: self.store.put(input).await?,
+            output_hash: self.store.put(output).await?,
+        })
+    }
+
+    /// Load the archived input and output for a reference
+    pub async fn load(&self, reference: &ArchiveRef) -> Result<(Vec<u8>, Vec<u8>)> {
+        Ok((
+            self.store.get(&reference.input_hash).await?,
+            self.store.get(&reference.output_hash).await?,
+        ))
+    }
+
+    /// Underlying content store
+    pub fn store(&self) -> &ContentStore {
+        &self.store
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_content_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+
+        let hash = store.put(b"{\"a\":1}").await.unwrap();
+        assert_eq!(hash, ContentStore::hash(b"{\"a\":1}"));
+        assert_eq!(store.put(b"{\"a\":1}").await.unwrap(), hash);
+        assert_eq!(store.get(&hash).await.unwrap(), b"{\"a\":1}".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_content_store_detects_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ContentStore::new(dir.path());
+
+        let hash = store.put(b"original").await.unwrap();
+        std::fs::write(dir.path().join(&hash[..2]).join(&hash), b"tampered").unwrap();
+
+        assert!(store.get(&hash).await.is_err());
+        assert!(store.get("../../etc/passwd").await.is_err());
+    }
+
+    #[test]
+    fn test_selection_by_module_and_sampling() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ArchiveConfig {
+            modules: ["accrual".to_string()].into_iter().collect(),
+            sample_rate: 0.0,
+        };
+        let archive = InvocationArchive::new(config, dir.path());
+        assert!(archive.should_archive("accrual", b"{}"));
+        assert!(!archive.should_archive("reporting", b"{}"));
+
+        let sampled = InvocationArchive::new(
+            ArchiveConfig { sample_rate: 0.5, ..Default::default() },
+            dir.path(),
+        );
+        let hits = (0..1000)
+            .filter(|i| sampled.should_archive("reporting", format!("{{\"i\":{}}}", i).as_bytes()))
+            .count();
+        assert!(hits > 350 && hits < 650, "sampled {} of 1000", hits);
+
+        // Sampling is a pure function of the input
+        assert_eq!(
+            sampled.should_archive("reporting", b"{\"i\":7}"),
+            sampled.should_archive("reporting", b"{\"i\":7}")
+        );
+    }
+}
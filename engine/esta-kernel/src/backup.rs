@@ -0,0 +1,249 @@
+//! Backup Archives
+//!
+//! A backup is one JSON file holding every tenant's lifecycle, policy
+//! history, and roster, the accrual ledger, and the audit log, so an
+//! employer can move to another machine or keep the records a retention rule
+//! requires. [`crate::Kernel::create_backup`] writes one;
+//! [`crate::Kernel::open_backup`] and [`crate::Kernel::restore_backup`] load
+//! one.
+//!
+//! The contents are signed with Ed25519. The signature covers the contents
+//! exactly as written (they are kept as raw JSON, never re-serialized), and
+//! the format version is checked before anything else is read, so a newer
+//! archive is refused rather than half understood. Opening an archive also
+//! checks that its audit entries still form an unbroken hash chain.
+
+use crate::error::StorageError;
+use crate::ledger::LedgerEvent;
+use crate::policy::PolicyHistory;
+use crate::security::audit::{verify_from, AuditEntry};
+use crate::security::sig::{ModuleSigner, SignatureError, SignatureVerifier};
+use crate::tenant::TenantLifecycle;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Archive format version written by this kernel
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Name of the backup signing seed in the secret store
+pub const BACKUP_SIGNING_KEY: &str = "backup-signing-key";
+
+/// Domain separator so a backup signature cannot be mistaken for a module signature
+const SIGNATURE_CONTEXT: &[u8] = b"esta-backup\0";
+
+/// A tenant as recorded in a backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupTenant {
+    pub lifecycle: TenantLifecycle,
+    pub policies: PolicyHistory,
+    /// Employees on the roster (sorted)
+    pub employees: Vec<String>,
+}
+
+/// What a backup holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupContents {
+    /// When the backup was made (ms since Unix epoch)
+    pub created_at: u64,
+    /// Version of the kernel that made it
+    pub kernel_version: String,
+    pub tenants: BTreeMap<String, BackupTenant>,
+    /// Ledger events, in sequence order
+    pub ledger: Vec<LedgerEvent>,
+    /// Audit entries, in sequence order
+    pub audit: Vec<AuditEntry>,
+}
+
+/// The archive file: a signed envelope around the contents
+#[derive(Serialize, Deserialize)]
+struct ArchiveFile {
+    format_version: u32,
+    /// Ed25519 public key of the signer (hex)
+    signer: String,
+    signature: String,
+    contents: Box<RawValue>,
+}
+
+/// Only the version, read first so newer formats are refused cleanly
+#[derive(Deserialize)]
+struct ArchiveVersion {
+    format_version: u32,
+}
+
+/// What a backup holds and what it was written with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub format_version: u32,
+    /// Public key the archive is signed with (hex)
+    pub signer: String,
+    pub created_at: u64,
+    pub tenants: usize,
+    pub ledger_events: usize,
+    pub audit_entries: usize,
+}
+
+/// A backup whose signature and audit chain have been verified
+#[derive(Debug, Clone)]
+pub struct BackupArchive {
+    /// Public key the archive is signed with (hex)
+    pub signer: String,
+    pub contents: BackupContents,
+}
+
+impl BackupArchive {
+    /// Sign contents and write them to `path`, replacing the file atomically
+    ///
+    /// The directory must already exist; callers choose and create it.
+    pub async fn write(path: &Path, contents: BackupContents, signer: &ModuleSigner) -> Result<BackupSummary> {
+        let raw = RawValue::from_string(serde_json::to_string(&contents)?)?;
+        let file = ArchiveFile {
+            format_version: BACKUP_FORMAT_VERSION,
+            signer: signer.public_key_hex(),
+            signature: signer.sign(&signed_message(raw.get())),
+            contents: raw,
+        };
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec(&file)?).await?;
+        tokio::fs::rename(&tmp, path).await.with_context(|| format!("writing {}", path.display()))?;
+        Ok(Self { signer: file.signer, contents }.summary())
+    }
+
+    /// Read a backup signed by one of `trusted_keys` (hex public keys)
+    pub async fn read(path: &Path, trusted_keys: &[String]) -> Result<Self> {
+        let bytes = tokio::fs::read(path).await.with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&bytes, trusted_keys).with_context(|| format!("backup {}", path.display()))
+    }
+
+    fn parse(bytes: &[u8], trusted_keys: &[String]) -> Result<Self> {
+        let corrupt = |e: serde_json::Error| StorageError::Corrupt(e.to_string());
+        let version: ArchiveVersion = serde_json::from_slice(bytes).map_err(corrupt)?;
+        if version.format_version != BACKUP_FORMAT_VERSION {
+            return Err(StorageError::Corrupt(format!(
+                "backup format version {} is not supported (expected {})",
+                version.format_version, BACKUP_FORMAT_VERSION
+            ))
+            .into());
+        }
+
+        let file: ArchiveFile = serde_json::from_slice(bytes).map_err(corrupt)?;
+        if !trusted_keys.iter().any(|key| key.eq_ignore_ascii_case(&file.signer)) {
+            return Err(SignatureError::UntrustedKey(file.signer).into());
+        }
+        SignatureVerifier::new(&file.signer)?.verify(&signed_message(file.contents.get()), &file.signature)?;
+
+        let contents: BackupContents = serde_json::from_str(file.contents.get()).map_err(corrupt)?;
+        if let Some(first) = contents.audit.first() {
+            let chain = verify_from(first.prev_hash.clone(), &contents.audit);
+            if !chain.valid {
+                return Err(StorageError::Corrupt(format!(
+                    "audit chain broken at sequence {}",
+                    chain.first_invalid.unwrap_or_default()
+                ))
+                .into());
+            }
+        }
+        Ok(Self { signer: file.signer, contents })
+    }
+
+    pub fn summary(&self) -> BackupSummary {
+        BackupSummary {
+            format_version: BACKUP_FORMAT_VERSION,
+            signer: self.signer.clone(),
+            created_at: self.contents.created_at,
+            tenants: self.contents.tenants.len(),
+            ledger_events: self.contents.ledger.len(),
+            audit_entries: self.contents.audit.len(),
+        }
+    }
+}
+
+fn signed_message(contents: &str) -> Vec<u8> {
+    [SIGNATURE_CONTEXT, contents.as_bytes()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+    use crate::security::AuditLog;
+
+    async fn contents() -> BackupContents {
+        let log = AuditLog::with_defaults();
+        log.log_tenant_created("acme", "tenant:acme", "kernel").await;
+        log.log_tenant_created("globex", "tenant:globex", "kernel").await;
+        let tenant = BackupTenant {
+            lifecycle: TenantLifecycle::default(),
+            policies: PolicyHistory::default(),
+            employees: vec!["e1".into()],
+        };
+        BackupContents {
+            created_at: 1_700_000_000_000,
+            kernel_version: "test".into(),
+            tenants: [("acme".to_string(), tenant)].into_iter().collect(),
+            ledger: vec![LedgerEvent {
+                sequence: 0,
+                recorded_at: 1,
+                event: NewLedgerEvent {
+                    tenant_id: "acme".into(),
+                    employee_id: "e1".into(),
+                    work_date: "2025-03-03".parse().unwrap(),
+                    kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+                    policy_version: None,
+                    source: "test".into(),
+                },
+            }],
+            audit: log.get_all_entries().await,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_archive_round_trip_and_rejections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.json");
+        let signer = ModuleSigner::from_seed(&[5; 32]).unwrap();
+        let trusted = vec![signer.public_key_hex()];
+
+        let summary = BackupArchive::write(&path, contents().await, &signer).await.unwrap();
+        assert_eq!((summary.tenants, summary.ledger_events, summary.audit_entries), (1, 1, 2));
+        let archive = BackupArchive::read(&path, &trusted).await.unwrap();
+        assert_eq!(archive.summary(), summary);
+        assert_eq!(archive.contents.tenants["acme"].employees, vec!["e1"]);
+
+        // Signed by a key the reader does not trust
+        let other = vec![ModuleSigner::from_seed(&[6; 32]).unwrap().public_key_hex()];
+        let err = BackupArchive::read(&path, &other).await.unwrap_err();
+        assert!(matches!(err.root_cause().downcast_ref(), Some(SignatureError::UntrustedKey(_))), "{:#}", err);
+
+        // Edited contents no longer verify
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replace("\"e1\"", "\"e2\"")).unwrap();
+        let err = BackupArchive::read(&path, &trusted).await.unwrap_err();
+        assert!(matches!(err.root_cause().downcast_ref(), Some(SignatureError::InvalidSignature)), "{:#}", err);
+
+        // Newer formats are refused before anything else is read
+        let mut file: serde_json::Value = serde_json::from_str(&text).unwrap();
+        file["format_version"] = (BACKUP_FORMAT_VERSION + 1).into();
+        std::fs::write(&path, file.to_string()).unwrap();
+        let err = BackupArchive::read(&path, &trusted).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("format version 2 is not supported"), "{:#}", err);
+    }
+
+    #[tokio::test]
+    async fn test_broken_audit_chain_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.json");
+        let signer = ModuleSigner::from_seed(&[5; 32]).unwrap();
+
+        // Signed after tampering, so only the chain check catches it
+        let mut contents = contents().await;
+        contents.audit[1].source = "someone else".into();
+        let tampered = contents.audit[1].sequence;
+        BackupArchive::write(&path, contents, &signer).await.unwrap();
+        let err = BackupArchive::read(&path, &[signer.public_key_hex()]).await.unwrap_err();
+        let expected = format!("audit chain broken at sequence {}", tampered);
+        assert!(format!("{:#}", err).contains(&expected), "{:#}", err);
+    }
+}
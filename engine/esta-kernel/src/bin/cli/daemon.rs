@@ -0,0 +1,561 @@
+//! `daemon` commands: run the kernel headless and install it as a background service
+//!
+//! `daemon run` opens the same files the desktop app keeps in its data
+//! directory (policies, ledger, audit segments, installed modules, statistics
+//! history, and with `--secrets` the module cache), loads the installed
+//! modules, and keeps the kernel's scheduled work going (storage vacuuming,
+//! statistics snapshots, audit write retries, audit checkpoint anchoring with
+//! `--anchor`) until it receives Ctrl-C or SIGTERM. Only one process may write a data directory;
+//! run the desktop app against it as a read replica (`ESTA_READ_REPLICA=1`)
+//! while the daemon is installed.
+//!
+//! With `--listen <addr>` (feature `server`) the daemon also serves the
+//! kernel's HTTP/JSON API (see `esta_kernel::server`). `ESTA_API_KEY`, if
+//! set, is the administrator API key; without it only capability tokens are
+//! accepted, so nothing but tenant operations can be called.
+//!
+//! `daemon install` creates the data directory and registers `daemon run`
+//! with the platform's service manager:
+//!
+//! - systemd: a user unit, `~/.config/systemd/user/esta-kernel.service`.
+//! - launchd: a user agent, `~/Library/LaunchAgents/org.esta.kernel.plist`.
+//! - Windows: a Task Scheduler task, not a Windows service. Services must
+//!   answer the Service Control Manager's start and stop requests, which the
+//!   CLI does not implement, so `sc create` would register a service that
+//!   fails to start. The task starts at logon of the installing user and runs
+//!   only while that user is logged on; it does not start at boot.
+//!
+//! The installed service writes `logs/esta-kernel.log` in the data directory,
+//! rotating it at `--log-max-mb` and keeping `--log-keep` old files. Install
+//! takes the same options and writes them into the service definition, so
+//! rotation is configured wherever the daemon is installed (journald's limits
+//! are system-wide, and launchd and Task Scheduler rotate nothing).
+//! `daemon uninstall` stops and unregisters the service and leaves the data
+//! directory alone. `--dry-run` prints the steps instead of taking them.
+
+use anyhow::{anyhow, bail, Context, Result};
+use esta_kernel::security::audit::AuditLogConfig;
+use esta_kernel::security::sig::ModuleSigner;
+use esta_kernel::stats_history::DEFAULT_RETENTION;
+use esta_kernel::{
+    AnchorProvider, AuditLog, Kernel, Ledger, ModuleCatalog, PolicyFile, SecretStore, StatsHistory, TenantRegistry, TrustStore,
+};
+use log::info;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Name the service is registered under
+const SYSTEMD_UNIT: &str = "esta-kernel.service";
+const LAUNCHD_LABEL: &str = "org.esta.kernel";
+const TASK_NAME: &str = "ESTA Kernel";
+
+/// Log file the installed daemon writes
+const LOG_FILE: &str = "logs/esta-kernel.log";
+
+/// Size a log file is rotated at, unless `--log-max-mb` says otherwise
+pub const DEFAULT_LOG_MAX_MB: u64 = 10;
+
+/// Rotated log files kept, unless `--log-keep` says otherwise
+pub const DEFAULT_LOG_KEEP: u64 = 5;
+
+/// How often failed audit segment writes are retried
+const AUDIT_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often module statistics are added to the history
+const STATS_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Settings for `daemon run`
+pub struct RunOptions {
+    pub data_dir: PathBuf,
+    pub vacuum_interval: Duration,
+    pub trust_store: Option<TrustStore>,
+    /// Where audit checkpoints are anchored, if anywhere
+    pub anchor: Option<Arc<dyn AnchorProvider>>,
+    /// Key anchored checkpoints are signed with
+    pub checkpoint_signer: Option<ModuleSigner>,
+    /// Secret store holding the module cache key; no cache without one
+    #[cfg_attr(feature = "interpreter", allow(dead_code))]
+    pub secrets: Option<SecretStore>,
+    pub anchor_interval: Duration,
+    /// Serve the HTTP API on this address
+    #[cfg(feature = "server")]
+    pub listen: Option<std::net::SocketAddr>,
+    /// Administrator API key for the HTTP API
+    #[cfg(feature = "server")]
+    pub api_key: Option<String>,
+}
+
+/// Run the kernel until Ctrl-C or SIGTERM, then shut it down gracefully
+pub async fn run(options: RunOptions) -> Result<String> {
+    let dir = &options.data_dir;
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+
+    let mut audit_log = AuditLog::with_segments(AuditLogConfig::default(), dir.join("audit"))?;
+    if let Some(signer) = options.checkpoint_signer {
+        audit_log = audit_log.with_checkpoint_signer(signer);
+    }
+    let tenants = TenantRegistry::with_policy_file(PolicyFile::new(dir.join("policies.json")))?;
+    let mut kernel = Kernel::new()?
+        .with_audit_log(audit_log)
+        .with_tenant_registry(tenants)
+        .with_ledger(Ledger::with_file(dir.join("ledger.jsonl"))?)
+        .with_stats_history(StatsHistory::with_file(dir.join("stats.jsonl"), DEFAULT_RETENTION)?)
+        .with_module_catalog(ModuleCatalog::open(dir.join("modules"))?);
+    // The interpreter backend compiles nothing, so it has no module cache
+    #[cfg(not(feature = "interpreter"))]
+    if let Some(mut store) = options.secrets {
+        kernel = kernel.with_module_cache(dir.join("module-cache"), &mut store)?;
+    }
+    if let Some(trust_store) = options.trust_store {
+        kernel = kernel.with_trust_store(trust_store);
+    }
+
+    let loaded = kernel.load_installed_modules().await?;
+    info!("Daemon started in {}; loaded modules {:?}", dir.display(), loaded);
+    kernel.storage().schedule(options.vacuum_interval);
+    kernel.schedule_stats_snapshots(STATS_SNAPSHOT_INTERVAL);
+    kernel.audit_log().schedule_retries(AUDIT_RETRY_INTERVAL);
+    if let Some(anchor) = options.anchor {
+        info!("Anchoring audit checkpoints with {}", anchor.name());
+        kernel.audit_log().schedule_anchoring(anchor, options.anchor_interval);
+    }
+    let kernel = Arc::new(kernel);
+
+    #[cfg(feature = "server")]
+    let server = options.listen.map(|listen| {
+        let mut config = esta_kernel::server::ServerConfig::new(listen);
+        match &options.api_key {
+            Some(key) => config = config.with_admin_key(key),
+            None => log::warn!("ESTA_API_KEY not set; the API accepts capability tokens only"),
+        }
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(esta_kernel::server::serve(kernel.clone(), config, async {
+            stopped.await.ok();
+        }));
+        (stop, task)
+    });
+
+    shutdown_requested().await?;
+    info!("Daemon stopping");
+    #[cfg(feature = "server")]
+    if let Some((stop, task)) = server {
+        stop.send(()).ok();
+        task.await??;
+    }
+    let stopped = kernel.shutdown().await?;
+    Ok(format!("Stopped; shut down {} module(s)", stopped.len()))
+}
+
+/// Wait for Ctrl-C, or SIGTERM on Unix (what systemd and launchd send)
+async fn shutdown_requested() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(tokio::signal::ctrl_c().await?)
+    }
+}
+
+/// Log file that moves to `.1`, `.2`, ... once it reaches its size limit
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Old files kept; the oldest is deleted when another is rotated out
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingLog {
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, max_bytes: max_bytes.max(1), keep, file, written })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+            self.file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Log at `info` unless `RUST_LOG` says otherwise, to `log_file` if given
+pub fn init_logging(log_file: Option<RotatingLog>) {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if let Some(log_file) = log_file {
+        builder.target(env_logger::Target::Pipe(Box::new(log_file)));
+    }
+    builder.init();
+}
+
+/// Platform service manager the daemon is registered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceManager {
+    Systemd,
+    Launchd,
+    TaskScheduler,
+}
+
+impl ServiceManager {
+    /// The service manager of the platform the CLI was built for
+    pub fn native() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(ServiceManager::Launchd)
+        } else if cfg!(windows) {
+            Ok(ServiceManager::TaskScheduler)
+        } else if cfg!(target_os = "linux") {
+            Ok(ServiceManager::Systemd)
+        } else {
+            bail!("no supported service manager on this platform; give --manager")
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "systemd" => Some(ServiceManager::Systemd),
+            "launchd" => Some(ServiceManager::Launchd),
+            "task-scheduler" => Some(ServiceManager::TaskScheduler),
+            _ => None,
+        }
+    }
+
+    /// Where the service definition lives, if the manager keeps one in a file
+    fn definition_path(self, home: &Path) -> Option<PathBuf> {
+        match self {
+            ServiceManager::Systemd => Some(home.join(".config/systemd/user").join(SYSTEMD_UNIT)),
+            ServiceManager::Launchd => Some(home.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL))),
+            ServiceManager::TaskScheduler => None,
+        }
+    }
+}
+
+/// One step of installing or uninstalling the service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    CreateDir(PathBuf),
+    Write(PathBuf, String),
+    Run(Vec<String>),
+    Remove(PathBuf),
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::CreateDir(dir) => write!(f, "create {}", dir.display()),
+            Step::Write(path, contents) => write!(f, "write {}:\n{}", path.display(), contents.trim_end()),
+            Step::Run(command) => write!(f, "run {}", command.join(" ")),
+            Step::Remove(path) => write!(f, "remove {}", path.display()),
+        }
+    }
+}
+
+fn command(args: &[&str]) -> Step {
+    Step::Run(args.iter().map(|a| a.to_string()).collect())
+}
+
+/// Settings for `daemon install`
+pub struct InstallOptions {
+    pub manager: ServiceManager,
+    /// The CLI executable the service runs
+    pub exe: PathBuf,
+    pub data_dir: PathBuf,
+    /// Passed through to `daemon run`
+    pub trust_file: Option<PathBuf>,
+    /// Log rotation settings passed through to `daemon run`
+    pub log_max_mb: u64,
+    pub log_keep: u64,
+    pub home: PathBuf,
+}
+
+/// Arguments the service starts the CLI with
+fn run_arguments(options: &InstallOptions) -> Vec<String> {
+    let mut args = vec![
+        options.exe.display().to_string(),
+        "daemon".into(),
+        "run".into(),
+        "--data-dir".into(),
+        options.data_dir.display().to_string(),
+    ];
+    args.extend([
+        "--log-file".into(),
+        options.data_dir.join(LOG_FILE).display().to_string(),
+        "--log-max-mb".into(),
+        options.log_max_mb.to_string(),
+        "--log-keep".into(),
+        options.log_keep.to_string(),
+    ]);
+    if let Some(trust_file) = &options.trust_file {
+        args.extend(["--trust-file".into(), trust_file.display().to_string()]);
+    }
+    args
+}
+
+/// Quote an argument for a systemd `ExecStart` line
+///
+/// Backslashes are escapes there, and `%` (specifiers) and `$` (environment
+/// variables) are expanded even inside quotes, so they are doubled.
+fn systemd_quoted(arg: &str) -> String {
+    let arg = arg.replace('%', "%%").replace('$', "$$");
+    if !arg.is_empty() && !arg.contains([' ', '"', '\'', '\\', '\t']) {
+        return arg;
+    }
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote an argument for a Windows command line, where backslashes are path separators
+fn windows_quoted(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '"', '\t']) {
+        return arg.to_string();
+    }
+    format!("\"{}\"", arg.replace('"', "\\\""))
+}
+
+fn xml_escaped(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Steps that create the data directory and register and start the service
+pub fn install_steps(options: &InstallOptions) -> Vec<Step> {
+    let data_dir = &options.data_dir;
+    let mut steps = vec![
+        Step::CreateDir(data_dir.clone()),
+        Step::CreateDir(data_dir.join("modules")),
+        Step::CreateDir(data_dir.join("logs")),
+    ];
+
+    let args = run_arguments(options);
+    let definition = options.manager.definition_path(&options.home);
+    match (options.manager, definition) {
+        (ServiceManager::Systemd, Some(unit)) => {
+            let exec = args.iter().map(|a| systemd_quoted(a)).collect::<Vec<_>>().join(" ");
+            let contents = format!(
+                "[Unit]\nDescription=ESTA kernel daemon\n\n\
+                 [Service]\nExecStart={}\nRestart=on-failure\n\n\
+                 [Install]\nWantedBy=default.target\n",
+                exec
+            );
+            steps.push(Step::Write(unit, contents));
+            steps.push(command(&["systemctl", "--user", "daemon-reload"]));
+            steps.push(command(&["systemctl", "--user", "enable", "--now", SYSTEMD_UNIT]));
+        }
+        (ServiceManager::Launchd, Some(plist)) => {
+            let program = args
+                .iter()
+                .map(|a| format!("    <string>{}</string>\n", xml_escaped(a)))
+                .collect::<String>();
+            let contents = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+                 <plist version=\"1.0\">\n<dict>\n  <key>Label</key>\n  <string>{}</string>\n  \
+                 <key>ProgramArguments</key>\n  <array>\n{}  </array>\n  \
+                 <key>RunAtLoad</key>\n  <true/>\n  <key>KeepAlive</key>\n  <true/>\n</dict>\n</plist>\n",
+                LAUNCHD_LABEL, program
+            );
+            let plist_arg = plist.display().to_string();
+            steps.push(Step::Write(plist, contents));
+            steps.push(command(&["launchctl", "load", "-w", &plist_arg]));
+        }
+        _ => {
+            let task = args.iter().map(|a| windows_quoted(a)).collect::<Vec<_>>().join(" ");
+            steps.push(command(&["schtasks", "/Create", "/TN", TASK_NAME, "/SC", "ONLOGON", "/RL", "LIMITED", "/F", "/TR", &task]));
+            steps.push(command(&["schtasks", "/Run", "/TN", TASK_NAME]));
+        }
+    }
+    steps
+}
+
+/// Steps that stop and unregister the service, keeping the data directory
+pub fn uninstall_steps(manager: ServiceManager, home: &Path) -> Vec<Step> {
+    match (manager, manager.definition_path(home)) {
+        (ServiceManager::Systemd, Some(unit)) => vec![
+            command(&["systemctl", "--user", "disable", "--now", SYSTEMD_UNIT]),
+            Step::Remove(unit),
+            command(&["systemctl", "--user", "daemon-reload"]),
+        ],
+        (ServiceManager::Launchd, Some(plist)) => {
+            let plist_arg = plist.display().to_string();
+            vec![command(&["launchctl", "unload", "-w", &plist_arg]), Step::Remove(plist)]
+        }
+        _ => vec![
+            command(&["schtasks", "/End", "/TN", TASK_NAME]),
+            command(&["schtasks", "/Delete", "/TN", TASK_NAME, "/F"]),
+        ],
+    }
+}
+
+/// Take the steps in order, stopping at the first failure
+///
+/// Stopping a service that is not running is not a failure when uninstalling.
+pub fn apply(steps: &[Step], uninstalling: bool) -> Result<String> {
+    let mut done = Vec::new();
+    for step in steps {
+        match step {
+            Step::CreateDir(dir) => std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?,
+            Step::Write(path, contents) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+                }
+                std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))?;
+            }
+            Step::Run(args) => {
+                let status = std::process::Command::new(&args[0])
+                    .args(&args[1..])
+                    .status()
+                    .with_context(|| format!("running {}", args[0]))?;
+                if !status.success() && !uninstalling {
+                    return Err(anyhow!("{} failed ({})", args.join(" "), status));
+                }
+            }
+            Step::Remove(path) => match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).with_context(|| format!("removing {}", path.display()));
+                }
+                _ => {}
+            },
+        }
+        done.push(step.to_string().lines().next().unwrap_or_default().to_string());
+    }
+    Ok(done.join("\n"))
+}
+
+/// The steps as text, for `--dry-run`
+pub fn describe(steps: &[Step]) -> String {
+    steps.iter().map(Step::to_string).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn options(manager: ServiceManager) -> InstallOptions {
+        InstallOptions {
+            manager,
+            exe: PathBuf::from("/opt/esta/esta-kernel-cli"),
+            data_dir: PathBuf::from("/home/pat/ESTA Data"),
+            trust_file: None,
+            log_max_mb: 20,
+            log_keep: 3,
+            home: PathBuf::from("/home/pat"),
+        }
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let steps = install_steps(&options(ServiceManager::Systemd));
+        assert_eq!(steps[0], Step::CreateDir(PathBuf::from("/home/pat/ESTA Data")));
+        let Step::Write(path, unit) = &steps[3] else { panic!("expected unit file, got {:?}", steps[3]) };
+        assert_eq!(path, Path::new("/home/pat/.config/systemd/user/esta-kernel.service"));
+        assert!(unit.contains(
+            "ExecStart=/opt/esta/esta-kernel-cli daemon run --data-dir \"/home/pat/ESTA Data\" \
+             --log-file \"/home/pat/ESTA Data/logs/esta-kernel.log\" --log-max-mb 20 --log-keep 3\n"
+        ));
+        assert_eq!(steps.last(), Some(&command(&["systemctl", "--user", "enable", "--now", SYSTEMD_UNIT])));
+
+        let steps = uninstall_steps(ServiceManager::Systemd, Path::new("/home/pat"));
+        assert_eq!(steps[1], Step::Remove(path.clone()));
+
+        // Specifiers and variables are not expanded in paths
+        assert_eq!(systemd_quoted("/srv/100%/$HOME"), "/srv/100%%/$$HOME");
+        assert_eq!(systemd_quoted("C:\\ESTA's data"), "\"C:\\\\ESTA's data\"");
+    }
+
+    #[test]
+    fn test_launchd_and_task_scheduler_definitions() {
+        let steps = install_steps(&InstallOptions {
+            trust_file: Some(PathBuf::from("/etc/esta/trust.json")),
+            ..options(ServiceManager::Launchd)
+        });
+        assert!(steps.contains(&Step::CreateDir(PathBuf::from("/home/pat/ESTA Data/logs"))));
+        let Step::Write(path, plist) = &steps[3] else { panic!("expected plist, got {:?}", steps[3]) };
+        assert_eq!(path, Path::new("/home/pat/Library/LaunchAgents/org.esta.kernel.plist"));
+        assert!(plist.contains("<string>/home/pat/ESTA Data/logs/esta-kernel.log</string>"));
+        assert!(plist.contains("<string>--trust-file</string>\n    <string>/etc/esta/trust.json</string>"));
+
+        let steps = install_steps(&options(ServiceManager::TaskScheduler));
+        let Step::Run(create) = &steps[3] else { panic!("expected schtasks, got {:?}", steps[3]) };
+        assert_eq!(create[..3], ["schtasks", "/Create", "/TN"]);
+        assert!(create.last().unwrap().ends_with("--log-file \"/home/pat/ESTA Data/logs/esta-kernel.log\" --log-max-mb 20 --log-keep 3"));
+    }
+
+    #[test]
+    fn test_apply_creates_and_removes_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("agents/esta.plist");
+        let steps = [Step::CreateDir(dir.path().join("data/modules")), Step::Write(file.clone(), "x".into())];
+        apply(&steps, false).unwrap();
+        assert!(dir.path().join("data/modules").is_dir());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "x");
+
+        apply(&[Step::Remove(file.clone()), Step::Remove(file.clone())], true).unwrap();
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_rotating_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/daemon.log");
+        let mut log = RotatingLog::open(&path, 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(std::fs::read_to_string(log.rotated(1)).unwrap(), "third\n");
+        assert_eq!(std::fs::read_to_string(log.rotated(2)).unwrap(), "second\n");
+        assert!(!log.rotated(3).exists());
+    }
+}
@@ -0,0 +1,980 @@
+//! esta-kernel-cli - Kernel operations outside the desktop app
+//!
+//! Signs and verifies module manifests, runs module functions, and inspects
+//! audit logs, using the same signing scheme, kernel, and hash chain as the
+//! desktop app.
+//!
+//! ```text
+//! esta-kernel-cli manifest create <module> --key <seed-file> [--name <name>] [--version <version>]
+//!                 [--capabilities <a,b,...>] [--out <file>]
+//! esta-kernel-cli sign <manifest> --key <seed-file> [--out <file>]
+//! esta-kernel-cli verify <manifest> (--public-key <hex> | --trust-file <file>)
+//! esta-kernel-cli run <manifest> <function> [--input <json> | --input-file <file>]
+//!                 [--public-key <hex> | --trust-file <file>] [--require-signatures]
+//!                 [--audit-out <file>]
+//! esta-kernel-cli audit export <log> [--format jsonl|json|csv] [--after <sequence> | --cursor <file>]
+//!                 [--out <file>]
+//! esta-kernel-cli audit verify <log> [--public-key <hex> | --trust-file <file>]
+//! esta-kernel-cli capabilities diff <earlier-snapshot> <later-snapshot>
+//! esta-kernel-cli capabilities check <fixture>...
+//! esta-kernel-cli database encrypt <database> --secrets <file>
+//! esta-kernel-cli database rotate-key <database> --secrets <file>
+//! esta-kernel-cli daemon run --data-dir <dir> [--log-file <file>] [--log-max-mb <n>] [--log-keep <n>]
+//!                 [--vacuum-hours <n>] [--public-key <hex> | --trust-file <file>] [--listen <addr>]
+//!                 [--anchor <file | url>] [--anchor-hours <n>] [--checkpoint-key <seed-file>]
+//!                 [--secrets <file>]
+//! esta-kernel-cli daemon install --data-dir <dir> [--manager systemd|launchd|task-scheduler]
+//!                 [--trust-file <file>] [--log-max-mb <n>] [--log-keep <n>] [--dry-run]
+//! esta-kernel-cli daemon uninstall [--manager systemd|launchd|task-scheduler] [--dry-run]
+//! ```
+//!
+//! A signing key file holds the 32-byte Ed25519 seed as hex. `manifest
+//! create` writes a complete signed manifest for a module (to stdout unless
+//! `--out` is given), with the module path relative to the manifest's
+//! directory when it is written to a file. `sign` fills in an existing
+//! manifest's checksum and signature, rewriting it in place unless `--out`
+//! is given. Audit logs are JSON Lines files of audit entries, one
+//! per line, as written by `run --audit-out`. `audit export --cursor`
+//! exports only the entries added since the export that last moved the
+//! cursor file, after checking they continue its chain, then moves it;
+//! appending each JSON Lines export to an archive keeps the archive
+//! verifiable with `audit verify`, which also checks anchored checkpoints
+//! and, given trusted keys, their signatures. Capability snapshots are the
+//! JSON produced by `CapabilityManager::export_state`; `capabilities diff`
+//! prints what was granted, withdrawn, or changed between two of them.
+//! `capabilities check` runs capability policy fixtures (see
+//! `esta_kernel::security::fixtures`) and fails if any expectation does not hold.
+//! `database encrypt` seals the employee records of an existing plaintext
+//! database in place, and `database rotate-key` re-seals them under a new
+//! key; the key lives in the secret store given by `--secrets`, unlocked
+//! with `ESTA_SECRET_PASSPHRASE` as in the desktop app.
+//! `daemon` runs the kernel headless over a data directory and registers it
+//! as a background service (see `daemon.rs`); with `--listen` it also serves
+//! the kernel's HTTP API (built with feature `server`), and with `--anchor`
+//! it records audit checkpoints in a file or with an RFC 3161 timestamp
+//! authority (an `http(s)://` URL, built with feature `anchor-http`),
+//! signed with the `--checkpoint-key` seed if one is given. Compiled modules
+//! are cached only with `--secrets`, whose store keeps the key authenticating
+//! the cache.
+//!
+//! Exits with status 1 on any error, including a failed verification, and 2
+//! on a usage error. Set `RUST_LOG` for kernel logging.
+
+use anyhow::{anyhow, bail, Context, Result};
+use esta_kernel::catalog::read_manifest;
+use esta_kernel::security::audit::{export_since, AuditEntry, ExportCursor};
+use esta_kernel::security::audit_verify_export;
+use esta_kernel::security::sig::ModuleSigner;
+use esta_kernel::{
+    AnchorProvider, CapabilityFixture, CapabilitySnapshot, Database, ExecutionConfig, FieldCipher, FileAnchor, Kernel,
+    ModuleManifest, SecretStore, TrustStore,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod daemon;
+
+const USAGE: &str = "\
+usage: esta-kernel-cli <command> [args]
+
+commands:
+  manifest create <module> --key <seed-file> [--name <name>] [--version <version>]
+      [--capabilities <a,b,...>] [--out <file>]
+  sign <manifest> --key <seed-file> [--out <file>]
+  verify <manifest> (--public-key <hex> | --trust-file <file>)
+  run <manifest> <function> [--input <json> | --input-file <file>]
+      [--public-key <hex> | --trust-file <file>] [--require-signatures] [--audit-out <file>]
+  audit export <log> [--format jsonl|json|csv] [--after <sequence> | --cursor <file>] [--out <file>]
+  audit verify <log> [--public-key <hex> | --trust-file <file>]
+  capabilities diff <earlier-snapshot> <later-snapshot>
+  capabilities check <fixture>...
+  database encrypt <database> --secrets <file>
+  database rotate-key <database> --secrets <file>
+  daemon run --data-dir <dir> [--log-file <file>] [--log-max-mb <n>] [--log-keep <n>]
+      [--vacuum-hours <n>] [--public-key <hex> | --trust-file <file>] [--listen <addr>]
+      [--anchor <file | url>] [--anchor-hours <n>] [--checkpoint-key <seed-file>] [--secrets <file>]
+  daemon install --data-dir <dir> [--manager systemd|launchd|task-scheduler] [--trust-file <file>]
+      [--log-max-mb <n>] [--log-keep <n>] [--dry-run]
+  daemon uninstall [--manager systemd|launchd|task-scheduler] [--dry-run]";
+
+/// Options that take no value
+const FLAGS: &[&str] = &["--require-signatures", "--dry-run"];
+
+/// A command line that could not be understood
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n\n{}", self.0, USAGE)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn usage(message: impl Into<String>) -> anyhow::Error {
+    UsageError(message.into()).into()
+}
+
+/// Positional arguments and `--name value` options
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+            } else if FLAGS.contains(&arg.as_str()) {
+                parsed.options.insert(arg, String::new());
+            } else {
+                let value = args.next().ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+                parsed.options.insert(arg, value);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The positional arguments, which must number exactly `N`
+    fn expect<const N: usize>(&self, command: &str) -> Result<[&str; N]> {
+        let values: Vec<&str> = self.positional.iter().map(String::as_str).collect();
+        values
+            .try_into()
+            .map_err(|_| usage(format!("{} takes {} argument(s)", command, N)))
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str> {
+        self.option(name).ok_or_else(|| usage(format!("{} is required", name)))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    /// Reject options the command does not know, so typos are not silently ignored
+    fn allow(&self, known: &[&str]) -> Result<()> {
+        match self.options.keys().find(|name| !known.contains(&name.as_str())) {
+            Some(name) => Err(usage(format!("unknown option {}", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let argv: Vec<String> = std::env::args().skip(1).collect();
+    // `daemon run` sets up its own logging
+    if argv.first().map(String::as_str) != Some("daemon") {
+        env_logger::init();
+    }
+
+    match dispatch(argv).await {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) if e.is::<UsageError>() => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run a command line, returning what to print on success
+async fn dispatch(mut argv: Vec<String>) -> Result<String> {
+    if argv.is_empty() {
+        return Err(usage("missing command"));
+    }
+    let command = argv.remove(0);
+    let command = match command.as_str() {
+        "audit" | "capabilities" | "daemon" | "database" | "manifest" if !argv.is_empty() => format!("{} {}", command, argv.remove(0)),
+        _ => command,
+    };
+    let args = Args::parse(argv)?;
+
+    match command.as_str() {
+        "manifest create" => {
+            args.allow(&["--key", "--name", "--version", "--capabilities", "--out"])?;
+            let [module] = args.expect("manifest create")?;
+            let capabilities = args
+                .option("--capabilities")
+                .map(|caps| caps.split(',').map(str::trim).filter(|c| !c.is_empty()).map(String::from).collect())
+                .unwrap_or_default();
+            let mut manifest = create_manifest(
+                Path::new(module),
+                Path::new(args.required("--key")?),
+                capabilities,
+                args.option("--out").map(Path::new),
+            )?;
+            if let Some(name) = args.option("--name") {
+                manifest.name = name.to_string();
+            }
+            manifest.version = args.option("--version").map(String::from);
+            let json = serde_json::to_string_pretty(&manifest)?;
+            match args.option("--out") {
+                Some(out) => {
+                    std::fs::write(out, json).with_context(|| format!("writing {}", out))?;
+                    Ok(format!("Wrote {} (checksum {})", out, manifest.checksum))
+                }
+                None => Ok(json),
+            }
+        }
+        "sign" => {
+            args.allow(&["--key", "--out"])?;
+            let [manifest] = args.expect("sign")?;
+            sign(Path::new(manifest), Path::new(args.required("--key")?), args.option("--out").map(Path::new))
+        }
+        "verify" => {
+            args.allow(&["--public-key", "--trust-file"])?;
+            let [manifest] = args.expect("verify")?;
+            let trust_store = trust_store(&args)?.ok_or_else(|| usage("--public-key or --trust-file is required"))?;
+            verify(Path::new(manifest), &trust_store)
+        }
+        "run" => {
+            args.allow(&[
+                "--input",
+                "--input-file",
+                "--public-key",
+                "--trust-file",
+                "--require-signatures",
+                "--audit-out",
+            ])?;
+            let [manifest, function] = args.expect("run")?;
+            let input = match (args.option("--input"), args.option("--input-file")) {
+                (Some(_), Some(_)) => return Err(usage("give --input or --input-file, not both")),
+                (Some(input), None) => input.as_bytes().to_vec(),
+                (None, Some(path)) => std::fs::read(path).with_context(|| format!("reading {}", path))?,
+                (None, None) => b"{}".to_vec(),
+            };
+            let config = ExecutionConfig {
+                require_signatures: args.flag("--require-signatures"),
+                ..ExecutionConfig::default()
+            };
+            let audit_out = args.option("--audit-out").map(Path::new);
+            run(Path::new(manifest), function, &input, config, trust_store(&args)?, audit_out).await
+        }
+        "audit export" => {
+            args.allow(&["--format", "--after", "--cursor", "--out"])?;
+            let [log] = args.expect("audit export")?;
+            let entries = read_entries(Path::new(log))?;
+            let format = args.option("--format").unwrap_or("jsonl");
+            let (entries, cursor) = match (args.option("--after"), args.option("--cursor")) {
+                (Some(_), Some(_)) => return Err(usage("give --after or --cursor, not both")),
+                (Some(after), None) => {
+                    let after: u64 = after.parse().map_err(|_| usage(format!("invalid sequence {}", after)))?;
+                    (entries.into_iter().filter(|e| e.sequence > after).collect(), None)
+                }
+                (None, Some(path)) => {
+                    let export = export_since(entries, &read_cursor(Path::new(path))?)?;
+                    (export.entries, Some((path, export.cursor)))
+                }
+                (None, None) => (entries, None),
+            };
+            let exported = audit_export(&entries, format)?;
+            let output = match args.option("--out") {
+                Some(out) => {
+                    std::fs::write(out, exported).with_context(|| format!("writing {}", out))?;
+                    String::new()
+                }
+                None => exported.trim_end().to_string(),
+            };
+            // The cursor moves only once the entries are written
+            if let Some((path, cursor)) = cursor {
+                std::fs::write(path, serde_json::to_vec(&cursor)?).with_context(|| format!("writing {}", path))?;
+            }
+            Ok(output)
+        }
+        "audit verify" => {
+            args.allow(&["--public-key", "--trust-file"])?;
+            let [log] = args.expect("audit verify")?;
+            audit_verify(Path::new(log), trust_store(&args)?.as_ref())
+        }
+        "capabilities diff" => {
+            args.allow(&[])?;
+            let [earlier, later] = args.expect("capabilities diff")?;
+            capabilities_diff(Path::new(earlier), Path::new(later))
+        }
+        "capabilities check" => {
+            args.allow(&[])?;
+            if args.positional.is_empty() {
+                return Err(usage("capabilities check needs at least one fixture"));
+            }
+            capabilities_check(&args.positional).await
+        }
+        "database encrypt" => {
+            args.allow(&["--secrets"])?;
+            let [database] = args.expect("database encrypt")?;
+            let mut store = secret_store(args.required("--secrets")?)?;
+            let cipher = FieldCipher::from_store(&mut store)?;
+            let sealed = Database::open(database)?.encrypt(cipher.clone()).await?;
+            Ok(format!("Encrypted {} with key {}: {} values sealed", database, cipher.key_id(), sealed))
+        }
+        "database rotate-key" => {
+            args.allow(&["--secrets"])?;
+            let [database] = args.expect("database rotate-key")?;
+            let mut store = secret_store(args.required("--secrets")?)?;
+            let cipher = FieldCipher::begin_rotation(&mut store)?;
+            let resealed = Database::open_encrypted(database, cipher.clone())?.rotate_key(cipher.clone()).await?;
+            FieldCipher::finish_rotation(&mut store)?;
+            Ok(format!("Rotated {} to key {}: {} values re-sealed", database, cipher.key_id(), resealed))
+        }
+        "daemon run" => {
+            args.allow(&[
+                "--data-dir",
+                "--log-file",
+                "--log-max-mb",
+                "--log-keep",
+                "--vacuum-hours",
+                "--public-key",
+                "--trust-file",
+                "--listen",
+                "--anchor",
+                "--anchor-hours",
+                "--checkpoint-key",
+                "--secrets",
+            ])?;
+            let [] = args.expect("daemon run")?;
+            let anchor = args.option("--anchor").map(anchor_provider).transpose()?;
+            #[cfg(feature = "server")]
+            let listen = args
+                .option("--listen")
+                .map(|addr| addr.parse().map_err(|_| usage(format!("invalid --listen {}", addr))))
+                .transpose()?;
+            #[cfg(not(feature = "server"))]
+            if args.option("--listen").is_some() {
+                return Err(usage("--listen requires a build with the `server` feature"));
+            }
+            let number = |name: &str, default: u64| match args.option(name) {
+                Some(value) => value.parse::<u64>().map_err(|_| usage(format!("invalid {} {}", name, value))),
+                None => Ok(default),
+            };
+            let log_file = match args.option("--log-file") {
+                Some(path) => Some(daemon::RotatingLog::open(
+                    path,
+                    number("--log-max-mb", daemon::DEFAULT_LOG_MAX_MB)? * 1024 * 1024,
+                    number("--log-keep", daemon::DEFAULT_LOG_KEEP)? as usize,
+                )?),
+                None => None,
+            };
+            daemon::init_logging(log_file);
+            daemon::run(daemon::RunOptions {
+                data_dir: PathBuf::from(args.required("--data-dir")?),
+                vacuum_interval: Duration::from_secs(number("--vacuum-hours", 24)?.max(1) * 3600),
+                trust_store: trust_store(&args)?,
+                anchor,
+                checkpoint_signer: args.option("--checkpoint-key").map(|path| read_signer(Path::new(path))).transpose()?,
+                secrets: args.option("--secrets").map(secret_store).transpose()?,
+                anchor_interval: Duration::from_secs(number("--anchor-hours", 24)?.max(1) * 3600),
+                #[cfg(feature = "server")]
+                listen,
+                #[cfg(feature = "server")]
+                api_key: std::env::var("ESTA_API_KEY").ok().filter(|key| !key.is_empty()),
+            })
+            .await
+        }
+        "daemon install" => {
+            args.allow(&["--data-dir", "--manager", "--trust-file", "--log-max-mb", "--log-keep", "--dry-run"])?;
+            let [] = args.expect("daemon install")?;
+            let data_dir = std::path::absolute(args.required("--data-dir")?)?;
+            let number = |name: &str, default: u64| match args.option(name) {
+                Some(value) => value.parse::<u64>().map_err(|_| usage(format!("invalid {} {}", name, value))),
+                None => Ok(default),
+            };
+            let options = daemon::InstallOptions {
+                manager: service_manager(&args)?,
+                exe: std::env::current_exe()?,
+                data_dir,
+                trust_file: args.option("--trust-file").map(std::path::absolute).transpose()?,
+                log_max_mb: number("--log-max-mb", daemon::DEFAULT_LOG_MAX_MB)?,
+                log_keep: number("--log-keep", daemon::DEFAULT_LOG_KEEP)?,
+                home: home_dir()?,
+            };
+            let steps = daemon::install_steps(&options);
+            if args.flag("--dry-run") {
+                return Ok(daemon::describe(&steps));
+            }
+            daemon::apply(&steps, false)
+        }
+        "daemon uninstall" => {
+            args.allow(&["--manager", "--dry-run"])?;
+            let [] = args.expect("daemon uninstall")?;
+            let steps = daemon::uninstall_steps(service_manager(&args)?, &home_dir()?);
+            if args.flag("--dry-run") {
+                return Ok(daemon::describe(&steps));
+            }
+            daemon::apply(&steps, true)
+        }
+        _ => Err(usage(format!("unknown command {}", command))),
+    }
+}
+
+/// Service manager from `--manager`, else the platform's own
+fn service_manager(args: &Args) -> Result<daemon::ServiceManager> {
+    match args.option("--manager") {
+        Some(name) => daemon::ServiceManager::parse(name).ok_or_else(|| usage(format!("unknown service manager {}", name))),
+        None => daemon::ServiceManager::native(),
+    }
+}
+
+/// The user's home directory, where per-user service definitions live
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("cannot find the home directory; set HOME"))
+}
+
+/// Trust store from `--trust-file` or `--public-key`, if either is given
+fn trust_store(args: &Args) -> Result<Option<TrustStore>> {
+    match (args.option("--public-key"), args.option("--trust-file")) {
+        (Some(_), Some(_)) => Err(usage("give --public-key or --trust-file, not both")),
+        (Some(key), None) => Ok(Some(TrustStore::new(key)?)),
+        (None, Some(path)) => Ok(Some(TrustStore::load(path)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Anchor provider for `--anchor`: a timestamp authority URL or a file
+fn anchor_provider(target: &str) -> Result<Arc<dyn AnchorProvider>> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        #[cfg(feature = "anchor-http")]
+        return Ok(Arc::new(esta_kernel::security::HttpAnchor::rfc3161(target)));
+        #[cfg(not(feature = "anchor-http"))]
+        return Err(usage("anchoring to a URL requires a build with the `anchor-http` feature"));
+    }
+    Ok(Arc::new(FileAnchor::new(target)))
+}
+
+/// Unlock a secret store with `ESTA_SECRET_PASSPHRASE`
+fn secret_store(path: &str) -> Result<SecretStore> {
+    let passphrase = std::env::var("ESTA_SECRET_PASSPHRASE")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("set ESTA_SECRET_PASSPHRASE to unlock {}", path))?;
+    Ok(SecretStore::open_with_passphrase(path, &passphrase)?)
+}
+
+fn read_signer(key_path: &Path) -> Result<ModuleSigner> {
+    let seed = std::fs::read_to_string(key_path).with_context(|| format!("reading {}", key_path.display()))?;
+    let seed: [u8; 32] = hex::decode(seed.trim())
+        .ok()
+        .and_then(|seed| seed.try_into().ok())
+        .ok_or_else(|| anyhow!("{} must hold a 32-byte seed as hex", key_path.display()))?;
+    Ok(ModuleSigner::from_seed(&seed)?)
+}
+
+/// Generate a signed manifest for a module
+///
+/// The module path is made relative to the directory the manifest is written
+/// to (the current directory without `--out`), so the pair can be moved
+/// together. Manifests may only name modules beside or below them, so a
+/// module elsewhere is an error.
+fn create_manifest(module: &Path, key_path: &Path, capabilities: Vec<String>, out: Option<&Path>) -> Result<ModuleManifest> {
+    let signer = read_signer(key_path)?;
+    let mut manifest = ModuleManifest::generate(module, &signer, capabilities)?;
+    let out_dir = out.and_then(Path::parent).filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let out_dir = std::fs::canonicalize(out_dir)?;
+    let relative = std::fs::canonicalize(module)?
+        .strip_prefix(&out_dir)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .map_err(|_| anyhow!("{} is not in {}, where the manifest goes", module.display(), out_dir.display()))?;
+    manifest.path = relative;
+    Ok(manifest)
+}
+
+/// Fill in a manifest's checksum and signature from its module file
+fn sign(manifest_path: &Path, key_path: &Path, out: Option<&Path>) -> Result<String> {
+    let signer = read_signer(key_path)?;
+
+    // Read the module through the resolved path, but keep the manifest's own path
+    let resolved = read_manifest(manifest_path).map_err(|e| anyhow!("{}: {}", manifest_path.display(), e))?;
+    let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(manifest_path)?)?;
+    let generated = ModuleManifest::generate(&resolved.path, &signer, manifest.capabilities.clone())?;
+    manifest.checksum = generated.checksum;
+    manifest.signature = generated.signature;
+
+    let out = out.unwrap_or(manifest_path);
+    std::fs::write(out, serde_json::to_vec_pretty(&manifest)?).with_context(|| format!("writing {}", out.display()))?;
+    Ok(format!("Signed {} (checksum {}) with key {}", manifest.name, manifest.checksum, signer.public_key_hex()))
+}
+
+/// Check a manifest's checksum and signature against trusted keys
+fn verify(manifest_path: &Path, trust_store: &TrustStore) -> Result<String> {
+    let manifest = read_manifest(manifest_path).map_err(|e| anyhow!("{}: {}", manifest_path.display(), e))?;
+    let module_bytes = std::fs::read(&manifest.path).with_context(|| format!("reading {}", manifest.path))?;
+    let signature = manifest
+        .signature
+        .as_deref()
+        .ok_or_else(|| anyhow!("{} is not signed", manifest.name))?;
+
+    let key = trust_store
+        .verify_module(&module_bytes, &manifest.checksum, signature)
+        .with_context(|| format!("verifying {}", manifest.name))?;
+    let retiring = if key.is_retiring() { " (retiring)" } else { "" };
+    Ok(format!("{}: signature valid, key {}{}", manifest.name, key.key_id, retiring))
+}
+
+/// Launch a module, call one function, and return its output
+async fn run(
+    manifest_path: &Path,
+    function: &str,
+    input: &[u8],
+    config: ExecutionConfig,
+    trust_store: Option<TrustStore>,
+    audit_out: Option<&Path>,
+) -> Result<String> {
+    let mut kernel = Kernel::with_config(config)?;
+    if let Some(trust_store) = trust_store {
+        kernel = kernel.with_trust_store(trust_store);
+    }
+
+    let manifest = read_manifest(manifest_path).map_err(|e| anyhow!("{}: {}", manifest_path.display(), e))?;
+    let name = manifest.name.clone();
+    let result = match kernel.launch_manifest(manifest).await {
+        Ok(()) => kernel.execute_function(&name, function, input).await,
+        Err(e) => Err(e),
+    };
+
+    // Failed launches and calls are audited too
+    if let Some(path) = audit_out {
+        let entries = kernel.audit_log().get_all_entries().await;
+        write_entries(path, &entries)?;
+    }
+    let report = result?;
+    Ok(String::from_utf8_lossy(&report.output).into_owned())
+}
+
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("{} line {}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+fn write_entries(path: &Path, entries: &[AuditEntry]) -> Result<()> {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+    std::fs::write(path, out).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Where the previous `audit export --cursor` left off (the start, if the file does not exist yet)
+fn read_cursor(path: &Path) -> Result<ExportCursor> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).with_context(|| format!("{} is not an export cursor", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ExportCursor::genesis()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+/// Convert audit entries for review
+fn audit_export(entries: &[AuditEntry], format: &str) -> Result<String> {
+    let mut out = String::new();
+    match format {
+        "jsonl" => {
+            for entry in entries {
+                out.push_str(&serde_json::to_string(entry)?);
+                out.push('\n');
+            }
+        }
+        "json" => out = serde_json::to_string_pretty(entries)? + "\n",
+        "csv" => {
+            out.push_str("sequence,timestamp,source,event_type,event,hash\n");
+            for entry in entries {
+                let event = serde_json::to_value(&entry.event)?;
+                let event_type = match &event {
+                    serde_json::Value::Object(fields) => fields.keys().next().cloned().unwrap_or_default(),
+                    serde_json::Value::String(name) => name.clone(),
+                    _ => String::new(),
+                };
+                let row = [
+                    entry.sequence.to_string(),
+                    entry.timestamp.to_string(),
+                    entry.source.clone(),
+                    event_type,
+                    event.to_string(),
+                    entry.hash.clone(),
+                ];
+                out.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+                out.push('\n');
+            }
+        }
+        _ => return Err(usage(format!("unknown format {} (expected jsonl, json, or csv)", format))),
+    }
+    Ok(out)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Check an audit log's hash chain from the genesis entry
+/// Verify an exported log's chain and checkpoints, and with a trust store
+/// the checkpoint signatures
+fn audit_verify(path: &Path, trust_store: Option<&TrustStore>) -> Result<String> {
+    let report = audit_verify_export(path, trust_store)?;
+    if let Some(sequence) = report.first_invalid {
+        bail!("{}: chain broken at sequence {}: {}", path.display(), sequence, report.error.unwrap_or_default());
+    }
+    let mut summary = format!("{}: chain valid, {} entries", path.display(), report.entries_checked);
+    if report.first_sequence.is_some_and(|first| first > 1) {
+        summary.push_str(&format!(" from sequence {}", report.first_sequence.unwrap_or_default()));
+    }
+    if report.checkpoints > 0 {
+        summary.push_str(&format!("; {} checkpoints", report.checkpoints));
+        if trust_store.is_some() {
+            summary.push_str(&format!(", {} signed by trusted keys", report.signed_checkpoints));
+        }
+    }
+    Ok(summary)
+}
+
+fn read_snapshot(path: &Path) -> Result<CapabilitySnapshot> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("{} is not a capability snapshot", path.display()))
+}
+
+/// Report the capability changes between two snapshots as JSON
+///
+/// Signatures can only be checked by the kernel that exported the snapshots.
+fn capabilities_diff(earlier: &Path, later: &Path) -> Result<String> {
+    let diff = read_snapshot(earlier)?.diff(&read_snapshot(later)?);
+    Ok(serde_json::to_string_pretty(&diff)?)
+}
+
+/// Run capability fixtures, listing every expectation that did not hold
+async fn capabilities_check(paths: &[String]) -> Result<String> {
+    let mut lines = Vec::new();
+    let mut failed = 0;
+    for path in paths {
+        let json = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+        let report = CapabilityFixture::from_json(&json)
+            .with_context(|| format!("{} is not a capability fixture", path))?
+            .run()
+            .await
+            .with_context(|| format!("running {}", path))?;
+        lines.push(format!("{}: {} ({} expectations)", path, report.fixture, report.results.len()));
+        for failure in report.failures() {
+            failed += 1;
+            lines.push(format!(
+                "  expectation {}: {} {} {} expected {}, was {}{}",
+                failure.index,
+                failure.owner,
+                failure.rights.join("+"),
+                failure.resource,
+                if failure.expected_allow { "allowed" } else { "denied" },
+                if failure.allowed { "allowed" } else { "denied" },
+                failure.denial.as_deref().map(|d| format!(" ({})", d)).unwrap_or_default(),
+            ));
+        }
+    }
+    if failed > 0 {
+        bail!("{}\n{} expectation(s) failed", lines.join("\n"), failed);
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECHO_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func $alloc (export "alloc") (param $size i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $size)))
+            (local.get $ptr))
+          (func (export "echo_json") (param $ptr i32) (param $len i32) (result i32)
+            (local $out i32)
+            (local.set $out (call $alloc (i32.add (local.get $len) (i32.const 4))))
+            (i32.store (local.get $out) (local.get $len))
+            (memory.copy (i32.add (local.get $out) (i32.const 4)) (local.get $ptr) (local.get $len))
+            (local.get $out)))
+    "#;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    /// Write an unsigned echo module with a relative path, and a signing seed
+    fn write_module(dir: &Path) -> (String, String) {
+        std::fs::write(dir.join("echo.wat"), ECHO_WAT).unwrap();
+        let manifest = serde_json::json!({
+            "name": "echo",
+            "path": "echo.wat",
+            "checksum": "",
+            "capabilities": [],
+            "signature": null,
+        });
+        let manifest_path = dir.join("echo.json");
+        std::fs::write(&manifest_path, manifest.to_string()).unwrap();
+        let key_path = dir.join("signing.key");
+        std::fs::write(&key_path, hex::encode([7u8; 32])).unwrap();
+        (manifest_path.display().to_string(), key_path.display().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_sign_verify_and_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, key) = write_module(dir.path());
+        let public_key = ModuleSigner::from_seed(&[7u8; 32]).unwrap().public_key_hex();
+
+        let signed = dispatch(argv(&format!("sign {} --key {}", manifest, key))).await.unwrap();
+        assert!(signed.contains(&public_key), "{}", signed);
+        let written: ModuleManifest = serde_json::from_slice(&std::fs::read(&manifest).unwrap()).unwrap();
+        assert_eq!(written.path, "echo.wat", "the manifest keeps its relative path");
+
+        let verified = dispatch(argv(&format!("verify {} --public-key {}", manifest, public_key))).await.unwrap();
+        assert!(verified.starts_with("echo: signature valid"), "{}", verified);
+        let stranger = ModuleSigner::from_seed(&[8u8; 32]).unwrap().public_key_hex();
+        assert!(dispatch(argv(&format!("verify {} --public-key {}", manifest, stranger))).await.is_err());
+
+        let audit = dir.path().join("audit.jsonl").display().to_string();
+        let output = dispatch(vec![
+            "run".into(),
+            manifest.clone(),
+            "echo_json".into(),
+            "--input".into(),
+            r#"{"minutes_worked":120}"#.into(),
+            "--public-key".into(),
+            public_key,
+            "--require-signatures".into(),
+            "--audit-out".into(),
+            audit.clone(),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(output, r#"{"minutes_worked":120}"#);
+        assert!(dispatch(argv(&format!("audit verify {}", audit))).await.unwrap().contains("chain valid"));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, key) = write_module(dir.path());
+        let module = dir.path().join("echo.wat").display().to_string();
+        let out = dir.path().join("generated.json").display().to_string();
+        let public_key = ModuleSigner::from_seed(&[7u8; 32]).unwrap().public_key_hex();
+
+        let line = format!("manifest create {} --key {} --version 1.2.0 --capabilities log,audit_emit --out {}", module, key, out);
+        dispatch(argv(&line)).await.unwrap();
+        let manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(&out).unwrap()).unwrap();
+        assert_eq!((manifest.name.as_str(), manifest.path.as_str()), ("echo", "echo.wat"));
+        assert_eq!(manifest.version.as_deref(), Some("1.2.0"));
+        assert_eq!(manifest.capabilities, ["log", "audit_emit"]);
+
+        let verified = dispatch(argv(&format!("verify {} --public-key {}", out, public_key))).await.unwrap();
+        assert!(verified.starts_with("echo: signature valid"), "{}", verified);
+
+        let line = format!("manifest create {} --key {} --capabilities filesystem --out {}", module, key, out);
+        assert!(dispatch(argv(&line)).await.is_err());
+
+        // A manifest cannot point outside its own directory
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        let nested = dir.path().join("nested/echo.json").display().to_string();
+        let err = dispatch(argv(&format!("manifest create {} --key {} --out {}", module, key, nested))).await.unwrap_err();
+        assert!(err.to_string().contains("where the manifest goes"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_audit_export_and_tamper_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = esta_kernel::AuditLog::with_defaults();
+        log.log_module_loaded("echo", "abc", "kernel").await;
+        log.log_module_loaded("accrual, v2", "def", "kernel").await;
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, &log.get_all_entries().await).unwrap();
+        let path = path.display().to_string();
+
+        let csv = dispatch(argv(&format!("audit export {} --format csv --after 1", path))).await.unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("2,"));
+        assert!(lines[1].contains(",ModuleLoaded,\"{\"\"ModuleLoaded\"\""), "{}", lines[1]);
+
+        let text = std::fs::read_to_string(&path).unwrap().replace("abc", "abd");
+        std::fs::write(&path, text).unwrap();
+        let err = dispatch(argv(&format!("audit verify {}", path))).await.unwrap_err();
+        assert!(err.to_string().contains("chain broken at sequence 1"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_audit_export_with_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = esta_kernel::AuditLog::with_defaults();
+        log.log_module_loaded("echo", "abc", "kernel").await;
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, &log.get_all_entries().await).unwrap();
+        let cursor = dir.path().join("cursor.json").display().to_string();
+        let archive = dir.path().join("archive.jsonl");
+        let export = |archive: &Path| {
+            let line = format!("audit export {} --cursor {}", path.display(), cursor);
+            let archive = archive.to_path_buf();
+            async move {
+                let exported = dispatch(argv(&line)).await?;
+                let mut text = std::fs::read_to_string(&archive).unwrap_or_default();
+                if !exported.is_empty() {
+                    text.push_str(&exported);
+                    text.push('\n');
+                }
+                std::fs::write(&archive, text).unwrap();
+                Ok::<_, anyhow::Error>(exported.lines().count())
+            }
+        };
+
+        assert_eq!(export(&archive).await.unwrap(), 1);
+        log.log_module_loaded("accrual", "def", "kernel").await;
+        log.log_module_loaded("echo", "ghi", "kernel").await;
+        write_entries(&path, &log.get_all_entries().await).unwrap();
+        assert_eq!(export(&archive).await.unwrap(), 2);
+        assert_eq!(export(&archive).await.unwrap(), 0);
+        let verified = dispatch(argv(&format!("audit verify {}", archive.display()))).await.unwrap();
+        assert!(verified.ends_with("chain valid, 3 entries"), "{}", verified);
+
+        // A different log does not continue the exported chain
+        let other = esta_kernel::AuditLog::with_defaults();
+        for module in ["a", "b", "c", "d"] {
+            other.log_module_loaded(module, "abc", "kernel").await;
+        }
+        write_entries(&path, &other.get_all_entries().await).unwrap();
+        let err = export(&archive).await.unwrap_err();
+        assert!(err.to_string().contains("does not match the previous export"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_audit_verify_checks_checkpoint_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer = ModuleSigner::generate().unwrap();
+        let public_key = signer.public_key_hex();
+        let log = esta_kernel::AuditLog::with_defaults().with_checkpoint_signer(signer);
+        log.log_module_loaded("echo", "abc", "kernel").await;
+        log.anchor(Arc::new(FileAnchor::new(dir.path().join("anchors.jsonl")))).await.unwrap();
+        let path = dir.path().join("audit.jsonl");
+        write_entries(&path, &log.get_all_entries().await).unwrap();
+
+        let line = format!("audit verify {} --public-key {}", path.display(), public_key);
+        let verified = dispatch(argv(&line)).await.unwrap();
+        assert!(verified.ends_with("chain valid, 2 entries; 1 checkpoints, 1 signed by trusted keys"), "{}", verified);
+        let other = ModuleSigner::generate().unwrap().public_key_hex();
+        let err = dispatch(argv(&format!("audit verify {} --public-key {}", path.display(), other))).await.unwrap_err();
+        assert!(err.to_string().contains("chain broken at sequence 2: checkpoint of entry 1"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_database_encrypt_and_rotate_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("esta.db");
+        let db = Database::open(&db_path).unwrap();
+        db.tenants().save("acme", &Default::default()).await.unwrap();
+        db.employees().add("acme", vec!["jane.doe".into()]).await.unwrap();
+        drop(db);
+
+        std::env::set_var("ESTA_SECRET_PASSPHRASE", "correct horse");
+        let secrets = dir.path().join("secrets.json").display().to_string();
+        let database = db_path.display().to_string();
+        let encrypted = dispatch(argv(&format!("database encrypt {} --secrets {}", database, secrets))).await.unwrap();
+        assert!(encrypted.ends_with("1 values sealed"), "{}", encrypted);
+        assert!(Database::open(&db_path).is_err());
+
+        let rotated = dispatch(argv(&format!("database rotate-key {} --secrets {}", database, secrets))).await.unwrap();
+        assert!(rotated.ends_with("1 values re-sealed"), "{}", rotated);
+        let mut store = SecretStore::open_with_passphrase(&secrets, "correct horse").unwrap();
+        let db = Database::open_encrypted(&db_path, FieldCipher::from_store(&mut store).unwrap()).unwrap();
+        assert_eq!(db.employees().list("acme").await.unwrap(), vec!["jane.doe"]);
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_diff() {
+        use esta_kernel::{CapabilityManager, ResourceType};
+
+        let dir = tempfile::tempdir().unwrap();
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let write = |name: &str, snapshot: &CapabilitySnapshot| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, serde_json::to_vec(snapshot).unwrap()).unwrap();
+            path.display().to_string()
+        };
+
+        let earlier = write("q1.json", &manager.export_state().await);
+        manager.create_read_only(ResourceType::Module, "ledger".into(), "ui".into()).await.unwrap();
+        let later = write("q2.json", &manager.export_state().await);
+
+        let diff = dispatch(argv(&format!("capabilities diff {} {}", earlier, later))).await.unwrap();
+        let diff: serde_json::Value = serde_json::from_str(&diff).unwrap();
+        assert_eq!(diff["added"][0]["resource_id"], "ledger");
+        assert!(dispatch(argv(&format!("capabilities diff {} {}", earlier, dir.path().display()))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_check() {
+        let bundled = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/capabilities/module-isolation.json");
+        let output = dispatch(argv(&format!("capabilities check {}", bundled))).await.unwrap();
+        assert!(output.contains("module isolation (10 expectations)"), "{}", output);
+
+        let dir = tempfile::tempdir().unwrap();
+        let loosened = dir.path().join("loosened.json");
+        let mut fixture: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(bundled).unwrap()).unwrap();
+        fixture["expect"][3]["allow"] = true.into();
+        std::fs::write(&loosened, fixture.to_string()).unwrap();
+        let err = dispatch(argv(&format!("capabilities check {} {}", bundled, loosened.display()))).await.unwrap_err();
+        assert!(err.to_string().contains("expectation 3: report read ledger expected allowed, was denied"), "{}", err);
+        assert!(err.to_string().contains("1 expectation(s) failed"), "{}", err);
+        assert!(dispatch(argv("capabilities check")).await.unwrap_err().is::<UsageError>());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_install_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let line = format!("daemon install --data-dir {} --manager launchd --log-keep 2 --dry-run", data_dir.display());
+        let output = dispatch(argv(&line)).await.unwrap();
+        assert!(output.contains("org.esta.kernel.plist"), "{}", output);
+        assert!(output.contains(&format!("<string>{}</string>", data_dir.join("logs/esta-kernel.log").display())));
+        assert!(output.contains("<string>--log-max-mb</string>\n    <string>10</string>\n    <string>--log-keep</string>\n    <string>2</string>"));
+        assert!(!data_dir.exists());
+
+        let output = dispatch(argv("daemon uninstall --manager systemd --dry-run")).await.unwrap();
+        assert!(output.starts_with("run systemctl --user disable --now esta-kernel.service"), "{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_usage_errors() {
+        for line in ["", "frobnicate", "sign m.json", "verify m.json --public-key", "audit verify a b", "run m.json f --typo 1",
+            "daemon install --manager upstart --dry-run", "daemon run extra"] {
+            let err = dispatch(argv(line)).await.unwrap_err();
+            assert!(err.is::<UsageError>(), "{}: {}", line, err);
+        }
+    }
+}
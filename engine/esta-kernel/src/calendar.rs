@@ -0,0 +1,228 @@
+//! Calendar Dates Without External Dependencies
+//!
+//! Compliance rules are expressed in calendar days (effective dates, work
+//! dates, benefit years). This module provides a minimal proleptic Gregorian
+//! `Date` that serializes as `YYYY-MM-DD`, orders chronologically, and
+//! converts to and from days since the Unix epoch.
+//!
+//! The conversions use Howard Hinnant's `days_from_civil` algorithms, which
+//! are exact for all dates in range and use integer arithmetic only.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Errors that can occur parsing dates
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    #[error("Invalid date '{0}': expected YYYY-MM-DD")]
+    InvalidFormat(String),
+
+    #[error("Invalid date '{0}': day out of range for month")]
+    OutOfRange(String),
+}
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// A calendar date (proleptic Gregorian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+impl Date {
+    /// Create a date, validating month and day
+    pub fn new(year: i32, month: u8, day: u8) -> Result<Self, DateError> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(DateError::OutOfRange(format!("{:04}-{:02}-{:02}", year, month, day)));
+        }
+        Ok(Self { year, month, day })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Days since 1970-01-01 (negative before the epoch)
+    pub fn days_since_epoch(&self) -> i64 {
+        let y = if self.month <= 2 { self.year as i64 - 1 } else { self.year as i64 };
+        let era = if y >= 0 { y } else { y - 399 } / 400;
+        let yoe = y - era * 400;
+        let m = self.month as i64;
+        let mp = if m > 2 { m - 3 } else { m + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Build a date from days since 1970-01-01
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Self { year, month, day }
+    }
+
+    /// Date of a Unix timestamp in milliseconds (UTC)
+    pub fn from_unix_millis(millis: u64) -> Self {
+        Self::from_days_since_epoch((millis / 86_400_000) as i64)
+    }
+
+    /// Today's date (UTC)
+    pub fn today() -> Self {
+        Self::from_unix_millis(crate::clock::now_millis())
+    }
+
+    /// Date offset by a number of days
+    pub fn add_days(&self, days: i64) -> Self {
+        Self::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// Number of days from `self` until `other` (negative if `other` is earlier)
+    pub fn days_until(&self, other: &Date) -> i64 {
+        other.days_since_epoch() - self.days_since_epoch()
+    }
+
+    /// Day of the week
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        match (self.days_since_epoch() + 3).rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = DateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DateError::InvalidFormat(s.to_string());
+        let bytes = s.as_bytes();
+        if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+            return Err(invalid());
+        }
+
+        let year = s[0..4].parse::<i32>().map_err(|_| invalid())?;
+        let month = s[5..7].parse::<u8>().map_err(|_| invalid())?;
+        let day = s[8..10].parse::<u8>().map_err(|_| invalid())?;
+
+        Self::new(year, month, day).map_err(|_| DateError::OutOfRange(s.to_string()))
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let date: Date = "2025-02-21".parse().unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2025, 2, 21));
+        assert_eq!(date.to_string(), "2025-02-21");
+
+        assert!("2025-2-21".parse::<Date>().is_err());
+        assert!("2025-02-30".parse::<Date>().is_err());
+        assert!("2024-02-29".parse::<Date>().is_ok());
+        assert!("2023-02-29".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_epoch_roundtrip() {
+        assert_eq!(Date::new(1970, 1, 1).unwrap().days_since_epoch(), 0);
+        assert_eq!(Date::new(2000, 3, 1).unwrap().days_since_epoch(), 11_017);
+        assert_eq!(Date::new(1969, 12, 31).unwrap().days_since_epoch(), -1);
+
+        for days in (-800_000..800_000).step_by(997) {
+            assert_eq!(Date::from_days_since_epoch(days).days_since_epoch(), days);
+        }
+    }
+
+    #[test]
+    fn test_ordering_and_arithmetic() {
+        let a: Date = "2024-12-31".parse().unwrap();
+        let b = a.add_days(1);
+        assert_eq!(b.to_string(), "2025-01-01");
+        assert!(a < b);
+        assert_eq!(a.days_until(&b), 1);
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(Date::new(1970, 1, 1).unwrap().weekday(), Weekday::Thursday);
+        assert_eq!(Date::new(2025, 2, 21).unwrap().weekday(), Weekday::Friday);
+        assert_eq!(Date::new(1969, 12, 29).unwrap().weekday(), Weekday::Monday);
+    }
+
+    #[test]
+    fn test_serde() {
+        let date: Date = serde_json::from_str("\"2025-02-21\"").unwrap();
+        assert_eq!(serde_json::to_string(&date).unwrap(), "\"2025-02-21\"");
+        assert!(serde_json::from_str::<Date>("\"yesterday\"").is_err());
+    }
+}
@@ -0,0 +1,426 @@
+//! Local Module Catalog
+//!
+//! Compliance rule modules are distributed as a `.wasm` file plus a JSON
+//! manifest. Rather than pointing the kernel at manifest paths by hand, users
+//! drop both files into a modules directory; the catalog scans it, the kernel
+//! verifies each manifest's checksum and signature, and verified modules can
+//! be installed by name.
+//!
+//! Installing a module copies it into a version store
+//! (`.versions/<name>/<version>/`) and pins that copy: it is what gets loaded
+//! on restart, even if the manifest in the modules directory is later
+//! replaced. The installed version of each module is recorded in
+//! `installed.json`. The store keeps the installed version plus a configurable
+//! number of previous versions, so a bad update can be rolled back.
+//!
+//! A manifest's `path` is relative to the directory holding the manifest. It
+//! may name a file in a subdirectory, but absolute paths and `..` are
+//! rejected, so a manifest dropped into the modules directory cannot have
+//! the kernel read (or the version store copy) a file from elsewhere.
+
+use crate::error::StorageError;
+use crate::kernel::ModuleManifest;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::RwLock;
+
+/// File recording installed modules
+const INSTALLED_FILE: &str = "installed.json";
+
+/// Directory (inside the modules directory) holding installed versions
+const VERSIONS_DIR: &str = ".versions";
+
+/// Previous versions kept per module unless configured otherwise
+const DEFAULT_RETAINED_VERSIONS: usize = 3;
+
+/// Outcome of verifying a catalog manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ModuleVerification {
+    /// Checksum matches and the signature verified against the trusted key
+    Signed,
+    /// Checksum matches but there is no verified signature (allowed only
+    /// when the kernel does not require signatures)
+    Unsigned,
+    /// The module cannot be installed
+    Rejected { reason: String },
+}
+
+impl ModuleVerification {
+    /// Whether the module can be installed
+    pub fn is_installable(&self) -> bool {
+        !matches!(self, ModuleVerification::Rejected { .. })
+    }
+}
+
+/// A module recorded as installed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledModule {
+    pub name: String,
+    pub version: Option<String>,
+    pub checksum: String,
+    pub manifest_path: PathBuf,
+    /// When the module was installed (ms since Unix epoch)
+    pub installed_at: u64,
+}
+
+/// A manifest found in the modules directory
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    /// Module name (the manifest file stem if the manifest could not be read)
+    pub name: String,
+    pub version: Option<String>,
+    pub manifest_path: PathBuf,
+    pub capabilities: Vec<String>,
+    pub verification: ModuleVerification,
+    /// The installed version of this module, if any
+    pub installed: Option<InstalledModule>,
+    /// Versions in the version store that can be rolled back to
+    pub stored_versions: Vec<String>,
+}
+
+/// A module version kept in the version store
+#[derive(Debug, Clone)]
+pub struct StoredVersion {
+    /// Version label (the manifest version, or `unversioned-<checksum prefix>`)
+    pub label: String,
+    pub manifest_path: PathBuf,
+    pub manifest: ModuleManifest,
+}
+
+/// Modules directory scanner, version store, and installed-module record
+pub struct ModuleCatalog {
+    dir: PathBuf,
+    installed: RwLock<BTreeMap<String, InstalledModule>>,
+    retained_versions: usize,
+}
+
+impl ModuleCatalog {
+    /// Open a modules directory, loading the installed-module record
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let installed = match std::fs::read(dir.join(INSTALLED_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| StorageError::Corrupt(format!("{}: {}", INSTALLED_FILE, e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            dir,
+            installed: RwLock::new(installed),
+            retained_versions: DEFAULT_RETAINED_VERSIONS,
+        })
+    }
+
+    /// Keep this many previous versions of each module (default 3)
+    pub fn with_retained_versions(mut self, versions: usize) -> Self {
+        self.retained_versions = versions;
+        self
+    }
+
+    /// The modules directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read every manifest in the modules directory, sorted by file name
+    ///
+    /// Manifests that cannot be parsed are returned as errors rather than
+    /// failing the scan. Relative module paths are resolved against the
+    /// modules directory.
+    pub fn manifests(&self) -> Result<Vec<(PathBuf, Result<ModuleManifest, String>)>> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_manifest = path.extension().is_some_and(|ext| ext == "json")
+                && path.file_name().is_some_and(|name| name != INSTALLED_FILE);
+            if is_manifest && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .map(|path| {
+                let manifest = read_manifest(&path);
+                (path, manifest)
+            })
+            .collect())
+    }
+
+    fn versions_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(VERSIONS_DIR).join(name)
+    }
+
+    /// Copy a verified module into the version store
+    ///
+    /// Returns the stored copy, whose manifest points at the stored module
+    /// file. Storing a version that is already stored replaces it.
+    pub async fn store_version(&self, manifest: &ModuleManifest) -> Result<StoredVersion> {
+        let label = version_label(manifest);
+        if !is_safe_path_component(&manifest.name) || !is_safe_path_component(&label) {
+            return Err(StorageError::Corrupt(format!(
+                "Module name or version not usable as a directory: {} {}",
+                manifest.name, label
+            ))
+            .into());
+        }
+
+        let dir = self.versions_dir(&manifest.name).join(&label);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::copy(&manifest.path, dir.join("module.wasm")).await?;
+
+        let stored = ModuleManifest {
+            path: "module.wasm".to_string(),
+            ..manifest.clone()
+        };
+        let manifest_path = dir.join("manifest.json");
+        tokio::fs::write(&manifest_path, serde_json::to_vec_pretty(&stored)?).await?;
+
+        Ok(StoredVersion {
+            manifest: read_manifest(&manifest_path).map_err(StorageError::Corrupt)?,
+            label,
+            manifest_path,
+        })
+    }
+
+    /// Every stored version of a module, oldest version first
+    pub fn stored_versions(&self, name: &str) -> Result<Vec<StoredVersion>> {
+        let dir = self.versions_dir(name);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let manifest_path = entry.path().join("manifest.json");
+            // Skip versions whose manifest is missing or damaged; they cannot be loaded
+            if let Ok(manifest) = read_manifest(&manifest_path) {
+                versions.push(StoredVersion {
+                    label: entry.file_name().to_string_lossy().into_owned(),
+                    manifest_path,
+                    manifest,
+                });
+            }
+        }
+        versions.sort_by(|a, b| compare_versions(Some(&a.label), Some(&b.label)));
+        Ok(versions)
+    }
+
+    /// Names of the modules with versions in the version store
+    pub fn stored_modules(&self) -> Result<Vec<String>> {
+        let dir = self.dir.join(VERSIONS_DIR);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// A stored version of a module, if present
+    pub fn stored_version(&self, name: &str, version: &str) -> Result<Option<StoredVersion>> {
+        Ok(self.stored_versions(name)?.into_iter().find(|v| v.label == version))
+    }
+
+    /// Remove stored versions beyond the retention limit
+    ///
+    /// The installed version is always kept, plus the newest
+    /// `retained_versions` others. Returns the labels removed.
+    pub async fn prune_versions(&self, name: &str) -> Result<Vec<String>> {
+        let installed = self
+            .installed_module(name)
+            .await
+            .and_then(|m| m.manifest_path.parent().map(Path::to_path_buf));
+
+        let mut previous: Vec<StoredVersion> = self
+            .stored_versions(name)?
+            .into_iter()
+            .filter(|v| v.manifest_path.parent() != installed.as_deref())
+            .collect();
+        let excess = previous.len().saturating_sub(self.retained_versions);
+
+        let mut removed = Vec::new();
+        for version in previous.drain(..excess) {
+            if let Some(dir) = version.manifest_path.parent() {
+                tokio::fs::remove_dir_all(dir).await?;
+            }
+            removed.push(version.label);
+        }
+        Ok(removed)
+    }
+
+    /// All installed modules
+    pub async fn installed(&self) -> Vec<InstalledModule> {
+        self.installed.read().await.values().cloned().collect()
+    }
+
+    /// The installed version of a module, if any
+    pub async fn installed_module(&self, name: &str) -> Option<InstalledModule> {
+        self.installed.read().await.get(name).cloned()
+    }
+
+    /// Record a module as installed, replacing any earlier version
+    pub async fn record_install(&self, module: InstalledModule) -> Result<()> {
+        let mut installed = self.installed.write().await;
+        let mut updated = installed.clone();
+        updated.insert(module.name.clone(), module);
+
+        // Write to a temp file first so a crash never leaves a truncated record
+        let path = self.dir.join(INSTALLED_FILE);
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(&updated)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        *installed = updated;
+        Ok(())
+    }
+}
+
+/// Read a manifest, resolving its module path against the manifest's directory
+///
+/// Fails if the module path is absolute or leaves that directory.
+pub fn read_manifest(path: &Path) -> std::result::Result<ModuleManifest, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let mut manifest: ModuleManifest =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid manifest: {}", e))?;
+    check_module_path(&manifest.path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    manifest.path = base.join(&manifest.path).to_string_lossy().into_owned();
+    Ok(manifest)
+}
+
+/// Check that a manifest's module path stays inside the manifest's directory
+fn check_module_path(module_path: &str) -> std::result::Result<(), String> {
+    let mut named = false;
+    for component in Path::new(module_path).components() {
+        match component {
+            Component::Normal(_) => named = true,
+            Component::CurDir => {}
+            Component::ParentDir => return Err(format!("Module path may not contain '..': {}", module_path)),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("Module path must be relative to the manifest: {}", module_path))
+            }
+        }
+    }
+    if !named {
+        return Err("Manifest names no module file".to_string());
+    }
+    Ok(())
+}
+
+/// Label a manifest's version for the version store
+pub fn version_label(manifest: &ModuleManifest) -> String {
+    match &manifest.version {
+        Some(version) => version.clone(),
+        None => format!("unversioned-{}", &manifest.checksum[..manifest.checksum.len().min(12)]),
+    }
+}
+
+fn is_safe_path_component(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('.')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '+'))
+}
+
+/// Compare version strings by their numeric dot-separated components
+///
+/// `1.10.0` sorts after `1.9.2`; a missing version sorts before any version.
+pub fn compare_versions(a: Option<&str>, b: Option<&str>) -> Ordering {
+    let key = |v: &str| -> Vec<u64> {
+        v.split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+    match (a, b) {
+        (Some(a), Some(b)) => key(a).cmp(&key(b)).then_with(|| a.cmp(b)),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions(Some("1.10.0"), Some("1.9.2")), Ordering::Greater);
+        assert_eq!(compare_versions(Some("2.0"), Some("2.0.0")), Ordering::Less);
+        assert_eq!(compare_versions(None, Some("0.1")), Ordering::Less);
+        assert_eq!(compare_versions(Some("1.0.0"), Some("1.0.0")), Ordering::Equal);
+    }
+
+    #[tokio::test]
+    async fn test_scan_and_record_install() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("accrual.json"),
+            r#"{"name":"accrual","version":"1.2.0","path":"accrual.wasm","checksum":"abc","capabilities":["log"],"signature":null}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let catalog = ModuleCatalog::open(dir.path()).unwrap();
+        let manifests = catalog.manifests().unwrap();
+        assert_eq!(manifests.len(), 2);
+
+        let accrual = manifests[0].1.as_ref().unwrap();
+        assert_eq!(accrual.version.as_deref(), Some("1.2.0"));
+        assert_eq!(Path::new(&accrual.path), dir.path().join("accrual.wasm"));
+        assert!(manifests[1].1.is_err());
+
+        catalog
+            .record_install(InstalledModule {
+                name: "accrual".into(),
+                version: Some("1.2.0".into()),
+                checksum: "abc".into(),
+                manifest_path: manifests[0].0.clone(),
+                installed_at: 1,
+            })
+            .await
+            .unwrap();
+
+        // The install record is not mistaken for a manifest, and survives reopening
+        let reopened = ModuleCatalog::open(dir.path()).unwrap();
+        assert_eq!(reopened.manifests().unwrap().len(), 2);
+        let installed = reopened.installed_module("accrual").await.unwrap();
+        assert_eq!(installed.version.as_deref(), Some("1.2.0"));
+    }
+
+    #[test]
+    fn test_module_path_stays_beside_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = |path: &str| {
+            let file = dir.path().join("m.json");
+            let json = serde_json::json!({"name":"m","path":path,"checksum":"abc","capabilities":[],"signature":null});
+            std::fs::write(&file, json.to_string()).unwrap();
+            read_manifest(&file)
+        };
+
+        assert_eq!(Path::new(&manifest("lib/m.wasm").unwrap().path), dir.path().join("lib/m.wasm"));
+        assert!(manifest("../m.wasm").unwrap_err().contains(".."));
+        assert!(manifest("lib/../../m.wasm").is_err());
+        assert!(manifest("/etc/passwd").unwrap_err().contains("relative"));
+        assert!(manifest("").is_err());
+    }
+}
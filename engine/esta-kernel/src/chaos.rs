@@ -0,0 +1,172 @@
+//! Chaos Testing
+//!
+//! Fault injection for validating supervision and audit recovery before
+//! shipping (feature `chaos`; never enabled in release builds). A [`Chaos`]
+//! decides at each hook whether to inject a fault, from a pseudo-random
+//! schedule fixed by its seed: the same seed and the same sequence of hook
+//! calls inject the same faults, so a failing run can be replayed.
+//!
+//! Hooks:
+//! - Kernel invocations: a trap instead of the call, or a store starved of fuel
+//! - `host_yield`: a delay before the guest resumes
+//! - Audit log: an entry not written to its segment, as after a failed write
+//! - Supervisor: a heartbeat report dropped, so the child looks hung
+//!
+//! Every injected fault is logged at `warn` and kept for assertions.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A fault the chaos schedule can inject
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// The invocation traps instead of running
+    Trap,
+    /// The invocation's store gets `starved_fuel` instead of the configured fuel
+    FuelStarvation,
+    /// `host_yield` sleeps for `host_delay` before returning
+    HostCallDelay,
+    /// An audit entry stays in memory but is not persisted
+    DroppedAuditWrite,
+    /// A heartbeat report never reaches the supervisor
+    DroppedHeartbeat,
+}
+
+/// Fault rates and parameters
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Seed of the fault schedule
+    pub seed: u64,
+    /// Probability (0.0 to 1.0) of each fault at each opportunity
+    pub trap_rate: f64,
+    pub fuel_starvation_rate: f64,
+    pub host_delay_rate: f64,
+    pub audit_drop_rate: f64,
+    pub heartbeat_drop_rate: f64,
+    /// Fuel a starved store gets
+    pub starved_fuel: u64,
+    /// How long a delayed host call waits
+    pub host_delay: Duration,
+    /// Modules faults are injected into; every module when empty
+    pub modules: HashSet<String>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            trap_rate: 0.0,
+            fuel_starvation_rate: 0.0,
+            host_delay_rate: 0.0,
+            audit_drop_rate: 0.0,
+            heartbeat_drop_rate: 0.0,
+            starved_fuel: 1_000,
+            host_delay: Duration::from_millis(100),
+            modules: HashSet::new(),
+        }
+    }
+}
+
+impl ChaosConfig {
+    fn rate(&self, fault: Fault) -> f64 {
+        match fault {
+            Fault::Trap => self.trap_rate,
+            Fault::FuelStarvation => self.fuel_starvation_rate,
+            Fault::HostCallDelay => self.host_delay_rate,
+            Fault::DroppedAuditWrite => self.audit_drop_rate,
+            Fault::DroppedHeartbeat => self.heartbeat_drop_rate,
+        }
+    }
+}
+
+/// A fault that was injected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InjectedFault {
+    pub fault: Fault,
+    /// Module (or, for audit writes, entry sequence) the fault hit
+    pub target: String,
+}
+
+/// Seeded fault injector shared by the kernel, audit log, and supervisor
+pub struct Chaos {
+    config: ChaosConfig,
+    /// SplitMix64 state
+    state: Mutex<u64>,
+    injected: Mutex<Vec<InjectedFault>>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        let state = Mutex::new(config.seed);
+        Self { config, state, injected: Mutex::new(Vec::new()) }
+    }
+
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    /// Whether to inject `fault` into `target` now
+    ///
+    /// Faults with a zero rate, and module faults outside `modules`, never
+    /// advance the schedule, so enabling one fault does not reshuffle another.
+    pub fn inject(&self, fault: Fault, target: &str) -> bool {
+        let rate = self.config.rate(fault);
+        let targets_modules = fault != Fault::DroppedAuditWrite;
+        if rate <= 0.0 || (targets_modules && !self.config.modules.is_empty() && !self.config.modules.contains(target)) {
+            return false;
+        }
+        if self.next_unit() >= rate {
+            return false;
+        }
+        log::warn!("chaos: injecting {:?} into {}", fault, target);
+        self.injected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(InjectedFault { fault, target: target.to_string() });
+        true
+    }
+
+    /// Faults injected so far, oldest first
+    pub fn injected(&self) -> Vec<InjectedFault> {
+        self.injected.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Next value of the schedule in [0, 1)
+    fn next_unit(&self) -> f64 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_is_reproducible() {
+        let config = ChaosConfig { seed: 42, trap_rate: 0.3, audit_drop_rate: 0.5, ..Default::default() };
+        let run = |chaos: &Chaos| (0..200).map(|i| chaos.inject(Fault::Trap, &format!("m{}", i % 3))).collect::<Vec<_>>();
+
+        let first = Chaos::new(config.clone());
+        let schedule = run(&first);
+        assert_eq!(run(&Chaos::new(config.clone())), schedule, "same seed, same faults");
+        assert_ne!(run(&Chaos::new(ChaosConfig { seed: 7, ..config.clone() })), schedule);
+        let traps = schedule.iter().filter(|&&t| t).count();
+        assert!((30..90).contains(&traps), "about 30% of 200: {}", traps);
+        assert_eq!(first.injected().len(), traps);
+
+        // Disabled faults and other modules do not consume the schedule
+        let filtered = Chaos::new(ChaosConfig { modules: ["m0".to_string()].into(), ..config });
+        assert!(!filtered.inject(Fault::HostCallDelay, "m0"));
+        assert!(!filtered.inject(Fault::Trap, "m1"));
+        assert_eq!(filtered.inject(Fault::Trap, "m0"), schedule[0]);
+    }
+}
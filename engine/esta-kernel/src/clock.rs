@@ -0,0 +1,97 @@
+//! Wall Clock
+//!
+//! Every wall-clock reading in the kernel (audit timestamps, capability
+//! expiry, ledger and report stamps, file ages for retention) goes through
+//! [`now`]. Normally that is the system clock. Under a
+//! [`TimeMachine`](crate::testing::TimeMachine) it is a simulated clock that
+//! moves with tokio's paused clock, so timers, backoff, expiry, and retention
+//! all observe the same time.
+//!
+//! Components whose timestamps matter to their callers (the capability
+//! manager and the audit log) read time through a [`Clock`] instead, which
+//! defaults to [`SystemClock`], this module's clock. A [`ManualClock`]
+//! moves only when told to: expiry, rolling windows, and audit timestamps can
+//! be tested exactly on any runtime, or replayed at the times they were
+//! recorded.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+thread_local! {
+    /// Simulated wall time at the given tokio instant, while a time machine runs
+    static SIMULATED: Cell<Option<(SystemTime, tokio::time::Instant)>> = const { Cell::new(None) };
+}
+
+/// Current wall-clock time
+pub fn now() -> SystemTime {
+    match SIMULATED.with(Cell::get) {
+        Some((wall, at)) => wall + tokio::time::Instant::now().saturating_duration_since(at),
+        None => SystemTime::now(),
+    }
+}
+
+/// Current wall-clock time as Unix milliseconds
+pub fn now_millis() -> u64 {
+    now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Make [`now`] read `wall` at the current tokio instant and follow tokio time from there
+///
+/// Thread-local, like tokio's paused clock, which only runs on a
+/// current-thread runtime.
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn simulate(wall: Option<SystemTime>) {
+    SIMULATED.with(|cell| cell.set(wall.map(|wall| (wall, tokio::time::Instant::now()))));
+}
+
+/// Source of wall-clock time for a component
+pub trait Clock: Send + Sync {
+    /// Current time as Unix milliseconds
+    fn now_millis(&self) -> u64;
+}
+
+/// The kernel wall clock, [`now_millis`]; follows a `TimeMachine` when one runs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        now_millis()
+    }
+}
+
+/// A clock that stands still until set or advanced
+///
+/// Unlike a `TimeMachine` it is not tied to a thread or a paused tokio
+/// runtime, and components sharing it see exactly the same time.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    millis: AtomicU64,
+}
+
+impl ManualClock {
+    /// A clock reading `millis` (Unix milliseconds)
+    pub fn new(millis: u64) -> Self {
+        Self { millis: AtomicU64::new(millis) }
+    }
+
+    /// Jump to `millis`, forwards or backwards
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Move forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.millis.fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
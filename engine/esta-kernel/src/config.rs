@@ -0,0 +1,258 @@
+//! Kernel Configuration Files
+//!
+//! [`ExecutionConfig`] is built in code; a deployment that only needs to
+//! change limits, keys, or paths describes them in `esta-kernel.toml` instead:
+//!
+//! ```toml
+//! sandbox = "strict"
+//! module_dir = "/var/lib/esta/modules"
+//! audit_dir = "/var/lib/esta/audit"
+//! # The last key is current; earlier ones verify modules signed before a rotation
+//! trusted_keys = ["<64 hex digits>", "<64 hex digits>"]
+//!
+//! [execution]
+//! max_fuel = 10_000_000
+//! call_timeout_ms = 5_000
+//! max_concurrent_invocations = 8
+//! ```
+//!
+//! Every setting is optional. The execution limits start from the sandbox
+//! profile's (see [`crate::sandbox`]), or from `ExecutionConfig::default()`
+//! without one, and the `[execution]` table overrides them one by one.
+//! Unknown keys are rejected so a misspelled limit is not silently ignored.
+//!
+//! The module and audit directories are read by the embedding application,
+//! which creates the kernel's storage; the kernel only records them here.
+//! [`Kernel::reload_config`](crate::Kernel::reload_config) applies a reloaded
+//! file's execution limits to a running kernel.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::kernel::ExecutionConfig;
+use crate::sandbox::SandboxProfile;
+use crate::security::trust::{TrustError, TrustStore};
+
+/// Errors reading a configuration file
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Cannot read {path}: {source}")]
+    Io { path: PathBuf, source: std::io::Error },
+
+    #[error("Invalid kernel configuration: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Invalid trusted key: {0}")]
+    Trust(#[from] TrustError),
+}
+
+/// Kernel settings read from `esta-kernel.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KernelConfig {
+    /// Sandbox profile the execution limits start from
+    pub sandbox: Option<SandboxProfile>,
+    /// Directory modules are installed in
+    pub module_dir: Option<PathBuf>,
+    /// Directory the audit log is persisted in
+    pub audit_dir: Option<PathBuf>,
+    /// Hex-encoded Ed25519 keys trusted to sign modules; the last is current
+    pub trusted_keys: Vec<String>,
+    /// Execution limits overriding the sandbox profile's
+    pub execution: ExecutionOverrides,
+}
+
+/// Execution limits set in a configuration file; unset ones keep their defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionOverrides {
+    pub max_fuel: Option<u64>,
+    pub max_memory_bytes: Option<usize>,
+    pub call_timeout_ms: Option<u64>,
+    pub yield_fuel_cost: Option<u64>,
+    pub max_input_bytes: Option<usize>,
+    pub max_output_bytes: Option<usize>,
+    pub max_concurrent_invocations: Option<usize>,
+    pub max_queued_invocations: Option<usize>,
+    pub shutdown_drain_secs: Option<u64>,
+    pub require_signatures: Option<bool>,
+    pub require_abi_version: Option<bool>,
+    pub strict_capabilities: Option<bool>,
+    pub enforce_registry: Option<bool>,
+    pub pseudonymize_identifiers: Option<bool>,
+    /// Answer repeated invocations from the result cache (see [`crate::result_cache`])
+    pub result_cache: Option<bool>,
+    pub result_cache_entries: Option<usize>,
+    pub result_cache_ttl_secs: Option<u64>,
+}
+
+impl KernelConfig {
+    /// Read a configuration file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Io { path: path.to_path_buf(), source })?;
+        Self::from_toml(&contents)
+    }
+
+    /// Parse a configuration, checking its trusted keys
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(toml)?;
+        config.trust_store()?;
+        Ok(config)
+    }
+
+    /// The execution configuration this file describes
+    pub fn execution_config(&self) -> ExecutionConfig {
+        let mut config = match self.sandbox {
+            Some(profile) => ExecutionConfig::sandboxed(profile),
+            None => ExecutionConfig::default(),
+        };
+        let e = &self.execution;
+        if let Some(v) = e.max_fuel {
+            config.max_fuel = v;
+        }
+        if let Some(v) = e.max_memory_bytes {
+            config.max_memory_bytes = v;
+        }
+        if let Some(v) = e.call_timeout_ms {
+            config.call_timeout = Some(Duration::from_millis(v));
+        }
+        if let Some(v) = e.yield_fuel_cost {
+            config.yield_fuel_cost = v;
+        }
+        if let Some(v) = e.max_input_bytes {
+            config.max_input_bytes = v;
+        }
+        if let Some(v) = e.max_output_bytes {
+            config.max_output_bytes = v;
+        }
+        if let Some(v) = e.max_concurrent_invocations {
+            config.max_concurrent_invocations = v;
+        }
+        if let Some(v) = e.max_queued_invocations {
+            config.max_queued_invocations = v;
+        }
+        if let Some(v) = e.shutdown_drain_secs {
+            config.shutdown_drain_timeout = Duration::from_secs(v);
+        }
+        if let Some(v) = e.require_signatures {
+            config.require_signatures = v;
+        }
+        if let Some(v) = e.require_abi_version {
+            config.require_abi_version = v;
+        }
+        if let Some(v) = e.strict_capabilities {
+            config.strict_capabilities = v;
+        }
+        if let Some(v) = e.enforce_registry {
+            config.enforce_registry = v;
+        }
+        if let Some(v) = e.pseudonymize_identifiers {
+            config.pseudonymize_identifiers = v;
+        }
+        if let Some(v) = e.result_cache {
+            config.result_cache.enabled = v;
+        }
+        if let Some(v) = e.result_cache_entries {
+            config.result_cache.max_entries = v;
+        }
+        if let Some(v) = e.result_cache_ttl_secs {
+            config.result_cache.ttl = Duration::from_secs(v);
+        }
+        config
+    }
+
+    /// An in-memory trust store holding the configured keys, if there are any
+    pub fn trust_store(&self) -> Result<Option<TrustStore>, ConfigError> {
+        if self.trusted_keys.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(TrustStore::with_keys(&self.trusted_keys)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const KEY_B: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+
+    #[test]
+    fn test_parse_overrides_sandbox_limits() {
+        let config = KernelConfig::from_toml(&format!(
+            r#"
+            sandbox = "strict"
+            module_dir = "/var/lib/esta/modules"
+            trusted_keys = ["{}", "{}"]
+
+            [execution]
+            max_fuel = 1_000_000
+            call_timeout_ms = 2_500
+            max_concurrent_invocations = 8
+            "#,
+            KEY_A, KEY_B
+        ))
+        .unwrap();
+
+        assert_eq!(config.module_dir, Some(PathBuf::from("/var/lib/esta/modules")));
+        assert_eq!(config.audit_dir, None);
+
+        let execution = config.execution_config();
+        assert_eq!(execution.sandbox, Some(SandboxProfile::Strict));
+        assert_eq!(execution.max_fuel, 1_000_000);
+        assert_eq!(execution.call_timeout, Some(Duration::from_millis(2_500)));
+        assert_eq!(execution.max_concurrent_invocations, 8);
+        // Not overridden: still the strict profile's
+        assert!(execution.require_signatures);
+        assert_eq!(execution.max_memory_bytes, SandboxProfile::Strict.limits().max_memory_bytes);
+
+        let trust = config.trust_store().unwrap().unwrap();
+        assert_eq!(trust.keys().len(), 2);
+        assert_eq!(trust.current().unwrap().public_key, KEY_B);
+    }
+
+    #[test]
+    fn test_empty_file_is_default() {
+        let config = KernelConfig::from_toml("").unwrap();
+        assert_eq!(config, KernelConfig::default());
+        assert_eq!(config.execution_config(), ExecutionConfig::default());
+        assert!(config.trust_store().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings_and_bad_keys() {
+        assert!(matches!(KernelConfig::from_toml("max_fule = 5"), Err(ConfigError::Parse(_))));
+        assert!(matches!(
+            KernelConfig::from_toml("[execution]\nmax_fule = 5"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(KernelConfig::from_toml("sandbox = \"lax\""), Err(ConfigError::Parse(_))));
+        assert!(matches!(
+            KernelConfig::from_toml("trusted_keys = [\"abcd\"]"),
+            Err(ConfigError::Trust(TrustError::InvalidKey(_)))
+        ));
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("esta-kernel.toml");
+        assert!(matches!(KernelConfig::from_file(&path), Err(ConfigError::Io { .. })));
+
+        std::fs::write(
+            &path,
+            "audit_dir = \"audit\"\n[execution]\nmax_input_bytes = 4096\nresult_cache = true\nresult_cache_ttl_secs = 30\n",
+        )
+        .unwrap();
+        let config = KernelConfig::from_file(&path).unwrap();
+        assert_eq!(config.audit_dir, Some(PathBuf::from("audit")));
+        let execution = config.execution_config();
+        assert_eq!(execution.max_input_bytes, 4096);
+        assert!(execution.result_cache.enabled);
+        assert_eq!(execution.result_cache.ttl, Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,98 @@
+//! Request Correlation
+//!
+//! A correlation ID ties together everything one request caused: the
+//! frontend action, the kernel calls it made, and the audit entries they
+//! wrote. The caller runs the request inside [`scope`]; every audit entry
+//! appended while it runs carries the ID, so support can pull a failed
+//! accrual's whole trail with one audit query.
+//!
+//! The ID is task-local. Work the request hands to another task only keeps
+//! it when spawned through [`propagate`].
+
+use std::future::Future;
+
+/// Longest accepted caller-supplied ID
+const MAX_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// A fresh random correlation ID (32 hex characters)
+///
+/// # Panics
+/// Panics if the system RNG fails, like every other kernel identifier.
+pub fn new_id() -> String {
+    let rng = ring::rand::SystemRandom::new();
+    let bytes: [u8; 16] = ring::rand::generate(&rng)
+        .expect("System RNG failed - cannot generate correlation ID")
+        .expose();
+    hex::encode(bytes)
+}
+
+/// A caller-supplied ID if it is usable, otherwise a fresh one
+///
+/// Supplied IDs must be 1 to 64 ASCII letters, digits, `-`, `_`, `.`, or `:`,
+/// so they stay safe to log and to match in queries.
+pub fn accept(supplied: Option<&str>) -> String {
+    match supplied {
+        Some(id)
+            if !id.is_empty()
+                && id.len() <= MAX_ID_LEN
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')) =>
+        {
+            id.to_string()
+        }
+        Some(id) => {
+            let fresh = new_id();
+            log::warn!("Ignoring invalid correlation ID {:?}; using {}", id, fresh);
+            fresh
+        }
+        None => new_id(),
+    }
+}
+
+/// Run `future` with `id` as the current correlation ID
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    CORRELATION_ID.scope(id, future).await
+}
+
+/// The correlation ID of the running request, if any
+pub fn current() -> Option<String> {
+    CORRELATION_ID.try_with(Clone::clone).ok()
+}
+
+/// Wrap `future` to run under the current correlation ID, for spawning
+pub fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let id = current();
+    async move {
+        match id {
+            Some(id) => scope(id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ids_follow_the_request() {
+        assert_eq!(current(), None);
+        assert_eq!(accept(Some("ui-42:accrue")), "ui-42:accrue");
+        assert_eq!(accept(Some("bad id\n")).len(), 32);
+        assert_ne!(new_id(), new_id());
+
+        let (inside, spawned, detached) = scope("req-1".to_string(), async {
+            let spawned = tokio::spawn(propagate(async { current() }));
+            let detached = tokio::spawn(async { current() });
+            (current(), spawned.await.unwrap(), detached.await.unwrap())
+        })
+        .await;
+        assert_eq!(inside.as_deref(), Some("req-1"));
+        assert_eq!(spawned.as_deref(), Some("req-1"));
+        assert_eq!(detached, None);
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,850 @@
+//! SQLite Storage
+//!
+//! Tenants, their employee rosters, policy versions, ledger events, and an
+//! index of persisted audit segments can be kept in one SQLite database, so
+//! nothing the kernel holds in memory is lost when the application closes.
+//! The database is a durable copy: the kernel writes through to it (see
+//! [`crate::Kernel::with_database`]) and restores from it at startup.
+//!
+//! The schema is created and upgraded by numbered migrations. The number of
+//! the last one applied is kept in `PRAGMA user_version`; a database written
+//! by a newer kernel is refused rather than guessed at.
+//!
+//! Each table has a repository ([`Database::tenants`], ...). SQLite calls
+//! block, so they run on tokio's blocking pool behind async methods.
+//!
+//! ## Encryption at Rest
+//!
+//! A database opened with [`Database::open_encrypted`] seals employee IDs
+//! and ledger event details (the hours) with a [`FieldCipher`]; tenant IDs,
+//! dates, and sequence numbers stay readable so queries keep working. A
+//! sealed check value records that the database is encrypted and with which
+//! key, so opening it without the key, or with the wrong one, fails rather
+//! than returning ciphertext.
+//!
+//! An existing plaintext database is encrypted in place by
+//! [`Database::encrypt`] (`esta-kernel-cli database encrypt`), and
+//! [`Database::rotate_key`] re-seals every value under a new key. Both
+//! rewrite all values in one transaction.
+
+use crate::calendar::Date;
+use crate::error::StorageError;
+use crate::ledger::{LedgerEvent, NewLedgerEvent};
+use crate::policy::{PolicyHistory, PolicyVersion};
+use crate::security::FieldCipher;
+use crate::tenant::{TenantLifecycle, TenantStatus};
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Schema migrations, applied in order; never edit one that has shipped
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE tenants (
+        tenant_id TEXT PRIMARY KEY,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        archived_at INTEGER
+    );
+    CREATE TABLE employees (
+        tenant_id TEXT NOT NULL REFERENCES tenants(tenant_id) ON DELETE CASCADE,
+        employee_id TEXT NOT NULL,
+        PRIMARY KEY (tenant_id, employee_id)
+    );
+    CREATE TABLE policies (
+        tenant_id TEXT NOT NULL REFERENCES tenants(tenant_id) ON DELETE CASCADE,
+        version INTEGER NOT NULL,
+        effective_from TEXT NOT NULL,
+        effective_to TEXT,
+        recorded_at INTEGER NOT NULL,
+        policy TEXT NOT NULL,
+        PRIMARY KEY (tenant_id, version)
+    );
+    CREATE TABLE ledger_events (
+        sequence INTEGER PRIMARY KEY,
+        tenant_id TEXT NOT NULL REFERENCES tenants(tenant_id) ON DELETE CASCADE,
+        employee_id TEXT NOT NULL,
+        work_date TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        policy_version INTEGER,
+        source TEXT NOT NULL
+    );
+    CREATE INDEX ledger_events_by_employee ON ledger_events (tenant_id, employee_id);
+    CREATE TABLE audit_segments (
+        file TEXT PRIMARY KEY,
+        first_sequence INTEGER NOT NULL,
+        last_sequence INTEGER NOT NULL,
+        last_hash TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL
+    );",
+    // 2: database-wide settings, such as the encryption check value
+    "CREATE TABLE settings (
+        name TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+];
+
+/// Setting holding the sealed check value of an encrypted database
+const KEY_CHECK: &str = "key_check";
+
+/// Plaintext of the check value
+const KEY_CHECK_PLAINTEXT: &str = "esta-database";
+
+/// What [`crate::Kernel::restore_from_database`] loaded
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// Tenants the registry did not have
+    pub tenants_added: usize,
+    /// Employees on the stored rosters
+    pub employees: usize,
+    /// Ledger events appended
+    pub ledger_events: usize,
+}
+
+/// An audit segment file the database knows about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditSegmentRecord {
+    /// Segment file name within the audit directory
+    pub file: String,
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    /// Hash of the segment's last entry, continuing the chain
+    pub last_hash: String,
+    /// When the segment was indexed (ms since Unix epoch)
+    pub recorded_at: u64,
+}
+
+/// How employee IDs and event details are stored: in the clear, or sealed
+#[derive(Clone, Default)]
+struct Pii(Option<FieldCipher>);
+
+impl Pii {
+    fn seal(&self, column: &str, tenant_id: &str, value: String) -> String {
+        match &self.0 {
+            Some(cipher) => cipher.seal(&format!("{}:{}", column, tenant_id), &value),
+            None => value,
+        }
+    }
+
+    /// Open a value read from column `index`
+    fn open(&self, index: usize, column: &str, tenant_id: &str, value: String) -> rusqlite::Result<String> {
+        match &self.0 {
+            Some(cipher) => cipher.open(&format!("{}:{}", column, tenant_id), &value).map_err(|e| corrupt(index, e)),
+            None => Ok(value),
+        }
+    }
+}
+
+/// A SQLite database holding the kernel's records
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<(Connection, Pii)>>,
+    path: Option<PathBuf>,
+}
+
+impl Database {
+    /// Open (or create) a database file and bring its schema up to date
+    ///
+    /// An encrypted database is refused; open it with [`Self::open_encrypted`].
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let conn = Connection::open(&path)?;
+        Self::init(conn, Some(path), None)
+    }
+
+    /// Open (or create) a database whose employee records are sealed with `cipher`
+    ///
+    /// A new database is encrypted from the start. A plaintext one holding
+    /// records is refused until [`Self::encrypt`] has converted it.
+    pub fn open_encrypted(path: impl Into<PathBuf>, cipher: FieldCipher) -> Result<Self> {
+        let path = path.into();
+        let conn = Connection::open(&path)?;
+        Self::init(conn, Some(path), Some(cipher))
+    }
+
+    /// Create a database that lives only in memory
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?, None, None)
+    }
+
+    fn init(mut conn: Connection, path: Option<PathBuf>, cipher: Option<FieldCipher>) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        if path.is_some() {
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+        }
+        migrate(&mut conn)?;
+        let check = key_check(&conn)?;
+        match (&cipher, check) {
+            (None, None) => {}
+            (None, Some(_)) => {
+                return Err(StorageError::Encryption("the database is encrypted; open it with its key".into()).into())
+            }
+            (Some(cipher), Some(check)) => {
+                if cipher.open(KEY_CHECK, &check).ok().as_deref() != Some(KEY_CHECK_PLAINTEXT) {
+                    return Err(StorageError::Encryption("the key does not match the database".into()).into());
+                }
+            }
+            (Some(cipher), None) => {
+                let tenants: u64 = conn.query_row("SELECT COUNT(*) FROM tenants", [], |row| row.get(0))?;
+                if tenants > 0 {
+                    return Err(StorageError::Encryption(
+                        "the database holds unencrypted records; encrypt it first (esta-kernel-cli database encrypt)"
+                            .into(),
+                    )
+                    .into());
+                }
+                set_key_check(&conn, cipher)?;
+            }
+        }
+        Ok(Self { conn: Arc::new(Mutex::new((conn, Pii(cipher)))), path })
+    }
+
+    /// The database file, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of the last migration applied
+    pub async fn schema_version(&self) -> Result<u32> {
+        self.call(|conn, _| conn.pragma_query_value(None, "user_version", |row| row.get(0))).await
+    }
+
+    /// ID of the key employee records are sealed with; None if stored in the clear
+    pub async fn key_id(&self) -> Result<Option<String>> {
+        self.call(|_, pii| Ok(pii.0.as_ref().map(|cipher| cipher.key_id().to_string()))).await
+    }
+
+    /// Encrypt a plaintext database in place, returning the number of values sealed
+    pub async fn encrypt(&self, cipher: FieldCipher) -> Result<usize> {
+        if self.key_id().await?.is_some() {
+            return Err(StorageError::Encryption("the database is already encrypted; rotate its key instead".into()).into());
+        }
+        self.reseal(cipher).await
+    }
+
+    /// Re-seal every value of an encrypted database under `cipher`'s current key
+    ///
+    /// `cipher` must still open values sealed with the old key (see
+    /// [`FieldCipher::begin_rotation`]). Returns the number of values re-sealed.
+    pub async fn rotate_key(&self, cipher: FieldCipher) -> Result<usize> {
+        if self.key_id().await?.is_none() {
+            return Err(StorageError::Encryption("the database is not encrypted; encrypt it first".into()).into());
+        }
+        self.reseal(cipher).await
+    }
+
+    /// Rewrite employee IDs and event details under a new cipher, in one transaction
+    async fn reseal(&self, cipher: FieldCipher) -> Result<usize> {
+        self.call(move |conn, pii| {
+            let next = Pii(Some(cipher.clone()));
+            let tx = conn.transaction()?;
+            let mut resealed = 0;
+            {
+                let mut select = tx.prepare("SELECT tenant_id, employee_id FROM employees")?;
+                let rows = select
+                    .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let mut update =
+                    tx.prepare("UPDATE employees SET employee_id = ?3 WHERE tenant_id = ?1 AND employee_id = ?2")?;
+                for (tenant_id, stored) in rows {
+                    let employee_id = pii.open(1, "employee_id", &tenant_id, stored.clone())?;
+                    update.execute(params![tenant_id, stored, next.seal("employee_id", &tenant_id, employee_id)])?;
+                    resealed += 1;
+                }
+
+                let mut select = tx.prepare("SELECT sequence, tenant_id, employee_id, kind FROM ledger_events")?;
+                let rows = select
+                    .query_map([], |row| {
+                        Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let mut update = tx.prepare("UPDATE ledger_events SET employee_id = ?2, kind = ?3 WHERE sequence = ?1")?;
+                for (sequence, tenant_id, employee_id, kind) in rows {
+                    let employee_id = pii.open(2, "employee_id", &tenant_id, employee_id)?;
+                    let kind = pii.open(3, "kind", &tenant_id, kind)?;
+                    update.execute(params![
+                        sequence,
+                        next.seal("employee_id", &tenant_id, employee_id),
+                        next.seal("kind", &tenant_id, kind),
+                    ])?;
+                    resealed += 2;
+                }
+            }
+            set_key_check(&tx, &cipher)?;
+            tx.commit()?;
+            *pii = next;
+            Ok(resealed)
+        })
+        .await
+    }
+
+    /// Run a query on the blocking pool
+    async fn call<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection, &mut Pii) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut guard = conn.lock().unwrap_or_else(|e| e.into_inner());
+            let (conn, pii) = &mut *guard;
+            f(conn, pii)
+        })
+        .await?;
+        Ok(result?)
+    }
+
+    pub fn tenants(&self) -> TenantStore<'_> {
+        TenantStore { db: self }
+    }
+
+    pub fn employees(&self) -> EmployeeStore<'_> {
+        EmployeeStore { db: self }
+    }
+
+    pub fn policies(&self) -> PolicyStore<'_> {
+        PolicyStore { db: self }
+    }
+
+    pub fn ledger(&self) -> LedgerStore<'_> {
+        LedgerStore { db: self }
+    }
+
+    pub fn audit_segments(&self) -> AuditSegmentStore<'_> {
+        AuditSegmentStore { db: self }
+    }
+}
+
+/// Apply the migrations a database has not seen, each in its own transaction
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if applied > MIGRATIONS.len() {
+        return Err(StorageError::Corrupt(format!(
+            "database schema version {} is newer than this kernel supports ({})",
+            applied,
+            MIGRATIONS.len()
+        ))
+        .into());
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// The sealed check value, if the database is encrypted
+fn key_check(conn: &Connection) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM settings WHERE name = ?1", params![KEY_CHECK], |row| row.get(0))
+        .optional()
+}
+
+fn set_key_check(conn: &Connection, cipher: &FieldCipher) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (name, value) VALUES (?1, ?2)",
+        params![KEY_CHECK, cipher.seal(KEY_CHECK, KEY_CHECK_PLAINTEXT)],
+    )?;
+    Ok(())
+}
+
+/// Error for a column holding something the kernel cannot read back
+fn corrupt(column: usize, error: impl std::fmt::Display) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(
+        column,
+        rusqlite::types::Type::Text,
+        Box::new(StorageError::Corrupt(error.to_string())),
+    )
+}
+
+fn parse_date(column: usize, value: String) -> rusqlite::Result<Date> {
+    value.parse().map_err(|e| corrupt(column, e))
+}
+
+/// Tenants and their lifecycles
+pub struct TenantStore<'a> {
+    db: &'a Database,
+}
+
+impl TenantStore<'_> {
+    /// Insert a tenant or update its lifecycle
+    pub async fn save(&self, tenant_id: &str, lifecycle: &TenantLifecycle) -> Result<()> {
+        let tenant_id = tenant_id.to_string();
+        let lifecycle = lifecycle.clone();
+        let status = match lifecycle.status {
+            TenantStatus::Active => "active",
+            TenantStatus::Archived => "archived",
+        };
+        self.db
+            .call(move |conn, _| {
+                conn.execute(
+                    "INSERT INTO tenants (tenant_id, status, created_at, archived_at) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (tenant_id) DO UPDATE SET status = ?2, archived_at = ?4",
+                    params![tenant_id, status, lifecycle.created_at, lifecycle.archived_at],
+                )
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Every tenant, sorted by ID
+    pub async fn list(&self) -> Result<Vec<(String, TenantLifecycle)>> {
+        self.db
+            .call(|conn, _| {
+                let mut statement =
+                    conn.prepare("SELECT tenant_id, status, created_at, archived_at FROM tenants ORDER BY tenant_id")?;
+                let rows = statement.query_map([], |row| {
+                    let status = match row.get::<_, String>(1)?.as_str() {
+                        "active" => TenantStatus::Active,
+                        "archived" => TenantStatus::Archived,
+                        other => return Err(corrupt(1, format!("unknown tenant status {}", other))),
+                    };
+                    let lifecycle = TenantLifecycle { status, created_at: row.get(2)?, archived_at: row.get(3)? };
+                    Ok((row.get(0)?, lifecycle))
+                })?;
+                rows.collect()
+            })
+            .await
+    }
+
+    /// Remove a tenant with its employees, policies, and ledger events
+    ///
+    /// Returns false if the tenant was not stored.
+    pub async fn delete(&self, tenant_id: &str) -> Result<bool> {
+        let tenant_id = tenant_id.to_string();
+        let deleted = self
+            .db
+            .call(move |conn, _| conn.execute("DELETE FROM tenants WHERE tenant_id = ?1", params![tenant_id]))
+            .await?;
+        Ok(deleted > 0)
+    }
+}
+
+/// Employee rosters
+pub struct EmployeeStore<'a> {
+    db: &'a Database,
+}
+
+impl EmployeeStore<'_> {
+    /// Add employees to a stored tenant's roster, ignoring ones already on it
+    pub async fn add(&self, tenant_id: &str, employee_ids: Vec<String>) -> Result<()> {
+        let tenant_id = tenant_id.to_string();
+        self.db
+            .call(move |conn, pii| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement =
+                        tx.prepare("INSERT OR IGNORE INTO employees (tenant_id, employee_id) VALUES (?1, ?2)")?;
+                    for employee_id in employee_ids {
+                        statement.execute(params![tenant_id, pii.seal("employee_id", &tenant_id, employee_id)])?;
+                    }
+                }
+                tx.commit()
+            })
+            .await
+    }
+
+    /// A tenant's employees, sorted
+    pub async fn list(&self, tenant_id: &str) -> Result<Vec<String>> {
+        let tenant_id = tenant_id.to_string();
+        self.db
+            .call(move |conn, pii| {
+                let mut statement = conn.prepare("SELECT employee_id FROM employees WHERE tenant_id = ?1")?;
+                let rows = statement.query_map(params![tenant_id], |row| pii.open(0, "employee_id", &tenant_id, row.get(0)?))?;
+                // Sealed IDs sort by ciphertext, so order after opening
+                let mut employees = rows.collect::<rusqlite::Result<Vec<String>>>()?;
+                employees.sort();
+                Ok(employees)
+            })
+            .await
+    }
+}
+
+/// Policy version histories
+pub struct PolicyStore<'a> {
+    db: &'a Database,
+}
+
+impl PolicyStore<'_> {
+    /// Save policy versions of a stored tenant, replacing stored copies
+    ///
+    /// Pass the previous latest version along with a new one: appending a
+    /// version closes the range of the one before it.
+    pub async fn save(&self, tenant_id: &str, versions: &[PolicyVersion]) -> Result<()> {
+        let tenant_id = tenant_id.to_string();
+        let rows = versions
+            .iter()
+            .map(|v| Ok((v.clone(), serde_json::to_string(&v.policy)?)))
+            .collect::<Result<Vec<_>>>()?;
+        self.db
+            .call(move |conn, _| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement = tx.prepare(
+                        "INSERT OR REPLACE INTO policies (tenant_id, version, effective_from, effective_to, recorded_at, policy)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    )?;
+                    for (version, policy) in &rows {
+                        statement.execute(params![
+                            tenant_id,
+                            version.version,
+                            version.effective_from.to_string(),
+                            version.effective_to.map(|d| d.to_string()),
+                            version.recorded_at,
+                            policy,
+                        ])?;
+                    }
+                }
+                tx.commit()
+            })
+            .await
+    }
+
+    /// A tenant's policy history, oldest version first
+    pub async fn history(&self, tenant_id: &str) -> Result<PolicyHistory> {
+        let tenant_id = tenant_id.to_string();
+        let versions = self
+            .db
+            .call(move |conn, _| {
+                let mut statement = conn.prepare(
+                    "SELECT version, effective_from, effective_to, recorded_at, policy FROM policies
+                     WHERE tenant_id = ?1 ORDER BY version",
+                )?;
+                let rows = statement.query_map(params![tenant_id], |row| {
+                    Ok(PolicyVersion {
+                        version: row.get(0)?,
+                        effective_from: parse_date(1, row.get(1)?)?,
+                        effective_to: row.get::<_, Option<String>>(2)?.map(|d| parse_date(2, d)).transpose()?,
+                        recorded_at: row.get(3)?,
+                        policy: serde_json::from_str(&row.get::<_, String>(4)?).map_err(|e| corrupt(4, e))?,
+                    })
+                })?;
+                rows.collect()
+            })
+            .await?;
+        Ok(PolicyHistory::from_versions(versions))
+    }
+}
+
+/// Ledger events
+pub struct LedgerStore<'a> {
+    db: &'a Database,
+}
+
+impl LedgerStore<'_> {
+    /// Store recorded events atomically, skipping sequence numbers already stored
+    pub async fn append(&self, events: &[LedgerEvent]) -> Result<()> {
+        let rows = events
+            .iter()
+            .map(|e| Ok((e.clone(), serde_json::to_string(&e.event.kind)?)))
+            .collect::<Result<Vec<_>>>()?;
+        self.db
+            .call(move |conn, pii| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement = tx.prepare(
+                        "INSERT OR IGNORE INTO ledger_events
+                         (sequence, tenant_id, employee_id, work_date, recorded_at, kind, policy_version, source)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    )?;
+                    for (event, kind) in rows {
+                        let tenant_id = &event.event.tenant_id;
+                        statement.execute(params![
+                            event.sequence,
+                            tenant_id,
+                            pii.seal("employee_id", tenant_id, event.event.employee_id.clone()),
+                            event.event.work_date.to_string(),
+                            event.recorded_at,
+                            pii.seal("kind", tenant_id, kind),
+                            event.event.policy_version,
+                            event.event.source,
+                        ])?;
+                    }
+                }
+                tx.commit()
+            })
+            .await
+    }
+
+    /// Replace the employee ID of a tenant's events, by sequence number (see
+    /// [`crate::Ledger::redact_employees`])
+    pub async fn redact_employees(&self, tenant_id: &str, redactions: Vec<(u64, String)>) -> Result<()> {
+        let tenant_id = tenant_id.to_string();
+        self.db
+            .call(move |conn, pii| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement = tx
+                        .prepare("UPDATE ledger_events SET employee_id = ?1 WHERE sequence = ?2 AND tenant_id = ?3")?;
+                    for (sequence, employee_id) in redactions {
+                        let redacted = pii.seal("employee_id", &tenant_id, employee_id);
+                        statement.execute(params![redacted, sequence, tenant_id])?;
+                    }
+                }
+                tx.commit()
+            })
+            .await
+    }
+
+    /// Every stored event, in sequence order
+    pub async fn all(&self) -> Result<Vec<LedgerEvent>> {
+        self.query("SELECT * FROM ledger_events ORDER BY sequence", None).await
+    }
+
+    /// A tenant's events, in sequence order
+    pub async fn events_for_tenant(&self, tenant_id: &str) -> Result<Vec<LedgerEvent>> {
+        let sql = "SELECT * FROM ledger_events WHERE tenant_id = ?1 ORDER BY sequence";
+        self.query(sql, Some(tenant_id.to_string())).await
+    }
+
+    async fn query(&self, sql: &'static str, tenant_id: Option<String>) -> Result<Vec<LedgerEvent>> {
+        self.db
+            .call(move |conn, pii| {
+                let mut statement = conn.prepare(sql)?;
+                let rows = statement.query_map(rusqlite::params_from_iter(tenant_id), |row| {
+                    let tenant_id: String = row.get("tenant_id")?;
+                    let kind = pii.open(5, "kind", &tenant_id, row.get("kind")?)?;
+                    Ok(LedgerEvent {
+                        sequence: row.get("sequence")?,
+                        recorded_at: row.get("recorded_at")?,
+                        event: NewLedgerEvent {
+                            employee_id: pii.open(2, "employee_id", &tenant_id, row.get("employee_id")?)?,
+                            tenant_id,
+                            work_date: parse_date(3, row.get("work_date")?)?,
+                            kind: serde_json::from_str(&kind).map_err(|e| corrupt(5, e))?,
+                            policy_version: row.get("policy_version")?,
+                            source: row.get("source")?,
+                        },
+                    })
+                })?;
+                rows.collect()
+            })
+            .await
+    }
+}
+
+/// Index of persisted audit segments
+pub struct AuditSegmentStore<'a> {
+    db: &'a Database,
+}
+
+impl AuditSegmentStore<'_> {
+    /// Record a segment, replacing an earlier record of the same file
+    pub async fn record(&self, segment: &AuditSegmentRecord) -> Result<()> {
+        let segment = segment.clone();
+        self.db
+            .call(move |conn, _| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO audit_segments (file, first_sequence, last_sequence, last_hash, recorded_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        segment.file,
+                        segment.first_sequence,
+                        segment.last_sequence,
+                        segment.last_hash,
+                        segment.recorded_at
+                    ],
+                )
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// The segment holding an audit entry, if indexed
+    pub async fn containing(&self, sequence: u64) -> Result<Option<AuditSegmentRecord>> {
+        self.db
+            .call(move |conn, _| {
+                conn.query_row(
+                    "SELECT file, first_sequence, last_sequence, last_hash, recorded_at FROM audit_segments
+                     WHERE first_sequence <= ?1 AND ?1 <= last_sequence",
+                    params![sequence],
+                    segment_from_row,
+                )
+                .optional()
+            })
+            .await
+    }
+
+    /// Every indexed segment, in sequence order
+    pub async fn list(&self) -> Result<Vec<AuditSegmentRecord>> {
+        self.db
+            .call(|conn, _| {
+                let mut statement = conn.prepare(
+                    "SELECT file, first_sequence, last_sequence, last_hash, recorded_at FROM audit_segments
+                     ORDER BY first_sequence",
+                )?;
+                let rows = statement.query_map([], segment_from_row)?;
+                rows.collect()
+            })
+            .await
+    }
+}
+
+fn segment_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AuditSegmentRecord> {
+    Ok(AuditSegmentRecord {
+        file: row.get(0)?,
+        first_sequence: row.get(1)?,
+        last_sequence: row.get(2)?,
+        last_hash: row.get(3)?,
+        recorded_at: row.get(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::LedgerEventKind;
+    use crate::tenant::TenantPolicy;
+
+    fn accrued(sequence: u64, tenant_id: &str, employee_id: &str) -> LedgerEvent {
+        LedgerEvent {
+            sequence,
+            recorded_at: 1_700_000_000_000,
+            event: NewLedgerEvent {
+                tenant_id: tenant_id.into(),
+                employee_id: employee_id.into(),
+                work_date: "2025-03-03".parse().unwrap(),
+                kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+                policy_version: Some(1),
+                source: "test".into(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repositories_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("esta.db");
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), MIGRATIONS.len() as u32);
+
+        let lifecycle = TenantLifecycle { created_at: 1, ..Default::default() };
+        db.tenants().save("acme", &lifecycle).await.unwrap();
+        db.tenants().save("globex", &lifecycle).await.unwrap();
+        db.employees().add("acme", vec!["e2".into(), "e1".into(), "e1".into()]).await.unwrap();
+
+        let policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        let mut history = PolicyHistory::default();
+        history.append(policy.clone(), "2025-01-01".parse().unwrap(), 10).unwrap();
+        db.policies().save("acme", history.versions()).await.unwrap();
+        history.append(policy, "2026-01-01".parse().unwrap(), 20).unwrap();
+        db.policies().save("acme", history.versions()).await.unwrap();
+
+        db.ledger().append(&[accrued(0, "acme", "e1"), accrued(1, "globex", "e1")]).await.unwrap();
+        db.ledger().append(&[accrued(1, "globex", "e1"), accrued(2, "acme", "e2")]).await.unwrap();
+
+        let archived = TenantLifecycle { status: TenantStatus::Archived, created_at: 1, archived_at: Some(5) };
+        db.tenants().save("globex", &archived).await.unwrap();
+        drop(db);
+
+        let db = Database::open(&path).unwrap();
+        let tenants = db.tenants().list().await.unwrap();
+        assert_eq!(tenants, vec![("acme".to_string(), lifecycle), ("globex".to_string(), archived)]);
+        assert_eq!(db.employees().list("acme").await.unwrap(), vec!["e1", "e2"]);
+        assert_eq!(db.policies().history("acme").await.unwrap(), history);
+        let sequences: Vec<u64> = db.ledger().all().await.unwrap().iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, vec![0, 1, 2]);
+        assert_eq!(db.ledger().events_for_tenant("acme").await.unwrap()[1], accrued(2, "acme", "e2"));
+
+        // Deleting a tenant takes its rows with it
+        assert!(db.tenants().delete("acme").await.unwrap());
+        assert!(!db.tenants().delete("acme").await.unwrap());
+        assert!(db.employees().list("acme").await.unwrap().is_empty());
+        assert!(db.policies().history("acme").await.unwrap().is_empty());
+        assert_eq!(db.ledger().all().await.unwrap().len(), 1);
+
+        // Rows must belong to a stored tenant
+        assert!(db.employees().add("initech", vec!["e1".into()]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encryption_migration_and_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("esta.db");
+        let key = FieldCipher::new(&[1; 32]).unwrap();
+
+        // Start in the clear, then encrypt in place
+        let db = Database::open(&path).unwrap();
+        db.tenants().save("acme", &TenantLifecycle::default()).await.unwrap();
+        db.employees().add("acme", vec!["jane.doe".into()]).await.unwrap();
+        db.ledger().append(&[accrued(0, "acme", "jane.doe")]).await.unwrap();
+        drop(db);
+        let err = Database::open_encrypted(&path, key.clone()).err().unwrap();
+        assert!(err.to_string().contains("encrypt it first"));
+
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.encrypt(key.clone()).await.unwrap(), 3);
+        assert!(db.encrypt(key.clone()).await.is_err());
+        assert_eq!(db.key_id().await.unwrap().as_deref(), Some(key.key_id()));
+        db.employees().add("acme", vec!["john.roe".into(), "jane.doe".into()]).await.unwrap();
+        drop(db);
+
+        let raw = Connection::open(&path).unwrap();
+        let stored: Vec<String> = raw
+            .prepare("SELECT employee_id FROM employees UNION ALL SELECT employee_id || kind FROM ledger_events")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored.iter().all(|v| !v.contains(".doe") && !v.contains(".roe") && !v.contains("480")));
+        drop(raw);
+
+        assert!(Database::open(&path).err().unwrap().to_string().contains("open it with its key"));
+        let wrong = FieldCipher::new(&[2; 32]).unwrap();
+        assert!(Database::open_encrypted(&path, wrong).err().unwrap().to_string().contains("does not match"));
+
+        let db = Database::open_encrypted(&path, key.clone()).unwrap();
+        assert_eq!(db.employees().list("acme").await.unwrap(), vec!["jane.doe", "john.roe"]);
+        assert_eq!(db.ledger().all().await.unwrap(), vec![accrued(0, "acme", "jane.doe")]);
+
+        // Rotation re-seals everything; the old key no longer opens the database
+        let next = FieldCipher::new(&[3; 32]).unwrap().with_previous(&[1; 32]).unwrap();
+        assert_eq!(db.rotate_key(next).await.unwrap(), 4);
+        drop(db);
+        assert!(Database::open_encrypted(&path, key).is_err());
+        let db = Database::open_encrypted(&path, FieldCipher::new(&[3; 32]).unwrap()).unwrap();
+        assert_eq!(db.employees().list("acme").await.unwrap(), vec!["jane.doe", "john.roe"]);
+        assert_eq!(db.ledger().events_for_tenant("acme").await.unwrap()[0], accrued(0, "acme", "jane.doe"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_segment_index_and_schema_guard() {
+        let db = Database::in_memory().unwrap();
+        for (i, file) in ["000000.jsonl", "000100.jsonl"].into_iter().enumerate() {
+            let first_sequence = i as u64 * 100;
+            let segment = AuditSegmentRecord {
+                file: file.into(),
+                first_sequence,
+                last_sequence: first_sequence + 99,
+                last_hash: format!("hash{}", i),
+                recorded_at: 0,
+            };
+            db.audit_segments().record(&segment).await.unwrap();
+        }
+        assert_eq!(db.audit_segments().list().await.unwrap().len(), 2);
+        assert_eq!(db.audit_segments().containing(150).await.unwrap().unwrap().file, "000100.jsonl");
+        assert!(db.audit_segments().containing(200).await.unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("future.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+        drop(conn);
+        let err = Database::open(&path).err().unwrap();
+        assert!(err.to_string().contains("newer than this kernel supports"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Kernel and Storage Error Types
+//!
+//! Kernel operations return `anyhow::Result`, but the failures callers need to
+//! tell apart are raised as these typed errors so they can be recovered with
+//! `downcast_ref` (see `user_errors` for the user-facing mapping). Hosts raise
+//! `InvalidRequest` for requests they reject before reaching the kernel.
+
+use thiserror::Error;
+
+use crate::runtime::ModuleError;
+use crate::sandbox::SandboxProfile;
+use crate::security::SignatureError;
+use crate::trap::TrapKind;
+
+/// Errors raised by module loading and execution
+#[derive(Error, Debug)]
+pub enum KernelError {
+    #[error("Module {0} is not loaded")]
+    ModuleNotLoaded(String),
+
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Signature required but not provided for module {0}")]
+    SignatureRequired(String),
+
+    #[error("Signature verification required but no verifier configured")]
+    NoVerifierConfigured,
+
+    #[error("Signature verification failed for module {module}: {source}")]
+    SignatureInvalid {
+        module: String,
+        #[source]
+        source: SignatureError,
+    },
+
+    #[error("Modules may only be loaded from the module catalog")]
+    CatalogOnly,
+
+    #[error("Module {module} requests unknown capability {capability}")]
+    UnknownCapability { module: String, capability: String },
+
+    #[error("Module {module} requires capability {capability} (imports {import})")]
+    MissingCapability { module: String, import: String, capability: String },
+
+    #[error("Module {module} imports {import}, which the host does not provide")]
+    UnknownImport { module: String, import: String },
+
+    #[error("Module {module} called {import} without capability {capability}")]
+    CapabilityDenied { module: String, import: String, capability: String },
+
+    #[error("Module {0} uses WASI but no WASI root is configured")]
+    WasiNotConfigured(String),
+
+    #[error("Module {module} needs {capability}, which the interpreter backend does not provide")]
+    InterpreterUnsupported { module: String, capability: String },
+
+    #[error("Module {module} runs in the {sandbox} sandbox, which does not allow capability {capability}")]
+    SandboxDenied { module: String, sandbox: SandboxProfile, capability: String },
+
+    #[error("Module does not export linear memory")]
+    MissingMemoryExport,
+
+    #[error("Module {module} was built for ABI version {version}; this host supports versions {min} to {max}")]
+    AbiIncompatible { module: String, version: i32, min: u32, max: u32 },
+
+    #[error("Module {0} does not declare its ABI version (export __abi_version)")]
+    AbiVersionMissing(String),
+
+    #[error("Input of {0} bytes is too large")]
+    InputTooLarge(usize),
+
+    #[error("Function {function} returned {size} bytes, over the {limit} byte output limit")]
+    OutputTooLarge { function: String, size: usize, limit: usize },
+
+    #[error("Function {0} rejected its input")]
+    InputRejected(String),
+
+    #[error("Function {function} reported {}: {}", error.code, error.message)]
+    ModuleReported { function: String, error: ModuleError },
+
+    /// A trap reported by a backend without trap types of its own (the
+    /// interpreter), or injected by chaos testing
+    #[error("Module trapped ({kind}): {message}")]
+    Trapped { kind: TrapKind, message: String },
+
+    #[error("{module}::{function} did not finish within {timeout_ms} ms ({yields} yields)")]
+    CallTimedOut {
+        module: String,
+        function: String,
+        timeout_ms: u64,
+        yields: u32,
+    },
+
+    #[error("{module}::{function} exceeded its {resource} limit of {limit} (used {used}; 99th percentile {p99})")]
+    ResourceAnomaly {
+        module: String,
+        function: String,
+        /// `fuel` or `memory`
+        resource: String,
+        used: u64,
+        limit: u64,
+        p99: u64,
+    },
+
+    #[error("Module {module} already has {queued} invocations waiting")]
+    QueueFull { module: String, queued: usize },
+
+    #[error("Tenant {tenant_id} used {used} of its {ceiling} fuel ceiling; resets at {resets_at}")]
+    FuelCeilingReached {
+        tenant_id: String,
+        used: u64,
+        ceiling: u64,
+        /// Start of the next window (ms since Unix epoch)
+        resets_at: u64,
+    },
+
+    #[error("The kernel is shutting down")]
+    ShuttingDown,
+
+    #[error("{module}::{function} was cancelled (invocation {invocation_id})")]
+    Cancelled {
+        module: String,
+        function: String,
+        invocation_id: String,
+    },
+
+    #[error("No module catalog directory is configured")]
+    NoCatalogConfigured,
+
+    #[error("Module {0} is not in the module catalog")]
+    ModuleNotInCatalog(String),
+
+    #[error("Version {version} of module {module} is not in the version store")]
+    VersionNotStored { module: String, version: String },
+
+    #[error("Usage insights are not enabled for tenant {0}")]
+    InsightsNotEnabled(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+/// Errors raised by persistent storage (ledger, archive)
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    #[error("{0} is a read-only snapshot")]
+    ReadOnly(String),
+
+    #[error("Stored data is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Stored item not found: {0}")]
+    NotFound(String),
+
+    #[error("Audit log cannot be persisted: {0}")]
+    AuditUnavailable(String),
+
+    #[error("Database encryption: {0}")]
+    Encryption(String),
+
+    #[error("Stored data conflicts: {0}")]
+    Conflict(String),
+}
@@ -0,0 +1,302 @@
+//! Crash Forensics
+//!
+//! A trap's error message and backtrace say where a module failed, not what
+//! state it failed in. With crash dumps enabled ([`Kernel::with_crash_dumps`]),
+//! the kernel also saves the guest's linear memory (up to a size cap), the
+//! fuel it had left, and the failing function to a crash directory whenever
+//! an invocation traps. The dump's ID is recorded in the `ModuleCrashed`
+//! audit entry and on the returned `ModuleTrap`, so a failure that looks
+//! non-deterministic can be inspected after the fact.
+//!
+//! Guest memory holds whatever the module was working on, employee records
+//! included, so each dump is sealed with ChaCha20-Poly1305 under a key kept
+//! in the [`SecretStore`], bound to its ID. Only the newest dumps are kept.
+//!
+//! Traps that ran out of fuel or past a resource profile, timeouts, and
+//! cancelled calls are not dumped; they are audited as such instead. Nor are
+//! traps on the interpreter backend, which reports them without a backtrace
+//! and audits them as failed executions.
+//!
+//! [`Kernel::with_crash_dumps`]: crate::Kernel::with_crash_dumps
+
+use anyhow::Result;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::StorageError;
+use crate::runtime::WasmInstance;
+use crate::security::secrets::{SecretError, SecretResult, SecretStore};
+use crate::trap::{BacktraceFrame, TrapKind};
+
+/// Name of the crash dump key in the secret store
+pub const CRASH_DUMP_KEY: &str = "crash-dump-key";
+
+/// Extension of crash dump files
+const DUMP_EXTENSION: &str = "dump";
+
+/// How much of each crash to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrashDumpConfig {
+    /// Leading bytes of linear memory saved per dump (default 1 MiB)
+    pub max_memory_bytes: usize,
+    /// Dumps kept; the oldest are removed past this (default 32)
+    pub max_dumps: usize,
+}
+
+impl Default for CrashDumpConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: 1024 * 1024,
+            max_dumps: 32,
+        }
+    }
+}
+
+/// State of a module when one of its invocations trapped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashDump {
+    /// Time-ordered ID, as referenced from the audit log
+    pub id: String,
+    /// When the invocation trapped (Unix milliseconds)
+    pub timestamp: u64,
+    pub module_name: String,
+    pub function_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    pub invocation_id: String,
+    pub error: String,
+    pub trap: TrapKind,
+    /// Symbolized WASM frames, innermost first
+    pub backtrace: Vec<BacktraceFrame>,
+    /// Fuel the invocation was given
+    pub fuel_limit: u64,
+    pub fuel_consumed: u64,
+    pub fuel_remaining: u64,
+    /// Size of linear memory when the call trapped
+    pub memory_size: usize,
+    /// Leading bytes of linear memory (all of it unless over the size cap)
+    #[serde(skip)]
+    pub memory: Vec<u8>,
+}
+
+impl CrashDump {
+    /// Whether memory was cut short by the size cap
+    pub fn memory_truncated(&self) -> bool {
+        self.memory.len() < self.memory_size
+    }
+}
+
+/// Encrypted crash dumps in a directory
+///
+/// Dumps live at `<dir>/<id>.dump`; IDs sort oldest first.
+pub struct CrashDumps {
+    dir: PathBuf,
+    key: LessSafeKey,
+    config: CrashDumpConfig,
+}
+
+impl CrashDumps {
+    /// Dumps in `dir`, sealed with a 32-byte key
+    pub fn new(dir: impl Into<PathBuf>, key: &[u8]) -> SecretResult<Self> {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, key)
+            .map_err(|_| SecretError::Corrupt("crash dump key must be 32 bytes".into()))?;
+        Ok(Self {
+            dir: dir.into(),
+            key: LessSafeKey::new(key),
+            config: CrashDumpConfig::default(),
+        })
+    }
+
+    /// Dumps in `dir`, sealed with the store's key, generated on first use
+    pub fn from_store(dir: impl Into<PathBuf>, store: &mut SecretStore) -> SecretResult<Self> {
+        Self::new(dir, store.get_or_generate(CRASH_DUMP_KEY, 32)?.expose())
+    }
+
+    /// Use a memory cap and retention other than the defaults
+    pub fn with_config(mut self, config: CrashDumpConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Memory cap and retention in effect
+    pub fn config(&self) -> &CrashDumpConfig {
+        &self.config
+    }
+
+    /// Directory the dumps are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// A fresh time-ordered dump ID
+    pub fn next_id(timestamp: u64) -> String {
+        let mut suffix = [0u8; 4];
+        SystemRandom::new()
+            .fill(&mut suffix)
+            .expect("System RNG failed - cannot name crash dump");
+        format!("{:013}-{}", timestamp, hex::encode(suffix))
+    }
+
+    /// Copy the leading bytes of an instance's memory, up to the size cap
+    #[cfg_attr(not(feature = "wasmtime"), allow(dead_code))]
+    pub(crate) fn capture<I: WasmInstance>(&self, instance: &I) -> Option<Vec<u8>> {
+        if !instance.has_memory() {
+            return None;
+        }
+        let mut memory = vec![0u8; instance.memory_size().min(self.config.max_memory_bytes)];
+        instance.read_memory(0, &mut memory).ok()?;
+        Some(memory)
+    }
+
+    /// Seal and save a dump, then remove the oldest past the retention limit
+    pub async fn write(&self, dump: &CrashDump) -> Result<PathBuf> {
+        let header = serde_json::to_vec(dump)?;
+        let mut buffer = Vec::with_capacity(4 + header.len() + dump.memory.len() + NONCE_LEN);
+        buffer.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(&header);
+        buffer.extend_from_slice(&dump.memory);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| SecretError::Rng)?;
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(dump.id.as_bytes()), &mut buffer)
+            .map_err(|_| SecretError::Corrupt("sealing crash dump failed".into()))?;
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let path = self.path(&dump.id);
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, [nonce.as_slice(), &buffer].concat()).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        let ids = self.list().await?;
+        for old in ids.iter().take(ids.len().saturating_sub(self.config.max_dumps)) {
+            tokio::fs::remove_file(self.path(old)).await?;
+        }
+        Ok(path)
+    }
+
+    /// Open a dump by ID
+    pub async fn read(&self, id: &str) -> Result<CrashDump> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(StorageError::NotFound(format!("invalid crash dump ID {}", id)).into());
+        }
+        let bytes = match tokio::fs::read(self.path(id)).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(StorageError::NotFound(format!("crash dump {}", id)).into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let corrupt = || StorageError::Corrupt(format!("crash dump {} does not open", id));
+        if bytes.len() < NONCE_LEN {
+            return Err(corrupt().into());
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let mut buffer = sealed.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce.try_into().expect("nonce length")),
+                Aad::from(id.as_bytes()),
+                &mut buffer,
+            )
+            .map_err(|_| corrupt())?;
+
+        let header_len = plaintext
+            .get(..4)
+            .map(|len| u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize)
+            .filter(|len| 4 + len <= plaintext.len())
+            .ok_or_else(corrupt)?;
+        let mut dump: CrashDump = serde_json::from_slice(&plaintext[4..4 + header_len]).map_err(|_| corrupt())?;
+        dump.memory = plaintext[4 + header_len..].to_vec();
+        Ok(dump)
+    }
+
+    /// IDs of the saved dumps, oldest first
+    pub async fn list(&self) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ids),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == DUMP_EXTENSION) {
+                if let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, DUMP_EXTENSION))
+    }
+}
+
+impl std::fmt::Debug for CrashDumps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrashDumps")
+            .field("dir", &self.dir)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(id: String) -> CrashDump {
+        CrashDump {
+            id,
+            timestamp: 1_700_000_000_000,
+            module_name: "accrual".into(),
+            function_name: "calculate".into(),
+            tenant_id: Some("acme".into()),
+            invocation_id: "inv-1".into(),
+            error: "wasm trap: unreachable".into(),
+            trap: TrapKind::Unreachable,
+            backtrace: Vec::new(),
+            fuel_limit: 1_000,
+            fuel_consumed: 400,
+            fuel_remaining: 600,
+            memory_size: 65_536,
+            memory: b"employee emp-42".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dumps_are_sealed_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let dumps = CrashDumps::new(dir.path(), &[7; 32])
+            .unwrap()
+            .with_config(CrashDumpConfig { max_dumps: 2, ..Default::default() });
+
+        let first = dump(CrashDumps::next_id(1));
+        let path = dumps.write(&first).await.unwrap();
+        let on_disk = std::fs::read(&path).unwrap();
+        assert!(!on_disk.windows(6).any(|w| w == b"emp-42"));
+        let read = dumps.read(&first.id).await.unwrap();
+        assert_eq!(read, first);
+        assert!(read.memory_truncated());
+
+        // Another key, or the dump under another name, does not open
+        let other = CrashDumps::new(dir.path(), &[8; 32]).unwrap();
+        assert!(other.read(&first.id).await.is_err());
+        let renamed = CrashDumps::next_id(2);
+        std::fs::copy(&path, dumps.path(&renamed)).unwrap();
+        assert!(dumps.read(&renamed).await.is_err());
+        assert!(dumps.read("../secrets").await.is_err());
+
+        // Only the newest two are kept
+        let third = dump(CrashDumps::next_id(3));
+        dumps.write(&third).await.unwrap();
+        assert_eq!(dumps.list().await.unwrap(), vec![renamed, third.id]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Usage Pattern Insights
+//!
+//! Tenants can opt in (via `TenantPolicy::usage_insights`) to having each
+//! employee's sick time usage checked for statistically unusual patterns,
+//! such as consistent Monday/Friday usage. The analysis itself runs in the
+//! `analytics` WASM module (`libs/usage-analytics-wasm`) on data that never
+//! leaves the machine, and returns an explain trace for every employee.
+//!
+//! Insights are informational only. They never change balances, block usage,
+//! or appear in compliance reports. Opting in or out and every analysis run
+//! are recorded in the audit log.
+
+use crate::calendar::Date;
+use crate::ledger::{LedgerEvent, LedgerEventKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Kernel module that performs usage analysis
+pub const USAGE_ANALYTICS_MODULE: &str = "analytics";
+
+/// JSON ABI function of the analytics module
+pub const USAGE_ANALYSIS_FUNCTION: &str = "analyze_usage_json";
+
+/// Days of usage history analyzed, ending on the analysis date
+pub const USAGE_LOOKBACK_DAYS: i64 = 365;
+
+/// An unusual usage pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageInsight {
+    /// Pattern found (e.g. `weekend_adjacent_usage`)
+    pub kind: String,
+    /// Always `informational`
+    pub severity: String,
+    pub summary: String,
+    /// Workday usage days considered
+    pub usage_days: u32,
+    /// Usage days matching the pattern
+    pub matching_days: u32,
+    pub expected_share: f64,
+    pub observed_share: f64,
+    /// Probability of the pattern arising by chance
+    pub p_value: f64,
+}
+
+/// Analysis result for one employee
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmployeeUsageInsights {
+    pub employee_id: String,
+    pub insights: Vec<UsageInsight>,
+    /// Each step of the analysis, in order
+    pub explain: Vec<String>,
+}
+
+/// Usage insights for every employee of a tenant with usage in the period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageInsightsReport {
+    pub tenant_id: String,
+    pub period_start: Date,
+    pub period_end: Date,
+    /// When the report was generated (ms since Unix epoch)
+    pub generated_at: u64,
+    pub employees: Vec<EmployeeUsageInsights>,
+}
+
+impl UsageInsightsReport {
+    /// Total insights across all employees
+    pub fn insight_count(&self) -> usize {
+        self.employees.iter().map(|e| e.insights.len()).sum()
+    }
+}
+
+/// Build the analytics module input for each employee with usage in the period
+///
+/// Returns inputs keyed by employee ID. Usage is listed per date with its
+/// weekday, since the module has no calendar of its own.
+pub fn usage_analysis_inputs(
+    events: &[LedgerEvent],
+    period_start: Date,
+    period_end: Date,
+) -> BTreeMap<String, serde_json::Value> {
+    let mut usage: BTreeMap<&str, BTreeMap<Date, u64>> = BTreeMap::new();
+    for event in events {
+        let date = event.event.work_date;
+        if let LedgerEventKind::Used { minutes } = event.event.kind {
+            if period_start <= date && date <= period_end {
+                *usage
+                    .entry(event.event.employee_id.as_str())
+                    .or_default()
+                    .entry(date)
+                    .or_default() += minutes;
+            }
+        }
+    }
+
+    usage
+        .into_iter()
+        .map(|(employee_id, days)| {
+            let days: Vec<serde_json::Value> = days
+                .into_iter()
+                .map(|(date, minutes)| {
+                    serde_json::json!({
+                        "date": date,
+                        "weekday": date.weekday(),
+                        "minutes": minutes
+                    })
+                })
+                .collect();
+            let input = serde_json::json!({
+                "employee_id": employee_id,
+                "usage": days
+            });
+            (employee_id.to_string(), input)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::NewLedgerEvent;
+
+    fn used(sequence: u64, employee_id: &str, date: &str, minutes: u64) -> LedgerEvent {
+        LedgerEvent {
+            sequence,
+            recorded_at: 0,
+            event: NewLedgerEvent {
+                tenant_id: "acme".into(),
+                employee_id: employee_id.into(),
+                work_date: date.parse().unwrap(),
+                kind: LedgerEventKind::Used { minutes },
+                policy_version: Some(1),
+                source: "test".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_usage_analysis_inputs() {
+        let events = vec![
+            used(0, "e1", "2025-03-07", 240),
+            used(1, "e1", "2025-03-07", 240),
+            used(2, "e1", "2025-03-10", 480),
+            used(3, "e1", "2024-01-05", 480),
+            used(4, "e2", "2025-03-11", 60),
+        ];
+        let start: Date = "2025-01-01".parse().unwrap();
+        let inputs = usage_analysis_inputs(&events, start, start.add_days(364));
+
+        assert_eq!(inputs.len(), 2);
+        let e1 = &inputs["e1"];
+        assert_eq!(e1["usage"].as_array().unwrap().len(), 2);
+        assert_eq!(e1["usage"][0], serde_json::json!({"date": "2025-03-07", "weekday": "Friday", "minutes": 480}));
+        assert_eq!(e1["usage"][1]["weekday"], "Monday");
+        assert_eq!(inputs["e2"]["usage"][0]["weekday"], "Tuesday");
+    }
+}
@@ -9,22 +9,104 @@
 //! - Capability-based access control
 //! - Memory limits and safety bounds
 //! - Integrated audit logging
+//! - Cooperative yielding (`host_yield`) so long calls honour timeouts
+//! - Per-invocation cancellation, by handle or ID
+//! - Heartbeats (`host_heartbeat`) from resident modules, for hang detection
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use arc_swap::ArcSwap;
+use tokio::sync::{oneshot, watch, Notify, RwLock};
 use tokio::task::JoinHandle;
-use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
 
-use crate::security::{AuditLog, SignatureVerifier};
-use crate::security::audit::{AuditEvent, AuditEventType};
+use crate::alerts::{ledger_alerts, AlertMonitor};
+use crate::archive::{ArchiveRef, ContentStore, InvocationArchive};
+use crate::catalog::{compare_versions, read_manifest, CatalogEntry, InstalledModule, ModuleCatalog, ModuleVerification};
+use crate::security::{
+    ArchivedAuditStats, AuditHealth, AuditLog, CapabilityMetricsConfig, FieldCipher, KeyRotation, SecretStore,
+    TrustStore, TrustedKey,
+};
+use crate::security::capabilities::{
+    Capability as SecCapability, CapabilityManager, CapabilityResult, CapabilityRight, CapabilityToken,
+    CapabilityStats, CapabilityValidity, InstanceNonce, ReissuedToken, ResourceType,
+};
+use crate::security::audit::{AuditEvent, AuditEventType, AuditFilter, AuditQuery};
+use crate::sandbox::{SandboxLimits, SandboxProfile};
+use crate::security::pseudonym::{PseudonymMap, Pseudonymizer, PSEUDONYM_SECRET};
+use crate::security::secrets::CAPABILITY_SECRET;
+use crate::security::sig::ModuleSigner;
+#[cfg(feature = "chaos")]
+use crate::chaos::{Chaos, Fault};
+use crate::calendar::Date;
+use crate::backup::{BackupArchive, BackupContents, BackupSummary, BackupTenant, BACKUP_SIGNING_KEY};
+use crate::database::{Database, RestoreReport};
+use crate::forensics::{CrashDump, CrashDumps};
+use crate::error::{KernelError, StorageError};
+use crate::insights::{
+    usage_analysis_inputs, EmployeeUsageInsights, UsageInsightsReport, USAGE_ANALYSIS_FUNCTION,
+    USAGE_ANALYTICS_MODULE, USAGE_LOOKBACK_DAYS,
+};
+use crate::ledger::{Ledger, LedgerEvent, LedgerEventKind, NewLedgerEvent};
+#[cfg(not(feature = "interpreter"))]
+use crate::module_cache::ModuleCache;
+use crate::policy::PolicyVersion;
+use crate::profile::SecurityProfile;
+use crate::resource_profile::{ResourceLimits, ResourceProfile, ResourceProfileConfig, ResourceUsage};
+use crate::result_cache::{CacheKey, ResultCache, ResultCacheConfig, ResultCacheStats};
+use crate::retention::{self, RetentionReport};
+use crate::runtime::{self, WasmInstance, WasmRuntime};
+use crate::replay::{InvocationRecord, ReplayOutcome, ReplayReport, ReplayedInvocation};
+use crate::report::liability::{generate_liability_report, GlAccountMapping, LiabilityReport, WageRate};
+use crate::report::{apply_template, build_compliance_report, generate_compliance_report, ComplianceReport, CustomReport, ReportTemplate, TemplateError, TemplateVersion};
+use crate::stats_history::{StatsHistory, StatsRecord};
+use crate::tenant_usage::{TenantFuelConfig, TenantMeter, TenantUsage};
+use crate::statutes::{rules_for, StatuteBook, StatuteError, StatuteFile, StatuteVersion, DEFAULT_JURISDICTION};
+use crate::storage::{StorageLimits, StorageMaintenance};
+use crate::clock::now_millis;
+use crate::tenant::{
+    check_payload_scope, CachedPolicy, TenantError, TenantPolicy, TenantProvisioning, TenantPurgeReport, TenantRegistry,
+    TenantResult,
+};
+use crate::supervisor::Supervisor;
+use crate::trap::{format_backtrace, BacktraceFrame, TrapKind};
+#[cfg(not(feature = "interpreter"))]
+use crate::wasi::{Preopen, WasiCtx};
+
+use host_api::HostApi;
+
+mod host_api;
+#[cfg(feature = "interpreter")]
+mod interpreter;
+#[cfg(not(feature = "interpreter"))]
+mod wasmtime_backend;
+/// The backend modules run on
+#[cfg(not(feature = "interpreter"))]
+type Runtime = wasmtime_backend::WasmtimeRuntime;
+#[cfg(feature = "interpreter")]
+type Runtime = interpreter::InterpreterRuntime;
+type Module = <Runtime as WasmRuntime>::Module;
+type Instance = <Runtime as WasmRuntime>::Instance;
+
+/// Rights of the root capability provisioned for each tenant's namespace
+const TENANT_ROOT_RIGHTS: [CapabilityRight; 6] = [
+    CapabilityRight::Read,
+    CapabilityRight::Write,
+    CapabilityRight::Create,
+    CapabilityRight::Delete,
+    CapabilityRight::List,
+    CapabilityRight::Delegate,
+];
 
 /// Configuration for deterministic WASM execution
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ExecutionConfig {
     /// Maximum fuel (instructions) per invocation
     pub max_fuel: u64,
@@ -38,6 +120,51 @@ pub struct ExecutionConfig {
     pub max_instances: u32,
     /// Whether to enforce signature verification
     pub require_signatures: bool,
+    /// Load modules only from the module catalog, not by manifest path
+    pub enforce_registry: bool,
+    /// Reject manifests naming unknown capabilities instead of ignoring them
+    pub strict_capabilities: bool,
+    /// Refuse modules that do not export `__abi_version` instead of taking
+    /// them to speak ABI version 1 (see [`crate::runtime`])
+    pub require_abi_version: bool,
+    /// Replace employee identifiers in invocation inputs with pseudonyms
+    /// (see [`crate::security::pseudonym`])
+    pub pseudonymize_identifiers: bool,
+    /// Largest invocation input accepted, in bytes (default 1 MiB)
+    pub max_input_bytes: usize,
+    /// Largest output read back from the guest, in bytes (default 1 MiB)
+    pub max_output_bytes: usize,
+    /// Wall-clock limit per invocation (none by default)
+    ///
+    /// Only enforced when the guest calls `host_yield`; a guest that never
+    /// yields runs until it returns or exhausts its fuel.
+    pub call_timeout: Option<Duration>,
+    /// Fuel deducted each time the guest calls `host_yield`
+    pub yield_fuel_cost: u64,
+    /// Invocations of one module allowed to run at once (each in its own store)
+    pub max_concurrent_invocations: usize,
+    /// Invocations of one module allowed to wait; further calls are rejected
+    pub max_queued_invocations: usize,
+    /// How long shutdown waits for running and queued invocations to finish
+    pub shutdown_drain_timeout: Duration,
+    /// Directory holding the directories WASI modules may preopen (see
+    /// [`crate::wasi`]); modules granted `wasi` are refused when unset
+    pub wasi_root: Option<PathBuf>,
+    /// Anomaly limits learned from each module's recent invocations (see
+    /// [`crate::resource_profile`]); not enforced by default
+    pub resource_profile: ResourceProfileConfig,
+    /// Per-tenant fuel ceilings and pricing (see [`crate::tenant_usage`]);
+    /// unlimited and unpriced by default
+    pub tenant_fuel: TenantFuelConfig,
+    /// Capability validation metrics window and denial alert threshold (see
+    /// [`crate::security::capability_metrics`]); no alerts by default
+    pub capability_metrics: CapabilityMetricsConfig,
+    /// Sandbox profile the limits above were taken from, if any (see
+    /// [`ExecutionConfig::sandboxed`]); recorded when modules launch
+    pub sandbox: Option<SandboxProfile>,
+    /// Memoization of invocation outputs (see [`crate::result_cache`]); off
+    /// by default
+    pub result_cache: ResultCacheConfig,
 }
 
 impl Default for ExecutionConfig {
@@ -49,10 +176,282 @@ impl Default for ExecutionConfig {
             max_tables: 10,
             max_instances: 10,
             require_signatures: false, // Set to true in production
+            enforce_registry: false,
+            strict_capabilities: false,
+            require_abi_version: false,
+            pseudonymize_identifiers: true,
+            max_input_bytes: 1024 * 1024,
+            max_output_bytes: 1024 * 1024,
+            call_timeout: None,
+            yield_fuel_cost: 10_000, // Roughly one yield per 1M instructions costs 1%
+            max_concurrent_invocations: 4,
+            max_queued_invocations: 64,
+            shutdown_drain_timeout: Duration::from_secs(10),
+            wasi_root: None,
+            resource_profile: ResourceProfileConfig::default(),
+            tenant_fuel: TenantFuelConfig::default(),
+            capability_metrics: CapabilityMetricsConfig::default(),
+            sandbox: None,
+            result_cache: ResultCacheConfig::default(),
+        }
+    }
+}
+
+impl ExecutionConfig {
+    /// The default configuration with a sandbox profile's limits (see [`crate::sandbox`])
+    pub fn sandboxed(profile: SandboxProfile) -> Self {
+        let limits = profile.limits();
+        Self {
+            require_signatures: limits.require_signatures,
+            max_fuel: limits.max_fuel,
+            max_memory_bytes: limits.max_memory_bytes,
+            call_timeout: limits.call_timeout,
+            sandbox: Some(profile),
+            ..Self::default()
+        }
+    }
+
+    /// Names of the settings whose values differ in `other`
+    pub fn changed_settings(&self, other: &ExecutionConfig) -> Vec<&'static str> {
+        // Destructured so a new setting cannot be left out
+        let Self {
+            max_fuel,
+            max_memory_bytes,
+            min_memory_bytes,
+            max_tables,
+            max_instances,
+            require_signatures,
+            enforce_registry,
+            strict_capabilities,
+            require_abi_version,
+            pseudonymize_identifiers,
+            max_input_bytes,
+            max_output_bytes,
+            call_timeout,
+            yield_fuel_cost,
+            max_concurrent_invocations,
+            max_queued_invocations,
+            shutdown_drain_timeout,
+            wasi_root,
+            resource_profile,
+            tenant_fuel,
+            capability_metrics,
+            sandbox,
+            result_cache,
+        } = self;
+        [
+            ("max_fuel", *max_fuel != other.max_fuel),
+            ("max_memory_bytes", *max_memory_bytes != other.max_memory_bytes),
+            ("min_memory_bytes", *min_memory_bytes != other.min_memory_bytes),
+            ("max_tables", *max_tables != other.max_tables),
+            ("max_instances", *max_instances != other.max_instances),
+            ("require_signatures", *require_signatures != other.require_signatures),
+            ("enforce_registry", *enforce_registry != other.enforce_registry),
+            ("strict_capabilities", *strict_capabilities != other.strict_capabilities),
+            ("require_abi_version", *require_abi_version != other.require_abi_version),
+            ("pseudonymize_identifiers", *pseudonymize_identifiers != other.pseudonymize_identifiers),
+            ("max_input_bytes", *max_input_bytes != other.max_input_bytes),
+            ("max_output_bytes", *max_output_bytes != other.max_output_bytes),
+            ("call_timeout", *call_timeout != other.call_timeout),
+            ("yield_fuel_cost", *yield_fuel_cost != other.yield_fuel_cost),
+            ("max_concurrent_invocations", *max_concurrent_invocations != other.max_concurrent_invocations),
+            ("max_queued_invocations", *max_queued_invocations != other.max_queued_invocations),
+            ("shutdown_drain_timeout", *shutdown_drain_timeout != other.shutdown_drain_timeout),
+            ("wasi_root", *wasi_root != other.wasi_root),
+            ("resource_profile", *resource_profile != other.resource_profile),
+            ("tenant_fuel", *tenant_fuel != other.tenant_fuel),
+            ("capability_metrics", *capability_metrics != other.capability_metrics),
+            ("sandbox", *sandbox != other.sandbox),
+            ("result_cache", *result_cache != other.result_cache),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    /// The limits this configuration sets for every module
+    pub fn limits(&self) -> SandboxLimits {
+        SandboxLimits {
+            require_signatures: self.require_signatures,
+            max_fuel: self.max_fuel,
+            max_memory_bytes: self.max_memory_bytes,
+            call_timeout: self.call_timeout,
+            host_io: self.sandbox.is_none_or(|profile| profile.limits().host_io),
+        }
+    }
+}
+
+/// What a configuration reload changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigReload {
+    /// Settings that took a new value
+    pub changed: Vec<&'static str>,
+    /// Changed settings that keep their old value until the kernel restarts
+    pub restart_required: Vec<&'static str>,
+}
+
+/// Result of a single function invocation
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReport {
+    /// Module that served the call
+    pub module_name: String,
+    /// Exported function that was invoked
+    pub function_name: String,
+    /// JSON bytes returned by the guest (the `data` of a response envelope)
+    pub output: Vec<u8>,
+    /// Fuel consumed by this invocation
+    pub fuel_consumed: u64,
+    /// Where the input and output were archived, if selected for archival
+    pub archived: Option<ArchiveRef>,
+    /// Symbolized WASM frames if the invocation trapped (innermost first)
+    pub backtrace: Vec<BacktraceFrame>,
+    /// Whether the output came from the result cache without running the guest
+    pub cached: bool,
+}
+
+/// Error returned when a module traps
+///
+/// Carries the execution report, including the symbolized backtrace, so
+/// callers can recover it with `error.downcast_ref::<ModuleTrap>()`.
+#[derive(Debug, thiserror::Error)]
+#[error("Module {} trapped in {}: {message}\n{}", report.module_name, report.function_name, format_backtrace(&report.backtrace))]
+pub struct ModuleTrap {
+    /// The trap message
+    pub message: String,
+    /// How the module trapped
+    pub kind: TrapKind,
+    /// Report of the failed invocation
+    pub report: ExecutionReport,
+    /// ID of the crash dump saved for the trap, when crash dumps are enabled
+    pub crash_dump: Option<String>,
+}
+
+/// A call's result, the resources it used, and the guest memory captured
+/// for a crash dump if it failed
+type CallOutcome = (Result<Vec<u8>>, ResourceUsage, Option<Vec<u8>>);
+
+/// Cancels one running invocation
+///
+/// On the wasmtime backend, cancelling bumps the engine's epoch so the guest
+/// stops at its next loop or call even if it never yields; the interpreter
+/// stops it at its next `host_yield`. An invocation still queued for a slot
+/// leaves the queue. Either way it fails with `KernelError::Cancelled` and is
+/// audited as `InvocationCancelled` rather than as a trap.
+#[derive(Clone)]
+pub struct CancelHandle {
+    id: Arc<str>,
+    cancelled: Arc<watch::Sender<bool>>,
+    /// Engine whose epoch is bumped to interrupt running guests
+    #[cfg(not(feature = "interpreter"))]
+    engine: wasmtime::Engine,
+}
+
+impl CancelHandle {
+    /// ID of the invocation, as accepted by [`Kernel::cancel_invocation`]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Stop the invocation; cancelling one that has finished does nothing
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+        #[cfg(not(feature = "interpreter"))]
+        self.engine.increment_epoch();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Wait until the invocation is cancelled
+    async fn cancelled(&self) {
+        // The sender lives as long as `self`, so waiting cannot fail
+        let _ = self.cancelled.subscribe().wait_for(|cancelled| *cancelled).await;
+    }
+
+    fn error(&self, module_name: &str, function_name: &str) -> KernelError {
+        KernelError::Cancelled {
+            module: module_name.to_string(),
+            function: function_name.to_string(),
+            invocation_id: self.id.to_string(),
         }
     }
 }
 
+impl std::fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("id", &self.id)
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// A function invocation, run by awaiting it
+///
+/// Returned by [`Kernel::execute_function`] and its variants. Take a
+/// [`CancelHandle`] before awaiting to cancel the call from another task, or
+/// cancel it by ID with [`Kernel::cancel_invocation`]. IDs are generated
+/// unless one is given with [`Invocation::with_id`].
+#[must_use = "invocations do nothing unless awaited"]
+pub struct Invocation<'a> {
+    kernel: &'a Kernel,
+    tenant_id: Option<&'a str>,
+    module_name: &'a str,
+    function_name: &'a str,
+    input: &'a [u8],
+    dry_run: bool,
+    use_cache: bool,
+    handle: CancelHandle,
+}
+
+impl Invocation<'_> {
+    /// Use a caller-chosen ID, such as one the frontend can later cancel by
+    ///
+    /// Handles taken before the ID is set do not cancel the invocation.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.handle.id = id.into().into();
+        self.handle.cancelled = Arc::new(watch::channel(false).0);
+        self
+    }
+
+    /// Run the guest even if the result cache holds an output for this call
+    pub fn bypass_cache(mut self) -> Self {
+        self.use_cache = false;
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        self.handle.id()
+    }
+
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.handle.clone()
+    }
+}
+
+impl<'a> std::future::IntoFuture for Invocation<'a> {
+    type Output = Result<ExecutionReport>;
+    type IntoFuture = std::pin::Pin<Box<dyn std::future::Future<Output = Result<ExecutionReport>> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.kernel.run(self))
+    }
+}
+
+/// Keeps an invocation cancellable by ID while it runs
+struct RunningInvocation<'a> {
+    running: &'a std::sync::Mutex<HashMap<String, CancelHandle>>,
+    id: String,
+}
+
+impl Drop for RunningInvocation<'_> {
+    fn drop(&mut self) {
+        self.running.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
 /// Module execution statistics
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct ModuleStats {
@@ -64,16 +463,103 @@ pub struct ModuleStats {
     pub error_count: u64,
     /// Peak memory usage in bytes
     pub peak_memory_bytes: usize,
+    /// Traps by kind (a subset of `error_count`)
+    pub traps: BTreeMap<TrapKind, u64>,
+    /// Fuel and memory of recent successful invocations
+    pub resource_profile: ResourceProfile,
+}
+
+/// How a module's signature was checked when it was loaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// Verified by a trusted key; `retiring` if that key was in its grace period
+    Verified { key_id: String, retiring: bool },
+    /// The manifest carries no signature
+    Unsigned,
+    /// Signed, but no trust store was configured to check it
+    Unchecked,
+    /// The signature did not verify; loaded because signatures are not required
+    Invalid { reason: String },
+}
+
+impl SignatureStatus {
+    fn verified(key: &TrustedKey) -> Self {
+        Self::Verified { key_id: key.key_id.clone(), retiring: key.is_retiring() }
+    }
+}
+
+/// A running module's manifest, signature verification, grants, and statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub version: Option<String>,
+    /// Path the module was loaded from
+    pub path: String,
+    /// SHA-256 of the module bytes, verified at load
+    pub checksum: String,
+    pub signature: SignatureStatus,
+    /// The trusted key that verified the signature, as the trust store has it now
+    pub signing_key: Option<TrustedKey>,
+    /// Capabilities granted, as named in the manifest
+    pub capabilities: Vec<String>,
+    /// Sandbox profile the manifest asked for
+    pub sandbox: Option<SandboxProfile>,
+    /// Limits the module runs under
+    pub limits: SandboxLimits,
+    pub abi_version: Option<u32>,
+    pub stats: ModuleStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleManifest {
     pub name: String,
+    /// Module version (e.g. `1.2.0`), used by the module catalog
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
     pub path: String,
     pub checksum: String,
     pub capabilities: Vec<String>,
     /// Ed25519 signature (hex-encoded) for module verification
     pub signature: Option<String>,
+    /// Sandbox profile tightening the kernel's limits for this module (see [`crate::sandbox`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxProfile>,
+}
+
+impl ModuleManifest {
+    /// Build a signed manifest for a module file
+    ///
+    /// The checksum and signature are computed with the same scheme
+    /// [`Kernel::launch_module`] verifies. The name is the file stem and the
+    /// path is stored as given. Unknown capability names are rejected rather
+    /// than silently granting nothing.
+    pub fn generate(
+        wasm_path: impl AsRef<std::path::Path>,
+        signer: &ModuleSigner,
+        capabilities: Vec<String>,
+    ) -> Result<Self> {
+        let wasm_path = wasm_path.as_ref();
+        if let Some(unknown) = capabilities.iter().find(|cap| Capability::from_str(cap).is_none()) {
+            return Err(KernelError::InvalidRequest(format!("Unknown capability {}", unknown)).into());
+        }
+        let name = wasm_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| KernelError::InvalidRequest(format!("No module name in {}", wasm_path.display())))?;
+
+        let module_bytes = std::fs::read(wasm_path)?;
+        let checksum = hex::encode(Sha256::digest(&module_bytes));
+        Ok(Self {
+            name: name.to_string(),
+            version: None,
+            path: wasm_path.to_string_lossy().into_owned(),
+            signature: Some(signer.sign_module(&module_bytes, &checksum)),
+            checksum,
+            capabilities,
+            sandbox: None,
+        })
+    }
 }
 
 /// Capability tokens that can be granted to WASM modules
@@ -83,6 +569,15 @@ pub enum Capability {
     AuditEmit,
     PersistenceRead,
     PersistenceWrite,
+    PolicyRead,
+    /// The configuration of the tenant a call runs for (`config`)
+    Config,
+    /// WASI preview 1 imports (`wasi`)
+    Wasi,
+    /// Read-only WASI access to a directory under the WASI root (`fs_read:<dir>`)
+    FsRead(String),
+    /// Read-write WASI access to a directory under the WASI root (`fs_write:<dir>`)
+    FsWrite(String),
 }
 
 impl Capability {
@@ -92,7 +587,73 @@ impl Capability {
             "audit_emit" => Some(Capability::AuditEmit),
             "persistence_read" => Some(Capability::PersistenceRead),
             "persistence_write" => Some(Capability::PersistenceWrite),
-            _ => None,
+            "policy_read" => Some(Capability::PolicyRead),
+            "config" => Some(Capability::Config),
+            "wasi" => Some(Capability::Wasi),
+            _ => match s.split_once(':') {
+                Some(("fs_read", dir)) if Self::is_directory_name(dir) => Some(Capability::FsRead(dir.to_string())),
+                Some(("fs_write", dir)) if Self::is_directory_name(dir) => Some(Capability::FsWrite(dir.to_string())),
+                _ => None,
+            },
+        }
+    }
+
+    /// The capability linking a module import takes
+    ///
+    /// `Some(None)` for host functions every module gets; `None` for imports
+    /// the host does not provide.
+    fn for_import(module: &str, name: &str) -> Option<Option<Capability>> {
+        match (module, name) {
+            (crate::runtime::WASI_MODULE, _) => Some(Some(Capability::Wasi)),
+            _ => HostApi::function(module, name).map(|function| function.capability.clone()),
+        }
+    }
+
+    /// Whether the capability reaches outside the sandbox (files, or the network through WASI)
+    fn is_host_io(&self) -> bool {
+        matches!(self, Capability::Wasi | Capability::FsRead(_) | Capability::FsWrite(_))
+    }
+
+    /// A single plain path component, so a grant names exactly one directory under the root
+    fn is_directory_name(dir: &str) -> bool {
+        !dir.is_empty()
+            && dir != "."
+            && dir != ".."
+            && dir.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    }
+
+    /// Directories a module's WASI capabilities preopen, writable grants winning
+    #[cfg(not(feature = "interpreter"))]
+    fn preopens(capabilities: &[Capability]) -> Vec<Preopen> {
+        let mut preopens: Vec<Preopen> = Vec::new();
+        for capability in capabilities {
+            let (name, writable) = match capability {
+                Capability::FsRead(name) => (name, false),
+                Capability::FsWrite(name) => (name, true),
+                _ => continue,
+            };
+            match preopens.iter_mut().find(|p| p.name == *name) {
+                Some(existing) => existing.writable |= writable,
+                None => preopens.push(Preopen { name: name.clone(), writable }),
+            }
+        }
+        preopens
+    }
+}
+
+/// Manifest name of the capability, as `from_str` parses it
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::Log => f.write_str("log"),
+            Capability::AuditEmit => f.write_str("audit_emit"),
+            Capability::PersistenceRead => f.write_str("persistence_read"),
+            Capability::PersistenceWrite => f.write_str("persistence_write"),
+            Capability::PolicyRead => f.write_str("policy_read"),
+            Capability::Config => f.write_str("config"),
+            Capability::Wasi => f.write_str("wasi"),
+            Capability::FsRead(dir) => write!(f, "fs_read:{}", dir),
+            Capability::FsWrite(dir) => write!(f, "fs_write:{}", dir),
         }
     }
 }
@@ -100,11 +661,73 @@ impl Capability {
 /// Store data for WASM module execution
 pub struct ModuleStoreData {
     /// Granted capabilities
+    #[allow(dead_code)]
     capabilities: Vec<Capability>,
-    /// Store limits for resource control
-    limits: StoreLimits,
+    /// Store limits for resource control (the interpreter keeps its own)
+    #[cfg(not(feature = "interpreter"))]
+    limits: wasmtime_backend::GuestLimits,
     /// Module name for logging
     module_name: String,
+    /// Tenant on whose behalf the store runs, if any
+    tenant_id: Option<String>,
+    /// Nonce of the module instance this store belongs to
+    instance_nonce: InstanceNonce,
+    capability_manager: Arc<CapabilityManager>,
+    /// Source of the policy cache behind `host_policy_*`
+    tenants: Arc<TenantRegistry>,
+    /// What `host_get_config` returns (JSON), for stores running for a tenant
+    config: Option<Arc<[u8]>>,
+    /// Fuel charged per `host_yield` call
+    yield_fuel_cost: u64,
+    /// Number of `host_yield` calls so far
+    yields: u32,
+    /// Last `host_heartbeat` of every module
+    heartbeats: Arc<Heartbeats>,
+    /// WASI state, for modules granted `wasi`
+    #[cfg(not(feature = "interpreter"))]
+    wasi: Option<WasiCtx>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<Chaos>>,
+}
+
+/// Last heartbeat (Unix millis) of each module that has sent one
+type Heartbeats = std::sync::Mutex<HashMap<String, u64>>;
+
+/// When a module last called `host_heartbeat`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleHeartbeat {
+    pub module: String,
+    /// Unix millis
+    pub last_heartbeat: u64,
+}
+
+impl ModuleStoreData {
+    /// Validate a capability token presented by this store's module instance
+    ///
+    /// Tokens bound to another instance (another module, or an earlier launch
+    /// of this one) are rejected.
+    pub async fn validate_capability(
+        &self,
+        token: &CapabilityToken,
+        required_rights: &[CapabilityRight],
+    ) -> CapabilityResult<SecCapability> {
+        self.capability_manager
+            .validate_from_instance(token, &self.instance_nonce, required_rights)
+            .await
+    }
+
+    /// Cached policy of the tenant a guest names, or the `host_policy_*`
+    /// error code when a tenant's store names another tenant
+    fn policy_for(&self, tenant_id: &str) -> std::result::Result<Option<Arc<CachedPolicy>>, i32> {
+        if self.tenant_id.as_ref().is_some_and(|own| own != tenant_id) {
+            warn!(
+                "[{}] Policy of tenant {} requested from a store of tenant {:?}",
+                self.module_name, tenant_id, self.tenant_id
+            );
+            return Err(Kernel::HOST_POLICY_DENIED);
+        }
+        Ok(self.tenants.cached_policy(tenant_id))
+    }
 }
 
 /// Tracks running module instances for lifecycle management.
@@ -114,6 +737,240 @@ struct ModuleHandle {
     handle: JoinHandle<()>,
     capabilities: Vec<Capability>,
     stats: Arc<RwLock<ModuleStats>>,
+    /// Compiled module, instantiated fresh for every invocation
+    module: Module,
+    /// Nonce established at launch; shared by every store of this instance
+    instance_nonce: InstanceNonce,
+    /// Manifest of the module version that was launched
+    manifest: ModuleManifest,
+    /// How its signature was checked at launch
+    signature: SignatureStatus,
+    /// Limits its stores run under
+    limits: SandboxLimits,
+    /// ABI version the module declared, if it exports `__abi_version`
+    abi_version: Option<u32>,
+}
+
+/// Everything needed to run an invocation against a module
+struct Executable {
+    module: Module,
+    capabilities: Vec<Capability>,
+    stats: Arc<RwLock<ModuleStats>>,
+    instance_nonce: InstanceNonce,
+    checksum: String,
+    limits: SandboxLimits,
+}
+
+/// Queue depth of one module in the invocation scheduler
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvocationQueueStatus {
+    pub module: String,
+    pub running: usize,
+    pub queued: usize,
+}
+
+/// Admits invocations per module, up to a concurrency limit
+///
+/// Invocations beyond the limit wait in a bounded per-module queue. Waiting
+/// invocations are grouped by tenant and admitted round-robin across tenants,
+/// so one tenant's large batch cannot starve another's single request. When
+/// the queue is full, new invocations fail with `KernelError::QueueFull`.
+///
+/// Once closed for shutdown, new invocations fail with
+/// `KernelError::ShuttingDown` while admitted and queued ones run to completion.
+struct InvocationScheduler {
+    max_concurrent: usize,
+    max_queued: usize,
+    queues: std::sync::Mutex<HashMap<String, ModuleQueue>>,
+    closed: AtomicBool,
+    /// Notified whenever an invocation slot is freed
+    slot_freed: Notify,
+}
+
+#[derive(Default)]
+struct ModuleQueue {
+    running: usize,
+    /// Waiting invocations per tenant ("" for calls without a tenant)
+    waiting: HashMap<String, VecDeque<oneshot::Sender<InvocationPermit>>>,
+    /// Tenants with waiting invocations, in the order they are next served
+    rotation: VecDeque<String>,
+}
+
+impl ModuleQueue {
+    fn queued(&self) -> usize {
+        self.waiting.values().map(VecDeque::len).sum()
+    }
+
+    /// Forget invocations whose callers stopped waiting
+    fn drop_abandoned(&mut self) {
+        for waiters in self.waiting.values_mut() {
+            waiters.retain(|waiter| !waiter.is_closed());
+        }
+        self.waiting.retain(|_, waiters| !waiters.is_empty());
+        let waiting = &self.waiting;
+        self.rotation.retain(|tenant| waiting.contains_key(tenant));
+    }
+
+    /// Take the next waiter, rotating to the following tenant
+    fn next_waiter(&mut self) -> Option<oneshot::Sender<InvocationPermit>> {
+        let tenant = self.rotation.pop_front()?;
+        let waiters = self.waiting.get_mut(&tenant)?;
+        let waiter = waiters.pop_front();
+        if waiters.is_empty() {
+            self.waiting.remove(&tenant);
+        } else {
+            self.rotation.push_back(tenant);
+        }
+        waiter
+    }
+}
+
+/// Slot held by a running invocation; dropping it admits the next one
+struct InvocationPermit {
+    scheduler: Arc<InvocationScheduler>,
+    module: String,
+    /// Cleared when the slot is handed over rather than released
+    armed: bool,
+}
+
+impl InvocationScheduler {
+    fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+            max_queued,
+            queues: std::sync::Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            slot_freed: Notify::new(),
+        }
+    }
+
+    /// Wait for a slot to run an invocation of `module`
+    async fn admit(self: &Arc<Self>, module: &str, tenant_id: Option<&str>) -> Result<InvocationPermit> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(KernelError::ShuttingDown.into());
+        }
+        let waiting = {
+            let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+            let queue = queues.entry(module.to_string()).or_default();
+            queue.drop_abandoned();
+
+            if queue.running < self.max_concurrent && queue.rotation.is_empty() {
+                queue.running += 1;
+                return Ok(InvocationPermit {
+                    scheduler: self.clone(),
+                    module: module.to_string(),
+                    armed: true,
+                });
+            }
+
+            let queued = queue.queued();
+            if queued >= self.max_queued {
+                return Err(KernelError::QueueFull {
+                    module: module.to_string(),
+                    queued,
+                }
+                .into());
+            }
+
+            let tenant = tenant_id.unwrap_or_default().to_string();
+            let (sender, receiver) = oneshot::channel();
+            if !queue.waiting.contains_key(&tenant) {
+                queue.rotation.push_back(tenant.clone());
+            }
+            queue.waiting.entry(tenant).or_default().push_back(sender);
+            receiver
+        };
+
+        // The permit is handed over by the invocation that finishes; the
+        // sender is only dropped when shutdown abandons the queue
+        waiting.await.map_err(|_| KernelError::ShuttingDown.into())
+    }
+
+    /// Hand a finished invocation's slot to the next waiter, or free it
+    fn release(self: &Arc<Self>, module: &str) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(queue) = queues.get_mut(module) else {
+            return;
+        };
+
+        let mut permit = InvocationPermit {
+            scheduler: self.clone(),
+            module: module.to_string(),
+            armed: true,
+        };
+        while let Some(waiter) = queue.next_waiter() {
+            match waiter.send(permit) {
+                Ok(()) => return,
+                // The caller stopped waiting; try the next one
+                Err(returned) => permit = returned,
+            }
+        }
+
+        permit.armed = false;
+        queue.running = queue.running.saturating_sub(1);
+        self.slot_freed.notify_waiters();
+    }
+
+    /// Reject all invocations from now on
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Running and queued invocations of a module
+    fn pending(&self, module: &str) -> usize {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        queues.get_mut(module).map_or(0, |queue| {
+            queue.drop_abandoned();
+            queue.running + queue.queued()
+        })
+    }
+
+    /// Wait until a module has no running or queued invocations, or the
+    /// deadline passes; returns how many are still pending
+    async fn drain(&self, module: &str, deadline: tokio::time::Instant) -> usize {
+        loop {
+            let freed = self.slot_freed.notified();
+            let pending = self.pending(module);
+            if pending == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, freed).await.is_err() {
+                return self.pending(module);
+            }
+        }
+    }
+
+    /// Drop every queued invocation; their callers get `ShuttingDown`
+    fn abandon_queued(&self) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        for queue in queues.values_mut() {
+            queue.waiting.clear();
+            queue.rotation.clear();
+        }
+    }
+
+    fn status(&self) -> Vec<InvocationQueueStatus> {
+        let queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        let mut status: Vec<InvocationQueueStatus> = queues
+            .iter()
+            .map(|(module, queue)| InvocationQueueStatus {
+                module: module.clone(),
+                running: queue.running,
+                queued: queue.queued(),
+            })
+            .collect();
+        status.sort_by(|a, b| a.module.cmp(&b.module));
+        status
+    }
+}
+
+impl Drop for InvocationPermit {
+    fn drop(&mut self) {
+        if self.armed {
+            self.armed = false;
+            self.scheduler.release(&self.module);
+        }
+    }
 }
 
 /// Module registry for tracking active modules and orderly shutdown
@@ -128,12 +985,19 @@ impl ModuleRegistry {
         }
     }
 
-    pub fn register(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn register(
         &mut self,
         name: String,
         handle: JoinHandle<()>,
         capabilities: Vec<Capability>,
         stats: Arc<RwLock<ModuleStats>>,
+        module: Module,
+        instance_nonce: InstanceNonce,
+        manifest: ModuleManifest,
+        signature: SignatureStatus,
+        limits: SandboxLimits,
+        abi_version: Option<u32>,
     ) {
         self.modules.insert(
             name.clone(),
@@ -142,11 +1006,50 @@ impl ModuleRegistry {
                 handle,
                 capabilities,
                 stats,
+                module,
+                instance_nonce,
+                manifest,
+                signature,
+                limits,
+                abi_version,
             },
         );
     }
 
-    #[allow(dead_code)]
+    /// ABI version a running module declared
+    fn abi_version(&self, name: &str) -> Option<u32> {
+        self.modules.get(name).and_then(|h| h.abi_version)
+    }
+
+    /// Manifest, signature check, grants, limits, and statistics of a running module
+    async fn module_info(&self, name: &str) -> Option<ModuleInfo> {
+        let h = self.modules.get(name)?;
+        Some(ModuleInfo {
+            name: h.name.clone(),
+            version: h.manifest.version.clone(),
+            path: h.manifest.path.clone(),
+            checksum: h.manifest.checksum.clone(),
+            signature: h.signature.clone(),
+            signing_key: None,
+            capabilities: h.capabilities.iter().map(|cap| cap.to_string()).collect(),
+            sandbox: h.manifest.sandbox,
+            limits: h.limits,
+            abi_version: h.abi_version,
+            stats: h.stats.read().await.clone(),
+        })
+    }
+
+    /// Instance nonce of a running module
+    fn instance_nonce(&self, name: &str) -> Option<InstanceNonce> {
+        self.modules.get(name).map(|h| h.instance_nonce.clone())
+    }
+
+    /// Instance nonces of every running module
+    fn instance_nonces(&self) -> Vec<InstanceNonce> {
+        self.modules.values().map(|h| h.instance_nonce.clone()).collect()
+    }
+
+    /// Forget a module, returning its `_start` task
     pub(crate) fn unregister(&mut self, name: &str) -> Option<JoinHandle<()>> {
         self.modules.remove(name).map(|h| h.handle)
     }
@@ -165,15 +1068,47 @@ impl ModuleRegistry {
         }
     }
 
-    pub async fn shutdown_all(&mut self) {
+    /// Statistics of every module, sorted by name
+    async fn all_stats(&self) -> Vec<(String, ModuleStats)> {
+        let mut stats = Vec::with_capacity(self.modules.len());
+        for name in self.list_modules() {
+            stats.push((name.to_string(), self.modules[name].stats.read().await.clone()));
+        }
+        stats
+    }
+
+    /// Get everything needed to run an invocation against a module
+    fn get_executable(&self, name: &str) -> Option<Executable> {
+        self.modules.get(name).map(|h| Executable {
+            module: h.module.clone(),
+            capabilities: h.capabilities.clone(),
+            stats: h.stats.clone(),
+            instance_nonce: h.instance_nonce.clone(),
+            checksum: h.manifest.checksum.clone(),
+            limits: h.limits,
+        })
+    }
+
+    /// Abort every module's `_start` task and forget the modules
+    ///
+    /// Returns each module's name and whether its task was still running.
+    pub async fn shutdown_all(&mut self) -> Vec<(String, bool)> {
+        let mut stopped = Vec::with_capacity(self.modules.len());
         for (name, handle) in self.modules.drain() {
             info!("Shutting down module: {}", name);
+            let running = !handle.handle.is_finished();
             handle.handle.abort();
+            stopped.push((name, running));
         }
+        stopped.sort();
+        stopped
     }
 
+    /// Loaded module IDs in sorted order
     pub fn list_modules(&self) -> Vec<&str> {
-        self.modules.keys().map(|s| s.as_str()).collect()
+        let mut ids: Vec<&str> = self.modules.keys().map(|s| s.as_str()).collect();
+        ids.sort_unstable();
+        ids
     }
 }
 
@@ -185,11 +1120,48 @@ impl Default for ModuleRegistry {
 
 /// The ESTA Kernel - manages WASM module execution
 pub struct Kernel {
-    engine: Engine,
+    runtime: Runtime,
     registry: Arc<RwLock<ModuleRegistry>>,
-    config: ExecutionConfig,
-    signature_verifier: Option<SignatureVerifier>,
+    /// Replaced whole by [`Kernel::reload_config`]; see [`Kernel::config`]
+    config: ArcSwap<ExecutionConfig>,
+    trust_store: Option<Arc<TrustStore>>,
     audit_log: Arc<AuditLog>,
+    tenants: Arc<TenantRegistry>,
+    ledger: Arc<Ledger>,
+    /// Durable copy of tenants, rosters, policies, and the ledger
+    database: Option<Database>,
+    archive: Option<Arc<InvocationArchive>>,
+    catalog: Option<Arc<ModuleCatalog>>,
+    capability_manager: Arc<CapabilityManager>,
+    pseudonymizer: Arc<Pseudonymizer>,
+    /// Persists the capability secret; it lives only in memory when unset
+    secret_store: Option<Arc<std::sync::Mutex<SecretStore>>>,
+    /// Signs backups; its seed is kept in the secret store when one is set
+    backup_signer: Arc<ModuleSigner>,
+    /// Largest input recorded verbatim in the audit log (0 = hashes only)
+    recorded_input_limit: usize,
+    storage_limits: StorageLimits,
+    #[cfg(not(feature = "interpreter"))]
+    module_cache: Option<ModuleCache>,
+    scheduler: Arc<InvocationScheduler>,
+    /// Set when shutdown gives up waiting; cancels invocations at their next yield
+    abort_invocations: watch::Sender<bool>,
+    /// Running invocations by ID, for [`Kernel::cancel_invocation`]
+    invocations: std::sync::Mutex<HashMap<String, CancelHandle>>,
+    statutes: Arc<RwLock<StatuteBook>>,
+    /// Jurisdiction whose statute tenant policies are checked against
+    jurisdiction: String,
+    profile: SecurityProfile,
+    heartbeats: Arc<Heartbeats>,
+    /// Hourly module statistics kept across restarts
+    stats_history: Option<Arc<StatsHistory>>,
+    tenant_meter: Arc<TenantMeter>,
+    /// Outputs of recent invocations, used when `ExecutionConfig::result_cache` enables it
+    result_cache: ResultCache,
+    alerts: Arc<AlertMonitor>,
+    crash_dumps: Option<Arc<CrashDumps>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<Chaos>>,
 }
 
 impl Kernel {
@@ -200,410 +1172,5302 @@ impl Kernel {
 
     /// Create a new kernel with custom configuration
     pub fn with_config(config: ExecutionConfig) -> Result<Self> {
-        // Configure engine for deterministic execution
-        let mut engine_config = Config::new();
-        engine_config
-            .async_support(true)
-            .consume_fuel(true)  // Enable fuel metering
-            .epoch_interruption(false)  // Use fuel instead of epochs
-            .wasm_threads(false)  // Disable threads for determinism
-            .wasm_simd(true)  // SIMD is deterministic
-            .wasm_multi_memory(false)  // Single memory for simplicity
-            .wasm_memory64(false)  // 32-bit memory addresses
-            .cranelift_nan_canonicalization(true);  // Deterministic NaN handling
-
-        let engine = Engine::new(&engine_config)?;
+        let runtime = Runtime::new(&config)?;
+        let scheduler = InvocationScheduler::new(config.max_concurrent_invocations, config.max_queued_invocations);
+        let tenant_meter = TenantMeter::new(config.tenant_fuel.clone());
+        let audit_log = Arc::new(AuditLog::with_defaults());
+        let capability_manager = CapabilityManager::new(CapabilityManager::generate_secret()?)
+            .with_audit_log(audit_log.clone())
+            .with_metrics(config.capability_metrics.clone());
 
         Ok(Self {
-            engine,
+            runtime,
             registry: Arc::new(RwLock::new(ModuleRegistry::new())),
-            config,
-            signature_verifier: None,
-            audit_log: Arc::new(AuditLog::with_defaults()),
+            config: ArcSwap::from_pointee(config),
+            trust_store: None,
+            audit_log,
+            tenants: Arc::new(TenantRegistry::new()),
+            ledger: Arc::new(Ledger::new()),
+            database: None,
+            archive: None,
+            catalog: None,
+            capability_manager: Arc::new(capability_manager),
+            pseudonymizer: Arc::new(Pseudonymizer::new(&CapabilityManager::generate_secret()?)),
+            secret_store: None,
+            backup_signer: Arc::new(ModuleSigner::generate()?),
+            recorded_input_limit: 0,
+            storage_limits: StorageLimits::default(),
+            #[cfg(not(feature = "interpreter"))]
+            module_cache: None,
+            scheduler: Arc::new(scheduler),
+            abort_invocations: watch::channel(false).0,
+            invocations: std::sync::Mutex::new(HashMap::new()),
+            statutes: Arc::new(RwLock::new(StatuteBook::bundled())),
+            jurisdiction: DEFAULT_JURISDICTION.to_string(),
+            profile: SecurityProfile::default(),
+            heartbeats: Arc::new(Heartbeats::default()),
+            stats_history: None,
+            tenant_meter: Arc::new(tenant_meter),
+            result_cache: ResultCache::new(),
+            alerts: Arc::new(AlertMonitor::new()),
+            crash_dumps: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
         })
     }
 
-    /// Set the signature verifier for module verification
-    pub fn with_signature_verifier(mut self, public_key_hex: &str) -> Result<Self> {
-        self.signature_verifier = Some(SignatureVerifier::new(public_key_hex)?);
-        Ok(self)
+    /// Inject invocation traps, fuel starvation, and `host_yield` delays
+    /// according to a chaos schedule
+    ///
+    /// Audit write and heartbeat faults are injected by giving the same
+    /// schedule to `AuditLog::with_chaos` and `Supervisor::with_chaos`.
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Arc<Chaos>) -> Self {
+        self.chaos = Some(chaos);
+        self
     }
 
-    /// Get the audit log
-    pub fn audit_log(&self) -> Arc<AuditLog> {
-        self.audit_log.clone()
+    /// Apply a security profile's settings on top of the configuration
+    ///
+    /// A profile only tightens: flags it requires are switched on and its
+    /// call timeout replaces a longer (or missing) one, but settings stricter
+    /// than the profile are kept. Requirements the configuration cannot meet
+    /// by itself are reported by [`Kernel::profile_violations`].
+    pub fn with_profile(mut self, profile: SecurityProfile) -> Self {
+        let mut config = ExecutionConfig::clone(&self.config());
+        Self::apply_profile(&mut config, profile);
+        self.config.store(Arc::new(config));
+        self.profile = profile;
+        self
     }
 
-    /// Verify module checksum matches the actual bytes
-    fn verify_checksum(module_bytes: &[u8], expected_checksum: &str) -> Result<()> {
-        let mut hasher = Sha256::new();
-        hasher.update(module_bytes);
-        let actual_checksum = hex::encode(hasher.finalize());
-
-        if actual_checksum != expected_checksum {
-            return Err(anyhow!(
-                "Checksum mismatch: expected {}, got {}",
-                expected_checksum,
-                actual_checksum
-            ));
+    /// Tighten a configuration to a security profile's settings
+    fn apply_profile(config: &mut ExecutionConfig, profile: SecurityProfile) {
+        let settings = profile.settings();
+        config.require_signatures |= settings.require_signatures;
+        config.enforce_registry |= settings.enforce_registry;
+        config.strict_capabilities |= settings.strict_capabilities;
+        config.require_abi_version |= settings.require_abi_version;
+        if let Some(timeout) = settings.call_timeout {
+            config.call_timeout = Some(config.call_timeout.map_or(timeout, |t| t.min(timeout)));
         }
-        Ok(())
     }
 
-    /// Verify module signature using Ed25519
-    fn verify_signature(&self, module_bytes: &[u8], manifest: &ModuleManifest) -> Result<()> {
-        if self.config.require_signatures {
-            let signature = manifest.signature.as_ref()
-                .ok_or_else(|| anyhow!("Signature required but not provided for module {}", manifest.name))?;
-
-            let verifier = self.signature_verifier.as_ref()
-                .ok_or_else(|| anyhow!("Signature verification required but no verifier configured"))?;
+    /// The security profile in effect
+    pub fn profile(&self) -> SecurityProfile {
+        self.profile
+    }
 
-            verifier.verify_module(module_bytes, &manifest.checksum, signature)
-                .map_err(|e| anyhow!("Signature verification failed for module {}: {}", manifest.name, e))?;
+    /// The execution configuration in effect, with the security profile applied
+    pub fn config(&self) -> Arc<ExecutionConfig> {
+        self.config.load_full()
+    }
 
-            info!("Signature verified for module {}", manifest.name);
-        } else {
-            // Development mode - warn about missing signatures
-            match &manifest.signature {
-                Some(sig) if !sig.is_empty() => {
-                    if let Some(verifier) = &self.signature_verifier {
-                        match verifier.verify_module(module_bytes, &manifest.checksum, sig) {
-                            Ok(()) => info!("Signature verified for module {}", manifest.name),
-                            Err(e) => warn!("Signature verification failed for module {} (dev mode): {}", manifest.name, e),
-                        }
-                    }
-                }
-                _ => {
-                    warn!(
-                        "No signature provided for module {}. This is acceptable for dev only.",
-                        manifest.name
-                    );
-                }
-            }
+    /// Replace the execution configuration while running
+    ///
+    /// The security profile is applied on top, as at startup, so a reload
+    /// cannot loosen what the profile requires. Size, memory, and WASI
+    /// settings apply from the next invocation; per-module limits (fuel, call
+    /// timeout, signatures, sandbox) to modules loaded from now on. The
+    /// settings in `restart_required` keep their startup values. The reload
+    /// is audited as `ConfigReloaded`.
+    pub async fn reload_config(&self, mut config: ExecutionConfig) -> ConfigReload {
+        Self::apply_profile(&mut config, self.profile);
+        let current = self.config();
+        let requested = config.clone();
+        Self::keep_startup_settings(&mut config, &current);
+        let changed = current.changed_settings(&requested);
+        let restart_required = config.changed_settings(&requested);
+        if !restart_required.is_empty() {
+            warn!("Configuration reload: {} take effect on restart", restart_required.join(", "));
         }
-        Ok(())
+        self.config.store(Arc::new(config));
+        info!("Configuration reloaded; changed: {:?}", changed);
+        self.audit_log.log_config_reloaded(&changed, &restart_required, "kernel").await;
+        ConfigReload { changed, restart_required }
     }
 
-    /// Parse and validate capabilities from manifest
-    fn parse_capabilities(manifest: &ModuleManifest) -> Vec<Capability> {
-        manifest
-            .capabilities
-            .iter()
-            .filter_map(|cap| Capability::from_str(cap))
-            .collect()
+    /// Put back the settings a running kernel cannot change
+    fn keep_startup_settings(config: &mut ExecutionConfig, current: &ExecutionConfig) {
+        // The invocation scheduler, tenant meter, and capability metrics are built once
+        config.max_concurrent_invocations = current.max_concurrent_invocations;
+        config.max_queued_invocations = current.max_queued_invocations;
+        config.tenant_fuel = current.tenant_fuel.clone();
+        config.capability_metrics = current.capability_metrics.clone();
+        // The interpreter fixes its memory and table limits when it is created
+        #[cfg(feature = "interpreter")]
+        {
+            config.max_memory_bytes = current.max_memory_bytes;
+            config.max_tables = current.max_tables;
+            config.max_instances = current.max_instances;
+        }
     }
 
-    /// Maximum allowed size for WASM memory operations
-    const MAX_WASM_MEMORY_SIZE: i32 = 1_048_576; // 1MB
-
-    /// Register host functions based on granted capabilities
-    fn register_host_functions(
-        linker: &mut Linker<ModuleStoreData>,
-        capabilities: &[Capability],
-    ) -> Result<()> {
-        if capabilities.contains(&Capability::Log) {
-            linker.func_wrap("env", "host_log", |caller: Caller<'_, ModuleStoreData>, level: i32, ptr: i32, len: i32| {
-                if ptr < 0 || len < 0 || len > Self::MAX_WASM_MEMORY_SIZE {
-                    warn!("WASM log: invalid parameters (ptr={}, len={})", ptr, len);
-                    return;
-                }
-                let module_name = &caller.data().module_name;
-                info!("[{}] WASM log (level={}, ptr={}, len={})", module_name, level, ptr, len);
-            })?;
+    /// Requirements of the security profile this kernel does not meet
+    ///
+    /// Check after all `with_*` builders have run; hosts should refuse to
+    /// start when this is not empty outside development.
+    pub fn profile_violations(&self) -> Vec<String> {
+        let settings = self.profile.settings();
+        let mut violations = Vec::new();
+        if settings.require_signatures && self.trust_store.is_none() {
+            violations.push("signatures are required but no trusted signing key is configured".to_string());
         }
-
-        if capabilities.contains(&Capability::AuditEmit) {
-            linker.func_wrap("env", "host_audit_emit", |caller: Caller<'_, ModuleStoreData>, event_type: i32, ptr: i32, len: i32| {
-                if ptr < 0 || len < 0 || len > Self::MAX_WASM_MEMORY_SIZE {
-                    warn!("WASM audit emit: invalid parameters (ptr={}, len={})", ptr, len);
-                    return;
-                }
-                let module_name = &caller.data().module_name;
-                info!("[{}] WASM audit emit (type={}, ptr={}, len={})", module_name, event_type, ptr, len);
-            })?;
+        if settings.enforce_registry && self.catalog.is_none() {
+            violations.push("modules must come from the module catalog but no catalog is configured".to_string());
         }
-
-        Ok(())
+        // A read replica never writes the audit log; the primary persists it
+        if settings.persist_audit && self.audit_log.segment_dir().is_none() && !self.is_read_only() {
+            violations.push("the audit log must be persisted but is kept in memory only".to_string());
+        }
+        violations
     }
 
-    /// Create a store with deterministic configuration and resource limits
-    fn create_store(&self, capabilities: Vec<Capability>, module_name: String) -> Store<ModuleStoreData> {
-        let limits = StoreLimitsBuilder::new()
-            .memory_size(self.config.max_memory_bytes)
-            .tables(self.config.max_tables as usize)
-            .instances(self.config.max_instances as usize)
-            .build();
-
-        let store_data = ModuleStoreData {
-            capabilities,
-            limits,
-            module_name,
-        };
+    /// Set the signature verifier for module verification
+    pub fn with_signature_verifier(mut self, public_key_hex: &str) -> Result<Self> {
+        self.trust_store = Some(Arc::new(TrustStore::new(public_key_hex)?));
+        Ok(self)
+    }
 
-        let mut store = Store::new(&self.engine, store_data);
-        
-        // Add fuel for this execution (fuel consumption is enabled in engine config)
-        let _ = store.add_fuel(self.config.max_fuel);
-        
-        // Enable resource limiting
-        store.limiter(|data| &mut data.limits);
+    /// Verify signatures against a trust store, which supports key rotation
+    pub fn with_trust_store(mut self, trust_store: TrustStore) -> Self {
+        self.trust_store = Some(Arc::new(trust_store));
+        self
+    }
 
-        store
+    /// Get the trust store, if signature verification is configured
+    pub fn trust_store(&self) -> Option<Arc<TrustStore>> {
+        self.trust_store.clone()
+    }
+
+    /// Make `new_key` the module signing key, trusting the current key for `grace_period`
+    ///
+    /// Modules signed by either key load during the grace period; afterwards
+    /// the old key's signatures are rejected. The rotation is audited.
+    pub async fn rotate_signing_key(&self, new_key: &str, grace_period: Duration) -> Result<KeyRotation> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Trust store".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+        let trust_store = self.trust_store.as_ref().ok_or(KernelError::NoVerifierConfigured)?;
+
+        let rotation = trust_store.rotate(new_key, grace_period)?;
+        info!(
+            "Module signing key rotated to {}; retiring {:?}",
+            rotation.current.key_id,
+            rotation.retiring.iter().map(|key| &key.key_id).collect::<Vec<_>>()
+        );
+        self.audit_log
+            .log_signing_key_rotated(
+                &rotation.current.key_id,
+                rotation.retiring.iter().map(|key| key.key_id.clone()).collect(),
+                rotation.retiring.iter().filter_map(|key| key.retires_at).max(),
+                "kernel",
+            )
+            .await;
+        Ok(rotation)
+    }
+
+    /// Use the given audit log (e.g. one persisted to segment files)
+    ///
+    /// Capability operations are recorded there too.
+    pub fn with_audit_log(mut self, audit_log: AuditLog) -> Self {
+        self.audit_log = Arc::new(audit_log);
+        self.capability_manager.attach_audit_log(self.audit_log.clone());
+        self
+    }
+
+    /// Cache compiled modules in a directory so later launches skip compilation
+    ///
+    /// Entries are authenticated with a key kept in `store` (see
+    /// [`crate::module_cache`]). Only the wasmtime backend compiles modules,
+    /// so the interpreter backend has no cache.
+    #[cfg(not(feature = "interpreter"))]
+    pub fn with_module_cache(mut self, dir: impl Into<std::path::PathBuf>, store: &mut SecretStore) -> Result<Self> {
+        self.module_cache = Some(ModuleCache::from_store(dir, self.runtime.engine(), store)?);
+        Ok(self)
+    }
+
+    /// Keep the capability secret in an encrypted secret store
+    ///
+    /// The stored secret is used, or generated and stored on first run; the
+    /// same holds for the pseudonym secret and the backup signing key. Call
+    /// before issuing any capabilities: the capability manager is replaced
+    /// with an empty one. Only the secret is persisted, not the capabilities,
+    /// so tokens issued before a restart no longer validate after it.
+    pub fn with_secret_store(mut self, mut store: SecretStore) -> Result<Self> {
+        let secret = store.get_or_generate(CAPABILITY_SECRET, 32)?;
+        self.capability_manager = Arc::new(
+            CapabilityManager::new(secret.expose().to_vec())
+                .with_audit_log(self.audit_log.clone())
+                .with_metrics(self.config().capability_metrics.clone()),
+        );
+        let secret = store.get_or_generate(PSEUDONYM_SECRET, 32)?;
+        self.pseudonymizer = Arc::new(Pseudonymizer::new(secret.expose()));
+        self.backup_signer = Arc::new(store.module_signer(BACKUP_SIGNING_KEY)?);
+        self.secret_store = Some(Arc::new(std::sync::Mutex::new(store)));
+        Ok(self)
+    }
+
+    /// Archive selected invocations' input and output for later replay
+    pub fn with_archive(mut self, archive: InvocationArchive) -> Self {
+        self.archive = Some(Arc::new(archive));
+        self
+    }
+
+    /// Use the given tenant registry (e.g. one backed by a policy file)
+    pub fn with_tenant_registry(mut self, tenants: TenantRegistry) -> Self {
+        self.tenants = Arc::new(tenants);
+        self
+    }
+
+    /// Use the given accrual ledger (e.g. one backed by a file)
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = Arc::new(ledger);
+        self
+    }
+
+    /// Write tenants, rosters, policies, and ledger events through to a database
+    ///
+    /// Call [`Kernel::restore_from_database`] at startup to load what it holds.
+    pub fn with_database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Record invocation inputs of up to `max_bytes` in the audit log
+    ///
+    /// Inputs are always recorded by hash. Recording the input itself lets
+    /// [`Kernel::replay`] re-execute invocations that were not archived.
+    pub fn with_recorded_inputs(mut self, max_bytes: usize) -> Self {
+        self.recorded_input_limit = max_bytes;
+        self
+    }
+
+    /// Use a modules directory for listing and installing modules by name
+    pub fn with_module_catalog(mut self, catalog: ModuleCatalog) -> Self {
+        self.catalog = Some(Arc::new(catalog));
+        self
+    }
+
+    /// Set the size alert threshold and archive retention used by [`Kernel::storage`]
+    pub fn with_storage_limits(mut self, limits: StorageLimits) -> Self {
+        self.storage_limits = limits;
+        self
+    }
+
+    /// Keep hourly module statistics (see [`crate::stats_history`])
+    pub fn with_stats_history(mut self, history: StatsHistory) -> Self {
+        self.stats_history = Some(Arc::new(history));
+        self
+    }
+
+    /// Apply the statute of a jurisdiction other than the default (`US-MI`)
+    pub fn with_jurisdiction(mut self, jurisdiction: impl Into<String>) -> Self {
+        self.jurisdiction = jurisdiction.into();
+        self
+    }
+
+    /// Raise compliance alerts through this monitor (see [`crate::alerts`])
+    pub fn with_alert_monitor(mut self, alerts: AlertMonitor) -> Self {
+        self.alerts = Arc::new(alerts);
+        self
+    }
+
+    /// Save guest memory and fuel state when invocations trap (see [`crate::forensics`])
+    pub fn with_crash_dumps(mut self, dumps: CrashDumps) -> Self {
+        self.crash_dumps = Some(Arc::new(dumps));
+        self
+    }
+
+    /// Get the alert monitor, e.g. to subscribe to alerts or configure a tenant's
+    pub fn alerts(&self) -> Arc<AlertMonitor> {
+        self.alerts.clone()
+    }
+
+    /// Watch the audit log for module crash loops; abort the returned task to stop
+    pub fn start_alert_monitor(&self) -> JoinHandle<()> {
+        let filter = AuditFilter { sources: Vec::new(), event_types: vec!["ModuleCrashed".to_string()] };
+        self.alerts.clone().watch(self.audit_log.subscribe(filter))
+    }
+
+    /// Get the audit log
+    pub fn audit_log(&self) -> Arc<AuditLog> {
+        self.audit_log.clone()
+    }
+
+    /// Get the capability manager
+    pub fn capability_manager(&self) -> Arc<CapabilityManager> {
+        self.capability_manager.clone()
+    }
+
+    /// Get the pseudonymizer, e.g. to find the pseudonym a module saw for an employee
+    pub fn pseudonymizer(&self) -> Arc<Pseudonymizer> {
+        self.pseudonymizer.clone()
+    }
+
+    /// Replace the capability secret and re-issue tokens for live capabilities
+    ///
+    /// The new secret is persisted first when a secret store is configured.
+    /// Tokens bound to running module instances are re-issued; see
+    /// [`CapabilityManager::rotate_secret`]. The rotation is audited.
+    pub async fn rotate_capability_secret(&self) -> Result<Vec<ReissuedToken>> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Secret store".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+
+        let (secret, generation) = match &self.secret_store {
+            Some(store) => {
+                let mut store = store.lock().unwrap_or_else(|e| e.into_inner());
+                let secret = store.rotate(CAPABILITY_SECRET, 32)?.expose().to_vec();
+                (secret, store.generation(CAPABILITY_SECRET))
+            }
+            None => (CapabilityManager::generate_secret()?, None),
+        };
+
+        let instances = self.registry.read().await.instance_nonces();
+        let (reissued, revoked) = self.capability_manager.rotate_secret(secret, &instances).await;
+        info!("Capability secret rotated: {} tokens re-issued, {} revoked", reissued.len(), revoked);
+        self.audit_log
+            .log_capability_secret_rotated(generation, reissued.len(), revoked, "kernel")
+            .await;
+        Ok(reissued)
+    }
+
+    /// Issue a capability bound to a running module instance
+    ///
+    /// The token is only honored when presented by a store of that instance
+    /// (see [`ModuleStoreData::validate_capability`]). It stops working if
+    /// the module is relaunched, since the new instance gets a new nonce.
+    pub async fn grant_module_capability(
+        &self,
+        module_name: &str,
+        resource_type: ResourceType,
+        resource_id: &str,
+        rights: HashSet<CapabilityRight>,
+        validity: CapabilityValidity,
+    ) -> Result<CapabilityToken> {
+        self.audit_log.check_writable()?;
+        let instance_nonce = self
+            .registry
+            .read()
+            .await
+            .instance_nonce(module_name)
+            .ok_or_else(|| KernelError::ModuleNotLoaded(module_name.to_string()))?;
+
+        Ok(self
+            .capability_manager
+            .create_bound_capability(
+                resource_type,
+                resource_id.to_string(),
+                rights,
+                module_name.to_string(),
+                validity,
+                &instance_nonce,
+            )
+            .await?)
+    }
+
+    /// Renew a lease granted to a running module instance
+    ///
+    /// Leases (see [`CapabilityValidity::lease`]) let a module hold
+    /// short-lived capabilities that the host keeps refreshing while the
+    /// module is up; once the module stops or is relaunched, its leases can
+    /// no longer be renewed and lapse. Returns the new expiry (Unix millis).
+    pub async fn renew_module_capability(
+        &self,
+        module_name: &str,
+        token: &CapabilityToken,
+        extension_ms: u64,
+    ) -> Result<u64> {
+        self.audit_log.check_writable()?;
+        let instance_nonce = self
+            .registry
+            .read()
+            .await
+            .instance_nonce(module_name)
+            .ok_or_else(|| KernelError::ModuleNotLoaded(module_name.to_string()))?;
+
+        Ok(self
+            .capability_manager
+            .renew_from_instance(token, &instance_nonce, extension_ms)
+            .await?)
+    }
+
+    /// Get the tenant registry
+    pub fn tenants(&self) -> Arc<TenantRegistry> {
+        self.tenants.clone()
+    }
+
+    /// Get the accrual ledger
+    pub fn ledger(&self) -> Arc<Ledger> {
+        self.ledger.clone()
+    }
+
+    /// The jurisdiction whose statute applies
+    pub fn jurisdiction(&self) -> &str {
+        &self.jurisdiction
+    }
+
+    /// The jurisdiction whose statute applies to a policy
+    pub fn policy_jurisdiction<'a>(&'a self, policy: &'a TenantPolicy) -> &'a str {
+        policy.jurisdiction.as_deref().unwrap_or(&self.jurisdiction)
+    }
+
+    /// The statute version in force in the kernel's jurisdiction on a date
+    pub async fn statute_at(&self, date: Date) -> Result<StatuteVersion, StatuteError> {
+        self.statute_for(&self.jurisdiction, date).await
+    }
+
+    /// The statute version in force in a jurisdiction on a date
+    pub async fn statute_for(&self, jurisdiction: &str, date: Date) -> Result<StatuteVersion, StatuteError> {
+        self.statutes.read().await.version_at(jurisdiction, date).cloned()
+    }
+
+    /// Statutory parameters for guest modules in a jurisdiction on a date
+    ///
+    /// None when the jurisdiction has no rules or no statute is in force.
+    pub async fn guest_statute(&self, jurisdiction: &str, date: Date) -> Option<serde_json::Value> {
+        let rules = rules_for(jurisdiction)?;
+        let statute = self.statute_for(jurisdiction, date).await.ok()?;
+        Some(rules.guest_parameters(&statute))
+    }
+
+    /// Load statute files shipped with a rule pack
+    ///
+    /// Every `<jurisdiction>.json` file in `dir` is checked like a module: its
+    /// signature over the file bytes is read from `<jurisdiction>.json.sig`
+    /// and is required when the kernel requires signatures. The files are
+    /// only applied if all of them verify and validate, and none is older
+    /// than the statute it replaces. Returns the jurisdictions loaded.
+    pub async fn load_statutes(&self, dir: impl AsRef<std::path::Path>) -> Result<Vec<String>> {
+        self.audit_log.check_writable()?;
+        let mut paths: Vec<_> = std::fs::read_dir(dir.as_ref())?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut book = self.statutes.read().await.clone();
+        let mut loaded = Vec::new();
+        for path in paths {
+            let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let bytes = std::fs::read(&path)?;
+            let signature = std::fs::read_to_string(path.with_extension("json.sig"))
+                .ok()
+                .map(|sig| sig.trim().to_string());
+            self.verify_statute_signature(&file, &bytes, signature.as_deref())?;
+
+            let statute = StatuteFile::parse(&file, &bytes)?;
+            info!(
+                "Loaded {} statute version {} from {}",
+                statute.jurisdiction,
+                statute.latest_version(),
+                path.display()
+            );
+            loaded.push(statute.jurisdiction.clone());
+            book.insert(&file, statute)?;
+        }
+
+        *self.statutes.write().await = book;
+        Ok(loaded)
+    }
+
+    fn verify_statute_signature(&self, file: &str, bytes: &[u8], signature: Option<&str>) -> Result<()> {
+        let require_signatures = self.config().require_signatures;
+        match (signature, &self.trust_store) {
+            (Some(signature), Some(trust_store)) => match trust_store.verify(bytes, signature) {
+                Ok(key) => info!("Signature verified for statute {} by key {}", file, key.key_id),
+                Err(source) if require_signatures => {
+                    return Err(KernelError::SignatureInvalid { module: file.to_string(), source }.into())
+                }
+                Err(e) => warn!("Signature verification failed for statute {} (dev mode): {}", file, e),
+            },
+            (Some(_), None) if require_signatures => return Err(KernelError::NoVerifierConfigured.into()),
+            (None, _) if require_signatures => {
+                return Err(KernelError::SignatureRequired(file.to_string()).into())
+            }
+            _ => warn!("No verified signature for statute {}. This is acceptable for dev only.", file),
+        }
+        Ok(())
+    }
+
+    /// Usage reporting and vacuuming for the ledger, policy file, archive, and catalog
+    ///
+    /// On a read replica only usage can be reported; the primary owns the files.
+    pub fn storage(&self) -> StorageMaintenance {
+        let mut storage = StorageMaintenance::new(self.audit_log.clone(), self.storage_limits.clone());
+        if let Some(path) = self.ledger.path() {
+            storage = storage.with_ledger_file(path);
+        }
+        if let Some(file) = self.tenants.policy_file() {
+            storage = storage.with_policy_file(file.path());
+        }
+        if let Some(archive) = &self.archive {
+            storage = storage.with_archive(archive.clone());
+        }
+        if let Some(catalog) = &self.catalog {
+            storage = storage.with_catalog(catalog.clone());
+        }
+        #[cfg(not(feature = "interpreter"))]
+        if let Some(cache) = &self.module_cache {
+            storage = storage.with_module_cache_dir(cache.dir());
+        }
+        if let Some(path) = self.stats_history.as_ref().and_then(|h| h.path()) {
+            storage = storage.with_stats_file(path);
+        }
+        if self.is_read_only() {
+            storage = storage.read_only();
+        }
+        storage
+    }
+
+    /// Execution statistics of every loaded module since its launch, sorted by name
+    pub async fn module_stats(&self) -> Vec<(String, ModuleStats)> {
+        self.registry.read().await.all_stats().await
+    }
+
+    /// The module statistics history, if one is kept
+    pub fn stats_history(&self) -> Option<Arc<StatsHistory>> {
+        self.stats_history.clone()
+    }
+
+    /// Append a snapshot of every module's statistics to the history
+    ///
+    /// Records nothing when no history is kept.
+    pub async fn record_stats_snapshot(&self) -> Result<Vec<StatsRecord>> {
+        let Some(history) = &self.stats_history else {
+            return Ok(Vec::new());
+        };
+        history.record(&self.module_stats().await, now_millis()).await
+    }
+
+    /// Snapshot module statistics now and then every `interval`
+    ///
+    /// Returns `None` when no history is kept. Failures are logged and
+    /// retried on the next run. Abort the returned handle to stop.
+    pub fn schedule_stats_snapshots(&self, interval: Duration) -> Option<JoinHandle<()>> {
+        let history = self.stats_history.clone()?;
+        let registry = self.registry.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let stats = registry.read().await.all_stats().await;
+                if let Err(e) = history.record(&stats, now_millis()).await {
+                    warn!("Module statistics snapshot failed: {}", e);
+                }
+            }
+        }))
+    }
+
+    /// Fuel a tenant has used, per module and in the current ceiling window
+    pub async fn tenant_usage(&self, tenant_id: &str) -> TenantResult<TenantUsage> {
+        self.tenants.get(tenant_id).await?;
+        Ok(self.tenant_meter.usage(tenant_id, now_millis()))
+    }
+
+    /// Whether tenant and ledger storage are read-only snapshots (read replica mode)
+    pub fn is_read_only(&self) -> bool {
+        self.tenants.is_read_only() || self.ledger.is_read_only()
+    }
+
+    /// Reload read-only snapshots from the primary's files
+    ///
+    /// Has no effect on writable storage.
+    pub async fn refresh_snapshot(&self) -> TenantResult<()> {
+        self.tenants.refresh().await?;
+        self.ledger
+            .refresh()
+            .await
+            .map_err(|e| TenantError::Persistence(e.to_string()))
+    }
+
+    /// Generate a tenant's annual compliance report from the ledger
+    ///
+    /// A read replica refreshes its snapshot first so the report reflects
+    /// everything the primary has written.
+    pub async fn compliance_report(&self, tenant_id: &str, year: i32) -> TenantResult<ComplianceReport> {
+        self.refresh_snapshot().await?;
+        generate_compliance_report(&self.tenants, &self.ledger, tenant_id, year).await
+    }
+
+    /// Lay out a tenant's compliance report with one of its saved templates
+    ///
+    /// Uses the template's latest version unless one is given. The template's
+    /// expressions share a budget of `max_fuel`, like a module invocation;
+    /// running out is audited as fuel exhaustion.
+    pub async fn custom_report(
+        &self,
+        tenant_id: &str,
+        year: i32,
+        template: &str,
+        version: Option<u32>,
+    ) -> Result<CustomReport> {
+        let report = self.compliance_report(tenant_id, year).await?;
+        let saved = self.tenants.report_template(tenant_id, template, version).await?;
+        let max_fuel = self.config().max_fuel;
+        match apply_template(&report, &saved, max_fuel) {
+            Ok(custom) => Ok(custom),
+            Err(e @ TemplateError::OutOfFuel { .. }) => {
+                let name = format!("report-template:{}", saved.template.name);
+                self.audit_log.log_fuel_exhausted(&name, max_fuel, "kernel").await;
+                Err(e.into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save a new version of a tenant's report template and audit it
+    pub async fn save_report_template(&self, tenant_id: &str, template: ReportTemplate) -> TenantResult<TemplateVersion> {
+        self.audit_log.check_writable().map_err(|e| TenantError::Persistence(e.to_string()))?;
+        let saved = self.tenants.save_report_template(tenant_id, template).await?;
+        self.audit_log
+            .log_report_template_recorded(tenant_id, &saved.template.name, saved.version, "kernel")
+            .await;
+        Ok(saved)
+    }
+
+    /// Set a tenant's configuration of a module (`null` removes it)
+    ///
+    /// Modules granted the `config` capability read it, along with the
+    /// tenant's policy, through `host_get_config` when they run for the
+    /// tenant. The change is audited.
+    pub async fn set_module_config(
+        &self,
+        tenant_id: &str,
+        module_name: &str,
+        config: serde_json::Value,
+    ) -> TenantResult<()> {
+        self.audit_log.check_writable().map_err(|e| TenantError::Persistence(e.to_string()))?;
+        let removed = config.is_null();
+        self.tenants.set_module_config(tenant_id, module_name, config).await?;
+        // Cached outputs were computed under the old configuration
+        self.result_cache.clear();
+        self.audit_log
+            .log_module_config_changed(tenant_id, module_name, removed, "kernel")
+            .await;
+        Ok(())
+    }
+
+    /// A tenant's configuration of a module, if it set one
+    pub async fn module_config(&self, tenant_id: &str, module_name: &str) -> TenantResult<Option<serde_json::Value>> {
+        self.refresh_snapshot().await?;
+        self.tenants.module_config(tenant_id, module_name).await
+    }
+
+    /// Value a tenant's unused sick time on a date at the given wage rates
+    pub async fn liability_report(
+        &self,
+        tenant_id: &str,
+        as_of: Date,
+        wages: &[WageRate],
+        mapping: &GlAccountMapping,
+    ) -> TenantResult<LiabilityReport> {
+        self.refresh_snapshot().await?;
+        generate_liability_report(&self.tenants, &self.ledger, tenant_id, as_of, wages, mapping).await
+    }
+
+    /// The database tenants and ledger events are written through to, if any
+    pub fn database(&self) -> Option<&Database> {
+        self.database.as_ref()
+    }
+
+    /// Load what the database holds that the registry and ledger lack
+    ///
+    /// Tenants missing from the registry are added with their lifecycles and
+    /// policy histories, every stored roster is merged in, and ledger events
+    /// after the ledger's last one are appended. Does nothing without a
+    /// database or on a read replica.
+    pub async fn restore_from_database(&self) -> Result<RestoreReport> {
+        let mut report = RestoreReport::default();
+        let Some(db) = self.database.as_ref().filter(|_| !self.is_read_only()) else {
+            return Ok(report);
+        };
+
+        for (tenant_id, lifecycle) in db.tenants().list().await? {
+            let policies = db.policies().history(&tenant_id).await?;
+            let employees = db.employees().list(&tenant_id).await?;
+            report.employees += employees.len();
+            if self.tenants.restore(&tenant_id, lifecycle, policies, employees).await? {
+                report.tenants_added += 1;
+            }
+        }
+        report.ledger_events = self.ledger.restore(db.ledger().all().await?).await?;
+
+        info!(
+            "Restored {} tenants, {} employees, and {} ledger events from the database",
+            report.tenants_added, report.employees, report.ledger_events
+        );
+        Ok(report)
+    }
+
+    /// Save a tenant as the registry holds it to the database, if configured
+    async fn save_tenant(&self, tenant_id: &str) -> Result<()> {
+        let Some(db) = &self.database else {
+            return Ok(());
+        };
+        let tenant = self.tenants.get(tenant_id).await?;
+        db.tenants().save(tenant_id, &tenant.lifecycle).await?;
+        db.policies().save(tenant_id, tenant.policies.versions()).await?;
+        db.employees().add(tenant_id, tenant.employees.into_iter().collect()).await
+    }
+
+    /// Add employees to an active tenant's roster
+    pub async fn add_employees(&self, tenant_id: &str, employee_ids: &[String]) -> Result<()> {
+        for employee_id in employee_ids {
+            self.tenants.add_employee(tenant_id, employee_id).await?;
+        }
+        self.save_tenant(tenant_id).await
+    }
+
+    /// Append events to the ledger and the database, if configured
+    ///
+    /// Use that leaves an employee over their balance or the annual limit
+    /// raises an alert.
+    pub async fn append_ledger_events(&self, batch: Vec<NewLedgerEvent>) -> Result<Vec<LedgerEvent>> {
+        let recorded = self.ledger.append_batch(batch).await?;
+        if let Some(db) = &self.database {
+            db.ledger().append(&recorded).await?;
+        }
+        self.check_ledger_alerts(&recorded).await;
+        Ok(recorded)
+    }
+
+    /// Raise alerts for violations the recorded use added to each employee's year
+    async fn check_ledger_alerts(&self, recorded: &[LedgerEvent]) {
+        let sequences: HashSet<u64> = recorded.iter().map(|e| e.sequence).collect();
+        let used: BTreeSet<(&str, &str, i32)> = recorded
+            .iter()
+            .filter(|e| matches!(e.event.kind, LedgerEventKind::Used { .. }))
+            .map(|e| (e.event.tenant_id.as_str(), e.event.employee_id.as_str(), e.event.work_date.year()))
+            .collect();
+
+        for (tenant_id, employee_id, year) in used {
+            // Unknown tenants have no policy to break
+            let Ok(policies) = self.tenants.policy_history(tenant_id).await else {
+                continue;
+            };
+            let employees = [employee_id.to_string()];
+            let after = self.ledger.events_for_employee(tenant_id, employee_id).await;
+            let before: Vec<LedgerEvent> = after.iter().filter(|e| !sequences.contains(&e.sequence)).cloned().collect();
+            let summarize = |events: &[LedgerEvent]| {
+                build_compliance_report(tenant_id, year, &employees, events, &policies).employees.pop()
+            };
+            if let (Some(before), Some(after)) = (summarize(&before), summarize(&after)) {
+                for alert in ledger_alerts(tenant_id, &before, &after, crate::clock::now_millis()) {
+                    self.alerts.raise(alert);
+                }
+            }
+        }
+    }
+
+    /// Public key this kernel signs backups with (hex)
+    ///
+    /// Without a secret store the key lasts only as long as the process.
+    pub fn backup_public_key(&self) -> String {
+        self.backup_signer.public_key_hex()
+    }
+
+    /// Write a signed backup of every tenant, the ledger, and the audit log
+    ///
+    /// The audit log is read from its persisted segments when it has them,
+    /// so entries trimmed from memory are included. The backup is audited.
+    pub async fn create_backup(&self, path: &Path) -> Result<BackupSummary> {
+        self.refresh_snapshot().await?;
+        let mut tenants = BTreeMap::new();
+        for tenant_id in self.tenants.list_tenants().await {
+            let tenant = self.tenants.get(&tenant_id).await?;
+            let backup = BackupTenant {
+                lifecycle: tenant.lifecycle,
+                policies: tenant.policies,
+                employees: tenant.employees.into_iter().collect(),
+            };
+            tenants.insert(tenant_id, backup);
+        }
+        let contents = BackupContents {
+            created_at: crate::clock::now_millis(),
+            kernel_version: env!("CARGO_PKG_VERSION").to_string(),
+            tenants,
+            ledger: self.ledger.events().await,
+            audit: self.audit_log.query(&AuditQuery::default(), usize::MAX).await?,
+        };
+        let summary = BackupArchive::write(path, contents, &self.backup_signer).await?;
+        info!(
+            "Backup written to {}: {} tenants, {} ledger events, {} audit entries",
+            path.display(),
+            summary.tenants,
+            summary.ledger_events,
+            summary.audit_entries
+        );
+        self.audit_log.log_backup_created(&summary, "kernel").await;
+        Ok(summary)
+    }
+
+    /// Read and verify a backup signed by this kernel or one of `trusted_keys`
+    ///
+    /// Backups from another installation are only accepted once its backup
+    /// key (see [`Kernel::backup_public_key`]) is passed here.
+    pub async fn open_backup(&self, path: &Path, trusted_keys: &[String]) -> Result<BackupArchive> {
+        let mut trusted = vec![self.backup_public_key()];
+        trusted.extend(trusted_keys.iter().cloned());
+        BackupArchive::read(path, &trusted).await
+    }
+
+    /// Merge a verified backup into the registry, ledger, and database
+    ///
+    /// Tenants the registry lacks are added and every roster is merged in;
+    /// ledger events after the ledger's last one are appended, as when
+    /// restoring from the database. An archive whose ledger does not continue
+    /// this one's (e.g. another installation's, restored into a ledger with
+    /// events of its own) is refused before anything is merged. The archive's
+    /// audit entries are history of another log and are not replayed into
+    /// this one. The restore is audited.
+    pub async fn restore_backup(&self, archive: &BackupArchive) -> Result<RestoreReport> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Backup restore".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+
+        // The ledger goes first: it refuses archives that don't continue its history
+        let mut report = RestoreReport::default();
+        let after = self.ledger.last_sequence().await;
+        report.ledger_events = self.ledger.restore(archive.contents.ledger.clone()).await?;
+        for (tenant_id, tenant) in &archive.contents.tenants {
+            report.employees += tenant.employees.len();
+            let added = self
+                .tenants
+                .restore(tenant_id, tenant.lifecycle.clone(), tenant.policies.clone(), tenant.employees.clone())
+                .await?;
+            if added {
+                report.tenants_added += 1;
+            }
+            self.save_tenant(tenant_id).await?;
+        }
+        if let Some(db) = &self.database {
+            let added: Vec<LedgerEvent> = archive
+                .contents
+                .ledger
+                .iter()
+                .filter(|e| after.is_none_or(|last| e.sequence > last))
+                .cloned()
+                .collect();
+            db.ledger().append(&added).await?;
+        }
+
+        let summary = archive.summary();
+        info!(
+            "Restored backup from {} signed by {}: {} tenants added, {} ledger events",
+            summary.created_at, summary.signer, report.tenants_added, report.ledger_events
+        );
+        self.audit_log
+            .log_backup_restored(&summary, report.tenants_added, report.ledger_events, "kernel")
+            .await;
+        Ok(report)
+    }
+
+    /// Replace the database field key and re-seal stored employee records
+    ///
+    /// Needs an encrypted database whose key is in the secret store. The old
+    /// key stays in the store until every value is re-sealed, so a rotation
+    /// that fails part way is resumed by calling this again. The rotation is
+    /// audited; returns the number of values re-sealed.
+    pub async fn rotate_database_key(&self) -> Result<usize> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Database".to_string()).into());
+        }
+        let (Some(db), Some(store)) = (&self.database, &self.secret_store) else {
+            return Err(StorageError::Encryption("key rotation needs a database and a secret store".into()).into());
+        };
+        self.audit_log.check_writable()?;
+
+        let cipher = FieldCipher::begin_rotation(&mut store.lock().unwrap_or_else(|e| e.into_inner()))?;
+        let resealed = db.rotate_key(cipher.clone()).await?;
+        FieldCipher::finish_rotation(&mut store.lock().unwrap_or_else(|e| e.into_inner()))?;
+        info!("Database key rotated to {}: {} values re-sealed", cipher.key_id(), resealed);
+        self.audit_log.log_database_key_rotated(cipher.key_id(), resealed, "kernel").await;
+        Ok(resealed)
+    }
+
+    /// Create a tenant and provision its capability partition
+    ///
+    /// The tenant starts active, without a policy. Its namespace gets a root
+    /// capability owned by the tenant, from which the capabilities of its
+    /// modules and users are delegated. Audited as `TenantCreated`.
+    pub async fn create_tenant(&self, tenant_id: &str) -> Result<TenantProvisioning> {
+        self.audit_log.check_writable()?;
+        let tenant = self.tenants.create(tenant_id).await?;
+        self.save_tenant(tenant_id).await?;
+        let root_capability = self
+            .capability_manager
+            .create_tenant_capability(
+                tenant_id,
+                ResourceType::Custom("tenant".into()),
+                "",
+                TENANT_ROOT_RIGHTS.into_iter().collect(),
+                tenant_id.to_string(),
+                CapabilityValidity::default(),
+            )
+            .await?;
+
+        info!("Tenant {} created", tenant_id);
+        crate::tenant::scope(tenant_id.to_string(), self.audit_log.log_tenant_created(tenant_id, &tenant.namespace(), "kernel"))
+            .await;
+        Ok(TenantProvisioning { tenant, root_capability })
+    }
+
+    /// Archive a tenant, closing it to new work
+    ///
+    /// Its ledger, policies, and reports stay readable, but invocations,
+    /// policy changes, and roster changes are refused and every capability in
+    /// its namespace is revoked. Returns the number revoked.
+    pub async fn archive_tenant(&self, tenant_id: &str) -> Result<usize> {
+        self.audit_log.check_writable()?;
+        self.tenants.archive(tenant_id).await?;
+        self.save_tenant(tenant_id).await?;
+        let revoked = self.capability_manager.revoke_tenant(tenant_id).await;
+
+        info!("Tenant {} archived, {} capabilities revoked", tenant_id, revoked);
+        crate::tenant::scope(tenant_id.to_string(), self.audit_log.log_tenant_archived(tenant_id, revoked, "kernel"))
+            .await;
+        Ok(revoked)
+    }
+
+    /// Remove an archived tenant and its ledger events for good
+    ///
+    /// The ledger is purged before the tenant leaves the registry, so a
+    /// failure part way leaves an archived tenant the purge can be retried
+    /// on. Audit entries are kept; the log is never rewritten.
+    pub async fn purge_tenant(&self, tenant_id: &str) -> Result<TenantPurgeReport> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Tenant registry".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+        if !self.tenants.get(tenant_id).await?.is_archived() {
+            return Err(TenantError::NotArchived(tenant_id.to_string()).into());
+        }
+
+        let ledger_events = self.ledger.purge_tenant(tenant_id).await?;
+        if let Some(db) = &self.database {
+            db.tenants().delete(tenant_id).await?;
+        }
+        let tenant = self.tenants.purge(tenant_id).await?;
+        let report = TenantPurgeReport {
+            tenant_id: tenant_id.to_string(),
+            ledger_events,
+            policy_versions: tenant.policies.versions().len(),
+            employees: tenant.employees.len(),
+            capabilities_revoked: self.capability_manager.revoke_tenant(tenant_id).await,
+        };
+        self.tenant_meter.forget(tenant_id);
+
+        info!("Tenant {} purged: {} ledger events removed", tenant_id, ledger_events);
+        crate::tenant::scope(tenant_id.to_string(), self.audit_log.log_tenant_purged(&report, "kernel")).await;
+        Ok(report)
+    }
+
+    /// Redact a tenant's employee data older than its policy's retention period
+    ///
+    /// Ledger events (in the ledger and the database) lose their employee
+    /// IDs and the tenant's audit entries their events; see
+    /// [`crate::retention`]. A tenant whose current policy sets no period is
+    /// left alone. A redaction is audited, untagged, so the record of it is
+    /// itself kept.
+    pub async fn apply_retention(&self, tenant_id: &str) -> Result<RetentionReport> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Ledger".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+
+        let mut report = RetentionReport { tenant_id: tenant_id.to_string(), ..Default::default() };
+        let Some(days) = self.tenants.get_policy(tenant_id).await?.and_then(|policy| policy.retention_days) else {
+            return Ok(report);
+        };
+        report.cutoff = retention::cutoff(now_millis(), days);
+
+        let redactions = self
+            .ledger
+            .redact_employees(tenant_id, retention::cutoff_date(report.cutoff), |employee_id| {
+                self.pseudonymizer.redacted(tenant_id, employee_id)
+            })
+            .await?;
+        report.ledger_events = redactions.len();
+        if let (Some(db), false) = (&self.database, redactions.is_empty()) {
+            db.ledger().redact_employees(tenant_id, redactions).await?;
+        }
+        let audit = self.audit_log.redact_tenant(tenant_id, report.cutoff).await?;
+        report.audit_entries = audit.redacted;
+        report.audit_entries_skipped = audit.skipped;
+
+        if report.ledger_events + report.audit_entries > 0 {
+            info!(
+                "Retention applied to tenant {}: {} ledger events and {} audit entries redacted",
+                tenant_id, report.ledger_events, report.audit_entries
+            );
+            self.audit_log.log_retention_applied(&report, "kernel").await;
+        }
+        Ok(report)
+    }
+
+    /// Apply every tenant's retention period (see [`Self::apply_retention`])
+    pub async fn apply_retention_all(&self) -> Result<Vec<RetentionReport>> {
+        let mut reports = Vec::new();
+        for tenant_id in self.tenants.list_tenants().await {
+            reports.push(self.apply_retention(&tenant_id).await?);
+        }
+        Ok(reports)
+    }
+
+    /// Record a new policy version for a tenant and audit it
+    ///
+    /// The policy must be at least as generous as the statute in force on
+    /// its effective date, under the rules of the policy's jurisdiction.
+    /// Policies taking effect before the jurisdiction's first statute version
+    /// are not checked.
+    pub async fn set_tenant_policy(
+        &self,
+        tenant_id: &str,
+        policy: TenantPolicy,
+        effective_from: Date,
+    ) -> TenantResult<PolicyVersion> {
+        self.audit_log.check_writable().map_err(|e| TenantError::Persistence(e.to_string()))?;
+        let insights_before = match self.tenants.policy_history(tenant_id).await {
+            Ok(history) => history.last().is_some_and(|v| v.policy.usage_insights),
+            Err(_) => false,
+        };
+
+        policy.validate()?;
+        let jurisdiction = self.policy_jurisdiction(&policy);
+        match (rules_for(jurisdiction), self.statute_for(jurisdiction, effective_from).await) {
+            (Some(rules), Ok(statute)) => rules.check_policy(&statute.parameters, &policy).map_err(|reason| {
+                TenantError::InvalidPolicy(format!("{} ({} statute version {})", reason, jurisdiction, statute.version))
+            })?,
+            (None, _) => warn!("Policy for tenant {} not checked: no rules for {}", tenant_id, jurisdiction),
+            (_, Err(e)) => warn!("Policy for tenant {} not checked against statute: {}", tenant_id, e),
+        }
+
+        let version = self.tenants.set_policy(tenant_id, policy, effective_from).await?;
+        self.save_tenant(tenant_id).await.map_err(|e| TenantError::Persistence(format!("{:#}", e)))?;
+        let effective_from = version.effective_from.to_string();
+        self.audit_log
+            .log_policy_version_recorded(tenant_id, version.version, &effective_from, "kernel")
+            .await;
+
+        if version.policy.usage_insights != insights_before {
+            info!(
+                "Usage insights {} for tenant {} from {}",
+                if version.policy.usage_insights { "enabled" } else { "disabled" },
+                tenant_id,
+                effective_from
+            );
+            self.audit_log
+                .log_usage_insights_changed(tenant_id, version.policy.usage_insights, &effective_from, "kernel")
+                .await;
+        }
+        Ok(version)
+    }
+
+    /// Analyze a tenant's sick time usage for unusual patterns
+    ///
+    /// Covers the year of usage ending on `as_of`. The tenant's policy in
+    /// force on `as_of` must opt in to usage insights, and the analytics
+    /// module must be loaded. Each run is audit-logged.
+    pub async fn usage_insights(&self, tenant_id: &str, as_of: Date) -> Result<UsageInsightsReport> {
+        self.refresh_snapshot().await?;
+
+        let enabled = self
+            .tenants
+            .policy_at(tenant_id, as_of)
+            .await?
+            .is_some_and(|v| v.policy.usage_insights);
+        if !enabled {
+            return Err(KernelError::InsightsNotEnabled(tenant_id.to_string()).into());
+        }
+
+        let period_start = as_of.add_days(1 - USAGE_LOOKBACK_DAYS);
+        let events = self.ledger.events_for_tenant(tenant_id).await;
+
+        let mut employees = Vec::new();
+        for (employee_id, input) in usage_analysis_inputs(&events, period_start, as_of) {
+            let report = self
+                .execute_for_tenant(tenant_id, USAGE_ANALYTICS_MODULE, USAGE_ANALYSIS_FUNCTION, &serde_json::to_vec(&input)?)
+                .await?;
+            let result: EmployeeUsageInsights = serde_json::from_slice(&report.output)
+                .map_err(|e| anyhow::anyhow!("Invalid analytics output for employee {}: {}", employee_id, e))?;
+            employees.push(result);
+        }
+
+        let report = UsageInsightsReport {
+            tenant_id: tenant_id.to_string(),
+            period_start,
+            period_end: as_of,
+            generated_at: now_millis(),
+            employees,
+        };
+        self.audit_log
+            .log_usage_insights_computed(tenant_id, report.employees.len(), report.insight_count(), "kernel")
+            .await;
+        Ok(report)
+    }
+
+    fn catalog(&self) -> Result<&ModuleCatalog> {
+        Ok(self.catalog.as_deref().ok_or(KernelError::NoCatalogConfigured)?)
+    }
+
+    /// List the modules in the catalog directory with their verification status
+    pub async fn available_modules(&self) -> Result<Vec<CatalogEntry>> {
+        let catalog = self.catalog()?;
+
+        let mut entries = Vec::new();
+        for (manifest_path, manifest) in catalog.manifests()? {
+            let entry = match manifest {
+                Ok(manifest) => CatalogEntry {
+                    verification: self.verify_manifest(&manifest).await,
+                    installed: catalog.installed_module(&manifest.name).await,
+                    stored_versions: catalog
+                        .stored_versions(&manifest.name)?
+                        .into_iter()
+                        .map(|v| v.label)
+                        .collect(),
+                    name: manifest.name,
+                    version: manifest.version,
+                    manifest_path,
+                    capabilities: manifest.capabilities,
+                },
+                Err(reason) => CatalogEntry {
+                    name: manifest_path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    version: None,
+                    manifest_path,
+                    capabilities: Vec::new(),
+                    verification: ModuleVerification::Rejected { reason },
+                    installed: None,
+                    stored_versions: Vec::new(),
+                },
+            };
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Check a manifest's module the same way loading it would
+    async fn verify_manifest(&self, manifest: &ModuleManifest) -> ModuleVerification {
+        let rejected = |e: anyhow::Error| ModuleVerification::Rejected { reason: e.to_string() };
+
+        let module_bytes = match tokio::fs::read(&manifest.path).await {
+            Ok(bytes) => bytes,
+            Err(e) => return rejected(e.into()),
+        };
+        if let Err(e) = Self::verify_checksum(&module_bytes, &manifest.checksum) {
+            return rejected(e);
+        }
+        match self.verify_signature(&module_bytes, manifest) {
+            Ok(SignatureStatus::Verified { .. }) => ModuleVerification::Signed,
+            Ok(_) => ModuleVerification::Unsigned,
+            Err(e) => rejected(e),
+        }
+    }
+
+    /// Load a module from the catalog and record it as installed
+    ///
+    /// Without a version, the highest version in the catalog is installed.
+    /// Installing a module that is already loaded replaces it. The verified
+    /// module is copied into the catalog's version store and that copy is
+    /// pinned as the installed version; older stored versions beyond the
+    /// catalog's retention limit are removed.
+    pub async fn install_module(&self, name: &str, version: Option<&str>) -> Result<InstalledModule> {
+        let catalog = self.catalog()?;
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Module catalog".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+
+        let (_, manifest) = catalog
+            .manifests()?
+            .into_iter()
+            .filter_map(|(path, manifest)| Some((path, manifest.ok()?)))
+            .filter(|(_, m)| m.name == name && version.is_none_or(|v| m.version.as_deref() == Some(v)))
+            .max_by(|(_, a), (_, b)| compare_versions(a.version.as_deref(), b.version.as_deref()))
+            .ok_or_else(|| KernelError::ModuleNotInCatalog(name.to_string()))?;
+
+        self.load_manifest(manifest.clone()).await?;
+        let stored = catalog.store_version(&manifest).await?;
+        let installed = InstalledModule {
+            name: manifest.name,
+            version: manifest.version,
+            checksum: manifest.checksum,
+            manifest_path: stored.manifest_path,
+            installed_at: now_millis(),
+        };
+        catalog.record_install(installed.clone()).await?;
+
+        for label in catalog.prune_versions(name).await? {
+            info!("Removed stored version {} of module {}", label, name);
+        }
+        info!("Installed module {} version {}", installed.name, stored.label);
+        Ok(installed)
+    }
+
+    /// Swap a module back to a version kept in the catalog's version store
+    ///
+    /// The stored copy is verified (checksum and signature) exactly as on
+    /// install before it replaces the running module. `version` is a label
+    /// from `CatalogEntry::stored_versions`. The rollback is recorded in the
+    /// audit log.
+    pub async fn rollback_module(&self, name: &str, version: &str) -> Result<InstalledModule> {
+        let catalog = self.catalog()?;
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly("Module catalog".to_string()).into());
+        }
+        self.audit_log.check_writable()?;
+
+        let stored = catalog
+            .stored_version(name, version)?
+            .ok_or_else(|| KernelError::VersionNotStored {
+                module: name.to_string(),
+                version: version.to_string(),
+            })?;
+        let previous = catalog.installed_module(name).await;
+
+        self.load_manifest(stored.manifest.clone()).await?;
+        let installed = InstalledModule {
+            name: stored.manifest.name,
+            version: stored.manifest.version,
+            checksum: stored.manifest.checksum,
+            manifest_path: stored.manifest_path,
+            installed_at: now_millis(),
+        };
+        catalog.record_install(installed.clone()).await?;
+
+        let from_version = previous.as_ref().and_then(|m| m.version.as_deref());
+        self.audit_log
+            .log_module_rolled_back(name, from_version, &stored.label, &installed.checksum, "kernel")
+            .await;
+        info!(
+            "Rolled back module {} from {} to {}",
+            name,
+            from_version.unwrap_or("unversioned"),
+            stored.label
+        );
+        Ok(installed)
+    }
+
+    /// Load every module recorded as installed in the catalog
+    ///
+    /// Each module is loaded from its pinned copy in the version store.
+    /// Modules that fail to load (e.g. removed or modified since install) are
+    /// logged and skipped. Returns the names of the modules loaded.
+    pub async fn load_installed_modules(&self) -> Result<Vec<String>> {
+        let catalog = self.catalog()?;
+
+        let mut loaded = Vec::new();
+        for module in catalog.installed().await {
+            let manifest = read_manifest(&module.manifest_path).ok();
+
+            match manifest {
+                Some(manifest) if manifest.checksum == module.checksum => {
+                    match self.load_manifest(manifest).await {
+                        Ok(()) => loaded.push(module.name),
+                        Err(e) => error!("Failed to load installed module {}: {}", module.name, e),
+                    }
+                }
+                Some(_) => warn!("Installed module {} changed since install; not loading it", module.name),
+                None => warn!("Installed module {} is missing from the version store", module.name),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Verify module checksum matches the actual bytes
+    fn verify_checksum(module_bytes: &[u8], expected_checksum: &str) -> Result<()> {
+        let mut hasher = Sha256::new();
+        hasher.update(module_bytes);
+        let actual_checksum = hex::encode(hasher.finalize());
+
+        if actual_checksum != expected_checksum {
+            return Err(KernelError::ChecksumMismatch {
+                expected: expected_checksum.to_string(),
+                actual: actual_checksum,
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// Verify module signature using Ed25519
+    ///
+    /// Fails when signatures are required and the module's does not verify;
+    /// otherwise reports how the signature was checked.
+    fn verify_signature(&self, module_bytes: &[u8], manifest: &ModuleManifest) -> Result<SignatureStatus> {
+        if self.module_limits(manifest).require_signatures {
+            let signature = manifest.signature.as_ref()
+                .ok_or_else(|| KernelError::SignatureRequired(manifest.name.clone()))?;
+
+            let trust_store = self.trust_store.as_ref()
+                .ok_or(KernelError::NoVerifierConfigured)?;
+
+            let key = trust_store.verify_module(module_bytes, &manifest.checksum, signature)
+                .map_err(|source| KernelError::SignatureInvalid { module: manifest.name.clone(), source })?;
+
+            info!("Signature verified for module {} by key {}", manifest.name, key.key_id);
+            Ok(SignatureStatus::verified(&key))
+        } else {
+            // Development mode - warn about missing signatures
+            match &manifest.signature {
+                Some(sig) if !sig.is_empty() => {
+                    let Some(trust_store) = &self.trust_store else {
+                        return Ok(SignatureStatus::Unchecked);
+                    };
+                    match trust_store.verify_module(module_bytes, &manifest.checksum, sig) {
+                        Ok(key) => {
+                            info!("Signature verified for module {} by key {}", manifest.name, key.key_id);
+                            Ok(SignatureStatus::verified(&key))
+                        }
+                        Err(e) => {
+                            warn!("Signature verification failed for module {} (dev mode): {}", manifest.name, e);
+                            Ok(SignatureStatus::Invalid { reason: e.to_string() })
+                        }
+                    }
+                }
+                _ => {
+                    warn!(
+                        "No signature provided for module {}. This is acceptable for dev only.",
+                        manifest.name
+                    );
+                    Ok(SignatureStatus::Unsigned)
+                }
+            }
+        }
+    }
+
+    /// Limits a module runs under: the kernel's, tightened by its manifest's sandbox profile
+    ///
+    /// Memory limits are the kernel's regardless of the profile.
+    fn module_limits(&self, manifest: &ModuleManifest) -> SandboxLimits {
+        let limits = self.config().limits();
+        match manifest.sandbox {
+            Some(profile) => SandboxLimits {
+                max_memory_bytes: limits.max_memory_bytes,
+                ..limits.tightened(&profile.limits())
+            },
+            None => limits,
+        }
+    }
+
+    /// Parse and validate capabilities from manifest
+    fn parse_capabilities(manifest: &ModuleManifest) -> Vec<Capability> {
+        manifest
+            .capabilities
+            .iter()
+            .filter_map(|cap| Capability::from_str(cap))
+            .collect()
+    }
+
+    /// Maximum allowed size for WASM memory operations
+    const MAX_WASM_MEMORY_SIZE: i32 = 1_048_576; // 1MB
+
+    /// `host_policy_*` result: pointer, length, or tenant ID is invalid
+    const HOST_POLICY_INVALID: i32 = -1;
+    /// `host_policy_*` result: a tenant's store asked for another tenant's policy
+    const HOST_POLICY_DENIED: i32 = -2;
+    /// `host_get_config` result: the output pointer or length is invalid
+    const HOST_CONFIG_INVALID: i32 = -1;
+
+    /// State for a new store, shared by both backends
+    fn store_data(
+        &self,
+        capabilities: Vec<Capability>,
+        module_name: String,
+        tenant_id: Option<String>,
+        instance_nonce: InstanceNonce,
+    ) -> ModuleStoreData {
+        let config = self.config();
+        #[cfg(not(feature = "interpreter"))]
+        let wasi = match &config.wasi_root {
+            Some(root) if capabilities.contains(&Capability::Wasi) => Some(WasiCtx::new(
+                &module_name,
+                root,
+                &Capability::preopens(&capabilities),
+                capabilities.contains(&Capability::Log),
+                self.audit_log.clone(),
+            )),
+            _ => None,
+        };
+
+        ModuleStoreData {
+            #[cfg(not(feature = "interpreter"))]
+            limits: wasmtime_backend::GuestLimits::new(&config),
+            capabilities,
+            module_name,
+            tenant_id,
+            instance_nonce,
+            capability_manager: self.capability_manager.clone(),
+            tenants: self.tenants.clone(),
+            config: None,
+            yield_fuel_cost: config.yield_fuel_cost,
+            yields: 0,
+            heartbeats: self.heartbeats.clone(),
+            #[cfg(not(feature = "interpreter"))]
+            wasi,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos.clone(),
+        }
+    }
+
+    /// Fuel a new store starts with, for a module allowed `max_fuel` per call
+    fn store_fuel(&self, module_name: &str, max_fuel: u64) -> u64 {
+        let fuel = max_fuel;
+        #[cfg(feature = "chaos")]
+        let fuel = match &self.chaos {
+            Some(chaos) if chaos.inject(Fault::FuelStarvation, module_name) => chaos.config().starved_fuel,
+            _ => fuel,
+        };
+        #[cfg(not(feature = "chaos"))]
+        let _ = module_name;
+        fuel
+    }
+
+    /// Compile verified module bytes, through the module cache if configured
+    fn compile_module(&self, module_bytes: &[u8], checksum: &str) -> Result<Module> {
+        #[cfg(not(feature = "interpreter"))]
+        if let Some(cache) = &self.module_cache {
+            return Ok(cache.load(self.runtime.engine(), module_bytes, checksum)?.0);
+        }
+        #[cfg(feature = "interpreter")]
+        let _ = checksum;
+        self.runtime.compile(module_bytes)
+    }
+
+    /// Check a compiled module's imports against the capabilities it was granted
+    ///
+    /// Runs before the module is first instantiated. Host API functions the
+    /// manifest does not grant are linked as denial stubs, so the module
+    /// loads and only calls to them fail (each one audited). Imports the
+    /// host does not provide, and WASI imports without `wasi`, cannot be
+    /// linked at all: the module is refused naming the import, rather than
+    /// with the linker's unknown import error, and the refusal is audited.
+    async fn check_imports(&self, module: &Module, module_name: &str, capabilities: &[Capability]) -> Result<()> {
+        let mut stubbed = BTreeMap::new();
+        for (import_module, name) in self.runtime.imports(module) {
+            let required = match Capability::for_import(&import_module, &name) {
+                Some(None) => continue,
+                Some(Some(capability)) if capabilities.contains(&capability) => continue,
+                Some(Some(capability)) if HostApi::function(&import_module, &name).is_some() => {
+                    warn!(
+                        "Module {} imports {}::{} without capability {}; calls to it will trap",
+                        module_name, import_module, name, capability
+                    );
+                    stubbed.insert(format!("{}::{}", import_module, name), capability.to_string());
+                    continue;
+                }
+                Some(Some(capability)) => Some(capability.to_string()),
+                None => None,
+            };
+            let import = format!("{}::{}", import_module, name);
+            self.audit_log
+                .log_module_import_denied(module_name, &import, required.as_deref(), "kernel")
+                .await;
+            let module = module_name.to_string();
+            return Err(match required {
+                Some(capability) => KernelError::MissingCapability { module, import, capability },
+                None => KernelError::UnknownImport { module, import },
+            }
+            .into());
+        }
+        if !stubbed.is_empty() {
+            self.audit_log.log_module_imports_stubbed(module_name, stubbed, "kernel").await;
+        }
+        Ok(())
+    }
+
+    /// Read the ABI version a compiled module declares and refuse ones this host does not speak
+    ///
+    /// `__abi_version` is called in a fresh instance that is then dropped.
+    /// Modules without the export are taken to speak version 1, unless the
+    /// configuration requires the export.
+    async fn check_abi_version(
+        &self,
+        module: &Module,
+        module_name: &str,
+        capabilities: &[Capability],
+        limits: &SandboxLimits,
+    ) -> Result<Option<u32>> {
+        if !self.runtime.has_export(module, runtime::ABI_VERSION_EXPORT) {
+            if self.config().require_abi_version {
+                return Err(KernelError::AbiVersionMissing(module_name.to_string()).into());
+            }
+            warn!("Module {} does not declare its ABI version; assuming version 1", module_name);
+            return Ok(None);
+        }
+        let mut instance = self
+            .instantiate(module, capabilities, module_name, None, &InstanceNonce::generate(), limits.max_fuel)
+            .await?;
+        let declared = runtime::abi_version(&mut instance).await?.unwrap_or(1);
+        match u32::try_from(declared) {
+            Ok(version) if runtime::SUPPORTED_ABI_VERSIONS.contains(&version) => Ok(Some(version)),
+            _ => Err(KernelError::AbiIncompatible {
+                module: module_name.to_string(),
+                version: declared,
+                min: *runtime::SUPPORTED_ABI_VERSIONS.start(),
+                max: *runtime::SUPPORTED_ABI_VERSIONS.end(),
+            }
+            .into()),
+        }
+    }
+
+    /// Instantiate a module in a fresh store with host functions for its capabilities
+    async fn instantiate(
+        &self,
+        module: &Module,
+        capabilities: &[Capability],
+        module_name: &str,
+        tenant_id: Option<&str>,
+        instance_nonce: &InstanceNonce,
+        fuel: u64,
+    ) -> Result<Instance> {
+        let mut host = self.store_data(
+            capabilities.to_vec(),
+            module_name.to_string(),
+            tenant_id.map(String::from),
+            instance_nonce.clone(),
+        );
+        if let (Some(tenant_id), true) = (tenant_id, capabilities.contains(&Capability::Config)) {
+            host.config = Some(self.guest_config(tenant_id, module_name).await?.into());
+        }
+        self.runtime.instantiate(module, host, fuel).await
+    }
+
+    /// What `host_get_config` returns to a module running for a tenant
+    ///
+    /// `{"tenant_id", "module", "policy", "config"}` as JSON: the policy
+    /// version in force today (or null) and the tenant's configuration of the
+    /// module (an empty object if none). It is read once, when the store is
+    /// created, so a call sees the same configuration throughout.
+    async fn guest_config(&self, tenant_id: &str, module_name: &str) -> TenantResult<Vec<u8>> {
+        let policy = self.tenants.policy_at(tenant_id, Date::today()).await?;
+        let config = self.tenants.module_config(tenant_id, module_name).await?;
+        let json = serde_json::json!({
+            "tenant_id": tenant_id,
+            "module": module_name,
+            "policy": policy,
+            "config": config.unwrap_or_else(|| serde_json::json!({})),
+        });
+        Ok(json.to_string().into_bytes())
     }
 
     /// Launch module given a manifest path
+    ///
+    /// Rejected with `KernelError::CatalogOnly` when the configuration
+    /// enforces the module registry; install through the catalog instead.
     pub async fn launch_module(&self, manifest_path: &str) -> Result<()> {
         let manifest_bytes = tokio::fs::read(manifest_path).await?;
         let manifest: ModuleManifest = serde_json::from_slice(&manifest_bytes)?;
+        self.launch_manifest(manifest).await
+    }
+
+    /// Verify, instantiate, and register the module a manifest describes
+    ///
+    /// Unlike [`Kernel::launch_module`], the module path is used as given; see
+    /// [`crate::catalog::read_manifest`] to resolve (and confine) it against the manifest file.
+    /// Rejected like `launch_module` when the module registry is enforced.
+    pub async fn launch_manifest(&self, manifest: ModuleManifest) -> Result<()> {
+        if self.config().enforce_registry {
+            return Err(KernelError::CatalogOnly.into());
+        }
+        self.load_manifest(manifest).await
+    }
+
+    /// Load a manifest regardless of registry enforcement (catalog installs)
+    async fn load_manifest(&self, manifest: ModuleManifest) -> Result<()> {
+        self.audit_log.check_writable()?;
+        let config = self.config();
+        info!("Loading module {} from {}", manifest.name, manifest.path);
+
+        let module_bytes = tokio::fs::read(&manifest.path).await?;
+
+        // Verify checksum first
+        Self::verify_checksum(&module_bytes, &manifest.checksum)?;
+        info!("Checksum verified for module {}", manifest.name);
+
+        // Verify signature, recording which trusted key made it
+        let signature = self.verify_signature(&module_bytes, &manifest)?;
+        if let SignatureStatus::Verified { key_id, retiring } = &signature {
+            self.audit_log.log_module_signature_verified(&manifest.name, key_id, *retiring, "kernel").await;
+        }
+
+        // Parse capabilities
+        if config.strict_capabilities {
+            if let Some(unknown) = manifest.capabilities.iter().find(|cap| Capability::from_str(cap).is_none()) {
+                return Err(KernelError::UnknownCapability {
+                    module: manifest.name.clone(),
+                    capability: unknown.clone(),
+                }
+                .into());
+            }
+        }
+        let capabilities = Self::parse_capabilities(&manifest);
+        let limits = self.module_limits(&manifest);
+        if !limits.host_io {
+            if let Some(denied) = capabilities.iter().find(|cap| cap.is_host_io()) {
+                return Err(KernelError::SandboxDenied {
+                    module: manifest.name.clone(),
+                    sandbox: manifest.sandbox.max(config.sandbox).unwrap_or(SandboxProfile::Strict),
+                    capability: denied.to_string(),
+                }
+                .into());
+            }
+        }
+        #[cfg(feature = "interpreter")]
+        if capabilities.contains(&Capability::Wasi) {
+            return Err(KernelError::InterpreterUnsupported {
+                module: manifest.name.clone(),
+                capability: "wasi".to_string(),
+            }
+            .into());
+        }
+        #[cfg(not(feature = "interpreter"))]
+        if capabilities.contains(&Capability::Wasi) {
+            let root = config.wasi_root.as_ref()
+                .ok_or_else(|| KernelError::WasiNotConfigured(manifest.name.clone()))?;
+            for preopen in Capability::preopens(&capabilities) {
+                tokio::fs::create_dir_all(root.join(&preopen.name)).await?;
+            }
+        }
+        info!(
+            "Module {} granted capabilities: {:?}",
+            manifest.name, capabilities
+        );
+
+        let module = self.compile_module(&module_bytes, &manifest.checksum)?;
+        self.check_imports(&module, &manifest.name, &capabilities).await?;
+        let abi_version = self.check_abi_version(&module, &manifest.name, &capabilities, &limits).await?;
+
+        // Log to audit, with the sandbox profile the module runs under
+        self.audit_log.log_module_launched(
+            &manifest.name,
+            &manifest.checksum,
+            manifest.sandbox.max(config.sandbox),
+            abi_version,
+            "kernel",
+        ).await;
+
+        // Each launch is a new instance; tokens bound to an earlier one stop working
+        let instance_nonce = InstanceNonce::generate();
+        self.heartbeats.lock().unwrap_or_else(|e| e.into_inner()).remove(&manifest.name);
+        // A relaunched or upgraded module keeps its predecessor's resource profile
+        let resource_profile = match self.registry.read().await.get_module_stats(&manifest.name).await {
+            Some(previous) => previous.resource_profile,
+            None => ResourceProfile::default(),
+        };
+        let stats = Arc::new(RwLock::new(ModuleStats { resource_profile, ..ModuleStats::default() }));
+        let run_handle = self
+            .start_instance(&module, &capabilities, &manifest.name, &instance_nonce, &limits, stats.clone())
+            .await?;
+
+        // Register module
+        let mut reg = self.registry.write().await;
+        reg.register(
+            manifest.name.clone(),
+            run_handle,
+            capabilities,
+            stats,
+            module,
+            instance_nonce,
+            manifest.clone(),
+            signature,
+            limits,
+            abi_version,
+        );
+        info!("Module {} registered in kernel", manifest.name);
+
+        Ok(())
+    }
+
+    /// Record how a module's `_start` export ended
+    async fn record_start(
+        module_name: &str,
+        result: Result<()>,
+        consumed: u64,
+        stats: &RwLock<ModuleStats>,
+        audit_log: &AuditLog,
+        max_fuel: u64,
+    ) {
+        match result {
+            Ok(()) => {
+                let mut s = stats.write().await;
+                s.fuel_consumed += consumed;
+                s.invocation_count += 1;
+
+                audit_log.log_execution_completed(
+                    module_name,
+                    "_start",
+                    consumed,
+                    "kernel",
+                ).await;
+            }
+            Err(e) => {
+                let trap = TrapKind::of(&e);
+                let mut s = stats.write().await;
+                s.error_count += 1;
+                s.invocation_count += 1;
+                if let Some(kind) = trap {
+                    *s.traps.entry(kind).or_default() += 1;
+                }
+                drop(s);
+
+                let error_msg = format!("{:?}", e);
+                error!("Module {} _start failed: {}", module_name, error_msg);
+
+                match trap {
+                    Some(TrapKind::FuelExhausted) => {
+                        audit_log.log_fuel_exhausted(module_name, max_fuel, "kernel").await;
+                    }
+                    Some(kind) => {
+                        let backtrace = Self::symbolize_trap(&e).unwrap_or_default();
+                        audit_log.log_module_trapped(module_name, &error_msg, kind, backtrace, None, "kernel").await;
+                    }
+                    None => {
+                        audit_log.log_module_crashed(module_name, &error_msg, "kernel").await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Execute a function on a module with fuel limits
+    ///
+    /// Uses the JSON ABI exported by guest modules: the input is copied into
+    /// memory obtained from the guest's `alloc` export, the function is called
+    /// with `(ptr, len)`, and it returns a pointer to a little-endian `u32`
+    /// length followed by the JSON output. Every invocation gets a fresh store
+    /// and instance so no state leaks between calls.
+    ///
+    /// Await the returned [`Invocation`] to run it; it can be cancelled while
+    /// it runs.
+    pub fn execute_function<'a>(&'a self, module_name: &'a str, function_name: &'a str, input: &'a [u8]) -> Invocation<'a> {
+        self.invocation(None, module_name, function_name, input, false)
+    }
+
+    /// Execute a function on behalf of a tenant
+    ///
+    /// The tenant must be registered and active, and the input may not name
+    /// any other tenant. The store records the tenant so host functions can
+    /// scope resource access to its namespace, and audit entries the call
+    /// writes are tagged with it.
+    pub fn execute_for_tenant<'a>(
+        &'a self,
+        tenant_id: &'a str,
+        module_name: &'a str,
+        function_name: &'a str,
+        input: &'a [u8],
+    ) -> Invocation<'a> {
+        self.invocation(Some(tenant_id), module_name, function_name, input, false)
+    }
+
+    /// Execute a function without side effects, for what-if analysis
+    ///
+    /// The call runs through the full WASM engine under the same fuel and
+    /// memory limits (and, with a tenant, the same scoping) as a real one, but
+    /// updates no module statistics, archives nothing, and writes no audit
+    /// events other than a single `DryRunExecuted` marker.
+    pub fn execute_dry_run<'a>(
+        &'a self,
+        tenant_id: Option<&'a str>,
+        module_name: &'a str,
+        function_name: &'a str,
+        input: &'a [u8],
+    ) -> Invocation<'a> {
+        self.invocation(tenant_id, module_name, function_name, input, true)
+    }
+
+    /// Cancel a running invocation by ID
+    ///
+    /// Returns false if no invocation with the ID is running, including one
+    /// that has already finished.
+    pub fn cancel_invocation(&self, id: &str) -> bool {
+        let handle = self.invocations.lock().unwrap_or_else(|e| e.into_inner()).get(id).cloned();
+        match handle {
+            Some(handle) => {
+                info!("Cancelling invocation {}", id);
+                handle.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// IDs of the invocations running or queued, sorted
+    pub fn running_invocations(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.invocations.lock().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    fn invocation<'a>(
+        &'a self,
+        tenant_id: Option<&'a str>,
+        module_name: &'a str,
+        function_name: &'a str,
+        input: &'a [u8],
+        dry_run: bool,
+    ) -> Invocation<'a> {
+        Invocation {
+            kernel: self,
+            tenant_id,
+            module_name,
+            function_name,
+            input,
+            dry_run,
+            use_cache: true,
+            handle: self.cancel_handle(crate::correlation::new_id()),
+        }
+    }
+
+    /// A handle for an invocation with this ID
+    fn cancel_handle(&self, id: String) -> CancelHandle {
+        CancelHandle {
+            id: id.into(),
+            cancelled: Arc::new(watch::channel(false).0),
+            #[cfg(not(feature = "interpreter"))]
+            engine: self.runtime.engine().clone(),
+        }
+    }
+
+    /// Run an awaited invocation, cancellable by ID until it finishes
+    async fn run(&self, invocation: Invocation<'_>) -> Result<ExecutionReport> {
+        let Invocation { tenant_id, module_name, function_name, input, dry_run, use_cache, handle, .. } = invocation;
+        let _running = {
+            let mut running = self.invocations.lock().unwrap_or_else(|e| e.into_inner());
+            if running.contains_key(handle.id()) {
+                return Err(KernelError::InvalidRequest(format!("Invocation {} is already running", handle.id())).into());
+            }
+            running.insert(handle.id().to_string(), handle.clone());
+            RunningInvocation { running: &self.invocations, id: handle.id().to_string() }
+        };
+
+        let Some(tenant_id) = tenant_id else {
+            return self.execute_invocation(None, module_name, function_name, input, dry_run, use_cache, &handle).await;
+        };
+        self.tenants.ensure_active(tenant_id).await?;
+        check_payload_scope(tenant_id, input)?;
+        let invocation =
+            self.execute_invocation(Some(tenant_id), module_name, function_name, input, dry_run, use_cache, &handle);
+        crate::tenant::scope(tenant_id.to_string(), invocation).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_invocation(
+        &self,
+        tenant_id: Option<&str>,
+        module_name: &str,
+        function_name: &str,
+        input: &[u8],
+        dry_run: bool,
+        use_cache: bool,
+        cancel: &CancelHandle,
+    ) -> Result<ExecutionReport> {
+        let executable = self
+            .registry
+            .read()
+            .await
+            .get_executable(module_name)
+            .ok_or_else(|| KernelError::ModuleNotLoaded(module_name.to_string()))?;
+
+        info!(
+            "Execute function {} on module {} with {} input bytes",
+            function_name, module_name, input.len()
+        );
+
+        let config = self.config();
+        if input.len() > config.max_input_bytes {
+            warn!("Refusing {} input bytes for {}::{}", input.len(), module_name, function_name);
+            if !dry_run {
+                self.audit_log
+                    .log_memory_limit_exceeded(
+                        module_name,
+                        function_name,
+                        "input",
+                        input.len() as u64,
+                        config.max_input_bytes as u64,
+                        "kernel",
+                    )
+                    .await;
+            }
+            return Err(KernelError::InputTooLarge(input.len()).into());
+        }
+
+        let cacheable = use_cache
+            && !dry_run
+            && config.result_cache.enabled
+            && !executable.capabilities.contains(&Capability::Wasi);
+        let cache_key = cacheable.then(|| {
+            CacheKey::new(&executable.checksum, function_name, input, tenant_id, self.tenants.policy_generation())
+        });
+        if let Some(key) = &cache_key {
+            if let Some(output) = self.result_cache.get(key, &config.result_cache, now_millis()) {
+                info!("Answered {}::{} from the result cache", module_name, function_name);
+                self.audit_log
+                    .log_execution_cache_hit(module_name, function_name, &key.input_hash, "kernel")
+                    .await;
+                return Ok(ExecutionReport {
+                    module_name: module_name.to_string(),
+                    function_name: function_name.to_string(),
+                    output,
+                    fuel_consumed: 0,
+                    archived: None,
+                    backtrace: Vec::new(),
+                    cached: true,
+                });
+            }
+        }
+
+        // The guest sees pseudonyms; records, archives, and replays keep its
+        // view, and only the caller gets the real identifiers back
+        let (guest_input, pseudonyms) = if config.pseudonymize_identifiers {
+            self.pseudonymizer.pseudonymize(tenant_id.unwrap_or_default(), input)
+        } else {
+            (input.to_vec(), PseudonymMap::default())
+        };
+        let input = guest_input.as_slice();
+
+        // Limits from the module's resource profile, if enforced
+        let (limits, p99) = {
+            let s = executable.stats.read().await;
+            let profile = &s.resource_profile;
+            (profile.limits(&config.resource_profile), (profile.p99_fuel, profile.p99_memory_bytes))
+        };
+        let fuel = self.store_fuel(module_name, executable.limits.max_fuel);
+        let fuel = limits.map_or(fuel, |limits| fuel.min(limits.fuel));
+
+        if let Some(tenant_id) = tenant_id {
+            if let Err(e) = self.tenant_meter.check(tenant_id, now_millis()) {
+                warn!("Refusing {}::{} for tenant {}: {}", module_name, function_name, tenant_id, e);
+                return Err(e.into());
+            }
+        }
+        let (result, usage, memory) = self
+            .run_invocation(&executable, module_name, tenant_id, function_name, input, fuel, cancel)
+            .await?;
+        let result = match limits {
+            Some(limits) => Self::check_resource_profile(module_name, function_name, result, usage, limits, fuel, p99),
+            None => result,
+        };
+        let consumed = usage.fuel;
+
+        if dry_run {
+            self.audit_log.log_dry_run_executed(
+                module_name,
+                function_name,
+                tenant_id,
+                result.is_ok(),
+                "kernel",
+            ).await;
+            return Ok(ExecutionReport {
+                module_name: module_name.to_string(),
+                function_name: function_name.to_string(),
+                output: pseudonyms.restore(&result?),
+                fuel_consumed: consumed,
+                archived: None,
+                backtrace: Vec::new(),
+                cached: false,
+            });
+        }
+
+        if let Some(tenant_id) = tenant_id {
+            self.tenant_meter.record(tenant_id, module_name, consumed, now_millis());
+        }
+        let mut s = executable.stats.write().await;
+        s.fuel_consumed += consumed;
+        s.invocation_count += 1;
+        s.peak_memory_bytes = s.peak_memory_bytes.max(usage.memory_bytes);
+
+        match result {
+            Ok(output) => {
+                s.resource_profile.observe(usage, config.resource_profile.window);
+                drop(s);
+                self.audit_log.log_execution_completed(
+                    module_name,
+                    function_name,
+                    consumed,
+                    "kernel",
+                ).await;
+                self.record_invocation(&executable.checksum, tenant_id, module_name, function_name, input, &output)
+                    .await;
+
+                let archived = self.archive_invocation(module_name, function_name, input, &output).await;
+                let output = pseudonyms.restore(&output);
+                if let Some(key) = cache_key {
+                    self.result_cache.insert(key, &output, &config.result_cache, now_millis());
+                }
+
+                Ok(ExecutionReport {
+                    module_name: module_name.to_string(),
+                    function_name: function_name.to_string(),
+                    output,
+                    fuel_consumed: consumed,
+                    archived,
+                    backtrace: Vec::new(),
+                    cached: false,
+                })
+            }
+            Err(e) if matches!(e.downcast_ref(), Some(KernelError::Cancelled { .. })) => {
+                // The caller stopped the call; the module did nothing wrong
+                drop(s);
+                warn!("Invocation {} of {}::{} was cancelled", cancel.id(), module_name, function_name);
+                self.audit_log
+                    .log_invocation_cancelled(module_name, function_name, cancel.id(), consumed, "kernel")
+                    .await;
+                Err(e)
+            }
+            Err(e) => {
+                let trap = TrapKind::of(&e);
+                s.error_count += 1;
+                if let Some(kind) = trap {
+                    *s.traps.entry(kind).or_default() += 1;
+                }
+                drop(s);
+
+                let error_msg = format!("{:?}", e);
+                error!("Module {} {} failed: {}", module_name, function_name, error_msg);
+
+                if let Some(KernelError::ResourceAnomaly { resource, used, limit, p99, .. }) = e.downcast_ref() {
+                    self.audit_log
+                        .log_resource_anomaly(module_name, function_name, resource, *used, *limit, *p99, "kernel")
+                        .await;
+                } else if let Some(KernelError::OutputTooLarge { size, limit, .. }) = e.downcast_ref() {
+                    self.audit_log
+                        .log_memory_limit_exceeded(module_name, function_name, "output", *size as u64, *limit as u64, "kernel")
+                        .await;
+                } else if let Some(KernelError::CapabilityDenied { import, capability, .. }) = e.downcast_ref() {
+                    // A denial stub trapped; see `host_api`
+                    if let Some(host_function) = HostApi::for_import(import) {
+                        self.audit_log
+                            .log_host_call_denied(
+                                module_name,
+                                function_name,
+                                import,
+                                capability,
+                                host_function.right,
+                                host_function.resource.clone(),
+                                "kernel",
+                            )
+                            .await;
+                    }
+                } else if trap == Some(TrapKind::FuelExhausted) {
+                    self.audit_log.log_fuel_exhausted(module_name, executable.limits.max_fuel, "kernel").await;
+                } else if let Some(backtrace) = Self::symbolize_trap(&e) {
+                    let message = e.root_cause().to_string();
+                    let kind = trap.unwrap_or(TrapKind::Unreachable);
+                    let crash_dump = match (&self.crash_dumps, memory) {
+                        (Some(dumps), Some(memory)) => {
+                            let dump = CrashDump {
+                                id: CrashDumps::next_id(now_millis()),
+                                timestamp: now_millis(),
+                                module_name: module_name.to_string(),
+                                function_name: function_name.to_string(),
+                                tenant_id: tenant_id.map(str::to_string),
+                                invocation_id: cancel.id().to_string(),
+                                error: message.clone(),
+                                trap: kind,
+                                backtrace: backtrace.clone(),
+                                fuel_limit: fuel,
+                                fuel_consumed: consumed,
+                                fuel_remaining: fuel.saturating_sub(consumed),
+                                memory_size: usage.memory_bytes,
+                                memory,
+                            };
+                            match dumps.write(&dump).await {
+                                Ok(_) => Some(dump.id),
+                                Err(e) => {
+                                    warn!("Could not save crash dump for {}::{}: {:#}", module_name, function_name, e);
+                                    None
+                                }
+                            }
+                        }
+                        _ => None,
+                    };
+                    self.audit_log.log_module_trapped(
+                        module_name,
+                        &message,
+                        kind,
+                        backtrace.clone(),
+                        crash_dump.as_deref(),
+                        "kernel",
+                    ).await;
+
+                    return Err(ModuleTrap {
+                        message,
+                        kind,
+                        report: ExecutionReport {
+                            module_name: module_name.to_string(),
+                            function_name: function_name.to_string(),
+                            output: Vec::new(),
+                            fuel_consumed: consumed,
+                            archived: None,
+                            backtrace,
+                            cached: false,
+                        },
+                        crash_dump,
+                    }.into());
+                } else {
+                    self.audit_log.log_execution_failed(
+                        module_name,
+                        function_name,
+                        &e.to_string(),
+                        trap,
+                        "kernel",
+                    ).await;
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    /// Fail an invocation that went past its module's resource profile
+    ///
+    /// Fuel was capped at the profile's limit, so a call that ran out of fuel
+    /// under that cap (rather than `max_fuel`) was stopped by the profile.
+    fn check_resource_profile(
+        module_name: &str,
+        function_name: &str,
+        result: Result<Vec<u8>>,
+        usage: ResourceUsage,
+        limits: ResourceLimits,
+        fuel: u64,
+        (p99_fuel, p99_memory_bytes): (u64, usize),
+    ) -> Result<Vec<u8>> {
+        let anomaly = |resource: &str, used: u64, limit: u64, p99: u64| KernelError::ResourceAnomaly {
+            module: module_name.to_string(),
+            function: function_name.to_string(),
+            resource: resource.to_string(),
+            used,
+            limit,
+            p99,
+        };
+        if usage.memory_bytes > limits.memory_bytes {
+            warn!("Module {} {} used {} bytes of memory, over its profile's limit", module_name, function_name, usage.memory_bytes);
+            return Err(anomaly("memory", usage.memory_bytes as u64, limits.memory_bytes as u64, p99_memory_bytes as u64).into());
+        }
+        match result {
+            Err(e) if fuel == limits.fuel && TrapKind::of(&e) == Some(TrapKind::FuelExhausted) => {
+                warn!("Module {} {} ran past its profile's fuel limit", module_name, function_name);
+                Err(anomaly("fuel", usage.fuel, limits.fuel, p99_fuel).into())
+            }
+            result => result,
+        }
+    }
+
+    /// Instantiate a module in a fresh store and call one JSON ABI function
+    ///
+    /// Waits for the module's invocation scheduler to admit the call first,
+    /// then runs it with `fuel` to spend. Returns the call's result, the
+    /// resources it used, and guest memory for a crash dump; the outer error is
+    /// for calls rejected by the scheduler and failures to set up the linker.
+    /// A call cancelled while queued fails without running. Records nothing.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_invocation(
+        &self,
+        executable: &Executable,
+        module_name: &str,
+        tenant_id: Option<&str>,
+        function_name: &str,
+        input: &[u8],
+        fuel: u64,
+        cancel: &CancelHandle,
+    ) -> Result<CallOutcome> {
+        let _permit = tokio::select! {
+            permit = self.scheduler.admit(module_name, tenant_id) => permit?,
+            _ = cancel.cancelled() => {
+                return Ok((Err(cancel.error(module_name, function_name).into()), ResourceUsage::default(), None));
+            }
+        };
+        let abort = self.abort_invocations.subscribe();
+        if *abort.borrow() {
+            return Err(KernelError::ShuttingDown.into());
+        }
+        #[cfg(feature = "chaos")]
+        if self.chaos.as_ref().is_some_and(|chaos| chaos.inject(Fault::Trap, module_name)) {
+            let trap = KernelError::Trapped { kind: TrapKind::Unreachable, message: "chaos: injected trap".into() };
+            return Ok((Err(trap.into()), ResourceUsage::default(), None));
+        }
+
+        self.call_in_new_store(executable, module_name, tenant_id, function_name, input, fuel, abort, cancel).await
+    }
+
+    /// Guest memory to dump if a call failed and crash dumps are enabled
+    fn crash_memory<I: WasmInstance>(&self, instance: &I, result: &Result<Vec<u8>>, cancel: &CancelHandle) -> Option<Vec<u8>> {
+        match &self.crash_dumps {
+            Some(dumps) if result.is_err() && !cancel.is_cancelled() => dumps.capture(instance),
+            _ => None,
+        }
+    }
+
+    /// Record a successful invocation in the audit log for later replay
+    async fn record_invocation(
+        &self,
+        module_checksum: &str,
+        tenant_id: Option<&str>,
+        module_name: &str,
+        function_name: &str,
+        input: &[u8],
+        output: &[u8],
+    ) {
+        let payload = (input.len() <= self.recorded_input_limit)
+            .then(|| String::from_utf8(input.to_vec()).ok())
+            .flatten();
+
+        self.audit_log.log_invocation_recorded(
+            InvocationRecord {
+                module_name: module_name.to_string(),
+                function: function_name.to_string(),
+                module_checksum: module_checksum.to_string(),
+                tenant_id: tenant_id.map(String::from),
+                input_hash: ContentStore::hash(input),
+                output_hash: ContentStore::hash(output),
+                input: payload,
+            },
+            "kernel",
+        ).await;
+    }
+
+    /// Re-execute the invocations recorded in a range of the audit log
+    ///
+    /// Each invocation runs against the module version (by checksum) that
+    /// originally ran it: the loaded module if it still matches, otherwise a
+    /// verified copy from the catalog's version store. Output hashes are
+    /// compared with the recorded ones. Only entries still held by the audit
+    /// log can be replayed. The replay is summarized in the audit log.
+    pub async fn replay(&self, sequences: RangeInclusive<u64>) -> Result<ReplayReport> {
+        let records: Vec<(u64, InvocationRecord)> = self
+            .audit_log
+            .get_all_entries()
+            .await
+            .into_iter()
+            .filter(|entry| sequences.contains(&entry.sequence))
+            .filter_map(|entry| match entry.event {
+                AuditEventType::InvocationRecorded(record) => Some((entry.sequence, record)),
+                _ => None,
+            })
+            .collect();
+
+        let mut executables: HashMap<(String, String), Option<Executable>> = HashMap::new();
+        let mut invocations = Vec::with_capacity(records.len());
+        for (sequence, record) in records {
+            let key = (record.module_name.clone(), record.module_checksum.clone());
+            if !executables.contains_key(&key) {
+                let executable = self.replay_executable(&record.module_name, &record.module_checksum).await;
+                executables.insert(key.clone(), executable);
+            }
+
+            let outcome = match (&executables[&key], self.recorded_input(&record).await) {
+                (_, None) => ReplayOutcome::InputUnavailable,
+                (None, _) => ReplayOutcome::ModuleUnavailable,
+                (Some(executable), Some(input)) => {
+                    let fuel = self.store_fuel(&record.module_name, executable.limits.max_fuel);
+                    let cancel = self.cancel_handle(format!("replay-{}", sequence));
+                    let result = self
+                        .run_invocation(executable, &record.module_name, record.tenant_id.as_deref(), &record.function, &input, fuel, &cancel)
+                        .await
+                        .and_then(|(result, ..)| result);
+                    match result {
+                        Ok(output) => {
+                            let output_hash = ContentStore::hash(&output);
+                            if output_hash == record.output_hash {
+                                ReplayOutcome::Matched
+                            } else {
+                                ReplayOutcome::Diverged { output_hash }
+                            }
+                        }
+                        Err(e) => ReplayOutcome::Failed { error: e.to_string() },
+                    }
+                }
+            };
+
+            invocations.push(ReplayedInvocation {
+                sequence,
+                module_name: record.module_name,
+                function: record.function,
+                module_checksum: record.module_checksum,
+                tenant_id: record.tenant_id,
+                input_hash: record.input_hash,
+                expected_output_hash: record.output_hash,
+                outcome,
+            });
+        }
+
+        let report = ReplayReport {
+            first_sequence: *sequences.start(),
+            last_sequence: *sequences.end(),
+            invocations,
+        };
+        if !report.is_deterministic() {
+            warn!(
+                "Replay of audit entries {}..={}: {} diverged, {} unavailable",
+                report.first_sequence,
+                report.last_sequence,
+                report.diverged(),
+                report.unavailable()
+            );
+        }
+        self.audit_log.log_replay_completed(&report, "kernel").await;
+        Ok(report)
+    }
+
+    /// The recorded input of an invocation, from the audit log or the archive
+    async fn recorded_input(&self, record: &InvocationRecord) -> Option<Vec<u8>> {
+        let input = match &record.input {
+            Some(input) => input.clone().into_bytes(),
+            None => self.archive.as_ref()?.store().get(&record.input_hash).await.ok()?,
+        };
+        (ContentStore::hash(&input) == record.input_hash).then_some(input)
+    }
+
+    /// The module version with the given checksum, if loaded or stored in the catalog
+    async fn replay_executable(&self, module_name: &str, checksum: &str) -> Option<Executable> {
+        if let Some(executable) = self.registry.read().await.get_executable(module_name) {
+            if executable.checksum == checksum {
+                return Some(executable);
+            }
+        }
+
+        let stored = self
+            .catalog
+            .as_deref()?
+            .stored_versions(module_name)
+            .ok()?
+            .into_iter()
+            .find(|v| v.manifest.checksum == checksum)?;
+        let module_bytes = tokio::fs::read(&stored.manifest.path).await.ok()?;
+        let verified = Self::verify_checksum(&module_bytes, checksum)
+            .and_then(|()| self.verify_signature(&module_bytes, &stored.manifest))
+            .and_then(|_| self.compile_module(&module_bytes, checksum));
+        match verified {
+            Ok(module) => Some(Executable {
+                module,
+                capabilities: Self::parse_capabilities(&stored.manifest),
+                stats: Arc::new(RwLock::new(ModuleStats::default())),
+                instance_nonce: InstanceNonce::generate(),
+                checksum: checksum.to_string(),
+                limits: self.module_limits(&stored.manifest),
+            }),
+            Err(e) => {
+                warn!("Stored version {} of module {} failed verification: {}", stored.label, module_name, e);
+                None
+            }
+        }
+    }
+
+    /// Extract the symbolized WASM backtrace from a trap error
+    ///
+    /// Function names come from the module's `name` section; frames of
+    /// stripped modules keep only their function index, and traps reported
+    /// as [`KernelError::Trapped`] have none. Returns None if the error is not
+    /// a trap.
+    fn symbolize_trap(error: &anyhow::Error) -> Option<Vec<BacktraceFrame>> {
+        match error.downcast_ref::<KernelError>() {
+            Some(KernelError::Trapped { .. }) => Some(Vec::new()),
+            #[cfg(not(feature = "interpreter"))]
+            _ => wasmtime_backend::backtrace(error),
+            #[cfg(feature = "interpreter")]
+            _ => None,
+        }
+    }
+
+    /// Archive an invocation if the archive selects it
+    ///
+    /// Archival failures are logged but never fail the invocation itself.
+    async fn archive_invocation(
+        &self,
+        module_name: &str,
+        function_name: &str,
+        input: &[u8],
+        output: &[u8],
+    ) -> Option<ArchiveRef> {
+        let archive = self.archive.as_ref()?;
+        if !archive.should_archive(module_name, input) {
+            return None;
+        }
+
+        match archive.archive(input, output).await {
+            Ok(reference) => {
+                self.audit_log.log_invocation_archived(
+                    module_name,
+                    function_name,
+                    &reference.input_hash,
+                    &reference.output_hash,
+                    "kernel",
+                ).await;
+                Some(reference)
+            }
+            Err(e) => {
+                warn!("Failed to archive {}::{} invocation: {}", module_name, function_name, e);
+                None
+            }
+        }
+    }
+
+    /// Last heartbeat of each module that has sent one, by module name
+    pub fn heartbeats(&self) -> Vec<ModuleHeartbeat> {
+        let mut heartbeats: Vec<ModuleHeartbeat> = self
+            .heartbeats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(module, at)| ModuleHeartbeat { module: module.clone(), last_heartbeat: *at })
+            .collect();
+        heartbeats.sort_by(|a, b| a.module.cmp(&b.module));
+        heartbeats
+    }
+
+    /// Forward module heartbeats to a supervisor and act on hangs
+    ///
+    /// Every `interval`, heartbeats received since the last check are reported
+    /// for the child of the same ID as the module, then children whose
+    /// heartbeat is overdue are recorded as crashed in the audit log and
+    /// handled by the supervisor (restarted through its callback, stopped, or
+    /// escalated). Abort the returned task to stop watching.
+    pub fn supervise_heartbeats(&self, supervisor: Arc<Supervisor>, interval: Duration) -> JoinHandle<()> {
+        let heartbeats = self.heartbeats.clone();
+        let audit_log = self.audit_log.clone();
+        tokio::spawn(async move {
+            let mut forwarded: HashMap<String, u64> = HashMap::new();
+            loop {
+                tokio::time::sleep(interval).await;
+                let latest = heartbeats.lock().unwrap_or_else(|e| e.into_inner()).clone();
+                for (module, at) in latest {
+                    if forwarded.get(&module) != Some(&at) && supervisor.report_heartbeat(&module).await.is_ok() {
+                        forwarded.insert(module, at);
+                    }
+                }
+
+                let hung = match supervisor.check_heartbeats().await {
+                    Ok(hung) => hung,
+                    Err(e) => {
+                        error!("Heartbeat check failed: {}", e);
+                        continue;
+                    }
+                };
+                for (module, action) in hung {
+                    audit_log.log_module_crashed(&module, "hung: heartbeat overdue", "supervisor").await;
+                    let supervisor = supervisor.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = supervisor.execute_restart(&module, action).await {
+                            error!("Failed to restart hung module {}: {}", module, e);
+                        }
+                    });
+                }
+            }
+        })
+    }
+
+    /// Get kernel status
+    pub async fn get_status(&self) -> KernelStatus {
+        let config = self.config();
+        let reg = self.registry.read().await;
+        let modules: Vec<String> = reg.list_modules().iter().map(|s| s.to_string()).collect();
+        let audit_stats = self.audit_log.stats().await;
+        let audit_archive = self.audit_log.archived_stats().await.unwrap_or_else(|e| {
+            warn!("Failed to read persisted audit segments: {:#}", e);
+            None
+        });
+
+        KernelStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            modules_loaded: modules.len(),
+            module_names: modules,
+            max_fuel_per_call: config.max_fuel,
+            max_memory_bytes: config.max_memory_bytes,
+            require_signatures: config.require_signatures,
+            security_profile: self.profile,
+            sandbox_profile: config.sandbox,
+            profile_violations: self.profile_violations(),
+            heartbeats: self.heartbeats(),
+            audit_entries: audit_stats.total_entries,
+            audit_archive,
+            audit_health: self.audit_log.health(),
+            invocation_queues: self.scheduler.status(),
+            capabilities: self.capability_manager.stats().await,
+            result_cache: self.result_cache.stats(),
+        }
+    }
+
+    /// Shutdown the kernel and all running modules
+    ///
+    /// New invocations are rejected with `KernelError::ShuttingDown`. Each
+    /// module that exports `__shutdown` (no parameters or results) has it
+    /// called in a fresh instance so it can flush state through its host
+    /// capabilities. Running and queued invocations then get up to
+    /// `shutdown_drain_timeout` to finish; those still pending are
+    /// cancelled at their next yield point and the modules are unloaded.
+    /// Each module's outcome is recorded in the audit log.
+    pub async fn shutdown(&self) -> Result<Vec<ModuleShutdown>> {
+        info!("Kernel shutdown initiated");
+        
+        self.audit_log.append(AuditEvent::new(
+            AuditEventType::KernelShutdown { 
+                reason: "normal shutdown".into() 
+            },
+            "kernel",
+        )).await;
+
+        self.scheduler.close();
+        let deadline = tokio::time::Instant::now() + self.config().shutdown_drain_timeout;
+
+        let executables: Vec<(String, Executable)> = {
+            let reg = self.registry.read().await;
+            let mut names = reg.list_modules();
+            names.sort();
+            names
+                .into_iter()
+                .filter_map(|name| reg.get_executable(name).map(|e| (name.to_string(), e)))
+                .collect()
+        };
+
+        let mut outcomes = Vec::with_capacity(executables.len());
+        for (name, executable) in &executables {
+            let signal = self.signal_shutdown(name, executable, deadline).await;
+            outcomes.push(ModuleShutdown {
+                module: name.clone(),
+                signal,
+                aborted_invocations: 0,
+                start_task_aborted: false,
+            });
+        }
+
+        for outcome in &mut outcomes {
+            outcome.aborted_invocations = self.scheduler.drain(&outcome.module, deadline).await;
+        }
+        if outcomes.iter().any(|o| o.aborted_invocations > 0) {
+            warn!("Drain timeout expired; cancelling remaining invocations");
+            self.abort_invocations.send_replace(true);
+            self.scheduler.abandon_queued();
+        }
+
+        let stopped = self.registry.write().await.shutdown_all().await;
+        for outcome in &mut outcomes {
+            outcome.start_task_aborted = stopped
+                .iter()
+                .any(|(name, running)| *running && *name == outcome.module);
+            self.audit_log
+                .log_module_shutdown(
+                    &outcome.module,
+                    outcome.signal.as_str(),
+                    outcome.aborted_invocations,
+                    outcome.start_task_aborted,
+                    "kernel",
+                )
+                .await;
+        }
+
+        info!("Kernel shutdown complete");
+        Ok(outcomes)
+    }
+
+    /// Unload one running module, leaving the others up
+    ///
+    /// The module is shut down as in [`Kernel::shutdown`]: its `__shutdown`
+    /// export is called and its invocations are given the drain timeout to
+    /// finish. With `flush`, the module's invocations since the last
+    /// statistics snapshot are then written to the stats history (other
+    /// modules' wait for the next snapshot). It is then removed from the registry, its `_start` task is given
+    /// what remains of the drain timeout to finish before it is aborted, and
+    /// the capabilities bound to its instance are revoked.
+    /// Invocations that outlast the drain timeout run to completion, but
+    /// their bound capabilities no longer validate.
+    pub async fn unload_module(&self, module_name: &str, flush: bool) -> Result<ModuleUnload> {
+        self.audit_log.check_writable()?;
+        let executable = self
+            .registry
+            .read()
+            .await
+            .get_executable(module_name)
+            .ok_or_else(|| KernelError::ModuleNotLoaded(module_name.to_string()))?;
+        info!("Unloading module: {}", module_name);
+
+        let deadline = tokio::time::Instant::now() + self.config().shutdown_drain_timeout;
+        let signal = self.signal_shutdown(module_name, &executable, deadline).await;
+        let pending = self.scheduler.drain(module_name, deadline).await;
+        if pending > 0 {
+            warn!("Module {} still has {} invocations after the drain timeout", module_name, pending);
+        }
+
+        let stats_flushed = flush && match &self.stats_history {
+            Some(history) => {
+                let stats = executable.stats.read().await.clone();
+                match history.record_final(module_name, &stats, now_millis()).await {
+                    Ok(_) => true,
+            Err(e) => {
+                        warn!("Failed to flush statistics of module {}: {}", module_name, e);
+                        false
+                    }
+                }
+            }
+            None => false,
+        };
+
+        let handle = self.registry.write().await.unregister(module_name);
+        let start_task_aborted = match handle {
+            // `_start` may still be running on a blocking thread; let it finish within the deadline
+            Some(mut handle) => match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(_) => false,
+                Err(_) => {
+                    warn!("Module {} _start did not finish before the drain timeout", module_name);
+                    handle.abort();
+                    true
+                }
+            },
+            None => false,
+        };
+        self.heartbeats.lock().unwrap_or_else(|e| e.into_inner()).remove(module_name);
+        let capabilities_revoked = self.capability_manager.revoke_instance(&executable.instance_nonce).await;
+
+        let unload = ModuleUnload {
+            shutdown: ModuleShutdown {
+                module: module_name.to_string(),
+                signal,
+                aborted_invocations: pending,
+                start_task_aborted,
+            },
+            capabilities_revoked,
+            stats_flushed,
+        };
+        self.audit_log
+            .log_module_unloaded(
+                module_name,
+                unload.shutdown.signal.as_str(),
+                pending,
+                start_task_aborted,
+                capabilities_revoked,
+                stats_flushed,
+                "kernel",
+            )
+            .await;
+        info!("Module {} unloaded, {} capabilities revoked", module_name, capabilities_revoked);
+        Ok(unload)
+    }
+
+    /// Call a module's optional `__shutdown` export, bounded by the deadline
+    async fn signal_shutdown(
+        &self,
+        module_name: &str,
+        executable: &Executable,
+        deadline: tokio::time::Instant,
+    ) -> ShutdownSignal {
+        if !self.runtime.has_export(&executable.module, "__shutdown") {
+            return ShutdownSignal::NotExported;
+        }
+
+        match tokio::time::timeout_at(deadline, self.call_shutdown(module_name, executable)).await {
+            Ok(Ok(())) => {
+                info!("Module {} acknowledged shutdown", module_name);
+                ShutdownSignal::Completed
+            }
+            Ok(Err(e)) => {
+                warn!("Module {} __shutdown failed: {:#}", module_name, e);
+                ShutdownSignal::Failed { error: format!("{:#}", e) }
+            }
+            Err(_) => {
+                warn!("Module {} __shutdown did not finish before the drain timeout", module_name);
+                ShutdownSignal::Failed { error: "drain timeout expired".into() }
+            }
+        }
+    }
+
+    /// ABI version a running module declared (none for modules without `__abi_version`)
+    pub async fn module_abi_version(&self, module_name: &str) -> Option<u32> {
+        self.registry.read().await.abi_version(module_name)
+    }
+
+    /// Manifest, signature verification, granted capabilities, and
+    /// statistics of a running module, for security review
+    ///
+    /// `signing_key` is the trust store's current record of the key that
+    /// verified the module, so a key retired since launch shows its deadline.
+    pub async fn module_info(&self, module_name: &str) -> Option<ModuleInfo> {
+        let mut info = self.registry.read().await.module_info(module_name).await?;
+        if let (SignatureStatus::Verified { key_id, .. }, Some(trust_store)) = (&info.signature, &self.trust_store) {
+            info.signing_key = trust_store.keys().into_iter().find(|key| &key.key_id == key_id);
+        }
+        Some(info)
+    }
+
+    /// List all running modules, sorted by ID
+    pub async fn list_modules(&self) -> Vec<String> {
+        let reg = self.registry.read().await;
+        reg.list_modules().into_iter().map(String::from).collect()
+    }
+}
+
+/// Kernel status information
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelStatus {
+    pub version: String,
+    pub modules_loaded: usize,
+    pub module_names: Vec<String>,
+    pub max_fuel_per_call: u64,
+    pub max_memory_bytes: usize,
+    pub require_signatures: bool,
+    pub security_profile: SecurityProfile,
+    /// Sandbox profile the kernel's module limits were taken from, if any
+    pub sandbox_profile: Option<SandboxProfile>,
+    /// Profile requirements the kernel does not meet (see `Kernel::profile_violations`)
+    pub profile_violations: Vec<String>,
+    /// Last heartbeat of each module that has sent one, by module name
+    pub heartbeats: Vec<ModuleHeartbeat>,
+    pub audit_entries: u64,
+    /// Extent of the persisted audit log, if persisted
+    pub audit_archive: Option<ArchivedAuditStats>,
+    /// Whether audit entries are reaching the segment files
+    pub audit_health: AuditHealth,
+    /// Running and waiting invocations per module
+    pub invocation_queues: Vec<InvocationQueueStatus>,
+    /// Capability counts, validation rates, and denials
+    pub capabilities: CapabilityStats,
+    /// Result cache hits, misses, and size
+    pub result_cache: ResultCacheStats,
+}
+
+/// Outcome of calling a module's `__shutdown` export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ShutdownSignal {
+    /// The module does not export `__shutdown`
+    NotExported,
+    /// `__shutdown` returned normally
+    Completed,
+    /// `__shutdown` trapped or did not finish in time
+    Failed { error: String },
+}
+
+impl ShutdownSignal {
+    /// Short name recorded in the audit log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShutdownSignal::NotExported => "not_exported",
+            ShutdownSignal::Completed => "completed",
+            ShutdownSignal::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// How one module was shut down
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleShutdown {
+    pub module: String,
+    pub signal: ShutdownSignal,
+    /// Invocations still running or queued when the drain timeout expired
+    pub aborted_invocations: usize,
+    /// Whether the module's `_start` task was still running when it was stopped
+    pub start_task_aborted: bool,
+}
+
+impl ModuleShutdown {
+    /// Whether nothing had to be cut short
+    pub fn is_graceful(&self) -> bool {
+        !matches!(self.signal, ShutdownSignal::Failed { .. })
+            && self.aborted_invocations == 0
+            && !self.start_task_aborted
+    }
+}
+
+/// How a module was unloaded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModuleUnload {
+    #[serde(flatten)]
+    pub shutdown: ModuleShutdown,
+    /// Capabilities bound to the instance, revoked with it
+    pub capabilities_revoked: usize,
+    /// Whether its final statistics were written to the stats history
+    pub stats_flushed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_kernel() {
+        let k = Kernel::new().unwrap();
+        let modules = k.list_modules().await;
+        assert!(modules.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kernel_with_config() {
+        let config = ExecutionConfig {
+            max_fuel: 10_000_000,
+            max_memory_bytes: 16 * 1024 * 1024,
+            require_signatures: false,
+            ..Default::default()
+        };
+        let k = Kernel::with_config(config).unwrap();
+        let status = k.get_status().await;
+        assert_eq!(status.max_fuel_per_call, 10_000_000);
+    }
+
+    #[test]
+    fn test_checksum_verification() {
+        let data = b"test module bytes";
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        let checksum = hex::encode(hasher.finalize());
+
+        assert!(Kernel::verify_checksum(data, &checksum).is_ok());
+        assert!(Kernel::verify_checksum(data, "invalid").is_err());
+    }
+
+    #[test]
+    fn test_capability_parsing() {
+        let manifest = ModuleManifest {
+            name: "test".into(),
+            version: None,
+            path: "test.wasm".into(),
+            checksum: "abc".into(),
+            capabilities: vec!["log".into(), "audit_emit".into(), "unknown".into()],
+            signature: None,
+            sandbox: None,
+        };
+        let caps = Kernel::parse_capabilities(&manifest);
+        assert_eq!(caps.len(), 2);
+        assert!(caps.contains(&Capability::Log));
+        assert!(caps.contains(&Capability::AuditEmit));
+    }
+
+    #[tokio::test]
+    async fn test_module_registry() {
+        let mut registry = ModuleRegistry::new();
+        let stats = Arc::new(RwLock::new(ModuleStats::default()));
+        let module = Kernel::new().unwrap().compile_module(b"(module)", "").unwrap();
+
+        let handle = tokio::spawn(async {});
+        registry.register(
+            "test".into(),
+            handle,
+            vec![Capability::Log],
+            stats,
+            module,
+            InstanceNonce::generate(),
+            ModuleManifest {
+                name: "test".into(),
+                version: None,
+                path: String::new(),
+                checksum: String::new(),
+                capabilities: vec!["log".into()],
+                signature: None,
+                sandbox: None,
+            },
+            SignatureStatus::Unsigned,
+            ExecutionConfig::default().limits(),
+            None,
+        );
+
+        assert_eq!(registry.list_modules(), vec!["test"]);
+
+        registry.shutdown_all().await;
+        assert!(registry.list_modules().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kernel_status() {
+        let k = Kernel::new().unwrap();
+        let status = k.get_status().await;
+        
+        assert_eq!(status.modules_loaded, 0);
+        assert_eq!(status.max_fuel_per_call, 20_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_kernel_audit_log() {
+        let k = Kernel::new().unwrap();
+        let audit_log = k.audit_log();
+        
+        // Log a test event
+        audit_log.log_custom("test", "test message", "test").await;
+        
+        let stats = audit_log.stats().await;
+        assert_eq!(stats.total_entries, 1);
+    }
+
+    #[test]
+    fn test_execution_config_default() {
+        let config = ExecutionConfig::default();
+        assert_eq!(config.max_fuel, 20_000_000);
+        assert_eq!(config.max_memory_bytes, 32 * 1024 * 1024);
+        assert!(!config.require_signatures);
+    }
+
+    /// Guest implementing the JSON ABI: a bump `alloc`, an `echo_json` that
+    /// returns its input, and a `reject_json` that returns a null pointer.
+    const JSON_ABI_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "__abi_version") (result i32)
+            (i32.const 1))
+          (func $alloc (export "alloc") (param $size i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $size)))
+            (local.get $ptr))
+          (func (export "echo_json") (param $ptr i32) (param $len i32) (result i32)
+            (local $out i32)
+            (local.set $out (call $alloc (i32.add (local.get $len) (i32.const 4))))
+            (i32.store (local.get $out) (local.get $len))
+            (memory.copy
+              (i32.add (local.get $out) (i32.const 4))
+              (local.get $ptr)
+              (local.get $len))
+            (local.get $out))
+          (func (export "reject_json") (param $ptr i32) (param $len i32) (result i32)
+            (i32.const 0))
+          (func $divide (param $d i32) (result i32)
+            (i32.div_u (i32.const 1) (local.get $d)))
+          (func (export "crash_json") (param $ptr i32) (param $len i32) (result i32)
+            (call $divide (i32.const 0))))
+    "#;
+
+    /// Write a module and its manifest to a temp dir and return the manifest path
+    pub(crate) fn write_test_module(dir: &std::path::Path, name: &str, wat: &str) -> String {
+        let module_path = dir.join(format!("{}.wat", name));
+        std::fs::write(&module_path, wat).unwrap();
+
+        let manifest = ModuleManifest {
+            name: name.into(),
+            version: None,
+            path: module_path.to_string_lossy().into_owned(),
+            checksum: hex::encode(Sha256::digest(wat.as_bytes())),
+            capabilities: vec![],
+            signature: None,
+            sandbox: None,
+        };
+        let manifest_path = dir.join(format!("{}.json", name));
+        std::fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+        manifest_path.to_string_lossy().into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_execute_function_json_abi() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+
+        let input = br#"{"minutes_worked":120}"#;
+        let report = k.execute_function("echo", "echo_json", input).await.unwrap();
+        assert_eq!(report.output, input.to_vec());
+        assert!(report.fuel_consumed > 0);
+
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!(stats.invocation_count, 1);
+        assert_eq!(stats.error_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stats_history_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let stats_path = dir.path().join("stats.jsonl");
+        let retention = crate::stats_history::DEFAULT_RETENTION;
+
+        for _ in 0..2 {
+            let k = Kernel::new().unwrap().with_stats_history(StatsHistory::with_file(&stats_path, retention).unwrap());
+            k.launch_module(&manifest_path).await.unwrap();
+            k.execute_function("echo", "echo_json", b"{}").await.unwrap();
+            assert!(k.execute_function("echo", "reject_json", b"{}").await.is_err());
+            assert_eq!(k.record_stats_snapshot().await.unwrap().len(), 1);
+            // Nothing new since the last snapshot
+            assert!(k.record_stats_snapshot().await.unwrap().is_empty());
+        }
+
+        let history = StatsHistory::with_file(&stats_path, retention).unwrap();
+        let samples = history.query(Some("echo"), 0, u64::MAX).await;
+        let (invocations, errors) = samples.iter().fold((0, 0), |(i, e), s| (i + s.invocations, e + s.errors));
+        assert_eq!((invocations, errors), (4, 2));
+        history.export(0, u64::MAX).await.verify().unwrap();
+
+        let usage = Kernel::new()
+            .unwrap()
+            .with_stats_history(history)
+            .storage()
+            .usage_report()
+            .await
+            .unwrap();
+        assert_eq!(usage.areas[0].area, crate::storage::StorageArea::StatsHistory);
+    }
+
+    #[tokio::test]
+    async fn test_module_catalog_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let catalog_manifest = |name: &str, version: &str, checksum: &str| {
+            serde_json::json!({
+                "name": name,
+                "version": version,
+                "path": "echo.wat",
+                "checksum": checksum,
+                "capabilities": []
+            })
+            .to_string()
+        };
+        let checksum = hex::encode(Sha256::digest(JSON_ABI_WAT.as_bytes()));
+        std::fs::write(dir.path().join("echo.wat"), JSON_ABI_WAT).unwrap();
+        std::fs::write(dir.path().join("echo-1.json"), catalog_manifest("echo", "1.9.0", &checksum)).unwrap();
+        std::fs::write(dir.path().join("echo-2.json"), catalog_manifest("echo", "1.10.0", &checksum)).unwrap();
+        std::fs::write(dir.path().join("tampered.json"), catalog_manifest("tampered", "1.0.0", "00")).unwrap();
+
+        assert!(Kernel::new().unwrap().available_modules().await.is_err());
+
+        let k = Kernel::new().unwrap().with_module_catalog(ModuleCatalog::open(dir.path()).unwrap());
+        let entries = k.available_modules().await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].verification, ModuleVerification::Unsigned);
+        assert!(!entries[2].verification.is_installable());
+
+        let installed = k.install_module("echo", None).await.unwrap();
+        assert_eq!(installed.version.as_deref(), Some("1.10.0"));
+        assert_eq!(k.list_modules().await, vec!["echo"]);
+        assert!(k.install_module("tampered", None).await.is_err());
+        assert!(k.install_module("missing", None).await.is_err());
+
+        let entries = k.available_modules().await.unwrap();
+        assert_eq!(entries[0].installed.as_ref().unwrap().version.as_deref(), Some("1.10.0"));
+
+        // A restarted kernel reloads what was installed
+        let restarted = Kernel::new().unwrap().with_module_catalog(ModuleCatalog::open(dir.path()).unwrap());
+        assert_eq!(restarted.load_installed_modules().await.unwrap(), vec!["echo"]);
+        assert!(restarted.execute_function("echo", "echo_json", b"{}").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_module_version_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let checksum = hex::encode(Sha256::digest(JSON_ABI_WAT.as_bytes()));
+        std::fs::write(dir.path().join("echo.wat"), JSON_ABI_WAT).unwrap();
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            let manifest = serde_json::json!({
+                "name": "echo",
+                "version": version,
+                "path": "echo.wat",
+                "checksum": checksum,
+                "capabilities": []
+            });
+            std::fs::write(dir.path().join(format!("echo-{}.json", version)), manifest.to_string()).unwrap();
+        }
+
+        let catalog = ModuleCatalog::open(dir.path()).unwrap().with_retained_versions(1);
+        let k = Kernel::new().unwrap().with_module_catalog(catalog);
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            k.install_module("echo", Some(version)).await.unwrap();
+        }
+
+        // The installed version plus one previous version are kept
+        let entries = k.available_modules().await.unwrap();
+        assert_eq!(entries[0].stored_versions, vec!["1.1.0", "1.2.0"]);
+        assert!(k.rollback_module("echo", "1.0.0").await.is_err());
+
+        let rolled_back = k.rollback_module("echo", "1.1.0").await.unwrap();
+        assert_eq!(rolled_back.version.as_deref(), Some("1.1.0"));
+        assert!(k.execute_function("echo", "echo_json", b"{}").await.is_ok());
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ModuleRolledBack { from_version: Some(from), to_version, .. }
+                if from == "1.2.0" && to_version == "1.1.0"
+        )));
+
+        // The pinned copy is loaded on restart even if the catalog manifests are gone
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            std::fs::remove_file(dir.path().join(format!("echo-{}.json", version))).unwrap();
+        }
+        let restarted = Kernel::new().unwrap().with_module_catalog(ModuleCatalog::open(dir.path()).unwrap());
+        assert_eq!(restarted.load_installed_modules().await.unwrap(), vec!["echo"]);
+
+        // A tampered stored copy fails verification and is not swapped in
+        let stored = dir.path().join(".versions/echo/1.2.0/module.wasm");
+        std::fs::write(&stored, "(module)").unwrap();
+        assert!(restarted.rollback_module("echo", "1.2.0").await.is_err());
+        let catalog = restarted.catalog().unwrap();
+        assert_eq!(catalog.installed_module("echo").await.unwrap().version.as_deref(), Some("1.1.0"));
+    }
+
+    const YIELDING_WAT: &str = r#"
+        (module
+          (import "env" "host_yield" (func $yield))
+          (memory (export "memory") 1)
+          (func (export "alloc") (param $size i32) (result i32)
+            (i32.const 1024))
+          (func (export "yield_three_json") (param $ptr i32) (param $len i32) (result i32)
+            (call $yield)
+            (call $yield)
+            (call $yield)
+            (i32.store (i32.const 16) (i32.const 2))
+            (i32.store16 (i32.const 20) (i32.const 0x7d7b))
+            (i32.const 16))
+          (func (export "spin_json") (param $ptr i32) (param $len i32) (result i32)
+            (loop $forever
+              (call $yield)
+              (br $forever))
+            (i32.const 0)))
+    "#;
+
+    #[tokio::test]
+    async fn test_shutdown_signals_modules_and_cancels_after_drain_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooked_wat = YIELDING_WAT.replacen("(memory", "(func (export \"__shutdown\") (call $yield))\n          (memory", 1);
+        let hooked = write_test_module(dir.path(), "hooked", &hooked_wat);
+        let batch = write_test_module(dir.path(), "batch", YIELDING_WAT);
+
+        let config = ExecutionConfig {
+            shutdown_drain_timeout: Duration::from_millis(100),
+            max_fuel: u64::MAX / 2,
+            ..Default::default()
+        };
+        let k = Arc::new(Kernel::with_config(config).unwrap());
+        k.launch_module(&hooked).await.unwrap();
+        k.launch_module(&batch).await.unwrap();
+
+        let spinning = {
+            let k = k.clone();
+            tokio::spawn(async move { k.execute_function("batch", "spin_json", b"{}").await })
+        };
+        while k.scheduler.pending("batch") == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let outcomes = k.shutdown().await.unwrap();
+        assert_eq!(outcomes.len(), 2);
+        let (batch, hooked) = (&outcomes[0], &outcomes[1]);
+        assert_eq!((batch.module.as_str(), hooked.module.as_str()), ("batch", "hooked"));
+        assert_eq!(batch.signal, ShutdownSignal::NotExported);
+        assert_eq!(batch.aborted_invocations, 1);
+        assert!(!batch.is_graceful());
+        assert_eq!(hooked.signal, ShutdownSignal::Completed);
+        assert!(hooked.is_graceful());
+
+        // The stuck invocation was cancelled at a yield point, and nothing new is admitted
+        let error = spinning.await.unwrap().unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(KernelError::ShuttingDown)));
+        let error = k.scheduler.admit("batch", None).await.err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(KernelError::ShuttingDown)));
+        assert!(k.list_modules().await.is_empty());
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ModuleShutdown { module_name, signal, aborted_invocations: 0, .. }
+                if module_name == "hooked" && signal == "completed"
+        )));
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ModuleShutdown { module_name, aborted_invocations: 1, .. } if module_name == "batch"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_unload_module_revokes_capabilities_and_flushes_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let echo = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let other = write_test_module(dir.path(), "other", JSON_ABI_WAT);
+        let history = StatsHistory::new(crate::stats_history::DEFAULT_RETENTION);
+        let k = Kernel::new().unwrap().with_stats_history(history);
+        k.launch_module(&echo).await.unwrap();
+        k.launch_module(&other).await.unwrap();
+        k.execute_function("echo", "echo_json", b"{}").await.unwrap();
+        k.execute_function("other", "echo_json", b"{}").await.unwrap();
+        let rights: HashSet<CapabilityRight> = [CapabilityRight::Read].into_iter().collect();
+        let token = k
+            .grant_module_capability("echo", ResourceType::Module, "ledger", rights.clone(), CapabilityValidity::default())
+            .await
+            .unwrap();
+        let kept = k
+            .grant_module_capability("other", ResourceType::Module, "ledger", rights, CapabilityValidity::default())
+            .await
+            .unwrap();
+        let nonce = k.registry.read().await.instance_nonce("echo").unwrap();
+
+        let unload = k.unload_module("echo", true).await.unwrap();
+        assert_eq!(unload.shutdown.signal, ShutdownSignal::NotExported);
+        assert!(unload.shutdown.is_graceful());
+        assert_eq!((unload.capabilities_revoked, unload.stats_flushed), (1, true));
+        assert_eq!(k.list_modules().await, vec!["other"]);
+        assert!(k.execute_function("echo", "echo_json", b"{}").await.is_err());
+
+        let manager = k.capability_manager();
+        assert!(matches!(
+            manager.validate_from_instance(&token, &nonce, &[CapabilityRight::Read]).await,
+            Err(crate::security::capabilities::CapabilityError::Revoked)
+        ));
+        let other_nonce = k.registry.read().await.instance_nonce("other").unwrap();
+        assert!(manager.validate_from_instance(&kept, &other_nonce, &[CapabilityRight::Read]).await.is_ok());
+
+        let history = k.stats_history().unwrap();
+        let samples = history.query(Some("echo"), 0, u64::MAX).await;
+        assert_eq!(samples.iter().map(|s| s.invocations).sum::<u64>(), 1);
+        // Only the unloaded module is flushed; the others are counted once, at the next snapshot
+        assert!(history.query(Some("other"), 0, u64::MAX).await.is_empty());
+        k.record_stats_snapshot().await.unwrap();
+        let samples = history.query(Some("other"), 0, u64::MAX).await;
+        assert_eq!(samples.iter().map(|s| s.invocations).sum::<u64>(), 1);
+        assert_eq!(history.query(Some("echo"), 0, u64::MAX).await.len(), 1);
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ModuleUnloaded { module_name, capabilities_revoked: 1, stats_flushed: true, .. }
+                if module_name == "echo"
+        )));
+
+        let error = k.unload_module("echo", false).await.unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(KernelError::ModuleNotLoaded(_))));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_queued_invocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let batch = write_test_module(dir.path(), "batch", YIELDING_WAT);
+        let config = ExecutionConfig { max_concurrent_invocations: 1, ..Default::default() };
+        let k = Arc::new(Kernel::with_config(config).unwrap());
+        k.launch_module(&batch).await.unwrap();
+
+        // Hold the only slot so both calls are queued when shutdown starts
+        let held = k.scheduler.admit("batch", None).await.unwrap();
+        let calls: Vec<_> = (0..2)
+            .map(|_| {
+                let k = k.clone();
+                tokio::spawn(async move { k.execute_function("batch", "yield_three_json", b"{}").await })
+            })
+            .collect();
+        while k.scheduler.pending("batch") < 3 {
+            tokio::task::yield_now().await;
+        }
+
+        let shutdown = {
+            let k = k.clone();
+            tokio::spawn(async move { k.shutdown().await })
+        };
+        while !k.scheduler.closed.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+        drop(held);
+
+        let outcomes = shutdown.await.unwrap().unwrap();
+        assert!(outcomes[0].is_graceful(), "{:?}", outcomes[0]);
+        for call in calls {
+            assert_eq!(call.await.unwrap().unwrap().output, b"{}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_yield_charges_fuel_and_allows_timeouts() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "batch", YIELDING_WAT);
+
+        let config = ExecutionConfig {
+            call_timeout: Some(Duration::from_millis(50)),
+            yield_fuel_cost: 1_000,
+            max_fuel: u64::MAX / 2,
+            ..Default::default()
+        };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest).await.unwrap();
+
+        let report = k.execute_function("batch", "yield_three_json", b"{}").await.unwrap();
+        assert_eq!(report.output, b"{}");
+        assert!(report.fuel_consumed >= 3_000, "consumed {}", report.fuel_consumed);
+
+        // A guest that never returns is stopped at a yield point once the timeout passes
+        let error = k.execute_function("batch", "spin_json", b"{}").await.unwrap_err();
+        match error.downcast_ref::<KernelError>() {
+            Some(KernelError::CallTimedOut { function, yields, .. }) => {
+                assert_eq!(function, "spin_json");
+                assert!(*yields > 0);
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert_eq!(k.registry.read().await.get_module_stats("batch").await.unwrap().error_count, 1);
+
+        // Yielding draws on the same fuel budget as instructions
+        let k = Kernel::with_config(ExecutionConfig { max_fuel: 100_000, ..Default::default() }).unwrap();
+        k.launch_module(&manifest).await.unwrap();
+        assert!(k.execute_function("batch", "spin_json", b"{}").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_invocation_by_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "batch", YIELDING_WAT);
+        let config = ExecutionConfig { max_fuel: u64::MAX / 2, max_concurrent_invocations: 1, ..Default::default() };
+        let k = Arc::new(Kernel::with_config(config).unwrap());
+        k.launch_module(&manifest).await.unwrap();
+
+        let spinning = {
+            let k = k.clone();
+            tokio::spawn(async move { k.execute_function("batch", "spin_json", b"{}").with_id("report-1").await })
+        };
+        while k.scheduler.pending("batch") == 0 {
+            tokio::task::yield_now().await;
+        }
+        // A second call waits for the only slot; cancelling it leaves the queue
+        let queued = {
+            let k = k.clone();
+            tokio::spawn(async move { k.execute_function("batch", "yield_three_json", b"{}").with_id("report-2").await })
+        };
+        while k.scheduler.pending("batch") < 2 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(k.running_invocations(), vec!["report-1", "report-2"]);
+        let duplicate = k.execute_function("batch", "yield_three_json", b"{}").with_id("report-1").await;
+        assert!(matches!(duplicate.unwrap_err().downcast_ref(), Some(KernelError::InvalidRequest(_))));
+
+        assert!(k.cancel_invocation("report-2"));
+        assert!(k.cancel_invocation("report-1"));
+        assert!(!k.cancel_invocation("report-3"));
+        for (call, id) in [(spinning, "report-1"), (queued, "report-2")] {
+            match call.await.unwrap().unwrap_err().downcast_ref() {
+                Some(KernelError::Cancelled { invocation_id, .. }) => assert_eq!(invocation_id, id),
+                other => panic!("expected a cancellation, got {:?}", other),
+            }
+        }
+        assert!(k.running_invocations().is_empty());
+
+        // Audited as cancellations, not failures, and not counted as errors
+        let entries = k.audit_log().get_all_entries().await;
+        let cancelled: Vec<&str> = entries
+            .iter()
+            .filter_map(|e| match &e.event {
+                AuditEventType::InvocationCancelled { invocation_id, .. } => Some(invocation_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cancelled.len(), 2);
+        assert!(cancelled.contains(&"report-1") && cancelled.contains(&"report-2"));
+        assert!(!entries.iter().any(|e| matches!(e.event, AuditEventType::ExecutionFailed { .. })));
+        let stats = k.registry.read().await.get_module_stats("batch").await.unwrap();
+        assert_eq!((stats.invocation_count, stats.error_count), (2, 0));
+
+        // A handle cancels the invocation it was taken from, even before it runs
+        let invocation = k.execute_function("batch", "spin_json", b"{}");
+        invocation.cancel_handle().cancel();
+        let error = invocation.await.unwrap_err();
+        assert_eq!(crate::user_errors::from_anyhow(&error, crate::Locale::English).code, "CANCELLED");
+    }
+
+    /// A guest that never yields is interrupted by the epoch bump
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_cancel_interrupts_guest_without_yields() {
+        const BUSY_WAT: &str = r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "spin_json") (param i32 i32) (result i32) (loop (br 0)) (i32.const 0)))
+        "#;
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "busy", BUSY_WAT);
+        let k = Kernel::with_config(ExecutionConfig { max_fuel: u64::MAX / 2, ..Default::default() }).unwrap();
+        k.launch_module(&manifest).await.unwrap();
+
+        let invocation = k.execute_function("busy", "spin_json", b"{}");
+        let handle = invocation.cancel_handle();
+        // The guest holds this thread, so cancel from another one
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            handle.cancel();
+        });
+        let error = invocation.await.unwrap_err();
+        canceller.join().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(KernelError::Cancelled { .. })), "{:?}", error);
+        assert_eq!(TrapKind::of(&error), None);
+    }
+
+    #[tokio::test]
+    async fn test_resource_profile_stops_anomalies() {
+        // Loops once per input byte, growing memory a page for each `g`
+        const WORK_WAT: &str = r#"
+            (module
+              (memory (export "memory") 1)
+              (global $next (mut i32) (i32.const 1024))
+              (func $alloc (export "alloc") (param $size i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $size)))
+                (local.get $ptr))
+              (func (export "work_json") (param $ptr i32) (param $len i32) (result i32)
+                (local $i i32) (local $out i32)
+                (block $done
+                  (loop $next
+                    (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                    (if (i32.eq (i32.load8_u (i32.add (local.get $ptr) (local.get $i))) (i32.const 103))
+                      (then (drop (memory.grow (i32.const 1)))))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br $next)))
+                (local.set $out (call $alloc (i32.const 4)))
+                (i32.store (local.get $out) (i32.const 0))
+                (local.get $out)))
+        "#;
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "work", WORK_WAT);
+        let config = ExecutionConfig {
+            resource_profile: ResourceProfileConfig { enforce: true, min_samples: 5, ..Default::default() },
+            ..Default::default()
+        };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest).await.unwrap();
+        for _ in 0..5 {
+            k.execute_function("work", "work_json", b"aaaa").await.unwrap();
+        }
+        let stats = k.registry.read().await.get_module_stats("work").await.unwrap();
+        assert_eq!(stats.resource_profile.samples, 5);
+        assert!(stats.resource_profile.p99_fuel > 0);
+        assert_eq!(stats.peak_memory_bytes, 65_536);
+
+        // Far more work than usual is stopped at 10× the usual fuel
+        let err = k.execute_function("work", "work_json", &[b'a'; 5_000]).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(KernelError::ResourceAnomaly { resource, limit, .. })
+                if resource == "fuel" && *limit == stats.resource_profile.p99_fuel * 10
+        ), "{}", err);
+
+        // So is memory growth past 10× the usual size, which the interpreter
+        // also charges fuel for
+        let err = k.execute_function("work", "work_json", &[b'g'; 20]).await.unwrap_err();
+        match err.downcast_ref() {
+            Some(KernelError::ResourceAnomaly { resource, used, .. }) if resource == "memory" => {
+                assert_eq!(*used, 21 * 65_536)
+            }
+            Some(KernelError::ResourceAnomaly { resource, .. }) => {
+                assert!(cfg!(feature = "interpreter") && resource == "fuel", "{}", err)
+            }
+            _ => panic!("{}", err),
+        }
+
+        let entries = k.audit_log().get_all_entries().await;
+        let anomalies = entries
+            .iter()
+            .filter(|e| matches!(e.event, AuditEventType::ResourceAnomaly { .. }))
+            .count();
+        assert_eq!(anomalies, 2);
+        let stats = k.registry.read().await.get_module_stats("work").await.unwrap();
+        assert_eq!((stats.resource_profile.samples, stats.error_count), (5, 2));
+
+        // A relaunch keeps the profile
+        k.launch_module(&manifest).await.unwrap();
+        let stats = k.registry.read().await.get_module_stats("work").await.unwrap();
+        assert_eq!((stats.resource_profile.samples, stats.invocation_count), (5, 0));
+        assert!(k.execute_function("work", "work_json", &[b'a'; 5_000]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_admits_tenants_round_robin() {
+        let scheduler = Arc::new(InvocationScheduler::new(1, 3));
+        let running = scheduler.admit("accrual", Some("acme")).await.unwrap();
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        for (queued, (label, tenant)) in [("acme-2", "acme"), ("acme-3", "acme"), ("globex-1", "globex")]
+            .into_iter()
+            .enumerate()
+        {
+            let waiter = scheduler.clone();
+            let order_tx = order_tx.clone();
+            tokio::spawn(async move {
+                let _permit = waiter.admit("accrual", Some(tenant)).await.unwrap();
+                order_tx.send(label).unwrap();
+            });
+            while scheduler.status()[0].queued == queued {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let error = scheduler.admit("accrual", Some("acme")).await.err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(KernelError::QueueFull { queued: 3, .. })));
+        // Other modules have their own queues
+        drop(scheduler.admit("reporting", None).await.unwrap());
+
+        drop(running);
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            order.push(order_rx.recv().await.unwrap());
+        }
+        assert_eq!(order, vec!["acme-2", "globex-1", "acme-3"]);
+        assert_eq!(
+            scheduler.status()[0],
+            InvocationQueueStatus { module: "accrual".into(), running: 0, queued: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_invocation_queue_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "batch", YIELDING_WAT);
+        let config = ExecutionConfig {
+            call_timeout: Some(Duration::from_millis(200)),
+            yield_fuel_cost: 0,
+            max_concurrent_invocations: 1,
+            max_queued_invocations: 0,
+            ..Default::default()
+        };
+        let k = Arc::new(Kernel::with_config(config).unwrap());
+        k.launch_module(&manifest).await.unwrap();
+
+        let busy = {
+            let k = k.clone();
+            tokio::spawn(async move { k.execute_function("batch", "spin_json", b"{}").await })
+        };
+        while k.get_status().await.invocation_queues.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        let error = k.execute_function("batch", "yield_three_json", b"{}").await.unwrap_err();
+        assert_eq!(crate::user_errors::from_anyhow(&error, crate::user_errors::Locale::English).code, "BUSY");
+        assert!(busy.await.unwrap().is_err());
+
+        // Rejected calls are not counted against the module
+        let stats = k.registry.read().await.get_module_stats("batch").await.unwrap();
+        assert_eq!(stats.invocation_count, 1);
+        assert!(k.execute_function("batch", "yield_three_json", b"{}").await.is_ok());
+    }
+
+    // The interpreter backend has no compiled code to cache
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_module_cache_reused_across_launches() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let cache_dir = dir.path().join("cache");
+        let secrets = dir.path().join("secrets.json");
+        let open = || SecretStore::open_with_key(&secrets, crate::security::MasterKey::from_bytes([7; 32])).unwrap();
+
+        let k = Kernel::new().unwrap().with_module_cache(&cache_dir, &mut open()).unwrap();
+        k.launch_module(&manifest).await.unwrap();
+        let entries = std::fs::read_dir(&cache_dir).unwrap().count();
+        assert_eq!(entries, 2, "compiled module and its tag");
+
+        // A restarted kernel loads the cached compilation
+        let restarted = Kernel::new().unwrap().with_module_cache(&cache_dir, &mut open()).unwrap();
+        restarted.launch_module(&manifest).await.unwrap();
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), entries);
+        let report = restarted.execute_function("echo", "echo_json", b"{\"a\":1}").await.unwrap();
+        assert_eq!(report.output, b"{\"a\":1}");
+
+        let usage = restarted.storage().usage_report().await.unwrap();
+        assert_eq!(usage.areas[0].area, crate::storage::StorageArea::ModuleCache);
+        assert_eq!(usage.areas[0].files, 2);
+    }
+
+    #[tokio::test]
+    async fn test_storage_vacuum_applies_lowered_version_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let checksum = hex::encode(Sha256::digest(JSON_ABI_WAT.as_bytes()));
+        std::fs::write(dir.path().join("echo.wat"), JSON_ABI_WAT).unwrap();
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            let manifest = serde_json::json!({
+                "name": "echo",
+                "version": version,
+                "path": "echo.wat",
+                "checksum": checksum,
+                "capabilities": []
+            });
+            std::fs::write(dir.path().join(format!("echo-{}.json", version)), manifest.to_string()).unwrap();
+        }
+
+        let k = Kernel::new().unwrap().with_module_catalog(ModuleCatalog::open(dir.path()).unwrap());
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            k.install_module("echo", Some(version)).await.unwrap();
+        }
+
+        // Retention lowered after the versions were stored
+        let k = Kernel::new()
+            .unwrap()
+            .with_module_catalog(ModuleCatalog::open(dir.path()).unwrap().with_retained_versions(0))
+            .with_storage_limits(StorageLimits { alert_bytes: Some(1), ..Default::default() });
+        let before = k.storage().usage_report().await.unwrap();
+        assert!(before.over_limit());
+
+        let vacuumed = k.storage().vacuum().await.unwrap();
+        assert_eq!(vacuumed.versions_pruned, vec!["echo@1.0.0", "echo@1.1.0"]);
+        assert!(vacuumed.bytes_reclaimed > 0);
+
+        let after = k.storage().usage_report().await.unwrap();
+        assert_eq!(after.total_bytes, before.total_bytes - vacuumed.bytes_reclaimed);
+        assert_eq!(k.available_modules().await.unwrap()[0].stored_versions, vec!["1.2.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_module_capability_bound_to_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let echo = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let other = write_test_module(dir.path(), "other", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        let rights: HashSet<CapabilityRight> = [CapabilityRight::Read].into_iter().collect();
+        assert!(k
+            .grant_module_capability("echo", ResourceType::Module, "ledger", rights.clone(), CapabilityValidity::default())
+            .await
+            .is_err());
+
+        k.launch_module(&echo).await.unwrap();
+        k.launch_module(&other).await.unwrap();
+        let token = k
+            .grant_module_capability("echo", ResourceType::Module, "ledger", rights, CapabilityValidity::default())
+            .await
+            .unwrap();
+
+        let store_for = |name: &str, nonce: Option<InstanceNonce>| {
+            k.store_data(Vec::new(), name.to_string(), None, nonce.unwrap())
+        };
+
+        let nonce = k.registry.read().await.instance_nonce("echo");
+        let echo_store = store_for("echo", nonce);
+        assert!(echo_store.validate_capability(&token, &[CapabilityRight::Read]).await.is_ok());
+
+        let nonce = k.registry.read().await.instance_nonce("other");
+        let other_store = store_for("other", nonce);
+        assert!(other_store.validate_capability(&token, &[CapabilityRight::Read]).await.is_err());
+        assert!(k.capability_manager().validate(&token, &[CapabilityRight::Read]).await.is_err());
+
+        // A relaunched module is a new instance
+        k.launch_module(&echo).await.unwrap();
+        let nonce = k.registry.read().await.instance_nonce("echo");
+        let relaunched = store_for("echo", nonce);
+        assert!(relaunched.validate_capability(&token, &[CapabilityRight::Read]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_imports_checked_against_capabilities() {
+        const LOGGING_WAT: &str = r#"
+            (module
+              (import "env" "host_heartbeat" (func $beat))
+              (import "env" "host_log" (func $log (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "beat_json") (param i32 i32) (result i32) (call $beat) (i32.const 2048))
+              (func (export "log_json") (param i32 i32) (result i32)
+                (call $log (i32.const 1) (i32.const 0) (i32.const 0))
+                (i32.const 2048)))
+        "#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_test_module(dir.path(), "logger", LOGGING_WAT);
+        let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+
+        // Without `log` the module loads; only calls to host_log fail
+        let k = Kernel::new().unwrap();
+        k.launch_manifest(manifest.clone()).await.unwrap();
+        k.execute_function("logger", "beat_json", b"{}").await.unwrap();
+        let err = k.execute_function("logger", "log_json", b"{}").await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::CapabilityDenied { import, capability, .. })
+            if import == "env::host_log" && capability == "log"), "{:?}", err);
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::HostCallDenied { module_name, function_name, capability, right, resource, .. }
+                if module_name == "logger" && function_name == "log_json" && capability == "log"
+                    && right == "log" && *resource == ResourceType::Process
+        )));
+        assert!(!entries.iter().any(|e| matches!(e.event, AuditEventType::ModuleImportDenied { .. })));
+        assert!(!entries.iter().any(|e| matches!(e.event, AuditEventType::ModuleCrashed { .. })));
+        let stubbed = |entries: &[crate::security::audit::AuditEntry]| {
+            entries
+                .iter()
+                .filter_map(|e| match &e.event {
+                    AuditEventType::ModuleImportsStubbed { module_name, imports } if module_name == "logger" => Some(imports.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected: BTreeMap<String, String> = [("env::host_log".to_string(), "log".to_string())].into();
+        assert_eq!(stubbed(&entries), vec![expected]);
+
+        manifest.capabilities.push("log".to_string());
+        k.launch_manifest(manifest).await.unwrap();
+        k.execute_function("logger", "log_json", b"{}").await.unwrap();
+        assert_eq!(stubbed(&k.audit_log().get_all_entries().await).len(), 1);
+
+        // WASI imports and imports the host does not provide cannot be stubbed
+        let path = write_test_module(dir.path(), "printer", r#"(module (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))"#);
+        let err = k.launch_module(&path).await.unwrap_err();
+        match err.downcast_ref() {
+            Some(KernelError::MissingCapability { module, import, capability }) => {
+                assert_eq!(
+                    (module.as_str(), import.as_str(), capability.as_str()),
+                    ("printer", "wasi_snapshot_preview1::proc_exit", "wasi")
+                );
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ModuleImportDenied { module_name, capability: Some(capability), .. }
+                if module_name == "printer" && capability == "wasi"
+        )));
+
+        let path = write_test_module(dir.path(), "kv", r#"(module (import "env" "host_kv_set" (func (param i32 i32))))"#);
+        let err = k.launch_module(&path).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::UnknownImport { import, .. }) if import == "env::host_kv_set"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_ungranted_host_functions_trap_when_called() {
+        const LOGGING_WAT: &str = r#"
+            (module
+              (import "env" "host_heartbeat" (func $beat))
+              (import "env" "host_log" (func $log (param i32 i32 i32)))
+              (func (export "beat") (call $beat))
+              (func (export "log") (call $log (i32.const 1) (i32.const 0) (i32.const 0))))
+        "#;
+        let k = Kernel::new().unwrap();
+        let module = k.runtime.compile(LOGGING_WAT.as_bytes()).unwrap();
+
+        // Without `log` the module links, but host_log is its denial stub
+        let mut denied = k
+            .instantiate(&module, &[], "logger", None, &InstanceNonce::generate(), k.config().max_fuel)
+            .await
+            .unwrap();
+        denied.call("beat", &[]).await.unwrap();
+        let err = denied.call("log", &[]).await.unwrap_err();
+        match err.downcast_ref() {
+            Some(KernelError::CapabilityDenied { module, import, capability }) => {
+                assert_eq!((module.as_str(), import.as_str(), capability.as_str()), ("logger", "env::host_log", "log"));
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+        assert_eq!(TrapKind::of(&err), Some(TrapKind::HostError));
+
+        let mut granted = k
+            .instantiate(&module, &[Capability::Log], "logger", None, &InstanceNonce::generate(), k.config().max_fuel)
+            .await
+            .unwrap();
+        granted.call("log", &[]).await.unwrap();
+    }
+
+    // Drives wasmtime's host functions directly
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_guest_policy_cache_tracks_updates() {
+        const POLICY_WAT: &str = r#"
+            (module
+              (import "env" "host_policy_version" (func $version (param i32 i32) (result i64)))
+              (import "env" "host_policy_get" (func $get (param i32 i32 i32 i32) (result i32)))
+              (import "env" "host_policy_generation" (func $generation (result i64)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "acme")
+              (data (i32.const 8) "other")
+              (func (export "acme_version") (result i64) (call $version (i32.const 0) (i32.const 4)))
+              (func (export "other_version") (result i64) (call $version (i32.const 8) (i32.const 5)))
+              (func (export "acme_get") (param i32) (result i32)
+                (call $get (i32.const 0) (i32.const 4) (i32.const 64) (local.get 0)))
+              (func (export "generation") (result i64) (call $generation)))
+        "#;
+
+        let k = Kernel::new().unwrap();
+        let module = k.runtime.compile(POLICY_WAT.as_bytes()).unwrap();
+        let instantiated = k
+            .instantiate(&module, &[Capability::PolicyRead], "cache", Some("acme"), &InstanceNonce::generate(), k.config().max_fuel)
+            .await
+            .unwrap();
+        let (mut store, instance) = (instantiated.store, instantiated.instance);
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        let version = instance.get_typed_func::<(), i64>(&mut store, "acme_version").unwrap();
+        let other_version = instance.get_typed_func::<(), i64>(&mut store, "other_version").unwrap();
+        let get = instance.get_typed_func::<i32, i32>(&mut store, "acme_get").unwrap();
+        let generation = instance.get_typed_func::<(), i64>(&mut store, "generation").unwrap();
+
+        assert_eq!(version.call_async(&mut store, ()).await.unwrap(), 0);
+        assert_eq!(get.call_async(&mut store, 1024).await.unwrap(), 0);
+
+        let policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        k.set_tenant_policy("acme", policy.clone(), "2025-03-01".parse().unwrap()).await.unwrap();
+        let revision = version.call_async(&mut store, ()).await.unwrap();
+        assert!(revision > 0);
+
+        // A short buffer gets the length needed and is left untouched
+        let len = get.call_async(&mut store, 1).await.unwrap();
+        assert!(len > 1);
+        assert_eq!(memory.data(&store)[64], 0);
+        assert_eq!(get.call_async(&mut store, 1024).await.unwrap(), len);
+        let json: serde_json::Value =
+            serde_json::from_slice(&memory.data(&store)[64..64 + len as usize]).unwrap();
+        assert_eq!(json["revision"], revision);
+        assert_eq!(json["versions"][0]["policy"]["max_usage_hours"], 72);
+
+        // Other tenants' updates move the generation but not acme's revision
+        let before = generation.call_async(&mut store, ()).await.unwrap();
+        k.set_tenant_policy("other", policy.clone(), "2025-03-01".parse().unwrap()).await.unwrap();
+        assert_eq!(generation.call_async(&mut store, ()).await.unwrap(), before + 1);
+        assert_eq!(version.call_async(&mut store, ()).await.unwrap(), revision);
+        assert_eq!(other_version.call_async(&mut store, ()).await.unwrap(), -2);
+
+        k.set_tenant_policy("acme", policy, "2026-01-01".parse().unwrap()).await.unwrap();
+        assert!(version.call_async(&mut store, ()).await.unwrap() > revision);
+    }
+
+    #[tokio::test]
+    async fn test_capability_secret_persisted_and_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let echo = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let secrets = dir.path().join("secrets.json");
+        let open = || SecretStore::open_with_key(&secrets, crate::security::MasterKey::from_bytes([7; 32])).unwrap();
+
+        let k = Kernel::new().unwrap().with_secret_store(open()).unwrap();
+        k.launch_module(&echo).await.unwrap();
+        let rights: HashSet<CapabilityRight> = [CapabilityRight::Read].into_iter().collect();
+        let token = k
+            .grant_module_capability("echo", ResourceType::Module, "ledger", rights, CapabilityValidity::default())
+            .await
+            .unwrap();
+        let nonce = k.registry.read().await.instance_nonce("echo").unwrap();
+        let manager = k.capability_manager();
+
+        let reissued = k.rotate_capability_secret().await.unwrap();
+        assert_eq!(reissued.len(), 1);
+        assert_eq!(reissued[0].previous, token);
+        assert!(manager.validate_from_instance(&token, &nonce, &[CapabilityRight::Read]).await.is_err());
+        assert!(manager
+            .validate_from_instance(&reissued[0].token, &nonce, &[CapabilityRight::Read])
+            .await
+            .is_ok());
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::CapabilitySecretRotated { generation: Some(2), reissued: 1, revoked: 0 }
+        )));
+
+        // The rotated secret survives a restart
+        let store = open();
+        assert_eq!(store.generation(CAPABILITY_SECRET), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_execute_function_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        assert!(k.execute_function("echo", "echo_json", b"{}").await.is_err());
+
+        k.launch_module(&manifest_path).await.unwrap();
+        assert!(k.execute_function("echo", "reject_json", b"{}").await.is_err());
+        assert!(k.execute_function("echo", "missing", b"{}").await.is_err());
+
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!(stats.error_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_execute_dry_run_has_no_side_effects() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+        k.tenants().register("acme").await.unwrap();
+        let audit_before = k.audit_log().get_all_entries().await.len();
+
+        let report = k.execute_dry_run(Some("acme"), "echo", "echo_json", b"{\"tenant_id\":\"acme\"}").await.unwrap();
+        assert_eq!(report.output, b"{\"tenant_id\":\"acme\"}");
+        assert!(report.archived.is_none());
+        assert!(k.execute_dry_run(None, "echo", "reject_json", b"{}").await.is_err());
+        // Tenant scoping still applies
+        assert!(k.execute_dry_run(Some("acme"), "echo", "echo_json", b"{\"tenant_id\":\"globex\"}").await.is_err());
+
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!((stats.invocation_count, stats.error_count), (0, 0));
+
+        let entries = k.audit_log().get_all_entries().await;
+        let markers: Vec<_> = entries[audit_before..]
+            .iter()
+            .map(|e| match &e.event {
+                AuditEventType::DryRunExecuted { succeeded, tenant_id, .. } => (*succeeded, tenant_id.clone()),
+                other => panic!("unexpected audit event {:?}", other),
+            })
+            .collect();
+        assert_eq!(markers, vec![(true, Some("acme".to_string())), (false, None)]);
+    }
+
+    #[tokio::test]
+    async fn test_result_cache_answers_repeated_invocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let config = ExecutionConfig {
+            result_cache: ResultCacheConfig { enabled: true, ..Default::default() },
+            ..Default::default()
+        };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+        k.tenants().register("acme").await.unwrap();
+        let input = b"{\"tenant_id\":\"acme\"}";
+
+        let first = k.execute_for_tenant("acme", "echo", "echo_json", input).await.unwrap();
+        assert!(!first.cached && first.fuel_consumed > 0);
+        let second = k.execute_for_tenant("acme", "echo", "echo_json", input).await.unwrap();
+        assert!(second.cached);
+        assert_eq!((second.output, second.fuel_consumed), (first.output, 0));
+        let bypassed = k.execute_for_tenant("acme", "echo", "echo_json", input).bypass_cache().await.unwrap();
+        assert!(!bypassed.cached);
+        // Failures are not cached
+        assert!(k.execute_function("echo", "reject_json", b"{}").await.is_err());
+        assert!(k.execute_function("echo", "reject_json", b"{}").await.is_err());
+
+        // A configuration change invalidates what the guest computed under the old one
+        k.set_module_config("acme", "echo", serde_json::json!({ "rounding": "down" })).await.unwrap();
+        let after = k.execute_for_tenant("acme", "echo", "echo_json", input).await.unwrap();
+        assert!(!after.cached);
+
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!(stats.invocation_count, 5);
+        let cache = k.get_status().await.result_cache;
+        assert_eq!((cache.hits, cache.misses, cache.entries), (1, 4, 1));
+        let entries = k.audit_log().get_all_entries().await;
+        assert_eq!(entries.iter().filter(|e| matches!(&e.event, AuditEventType::ExecutionCacheHit { .. })).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_invocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap().with_recorded_inputs(1024);
+        k.launch_module(&manifest_path).await.unwrap();
+        let first = k.audit_log().get_all_entries().await.len() as u64;
+        k.execute_function("echo", "echo_json", b"{\"hours\":8}").await.unwrap();
+        k.execute_function("echo", "echo_json", b"{\"hours\":4}").await.unwrap();
+
+        let entries = k.audit_log().get_all_entries().await;
+        let record = entries.iter().find_map(|e| match &e.event {
+            AuditEventType::InvocationRecorded(record) => Some(record.clone()),
+            _ => None,
+        }).unwrap();
+        assert_eq!(record.input.as_deref(), Some("{\"hours\":8}"));
+        assert_eq!(record.output_hash, ContentStore::hash(b"{\"hours\":8}"));
+
+        // A record whose output does not match what the module produces
+        k.audit_log().log_invocation_recorded(
+            InvocationRecord { output_hash: ContentStore::hash(b"{}"), ..record.clone() },
+            "test",
+        ).await;
+        // A record with neither a payload nor an archived input
+        k.audit_log().log_invocation_recorded(InvocationRecord { input: None, ..record.clone() }, "test").await;
+
+        let report = k.replay(first..=u64::MAX).await.unwrap();
+        let outcomes: Vec<_> = report.invocations.iter().map(|i| i.outcome.clone()).collect();
+        assert_eq!(outcomes, vec![
+            ReplayOutcome::Matched,
+            ReplayOutcome::Matched,
+            ReplayOutcome::Diverged { output_hash: record.output_hash.clone() },
+            ReplayOutcome::InputUnavailable,
+        ]);
+        assert!(!report.is_deterministic());
+        assert_eq!((report.matched(), report.diverged(), report.unavailable()), (2, 1, 1));
+        assert!(matches!(
+            k.audit_log().get_all_entries().await.last().unwrap().event,
+            AuditEventType::ReplayCompleted { invocations: 4, matched: 2, diverged: 1, .. }
+        ));
+
+        // Replays only run against the module version that was recorded
+        let replaced = write_test_module(dir.path(), "echo", &JSON_ABI_WAT.replace("(module", "(module $v2"));
+        k.launch_module(&replaced).await.unwrap();
+        let report = k.replay(first..=first + 3).await.unwrap();
+        assert_eq!(report.invocations[0].outcome, ReplayOutcome::ModuleUnavailable);
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!(stats.invocation_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_for_tenant_scoping() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+        k.tenants().register("acme").await.unwrap();
+
+        let own = br#"{"tenant_id":"acme"}"#;
+        let report = k.execute_for_tenant("acme", "echo", "echo_json", own).await.unwrap();
+        assert_eq!(report.output, own.to_vec());
+
+        let other = br#"{"tenant_id":"globex"}"#;
+        assert!(k.execute_for_tenant("acme", "echo", "echo_json", other).await.is_err());
+        assert!(k.execute_for_tenant("globex", "echo", "echo_json", other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_config_injected_per_call() {
+        // Returns the configuration it was given, in place of a response envelope
+        const CONFIG_WAT: &str = r#"
+            (module
+              (import "env" "host_get_config" (func $config (param i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "config_json") (param i32 i32) (result i32)
+                (i32.store (i32.const 4096) (call $config (i32.const 4100) (i32.const 8192)))
+                (i32.const 4096)))
+        "#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_test_module(dir.path(), "configured", CONFIG_WAT);
+        let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        manifest.capabilities.push("config".to_string());
+
+        let k = Kernel::new().unwrap();
+        k.launch_manifest(manifest).await.unwrap();
+        for tenant in ["acme", "globex"] {
+            k.tenants().register(tenant).await.unwrap();
+        }
+        k.set_module_config("acme", "configured", serde_json::json!({"rounding": "up"})).await.unwrap();
+        let err = k.set_module_config("acme", "configured", serde_json::json!([1])).await.unwrap_err();
+        assert!(matches!(err, TenantError::InvalidModuleConfig(_)), "{}", err);
+
+        let config = |output: Vec<u8>| -> serde_json::Value { serde_json::from_slice(&output).unwrap() };
+        let acme = config(k.execute_for_tenant("acme", "configured", "config_json", b"{}").await.unwrap().output);
+        assert_eq!(acme["tenant_id"], "acme");
+        assert_eq!(acme["config"], serde_json::json!({"rounding": "up"}));
+        assert!(acme["policy"].is_null());
+        let globex = config(k.execute_for_tenant("globex", "configured", "config_json", b"{}").await.unwrap().output);
+        assert_eq!((&globex["tenant_id"], &globex["config"]), (&"globex".into(), &serde_json::json!({})));
+
+        // Calls for no tenant get nothing
+        assert!(k.execute_function("configured", "config_json", b"{}").await.unwrap().output.is_empty());
+
+        k.set_module_config("acme", "configured", serde_json::Value::Null).await.unwrap();
+        assert_eq!(k.module_config("acme", "configured").await.unwrap(), None);
+        let entries = k.audit_log().get_all_entries().await;
+        let changes: Vec<bool> = entries
+            .iter()
+            .filter_map(|e| match &e.event {
+                AuditEventType::ModuleConfigChanged { removed, .. } => Some(*removed),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, vec![false, true]);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_fuel_ceiling() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let config = ExecutionConfig {
+            tenant_fuel: TenantFuelConfig {
+                ceilings: [("acme".to_string(), 1)].into(),
+                price_cents_per_billion: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+        k.tenants().register("acme").await.unwrap();
+        k.tenants().register("globex").await.unwrap();
+
+        let report = k.execute_for_tenant("acme", "echo", "echo_json", b"{}").await.unwrap();
+        let err = k.execute_for_tenant("acme", "echo", "echo_json", b"{}").await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::FuelCeilingReached { ceiling: 1, .. })));
+        k.execute_for_tenant("globex", "echo", "echo_json", b"{}").await.unwrap();
+        k.execute_function("echo", "echo_json", b"{}").await.unwrap();
+
+        let usage = k.tenant_usage("acme").await.unwrap();
+        assert_eq!((usage.invocations, usage.fuel_consumed), (1, report.fuel_consumed));
+        assert_eq!(usage.window_fuel, report.fuel_consumed);
+        assert_eq!(usage.ceiling, Some(1));
+        assert_eq!(usage.cost_cents, Some(1));
+        assert_eq!(usage.modules[0].module, "echo");
+        assert_eq!(k.tenant_usage("globex").await.unwrap().ceiling, None);
+        assert!(k.tenant_usage("initech").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_lifecycle() {
+        use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+
+        let provisioned = k.create_tenant("acme").await.unwrap();
+        let capabilities = k.capability_manager();
+        let root = capabilities.validate_for_tenant(&provisioned.root_capability, "acme", &[CapabilityRight::Delegate]);
+        assert_eq!(root.await.unwrap().resource_id, "tenant:acme/");
+        let err = k.create_tenant("acme").await.unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&TenantError::AlreadyExists("acme".into())));
+
+        k.execute_for_tenant("acme", "echo", "echo_json", b"{}").await.unwrap();
+        k.ledger()
+            .append(NewLedgerEvent {
+                tenant_id: "acme".into(),
+                employee_id: "e1".into(),
+                work_date: "2025-03-03".parse().unwrap(),
+                kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+                policy_version: None,
+                source: "test".into(),
+            })
+            .await
+            .unwrap();
+        assert!(k.purge_tenant("acme").await.is_err());
+
+        assert_eq!(k.archive_tenant("acme").await.unwrap(), 1);
+        assert!(capabilities.validate(&provisioned.root_capability, &[]).await.is_err());
+        let err = k.execute_for_tenant("acme", "echo", "echo_json", b"{}").await.unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&TenantError::Archived("acme".into())));
+        assert_eq!(k.ledger().events_for_tenant("acme").await.len(), 1, "archived records stay readable");
+
+        let report = k.purge_tenant("acme").await.unwrap();
+        assert_eq!((report.ledger_events, report.capabilities_revoked), (1, 0));
+        assert!(k.ledger().is_empty().await);
+        assert!(!k.tenants().contains("acme").await);
+
+        // The tenant's whole trail, invocation included, is one query away
+        let query = AuditQuery { tenant_id: Some("acme".into()), ..Default::default() };
+        let kinds: Vec<String> = k.audit_log().query(&query, 100).await.unwrap().iter().map(|e| e.event.kind()).collect();
+        for kind in ["TenantCreated", "ExecutionCompleted", "TenantArchived", "TenantPurged"] {
+            assert!(kinds.iter().any(|k| k == kind), "no {} in {:?}", kind, kinds);
+        }
+        assert!(k.audit_log().verify_chain().await.valid);
+    }
+
+    #[tokio::test]
+    async fn test_database_write_through_and_restore() {
+        use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("esta.db");
+        let k = Kernel::new().unwrap().with_database(Database::open(&db_path).unwrap());
+        k.create_tenant("acme").await.unwrap();
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        k.set_tenant_policy("acme", policy, "2025-01-01".parse().unwrap()).await.unwrap();
+        k.add_employees("acme", &["e1".into(), "e2".into()]).await.unwrap();
+        let event = |employee_id: &str| NewLedgerEvent {
+            tenant_id: "acme".into(),
+            employee_id: employee_id.into(),
+            work_date: "2025-03-03".parse().unwrap(),
+            kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+            policy_version: Some(1),
+            source: "test".into(),
+        };
+        k.append_ledger_events(vec![event("e1"), event("e2")]).await.unwrap();
+        drop(k);
+
+        // A restart with nothing but the database gets everything back
+        let k = Kernel::new().unwrap().with_database(Database::open(&db_path).unwrap());
+        let restored = k.restore_from_database().await.unwrap();
+        assert_eq!(restored, RestoreReport { tenants_added: 1, employees: 2, ledger_events: 2 });
+        assert_eq!(k.tenants().list_employees("acme").await.unwrap(), vec!["e1", "e2"]);
+        assert_eq!(k.tenants().policy_history("acme").await.unwrap().len(), 1);
+        assert_eq!(k.compliance_report("acme", 2025).await.unwrap().totals.accrued_minutes, 32);
+        let next = k.append_ledger_events(vec![event("e1")]).await.unwrap();
+        assert_eq!(next[0].sequence, 2, "sequence numbers continue");
+
+        // Restoring again adds nothing
+        let again = k.restore_from_database().await.unwrap();
+        assert_eq!((again.tenants_added, again.ledger_events), (0, 0));
+
+        k.archive_tenant("acme").await.unwrap();
+        k.purge_tenant("acme").await.unwrap();
+        let db = k.database().unwrap();
+        assert!(db.tenants().list().await.unwrap().is_empty());
+        assert!(db.ledger().all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backup_moves_records_between_kernels() {
+        use crate::ledger::LedgerEventKind;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("acme.json");
+        let source = Kernel::new().unwrap();
+        source.create_tenant("acme").await.unwrap();
+        source.add_employees("acme", &["e1".to_string(), "e2".to_string()]).await.unwrap();
+        let event = NewLedgerEvent {
+            tenant_id: "acme".into(),
+            employee_id: "e1".into(),
+            work_date: "2025-03-03".parse().unwrap(),
+            kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+            policy_version: None,
+            source: "test".into(),
+        };
+        source.append_ledger_events(vec![event.clone()]).await.unwrap();
+        let summary = source.create_backup(&path).await.unwrap();
+        assert_eq!((summary.tenants, summary.ledger_events), (1, 1));
+        assert_eq!(summary.signer, source.backup_public_key());
+        assert!(summary.audit_entries >= 2);
+
+        // Another installation must be told to trust the source's key
+        let target = Kernel::new().unwrap().with_database(Database::in_memory().unwrap());
+        let err = target.open_backup(&path, &[]).await.unwrap_err();
+        assert_eq!(crate::user_errors::from_anyhow(&err, crate::user_errors::Locale::English).code, "SIGNATURE_INVALID");
+        let archive = target.open_backup(&path, &[source.backup_public_key()]).await.unwrap();
+        let report = target.restore_backup(&archive).await.unwrap();
+        assert_eq!(report, RestoreReport { tenants_added: 1, employees: 2, ledger_events: 1 });
+        assert_eq!(target.tenants().list_employees("acme").await.unwrap(), vec!["e1", "e2"]);
+        assert_eq!(target.database().unwrap().ledger().all().await.unwrap(), source.ledger.events().await);
+
+        let again = target.restore_backup(&archive).await.unwrap();
+        assert_eq!((again.tenants_added, again.ledger_events), (0, 0));
+
+        // A ledger with history of its own is left alone
+        let populated = Kernel::new().unwrap();
+        populated.create_tenant("globex").await.unwrap();
+        let own = NewLedgerEvent { tenant_id: "globex".into(), ..event };
+        populated.append_ledger_events(vec![own]).await.unwrap();
+        let err = populated.restore_backup(&archive).await.unwrap_err();
+        assert_eq!(crate::user_errors::from_anyhow(&err, crate::user_errors::Locale::English).code, "INVALID_REQUEST");
+        assert_eq!(populated.tenants().list_tenants().await, vec!["globex"]);
+        assert_eq!(populated.ledger.len().await, 1);
+        let entries = target.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(&e.event, AuditEventType::BackupRestored { tenants_added: 1, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_retention_redacts_old_records() {
+        use crate::ledger::{LedgerEventKind, REDACTED_EMPLOYEE_PREFIX};
+        use crate::testing::TimeMachine;
+
+        let time = TimeMachine::start_on("2025-01-06".parse().unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::with_segments(Default::default(), dir.path().join("audit")).unwrap();
+        let k = Kernel::new()
+            .unwrap()
+            .with_audit_log(audit)
+            .with_database(Database::open(dir.path().join("esta.db")).unwrap());
+        k.create_tenant("acme").await.unwrap();
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: Some(30),
+        };
+        let effective = "2025-01-01".parse().unwrap();
+        assert!(k.set_tenant_policy("acme", policy.clone(), effective).await.is_err());
+        let policy = TenantPolicy { retention_days: Some(retention::MIN_RETENTION_DAYS), ..policy };
+        k.set_tenant_policy("acme", policy, effective).await.unwrap();
+
+        let event = |employee_id: &str, work_date: &str| NewLedgerEvent {
+            tenant_id: "acme".into(),
+            employee_id: employee_id.into(),
+            work_date: work_date.parse().unwrap(),
+            kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+            policy_version: Some(1),
+            source: "test".into(),
+        };
+        k.append_ledger_events(vec![event("e1", "2025-01-06"), event("e2", "2025-01-06")]).await.unwrap();
+        let old = crate::tenant::scope("acme".into(), k.audit_log().log_custom("payroll", "e1 imported", "test")).await;
+        // Nothing is old enough yet
+        assert_eq!(k.apply_retention("acme").await.unwrap().ledger_events, 0);
+
+        time.advance(Duration::from_secs(86_400 * 1_100)).await;
+        k.append_ledger_events(vec![event("e1", "2028-01-10")]).await.unwrap();
+        let report = k.apply_retention("acme").await.unwrap();
+        assert_eq!(report.ledger_events, 2);
+        assert!(report.audit_entries >= 2, "{:?}", report);
+        assert_eq!(report.audit_entries_skipped, 0);
+
+        // Each employee's old records get their own identifier that says nothing about who they were
+        assert_eq!(k.ledger().events_for_employee("acme", "e1").await.len(), 1);
+        let stored = k.database().unwrap().ledger().events_for_tenant("acme").await.unwrap();
+        let (e1, e2) = (&stored[0].event.employee_id, &stored[1].event.employee_id);
+        assert!(e1.starts_with(REDACTED_EMPLOYEE_PREFIX) && e2.starts_with(REDACTED_EMPLOYEE_PREFIX), "{} {}", e1, e2);
+        assert_ne!(e1, e2);
+        assert_eq!(k.ledger().events_for_employee("acme", e1).await.len(), 1);
+        assert_eq!(k.ledger().events_for_employee("acme", e2).await.len(), 1);
+        assert_eq!(k.apply_retention("acme").await.unwrap().ledger_events, 0);
+
+        // The redacted entry keeps its place in the chain, in memory and on disk
+        let entries = k.audit_log().query(&AuditQuery::default(), usize::MAX).await.unwrap();
+        let redacted = entries.iter().find(|e| e.sequence == old.sequence).unwrap();
+        assert!(matches!(&redacted.event, AuditEventType::Redacted { kind, .. } if kind == "Custom"));
+        assert_eq!(redacted.hash, old.hash);
+        assert!(k.audit_log().verify_chain().await.valid);
+        let mut progress = k.audit_log().start_full_verification().await;
+        let result = progress.wait_for(|p| !p.running).await.unwrap().result.clone().unwrap();
+        assert!(result.valid, "{:?}", result);
+        assert!(entries.iter().any(|e| matches!(e.event, AuditEventType::RetentionApplied { ledger_events: 2, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_ledger_use_raises_alerts() {
+        use crate::alerts::{AlertConfig, AlertKind};
+        use crate::ledger::LedgerEventKind;
+
+        let k = Kernel::new().unwrap();
+        k.create_tenant("acme").await.unwrap();
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 1,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        k.set_tenant_policy("acme", policy, "2025-01-01".parse().unwrap()).await.unwrap();
+        let mut alerts = k.alerts().subscribe();
+
+        let event = |work_date: &str, kind| NewLedgerEvent {
+            tenant_id: "acme".into(),
+            employee_id: "e1".into(),
+            work_date: work_date.parse().unwrap(),
+            kind,
+            policy_version: Some(1),
+            source: "test".into(),
+        };
+        let accrued = LedgerEventKind::Accrued { minutes_worked: 3_000, accrued_minutes: 100 };
+        k.append_ledger_events(vec![event("2025-03-03", accrued), event("2025-03-04", LedgerEventKind::Used { minutes: 50 })])
+            .await
+            .unwrap();
+        assert!(alerts.try_recv().is_err());
+
+        // Over the balance and the annual limit at once
+        k.append_ledger_events(vec![event("2025-03-05", LedgerEventKind::Used { minutes: 70 })]).await.unwrap();
+        let mut kinds = vec![alerts.try_recv().unwrap().alert.kind, alerts.try_recv().unwrap().alert.kind];
+        kinds.sort();
+        assert_eq!(kinds, vec![AlertKind::NegativeBalanceAttempt, AlertKind::CapExceeded]);
+
+        // The limit was already passed; only the new overdraft is reported
+        let config = AlertConfig { disabled: [AlertKind::NegativeBalanceAttempt].into(), webhooks: Vec::new() };
+        k.alerts().set_config(Some("acme"), config).unwrap();
+        k.append_ledger_events(vec![event("2025-03-06", LedgerEventKind::Used { minutes: 10 })]).await.unwrap();
+        assert!(alerts.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_database_key_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let open_store = || {
+            SecretStore::open_with_key(dir.path().join("secrets.json"), crate::security::MasterKey::from_bytes([7; 32]))
+                .unwrap()
+        };
+        let mut store = open_store();
+        let db = Database::open_encrypted(dir.path().join("esta.db"), FieldCipher::from_store(&mut store).unwrap()).unwrap();
+        let k = Kernel::new().unwrap().with_secret_store(store).unwrap().with_database(db.clone());
+        k.create_tenant("acme").await.unwrap();
+        k.add_employees("acme", &["e1".to_string()]).await.unwrap();
+        let before = db.key_id().await.unwrap();
+
+        assert_eq!(k.rotate_database_key().await.unwrap(), 1);
+        assert_ne!(db.key_id().await.unwrap(), before);
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries
+            .iter()
+            .any(|e| matches!(&e.event, AuditEventType::DatabaseKeyRotated { resealed: 1, .. })));
+        drop((k, db));
+
+        // The store holds only the new key, and it opens the database
+        let mut store = open_store();
+        let db = Database::open_encrypted(dir.path().join("esta.db"), FieldCipher::from_store(&mut store).unwrap()).unwrap();
+        assert_eq!(db.employees().list("acme").await.unwrap(), vec!["e1"]);
+
+        let err = Kernel::new().unwrap().with_database(db).rotate_database_key().await.unwrap_err();
+        assert!(err.to_string().contains("needs a database and a secret store"));
+    }
+
+    #[tokio::test]
+    async fn test_modules_see_pseudonymized_employee_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap().with_recorded_inputs(1024);
+        k.launch_module(&manifest_path).await.unwrap();
+        k.tenants().register("acme").await.unwrap();
+
+        let input = br#"{"employee_id":"maria.lopez","tenant_id":"acme"}"#;
+        let report = k.execute_for_tenant("acme", "echo", "echo_json", input).await.unwrap();
+        assert_eq!(report.output, input.to_vec(), "the caller gets real identifiers back");
+
+        // The guest (and so the audit record and any replay) only saw the pseudonym
+        let pseudonym = k.pseudonymizer().pseudonym("acme", "maria.lopez");
+        let recorded = k.audit_log().get_all_entries().await.into_iter().find_map(|e| match e.event {
+            AuditEventType::InvocationRecorded(record) => record.input,
+            _ => None,
+        });
+        let recorded = recorded.unwrap();
+        assert!(recorded.contains(&pseudonym) && !recorded.contains("maria.lopez"), "{}", recorded);
+    }
+
+    // The interpreter backend reports traps without backtraces
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_trap_backtrace_symbolication() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
+
+        let err = k.execute_function("echo", "crash_json", b"{}").await.unwrap_err();
+        let trap = err.downcast_ref::<ModuleTrap>().expect("trap error");
+        assert!(trap.message.contains("divide by zero"));
+
+        let names: Vec<_> = trap.report.backtrace.iter().map(|f| f.func_name.as_deref()).collect();
+        assert_eq!(names[0], Some("divide"));
+        assert!(trap.report.backtrace.len() >= 2);
+        assert!(err.to_string().contains("divide (func["));
+
+        let entries = k.audit_log().get_all_entries().await;
+        let crashed = entries.iter().find_map(|e| match &e.event {
+            AuditEventType::ModuleCrashed { module_name, backtrace, .. } if module_name == "echo" => {
+                Some(backtrace.clone())
+            }
+            _ => None,
+        });
+        assert_eq!(crashed.unwrap(), trap.report.backtrace);
+
+        // Non-trap failures are not reported as crashes
+        assert!(k.execute_function("echo", "reject_json", b"{}").await.unwrap_err()
+            .downcast_ref::<ModuleTrap>()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invocation_size_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let config = ExecutionConfig { max_input_bytes: 64, max_output_bytes: 16, ..Default::default() };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
 
-        info!("Loading module {} from {}", manifest.name, manifest.path);
+        assert_eq!(k.execute_function("echo", "echo_json", b"[1,2]").await.unwrap().output, b"[1,2]");
 
-        let module_bytes = tokio::fs::read(&manifest.path).await?;
+        let err = k.execute_function("echo", "echo_json", &[b' '; 65]).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::InputTooLarge(65))), "{:?}", err);
+        let err = k.execute_function("echo", "echo_json", b"[1,2,3,4,5,6,7,8,9]").await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::OutputTooLarge { size: 19, limit: 16, .. })), "{:?}", err);
 
-        // Verify checksum first
-        Self::verify_checksum(&module_bytes, &manifest.checksum)?;
-        info!("Checksum verified for module {}", manifest.name);
+        let limits: Vec<_> = k
+            .audit_log()
+            .get_all_entries()
+            .await
+            .into_iter()
+            .filter_map(|e| match e.event {
+                AuditEventType::MemoryLimitExceeded { limit, function, resource, size, .. } => {
+                    Some((resource.unwrap(), size.unwrap(), limit, function.unwrap()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(limits, [("input".into(), 65, 64, "echo_json".into()), ("output".into(), 19, 16, "echo_json".into())]);
+        let stats = k.registry.read().await.get_module_stats("echo").await.unwrap();
+        assert_eq!((stats.invocation_count, stats.error_count), (2, 1));
+    }
 
-        // Verify signature
-        self.verify_signature(&module_bytes, &manifest)?;
+    #[tokio::test]
+    async fn test_reload_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let k = Kernel::new().unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
 
-        // Parse capabilities
-        let capabilities = Self::parse_capabilities(&manifest);
-        info!(
-            "Module {} granted capabilities: {:?}",
-            manifest.name, capabilities
-        );
+        let reload = k
+            .reload_config(ExecutionConfig { max_input_bytes: 4, max_concurrent_invocations: 16, ..Default::default() })
+            .await;
+        assert_eq!(reload.changed, ["max_input_bytes", "max_concurrent_invocations"]);
+        assert_eq!(reload.restart_required, ["max_concurrent_invocations"]);
 
-        // Log to audit
-        self.audit_log.log_module_loaded(
-            &manifest.name,
-            &manifest.checksum,
-            "kernel",
-        ).await;
+        let config = k.config();
+        assert_eq!(config.max_input_bytes, 4);
+        assert_eq!(config.max_concurrent_invocations, ExecutionConfig::default().max_concurrent_invocations);
 
-        let module = Module::new(&self.engine, &module_bytes)?;
+        let err = k.execute_function("echo", "echo_json", b"[1,2]").await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::InputTooLarge(5))), "{:?}", err);
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ConfigReloaded { changed, restart_required }
+                if changed.len() == 2 && restart_required == &["max_concurrent_invocations"]
+        )));
 
-        // Create linker with capability-based host functions
-        let mut linker = Linker::new(&self.engine);
-        Self::register_host_functions(&mut linker, &capabilities)?;
+        // A reload cannot loosen the security profile
+        let production = Kernel::new().unwrap().with_profile(SecurityProfile::Production);
+        let reload = production.reload_config(ExecutionConfig::default()).await;
+        assert!(reload.changed.is_empty(), "{:?}", reload);
+        assert!(production.config().require_signatures && production.config().enforce_registry);
+    }
 
-        let mut store = self.create_store(capabilities.clone(), manifest.name.clone());
-        let instance = linker.instantiate_async(&mut store, &module).await?;
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_trap_saves_crash_dump() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let dumps = || CrashDumps::new(dir.path().join("crashes"), &[9; 32]).unwrap();
 
-        let module_name = manifest.name.clone();
-        let stats = Arc::new(RwLock::new(ModuleStats::default()));
-        let stats_clone = stats.clone();
-        let audit_log = self.audit_log.clone();
-        let max_fuel = self.config.max_fuel;
-
-        // Run in supervised task
-        let run_handle = tokio::spawn(async move {
-            if let Ok(start) = instance.get_typed_func::<(), ()>(&mut store, "_start") {
-                match start.call_async(&mut store, ()).await {
-                    Ok(()) => {
-                        // Calculate fuel consumed
-                        let consumed = store.fuel_consumed().unwrap_or(0);
-                        
-                        let mut s = stats_clone.write().await;
-                        s.fuel_consumed += consumed;
-                        s.invocation_count += 1;
-
-                        audit_log.log_execution_completed(
-                            &module_name,
-                            "_start",
-                            consumed,
-                            "kernel",
-                        ).await;
-                    }
-                    Err(e) => {
-                        let mut s = stats_clone.write().await;
-                        s.error_count += 1;
-                        s.invocation_count += 1;
-                        
-                        let error_msg = format!("{:?}", e);
-                        error!("Module {} _start failed: {}", module_name, error_msg);
-
-                        if error_msg.contains("fuel") {
-                            audit_log.log_fuel_exhausted(&module_name, max_fuel, "kernel").await;
-                        } else {
-                            audit_log.log_module_crashed(&module_name, &error_msg, "kernel").await;
-                        }
-                    }
-                }
-            }
-        });
+        let k = Kernel::new().unwrap().with_crash_dumps(dumps());
+        k.launch_module(&manifest_path).await.unwrap();
 
-        // Register module
-        let mut reg = self.registry.write().await;
-        reg.register(manifest.name.clone(), run_handle, capabilities, stats);
-        info!("Module {} registered in kernel", manifest.name);
+        let err = k.execute_function("echo", "crash_json", b"{}").with_id("inv-crash").await.unwrap_err();
+        let trap = err.downcast_ref::<ModuleTrap>().expect("trap error");
+        let id = trap.crash_dump.clone().expect("crash dump");
 
-        Ok(())
-    }
+        let entries = k.audit_log().get_all_entries().await;
+        let audited = entries.iter().find_map(|e| match &e.event {
+            AuditEventType::ModuleCrashed { crash_dump, .. } => crash_dump.clone(),
+            _ => None,
+        });
+        assert_eq!(audited.as_ref(), Some(&id));
 
-    /// Execute a function on a module with fuel limits
-    pub async fn execute_function(
-        &self,
-        module_name: &str,
-        function_name: &str,
-        input_ptr: i32,
-        input_len: i32,
-    ) -> Result<i32> {
-        // This is a placeholder for direct function execution
-        // In a full implementation, this would look up the module instance
-        // and call the specified function with fuel metering
-        info!(
-            "Execute function {} on module {} with input at ptr={}, len={}",
-            function_name, module_name, input_ptr, input_len
-        );
-        
-        Ok(0)
+        let dump = dumps().read(&id).await.unwrap();
+        assert_eq!((dump.module_name.as_str(), dump.function_name.as_str()), ("echo", "crash_json"));
+        assert_eq!(dump.invocation_id, "inv-crash");
+        assert_eq!(dump.trap, trap.kind);
+        assert_eq!(dump.backtrace, trap.report.backtrace);
+        assert_eq!(dump.fuel_consumed, trap.report.fuel_consumed);
+        assert_eq!(dump.fuel_remaining, dump.fuel_limit - dump.fuel_consumed);
+        assert_eq!(dump.memory.len(), dump.memory_size.min(crate::forensics::CrashDumpConfig::default().max_memory_bytes));
+        assert!(dump.memory_size > 0);
+
+        // Failures that are not traps leave no dump
+        k.execute_function("echo", "reject_json", b"{}").await.unwrap_err();
+        assert_eq!(dumps().list().await.unwrap(), vec![id]);
     }
 
-    /// Get kernel status
-    pub async fn get_status(&self) -> KernelStatus {
-        let reg = self.registry.read().await;
-        let modules: Vec<String> = reg.list_modules().iter().map(|s| s.to_string()).collect();
-        let audit_stats = self.audit_log.stats().await;
+    #[tokio::test]
+    async fn test_failed_calls_classified_by_trap_kind() {
+        const TRAPS_WAT: &str = r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "crash_json") (param i32 i32) (result i32) unreachable)
+              (func (export "spin_json") (param i32 i32) (result i32) (loop (br 0)) (i32.const 0))
+              (func (export "grow_json") (param i32 i32) (result i32)
+                (if (i32.lt_s (memory.grow (i32.const 64)) (i32.const 0)) (then unreachable))
+                (i32.const 0)))
+        "#;
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "traps", TRAPS_WAT);
+        let config = ExecutionConfig { max_fuel: 100_000, max_memory_bytes: 1 << 20, ..Default::default() };
+        let k = Kernel::with_config(config).unwrap();
+        k.launch_module(&manifest_path).await.unwrap();
 
-        KernelStatus {
-            version: env!("CARGO_PKG_VERSION").to_string(),
-            modules_loaded: modules.len(),
-            module_names: modules,
-            max_fuel_per_call: self.config.max_fuel,
-            max_memory_bytes: self.config.max_memory_bytes,
-            require_signatures: self.config.require_signatures,
-            audit_entries: audit_stats.total_entries,
+        for (function, kind) in [
+            ("crash_json", TrapKind::Unreachable),
+            ("spin_json", TrapKind::FuelExhausted),
+            ("grow_json", TrapKind::MemoryLimit),
+        ] {
+            let err = k.execute_function("traps", function, b"{}").await.unwrap_err();
+            assert_eq!(TrapKind::of(&err), Some(kind), "{}: {:#}", function, err);
+            assert_eq!(crate::user_errors::from_anyhow(&err, crate::Locale::English).trap, Some(kind));
         }
-    }
+        // Failures outside the guest are not traps
+        assert_eq!(TrapKind::of(&k.execute_function("traps", "missing_json", b"{}").await.unwrap_err()), None);
 
-    /// Shutdown the kernel and all running modules
-    pub async fn shutdown(&self) -> Result<()> {
-        info!("Kernel shutdown initiated");
-        
-        self.audit_log.append(AuditEvent::new(
-            AuditEventType::KernelShutdown { 
-                reason: "normal shutdown".into() 
-            },
-            "kernel",
-        )).await;
+        let stats = k.registry.read().await.get_module_stats("traps").await.unwrap();
+        assert_eq!(stats.error_count, 4);
+        let expected: BTreeMap<_, _> =
+            [(TrapKind::FuelExhausted, 1), (TrapKind::MemoryLimit, 1), (TrapKind::Unreachable, 1)].into();
+        assert_eq!(stats.traps, expected);
 
-        let mut reg = self.registry.write().await;
-        reg.shutdown_all().await;
-        info!("Kernel shutdown complete");
-        Ok(())
+        let crashes: Vec<_> = k
+            .audit_log()
+            .get_all_entries()
+            .await
+            .into_iter()
+            .filter_map(|e| match e.event {
+                AuditEventType::ModuleCrashed { trap, .. } => trap,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(crashes, vec![TrapKind::Unreachable, TrapKind::MemoryLimit]);
     }
 
-    /// List all running modules
-    pub async fn list_modules(&self) -> Vec<String> {
-        let reg = self.registry.read().await;
-        reg.list_modules().into_iter().map(String::from).collect()
+    #[tokio::test]
+    async fn test_read_replica_reports_primary_data() {
+        use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+        use crate::policy::PolicyFile;
+
+        let dir = tempfile::tempdir().unwrap();
+        let policy_path = dir.path().join("policies.json");
+        let ledger_path = dir.path().join("ledger.jsonl");
+
+        let primary = Kernel::new()
+            .unwrap()
+            .with_tenant_registry(TenantRegistry::with_policy_file(PolicyFile::new(&policy_path)).unwrap())
+            .with_ledger(Ledger::with_file(&ledger_path).unwrap());
+        let replica = Kernel::new()
+            .unwrap()
+            .with_tenant_registry(TenantRegistry::snapshot(PolicyFile::new(&policy_path)).unwrap())
+            .with_ledger(Ledger::snapshot(&ledger_path).unwrap());
+        assert!(!primary.is_read_only());
+        assert!(replica.is_read_only());
+
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        primary.set_tenant_policy("acme", policy.clone(), "2025-01-01".parse().unwrap()).await.unwrap();
+        primary.ledger().append(NewLedgerEvent {
+            tenant_id: "acme".into(),
+            employee_id: "e1".into(),
+            work_date: "2025-03-03".parse().unwrap(),
+            kind: LedgerEventKind::Accrued { minutes_worked: 480, accrued_minutes: 16 },
+            policy_version: Some(1),
+            source: "test".into(),
+        }).await.unwrap();
+
+        // Reports on the replica see the primary's writes without a restart
+        let report = replica.compliance_report("acme", 2025).await.unwrap();
+        assert_eq!(report.totals.accrued_minutes, 16);
+        assert!(replica.set_tenant_policy("acme", policy, "2026-01-01".parse().unwrap()).await.is_err());
     }
-}
 
-/// Kernel status information
-#[derive(Debug, Clone, Serialize)]
-pub struct KernelStatus {
-    pub version: String,
-    pub modules_loaded: usize,
-    pub module_names: Vec<String>,
-    pub max_fuel_per_call: u64,
-    pub max_memory_bytes: usize,
-    pub require_signatures: bool,
-    pub audit_entries: u64,
-}
+    #[tokio::test]
+    async fn test_set_tenant_policy_is_audited() {
+        let k = Kernel::new().unwrap();
+        let policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let version = k
+            .set_tenant_policy("acme", policy, "2025-02-21".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(version.version, 1);
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::PolicyVersionRecorded { tenant_id, version: 1, effective_from }
+                if tenant_id == "acme" && effective_from == "2025-02-21"
+        )));
+    }
 
     #[tokio::test]
-    async fn test_new_kernel() {
+    async fn test_custom_report_runs_under_fuel_limit() {
+        use crate::report::template::TemplateColumn;
+
         let k = Kernel::new().unwrap();
-        let modules = k.list_modules().await;
-        assert!(modules.is_empty());
+        k.create_tenant("acme").await.unwrap();
+        k.tenants().add_employee("acme", "e1").await.unwrap();
+        k.tenants().add_employee("acme", "e2").await.unwrap();
+        let template = ReportTemplate {
+            name: "balances".into(),
+            columns: vec![TemplateColumn { header: "Employee".into(), expression: "employee_id".into() }],
+            filter: Some("employee_id != \"e2\"".into()),
+        };
+
+        let saved = k.save_report_template("acme", template).await.unwrap();
+        assert_eq!(saved.version, 1);
+        let custom = k.custom_report("acme", 2025, "balances", None).await.unwrap();
+        assert_eq!((custom.rows.len(), custom.fuel_consumed), (1, 7));
+        assert!(k.custom_report("acme", 2025, "missing", None).await.is_err());
+
+        let k = Kernel::with_config(ExecutionConfig { max_fuel: 5, ..Default::default() }).unwrap();
+        k.create_tenant("acme").await.unwrap();
+        k.tenants().add_employee("acme", "e1").await.unwrap();
+        k.tenants().add_employee("acme", "e2").await.unwrap();
+        k.save_report_template("acme", saved.template).await.unwrap();
+        let err = k.custom_report("acme", 2025, "balances", None).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<TemplateError>(), Some(&TemplateError::OutOfFuel { limit: 5 }));
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::ReportTemplateRecorded { tenant_id, version: 1, .. } if tenant_id == "acme"
+        )));
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::FuelExhausted { module_name, .. } if module_name == "report-template:balances"
+        )));
     }
 
     #[tokio::test]
-    async fn test_kernel_with_config() {
-        let config = ExecutionConfig {
-            max_fuel: 10_000_000,
-            max_memory_bytes: 16 * 1024 * 1024,
-            require_signatures: false,
-            ..Default::default()
+    async fn test_policy_checked_against_statute_in_force() {
+        let k = Kernel::new().unwrap();
+        let policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
         };
+
+        let err = k
+            .set_tenant_policy("acme", policy.clone(), "2025-06-01".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TenantError::InvalidPolicy(reason) if reason.contains("72 hours")));
+
+        // Before the statute took effect there is nothing to check against
+        k.set_tenant_policy("acme", policy, "2024-06-01".parse().unwrap()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_generated_manifest_passes_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_path = dir.path().join("echo.wat");
+        std::fs::write(&wasm_path, JSON_ABI_WAT).unwrap();
+        let signer = ModuleSigner::from_seed(&[3u8; 32]).unwrap();
+
+        let manifest = ModuleManifest::generate(&wasm_path, &signer, vec!["log".to_string()]).unwrap();
+        assert_eq!(manifest.name, "echo");
+        let config = ExecutionConfig { require_signatures: true, ..Default::default() };
+        let k = Kernel::with_config(config).unwrap().with_signature_verifier(&signer.public_key_hex()).unwrap();
+        k.launch_manifest(manifest).await.unwrap();
+        let report = k.execute_function("echo", "echo_json", b"{}").await.unwrap();
+        assert_eq!(report.output, b"{}");
+
+        let err = ModuleManifest::generate(&wasm_path, &signer, vec!["network".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("Unknown capability network"), "{}", err);
+    }
+
+    // The interpreter backend refuses WASI modules
+    #[cfg(not(feature = "interpreter"))]
+    #[tokio::test]
+    async fn test_wasi_module_reads_granted_directory() {
+        // Reactor that reads rules/policy.json through WASI and returns it
+        const WASI_READER_WAT: &str = r#"
+            (module
+              (import "wasi_snapshot_preview1" "path_open"
+                (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+              (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (global $ready (mut i32) (i32.const 0))
+              (data (i32.const 16) "policy.json")
+              (func (export "_initialize") (global.set $ready (i32.const 1)))
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "read_json") (param i32 i32) (result i32)
+                (if (i32.eqz (global.get $ready)) (then unreachable))
+                (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 16) (i32.const 11) (i32.const 0)
+                      (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 32))
+                  (then (return (i32.const 0))))
+                (i32.store (i32.const 40) (i32.const 2052))
+                (i32.store (i32.const 44) (i32.const 256))
+                (drop (call $fd_read (i32.load (i32.const 32)) (i32.const 40) (i32.const 1) (i32.const 2048)))
+                (i32.const 2048)))
+        "#;
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "reader", WASI_READER_WAT);
+        let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(manifest_path).unwrap()).unwrap();
+        manifest.capabilities = vec!["wasi".into(), "fs_read:rules".into()];
+
+        let err = Kernel::new().unwrap().launch_manifest(manifest.clone()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<KernelError>(), Some(KernelError::WasiNotConfigured(_))), "{}", err);
+
+        let root = dir.path().join("wasi");
+        let config = ExecutionConfig { wasi_root: Some(root.clone()), ..Default::default() };
         let k = Kernel::with_config(config).unwrap();
-        let status = k.get_status().await;
-        assert_eq!(status.max_fuel_per_call, 10_000_000);
+        k.launch_manifest(manifest.clone()).await.unwrap();
+        std::fs::write(root.join("rules/policy.json"), br#"{"rate":30}"#).unwrap();
+        let report = k.execute_function("reader", "read_json", b"{}").await.unwrap();
+        assert_eq!(report.output, br#"{"rate":30}"#);
+
+        let access = k.audit_log().get_all_entries().await.into_iter().find_map(|entry| match entry.event {
+            AuditEventType::WasiFileAccess { path, allowed, .. } => Some((path, allowed)),
+            _ => None,
+        });
+        assert_eq!(access, Some(("/rules/policy.json".to_string(), true)));
+
+        // Only plain directory names can be granted
+        let signer = ModuleSigner::from_seed(&[3u8; 32]).unwrap();
+        let wat_path = dir.path().join("reader.wat");
+        assert!(ModuleManifest::generate(&wat_path, &signer, vec!["fs_read:../etc".to_string()]).is_err());
     }
 
-    #[test]
-    fn test_checksum_verification() {
-        let data = b"test module bytes";
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        let checksum = hex::encode(hasher.finalize());
+    #[tokio::test]
+    async fn test_security_profiles() {
+        use crate::security::audit::AuditLogConfig;
 
-        assert!(Kernel::verify_checksum(data, &checksum).is_ok());
-        assert!(Kernel::verify_checksum(data, "invalid").is_err());
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_path = dir.path().join("echo.wat");
+        std::fs::write(&wasm_path, JSON_ABI_WAT).unwrap();
+        let signer = ModuleSigner::from_seed(&[4u8; 32]).unwrap();
+        let manifest = ModuleManifest::generate(&wasm_path, &signer, vec![]).unwrap();
+        let listed = ModuleManifest { path: "echo.wat".into(), ..manifest.clone() };
+        std::fs::write(dir.path().join("echo.json"), serde_json::to_vec(&listed).unwrap()).unwrap();
+
+        // Production reports what a bare kernel is missing, and ignores a lax flag
+        let config = ExecutionConfig { require_signatures: false, call_timeout: Some(Duration::from_secs(5)), ..Default::default() };
+        let bare = Kernel::with_config(config.clone()).unwrap().with_profile(SecurityProfile::Production);
+        assert_eq!(bare.profile_violations().len(), 3);
+        assert!(bare.config().require_signatures);
+        assert_eq!(bare.config().call_timeout, Some(Duration::from_secs(5)));
+
+        let k = Kernel::with_config(config)
+            .unwrap()
+            .with_audit_log(AuditLog::with_segments(AuditLogConfig::default(), dir.path().join("audit")).unwrap())
+            .with_signature_verifier(&signer.public_key_hex())
+            .unwrap()
+            .with_module_catalog(ModuleCatalog::open(dir.path()).unwrap())
+            .with_profile(SecurityProfile::Production);
+        let status = k.get_status().await;
+        assert_eq!(status.security_profile, SecurityProfile::Production);
+        assert!(status.profile_violations.is_empty(), "{:?}", status.profile_violations);
+
+        // Only the catalog may load modules
+        let err = k.launch_manifest(manifest.clone()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::CatalogOnly)), "{}", err);
+        k.install_module("echo", None).await.unwrap();
+        assert_eq!(k.list_modules().await, vec!["echo"]);
+
+        // Staging loads by path, but not manifests naming unknown capabilities
+        let staging = Kernel::new()
+            .unwrap()
+            .with_signature_verifier(&signer.public_key_hex())
+            .unwrap()
+            .with_profile(SecurityProfile::Staging);
+        let mut unknown = manifest.clone();
+        unknown.capabilities.push("network".to_string());
+        let err = staging.launch_manifest(unknown).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::UnknownCapability { .. })), "{}", err);
+        staging.launch_manifest(manifest).await.unwrap();
     }
 
-    #[test]
-    fn test_capability_parsing() {
-        let manifest = ModuleManifest {
-            name: "test".into(),
-            path: "test.wasm".into(),
-            checksum: "abc".into(),
-            capabilities: vec!["log".into(), "audit_emit".into(), "unknown".into()],
-            signature: None,
-        };
-        let caps = Kernel::parse_capabilities(&manifest);
-        assert_eq!(caps.len(), 2);
-        assert!(caps.contains(&Capability::Log));
-        assert!(caps.contains(&Capability::AuditEmit));
+    #[tokio::test]
+    async fn test_abi_version_negotiation() {
+        let dir = tempfile::tempdir().unwrap();
+        let k = Kernel::new().unwrap();
+
+        k.launch_module(&write_test_module(dir.path(), "echo", JSON_ABI_WAT)).await.unwrap();
+        assert_eq!(k.module_abi_version("echo").await, Some(1));
+        let loaded = k.audit_log().get_all_entries().await.into_iter().find_map(|e| match e.event {
+            AuditEventType::ModuleLoaded { abi_version, .. } => Some(abi_version),
+            _ => None,
+        });
+        assert_eq!(loaded, Some(Some(1)));
+
+        // A module built for a newer ABI is refused before it runs
+        let newer = r#"(module (memory (export "memory") 1) (func (export "__abi_version") (result i32) (i32.const 2)))"#;
+        let err = k.launch_module(&write_test_module(dir.path(), "newer", newer)).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::AbiIncompatible { version: 2, min: 1, max: 1, .. })), "{}", err);
+        assert!(!k.list_modules().await.contains(&"newer".to_string()));
+
+        // Modules from before the convention load, unless the export is required
+        let legacy = write_test_module(dir.path(), "legacy", r#"(module (memory (export "memory") 1))"#);
+        k.launch_module(&legacy).await.unwrap();
+        assert_eq!(k.module_abi_version("legacy").await, None);
+        let strict = Kernel::with_config(ExecutionConfig { require_abi_version: true, ..Default::default() }).unwrap();
+        let err = strict.launch_module(&legacy).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::AbiVersionMissing(_))), "{}", err);
     }
 
     #[tokio::test]
-    async fn test_module_registry() {
-        let mut registry = ModuleRegistry::new();
-        let stats = Arc::new(RwLock::new(ModuleStats::default()));
+    async fn test_sandbox_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_path = dir.path().join("echo.wat");
+        std::fs::write(&wasm_path, JSON_ABI_WAT).unwrap();
+        let signer = ModuleSigner::from_seed(&[5u8; 32]).unwrap();
+        let mut manifest = ModuleManifest::generate(&wasm_path, &signer, vec![]).unwrap();
+        manifest.sandbox = Some(SandboxProfile::Strict);
 
-        let handle = tokio::spawn(async {});
-        registry.register("test".into(), handle, vec![Capability::Log], stats);
+        // A strict module must be signed, even where the kernel does not require it
+        let k = Kernel::with_config(ExecutionConfig::sandboxed(SandboxProfile::Dev)).unwrap();
+        let unsigned = ModuleManifest { signature: None, ..manifest.clone() };
+        let err = k.launch_manifest(unsigned).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::SignatureRequired(_))), "{}", err);
 
-        assert_eq!(registry.list_modules(), vec!["test"]);
+        // ...and cannot be granted file or network access
+        let k = k.with_signature_verifier(&signer.public_key_hex()).unwrap();
+        let wasi = ModuleManifest { capabilities: vec!["wasi".into()], ..manifest.clone() };
+        let err = k.launch_manifest(wasi).await.unwrap_err();
+        assert!(
+            matches!(err.downcast_ref(), Some(KernelError::SandboxDenied { sandbox: SandboxProfile::Strict, .. })),
+            "{}",
+            err
+        );
 
-        registry.shutdown_all().await;
-        assert!(registry.list_modules().is_empty());
+        k.launch_manifest(manifest).await.unwrap();
+        k.execute_function("echo", "echo_json", b"{}").await.unwrap();
+        // A module without a profile runs under the kernel's
+        k.launch_module(&write_test_module(dir.path(), "plain", JSON_ABI_WAT)).await.unwrap();
+
+        let registry = k.registry.read().await;
+        let strict = registry.get_executable("echo").unwrap().limits;
+        assert_eq!(strict, SandboxLimits { max_memory_bytes: 64 << 20, ..SandboxProfile::Strict.limits() });
+        assert_eq!(registry.get_executable("plain").unwrap().limits, SandboxProfile::Dev.limits());
+        let launched: Vec<_> = k.audit_log.get_all_entries().await.into_iter().filter_map(|e| match e.event {
+            AuditEventType::ModuleLoaded { module_name, sandbox, .. } => Some((module_name, sandbox)),
+            _ => None,
+        }).collect();
+        assert_eq!(launched, vec![
+            ("echo".to_string(), Some(SandboxProfile::Strict)),
+            ("plain".to_string(), Some(SandboxProfile::Dev)),
+        ]);
     }
 
     #[tokio::test]
-    async fn test_kernel_status() {
+    async fn test_stale_heartbeat_restarts_module() {
+        use crate::supervisor::ChildSpec;
+        use crate::testing::TimeMachine;
+        use std::sync::atomic::AtomicU32;
+
+        let time = TimeMachine::start();
+        let dir = tempfile::tempdir().unwrap();
+        // Beats once from its own loop, then goes silent
+        let resident = write_test_module(dir.path(), "resident", r#"
+            (module
+              (import "env" "host_heartbeat" (func $heartbeat))
+              (func (export "_start") (call $heartbeat)))
+        "#);
         let k = Kernel::new().unwrap();
+        k.launch_module(&resident).await.unwrap();
+        time.advance(Duration::from_millis(10)).await;
+        // The interpreter backend runs `_start` on a blocking thread
+        while k.get_status().await.heartbeats.is_empty() {
+            tokio::task::yield_now().await;
+        }
         let status = k.get_status().await;
-        
-        assert_eq!(status.modules_loaded, 0);
-        assert_eq!(status.max_fuel_per_call, 20_000_000);
+        assert_eq!(status.heartbeats.len(), 1);
+        assert_eq!(status.heartbeats[0].module, "resident");
+        assert!(status.heartbeats[0].last_heartbeat <= now_millis());
+
+        let restarts = Arc::new(AtomicU32::new(0));
+        let counter = restarts.clone();
+        let supervisor = Arc::new(Supervisor::new(move |_, _, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }));
+        let spec = ChildSpec {
+            id: "resident".into(),
+            manifest_path: resident,
+            heartbeat_timeout_ms: Some(1_000),
+            base_restart_delay_ms: 10,
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("resident").await.unwrap();
+        let watcher = k.supervise_heartbeats(supervisor.clone(), Duration::from_millis(100));
+
+        // The watcher wakes every 100 ms; step time so it sees each tick
+        for _ in 0..9 {
+            time.advance(Duration::from_millis(100)).await;
+            time.settle().await;
+        }
+        assert_eq!(restarts.load(Ordering::SeqCst), 0);
+        for _ in 0..10 {
+            time.advance(Duration::from_millis(100)).await;
+            time.settle().await;
+        }
+        assert_eq!(restarts.load(Ordering::SeqCst), 1);
+        assert_eq!(supervisor.get_child_status("resident").await.unwrap().total_crashes, 1);
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(&e.event, AuditEventType::ModuleCrashed { .. })));
+        watcher.abort();
     }
 
     #[tokio::test]
-    async fn test_kernel_audit_log() {
+    async fn test_signing_key_rotation_grace_period() {
+        use crate::security::sig::ModuleSigner;
+        use crate::testing::TimeMachine;
+
+        let time = TimeMachine::start();
+        let dir = tempfile::tempdir().unwrap();
+        let old = ModuleSigner::from_seed(&[1u8; 32]).unwrap();
+        let new = ModuleSigner::from_seed(&[2u8; 32]).unwrap();
+        let signed_module = |name: &str, signer: &ModuleSigner| {
+            let path = write_test_module(dir.path(), name, JSON_ABI_WAT);
+            let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            manifest.signature = Some(signer.sign_module(JSON_ABI_WAT.as_bytes(), &manifest.checksum));
+            std::fs::write(&path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+            path
+        };
+
+        let config = ExecutionConfig { require_signatures: true, ..Default::default() };
+        let k = Kernel::with_config(config)
+            .unwrap()
+            .with_trust_store(TrustStore::open(dir.path().join("trust.json"), &old.public_key_hex()).unwrap());
+        let rotation = k.rotate_signing_key(&new.public_key_hex(), Duration::from_secs(86_400)).await.unwrap();
+        let old_id = rotation.retiring[0].key_id.clone();
+
+        k.launch_module(&signed_module("legacy", &old)).await.unwrap();
+        k.launch_module(&signed_module("current", &new)).await.unwrap();
+        let entries = k.audit_log().get_all_entries().await;
+        let verified: Vec<_> = entries
+            .iter()
+            .filter_map(|e| match &e.event {
+                AuditEventType::SignatureVerified { module_name, key_id, retiring } => {
+                    Some((module_name.as_str(), key_id.as_str(), *retiring))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            verified,
+            [("legacy", old_id.as_str(), true), ("current", rotation.current.key_id.as_str(), false)]
+        );
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::SigningKeyRotated { key_id, retiring_key_ids, .. }
+                if *key_id == rotation.current.key_id && *retiring_key_ids == [old_id.clone()]
+        )));
+
+        time.advance(Duration::from_secs(86_400)).await;
+        let err = k.launch_module(&signed_module("stale", &old)).await.unwrap_err();
+        assert_eq!(crate::user_errors::from_anyhow(&err, crate::user_errors::Locale::English).code, "SIGNATURE_INVALID");
+        k.launch_module(&signed_module("fresh", &new)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_module_info_reports_signature_and_grants() {
+        use crate::security::sig::ModuleSigner;
+
+        let dir = tempfile::tempdir().unwrap();
+        let signer = ModuleSigner::from_seed(&[3u8; 32]).unwrap();
+        let signed = write_test_module(dir.path(), "signed", JSON_ABI_WAT);
+        let mut manifest: ModuleManifest = serde_json::from_slice(&std::fs::read(&signed).unwrap()).unwrap();
+        manifest.signature = Some(signer.sign_module(JSON_ABI_WAT.as_bytes(), &manifest.checksum));
+        std::fs::write(&signed, serde_json::to_vec(&manifest).unwrap()).unwrap();
+        let unsigned = write_test_module(dir.path(), "unsigned", JSON_ABI_WAT);
+
+        let k = Kernel::new()
+            .unwrap()
+            .with_trust_store(TrustStore::open(dir.path().join("trust.json"), &signer.public_key_hex()).unwrap());
+        k.launch_module(&signed).await.unwrap();
+        k.launch_module(&unsigned).await.unwrap();
+
+        let info = k.module_info("signed").await.unwrap();
+        let key = info.signing_key.clone().unwrap();
+        assert_eq!(info.signature, SignatureStatus::Verified { key_id: key.key_id.clone(), retiring: false });
+        assert_eq!(info.checksum, manifest.checksum);
+        assert_eq!(info.capabilities, manifest.capabilities);
+        assert_eq!(info.stats.invocation_count, 0);
+
+        let info = k.module_info("unsigned").await.unwrap();
+        assert_eq!(info.signature, SignatureStatus::Unsigned);
+        assert!(info.signing_key.is_none());
+        assert!(k.module_info("missing").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_signed_statute_update_applies_from_effective_date() {
+        use crate::security::sig::ModuleSigner;
+
+        let signer = ModuleSigner::from_seed(&[7u8; 32]).unwrap();
+        let config = ExecutionConfig { require_signatures: true, ..Default::default() };
+        let k = Kernel::with_config(config)
+            .unwrap()
+            .with_signature_verifier(&signer.public_key_hex())
+            .unwrap();
+
+        let mut statute: serde_json::Value =
+            serde_json::from_str(include_str!("../statutes/US-MI.json")).unwrap();
+        let mut amendment = statute["versions"][0].clone();
+        amendment["version"] = serde_json::json!(2);
+        amendment["effective_from"] = serde_json::json!("2027-01-01");
+        amendment["parameters"]["large_employer_usage_hours"] = serde_json::json!(80);
+        statute["versions"].as_array_mut().unwrap().push(amendment);
+        let bytes = serde_json::to_vec_pretty(&statute).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("US-MI.json"), &bytes).unwrap();
+        assert!(k.load_statutes(dir.path()).await.is_err(), "unsigned statutes are rejected");
+
+        std::fs::write(dir.path().join("US-MI.json.sig"), signer.sign(b"tampered")).unwrap();
+        assert!(k.load_statutes(dir.path()).await.is_err());
+        assert_eq!(k.statute_at("2027-06-01".parse().unwrap()).await.unwrap().version, 1);
+
+        std::fs::write(dir.path().join("US-MI.json.sig"), signer.sign(&bytes)).unwrap();
+        assert_eq!(k.load_statutes(dir.path()).await.unwrap(), vec!["US-MI".to_string()]);
+        assert_eq!(k.statute_at("2026-12-31".parse().unwrap()).await.unwrap().version, 1);
+        let amended = k.statute_at("2027-06-01".parse().unwrap()).await.unwrap();
+        assert_eq!(amended.parameters.large_employer_usage_hours, 80);
+
+        let policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        k.set_tenant_policy("acme", policy.clone(), "2026-01-01".parse().unwrap()).await.unwrap();
+        assert!(k.set_tenant_policy("acme", policy.clone(), "2027-01-01".parse().unwrap()).await.is_err());
+
+        // A Minnesota location is held to Minnesota's statute
+        let minnesota = TenantPolicy { jurisdiction: Some("US-MN".into()), ..policy.clone() };
+        let err = k.set_tenant_policy("north", minnesota.clone(), "2026-01-01".parse().unwrap()).await.unwrap_err();
+        assert!(err.to_string().contains("US-MN statute"));
+        let minnesota = TenantPolicy { max_carryover_hours: 80, ..minnesota };
+        k.set_tenant_policy("north", minnesota.clone(), "2026-01-01".parse().unwrap()).await.unwrap();
+        assert_eq!(k.policy_jurisdiction(&minnesota), "US-MN");
+        assert_eq!(k.policy_jurisdiction(&policy), "US-MI");
+
+        let unsupported = TenantPolicy { jurisdiction: Some("US-XX".into()), ..policy };
+        assert!(k.set_tenant_policy("south", unsupported, "2026-01-01".parse().unwrap()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_usage_insights_opt_in() {
+        use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+
         let k = Kernel::new().unwrap();
-        let audit_log = k.audit_log();
-        
-        // Log a test event
-        audit_log.log_custom("test", "test message", "test").await;
-        
-        let stats = audit_log.stats().await;
-        assert_eq!(stats.total_entries, 1);
+        let policy = |usage_insights| TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        k.set_tenant_policy("acme", policy(false), "2025-01-01".parse().unwrap()).await.unwrap();
+        k.ledger()
+            .append(NewLedgerEvent {
+                tenant_id: "acme".into(),
+                employee_id: "e1".into(),
+                work_date: "2025-03-07".parse().unwrap(),
+                kind: LedgerEventKind::Used { minutes: 480 },
+                policy_version: Some(1),
+                source: "test".into(),
+            })
+            .await
+            .unwrap();
+
+        let as_of: Date = "2025-06-30".parse().unwrap();
+        let err = k.usage_insights("acme", as_of).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<KernelError>(), Some(KernelError::InsightsNotEnabled(_))));
+
+        k.set_tenant_policy("acme", policy(true), "2025-06-01".parse().unwrap()).await.unwrap();
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::UsageInsightsChanged { enabled: true, effective_from, .. } if effective_from == "2025-06-01"
+        )));
+
+        // Stub analytics module: always reports one informational insight
+        let output = r#"{"employee_id":"e1","insights":[{"kind":"weekend_adjacent_usage","severity":"informational","summary":"stub","usage_days":1,"matching_days":1,"expected_share":0.4,"observed_share":1.0,"p_value":0.4}],"explain":["stub"]}"#;
+        let len: String = (output.len() as u32).to_le_bytes().iter().map(|b| format!("\\{:02x}", b)).collect();
+        let wat = format!(
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+                 (data (i32.const 16) "{}{}")
+                 (func (export "analyze_usage_json") (param i32 i32) (result i32) (i32.const 16)))"#,
+            len,
+            output.replace('"', "\\\"")
+        );
+        let dir = tempfile::tempdir().unwrap();
+        k.launch_module(&write_test_module(dir.path(), "analytics", &wat)).await.unwrap();
+
+        let report = k.usage_insights("acme", as_of).await.unwrap();
+        assert_eq!(report.period_start.to_string(), "2024-07-01");
+        assert_eq!(report.employees.len(), 1);
+        assert_eq!(report.insight_count(), 1);
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::UsageInsightsComputed { employees_analyzed: 1, insights: 1, .. }
+        )));
     }
 
-    #[test]
-    fn test_execution_config_default() {
-        let config = ExecutionConfig::default();
-        assert_eq!(config.max_fuel, 20_000_000);
-        assert_eq!(config.max_memory_bytes, 32 * 1024 * 1024);
-        assert!(!config.require_signatures);
+    #[tokio::test]
+    async fn test_invocation_archival() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+
+        let config = crate::archive::ArchiveConfig {
+            modules: ["echo".to_string()].into_iter().collect(),
+            sample_rate: 0.0,
+        };
+        let archive = InvocationArchive::new(config, dir.path().join("archive"));
+        let k = Kernel::new().unwrap().with_archive(archive);
+        k.launch_module(&manifest_path).await.unwrap();
+
+        let input = br#"{"minutes_worked":90}"#;
+        let report = k.execute_function("echo", "echo_json", input).await.unwrap();
+        let reference = report.archived.expect("echo invocations are archived");
+
+        let (archived_input, archived_output) =
+            k.archive.as_ref().unwrap().load(&reference).await.unwrap();
+        assert_eq!(archived_input, input.to_vec());
+        assert_eq!(archived_output, report.output);
+
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(
+            &e.event,
+            AuditEventType::InvocationArchived { input_hash, .. } if *input_hash == reference.input_hash
+        )));
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn test_chaos_faults_exercise_recovery() {
+        use crate::chaos::{ChaosConfig, InjectedFault};
+        use crate::supervisor::ChildSpec;
+        use crate::testing::TimeMachine;
+
+        let time = TimeMachine::start();
+        let dir = tempfile::tempdir().unwrap();
+        let echo = write_test_module(dir.path(), "echo", JSON_ABI_WAT);
+        let batch = write_test_module(dir.path(), "batch", YIELDING_WAT);
+        let always = |config: ChaosConfig| Arc::new(Chaos::new(ChaosConfig { seed: 1, ..config }));
+
+        // Injected traps surface like real ones, with the crash audited
+        let chaos = always(ChaosConfig { trap_rate: 1.0, modules: ["echo".to_string()].into(), ..Default::default() });
+        let k = Kernel::new().unwrap().with_chaos(chaos.clone());
+        k.launch_module(&echo).await.unwrap();
+        k.launch_module(&batch).await.unwrap();
+        let error = k.execute_function("echo", "echo_json", b"{}").await.unwrap_err();
+        assert!(error.downcast_ref::<ModuleTrap>().is_some(), "{:?}", error);
+        assert!(k.execute_function("batch", "yield_three_json", b"{}").await.is_ok(), "other modules unaffected");
+        assert_eq!(chaos.injected(), [InjectedFault { fault: Fault::Trap, target: "echo".into() }]);
+
+        // Starved stores run out of fuel
+        let k = Kernel::new().unwrap().with_chaos(always(ChaosConfig { fuel_starvation_rate: 1.0, ..Default::default() }));
+        k.launch_module(&batch).await.unwrap();
+        assert!(k.execute_function("batch", "yield_three_json", b"{}").await.is_err());
+        let entries = k.audit_log().get_all_entries().await;
+        assert!(entries.iter().any(|e| matches!(e.event, AuditEventType::FuelExhausted { .. })));
+
+        // Delayed host calls run into the call timeout
+        let config = ExecutionConfig { call_timeout: Some(Duration::from_millis(50)), ..Default::default() };
+        let chaos = always(ChaosConfig { host_delay_rate: 1.0, host_delay: Duration::from_secs(1), ..Default::default() });
+        let k = Kernel::with_config(config).unwrap().with_chaos(chaos);
+        k.launch_module(&batch).await.unwrap();
+        let error = k.execute_function("batch", "yield_three_json", b"{}").await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<KernelError>(), Some(KernelError::CallTimedOut { .. })), "{:?}", error);
+
+        // A dropped audit write is found by full verification
+        let chaos = always(ChaosConfig { audit_drop_rate: 1.0, ..Default::default() });
+        let audit_log = AuditLog::with_segments(Default::default(), dir.path().join("audit")).unwrap().with_chaos(chaos);
+        audit_log.log_custom("test", "lost", "kernel").await;
+        let mut progress = audit_log.start_full_verification().await;
+        let result = progress.wait_for(|p| !p.running).await.unwrap().result.clone().unwrap();
+        assert_eq!(result.first_invalid, Some(1));
+
+        // Dropped heartbeats make the child look hung
+        let chaos = always(ChaosConfig { heartbeat_drop_rate: 1.0, ..Default::default() });
+        let supervisor = Supervisor::new_noop().with_chaos(chaos);
+        let spec = ChildSpec { id: "echo".into(), manifest_path: echo, heartbeat_timeout_ms: Some(100), ..Default::default() };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("echo").await.unwrap();
+        time.advance(Duration::from_millis(200)).await;
+        supervisor.report_heartbeat("echo").await.unwrap();
+        assert_eq!(supervisor.check_heartbeats().await.unwrap().len(), 1);
     }
 }
@@ -0,0 +1,190 @@
+//! Host API
+//!
+//! Every host function the kernel provides to guests is declared once in
+//! [`HostApi`]: its import name and signature, the manifest capability that
+//! grants it, and the capability right and resource type a call exercises.
+//! The rest follows from the table:
+//!
+//! - Load-time import checks ask it which capability an import needs, and
+//!   let modules load that import functions they were not granted.
+//! - Both backends link a function's implementation only for modules granted
+//!   its capability. Everywhere else a denial stub stands in, which traps with
+//!   [`KernelError::CapabilityDenied`] when called.
+//! - The kernel audits each denied call with the right and resource the table
+//!   gives (`HostCallDenied`).
+//!
+//! Adding a host function means adding an entry here and its implementation
+//! in each backend. WASI imports are granted as a whole by the `wasi`
+//! capability and are not listed.
+
+use crate::error::KernelError;
+use crate::security::capabilities::{CapabilityRight, ResourceType};
+
+use super::Capability;
+use HostType::{I32, I64};
+
+/// Import module every host function is provided under
+pub(crate) const HOST_MODULE: &str = "env";
+
+/// Value types host functions take and return
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HostType {
+    I32,
+    I64,
+}
+
+/// Declaration of one host function
+#[derive(Debug)]
+pub(crate) struct HostFunction {
+    /// Import name, under [`HOST_MODULE`]
+    pub name: &'static str,
+    /// Signature, for the denial stubs the interpreter has no need of
+    #[cfg_attr(feature = "interpreter", allow(dead_code))]
+    pub params: &'static [HostType],
+    #[cfg_attr(feature = "interpreter", allow(dead_code))]
+    pub results: &'static [HostType],
+    /// Manifest capability that grants the function (None: every module gets it)
+    pub capability: Option<Capability>,
+    /// Right a call exercises
+    pub right: CapabilityRight,
+    /// Resource a call acts on
+    pub resource: ResourceType,
+}
+
+impl HostFunction {
+    /// Whether a module with these capabilities gets the implementation
+    pub fn is_granted(&self, capabilities: &[Capability]) -> bool {
+        self.capability.as_ref().is_none_or(|capability| capabilities.contains(capability))
+    }
+
+    /// `module::name` of the import
+    pub fn import(&self) -> String {
+        format!("{}::{}", HOST_MODULE, self.name)
+    }
+
+    /// Error a call from a module without the capability traps with
+    pub fn denied(&self, module_name: &str) -> KernelError {
+        KernelError::CapabilityDenied {
+            module: module_name.to_string(),
+            import: self.import(),
+            capability: self.capability.as_ref().map(ToString::to_string).unwrap_or_default(),
+        }
+    }
+}
+
+/// The host functions, in the order backends link them
+static HOST_FUNCTIONS: [HostFunction; 8] = [
+    HostFunction {
+        name: "host_log",
+        params: &[I32, I32, I32],
+        results: &[],
+        capability: Some(Capability::Log),
+        right: CapabilityRight::Log,
+        resource: ResourceType::Process,
+    },
+    HostFunction {
+        name: "host_audit_emit",
+        params: &[I32, I32, I32],
+        results: &[],
+        capability: Some(Capability::AuditEmit),
+        right: CapabilityRight::AuditEmit,
+        resource: ResourceType::AuditLog,
+    },
+    HostFunction {
+        name: "host_policy_version",
+        params: &[I32, I32],
+        results: &[I64],
+        capability: Some(Capability::PolicyRead),
+        right: CapabilityRight::Read,
+        resource: ResourceType::Config,
+    },
+    HostFunction {
+        name: "host_policy_get",
+        params: &[I32, I32, I32, I32],
+        results: &[I32],
+        capability: Some(Capability::PolicyRead),
+        right: CapabilityRight::Read,
+        resource: ResourceType::Config,
+    },
+    HostFunction {
+        name: "host_policy_generation",
+        params: &[],
+        results: &[I64],
+        capability: Some(Capability::PolicyRead),
+        right: CapabilityRight::Read,
+        resource: ResourceType::Config,
+    },
+    HostFunction {
+        name: "host_get_config",
+        params: &[I32, I32],
+        results: &[I32],
+        capability: Some(Capability::Config),
+        right: CapabilityRight::Read,
+        resource: ResourceType::Config,
+    },
+    HostFunction {
+        name: "host_yield",
+        params: &[],
+        results: &[],
+        capability: None,
+        right: CapabilityRight::Execute,
+        resource: ResourceType::Process,
+    },
+    HostFunction {
+        name: "host_heartbeat",
+        params: &[],
+        results: &[],
+        capability: None,
+        right: CapabilityRight::Execute,
+        resource: ResourceType::Process,
+    },
+];
+
+/// Registry of the host functions guests can import
+pub(crate) struct HostApi;
+
+impl HostApi {
+    /// Every declared host function
+    pub fn functions() -> &'static [HostFunction] {
+        &HOST_FUNCTIONS
+    }
+
+    /// The host function an import names, if the host provides one
+    pub fn function(module: &str, name: &str) -> Option<&'static HostFunction> {
+        if module != HOST_MODULE {
+            return None;
+        }
+        HOST_FUNCTIONS.iter().find(|function| function.name == name)
+    }
+
+    /// The host function behind a denial's `module::name` import
+    pub fn for_import(import: &str) -> Option<&'static HostFunction> {
+        let (module, name) = import.split_once("::")?;
+        Self::function(module, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_api_declarations() {
+        let mut names: Vec<_> = HostApi::functions().iter().map(|function| function.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), HostApi::functions().len());
+
+        let policy_get = HostApi::function("env", "host_policy_get").unwrap();
+        assert!(policy_get.is_granted(&[Capability::PolicyRead]));
+        assert!(!policy_get.is_granted(&[Capability::Config]));
+        assert!(HostApi::function("env", "host_yield").unwrap().is_granted(&[]));
+        assert!(HostApi::function("wasi_snapshot_preview1", "fd_write").is_none());
+        assert!(HostApi::function("env", "host_exec").is_none());
+
+        let denied = policy_get.denied("accrual");
+        assert!(matches!(&denied, KernelError::CapabilityDenied { import, capability, .. }
+            if import == "env::host_policy_get" && capability == "policy_read"));
+        assert_eq!(HostApi::for_import("env::host_policy_get").map(|f| f.name), Some("host_policy_get"));
+    }
+}
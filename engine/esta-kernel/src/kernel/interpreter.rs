@@ -0,0 +1,708 @@
+//! Interpreter Backend
+//!
+//! With the `interpreter` feature the kernel runs modules in wasmi, a
+//! WebAssembly interpreter, instead of compiling them to machine code with
+//! wasmtime, for platforms that prohibit generating executable code at run
+//! time. The `Kernel` API, host functions, and a module's outputs are the
+//! same on both backends. What differs:
+//!
+//! - Fuel is counted per interpreter instruction, so `fuel_consumed` and the
+//!   point at which `max_fuel` runs out differ from wasmtime's.
+//! - Calls run on a blocking thread. `call_timeout` and shutdown stop a call
+//!   at the guest's next `host_yield`, as they do with wasmtime. The yield
+//!   also checks the call's deadline itself, since tokio's paused test clock
+//!   stands still while a blocking thread runs and the timer would never fire.
+//! - Traps carry no backtrace, WASI modules and SIMD are unsupported, and the
+//!   module cache is not used.
+//!
+//! wasmi's engine registers function types under a lock that waits for every
+//! call running on the engine. Each module therefore gets its own engine, and
+//! a linker holding every host function is built before any call runs;
+//! capabilities are enforced when a host function is called instead of by
+//! what the linker defines.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::{info, warn};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+use wasmi::core::TrapCode;
+use wasmi::errors::{MemoryError, TableError};
+use wasmi::{
+    Caller, CompilationMode, Config, Engine, Instance, Linker, Module, ResourceLimiter, Store, StoreLimits,
+    StoreLimitsBuilder, Val,
+};
+
+#[cfg(feature = "chaos")]
+use crate::chaos::Fault;
+use crate::clock::now_millis;
+use crate::error::KernelError;
+use crate::resource_profile::ResourceUsage;
+use crate::sandbox::SandboxLimits;
+use crate::runtime::{self, WasmInstance, WasmRuntime};
+use crate::security::capabilities::InstanceNonce;
+use crate::trap::TrapKind;
+
+use super::host_api::{HostApi, HostFunction, HOST_MODULE};
+use super::{CallOutcome, CancelHandle, Capability, CachedPolicy, Executable, ExecutionConfig, Kernel, ModuleStats, ModuleStoreData};
+
+/// Translates each module with its own engine; see the module docs
+pub(crate) struct InterpreterRuntime {
+    max_memory_bytes: usize,
+    max_tables: usize,
+    max_instances: usize,
+}
+
+impl InterpreterRuntime {
+    pub(super) fn new(config: &ExecutionConfig) -> Result<Self> {
+        Ok(Self {
+            max_memory_bytes: config.max_memory_bytes,
+            max_tables: config.max_tables as usize,
+            max_instances: config.max_instances as usize,
+        })
+    }
+}
+
+impl WasmRuntime for InterpreterRuntime {
+    type Host = ModuleStoreData;
+    type Module = Arc<CompiledModule>;
+    type Instance = Program;
+
+    fn compile(&self, bytes: &[u8]) -> Result<Arc<CompiledModule>> {
+        Ok(Arc::new(CompiledModule::new(bytes)?))
+    }
+
+    fn has_export(&self, module: &Arc<CompiledModule>, name: &str) -> bool {
+        module.module.get_export(name).is_some()
+    }
+
+    fn imports(&self, module: &Arc<CompiledModule>) -> Vec<(String, String)> {
+        module.module.imports().map(|import| (import.module().to_string(), import.name().to_string())).collect()
+    }
+
+    async fn instantiate(&self, module: &Arc<CompiledModule>, host: ModuleStoreData, fuel: u64) -> Result<Program> {
+        let state = InterpreterState {
+            data: host,
+            limits: InterpreterLimits {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.max_memory_bytes)
+                    .tables(self.max_tables)
+                    .instances(self.max_instances)
+                    .build(),
+                memory_refused: false,
+            },
+            cancelled: Arc::default(),
+            deadline: None,
+            deadline_passed: false,
+        };
+        let mut store = Store::new(&module.engine, state);
+        store.set_fuel(fuel).map_err(|e| anyhow::anyhow!("{}", e))?;
+        store.limiter(|state| &mut state.limits);
+
+        let instance = module
+            .linker
+            .instantiate(&mut store, &module.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| store.data().limits.classify(into_error(e)))?;
+        Ok(Program { store, instance, fuel })
+    }
+}
+
+/// A translated module with its own engine and linker
+pub(crate) struct CompiledModule {
+    engine: Engine,
+    module: Module,
+    linker: Linker<InterpreterState>,
+}
+
+impl CompiledModule {
+    /// Validate and translate module bytes (binary or text format)
+    ///
+    /// Modules are translated in full here, so invalid ones are rejected at launch.
+    fn new(module_bytes: &[u8]) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true).compilation_mode(CompilationMode::Eager);
+        let engine = Engine::new(&config);
+
+        let module = Module::new(&engine, &wat::parse_bytes(module_bytes)?)?;
+        let mut linker = Linker::new(&engine);
+        link(&mut linker)?;
+        Ok(Self { engine, module, linker })
+    }
+}
+
+/// Lets host functions trap with a [`KernelError`]
+impl wasmi::core::HostError for KernelError {}
+
+/// Store data plus the interpreter's own limits, cancellation flag, and deadline
+struct InterpreterState {
+    data: ModuleStoreData,
+    limits: InterpreterLimits,
+    cancelled: Arc<AtomicBool>,
+    /// When the call times out, checked at each yield
+    deadline: Option<tokio::time::Instant>,
+    /// Whether a yield stopped the call at its deadline
+    deadline_passed: bool,
+}
+
+impl InterpreterState {
+    /// Whether the call would be past its deadline after waiting `delay`
+    fn past_deadline(&self, delay: std::time::Duration) -> bool {
+        self.deadline.is_some_and(|deadline| tokio::time::Instant::now() + delay >= deadline)
+    }
+}
+
+/// wasmi's store limits, remembering refused memory as the kernel's do
+struct InterpreterLimits {
+    limits: StoreLimits,
+    memory_refused: bool,
+}
+
+impl InterpreterLimits {
+    /// Mark a failure as hitting the memory limit if memory was refused first
+    fn classify(&self, error: anyhow::Error) -> anyhow::Error {
+        if self.memory_refused {
+            error.context(TrapKind::MemoryLimit)
+        } else {
+            error
+        }
+    }
+}
+
+impl ResourceLimiter for InterpreterLimits {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> Result<bool, MemoryError> {
+        let allowed = self.limits.memory_growing(current, desired, maximum);
+        self.memory_refused |= !matches!(allowed, Ok(true));
+        allowed
+    }
+
+    fn table_growing(&mut self, current: u32, desired: u32, maximum: Option<u32>) -> Result<bool, TableError> {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
+
+/// Stops a running call at the guest's next `host_yield`; dropping it does too
+pub(super) struct CancelGuard(Arc<AtomicBool>);
+
+impl CancelGuard {
+    fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// A module instance in its own store
+///
+/// Calls run to completion before their future is first polled, so drive
+/// them on a blocking thread (see [`Kernel::call_in_new_store`]).
+pub(crate) struct Program {
+    store: Store<InterpreterState>,
+    instance: Instance,
+    fuel: u64,
+}
+
+impl Program {
+    fn cancel_guard(&self) -> CancelGuard {
+        CancelGuard(self.store.data().cancelled.clone())
+    }
+
+    /// Stop the next call at the first yield `timeout` or more after now
+    fn set_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.store.data_mut().deadline = timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+    }
+}
+
+impl WasmInstance for Program {
+    type Host = ModuleStoreData;
+
+    fn host(&self) -> &ModuleStoreData {
+        &self.store.data().data
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        self.instance.get_func(&self.store, name).is_some()
+    }
+
+    async fn call(&mut self, name: &str, args: &[i32]) -> Result<Vec<i32>> {
+        let func = self
+            .instance
+            .get_func(&self.store, name)
+            .ok_or_else(|| anyhow::anyhow!("failed to find function export `{}`", name))?;
+        let params: Vec<Val> = args.iter().map(|&arg| Val::I32(arg)).collect();
+        let mut results = vec![Val::I32(0); func.ty(&self.store).results().len()];
+        if let Err(e) = func.call(&mut self.store, &params, &mut results) {
+            return Err(self.store.data().limits.classify(into_error(e)));
+        }
+        results
+            .into_iter()
+            .map(|value| value.i32().ok_or_else(|| anyhow::anyhow!("{} returned a non-i32 value", name)))
+            .collect()
+    }
+
+    fn fuel_consumed(&self) -> u64 {
+        self.fuel.saturating_sub(self.store.get_fuel().unwrap_or(0))
+    }
+
+    fn has_memory(&self) -> bool {
+        self.instance.get_memory(&self.store, "memory").is_some()
+    }
+
+    fn memory_size(&self) -> usize {
+        self.instance.get_memory(&self.store, "memory").map_or(0, |memory| memory.data(&self.store).len())
+    }
+
+    fn read_memory(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let memory = self.instance.get_memory(&self.store, "memory").ok_or(KernelError::MissingMemoryExport)?;
+        Ok(memory.read(&self.store, offset, buf).map_err(wasmi::Error::from)?)
+    }
+
+    fn write_memory(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+        let memory = self.instance.get_memory(&self.store, "memory").ok_or(KernelError::MissingMemoryExport)?;
+        Ok(memory.write(&mut self.store, offset, bytes).map_err(wasmi::Error::from)?)
+    }
+}
+
+/// Report traps as [`KernelError::Trapped`], so fuel exhaustion and traps
+/// are audited the same way on both backends
+fn into_error(error: wasmi::Error) -> anyhow::Error {
+    // Kernel errors a host function raised, capability denials among them
+    if error.downcast_ref::<KernelError>().is_some() {
+        return error.downcast::<KernelError>().expect("checked above").into();
+    }
+    let kind = match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => TrapKind::FuelExhausted,
+        Some(TrapCode::StackOverflow) => TrapKind::MemoryLimit,
+        Some(_) => TrapKind::Unreachable,
+        None => return error.into(),
+    };
+    KernelError::Trapped { kind, message: error.to_string() }.into()
+}
+
+impl Kernel {
+    /// Instantiate a launched module and run its `_start` export, if any, in
+    /// a supervised task
+    pub(super) async fn start_instance(
+        &self,
+        module: &Arc<CompiledModule>,
+        capabilities: &[Capability],
+        module_name: &str,
+        instance_nonce: &InstanceNonce,
+        limits: &SandboxLimits,
+        stats: Arc<RwLock<ModuleStats>>,
+    ) -> Result<JoinHandle<()>> {
+        let fuel = self.store_fuel(module_name, limits.max_fuel);
+        let mut program = self.instantiate(module, capabilities, module_name, None, instance_nonce, fuel).await?;
+
+        let module_name = module_name.to_string();
+        let audit_log = self.audit_log.clone();
+        let max_fuel = limits.max_fuel;
+
+        // Aborting the task stops `_start` at its next yield
+        Ok(tokio::spawn(crate::correlation::propagate(async move {
+            let _cancel = program.cancel_guard();
+            let handle = tokio::runtime::Handle::current();
+            let start = tokio::task::spawn_blocking(move || {
+                if !program.has_function("_start") {
+                    return None;
+                }
+                let result = handle.block_on(program.call("_start", &[])).map(drop);
+                Some((result, program.fuel_consumed()))
+            });
+            if let Ok(Some((result, consumed))) = start.await {
+                Self::record_start(&module_name, result, consumed, &stats, &audit_log, max_fuel).await;
+            }
+        })))
+    }
+
+    /// Call one JSON ABI function in a fresh store with `fuel` to spend,
+    /// stopping early on a timeout, when shutdown aborts invocations (see
+    /// `Kernel::run_invocation`), or when the invocation is cancelled
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn call_in_new_store(
+        &self,
+        executable: &Executable,
+        module_name: &str,
+        tenant_id: Option<&str>,
+        function_name: &str,
+        input: &[u8],
+        fuel: u64,
+        mut abort: watch::Receiver<bool>,
+        cancel: &CancelHandle,
+    ) -> Result<CallOutcome> {
+        let instantiated = self
+            .instantiate(&executable.module, &executable.capabilities, module_name, tenant_id, &executable.instance_nonce, fuel)
+            .await;
+        let mut program = match instantiated {
+            Ok(program) => program,
+            Err(e) => return Ok((Err(e), ResourceUsage::default(), None)),
+        };
+
+        let guard = program.cancel_guard();
+        program.set_timeout(executable.limits.call_timeout);
+        let (function, input) = (function_name.to_string(), input.to_vec());
+        let max_output_bytes = self.config().max_output_bytes;
+        let handle = tokio::runtime::Handle::current();
+        let mut call = tokio::task::spawn_blocking(move || {
+            let result = handle.block_on(runtime::call_json(&mut program, &function, &input, max_output_bytes));
+            (result, program)
+        });
+        let timeout = async {
+            match executable.limits.call_timeout {
+                Some(limit) => {
+                    tokio::time::sleep(limit).await;
+                    limit
+                }
+                None => std::future::pending().await,
+            }
+        };
+
+        let timed_out = |limit: std::time::Duration, program: &Program| KernelError::CallTimedOut {
+            module: module_name.to_string(),
+            function: function_name.to_string(),
+            timeout_ms: limit.as_millis() as u64,
+            yields: program.store.data().data.yields,
+        };
+        let stopped = tokio::select! {
+            joined = &mut call => {
+                let (result, program) = joined?;
+                match executable.limits.call_timeout {
+                    Some(limit) if program.store.data().deadline_passed => {
+                        let error = timed_out(limit, &program);
+                        return Ok((Err(error.into()), program.usage(), None));
+                    }
+                    _ => {
+                        let memory = self.crash_memory(&program, &result, cancel);
+                        return Ok((result, program.usage(), memory));
+                    }
+                }
+            }
+            _ = abort.wait_for(|aborted| *aborted) => None,
+            _ = cancel.cancelled() => None,
+            limit = timeout => Some(limit),
+        };
+
+        // The guest stops at its next yield (or when its fuel runs out)
+        guard.cancel();
+        let (_, program) = call.await?;
+        let error = match stopped {
+            _ if cancel.is_cancelled() => cancel.error(module_name, function_name),
+            Some(limit) => timed_out(limit, &program),
+            None => KernelError::ShuttingDown,
+        };
+        Ok((Err(error.into()), program.usage(), None))
+    }
+
+    /// Call `__shutdown` in a fresh store
+    pub(super) async fn call_shutdown(&self, module_name: &str, executable: &Executable) -> Result<()> {
+        let fuel = self.store_fuel(module_name, executable.limits.max_fuel);
+        let mut program = self
+            .instantiate(&executable.module, &executable.capabilities, module_name, None, &executable.instance_nonce, fuel)
+            .await?;
+        // Dropped when the drain deadline passes, stopping the call
+        let _cancel = program.cancel_guard();
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || handle.block_on(program.call("__shutdown", &[])).map(drop)).await?
+    }
+}
+
+/// Register every host function; these match `Kernel::register_host_functions`
+///
+/// The linker is shared by every instance of a module, so functions that need
+/// a capability check the caller's at call time, failing as the wasmtime
+/// backend's denial stubs do when it was not granted.
+fn link(linker: &mut Linker<InterpreterState>) -> Result<()> {
+    for function in HostApi::functions() {
+        link_host_function(linker, function)?;
+    }
+    Ok(())
+}
+
+/// Link the implementation of one host function
+fn link_host_function(linker: &mut Linker<InterpreterState>, function: &'static HostFunction) -> Result<()> {
+    match function.name {
+        "host_log" => {
+            linker.func_wrap(HOST_MODULE, "host_log", move |caller: Caller<'_, InterpreterState>, level: i32, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+                granted(&caller, function)?;
+                if ptr < 0 || !(0..=Kernel::MAX_WASM_MEMORY_SIZE).contains(&len) {
+                    warn!("WASM log: invalid parameters (ptr={}, len={})", ptr, len);
+                    return Ok(());
+                }
+                let module_name = &caller.data().data.module_name;
+                info!("[{}] WASM log (level={}, ptr={}, len={})", module_name, level, ptr, len);
+                Ok(())
+            })?;
+        }
+        "host_audit_emit" => {
+            linker.func_wrap(HOST_MODULE, "host_audit_emit", move |caller: Caller<'_, InterpreterState>, event_type: i32, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+                granted(&caller, function)?;
+                if ptr < 0 || !(0..=Kernel::MAX_WASM_MEMORY_SIZE).contains(&len) {
+                    warn!("WASM audit emit: invalid parameters (ptr={}, len={})", ptr, len);
+                    return Ok(());
+                }
+                let module_name = &caller.data().data.module_name;
+                info!("[{}] WASM audit emit (type={}, ptr={}, len={})", module_name, event_type, ptr, len);
+                Ok(())
+            })?;
+        }
+        "host_policy_version" => {
+            linker.func_wrap(HOST_MODULE, "host_policy_version", move |caller: Caller<'_, InterpreterState>, tenant_ptr: i32, tenant_len: i32| -> Result<i64, wasmi::Error> {
+                granted(&caller, function)?;
+                Ok(match guest_policy(&caller, tenant_ptr, tenant_len) {
+                    Ok(cached) => cached.map_or(0, |c| c.revision as i64),
+                    Err(code) => code as i64,
+                })
+            })?;
+        }
+        "host_policy_get" => {
+            linker.func_wrap(HOST_MODULE, "host_policy_get", move |mut caller: Caller<'_, InterpreterState>, tenant_ptr: i32, tenant_len: i32, out_ptr: i32, out_len: i32| -> Result<i32, wasmi::Error> {
+                granted(&caller, function)?;
+                let cached = match guest_policy(&caller, tenant_ptr, tenant_len) {
+                    Ok(Some(cached)) => cached,
+                    Ok(None) => return Ok(0),
+                    Err(code) => return Ok(code),
+                };
+                // Too small a buffer gets the required length and nothing written
+                let len = cached.json.len() as i32;
+                if out_ptr < 0 || out_len < 0 {
+                    return Ok(Kernel::HOST_POLICY_INVALID);
+                }
+                if len <= out_len && !write_guest_bytes(&mut caller, out_ptr, &cached.json) {
+                    return Ok(Kernel::HOST_POLICY_INVALID);
+                }
+                Ok(len)
+            })?;
+        }
+        "host_policy_generation" => {
+            linker.func_wrap(HOST_MODULE, "host_policy_generation", move |caller: Caller<'_, InterpreterState>| -> Result<i64, wasmi::Error> {
+                granted(&caller, function)?;
+                Ok(caller.data().data.tenants.policy_generation() as i64)
+            })?;
+        }
+        "host_get_config" => {
+            linker.func_wrap(HOST_MODULE, "host_get_config", move |mut caller: Caller<'_, InterpreterState>, out_ptr: i32, out_len: i32| -> Result<i32, wasmi::Error> {
+                granted(&caller, function)?;
+                let Some(config) = caller.data().data.config.clone() else {
+                    return Ok(0);
+                };
+                // Too small a buffer gets the required length and nothing written
+                let len = config.len() as i32;
+                if out_ptr < 0 || out_len < 0 {
+                    return Ok(Kernel::HOST_CONFIG_INVALID);
+                }
+                if len <= out_len && !write_guest_bytes(&mut caller, out_ptr, &config) {
+                    return Ok(Kernel::HOST_CONFIG_INVALID);
+                }
+                Ok(len)
+            })?;
+        }
+        // The interpreter cannot suspend a call, so a yield only charges fuel and
+        // checks whether the kernel has stopped waiting for the call or its
+        // deadline has passed
+        "host_yield" => {
+            linker.func_wrap(HOST_MODULE, "host_yield", |mut caller: Caller<'_, InterpreterState>| -> Result<(), wasmi::Error> {
+                let cost = caller.data().data.yield_fuel_cost;
+                let fuel = caller.get_fuel().unwrap_or(0);
+                if fuel < cost {
+                    return Err(TrapCode::OutOfFuel.into());
+                }
+                caller.set_fuel(fuel - cost).map_err(|e| wasmi::Error::new(e.to_string()))?;
+                caller.data_mut().data.yields += 1;
+                #[cfg(feature = "chaos")]
+                let delay = caller.data().data.chaos.clone().filter(|chaos| {
+                    chaos.inject(Fault::HostCallDelay, &caller.data().data.module_name)
+                });
+                #[cfg(feature = "chaos")]
+                let delay = delay.map(|chaos| chaos.config().host_delay).unwrap_or_default();
+                #[cfg(not(feature = "chaos"))]
+                let delay = std::time::Duration::ZERO;
+                // A delay running past the deadline ends the call instead
+                if caller.data().past_deadline(delay) {
+                    caller.data_mut().deadline_passed = true;
+                    return Err(wasmi::Error::new("call deadline passed"));
+                }
+                if !delay.is_zero() {
+                    std::thread::sleep(delay);
+                }
+                if caller.data().cancelled.load(Ordering::Acquire) {
+                    return Err(wasmi::Error::new("invocation cancelled"));
+                }
+                Ok(())
+            })?;
+        }
+        "host_heartbeat" => {
+            linker.func_wrap(HOST_MODULE, "host_heartbeat", |caller: Caller<'_, InterpreterState>| {
+                let data = &caller.data().data;
+                data.heartbeats
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(data.module_name.clone(), now_millis());
+            })?;
+        }
+        name => anyhow::bail!("host function {} has no implementation", name),
+    }
+    Ok(())
+}
+
+/// Trap with the function's denial unless the calling module was granted it
+fn granted(caller: &Caller<'_, InterpreterState>, function: &HostFunction) -> Result<(), wasmi::Error> {
+    let data = &caller.data().data;
+    if function.is_granted(&data.capabilities) {
+        Ok(())
+    } else {
+        Err(wasmi::Error::host(function.denied(&data.module_name)))
+    }
+}
+
+/// Resolve the tenant a guest names to its cached policy
+fn guest_policy(
+    caller: &Caller<'_, InterpreterState>,
+    tenant_ptr: i32,
+    tenant_len: i32,
+) -> std::result::Result<Option<Arc<CachedPolicy>>, i32> {
+    let tenant_id = read_guest_bytes(caller, tenant_ptr, tenant_len)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or(Kernel::HOST_POLICY_INVALID)?;
+    caller.data().data.policy_for(&tenant_id)
+}
+
+/// Copy bytes out of the calling module's exported memory
+fn read_guest_bytes(caller: &Caller<'_, InterpreterState>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    if ptr < 0 || !(0..=Kernel::MAX_WASM_MEMORY_SIZE).contains(&len) {
+        return None;
+    }
+    let memory = caller.get_export("memory")?.into_memory()?;
+    let mut bytes = vec![0u8; len as usize];
+    memory.read(caller, ptr as usize, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Copy bytes into the calling module's exported memory
+fn write_guest_bytes(caller: &mut Caller<'_, InterpreterState>, ptr: i32, bytes: &[u8]) -> bool {
+    match caller.get_export("memory").and_then(|export| export.into_memory()) {
+        Some(memory) => memory.write(caller, ptr as usize, bytes).is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::tests::write_test_module;
+    #[cfg(feature = "wasmtime")]
+    use sha2::{Digest, Sha256};
+
+    /// Hashes its input two ways: FNV-1a over the bytes, and a floating-point
+    /// fold using division and square roots
+    const DIGEST_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func $alloc (export "alloc") (param $size i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $size)))
+            (local.get $ptr))
+          (func (export "digest_json") (param $ptr i32) (param $len i32) (result i32)
+            (local $i i32) (local $byte i32) (local $hash i64) (local $fold f64) (local $out i32)
+            (local.set $hash (i64.const 0xcbf29ce484222325))
+            (local.set $fold (f64.const 1))
+            (block $done
+              (loop $next
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $byte (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+                (local.set $hash
+                  (i64.mul (i64.xor (local.get $hash) (i64.extend_i32_u (local.get $byte)))
+                           (i64.const 0x100000001b3)))
+                (local.set $fold
+                  (f64.add (f64.sqrt (f64.add (local.get $fold) (f64.convert_i32_u (local.get $byte))))
+                           (f64.div (local.get $fold) (f64.const 3))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $next)))
+            (local.set $out (call $alloc (i32.const 20)))
+            (i32.store (local.get $out) (i32.const 16))
+            (i64.store (i32.add (local.get $out) (i32.const 4)) (local.get $hash))
+            (f64.store (i32.add (local.get $out) (i32.const 12)) (local.get $fold))
+            (local.get $out)))
+    "#;
+
+    /// Run `digest_json` compiled by wasmtime, outside the kernel
+    #[cfg(feature = "wasmtime")]
+    async fn compiled_digest(input: &[u8]) -> Vec<u8> {
+        let mut config = wasmtime::Config::new();
+        config.async_support(true).consume_fuel(true).cranelift_nan_canonicalization(true);
+        let engine = &wasmtime::Engine::new(&config).unwrap();
+        let module = wasmtime::Module::new(engine, DIGEST_WAT).unwrap();
+        let mut store = wasmtime::Store::new(engine, ());
+        store.add_fuel(u64::MAX / 2).unwrap();
+        let instance = wasmtime::Linker::new(engine).instantiate_async(&mut store, &module).await.unwrap();
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc").unwrap();
+        let digest = instance.get_typed_func::<(i32, i32), i32>(&mut store, "digest_json").unwrap();
+
+        let ptr = alloc.call_async(&mut store, input.len() as i32).await.unwrap();
+        memory.write(&mut store, ptr as usize, input).unwrap();
+        let out = digest.call_async(&mut store, (ptr, input.len() as i32)).await.unwrap() as usize;
+        memory.data(&store)[out + 4..out + 20].to_vec()
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[tokio::test]
+    async fn test_outputs_match_compiled_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let k = Kernel::new().unwrap();
+        k.launch_module(&write_test_module(dir.path(), "digest", DIGEST_WAT)).await.unwrap();
+
+        let inputs: [&[u8]; 4] = [
+            b"",
+            br#"{"minutes_worked":120}"#,
+            br#"{"hours_worked":[7.5,8.25,0.1],"carryover":40}"#,
+            &[0xff; 4096],
+        ];
+        for input in inputs {
+            let interpreted = k.execute_function("digest", "digest_json", input).await.unwrap();
+            let compiled = compiled_digest(input).await;
+            assert_eq!(
+                hex::encode(Sha256::digest(&interpreted.output)),
+                hex::encode(Sha256::digest(&compiled)),
+                "input {:?}",
+                String::from_utf8_lossy(input)
+            );
+            assert!(interpreted.fuel_consumed > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wasi_modules_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = write_test_module(dir.path(), "digest", DIGEST_WAT);
+        let mut manifest: crate::kernel::ModuleManifest =
+            serde_json::from_slice(&std::fs::read(&manifest_path).unwrap()).unwrap();
+        manifest.capabilities = vec!["wasi".into()];
+
+        let k = Kernel::new().unwrap();
+        let err = k.launch_manifest(manifest).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<KernelError>(),
+            Some(KernelError::InterpreterUnsupported { capability, .. }) if capability == "wasi"
+        ));
+    }
+}
@@ -0,0 +1,538 @@
+//! wasmtime Backend
+//!
+//! The default backend compiles modules to machine code with wasmtime, under
+//! an engine configured for deterministic execution: fuel metering, no
+//! threads, canonical NaNs. Calls run on the async executor, and
+//! `host_yield` suspends the guest so timeouts and shutdown can stop it.
+//! Cancelling an invocation bumps the engine's epoch, which interrupts the
+//! guest at its next epoch check whether or not it yields.
+//! Host functions are linked per instance: their implementations for the
+//! capabilities the module was granted, denial stubs for the rest.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::{info, warn};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use wasmtime::{
+    Caller, Config, Engine, ExternType, FuncType, Instance, Linker, Memory, Module, ResourceLimiter, Store, StoreLimits,
+    StoreLimitsBuilder, Trap, Val, ValType, WasmBacktrace,
+};
+
+#[cfg(feature = "chaos")]
+use crate::chaos::Fault;
+use crate::clock::now_millis;
+use crate::error::KernelError;
+use crate::resource_profile::ResourceUsage;
+use crate::sandbox::SandboxLimits;
+use crate::runtime::{self, WasmInstance, WasmRuntime};
+use crate::security::capabilities::InstanceNonce;
+use crate::trap::{BacktraceFrame, TrapKind};
+
+use super::host_api::{HostApi, HostFunction, HostType, HOST_MODULE};
+use super::{CallOutcome, CancelHandle, Capability, CachedPolicy, Executable, ExecutionConfig, Kernel, ModuleStats, ModuleStoreData};
+
+/// Store limits that remember refusing memory, so the failure that follows
+/// is classified as [`TrapKind::MemoryLimit`]
+pub(super) struct GuestLimits {
+    limits: StoreLimits,
+    memory_refused: bool,
+}
+
+impl GuestLimits {
+    pub(super) fn new(config: &ExecutionConfig) -> Self {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(config.max_memory_bytes)
+            .tables(config.max_tables as usize)
+            .instances(config.max_instances as usize)
+            .build();
+        Self { limits, memory_refused: false }
+    }
+
+    /// Mark a failure as hitting the memory limit if memory was refused first
+    fn classify(&self, error: anyhow::Error) -> anyhow::Error {
+        if self.memory_refused {
+            error.context(TrapKind::MemoryLimit)
+        } else {
+            error
+        }
+    }
+}
+
+impl ResourceLimiter for GuestLimits {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> bool {
+        let allowed = self.limits.memory_growing(current, desired, maximum);
+        self.memory_refused |= !allowed;
+        allowed
+    }
+
+    fn table_growing(&mut self, current: u32, desired: u32, maximum: Option<u32>) -> bool {
+        self.limits.table_growing(current, desired, maximum)
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
+}
+
+/// The symbolized WASM backtrace of a wasmtime trap, or None if the error is not one
+pub(super) fn backtrace(error: &anyhow::Error) -> Option<Vec<BacktraceFrame>> {
+    error.downcast_ref::<Trap>()?;
+    let frames = error
+        .downcast_ref::<WasmBacktrace>()
+        .map(|backtrace| {
+            backtrace
+                .frames()
+                .iter()
+                .map(|frame| BacktraceFrame {
+                    func_index: frame.func_index(),
+                    func_name: frame.func_name().map(String::from),
+                    module_offset: frame.module_offset(),
+                    func_offset: frame.func_offset(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(frames)
+}
+
+/// Compiles modules with one engine shared by every instance
+pub(crate) struct WasmtimeRuntime {
+    engine: Engine,
+}
+
+impl WasmtimeRuntime {
+    pub(super) fn new(_config: &ExecutionConfig) -> Result<Self> {
+        // Configure engine for deterministic execution
+        let mut engine_config = Config::new();
+        engine_config
+            .async_support(true)
+            .consume_fuel(true)  // Enable fuel metering
+            .epoch_interruption(true)  // Epoch bumps interrupt cancelled invocations; fuel still meters
+            .wasm_threads(false)  // Disable threads for determinism
+            .wasm_simd(true)  // SIMD is deterministic
+            .wasm_multi_memory(false)  // Single memory for simplicity
+            .wasm_memory64(false)  // 32-bit memory addresses
+            .cranelift_nan_canonicalization(true);  // Deterministic NaN handling
+
+        Ok(Self { engine: Engine::new(&engine_config)? })
+    }
+
+    /// The engine, for the module cache
+    pub(super) fn engine(&self) -> &Engine {
+        &self.engine
+    }
+}
+
+impl WasmRuntime for WasmtimeRuntime {
+    type Host = ModuleStoreData;
+    type Module = Module;
+    type Instance = WasmtimeInstance;
+
+    fn compile(&self, bytes: &[u8]) -> Result<Module> {
+        Module::new(&self.engine, bytes)
+    }
+
+    fn has_export(&self, module: &Module, name: &str) -> bool {
+        module.get_export(name).is_some()
+    }
+
+    fn imports(&self, module: &Module) -> Vec<(String, String)> {
+        module.imports().map(|import| (import.module().to_string(), import.name().to_string())).collect()
+    }
+
+    async fn instantiate(&self, module: &Module, host: ModuleStoreData, fuel: u64) -> Result<WasmtimeInstance> {
+        // Create linker with capability-based host functions
+        let mut linker = Linker::new(&self.engine);
+        Kernel::register_host_functions(&mut linker, module, &host.capabilities)?;
+
+        let mut store = Store::new(&self.engine, host);
+        // Add fuel for this execution (fuel consumption is enabled in engine config)
+        let _ = store.add_fuel(fuel);
+        // Enable resource limiting
+        store.limiter(|data| &mut data.limits);
+        // Every epoch bump reaches every store; only cancelled calls stop
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(|_| Ok(1));
+
+        let instance = match linker.instantiate_async(&mut store, module).await {
+            Ok(instance) => instance,
+            Err(e) => return Err(store.data().limits.classify(e)),
+        };
+        let memory = instance.get_memory(&mut store, "memory");
+        Ok(WasmtimeInstance { store, instance, module: module.clone(), memory })
+    }
+}
+
+/// wasmtime's type for a host function parameter or result
+fn val_type(ty: HostType) -> ValType {
+    match ty {
+        HostType::I32 => ValType::I32,
+        HostType::I64 => ValType::I64,
+    }
+}
+
+/// A module instance in its own store
+pub(crate) struct WasmtimeInstance {
+    pub(super) store: Store<ModuleStoreData>,
+    pub(super) instance: Instance,
+    /// Export types are read from the module; the instance needs mutable store access
+    module: Module,
+    memory: Option<Memory>,
+}
+
+impl WasmInstance for WasmtimeInstance {
+    type Host = ModuleStoreData;
+
+    fn host(&self) -> &ModuleStoreData {
+        self.store.data()
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        matches!(self.module.get_export(name), Some(ExternType::Func(_)))
+    }
+
+    async fn call(&mut self, name: &str, args: &[i32]) -> Result<Vec<i32>> {
+        let func = self
+            .instance
+            .get_func(&mut self.store, name)
+            .ok_or_else(|| anyhow::anyhow!("failed to find function export `{}`", name))?;
+        let params: Vec<Val> = args.iter().map(|&arg| Val::I32(arg)).collect();
+        let mut results = vec![Val::I32(0); func.ty(&self.store).results().len()];
+        if let Err(e) = func.call_async(&mut self.store, &params, &mut results).await {
+            return Err(self.store.data().limits.classify(e));
+        }
+        results
+            .into_iter()
+            .map(|value| value.i32().ok_or_else(|| anyhow::anyhow!("{} returned a non-i32 value", name)))
+            .collect()
+    }
+
+    fn fuel_consumed(&self) -> u64 {
+        self.store.fuel_consumed().unwrap_or(0)
+    }
+
+    fn has_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    fn memory_size(&self) -> usize {
+        self.memory.map_or(0, |memory| memory.data_size(&self.store))
+    }
+
+    fn read_memory(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let memory = self.memory.ok_or(KernelError::MissingMemoryExport)?;
+        Ok(memory.read(&self.store, offset, buf)?)
+    }
+
+    fn write_memory(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+        let memory = self.memory.ok_or(KernelError::MissingMemoryExport)?;
+        Ok(memory.write(&mut self.store, offset, bytes)?)
+    }
+}
+
+impl Kernel {
+    /// Register host functions based on granted capabilities
+    ///
+    /// Each function in the [`HostApi`] is linked to its implementation when
+    /// the capabilities grant it, and to a denial stub otherwise.
+    fn register_host_functions(
+        linker: &mut Linker<ModuleStoreData>,
+        module: &Module,
+        capabilities: &[Capability],
+    ) -> Result<()> {
+        if capabilities.contains(&Capability::Wasi) {
+            crate::wasi::add_to_linker(linker, module, |data: &mut ModuleStoreData| data.wasi.as_mut())?;
+        }
+
+        for function in HostApi::functions() {
+            if function.is_granted(capabilities) {
+                Self::link_host_function(linker, function)?;
+            } else {
+                let ty = FuncType::new(
+                    function.params.iter().map(|&ty| val_type(ty)),
+                    function.results.iter().map(|&ty| val_type(ty)),
+                );
+                linker.func_new(HOST_MODULE, function.name, ty, move |caller: Caller<'_, ModuleStoreData>, _, _| {
+                    Err(function.denied(&caller.data().module_name).into())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Link the implementation of one host function
+    fn link_host_function(linker: &mut Linker<ModuleStoreData>, function: &HostFunction) -> Result<()> {
+        match function.name {
+            "host_log" => {
+                linker.func_wrap(HOST_MODULE, "host_log", |caller: Caller<'_, ModuleStoreData>, level: i32, ptr: i32, len: i32| {
+                    if ptr < 0 || !(0..=Self::MAX_WASM_MEMORY_SIZE).contains(&len) {
+                        warn!("WASM log: invalid parameters (ptr={}, len={})", ptr, len);
+                        return;
+                    }
+                    let module_name = &caller.data().module_name;
+                    info!("[{}] WASM log (level={}, ptr={}, len={})", module_name, level, ptr, len);
+                })?;
+            }
+            "host_audit_emit" => {
+                linker.func_wrap(HOST_MODULE, "host_audit_emit", |caller: Caller<'_, ModuleStoreData>, event_type: i32, ptr: i32, len: i32| {
+                    if ptr < 0 || !(0..=Self::MAX_WASM_MEMORY_SIZE).contains(&len) {
+                        warn!("WASM audit emit: invalid parameters (ptr={}, len={})", ptr, len);
+                        return;
+                    }
+                    let module_name = &caller.data().module_name;
+                    info!("[{}] WASM audit emit (type={}, ptr={}, len={})", module_name, event_type, ptr, len);
+                })?;
+            }
+            // Policy cache: a tenant's policy history as JSON plus revision
+            // counters, so resident modules can keep parsed policies and refetch
+            // only after an update. Stores running for a tenant see only that
+            // tenant's policy.
+            "host_policy_version" => {
+                linker.func_wrap(HOST_MODULE, "host_policy_version", |mut caller: Caller<'_, ModuleStoreData>, tenant_ptr: i32, tenant_len: i32| -> i64 {
+                    match Self::guest_policy(&mut caller, tenant_ptr, tenant_len) {
+                        Ok(cached) => cached.map_or(0, |c| c.revision as i64),
+                        Err(code) => code as i64,
+                    }
+                })?;
+            }
+            "host_policy_get" => {
+                linker.func_wrap(HOST_MODULE, "host_policy_get", |mut caller: Caller<'_, ModuleStoreData>, tenant_ptr: i32, tenant_len: i32, out_ptr: i32, out_len: i32| -> i32 {
+                    let cached = match Self::guest_policy(&mut caller, tenant_ptr, tenant_len) {
+                        Ok(Some(cached)) => cached,
+                        Ok(None) => return 0,
+                        Err(code) => return code,
+                    };
+                    // Too small a buffer gets the required length and nothing written
+                    let len = cached.json.len() as i32;
+                    if out_ptr < 0 || out_len < 0 {
+                        return Self::HOST_POLICY_INVALID;
+                    }
+                    if len <= out_len && !Self::write_guest_bytes(&mut caller, out_ptr, &cached.json) {
+                        return Self::HOST_POLICY_INVALID;
+                    }
+                    len
+                })?;
+            }
+            "host_policy_generation" => {
+                linker.func_wrap(HOST_MODULE, "host_policy_generation", |caller: Caller<'_, ModuleStoreData>| -> i64 {
+                    caller.data().tenants.policy_generation() as i64
+                })?;
+            }
+            // Configuration of the tenant the call runs for, fixed when the store
+            // was created; 0 when it runs for no tenant
+            "host_get_config" => {
+                linker.func_wrap(HOST_MODULE, "host_get_config", |mut caller: Caller<'_, ModuleStoreData>, out_ptr: i32, out_len: i32| -> i32 {
+                    let Some(config) = caller.data().config.clone() else {
+                        return 0;
+                    };
+                    // Too small a buffer gets the required length and nothing written
+                    let len = config.len() as i32;
+                    if out_ptr < 0 || out_len < 0 {
+                        return Self::HOST_CONFIG_INVALID;
+                    }
+                    if len <= out_len && !Self::write_guest_bytes(&mut caller, out_ptr, &config) {
+                        return Self::HOST_CONFIG_INVALID;
+                    }
+                    len
+                })?;
+            }
+            // Cooperative yield point, available to every module. Charges fuel so
+            // yielding is not free, then returns control to the async executor so
+            // timeouts and cancellation of the invocation can take effect.
+            "host_yield" => {
+                linker.func_wrap0_async(HOST_MODULE, "host_yield", |mut caller: Caller<'_, ModuleStoreData>| {
+                    Box::new(async move {
+                        let cost = caller.data().yield_fuel_cost;
+                        caller.consume_fuel(cost).map_err(|_| Trap::OutOfFuel)?;
+                        caller.data_mut().yields += 1;
+                        #[cfg(feature = "chaos")]
+                        if let Some(chaos) = caller.data().chaos.clone() {
+                            if chaos.inject(Fault::HostCallDelay, &caller.data().module_name) {
+                                tokio::time::sleep(chaos.config().host_delay).await;
+                            }
+                        }
+                        tokio::task::yield_now().await;
+                        Ok(())
+                    })
+                })?;
+            }
+            // Liveness signal, available to every module. Resident modules that
+            // run their own loop call it periodically; a supervisor watching the
+            // heartbeats treats a module that stops calling it as hung.
+            "host_heartbeat" => {
+                linker.func_wrap(HOST_MODULE, "host_heartbeat", |caller: Caller<'_, ModuleStoreData>| {
+                    let data = caller.data();
+                    data.heartbeats
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(data.module_name.clone(), now_millis());
+                })?;
+            }
+            name => anyhow::bail!("host function {} has no implementation", name),
+        }
+        Ok(())
+    }
+
+    /// Resolve the tenant a guest names to its cached policy
+    fn guest_policy(
+        caller: &mut Caller<'_, ModuleStoreData>,
+        tenant_ptr: i32,
+        tenant_len: i32,
+    ) -> std::result::Result<Option<Arc<CachedPolicy>>, i32> {
+        let tenant_id = Self::read_guest_bytes(caller, tenant_ptr, tenant_len)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(Self::HOST_POLICY_INVALID)?;
+        caller.data().policy_for(&tenant_id)
+    }
+
+    /// Copy bytes out of the calling module's exported memory
+    fn read_guest_bytes(caller: &mut Caller<'_, ModuleStoreData>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+        if ptr < 0 || !(0..=Self::MAX_WASM_MEMORY_SIZE).contains(&len) {
+            return None;
+        }
+        let memory = caller.get_export("memory")?.into_memory()?;
+        let mut bytes = vec![0u8; len as usize];
+        memory.read(&*caller, ptr as usize, &mut bytes).ok()?;
+        Some(bytes)
+    }
+
+    /// Copy bytes into the calling module's exported memory
+    fn write_guest_bytes(caller: &mut Caller<'_, ModuleStoreData>, ptr: i32, bytes: &[u8]) -> bool {
+        match caller.get_export("memory").and_then(|export| export.into_memory()) {
+            Some(memory) => memory.write(&mut *caller, ptr as usize, bytes).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Instantiate a launched module and run its `_start` export, if any, in
+    /// a supervised task
+    pub(super) async fn start_instance(
+        &self,
+        module: &Module,
+        capabilities: &[Capability],
+        module_name: &str,
+        instance_nonce: &InstanceNonce,
+        limits: &SandboxLimits,
+        stats: Arc<RwLock<ModuleStats>>,
+    ) -> Result<JoinHandle<()>> {
+        let fuel = self.store_fuel(module_name, limits.max_fuel);
+        let mut instance = self.instantiate(module, capabilities, module_name, None, instance_nonce, fuel).await?;
+
+        let module_name = module_name.to_string();
+        let audit_log = self.audit_log.clone();
+        let max_fuel = limits.max_fuel;
+
+        // Run in supervised task, auditing under the loading request's correlation ID
+        Ok(tokio::spawn(crate::correlation::propagate(async move {
+            if instance.has_function("_start") {
+                let result = instance.call("_start", &[]).await.map(drop);
+                let consumed = instance.fuel_consumed();
+                Self::record_start(&module_name, result, consumed, &stats, &audit_log, max_fuel).await;
+            }
+        })))
+    }
+
+    /// Call one JSON ABI function in a fresh store with `fuel` to spend,
+    /// stopping early on a timeout, when shutdown aborts invocations (see
+    /// [`Kernel::run_invocation`]), or when the invocation is cancelled
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn call_in_new_store(
+        &self,
+        executable: &Executable,
+        module_name: &str,
+        tenant_id: Option<&str>,
+        function_name: &str,
+        input: &[u8],
+        fuel: u64,
+        mut abort: watch::Receiver<bool>,
+        cancel: &CancelHandle,
+    ) -> Result<CallOutcome> {
+        let deadline = executable.limits.call_timeout.map(|limit| (Instant::now() + limit, limit));
+        let expired = move || async move {
+            match deadline {
+                Some((at, _)) => tokio::time::sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+        // Why a call stopped before finishing
+        let stopped = |yields: u32| -> anyhow::Error {
+            if cancel.is_cancelled() {
+                return cancel.error(module_name, function_name).into();
+            }
+            match deadline {
+                Some((at, limit)) if Instant::now() >= at => KernelError::CallTimedOut {
+                    module: module_name.to_string(),
+                    function: function_name.to_string(),
+                    timeout_ms: limit.as_millis() as u64,
+                    yields,
+                }
+                .into(),
+                _ => KernelError::ShuttingDown.into(),
+            }
+        };
+
+        let instantiate = self.instantiate(
+            &executable.module,
+            &executable.capabilities,
+            module_name,
+            tenant_id,
+            &executable.instance_nonce,
+            fuel,
+        );
+        let mut instance = tokio::select! {
+            instance = instantiate => match instance {
+                Ok(instance) => instance,
+                Err(e) => return Ok((Err(e), ResourceUsage::default(), None)),
+            },
+            _ = abort.wait_for(|aborted| *aborted) => return Ok((Err(stopped(0)), ResourceUsage::default(), None)),
+            _ = cancel.cancelled() => return Ok((Err(stopped(0)), ResourceUsage::default(), None)),
+            _ = expired() => return Ok((Err(stopped(0)), ResourceUsage::default(), None)),
+        };
+
+        // A guest computing without yielding is interrupted at its next epoch check
+        let interrupt = cancel.clone();
+        instance.store.epoch_deadline_callback(move |_| {
+            if interrupt.is_cancelled() {
+                return Err(Trap::Interrupt.into());
+            }
+            Ok(1)
+        });
+
+        let finished = {
+            let call = runtime::call_json(&mut instance, function_name, input, self.config().max_output_bytes);
+            tokio::select! {
+                result = call => Some(result),
+                _ = abort.wait_for(|aborted| *aborted) => None,
+                _ = cancel.cancelled() => None,
+                _ = expired() => None,
+            }
+        };
+        let result = match finished {
+            Some(Err(_)) if cancel.is_cancelled() => Err(stopped(instance.host().yields)),
+            Some(result) => result,
+            None => Err(stopped(instance.host().yields)),
+        };
+        let memory = self.crash_memory(&instance, &result, cancel);
+        Ok((result, instance.usage(), memory))
+    }
+
+    /// Call `__shutdown` in a fresh store
+    pub(super) async fn call_shutdown(&self, module_name: &str, executable: &Executable) -> Result<()> {
+        let fuel = self.store_fuel(module_name, executable.limits.max_fuel);
+        let mut instance = self
+            .instantiate(&executable.module, &executable.capabilities, module_name, None, &executable.instance_nonce, fuel)
+            .await?;
+        instance.call("__shutdown", &[]).await.map(drop)
+    }
+}
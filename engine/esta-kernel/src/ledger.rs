@@ -0,0 +1,646 @@
+//! Accrual Ledger
+//!
+//! The ledger is the append-only record of hours worked, sick time accrued,
+//! and sick time used for every employee of every tenant. Balances and
+//! compliance reports are derived from it; nothing is ever updated in place.
+//!
+//! Events can be persisted to a JSON Lines file (one event per line) so the
+//! ledger survives restarts. A final line left partially written by a crash
+//! is cut off when the file is next opened for writing; the events it held
+//! were never reported as recorded.
+//!
+//! A read-only snapshot of the same file can be opened alongside a running
+//! primary (e.g. by a reporting replica). Snapshots reject appends, tolerate a
+//! partially written final line, and can be refreshed to pick up new events.
+//!
+//! Purging a tenant is one exception to append-only: every event of the
+//! tenant is removed and the file rewritten, the remaining events keeping
+//! their sequence numbers. The other is redaction of employee IDs past a
+//! tenant's retention period (see [`crate::retention`]).
+//!
+//! A dry-run copy holds the current events in memory only, so hypothetical
+//! events (e.g. from a what-if import) can be appended and balances derived
+//! without touching the real ledger or its file.
+
+use crate::calendar::Date;
+use crate::clock::now_millis;
+use crate::error::StorageError;
+use crate::pagination::{Page, PageRequest};
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+
+/// Prefix of the employee ID of events redacted under a retention period
+///
+/// The rest is a keyed hash of the original ID (see
+/// [`crate::security::pseudonym::Pseudonymizer::redacted`]).
+pub const REDACTED_EMPLOYEE_PREFIX: &str = "redacted_";
+
+/// What happened to an employee's sick time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LedgerEventKind {
+    /// Hours worked and the sick time they accrued
+    Accrued { minutes_worked: u64, accrued_minutes: u64 },
+    /// Sick time taken
+    Used { minutes: u64 },
+}
+
+/// An event to be appended to the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewLedgerEvent {
+    pub tenant_id: String,
+    pub employee_id: String,
+    /// Day the work or leave happened
+    pub work_date: Date,
+    pub kind: LedgerEventKind,
+    /// Policy version the accrual was calculated under, if any
+    pub policy_version: Option<u32>,
+    /// Where the event came from (e.g. `import:payroll.csv#12`)
+    pub source: String,
+}
+
+/// An event recorded in the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEvent {
+    /// Sequence number (monotonically increasing across all tenants)
+    pub sequence: u64,
+    /// When the event was recorded (ms since Unix epoch)
+    pub recorded_at: u64,
+    #[serde(flatten)]
+    pub event: NewLedgerEvent,
+}
+
+/// Append-only ledger of accrual events
+pub struct Ledger {
+    events: RwLock<Vec<LedgerEvent>>,
+    file: Option<PathBuf>,
+    read_only: bool,
+    dry_run: bool,
+}
+
+impl Ledger {
+    /// Create an in-memory ledger
+    pub fn new() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+            file: None,
+            read_only: false,
+            dry_run: false,
+        }
+    }
+
+    /// Open a ledger persisted to a JSON Lines file, loading existing events
+    ///
+    /// A partially written final line is truncated away before loading.
+    pub fn with_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Self::truncate_partial_tail(&path)?;
+        let events = Self::load(&path, false)?;
+
+        Ok(Self {
+            events: RwLock::new(events),
+            file: Some(path),
+            read_only: false,
+            dry_run: false,
+        })
+    }
+
+    /// Open a read-only snapshot of a ledger file written by another process
+    pub fn snapshot(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let events = Self::load(&path, true)?;
+
+        Ok(Self {
+            events: RwLock::new(events),
+            file: Some(path),
+            read_only: true,
+            dry_run: false,
+        })
+    }
+
+    /// Copy the current events into an in-memory ledger for a dry run
+    ///
+    /// Appends to the copy are never written to a file or seen by this ledger.
+    /// Works on read-only snapshots too.
+    pub async fn dry_run(&self) -> Self {
+        Self {
+            events: RwLock::new(self.events.read().await.clone()),
+            file: None,
+            read_only: false,
+            dry_run: true,
+        }
+    }
+
+    /// Read events from a JSON Lines file
+    ///
+    /// When `allow_partial_tail` is set, a final line without a trailing
+    /// newline is assumed to be mid-write by the primary and skipped.
+    fn load(path: &Path, allow_partial_tail: bool) -> Result<Vec<LedgerEvent>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let complete = if allow_partial_tail && !contents.ends_with('\n') {
+            contents.rfind('\n').map_or("", |end| &contents[..end])
+        } else {
+            contents.as_str()
+        };
+
+        complete
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| StorageError::Corrupt(format!("{}:{}: {}", path.display(), i + 1, e)).into())
+            })
+            .collect()
+    }
+
+    /// Cut a final line without a trailing newline (a torn write) off the file
+    fn truncate_partial_tail(path: &Path) -> Result<()> {
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if contents.is_empty() || contents.ends_with(b"\n") {
+            return Ok(());
+        }
+
+        let complete = contents.iter().rposition(|&b| b == b'\n').map_or(0, |end| end + 1);
+        warn!(
+            "Ledger file {} ends with a partially written line; truncating {} bytes",
+            path.display(),
+            contents.len() - complete
+        );
+        let file = std::fs::OpenOptions::new().write(true).open(path)?;
+        file.set_len(complete as u64)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Reload a snapshot from its file to pick up events appended since it was opened
+    ///
+    /// Has no effect on a writable ledger, which is always current.
+    pub async fn refresh(&self) -> Result<()> {
+        if let (true, Some(path)) = (self.read_only, &self.file) {
+            let events = Self::load(path, true)?;
+            *self.events.write().await = events;
+        }
+        Ok(())
+    }
+
+    /// Whether this ledger is a read-only snapshot
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The JSON Lines file backing this ledger, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Whether this ledger is a dry-run copy
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Append a single event
+    pub async fn append(&self, event: NewLedgerEvent) -> Result<LedgerEvent> {
+        let mut recorded = self.append_batch(vec![event]).await?;
+        Ok(recorded.remove(0))
+    }
+
+    /// Append several events with consecutive sequence numbers
+    ///
+    /// The batch is written to the file in a single write and synced before
+    /// any of it is visible in memory, so a failed write records nothing
+    /// here. It is not crash-atomic, though: a crash mid-write can leave the
+    /// first events of the batch on disk, which are loaded on the next open
+    /// (only a torn last line is dropped).
+    pub async fn append_batch(&self, batch: Vec<NewLedgerEvent>) -> Result<Vec<LedgerEvent>> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Ledger".to_string()).into());
+        }
+
+        let mut events = self.events.write().await;
+        let next = events.last().map(|e| e.sequence + 1).unwrap_or(0);
+        let recorded_at = now_millis();
+
+        let recorded: Vec<LedgerEvent> = batch
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| LedgerEvent {
+                sequence: next + i as u64,
+                recorded_at,
+                event,
+            })
+            .collect();
+
+        if let Some(path) = &self.file {
+            Self::write_lines(path, &recorded).await?;
+        }
+
+        events.extend(recorded.iter().cloned());
+        Ok(recorded)
+    }
+
+    /// Append events to the ledger file and flush it
+    async fn write_lines(path: &Path, events: &[LedgerEvent]) -> Result<()> {
+        let mut lines = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut lines, event)?;
+            lines.push(b'\n');
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(&lines).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Bring back recorded events stored elsewhere (e.g. in a database)
+    ///
+    /// Events keep their sequence numbers, so they must continue this
+    /// ledger's history: those not after its last event must already be in
+    /// it, unchanged, and are skipped. Anything else (e.g. another
+    /// installation's backup restored into a ledger with events of its own)
+    /// is refused with nothing added. Returns how many were added.
+    pub async fn restore(&self, mut restored: Vec<LedgerEvent>) -> Result<usize> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Ledger".to_string()).into());
+        }
+
+        let mut events = self.events.write().await;
+        let after = events.last().map(|e| e.sequence);
+        restored.sort_by_key(|e| e.sequence);
+        restored.dedup_by_key(|e| e.sequence);
+        let (known, added): (Vec<LedgerEvent>, Vec<LedgerEvent>) =
+            restored.into_iter().partition(|e| after.is_some_and(|last| e.sequence <= last));
+        for event in &known {
+            let existing = events.binary_search_by_key(&event.sequence, |e| e.sequence).ok().map(|i| &events[i]);
+            if existing != Some(event) {
+                return Err(StorageError::Conflict(format!(
+                    "restored ledger event {} differs from this ledger's; restore into an empty ledger",
+                    event.sequence
+                ))
+                .into());
+            }
+        }
+
+        if let (Some(path), false) = (&self.file, added.is_empty()) {
+            Self::write_lines(path, &added).await?;
+        }
+
+        let count = added.len();
+        events.extend(added);
+        Ok(count)
+    }
+
+    /// Remove every event of a purged tenant, returning how many were removed
+    ///
+    /// The file is replaced atomically, so a crash leaves either the old
+    /// events or the new ones.
+    pub async fn purge_tenant(&self, tenant_id: &str) -> Result<usize> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Ledger".to_string()).into());
+        }
+
+        let mut events = self.events.write().await;
+        let kept: Vec<LedgerEvent> = events.iter().filter(|e| e.event.tenant_id != tenant_id).cloned().collect();
+        let removed = events.len() - kept.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        if let Some(path) = &self.file {
+            Self::rewrite(path, &kept).await?;
+        }
+
+        *events = kept;
+        Ok(removed)
+    }
+
+    /// Replace the employee ID of a tenant's events from before `before`
+    /// with `redact(employee_id)`, returning the sequence numbers changed and
+    /// their new IDs
+    ///
+    /// Hours and accruals are kept, so totals still add up; they are just no
+    /// longer attributable to anyone. IDs starting with
+    /// [`REDACTED_EMPLOYEE_PREFIX`] are taken to be redacted already. The
+    /// file is replaced atomically.
+    pub async fn redact_employees(
+        &self,
+        tenant_id: &str,
+        before: Date,
+        redact: impl Fn(&str) -> String,
+    ) -> Result<Vec<(u64, String)>> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Ledger".to_string()).into());
+        }
+
+        let mut events = self.events.write().await;
+        let mut redacted = events.clone();
+        let mut changed = Vec::new();
+        for event in redacted.iter_mut().filter(|e| {
+            e.event.tenant_id == tenant_id
+                && e.event.work_date < before
+                && !e.event.employee_id.starts_with(REDACTED_EMPLOYEE_PREFIX)
+        }) {
+            event.event.employee_id = redact(&event.event.employee_id);
+            changed.push((event.sequence, event.event.employee_id.clone()));
+        }
+        if changed.is_empty() {
+            return Ok(changed);
+        }
+
+        if let Some(path) = &self.file {
+            Self::rewrite(path, &redacted).await?;
+        }
+
+        *events = redacted;
+        Ok(changed)
+    }
+
+    /// Replace the ledger file with `events`, atomically
+    async fn rewrite(path: &Path, events: &[LedgerEvent]) -> Result<()> {
+        let mut lines = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut lines, event)?;
+            lines.push(b'\n');
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, &lines).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    /// Every event, in sequence order
+    pub async fn events(&self) -> Vec<LedgerEvent> {
+        self.events.read().await.clone()
+    }
+
+    /// Sequence number of the last event, if any
+    pub async fn last_sequence(&self) -> Option<u64> {
+        self.events.read().await.last().map(|e| e.sequence)
+    }
+
+    /// All events for a tenant, in sequence order
+    pub async fn events_for_tenant(&self, tenant_id: &str) -> Vec<LedgerEvent> {
+        self.events
+            .read()
+            .await
+            .iter()
+            .filter(|e| e.event.tenant_id == tenant_id)
+            .cloned()
+            .collect()
+    }
+
+    /// All events for one employee of a tenant, in sequence order
+    pub async fn events_for_employee(&self, tenant_id: &str, employee_id: &str) -> Vec<LedgerEvent> {
+        self.events
+            .read()
+            .await
+            .iter()
+            .filter(|e| e.event.tenant_id == tenant_id && e.event.employee_id == employee_id)
+            .cloned()
+            .collect()
+    }
+
+    /// One page of a tenant's events, in sequence order
+    ///
+    /// The cursor is the sequence number of the previous page's last event.
+    pub async fn events_page(&self, tenant_id: &str, page: &PageRequest) -> Result<Page<LedgerEvent>> {
+        let events = self.events.read().await;
+        let events = events.iter().filter(|e| e.event.tenant_id == tenant_id).cloned();
+        Ok(Page::from_sorted(events, page, |e| e.sequence)?)
+    }
+
+    /// Number of recorded events
+    pub async fn len(&self) -> usize {
+        self.events.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.events.read().await.is_empty()
+    }
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accrued(tenant_id: &str, employee_id: &str, minutes_worked: u64) -> NewLedgerEvent {
+        NewLedgerEvent {
+            tenant_id: tenant_id.into(),
+            employee_id: employee_id.into(),
+            work_date: "2025-03-03".parse().unwrap(),
+            kind: LedgerEventKind::Accrued {
+                minutes_worked,
+                accrued_minutes: minutes_worked / 30,
+            },
+            policy_version: Some(1),
+            source: "test".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_and_query() {
+        let ledger = Ledger::new();
+        ledger.append(accrued("acme", "e1", 480)).await.unwrap();
+        let batch = ledger
+            .append_batch(vec![accrued("acme", "e2", 240), accrued("globex", "e1", 60)])
+            .await
+            .unwrap();
+
+        assert_eq!(batch[0].sequence, 1);
+        assert_eq!(batch[1].sequence, 2);
+        assert_eq!(ledger.len().await, 3);
+        assert_eq!(ledger.events_for_tenant("acme").await.len(), 2);
+        assert_eq!(ledger.events_for_employee("acme", "e1").await.len(), 1);
+        assert_eq!(ledger.events_for_employee("globex", "e1").await[0].event.tenant_id, "globex");
+
+        let first = ledger.events_page("acme", &PageRequest::first(1)).await.unwrap();
+        assert_eq!(first.items[0].sequence, 0);
+        let cursor = first.next_cursor.unwrap();
+        let second = ledger.events_page("acme", &PageRequest::after(cursor, 1)).await.unwrap();
+        assert_eq!(second.items[0].sequence, 1);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_file_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let ledger = Ledger::with_file(&path).unwrap();
+        ledger.append(accrued("acme", "e1", 480)).await.unwrap();
+        ledger.append(accrued("acme", "e1", 120)).await.unwrap();
+        drop(ledger);
+
+        let reopened = Ledger::with_file(&path).unwrap();
+        let events = reopened.events_for_employee("acme", "e1").await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].sequence, 1);
+
+        let next = reopened.append(accrued("acme", "e1", 60)).await.unwrap();
+        assert_eq!(next.sequence, 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_continues_history_only() {
+        let source = Ledger::new();
+        source.append_batch(vec![accrued("acme", "e1", 480), accrued("acme", "e1", 120)]).await.unwrap();
+        let backup = source.events().await;
+
+        let empty = Ledger::new();
+        assert_eq!(empty.restore(backup.clone()).await.unwrap(), 2);
+        assert_eq!(empty.restore(backup.clone()).await.unwrap(), 0);
+        assert_eq!(empty.events().await, backup);
+
+        // Events before the backup was taken are skipped, later ones added
+        let behind = Ledger::new();
+        behind.restore(backup[..1].to_vec()).await.unwrap();
+        assert_eq!(behind.restore(backup.clone()).await.unwrap(), 1);
+        assert_eq!(behind.events().await, backup);
+
+        // A ledger with its own history would lose or misnumber events
+        let populated = Ledger::new();
+        populated.append(accrued("globex", "e9", 60)).await.unwrap();
+        let err = populated.restore(backup).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<StorageError>(), Some(StorageError::Conflict(_))));
+        assert_eq!(populated.len().await, 1);
+        assert_eq!(populated.events().await[0].event.tenant_id, "globex");
+    }
+
+    #[tokio::test]
+    async fn test_purge_tenant_rewrites_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let ledger = Ledger::with_file(&path).unwrap();
+        ledger
+            .append_batch(vec![accrued("acme", "e1", 480), accrued("globex", "e1", 60), accrued("acme", "e2", 120)])
+            .await
+            .unwrap();
+        assert_eq!(ledger.purge_tenant("acme").await.unwrap(), 2);
+        assert_eq!(ledger.purge_tenant("acme").await.unwrap(), 0);
+
+        let reopened = Ledger::with_file(&path).unwrap();
+        let events = reopened.events_for_tenant("globex").await;
+        assert_eq!((reopened.len().await, events[0].sequence), (1, 1));
+        assert!(Ledger::snapshot(&path).unwrap().purge_tenant("globex").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redact_employees_before_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let ledger = Ledger::with_file(&path).unwrap();
+        let recent = NewLedgerEvent { work_date: "2025-06-02".parse().unwrap(), ..accrued("acme", "e1", 60) };
+        ledger
+            .append_batch(vec![accrued("acme", "e1", 480), accrued("globex", "e1", 60), recent])
+            .await
+            .unwrap();
+        let before = "2025-04-01".parse().unwrap();
+        let redact = |id: &str| format!("{}{}", REDACTED_EMPLOYEE_PREFIX, id.len());
+        assert_eq!(ledger.redact_employees("acme", before, redact).await.unwrap(), vec![(0, "redacted_2".to_string())]);
+        assert!(ledger.redact_employees("acme", before, redact).await.unwrap().is_empty());
+
+        let reopened = Ledger::with_file(&path).unwrap();
+        let redacted = reopened.events_for_employee("acme", "redacted_2").await;
+        assert_eq!(redacted[0].event.kind, accrued("acme", "e1", 480).kind);
+        assert_eq!(reopened.events_for_employee("acme", "e1").await.len(), 1);
+        assert_eq!(reopened.events_for_employee("globex", "e1").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_read_only_and_refreshes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let primary = Ledger::with_file(&path).unwrap();
+        primary.append(accrued("acme", "e1", 480)).await.unwrap();
+
+        let replica = Ledger::snapshot(&path).unwrap();
+        assert!(replica.is_read_only());
+        assert_eq!(replica.len().await, 1);
+        assert!(replica.append(accrued("acme", "e1", 60)).await.is_err());
+
+        primary.append(accrued("acme", "e2", 120)).await.unwrap();
+        assert_eq!(replica.len().await, 1);
+        replica.refresh().await.unwrap();
+        assert_eq!(replica.len().await, 2);
+
+        // A line the primary is still writing is skipped rather than failing the snapshot
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"sequence\":2,");
+        std::fs::write(&path, contents).unwrap();
+        replica.refresh().await.unwrap();
+        assert_eq!(replica.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_torn_final_line_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let ledger = Ledger::with_file(&path).unwrap();
+        ledger.append(accrued("acme", "e1", 480)).await.unwrap();
+        drop(ledger);
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        let intact = contents.len() as u64;
+        contents.push_str("{\"sequence\":1,");
+        std::fs::write(&path, contents).unwrap();
+
+        let reopened = Ledger::with_file(&path).unwrap();
+        assert_eq!(reopened.len().await, 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), intact);
+        assert_eq!(reopened.append(accrued("acme", "e1", 60)).await.unwrap().sequence, 1);
+        assert_eq!(Ledger::with_file(&path).unwrap().len().await, 2);
+
+        // A corrupt complete line is still an error
+        std::fs::write(&path, "not json\n").unwrap();
+        assert!(Ledger::with_file(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_copy_is_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let ledger = Ledger::with_file(&path).unwrap();
+        ledger.append(accrued("acme", "e1", 480)).await.unwrap();
+
+        let dry_run = ledger.dry_run().await;
+        assert!(dry_run.is_dry_run() && !ledger.is_dry_run());
+        let hypothetical = dry_run.append(accrued("acme", "e1", 120)).await.unwrap();
+        assert_eq!(hypothetical.sequence, 1);
+        assert_eq!(dry_run.len().await, 2);
+
+        assert_eq!(ledger.len().await, 1);
+        assert_eq!(Ledger::with_file(&path).unwrap().len().await, 1);
+
+        // Snapshots can be dry-run too
+        let snapshot = Ledger::snapshot(&path).unwrap().dry_run().await;
+        assert!(snapshot.append(accrued("acme", "e2", 60)).await.is_ok());
+    }
+}
@@ -8,27 +8,246 @@
 //! - **Deterministic Execution**: Fuel metering and memory limits ensure
 //!   predictable, reproducible module execution.
 //! - **Capability-Based Security**: Modules only have access to explicitly
-//!   granted capabilities.
+//!   granted capabilities, with validation rates tracked and denial spikes
+//!   raised as security alerts. Host functions are declared in one table,
+//!   and calls to ungranted ones trap and are audited.
 //! - **Ed25519 Signatures**: Cryptographic verification of module integrity.
-//! - **Audit Logging**: Tamper-evident append-only log of all operations.
-//! - **Supervision**: Erlang-inspired crash-restart supervision tree.
+//! - **Audit Logging**: Tamper-evident append-only log of all operations, with
+//!   memory-mapped queries over the persisted history.
+//! - **Supervision**: Erlang-inspired crash-restart supervision tree, with
+//!   restart budgets that persist across restarts, jittered backoff, and
+//!   circuit breaking for crash-looping modules.
+//! - **Cancellation**: Running invocations are cancelled by handle or ID,
+//!   interrupting the guest through wasmtime's epochs, and audited apart
+//!   from traps.
+//! - **Crash Forensics**: Encrypted dumps of guest memory and fuel state when
+//!   modules trap, referenced from the audit log.
+//! - **Invocation Archival**: Content-addressed input/output capture for replay.
+//! - **Deterministic Replay**: Re-execute recorded invocations and compare output hashes.
+//! - **Tenant Isolation**: Per-tenant policy, employees, and capability namespaces.
+//! - **Tenant Lifecycle**: Tenants are explicitly created, archived, and purged,
+//!   and audit entries written on a tenant's behalf are tagged with it.
+//! - **Fuel Accounting**: Fuel used per tenant and module, optionally priced,
+//!   with per-tenant fuel ceilings per time window.
+//! - **Policy History**: Versioned tenant policies with effective-date ranges.
+//! - **Accrual Ledger**: Append-only record of hours worked and sick time.
+//! - **SQLite Storage**: Tenants, rosters, policies, ledger events, and an
+//!   audit segment index kept in a migrated SQLite database across restarts,
+//!   with employee IDs and hour records optionally encrypted at rest.
+//! - **Backups**: Signed, versioned archives of tenants, the ledger, and the
+//!   audit log, for moving machines and record retention.
+//! - **Record Retention**: Employee data in ledger events and audit entries
+//!   is redacted after a per-tenant retention period, keeping the audit
+//!   chain verifiable.
+//! - **Audit Anchoring**: Periodic audit checkpoints recorded in a file or
+//!   with an RFC 3161 timestamp authority (feature `anchor-http`), proving
+//!   the log existed by a given time.
+//! - **Compliance Alerts**: Overdrawn balances, passed annual limits, and
+//!   module crash loops raise alerts, configured per tenant and delivered to
+//!   allow-listed webhooks.
+//! - **Stable Listings**: List APIs return items in a documented order
+//!   (sequence or ID) and page through them with cursors.
+//! - **Compliance Reports**: Annual per-employee summaries as JSON or PDF.
+//! - **Report Templates**: Per-tenant, versioned report layouts whose columns
+//!   and filters are sandboxed expressions evaluated under the fuel limit.
+//! - **Statutory Parameters**: Versioned per-jurisdiction statute data with
+//!   effective dates, bundled and updatable through signed rule packs.
+//! - **Module Catalog**: Install verified rule modules from a local directory,
+//!   keeping previous versions for rollback.
+//! - **Storage Maintenance**: Disk usage reports, size alerts, and vacuuming.
+//! - **Statistics History**: Hourly per-module fuel, invocation, and error
+//!   counts persisted with retention, hash-chained, and exportable.
+//! - **Resource Profiles**: Per-module normal fuel and memory ranges learned
+//!   from recent invocations, optionally enforced as anomaly limits.
+//! - **Result Caching**: Optional memoization of deterministic invocation
+//!   outputs, with a size limit, TTL, and hit statistics.
+//! - **Module Cache**: Precompiled modules cached on disk for faster startup, authenticated with a stored key.
+//! - **Usage Insights**: Opt-in, informational usage pattern analysis with explain traces.
+//! - **Pseudonymization**: Modules see stable per-tenant pseudonyms in place
+//!   of employee identifiers.
+//! - **Security Profiles**: Named development, staging, and production
+//!   settings selected with one value.
+//! - **Sandbox Profiles**: Strict, standard, and dev module limits selected
+//!   for the kernel or per module in its manifest.
+//! - **Configuration Files**: Execution limits, trusted keys, directories,
+//!   and the sandbox profile read from `esta-kernel.toml`, with limits
+//!   reloadable while the kernel runs.
+//! - **WASI Modules**: Preview 1 imports for modules compiled against WASI,
+//!   with file access limited to capability-granted directories and audited.
+//! - **Request Correlation**: Per-request correlation IDs attached to every
+//!   audit entry the request writes.
+//! - **User Errors**: Stable error codes with localized messages and remediation.
+//! - **Chaos Testing**: Seeded fault injection into invocations, host calls,
+//!   audit writes, and heartbeats (feature `chaos`).
+//! - **Runtime Abstraction**: The kernel drives modules through the
+//!   `WasmRuntime` and `WasmInstance` traits, implemented by each backend.
+//! - **ABI Versions**: Modules declare the ABI they were built for with an
+//!   `__abi_version` export, and incompatible modules are refused at load.
+//! - **Response Envelopes**: Modules report failures as `{ok, error, data}`
+//!   envelopes, surfaced as typed `ModuleReported` errors.
+//! - **Interpreter Backend**: Modules run in the wasmi interpreter instead of
+//!   wasmtime's JIT, for targets that forbid runtime code generation (feature
+//!   `interpreter`; build with `--no-default-features` to leave wasmtime and
+//!   its JIT out entirely).
+//! - **Service Mode**: The kernel's operations over an authenticated HTTP/JSON
+//!   API for headless deployments, authorized by API keys and capability
+//!   tokens and audited (feature `server`).
+//! - **Simulated Time**: `testing::TimeMachine` (feature `testing`) for
+//!   deterministic tests of timers, backoff, expiry, and retention.
+//! - **Output Roots**: Files callers name (reports, backups) are confined to
+//!   an app-owned directory, refusing absolute paths, `..`, and symlink escapes.
+//! - **Injected Clocks**: The capability manager and audit log read time
+//!   through a `Clock`; a `ManualClock` makes expiry, rate windows, and audit
+//!   timestamps exact in tests and replays.
 
+pub mod alerts;
+pub mod archive;
+pub mod backup;
+pub mod calendar;
+pub mod clock;
+#[cfg(feature = "kernel")]
+pub mod config;
+pub mod correlation;
+pub mod database;
+#[cfg(feature = "kernel")]
+pub mod catalog;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod error;
+pub mod forensics;
+pub mod insights;
+pub mod ledger;
+#[cfg(feature = "wasmtime")]
+pub mod module_cache;
+pub mod output_root;
+pub mod pagination;
+pub mod policy;
+pub mod profile;
+pub mod replay;
+pub mod report;
+pub mod resource_profile;
+pub mod result_cache;
+pub mod retention;
+pub mod runtime;
+pub mod sandbox;
 pub mod security;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "kernel")]
+pub mod stats_history;
+pub mod statutes;
+#[cfg(feature = "kernel")]
+pub mod storage;
 pub mod supervisor;
-
+pub mod tenant;
+pub mod tenant_usage;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod trap;
+pub mod user_errors;
 #[cfg(feature = "wasmtime")]
+pub mod wasi;
+
+#[cfg(feature = "kernel")]
 pub mod kernel;
 
-#[cfg(feature = "wasmtime")]
-pub use kernel::{Kernel, ModuleManifest, ExecutionConfig, KernelStatus};
+#[cfg(all(feature = "kernel", not(any(feature = "wasmtime", feature = "interpreter"))))]
+compile_error!("the `kernel` feature needs a backend: enable `wasmtime` or `interpreter`");
+
+#[cfg(feature = "kernel")]
+pub use kernel::{CancelHandle, ConfigReload, Invocation, Kernel, ModuleInfo, ModuleManifest, ExecutionConfig, ExecutionReport, InvocationQueueStatus, KernelStatus, ModuleHeartbeat, ModuleShutdown, ModuleStats, ModuleTrap, ModuleUnload, ShutdownSignal, SignatureStatus};
 
 pub use security::{
     SignatureVerifier, SignatureError,
-    CapabilityManager, CapabilityToken, CapabilityError, Capability as SecCapability,
-    AuditLog, AuditEvent, AuditEventType,
+    CapabilityManager, CapabilityMetricsConfig, CapabilityStats, CapabilityToken, CapabilityError,
+    Capability as SecCapability, InstanceNonce,
+    AuditLog, AuditEvent, AuditEventType, AuditFailurePolicy, AuditFilter, AuditHealth, AuditQuery, AuditSubscription, AuditWriter,
+    AuditExport, AuditExportError, ChainVerification, ExportCursor, MissedEntries, VerificationProgress,
+    ArchivedAuditStats, AuditSegmentReader, ExportVerification,
+    AnchorError, AnchorProvider, AnchorReceipt, AuditCheckpoint, FileAnchor,
+    MasterKey, SecretError, SecretStore, FieldCipher,
+    KeyRotation, TrustError, TrustStore, TrustedKey,
+    CapabilitySnapshot, SnapshotDiff,
+    CapabilityFixture, FixtureError, FixtureReport,
+    PseudonymMap, Pseudonymizer,
 };
 pub use security::capabilities::{CapabilityRight, ResourceType};
 
+pub use alerts::{Alert, AlertConfig, AlertDelivery, AlertError, AlertKind, AlertMonitor};
+
+pub use archive::{ArchiveConfig, ArchiveRef, InvocationArchive};
+
+pub use calendar::{Date, DateError, Weekday};
+
+pub use clock::{Clock, ManualClock, SystemClock};
+
+#[cfg(feature = "kernel")]
+pub use config::{ConfigError, ExecutionOverrides, KernelConfig};
+
+#[cfg(feature = "chaos")]
+pub use chaos::{Chaos, ChaosConfig, Fault, InjectedFault};
+
+#[cfg(feature = "kernel")]
+pub use catalog::{CatalogEntry, InstalledModule, ModuleCatalog, ModuleVerification, StoredVersion};
+
+pub use database::{AuditSegmentRecord, Database, RestoreReport};
+pub use backup::{BackupArchive, BackupSummary};
+
+pub use error::{KernelError, StorageError};
+
+pub use forensics::{CrashDump, CrashDumpConfig, CrashDumps};
+
+pub use insights::{EmployeeUsageInsights, UsageInsight, UsageInsightsReport};
+
+pub use ledger::{Ledger, LedgerEvent, LedgerEventKind, NewLedgerEvent};
+
+#[cfg(feature = "wasmtime")]
+pub use module_cache::{ModuleCache, MODULE_CACHE_KEY};
+
+pub use output_root::{OutputPathError, OutputRoot};
+
+pub use pagination::{Page, PageRequest, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+pub use policy::{PolicyFile, PolicyHistory, PolicyVersion, StoredTenant};
+
+pub use profile::{ProfileSettings, SecurityProfile, UnknownProfile};
+
+pub use replay::{InvocationRecord, ReplayOutcome, ReplayReport, ReplayedInvocation};
+
+pub use resource_profile::{ResourceLimits, ResourceProfile, ResourceProfileConfig, ResourceUsage};
+pub use result_cache::{ResultCacheConfig, ResultCacheStats};
+
+pub use retention::RetentionReport;
+
+pub use runtime::{ModuleError, WasmInstance, WasmRuntime};
+
+pub use sandbox::{SandboxLimits, SandboxProfile, UnknownSandbox};
+
+#[cfg(feature = "kernel")]
+pub use stats_history::{StatsExport, StatsHistory, StatsRecord, StatsSample};
+
+#[cfg(feature = "kernel")]
+pub use storage::{StorageArea, StorageLimits, StorageMaintenance, StorageUsage, StorageUsageReport, VacuumReport};
+
+pub use statutes::{rules_for, StatuteBook, StatuteError, StatuteFile, StatuteRules, StatuteVersion, StatutoryParameters};
+
+pub use report::liability::{AccountTotal, GlAccountMapping, LiabilityLine, LiabilityProvenance, LiabilityReport, WageRate};
+pub use report::template::{CustomReport, ReportTemplate, TemplateColumn, TemplateError, TemplateVersion};
+pub use report::{ComplianceReport, EmployeeSummary, Violation, ViolationKind};
+
+pub use tenant::{
+    CachedPolicy, Tenant, TenantError, TenantLifecycle, TenantPolicy, TenantProvisioning, TenantPurgeReport,
+    TenantRegistry, TenantStatus,
+};
+
+pub use tenant_usage::{ModuleUsage, TenantFuelConfig, TenantMeter, TenantUsage};
+
+pub use trap::{BacktraceFrame, TrapKind};
+
+pub use user_errors::{ErrorCode, Locale, UserError, UserFacing};
+
 pub use supervisor::{
-    Supervisor, ChildSpec, ChildStatus, RestartStrategy, EscalationLevel, SupervisorAction,
+    Supervisor, BackoffPolicy, ChildSpec, ChildState, ChildStatus, ChildTransition, CircuitBreaker, CircuitState, CrashHistory,
+    RestartStrategy, EscalationLevel, SupervisorAction, SupervisorTimer, TokioTimer, JitterSource, SystemJitter,
+    SeededJitter,
 };
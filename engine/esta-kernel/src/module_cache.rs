@@ -0,0 +1,240 @@
+//! Precompiled Module Cache
+//!
+//! Compiling every module on every launch slows application start. The cache
+//! keeps the output of `Engine::precompile_module` on disk, keyed by the
+//! module's checksum and a fingerprint of the engine's compilation settings,
+//! so a module is compiled once per engine configuration and deserialized on
+//! later launches.
+//!
+//! Each entry is a `<checksum>-<fingerprint>.cwasm` file with a `.mac` file
+//! holding an HMAC-SHA256 of the compiled code, the module's checksum, and
+//! the engine fingerprint, keyed by a secret kept in the secret store
+//! ([`MODULE_CACHE_KEY`]). Deserializing compiled code runs whatever it
+//! contains, so the tag is checked before an entry is loaded: only code this
+//! installation compiled from the module its manifest vouches for is loaded,
+//! even if someone else can write the cache directory. A missing, mismatched,
+//! or unloadable entry is replaced by compiling the module again. Without a
+//! secret store there is no key, and no cache.
+//!
+//! The engine fingerprint is the hash of an empty module precompiled by the
+//! engine, which embeds the wasmtime version and every compiler setting.
+//! The interpreter backend compiles nothing, so it only reports the cache's
+//! disk usage.
+#![cfg_attr(feature = "interpreter", allow(dead_code))]
+
+use crate::security::secrets::SecretStore;
+use anyhow::Result;
+use log::{info, warn};
+use ring::hmac;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use wasmtime::{Engine, Module};
+
+/// Name of the key authenticating cache entries in the secret store
+pub const MODULE_CACHE_KEY: &str = "module-cache-key";
+
+/// How a module was obtained from the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CacheLookup {
+    /// Deserialized from a valid entry
+    Hit,
+    /// No entry; compiled and cached
+    Miss,
+    /// The entry failed validation; compiled and replaced
+    Invalid,
+}
+
+/// On-disk cache of precompiled modules for one engine configuration
+pub struct ModuleCache {
+    dir: PathBuf,
+    engine_fingerprint: String,
+    key: hmac::Key,
+}
+
+impl ModuleCache {
+    /// Use a cache directory for modules compiled by the given engine,
+    /// authenticating entries with `key`
+    ///
+    /// The directory is created on first write.
+    pub fn open(dir: impl Into<PathBuf>, engine: &Engine, key: &[u8]) -> Result<Self> {
+        let reference = engine.precompile_module(b"(module)")?;
+        Ok(Self {
+            dir: dir.into(),
+            engine_fingerprint: hex::encode(Sha256::digest(&reference)),
+            key: hmac::Key::new(hmac::HMAC_SHA256, key),
+        })
+    }
+
+    /// [`Self::open`] with the store's key, generated on first use
+    pub fn from_store(dir: impl Into<PathBuf>, engine: &Engine, store: &mut SecretStore) -> Result<Self> {
+        Self::open(dir, engine, store.get_or_generate(MODULE_CACHE_KEY, 32)?.expose())
+    }
+
+    /// The cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, checksum: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}.cwasm", checksum, &self.engine_fingerprint[..16]))
+    }
+
+    /// What an entry's tag covers: the module it was compiled from, the
+    /// engine that compiled it, and the compiled code
+    fn signed_message(&self, checksum: &str, compiled: &[u8]) -> Vec<u8> {
+        let mut message = Vec::with_capacity(checksum.len() + self.engine_fingerprint.len() + compiled.len() + 2);
+        message.extend_from_slice(checksum.as_bytes());
+        message.push(0);
+        message.extend_from_slice(self.engine_fingerprint.as_bytes());
+        message.push(0);
+        message.extend_from_slice(compiled);
+        message
+    }
+
+    fn is_authentic(&self, checksum: &str, compiled: &[u8], tag: &str) -> bool {
+        hex::decode(tag.trim())
+            .is_ok_and(|tag| hmac::verify(&self.key, &self.signed_message(checksum, compiled), &tag).is_ok())
+    }
+
+    /// Load a module from the cache, compiling and caching it on a miss
+    ///
+    /// `checksum` must already have been verified against `module_bytes`
+    /// (and the manifest's signature, when signatures are required).
+    /// Failing to write the cache is logged and does not fail the load.
+    pub(crate) fn load(
+        &self,
+        engine: &Engine,
+        module_bytes: &[u8],
+        checksum: &str,
+    ) -> Result<(Module, CacheLookup)> {
+        if !checksum.chars().all(|c| c.is_ascii_hexdigit()) || checksum.is_empty() {
+            return Ok((Module::new(engine, module_bytes)?, CacheLookup::Miss));
+        }
+
+        let path = self.entry_path(checksum);
+        let tag_path = path.with_extension("mac");
+
+        let lookup = match (std::fs::read(&path), std::fs::read_to_string(&tag_path)) {
+            (Ok(compiled), Ok(tag)) if self.is_authentic(checksum, &compiled, &tag) => {
+                // SAFETY: the tag proves the entry was written by `write_entry`
+                // with this installation's key, from `precompile_module` output
+                // for this module by an engine of the same fingerprint.
+                match unsafe { Module::deserialize(engine, &compiled) } {
+                    Ok(module) => return Ok((module, CacheLookup::Hit)),
+                    Err(e) => {
+                        warn!("Discarding cached module {}: {}", path.display(), e);
+                        CacheLookup::Invalid
+                    }
+                }
+            }
+            (Ok(_), _) => {
+                warn!("Cached module {} failed authentication; recompiling", path.display());
+                CacheLookup::Invalid
+            }
+            (Err(_), _) => CacheLookup::Miss,
+        };
+
+        let compiled = engine.precompile_module(module_bytes)?;
+        // SAFETY: the bytes were just produced by this engine
+        let module = unsafe { Module::deserialize(engine, &compiled)? };
+
+        match self.write_entry(&path, &tag_path, checksum, &compiled) {
+            Ok(()) => info!("Cached compiled module at {}", path.display()),
+            Err(e) => warn!("Failed to cache compiled module at {}: {}", path.display(), e),
+        }
+        Ok((module, lookup))
+    }
+
+    fn write_entry(&self, path: &Path, tag_path: &Path, checksum: &str, compiled: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        // Write to a temp file first so a crash never leaves a partial entry;
+        // the tag is written last so an entry without one is a plain miss
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, compiled)?;
+        std::fs::rename(&tmp, path)?;
+        let tag = hmac::sign(&self.key, &self.signed_message(checksum, compiled));
+        std::fs::write(tag_path, hex::encode(tag.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAT: &str = r#"(module (func (export "answer") (result i32) (i32.const 42)))"#;
+    const KEY: &[u8] = &[7; 32];
+
+    fn checksum(bytes: &[u8]) -> String {
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    #[test]
+    fn test_compiled_once_then_loaded_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = Engine::default();
+        let cache = ModuleCache::open(dir.path(), &engine, KEY).unwrap();
+        let sum = checksum(WAT.as_bytes());
+
+        let (_, lookup) = cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+        assert_eq!(lookup, CacheLookup::Miss);
+        let (module, lookup) = cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+        assert_eq!(lookup, CacheLookup::Hit);
+        assert!(module.exports().any(|e| e.name() == "answer"));
+
+        // A differently configured engine gets its own entries
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let fueled = Engine::new(&config).unwrap();
+        let other = ModuleCache::open(dir.path(), &fueled, KEY).unwrap();
+        assert_eq!(other.load(&fueled, WAT.as_bytes(), &sum).unwrap().1, CacheLookup::Miss);
+    }
+
+    #[test]
+    fn test_damaged_entry_is_recompiled() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = Engine::default();
+        let cache = ModuleCache::open(dir.path(), &engine, KEY).unwrap();
+        let sum = checksum(WAT.as_bytes());
+        cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+
+        let path = cache.entry_path(&sum);
+        let mut compiled = std::fs::read(&path).unwrap();
+        let last = compiled.len() - 1;
+        compiled[last] ^= 0xff;
+        std::fs::write(&path, &compiled).unwrap();
+
+        let (module, lookup) = cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+        assert_eq!(lookup, CacheLookup::Invalid);
+        assert!(module.exports().any(|e| e.name() == "answer"));
+        assert_eq!(cache.load(&engine, WAT.as_bytes(), &sum).unwrap().1, CacheLookup::Hit);
+    }
+
+    #[test]
+    fn test_forged_entries_are_not_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let engine = Engine::default();
+        let cache = ModuleCache::open(dir.path(), &engine, KEY).unwrap();
+        let sum = checksum(WAT.as_bytes());
+        cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+
+        // Replaced code with a recomputed plain hash, as the old sidecar allowed
+        let other = r#"(module (func (export "other")))"#;
+        let forged = engine.precompile_module(other.as_bytes()).unwrap();
+        let path = cache.entry_path(&sum);
+        std::fs::write(&path, &forged).unwrap();
+        std::fs::write(path.with_extension("mac"), checksum(&forged)).unwrap();
+        let (module, lookup) = cache.load(&engine, WAT.as_bytes(), &sum).unwrap();
+        assert_eq!(lookup, CacheLookup::Invalid);
+        assert!(module.exports().any(|e| e.name() == "answer"));
+
+        // An entry tagged with another key, or for another module, is not trusted
+        let stranger = ModuleCache::open(dir.path(), &engine, &[8; 32]).unwrap();
+        assert_eq!(stranger.load(&engine, WAT.as_bytes(), &sum).unwrap().1, CacheLookup::Invalid);
+        let compiled = std::fs::read(&path).unwrap();
+        let tag = std::fs::read_to_string(path.with_extension("mac")).unwrap();
+        assert!(stranger.is_authentic(&sum, &compiled, &tag));
+        assert!(!stranger.is_authentic(&checksum(other.as_bytes()), &compiled, &tag));
+    }
+}
@@ -0,0 +1,145 @@
+//! Output Roots
+//!
+//! Files a caller names (report exports, backups) are written only inside an
+//! app-owned directory. A caller gives a path relative to that directory;
+//! absolute paths and `..` are refused outright, and every directory on the
+//! way, and the file itself if it exists, must still resolve inside the root
+//! once symlinks are followed. Missing directories are created one at a time
+//! and checked as they are, so a symlink cannot lead the creation outside.
+
+use std::path::{Component, Path, PathBuf};
+
+use thiserror::Error;
+
+/// Why a caller's output path was refused
+#[derive(Debug, Error)]
+pub enum OutputPathError {
+    #[error("Output path {0:?} names no file")]
+    Empty(String),
+
+    #[error("Output path {0:?} must be relative to the output directory")]
+    Absolute(String),
+
+    #[error("Output path {0:?} must not contain '..'")]
+    ParentTraversal(String),
+
+    #[error("Output path {0:?} leads outside the output directory")]
+    Escapes(String),
+
+    #[error("Output directory unavailable: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A directory outputs are confined to
+#[derive(Debug, Clone)]
+pub struct OutputRoot {
+    /// Canonical path of the directory
+    root: PathBuf,
+}
+
+impl OutputRoot {
+    /// Confine outputs to `dir`, creating it if needed
+    pub fn new(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { root: dir.as_ref().canonicalize()? })
+    }
+
+    /// The directory, canonicalized
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The file `name` (relative to the root) names, with its parent directories created
+    pub fn resolve(&self, name: &str) -> Result<PathBuf, OutputPathError> {
+        let mut parts = Vec::new();
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(part) => parts.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => return Err(OutputPathError::ParentTraversal(name.to_string())),
+                Component::RootDir | Component::Prefix(_) => return Err(OutputPathError::Absolute(name.to_string())),
+            }
+        }
+        let Some((file, dirs)) = parts.split_last() else {
+            return Err(OutputPathError::Empty(name.to_string()));
+        };
+
+        let mut dir = self.root.clone();
+        for part in dirs {
+            dir.push(part);
+            match std::fs::create_dir(&dir) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+            dir = self.inside(&dir, name)?;
+        }
+
+        let path = dir.join(file);
+        if std::fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            // Dangling links would be written through to wherever they point
+            self.inside(&path, name)?;
+        }
+        Ok(path)
+    }
+
+    /// `path` with symlinks resolved, if that stays inside the root
+    fn inside(&self, path: &Path, name: &str) -> Result<PathBuf, OutputPathError> {
+        match path.canonicalize() {
+            Ok(real) if real.starts_with(&self.root) => Ok(real),
+            _ => Err(OutputPathError::Escapes(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_inside_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = OutputRoot::new(dir.path().join("reports")).unwrap();
+
+        let path = root.resolve("2025/acme.pdf").unwrap();
+        assert_eq!(path, root.root().join("2025").join("acme.pdf"));
+        assert!(path.parent().unwrap().is_dir());
+        assert_eq!(root.resolve("./acme.csv").unwrap(), root.root().join("acme.csv"));
+
+        let outside = dir.path().join("outside.pdf");
+        assert!(matches!(root.resolve(outside.to_str().unwrap()), Err(OutputPathError::Absolute(_))));
+        assert!(matches!(root.resolve("../outside.pdf"), Err(OutputPathError::ParentTraversal(_))));
+        assert!(matches!(root.resolve("2025/../../outside.pdf"), Err(OutputPathError::ParentTraversal(_))));
+        assert!(matches!(root.resolve(""), Err(OutputPathError::Empty(_))));
+        assert!(matches!(root.resolve("."), Err(OutputPathError::Empty(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_cannot_escape() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = OutputRoot::new(dir.path().join("reports")).unwrap();
+        let elsewhere = dir.path().join("elsewhere");
+        std::fs::create_dir(&elsewhere).unwrap();
+
+        // A linked directory, even one not yet containing the file
+        symlink(&elsewhere, root.root().join("linked")).unwrap();
+        assert!(matches!(root.resolve("linked/acme.pdf"), Err(OutputPathError::Escapes(_))));
+        assert!(matches!(root.resolve("linked/new/acme.pdf"), Err(OutputPathError::Escapes(_))));
+        assert!(!elsewhere.join("new").exists());
+
+        // A linked file, existing or dangling
+        std::fs::write(elsewhere.join("passwd"), "").unwrap();
+        symlink(elsewhere.join("passwd"), root.root().join("existing.pdf")).unwrap();
+        symlink(elsewhere.join("missing"), root.root().join("dangling.pdf")).unwrap();
+        assert!(matches!(root.resolve("existing.pdf"), Err(OutputPathError::Escapes(_))));
+        assert!(matches!(root.resolve("dangling.pdf"), Err(OutputPathError::Escapes(_))));
+
+        // Links that stay inside the root are fine
+        std::fs::create_dir(root.root().join("2025")).unwrap();
+        symlink(root.root().join("2025"), root.root().join("current")).unwrap();
+        assert_eq!(root.resolve("current/acme.pdf").unwrap(), root.root().join("2025").join("acme.pdf"));
+    }
+}
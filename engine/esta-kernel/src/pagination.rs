@@ -0,0 +1,160 @@
+//! Ordering and Pagination of List APIs
+//!
+//! Every list API returns its items in a documented total order, never in
+//! the incidental order of a hash map: audit entries and ledger events by
+//! sequence number, capabilities by ID, tenants and employees by ID. Two
+//! listings of the same state are therefore identical, and exports diff
+//! cleanly.
+//!
+//! Listings are also offered a page at a time. A page's `next_cursor` is the
+//! key of its last item, and the following page holds the items after that
+//! key. Because the cursor names a position in the order rather than an
+//! offset, items added or removed elsewhere never shift a later page: nothing
+//! is repeated or skipped. `next_cursor` is `None` on the last page.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::KernelError;
+
+/// Items per page when a request does not say
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Most items one page may hold
+pub const MAX_PAGE_SIZE: usize = 1_000;
+
+/// Which page of a listing to return
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// `next_cursor` of the previous page; `None` for the first page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Items per page (default [`DEFAULT_PAGE_SIZE`], at most [`MAX_PAGE_SIZE`])
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl PageRequest {
+    /// The first page of `limit` items
+    pub fn first(limit: usize) -> Self {
+        Self { cursor: None, limit: Some(limit) }
+    }
+
+    /// The page of `limit` items after a cursor
+    pub fn after(cursor: impl Into<String>, limit: usize) -> Self {
+        Self { cursor: Some(cursor.into()), limit: Some(limit) }
+    }
+
+    /// Items per page, within bounds
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// The cursor parsed as the listing's key
+    pub fn cursor_key<K: FromStr>(&self) -> Result<Option<K>, KernelError> {
+        self.cursor
+            .as_deref()
+            .map(|cursor| {
+                cursor
+                    .parse()
+                    .map_err(|_| KernelError::InvalidRequest(format!("invalid page cursor {:?}", cursor)))
+            })
+            .transpose()
+    }
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page; `None` when this is the last
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Page through items sorted by a unique key
+    pub fn from_sorted<K>(
+        items: impl IntoIterator<Item = T>,
+        request: &PageRequest,
+        key: impl Fn(&T) -> K,
+    ) -> Result<Self, KernelError>
+    where
+        K: Ord + Display + FromStr,
+    {
+        let after: Option<K> = request.cursor_key()?;
+        let limit = request.limit();
+        let items = items
+            .into_iter()
+            .filter(|item| after.as_ref().is_none_or(|after| key(item) > *after))
+            .take(limit + 1)
+            .collect();
+        Ok(Self::truncate(items, limit, key))
+    }
+
+    /// A page from up to `limit + 1` items following the cursor; the extra
+    /// item only tells whether another page follows
+    pub(crate) fn truncate<K: Display>(mut items: Vec<T>, limit: usize, key: impl Fn(&T) -> K) -> Self {
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| key(item).to_string())
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+
+    /// Apply a function to every item, keeping the cursor
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page { items: self.items.into_iter().map(f).collect(), next_cursor: self.next_cursor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_cover_every_item_once() {
+        let items: Vec<u64> = (1..=25).collect();
+        let mut request = PageRequest::first(10);
+        let mut seen = Vec::new();
+        loop {
+            let page = Page::from_sorted(items.iter().copied(), &request, |n| *n).unwrap();
+            assert!(page.items.len() <= 10);
+            seen.extend(page.items);
+            match page.next_cursor {
+                Some(cursor) => request = PageRequest::after(cursor, 10),
+                None => break,
+            }
+        }
+        assert_eq!(seen, items);
+
+        // An exact multiple of the limit has no empty trailing page
+        let page = Page::from_sorted(1..=10u64, &PageRequest::first(10), |n| *n).unwrap();
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_cursor_survives_changes_before_it() {
+        let first = Page::from_sorted(["a", "c", "e", "g"].map(String::from), &PageRequest::first(2), Clone::clone).unwrap();
+        assert_eq!(first.next_cursor.as_deref(), Some("c"));
+
+        // "b" is added and "a" removed before the next page is read
+        let request = PageRequest::after("c", 2);
+        let second = Page::from_sorted(["b", "c", "e", "g"].map(String::from), &request, Clone::clone).unwrap();
+        assert_eq!(second.items, ["e", "g"]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[test]
+    fn test_request_bounds() {
+        assert_eq!(PageRequest::default().limit(), DEFAULT_PAGE_SIZE);
+        assert_eq!(PageRequest::first(0).limit(), 1);
+        assert_eq!(PageRequest::first(1_000_000).limit(), MAX_PAGE_SIZE);
+
+        let err = Page::from_sorted(1..=3u64, &PageRequest::after("x", 2), |n| *n).unwrap_err();
+        assert!(matches!(err, KernelError::InvalidRequest(_)));
+    }
+}
@@ -0,0 +1,273 @@
+//! Versioned Tenant Policy History
+//!
+//! Employer policies change over time (an employer grows past the small
+//! employer threshold, adopts a more generous rate, ...). Accrual for a given
+//! work date must use the rules that were in force on that date, and auditors
+//! must be able to see every rule set that was ever in force.
+//!
+//! Each tenant's policies form an append-only history of versions. A version
+//! is effective from its `effective_from` date (inclusive) until the next
+//! version's start date (exclusive). Versions are never edited or removed;
+//! a correction is a new version.
+//!
+//! Histories can be persisted to a JSON file so they survive restarts. The
+//! file also records each tenant's lifecycle (see `tenant`) and saved report
+//! templates (see `report::template`), so it holds every tenant that has not
+//! been purged, with or without a policy.
+
+use crate::calendar::Date;
+use crate::report::template::ReportTemplates;
+use crate::tenant::{TenantError, TenantLifecycle, TenantPolicy, TenantResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// One version of a tenant's policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyVersion {
+    /// Version number, starting at 1
+    pub version: u32,
+    /// First day this version applies
+    pub effective_from: Date,
+    /// First day this version no longer applies (None while current)
+    pub effective_to: Option<Date>,
+    /// When this version was recorded (ms since Unix epoch)
+    pub recorded_at: u64,
+    /// The policy values
+    pub policy: TenantPolicy,
+}
+
+impl PolicyVersion {
+    /// Check whether this version applies on the given date
+    pub fn applies_on(&self, date: Date) -> bool {
+        self.effective_from <= date && self.effective_to.is_none_or(|to| date < to)
+    }
+}
+
+/// Append-only policy history for a single tenant
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PolicyHistory {
+    versions: Vec<PolicyVersion>,
+}
+
+impl PolicyHistory {
+    /// Append a new version effective from the given date
+    ///
+    /// The new version must start after the current latest version; the
+    /// latest version's range is closed at the new start date.
+    pub fn append(
+        &mut self,
+        policy: TenantPolicy,
+        effective_from: Date,
+        recorded_at: u64,
+    ) -> TenantResult<&PolicyVersion> {
+        policy.validate()?;
+
+        let version = match self.versions.last_mut() {
+            Some(latest) => {
+                if effective_from <= latest.effective_from {
+                    return Err(TenantError::InvalidPolicy(format!(
+                        "effective_from {} must be after {} (version {})",
+                        effective_from, latest.effective_from, latest.version
+                    )));
+                }
+                latest.effective_to = Some(effective_from);
+                latest.version + 1
+            }
+            None => 1,
+        };
+
+        self.versions.push(PolicyVersion {
+            version,
+            effective_from,
+            effective_to: None,
+            recorded_at,
+            policy,
+        });
+        Ok(self.versions.last().expect("version was just pushed"))
+    }
+
+    /// The version in force on the given date
+    pub fn at(&self, date: Date) -> Option<&PolicyVersion> {
+        self.versions.iter().rev().find(|v| v.applies_on(date))
+    }
+
+    /// The most recently recorded version
+    pub fn latest(&self) -> Option<&PolicyVersion> {
+        self.versions.last()
+    }
+
+    /// All versions, oldest first
+    pub fn versions(&self) -> &[PolicyVersion] {
+        &self.versions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
+    /// A history of versions already recorded elsewhere, oldest first
+    pub(crate) fn from_versions(versions: Vec<PolicyVersion>) -> Self {
+        Self { versions }
+    }
+}
+
+/// A tenant as stored in the policy file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredEntry")]
+pub struct StoredTenant {
+    pub lifecycle: TenantLifecycle,
+    pub policies: PolicyHistory,
+    #[serde(default, skip_serializing_if = "ReportTemplates::is_empty")]
+    pub report_templates: ReportTemplates,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub module_configs: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Tenant {
+        #[serde(default)]
+        lifecycle: TenantLifecycle,
+        policies: PolicyHistory,
+        #[serde(default)]
+        report_templates: ReportTemplates,
+        #[serde(default)]
+        module_configs: BTreeMap<String, serde_json::Value>,
+    },
+    /// Files written before lifecycles were recorded hold bare histories
+    History(PolicyHistory),
+}
+
+impl From<StoredEntry> for StoredTenant {
+    fn from(entry: StoredEntry) -> Self {
+        match entry {
+            StoredEntry::Tenant { lifecycle, policies, report_templates, module_configs } => {
+                Self { lifecycle, policies, report_templates, module_configs }
+            }
+            StoredEntry::History(policies) => Self { policies, ..Default::default() },
+        }
+    }
+}
+
+/// JSON file holding the lifecycles and policy histories of all tenants
+pub struct PolicyFile {
+    path: PathBuf,
+}
+
+impl PolicyFile {
+    /// Use the given file; it is created on first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Load all tenants, returning an empty map if the file does not exist
+    pub fn load(&self) -> TenantResult<BTreeMap<String, StoredTenant>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                TenantError::Persistence(format!("{}: {}", self.path.display(), e))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(TenantError::Persistence(format!("{}: {}", self.path.display(), e))),
+        }
+    }
+
+    /// Save all tenants, replacing the file atomically
+    pub async fn save(&self, tenants: &BTreeMap<String, StoredTenant>) -> TenantResult<()> {
+        let persist_err = |e: std::io::Error| {
+            TenantError::Persistence(format!("{}: {}", self.path.display(), e))
+        };
+
+        let json = serde_json::to_vec_pretty(tenants)
+            .map_err(|e| TenantError::Persistence(e.to_string()))?;
+
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(persist_err)?;
+        }
+
+        // Write to a temp file first so a crash never leaves a truncated history
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, json).await.map_err(persist_err)?;
+        tokio::fs::rename(&tmp, &self.path).await.map_err(persist_err)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(employer_size: &str) -> TenantPolicy {
+        TenantPolicy {
+            employer_size: employer_size.into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        }
+    }
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_version_lookup_by_date() {
+        let mut history = PolicyHistory::default();
+        history.append(policy("small"), date("2024-01-01"), 1).unwrap();
+        history.append(policy("large"), date("2025-02-21"), 2).unwrap();
+
+        assert!(history.at(date("2023-12-31")).is_none());
+        assert_eq!(history.at(date("2024-06-01")).unwrap().version, 1);
+        assert_eq!(history.at(date("2025-02-20")).unwrap().policy.employer_size, "small");
+        assert_eq!(history.at(date("2025-02-21")).unwrap().policy.employer_size, "large");
+        assert_eq!(history.at(date("2030-01-01")).unwrap().version, 2);
+
+        let first = &history.versions()[0];
+        assert_eq!(first.effective_to, Some(date("2025-02-21")));
+        assert_eq!(history.latest().unwrap().effective_to, None);
+    }
+
+    #[test]
+    fn test_history_is_append_only() {
+        let mut history = PolicyHistory::default();
+        history.append(policy("small"), date("2025-01-01"), 1).unwrap();
+
+        assert!(history.append(policy("large"), date("2025-01-01"), 2).is_err());
+        assert!(history.append(policy("large"), date("2024-06-01"), 2).is_err());
+        assert!(history.append(policy("medium"), date("2025-06-01"), 2).is_err());
+        assert_eq!(history.versions().len(), 1);
+        assert_eq!(history.latest().unwrap().effective_to, None);
+    }
+
+    #[tokio::test]
+    async fn test_policy_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = PolicyFile::new(dir.path().join("policies.json"));
+        assert!(file.load().unwrap().is_empty());
+
+        let mut history = PolicyHistory::default();
+        history.append(policy("small"), date("2025-01-01"), 1).unwrap();
+        let stored = StoredTenant { policies: history.clone(), ..Default::default() };
+        let tenants: BTreeMap<_, _> = [("acme".to_string(), stored.clone())].into_iter().collect();
+
+        file.save(&tenants).await.unwrap();
+        assert_eq!(file.load().unwrap(), tenants);
+
+        // Files from before lifecycles were recorded map tenants to bare histories
+        let legacy: BTreeMap<_, _> = [("acme".to_string(), history)].into_iter().collect();
+        std::fs::write(file.path(), serde_json::to_vec(&legacy).unwrap()).unwrap();
+        assert_eq!(file.load().unwrap(), tenants);
+
+        std::fs::write(file.path(), b"not json").unwrap();
+        assert!(matches!(file.load(), Err(TenantError::Persistence(_))));
+    }
+}
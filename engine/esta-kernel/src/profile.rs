@@ -0,0 +1,124 @@
+//! Security Profiles
+//!
+//! A profile names a complete set of security settings, so a deployment
+//! selects `production` with one value instead of getting several flags right
+//! individually. Applying a profile only ever tightens the kernel's
+//! configuration: a stray `require_signatures: false` cannot weaken
+//! production, and settings the profile requires but the kernel lacks (such
+//! as a persisted audit log) are reported by `Kernel::profile_violations`.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/// A named bundle of security settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityProfile {
+    /// Unsigned modules from any path; everything in memory
+    #[default]
+    Development,
+    /// Production security, except modules may load from any path for testing
+    Staging,
+    /// Signed modules from the module catalog only, with a persisted audit log
+    Production,
+}
+
+/// An unrecognized profile name
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown security profile {0} (expected development, staging, or production)")]
+pub struct UnknownProfile(pub String);
+
+impl std::str::FromStr for SecurityProfile {
+    type Err = UnknownProfile;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dev" | "development" => Ok(Self::Development),
+            "staging" => Ok(Self::Staging),
+            "prod" | "production" => Ok(Self::Production),
+            _ => Err(UnknownProfile(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for SecurityProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Settings a profile requires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProfileSettings {
+    /// Modules must carry a signature by a trusted key
+    pub require_signatures: bool,
+    /// Modules load only from the module catalog, never from an arbitrary manifest path
+    pub enforce_registry: bool,
+    /// The audit log must be persisted to disk
+    pub persist_audit: bool,
+    /// Manifests naming unknown capabilities are rejected, not loaded without them
+    pub strict_capabilities: bool,
+    /// Modules must declare their ABI version
+    pub require_abi_version: bool,
+    /// Longest an invocation may run (enforced at `host_yield`)
+    pub call_timeout: Option<Duration>,
+}
+
+impl SecurityProfile {
+    /// The profile's name, as accepted by `parse`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Staging => "staging",
+            Self::Production => "production",
+        }
+    }
+
+    /// The settings this profile requires
+    pub fn settings(&self) -> ProfileSettings {
+        match self {
+            Self::Development => ProfileSettings {
+                require_signatures: false,
+                enforce_registry: false,
+                persist_audit: false,
+                strict_capabilities: false,
+                require_abi_version: false,
+                call_timeout: None,
+            },
+            Self::Staging => ProfileSettings {
+                require_signatures: true,
+                enforce_registry: false,
+                persist_audit: true,
+                strict_capabilities: true,
+                require_abi_version: true,
+                call_timeout: Some(Duration::from_secs(30)),
+            },
+            Self::Production => ProfileSettings {
+                require_signatures: true,
+                enforce_registry: true,
+                persist_audit: true,
+                strict_capabilities: true,
+                require_abi_version: true,
+                call_timeout: Some(Duration::from_secs(30)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profiles() {
+        assert_eq!("prod".parse(), Ok(SecurityProfile::Production));
+        assert_eq!(" Staging ".parse(), Ok(SecurityProfile::Staging));
+        assert_eq!("dev".parse(), Ok(SecurityProfile::Development));
+        assert_eq!("lax".parse::<SecurityProfile>(), Err(UnknownProfile("lax".to_string())));
+        for profile in [SecurityProfile::Development, SecurityProfile::Staging, SecurityProfile::Production] {
+            assert_eq!(profile.as_str().parse(), Ok(profile));
+        }
+        assert!(SecurityProfile::Production.settings().enforce_registry);
+    }
+}
@@ -0,0 +1,98 @@
+//! Deterministic Replay
+//!
+//! Every successful invocation is recorded in the audit log as an
+//! `InvocationRecorded` event carrying the checksum of the module that ran,
+//! the SHA-256 of its input and output, and (optionally) the input itself.
+//! `Kernel::replay` re-executes recorded invocations against a module with the
+//! same checksum and compares output hashes, so a disputed calculation can be
+//! shown to be reproducible, or any divergence pinpointed.
+//!
+//! The input comes from the audit event when it carries the payload, and
+//! otherwise from the invocation archive by input hash. Replays update no
+//! module statistics and archive nothing; only a `ReplayCompleted` summary is
+//! written to the audit log.
+
+use serde::{Deserialize, Serialize};
+
+/// What the audit log records about a successful invocation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvocationRecord {
+    pub module_name: String,
+    pub function: String,
+    /// Checksum of the module version that ran
+    pub module_checksum: String,
+    pub tenant_id: Option<String>,
+    /// SHA-256 of the input bytes (hex)
+    pub input_hash: String,
+    /// SHA-256 of the output bytes (hex)
+    pub output_hash: String,
+    /// The input itself, when recording payloads is enabled and it fits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+}
+
+/// Result of replaying one recorded invocation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    /// The output hash matches the recorded one
+    Matched,
+    /// The module produced different output
+    Diverged { output_hash: String },
+    /// The module failed on the recorded input
+    Failed { error: String },
+    /// The input was neither recorded nor archived (or no longer matches its hash)
+    InputUnavailable,
+    /// No module with the recorded checksum is loaded or stored in the catalog
+    ModuleUnavailable,
+}
+
+/// A recorded invocation and the outcome of replaying it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayedInvocation {
+    /// Audit log sequence number of the `InvocationRecorded` event
+    pub sequence: u64,
+    pub module_name: String,
+    pub function: String,
+    pub module_checksum: String,
+    pub tenant_id: Option<String>,
+    pub input_hash: String,
+    /// Output hash recorded when the invocation originally ran
+    pub expected_output_hash: String,
+    pub outcome: ReplayOutcome,
+}
+
+/// Outcome of replaying a range of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    /// Recorded invocations in the range, in sequence order
+    pub invocations: Vec<ReplayedInvocation>,
+}
+
+impl ReplayReport {
+    fn count(&self, matches: impl Fn(&ReplayOutcome) -> bool) -> usize {
+        self.invocations.iter().filter(|i| matches(&i.outcome)).count()
+    }
+
+    /// Invocations whose output matched
+    pub fn matched(&self) -> usize {
+        self.count(|o| matches!(o, ReplayOutcome::Matched))
+    }
+
+    /// Invocations that produced different output or failed
+    pub fn diverged(&self) -> usize {
+        self.count(|o| matches!(o, ReplayOutcome::Diverged { .. } | ReplayOutcome::Failed { .. }))
+    }
+
+    /// Invocations that could not be re-executed
+    pub fn unavailable(&self) -> usize {
+        self.count(|o| matches!(o, ReplayOutcome::InputUnavailable | ReplayOutcome::ModuleUnavailable))
+    }
+
+    /// Whether every invocation in the range was replayed and matched
+    pub fn is_deterministic(&self) -> bool {
+        self.matched() == self.invocations.len()
+    }
+}
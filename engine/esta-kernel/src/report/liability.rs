@@ -0,0 +1,328 @@
+//! Accrued Sick-Leave Liability
+//!
+//! Unused sick time is a liability on the employer's books. This report
+//! values each employee's balance on a date at a wage rate the employer
+//! supplies (the kernel keeps no payroll data) and assigns it to a general
+//! ledger account, so accountants can book the accrual directly from the
+//! JSON or CSV export.
+//!
+//! Balances are replayed from the ledger exactly as in the compliance report,
+//! including prior years' carryover caps, and each line cites the ledger
+//! events and policy versions it was derived from. Amounts are integer cents;
+//! an hour of balance at a rate is rounded to the nearest cent. Negative
+//! balances (time used before it accrued) carry no liability.
+
+use super::{policy_on, summarize_employee};
+use crate::calendar::Date;
+use crate::ledger::{Ledger, LedgerEvent};
+use crate::policy::PolicyVersion;
+use crate::tenant::{TenantRegistry, TenantResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An employee's wage rate, supplied by the employer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WageRate {
+    pub employee_id: String,
+    pub hourly_rate_cents: u64,
+    /// Cost center selecting the GL account (see [`GlAccountMapping`])
+    #[serde(default)]
+    pub cost_center: Option<String>,
+}
+
+/// Which general ledger account each employee's liability is booked to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlAccountMapping {
+    /// Account for employees without a mapped cost center
+    pub default_account: String,
+    /// Account per cost center
+    #[serde(default)]
+    pub cost_centers: BTreeMap<String, String>,
+}
+
+impl Default for GlAccountMapping {
+    fn default() -> Self {
+        Self { default_account: "2150 Accrued Sick Leave".to_string(), cost_centers: BTreeMap::new() }
+    }
+}
+
+impl GlAccountMapping {
+    fn account_for(&self, cost_center: Option<&str>) -> &str {
+        cost_center
+            .and_then(|center| self.cost_centers.get(center))
+            .unwrap_or(&self.default_account)
+    }
+}
+
+/// Ledger events and policies a liability line was derived from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiabilityProvenance {
+    /// Ledger events replayed for the balance
+    pub ledger_events: usize,
+    /// Sequence of the latest of them
+    pub last_ledger_sequence: Option<u64>,
+    /// Policy versions the events were calculated under
+    pub policy_versions: Vec<u32>,
+    /// Policy version in force on the report date
+    pub policy_in_force: Option<u32>,
+}
+
+/// One employee's accrued liability
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiabilityLine {
+    pub employee_id: String,
+    pub cost_center: Option<String>,
+    pub gl_account: String,
+    /// Unused sick time on the report date
+    pub balance_minutes: i64,
+    /// `None` when no wage rate was supplied
+    pub hourly_rate_cents: Option<u64>,
+    pub liability_cents: Option<u64>,
+    pub provenance: LiabilityProvenance,
+}
+
+/// Liability booked to one GL account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountTotal {
+    pub gl_account: String,
+    pub employees: usize,
+    pub liability_cents: u64,
+}
+
+/// A tenant's accrued sick-leave liability on a date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiabilityReport {
+    pub tenant_id: String,
+    pub as_of: Date,
+    /// When the report was generated (ms since Unix epoch)
+    pub generated_at: u64,
+    /// Employees sorted by ID
+    pub lines: Vec<LiabilityLine>,
+    /// Sorted by account
+    pub accounts: Vec<AccountTotal>,
+    pub total_liability_cents: u64,
+    /// Employees with a balance but no wage rate, left out of the totals
+    pub missing_rates: Vec<String>,
+}
+
+/// Value of a balance at an hourly rate, to the nearest cent
+fn value_cents(balance_minutes: i64, hourly_rate_cents: u64) -> u64 {
+    let minutes = balance_minutes.max(0) as u128;
+    ((minutes * hourly_rate_cents as u128 + 30) / 60) as u64
+}
+
+/// Build a liability report from a tenant's roster, ledger events, and policy history
+///
+/// Events after `as_of` are ignored. Employees on the roster, in the ledger,
+/// or in `wages` all get a line.
+pub fn build_liability_report(
+    tenant_id: &str,
+    as_of: Date,
+    employees: &[String],
+    events: &[LedgerEvent],
+    policies: &[PolicyVersion],
+    wages: &[WageRate],
+    mapping: &GlAccountMapping,
+) -> LiabilityReport {
+    let wages: BTreeMap<&str, &WageRate> = wages.iter().map(|w| (w.employee_id.as_str(), w)).collect();
+    let mut by_employee: BTreeMap<&str, Vec<&LedgerEvent>> = employees
+        .iter()
+        .map(String::as_str)
+        .chain(wages.keys().copied())
+        .map(|id| (id, Vec::new()))
+        .collect();
+    for event in events.iter().filter(|e| e.event.tenant_id == tenant_id && e.event.work_date <= as_of) {
+        by_employee.entry(event.event.employee_id.as_str()).or_default().push(event);
+    }
+
+    let policy_in_force = policy_on(policies, as_of).map(|p| p.version);
+    let mut missing_rates = Vec::new();
+    let lines: Vec<LiabilityLine> = by_employee
+        .into_iter()
+        .map(|(employee_id, mut events)| {
+            events.sort_by_key(|e| (e.event.work_date, e.sequence));
+            let balance_minutes = summarize_employee(employee_id, as_of.year(), &events, policies).ending_balance_minutes;
+            let wage = wages.get(employee_id);
+            if wage.is_none() && balance_minutes > 0 {
+                missing_rates.push(employee_id.to_string());
+            }
+            let cost_center = wage.and_then(|w| w.cost_center.clone());
+            let policy_versions: BTreeSet<u32> = events.iter().filter_map(|e| e.event.policy_version).collect();
+            LiabilityLine {
+                employee_id: employee_id.to_string(),
+                gl_account: mapping.account_for(cost_center.as_deref()).to_string(),
+                cost_center,
+                balance_minutes,
+                hourly_rate_cents: wage.map(|w| w.hourly_rate_cents),
+                liability_cents: wage.map(|w| value_cents(balance_minutes, w.hourly_rate_cents)),
+                provenance: LiabilityProvenance {
+                    ledger_events: events.len(),
+                    last_ledger_sequence: events.iter().map(|e| e.sequence).max(),
+                    policy_versions: policy_versions.into_iter().collect(),
+                    policy_in_force,
+                },
+            }
+        })
+        .collect();
+
+    let mut accounts: BTreeMap<&str, AccountTotal> = BTreeMap::new();
+    for line in &lines {
+        let Some(liability) = line.liability_cents else { continue };
+        let total = accounts.entry(&line.gl_account).or_insert_with(|| AccountTotal {
+            gl_account: line.gl_account.clone(),
+            employees: 0,
+            liability_cents: 0,
+        });
+        total.employees += 1;
+        total.liability_cents += liability;
+    }
+    let accounts: Vec<AccountTotal> = accounts.into_values().collect();
+
+    LiabilityReport {
+        tenant_id: tenant_id.to_string(),
+        as_of,
+        generated_at: crate::clock::now_millis(),
+        total_liability_cents: accounts.iter().map(|a| a.liability_cents).sum(),
+        lines,
+        accounts,
+        missing_rates,
+    }
+}
+
+/// Generate a tenant's accrued liability on a date
+pub async fn generate_liability_report(
+    tenants: &TenantRegistry,
+    ledger: &Ledger,
+    tenant_id: &str,
+    as_of: Date,
+    wages: &[WageRate],
+    mapping: &GlAccountMapping,
+) -> TenantResult<LiabilityReport> {
+    let employees = tenants.list_employees(tenant_id).await?;
+    let policies = tenants.policy_history(tenant_id).await?;
+    let events = ledger.events_for_tenant(tenant_id).await;
+    Ok(build_liability_report(tenant_id, as_of, &employees, &events, &policies, wages, mapping))
+}
+
+impl LiabilityReport {
+    /// One CSV row per employee, for import into accounting software
+    ///
+    /// Hours and amounts are decimal (`12.50`); amounts are empty for
+    /// employees without a wage rate.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "employee_id,cost_center,gl_account,balance_hours,hourly_rate,liability,as_of,policy_versions,last_ledger_sequence\n",
+        );
+        let dollars = |cents: Option<u64>| cents.map_or(String::new(), |c| format!("{}.{:02}", c / 100, c % 100));
+        for line in &self.lines {
+            let versions: Vec<String> = line.provenance.policy_versions.iter().map(u32::to_string).collect();
+            let row = [
+                csv_field(&line.employee_id),
+                csv_field(line.cost_center.as_deref().unwrap_or_default()),
+                csv_field(&line.gl_account),
+                format!("{:.2}", line.balance_minutes as f64 / 60.0),
+                dollars(line.hourly_rate_cents),
+                dollars(line.liability_cents),
+                self.as_of.to_string(),
+                versions.join(";"),
+                line.provenance.last_ledger_sequence.map_or(String::new(), |s| s.to_string()),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Quote a CSV field if needed; fields starting with a formula character are
+/// prefixed with `'` so spreadsheets do not evaluate them
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) { format!("'{}", value) } else { value.to_string() };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{LedgerEventKind, NewLedgerEvent};
+    use crate::policy::PolicyHistory;
+    use crate::tenant::TenantPolicy;
+
+    fn event(sequence: u64, employee_id: &str, date: &str, kind: LedgerEventKind) -> LedgerEvent {
+        LedgerEvent {
+            sequence,
+            recorded_at: 0,
+            event: NewLedgerEvent {
+                tenant_id: "acme".into(),
+                employee_id: employee_id.into(),
+                work_date: date.parse().unwrap(),
+                kind,
+                policy_version: Some(1),
+                source: "test".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_liability_by_account() {
+        let mut history = PolicyHistory::default();
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        history.append(policy, "2024-01-01".parse().unwrap(), 0).unwrap();
+        let accrued = |minutes| LedgerEventKind::Accrued { minutes_worked: minutes * 30, accrued_minutes: minutes };
+        let events = vec![
+            event(1, "e1", "2025-01-10", accrued(600)),
+            event(2, "e1", "2025-02-10", LedgerEventKind::Used { minutes: 90 }),
+            event(3, "e2", "2025-01-10", accrued(100)),
+            event(4, "e2", "2025-01-11", LedgerEventKind::Used { minutes: 160 }),
+            event(5, "e3", "2025-03-01", accrued(45)),
+            event(6, "e1", "2025-12-01", accrued(999)),
+        ];
+        let wages = vec![
+            WageRate { employee_id: "e1".into(), hourly_rate_cents: 2_550, cost_center: Some("warehouse".into()) },
+            WageRate { employee_id: "e2".into(), hourly_rate_cents: 2_000, cost_center: None },
+        ];
+        let mapping = GlAccountMapping {
+            default_account: "2150".into(),
+            cost_centers: [("warehouse".to_string(), "2151".to_string())].into(),
+        };
+        let report =
+            build_liability_report("acme", "2025-06-30".parse().unwrap(), &[], &events, history.versions(), &wages, &mapping);
+
+        // 510 minutes at $25.50/hour; events after the report date are ignored
+        let e1 = &report.lines[0];
+        assert_eq!((e1.balance_minutes, e1.liability_cents), (510, Some(21_675)));
+        assert_eq!(e1.gl_account, "2151");
+        assert_eq!(e1.provenance.ledger_events, 2);
+        assert_eq!(e1.provenance.last_ledger_sequence, Some(2));
+        assert_eq!(e1.provenance.policy_in_force, Some(1));
+
+        // Overdrawn balances carry no liability; balances without a rate are reported, not valued
+        assert_eq!(report.lines[1].liability_cents, Some(0));
+        assert_eq!(report.lines[2].liability_cents, None);
+        assert_eq!(report.missing_rates, ["e3"]);
+
+        let accounts: Vec<_> = report.accounts.iter().map(|a| (a.gl_account.as_str(), a.liability_cents)).collect();
+        assert_eq!(accounts, [("2150", 0), ("2151", 21_675)]);
+        assert_eq!(report.total_liability_cents, 21_675);
+
+        let csv = report.to_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1], "e1,warehouse,2151,8.50,25.50,216.75,2025-06-30,1,2");
+        assert_eq!(rows[3], "e3,,2150,0.75,,,2025-06-30,1,5");
+        assert_eq!(csv_field("=SUM(A1)"), "'=SUM(A1)");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
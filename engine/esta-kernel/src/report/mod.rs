@@ -0,0 +1,332 @@
+//! Compliance Reporting
+//!
+//! This module aggregates a tenant's ledger into an annual compliance report:
+//! per employee, the sick time carried over into the year, accrued and used
+//! during it, the resulting balance and carryover, and any violations of the
+//! tenant's policy. Reports are plain data and can be rendered as JSON or PDF.
+//!
+//! Balances are replayed from the first ledger event so carryover into the
+//! report year reflects every earlier year's carryover cap.
+//!
+//! Employers can lay the report out their own way with saved templates (see
+//! `template`).
+
+pub mod liability;
+pub mod pdf;
+pub mod template;
+
+use crate::calendar::Date;
+use crate::ledger::{Ledger, LedgerEvent, LedgerEventKind};
+use crate::policy::PolicyVersion;
+use crate::tenant::{TenantRegistry, TenantResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub use pdf::render_pdf;
+pub use template::{apply_template, CustomReport, ReportTemplate, ReportTemplates, TemplateError, TemplateVersion};
+
+/// Kind of policy violation found in the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// Sick time was used beyond the available balance
+    UsageExceedsBalance,
+    /// Sick time used in the year exceeds the policy's annual usage limit
+    UsageExceedsAnnualLimit,
+    /// Hours were recorded on a date with no policy in force
+    NoPolicyInForce,
+}
+
+/// A policy violation for one employee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    pub employee_id: String,
+    pub kind: ViolationKind,
+    /// Day of the offending event, if tied to one
+    pub date: Option<Date>,
+    pub detail: String,
+}
+
+/// One employee's sick time for the report period
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmployeeSummary {
+    pub employee_id: String,
+    pub minutes_worked: u64,
+    /// Balance carried over from the previous year
+    pub carryover_in_minutes: i64,
+    pub accrued_minutes: u64,
+    pub used_minutes: u64,
+    /// Balance at the end of the period
+    pub ending_balance_minutes: i64,
+    /// Balance carried into the next year, after the carryover cap
+    pub carryover_out_minutes: i64,
+    pub violations: Vec<Violation>,
+}
+
+/// Totals across all employees
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportTotals {
+    pub employees: usize,
+    pub minutes_worked: u64,
+    pub accrued_minutes: u64,
+    pub used_minutes: u64,
+    pub violations: usize,
+}
+
+/// Annual compliance report for a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    pub tenant_id: String,
+    pub year: i32,
+    pub period_start: Date,
+    pub period_end: Date,
+    /// When the report was generated (ms since Unix epoch)
+    pub generated_at: u64,
+    /// Policy versions in force at any point during the period
+    pub policies: Vec<PolicyVersion>,
+    /// Employees sorted by ID
+    pub employees: Vec<EmployeeSummary>,
+    pub totals: ReportTotals,
+}
+
+fn policy_on(policies: &[PolicyVersion], date: Date) -> Option<&PolicyVersion> {
+    policies.iter().rev().find(|v| v.applies_on(date))
+}
+
+fn year_bounds(year: i32) -> (Date, Date) {
+    let start = Date::new(year, 1, 1).expect("January 1st is valid");
+    let end = Date::new(year, 12, 31).expect("December 31st is valid");
+    (start, end)
+}
+
+/// Replay one employee's events up to the end of the report year
+fn summarize_employee(
+    employee_id: &str,
+    year: i32,
+    events: &[&LedgerEvent],
+    policies: &[PolicyVersion],
+) -> EmployeeSummary {
+    let first_year = events.first().map(|e| e.event.work_date.year()).unwrap_or(year).min(year);
+    let mut carryover = 0i64;
+    let mut summary = EmployeeSummary::default();
+
+    for current in first_year..=year {
+        let (_, year_end) = year_bounds(current);
+        summary = EmployeeSummary {
+            employee_id: employee_id.to_string(),
+            carryover_in_minutes: carryover,
+            ..Default::default()
+        };
+        let mut balance = carryover;
+
+        for entry in events.iter().filter(|e| e.event.work_date.year() == current) {
+            let date = entry.event.work_date;
+            let mut violation = |kind, detail: String| {
+                summary.violations.push(Violation {
+                    employee_id: employee_id.to_string(),
+                    kind,
+                    date: Some(date),
+                    detail,
+                })
+            };
+
+            match entry.event.kind {
+                LedgerEventKind::Accrued { minutes_worked, accrued_minutes } => {
+                    if policy_on(policies, date).is_none() {
+                        violation(
+                            ViolationKind::NoPolicyInForce,
+                            format!("{} minutes worked with no policy in force", minutes_worked),
+                        );
+                    }
+                    summary.minutes_worked += minutes_worked;
+                    summary.accrued_minutes += accrued_minutes;
+                    balance += accrued_minutes as i64;
+                }
+                LedgerEventKind::Used { minutes } => {
+                    if minutes as i64 > balance {
+                        violation(
+                            ViolationKind::UsageExceedsBalance,
+                            format!("used {} minutes with {} available", minutes, balance.max(0)),
+                        );
+                    }
+                    summary.used_minutes += minutes;
+                    balance -= minutes as i64;
+                }
+            }
+        }
+
+        let policy = policy_on(policies, year_end);
+        if let Some(policy) = policy {
+            let limit = policy.policy.max_usage_hours as u64 * 60;
+            if summary.used_minutes > limit {
+                summary.violations.push(Violation {
+                    employee_id: employee_id.to_string(),
+                    kind: ViolationKind::UsageExceedsAnnualLimit,
+                    date: None,
+                    detail: format!(
+                        "used {} minutes; annual limit is {} minutes",
+                        summary.used_minutes, limit
+                    ),
+                });
+            }
+        }
+
+        let cap = policy.map_or(i64::MAX, |p| p.policy.max_carryover_hours as i64 * 60);
+        summary.ending_balance_minutes = balance;
+        summary.carryover_out_minutes = balance.clamp(0, cap);
+        carryover = summary.carryover_out_minutes;
+    }
+
+    summary
+}
+
+/// Build a compliance report from a tenant's roster, ledger events, and policy history
+///
+/// Events after the report year are ignored.
+pub fn build_compliance_report(
+    tenant_id: &str,
+    year: i32,
+    employees: &[String],
+    events: &[LedgerEvent],
+    policies: &[PolicyVersion],
+) -> ComplianceReport {
+    let (period_start, period_end) = year_bounds(year);
+
+    let mut by_employee: BTreeMap<&str, Vec<&LedgerEvent>> =
+        employees.iter().map(|id| (id.as_str(), Vec::new())).collect();
+    for event in events.iter().filter(|e| e.event.tenant_id == tenant_id && e.event.work_date <= period_end) {
+        by_employee.entry(event.event.employee_id.as_str()).or_default().push(event);
+    }
+
+    let employees: Vec<EmployeeSummary> = by_employee
+        .into_iter()
+        .map(|(employee_id, mut events)| {
+            events.sort_by_key(|e| (e.event.work_date, e.sequence));
+            summarize_employee(employee_id, year, &events, policies)
+        })
+        .collect();
+
+    let totals = ReportTotals {
+        employees: employees.len(),
+        minutes_worked: employees.iter().map(|e| e.minutes_worked).sum(),
+        accrued_minutes: employees.iter().map(|e| e.accrued_minutes).sum(),
+        used_minutes: employees.iter().map(|e| e.used_minutes).sum(),
+        violations: employees.iter().map(|e| e.violations.len()).sum(),
+    };
+
+    let policies = policies
+        .iter()
+        .filter(|v| v.effective_from <= period_end && v.effective_to.is_none_or(|to| to > period_start))
+        .cloned()
+        .collect();
+
+    ComplianceReport {
+        tenant_id: tenant_id.to_string(),
+        year,
+        period_start,
+        period_end,
+        generated_at: crate::clock::now_millis(),
+        policies,
+        employees,
+        totals,
+    }
+}
+
+/// Generate a tenant's compliance report for a calendar year
+pub async fn generate_compliance_report(
+    tenants: &TenantRegistry,
+    ledger: &Ledger,
+    tenant_id: &str,
+    year: i32,
+) -> TenantResult<ComplianceReport> {
+    let employees = tenants.list_employees(tenant_id).await?;
+    let policies = tenants.policy_history(tenant_id).await?;
+    let events = ledger.events_for_tenant(tenant_id).await;
+    Ok(build_compliance_report(tenant_id, year, &employees, &events, &policies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::NewLedgerEvent;
+    use crate::policy::PolicyHistory;
+    use crate::tenant::TenantPolicy;
+
+    fn policies() -> Vec<PolicyVersion> {
+        let mut history = PolicyHistory::default();
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 1,
+            max_usage_hours: 2,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        history.append(policy, "2024-01-01".parse().unwrap(), 0).unwrap();
+        history.versions().to_vec()
+    }
+
+    fn event(sequence: u64, employee_id: &str, date: &str, kind: LedgerEventKind) -> LedgerEvent {
+        LedgerEvent {
+            sequence,
+            recorded_at: 0,
+            event: NewLedgerEvent {
+                tenant_id: "acme".into(),
+                employee_id: employee_id.into(),
+                work_date: date.parse().unwrap(),
+                kind,
+                policy_version: Some(1),
+                source: "test".into(),
+            },
+        }
+    }
+
+    fn accrued(minutes: u64) -> LedgerEventKind {
+        LedgerEventKind::Accrued { minutes_worked: minutes * 30, accrued_minutes: minutes }
+    }
+
+    #[test]
+    fn test_carryover_is_capped() {
+        let events = vec![
+            event(0, "e1", "2024-03-01", accrued(100)),
+            event(1, "e1", "2025-03-01", accrued(30)),
+            event(2, "e1", "2025-04-01", LedgerEventKind::Used { minutes: 50 }),
+            event(3, "e1", "2026-01-05", accrued(999)),
+        ];
+        let report = build_compliance_report("acme", 2025, &[], &events, &policies());
+
+        let e1 = &report.employees[0];
+        assert_eq!(e1.carryover_in_minutes, 60);
+        assert_eq!((e1.accrued_minutes, e1.used_minutes), (30, 50));
+        assert_eq!(e1.ending_balance_minutes, 40);
+        assert_eq!(e1.carryover_out_minutes, 40);
+        assert!(e1.violations.is_empty());
+        assert_eq!(report.totals.accrued_minutes, 30);
+        assert_eq!(report.policies.len(), 1);
+    }
+
+    #[test]
+    fn test_violations() {
+        let events = vec![
+            event(0, "e1", "2025-01-02", accrued(60)),
+            event(1, "e1", "2025-01-03", LedgerEventKind::Used { minutes: 90 }),
+            event(2, "e1", "2025-06-01", accrued(120)),
+            event(3, "e1", "2025-06-02", LedgerEventKind::Used { minutes: 60 }),
+            event(4, "e2", "2023-06-01", accrued(10)),
+        ];
+        let report = build_compliance_report("acme", 2025, &["e3".to_string()], &events, &policies());
+
+        let ids: Vec<_> = report.employees.iter().map(|e| e.employee_id.as_str()).collect();
+        assert_eq!(ids, vec!["e1", "e2", "e3"]);
+
+        let kinds: Vec<_> = report.employees[0].violations.iter().map(|v| v.kind.clone()).collect();
+        assert_eq!(kinds, vec![ViolationKind::UsageExceedsBalance, ViolationKind::UsageExceedsAnnualLimit]);
+        assert_eq!(report.employees[0].ending_balance_minutes, 30);
+
+        // The 2023 accrual predates every policy, but it is outside the report year
+        assert!(report.employees[1].violations.is_empty());
+        assert_eq!(report.employees[1].carryover_in_minutes, 10);
+        assert_eq!(report.totals.violations, 2);
+    }
+}
@@ -0,0 +1,178 @@
+//! Minimal PDF Rendering for Compliance Reports
+//!
+//! Produces a plain, paginated text document in a built-in monospace font.
+//! This keeps report output dependency-free; the layout is a fixed-width table
+//! rather than a designed document.
+
+use super::ComplianceReport;
+
+/// Text lines per page
+const LINES_PER_PAGE: usize = 64;
+
+/// Format minutes as decimal hours (e.g. `90` -> `1.50`)
+fn hours(minutes: i64) -> String {
+    format!("{:.2}", minutes as f64 / 60.0)
+}
+
+/// Lay the report out as lines of text
+fn report_lines(report: &ComplianceReport) -> Vec<String> {
+    let mut lines = vec![
+        format!("ESTA Compliance Report - {}", report.year),
+        format!("Tenant: {}", report.tenant_id),
+        format!("Period: {} to {}", report.period_start, report.period_end),
+        String::new(),
+        "Policies in force:".to_string(),
+    ];
+
+    for version in &report.policies {
+        let until = version.effective_to.map(|d| d.to_string()).unwrap_or_else(|| "present".into());
+        lines.push(format!(
+            "  v{} {} to {}: {} employer, usage limit {}h, carryover cap {}h",
+            version.version,
+            version.effective_from,
+            until,
+            version.policy.employer_size,
+            version.policy.max_usage_hours,
+            version.policy.max_carryover_hours
+        ));
+    }
+    if report.policies.is_empty() {
+        lines.push("  (none)".to_string());
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "{:<20} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "Employee", "Worked", "Carry in", "Accrued", "Used", "Balance", "Carry out"
+    ));
+    lines.push("-".repeat(86));
+    for employee in &report.employees {
+        lines.push(format!(
+            "{:<20} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}",
+            employee.employee_id,
+            hours(employee.minutes_worked as i64),
+            hours(employee.carryover_in_minutes),
+            hours(employee.accrued_minutes as i64),
+            hours(employee.used_minutes as i64),
+            hours(employee.ending_balance_minutes),
+            hours(employee.carryover_out_minutes)
+        ));
+    }
+    lines.push("-".repeat(86));
+    lines.push(format!(
+        "{:<20} {:>9} {:>9} {:>9} {:>9}",
+        format!("Total ({})", report.totals.employees),
+        hours(report.totals.minutes_worked as i64),
+        "",
+        hours(report.totals.accrued_minutes as i64),
+        hours(report.totals.used_minutes as i64)
+    ));
+
+    lines.push(String::new());
+    lines.push(format!("Violations: {}", report.totals.violations));
+    for violation in report.employees.iter().flat_map(|e| &e.violations) {
+        let date = violation.date.map(|d| d.to_string()).unwrap_or_else(|| "year".into());
+        lines.push(format!(
+            "  {} {} {:?}: {}",
+            violation.employee_id, date, violation.kind, violation.detail
+        ));
+    }
+
+    lines
+}
+
+/// Escape text for a PDF string literal, replacing non-ASCII characters
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+/// Render a compliance report as a PDF document
+pub fn render_pdf(report: &ComplianceReport) -> Vec<u8> {
+    let lines = report_lines(report);
+    let pages: Vec<&[String]> = lines.chunks(LINES_PER_PAGE).collect();
+
+    // Objects: 1 catalog, 2 page tree, 3 font, then a page and content stream per page
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 4 + i * 2))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_string(),
+    ];
+
+    for (i, page) in pages.iter().enumerate() {
+        let mut content = String::from("BT /F1 8 Tf 11 TL 36 806 Td\n");
+        for line in page.iter() {
+            content.push_str(&format!("({}) Tj T*\n", escape(line)));
+        }
+        content.push_str("ET");
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 842] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            5 + i * 2
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+
+    let xref_offset = pdf.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        xref.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    xref.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+    pdf.extend_from_slice(xref.as_bytes());
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::build_compliance_report;
+
+    #[test]
+    fn test_render_pdf_structure() {
+        let employees: Vec<String> = (0..100).map(|i| format!("emp({})", i)).collect();
+        let report = build_compliance_report("acme", 2025, &employees, &[], &[]);
+        let pdf = render_pdf(&report);
+        let text = String::from_utf8(pdf.clone()).unwrap();
+
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("emp\\(0\\)"));
+
+        // The xref table must point at the start of each object
+        let startxref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(text[startxref..].starts_with("xref"));
+        let first_entry = text[startxref..].lines().nth(3).unwrap();
+        let offset: usize = first_entry[..10].parse().unwrap();
+        assert!(text[offset..].starts_with("1 0 obj"));
+    }
+}
@@ -0,0 +1,765 @@
+//! Report Templates
+//!
+//! Employers customize compliance reports without code changes by saving a
+//! template: a list of columns, each computed by a small expression over an
+//! employee's summary, and an optional filter selecting which employees
+//! appear. For example:
+//!
+//! ```text
+//! columns: "Employee" = employee_id
+//!          "Hours left" = hours(ending_balance_minutes)
+//!          "Flag" = if(violations > 0, "review", "")
+//! filter:  minutes_worked > 0 && !(employee_id == "contractor")
+//! ```
+//!
+//! The expression language is sandboxed by construction: it can only read
+//! the fields of the row being evaluated, has no loops, variables, or I/O,
+//! and every evaluated node burns one unit of fuel from a budget shared by
+//! the whole report, so a template runs under the same fuel limit as any
+//! module invocation. Arithmetic is checked; overflow and division by zero
+//! are errors rather than wrapped values.
+//!
+//! Templates are stored per tenant alongside its policy history, and like
+//! policies they are versioned: saving a template under an existing name
+//! records a new version, and earlier versions stay available so a past
+//! report can be reproduced exactly.
+
+use super::{ComplianceReport, EmployeeSummary};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Maximum length of a template name
+const MAX_NAME_LEN: usize = 64;
+
+/// Maximum number of columns in a template
+pub const MAX_COLUMNS: usize = 64;
+
+/// Maximum length of a single expression, in bytes
+pub const MAX_EXPRESSION_LEN: usize = 1024;
+
+/// Maximum nesting depth of an expression
+const MAX_DEPTH: usize = 32;
+
+/// Errors that can occur compiling or evaluating a template
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("Invalid template: {0}")]
+    Invalid(String),
+
+    #[error("Syntax error in `{expression}`: {message}")]
+    Syntax { expression: String, message: String },
+
+    #[error("Type error: {0}")]
+    Type(String),
+
+    #[error("Arithmetic error: {0}")]
+    Arithmetic(String),
+
+    #[error("Template ran out of fuel (limit {limit})")]
+    OutOfFuel { limit: u64 },
+}
+
+/// A value computed by a template expression
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Text(_) => "text",
+            Value::Bool(_) => "boolean",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Text(s) => f.write_str(s),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// Employee summary fields an expression can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    EmployeeId,
+    MinutesWorked,
+    CarryoverIn,
+    Accrued,
+    Used,
+    EndingBalance,
+    CarryoverOut,
+    Violations,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "employee_id" => Field::EmployeeId,
+            "minutes_worked" => Field::MinutesWorked,
+            "carryover_in_minutes" => Field::CarryoverIn,
+            "accrued_minutes" => Field::Accrued,
+            "used_minutes" => Field::Used,
+            "ending_balance_minutes" => Field::EndingBalance,
+            "carryover_out_minutes" => Field::CarryoverOut,
+            "violations" => Field::Violations,
+            _ => return None,
+        })
+    }
+
+    fn read(self, row: &EmployeeSummary) -> Value {
+        let saturate = |n: u64| i64::try_from(n).unwrap_or(i64::MAX);
+        match self {
+            Field::EmployeeId => Value::Text(row.employee_id.clone()),
+            Field::MinutesWorked => Value::Int(saturate(row.minutes_worked)),
+            Field::CarryoverIn => Value::Int(row.carryover_in_minutes),
+            Field::Accrued => Value::Int(saturate(row.accrued_minutes)),
+            Field::Used => Value::Int(saturate(row.used_minutes)),
+            Field::EndingBalance => Value::Int(row.ending_balance_minutes),
+            Field::CarryoverOut => Value::Int(row.carryover_out_minutes),
+            Field::Violations => Value::Int(row.violations.len() as i64),
+        }
+    }
+}
+
+/// Built-in functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    /// Minutes as hours with two decimals, e.g. `hours(90)` is `"1.50"`
+    Hours,
+    /// `if(condition, then, else)`; only the chosen branch is evaluated
+    If,
+    Min,
+    Max,
+    Abs,
+    /// Text of every argument joined together
+    Concat,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "hours" => Function::Hours,
+            "if" => Function::If,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "abs" => Function::Abs,
+            "concat" => Function::Concat,
+            _ => return None,
+        })
+    }
+
+    /// Whether the function accepts this many arguments
+    fn accepts(self, count: usize) -> bool {
+        match self {
+            Function::Hours | Function::Abs => count == 1,
+            Function::Min | Function::Max => count == 2,
+            Function::If => count == 3,
+            Function::Concat => count >= 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Literal(Value),
+    Field(Field),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Text(String),
+    Ident(String),
+    Op(&'static str),
+}
+
+/// Operators, longest first so `<=` is not read as `<` then `=`
+const OPERATORS: [&str; 17] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let n = rest[..end].parse().map_err(|_| format!("number {} is too large", &rest[..end]))?;
+            tokens.push(Token::Int(n));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' {
+            let end = rest[1..].find('"').ok_or("unterminated string")? + 1;
+            tokens.push(Token::Text(rest[1..end].to_string()));
+            rest = &rest[end + 1..];
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unexpected character '{}'", c))?;
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, lowest precedence first
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn parse(source: &str) -> Result<Expr, TemplateError> {
+        let syntax = |message: String| TemplateError::Syntax { expression: source.to_string(), message };
+        if source.len() > MAX_EXPRESSION_LEN {
+            return Err(syntax(format!("longer than {} bytes", MAX_EXPRESSION_LEN)));
+        }
+
+        let mut parser = Parser { tokens: tokenize(source).map_err(syntax)?, position: 0, depth: 0 };
+        let expr = parser.expression().map_err(syntax)?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(expr),
+            Some(token) => Err(syntax(format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<(), String> {
+        match self.peek_op(&[op]) {
+            Some(_) => {
+                self.position += 1;
+                Ok(())
+            }
+            None => Err(format!("expected '{}'", op)),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        self.nested(|parser| parser.binary(0))
+    }
+
+    /// Parse with the nesting depth raised, so chains of unary operators count too
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("nested deeper than {} levels", MAX_DEPTH));
+        }
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    /// Binary operators by precedence level, loosest binding first
+    const LEVELS: [&'static [&'static str]; 5] =
+        [&["||"], &["&&"], &["==", "!=", "<", "<=", ">", ">="], &["+", "-"], &["*", "/", "%"]];
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        let Some(ops) = Self::LEVELS.get(level) else {
+            return self.unary();
+        };
+
+        let mut left = self.binary(level + 1)?;
+        while let Some(op) = self.peek_op(ops) {
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            let op = match op {
+                "||" => BinaryOp::Or,
+                "&&" => BinaryOp::And,
+                "==" => BinaryOp::Eq,
+                "!=" => BinaryOp::Ne,
+                "<" => BinaryOp::Lt,
+                "<=" => BinaryOp::Le,
+                ">" => BinaryOp::Gt,
+                ">=" => BinaryOp::Ge,
+                "+" => BinaryOp::Add,
+                "-" => BinaryOp::Sub,
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.peek_op(&["!", "-"]) {
+            Some(op) => {
+                self.position += 1;
+                let operand = Box::new(self.nested(Self::unary)?);
+                Ok(if op == "!" { Expr::Not(operand) } else { Expr::Neg(operand) })
+            }
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("unexpected end of expression")?;
+        self.position += 1;
+
+        match token {
+            Token::Int(n) => Ok(Expr::Literal(Value::Int(n))),
+            Token::Text(s) => Ok(Expr::Literal(Value::Text(s))),
+            Token::Op("(") => {
+                let expr = self.expression()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) if self.peek_op(&["("]).is_some() => {
+                let function = Function::parse(&name).ok_or_else(|| format!("unknown function '{}'", name))?;
+                self.position += 1;
+                let mut args = Vec::new();
+                if self.peek_op(&[")"]).is_none() {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.peek_op(&[","]).is_none() {
+                            break;
+                        }
+                        self.position += 1;
+                    }
+                }
+                self.expect(")")?;
+                if !function.accepts(args.len()) {
+                    return Err(format!("{}() does not take {} arguments", name, args.len()));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                _ => Field::parse(&name).map(Expr::Field).ok_or_else(|| format!("unknown field '{}'", name)),
+            },
+            Token::Op(op) => Err(format!("unexpected '{}'", op)),
+        }
+    }
+}
+
+/// Fuel shared by every expression evaluated for one report
+struct Fuel {
+    limit: u64,
+    remaining: u64,
+}
+
+impl Fuel {
+    fn burn(&mut self) -> Result<(), TemplateError> {
+        self.remaining = self.remaining.checked_sub(1).ok_or(TemplateError::OutOfFuel { limit: self.limit })?;
+        Ok(())
+    }
+}
+
+fn int(value: Value) -> Result<i64, TemplateError> {
+    match value {
+        Value::Int(n) => Ok(n),
+        other => Err(TemplateError::Type(format!("expected an integer, found {}", other.type_name()))),
+    }
+}
+
+fn boolean(value: Value) -> Result<bool, TemplateError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(TemplateError::Type(format!("expected a boolean, found {}", other.type_name()))),
+    }
+}
+
+fn overflow() -> TemplateError {
+    TemplateError::Arithmetic("integer overflow".into())
+}
+
+fn eval(expr: &Expr, row: &EmployeeSummary, fuel: &mut Fuel) -> Result<Value, TemplateError> {
+    fuel.burn()?;
+
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Field(field) => Ok(field.read(row)),
+        Expr::Not(operand) => Ok(Value::Bool(!boolean(eval(operand, row, fuel)?)?)),
+        Expr::Neg(operand) => int(eval(operand, row, fuel)?)?.checked_neg().map(Value::Int).ok_or_else(overflow),
+        Expr::Binary(BinaryOp::And, left, right) => {
+            let result = boolean(eval(left, row, fuel)?)? && boolean(eval(right, row, fuel)?)?;
+            Ok(Value::Bool(result))
+        }
+        Expr::Binary(BinaryOp::Or, left, right) => {
+            let result = boolean(eval(left, row, fuel)?)? || boolean(eval(right, row, fuel)?)?;
+            Ok(Value::Bool(result))
+        }
+        Expr::Binary(op, left, right) => binary(*op, eval(left, row, fuel)?, eval(right, row, fuel)?),
+        Expr::Call(Function::If, args) => {
+            let branch = if boolean(eval(&args[0], row, fuel)?)? { &args[1] } else { &args[2] };
+            eval(branch, row, fuel)
+        }
+        Expr::Call(function, args) => {
+            let args = args.iter().map(|arg| eval(arg, row, fuel)).collect::<Result<Vec<_>, _>>()?;
+            call(*function, args)
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, TemplateError> {
+    use std::cmp::Ordering;
+
+    let ordering = |left: &Value, right: &Value| -> Result<Ordering, TemplateError> {
+        match (left, right) {
+            (Value::Int(a), Value::Int(b)) => Ok(a.cmp(b)),
+            (Value::Text(a), Value::Text(b)) => Ok(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Ok(a.cmp(b)),
+            _ => Err(TemplateError::Type(format!(
+                "cannot compare {} with {}",
+                left.type_name(),
+                right.type_name()
+            ))),
+        }
+    };
+
+    let result = match op {
+        BinaryOp::Eq => Value::Bool(ordering(&left, &right)? == Ordering::Equal),
+        BinaryOp::Ne => Value::Bool(ordering(&left, &right)? != Ordering::Equal),
+        BinaryOp::Lt => Value::Bool(ordering(&left, &right)? == Ordering::Less),
+        BinaryOp::Le => Value::Bool(ordering(&left, &right)? != Ordering::Greater),
+        BinaryOp::Gt => Value::Bool(ordering(&left, &right)? == Ordering::Greater),
+        BinaryOp::Ge => Value::Bool(ordering(&left, &right)? != Ordering::Less),
+        _ => {
+            let (a, b) = (int(left)?, int(right)?);
+            let n = match op {
+                BinaryOp::Add => a.checked_add(b).ok_or_else(overflow)?,
+                BinaryOp::Sub => a.checked_sub(b).ok_or_else(overflow)?,
+                BinaryOp::Mul => a.checked_mul(b).ok_or_else(overflow)?,
+                _ if b == 0 => return Err(TemplateError::Arithmetic("division by zero".into())),
+                BinaryOp::Div => a.checked_div(b).ok_or_else(overflow)?,
+                _ => a.checked_rem(b).ok_or_else(overflow)?,
+            };
+            Value::Int(n)
+        }
+    };
+    Ok(result)
+}
+
+fn call(function: Function, mut args: Vec<Value>) -> Result<Value, TemplateError> {
+    let result = match function {
+        Function::Hours => {
+            let minutes = int(args.remove(0))?;
+            let sign = if minutes < 0 { "-" } else { "" };
+            let cents = (minutes.unsigned_abs() as u128 * 100 + 30) / 60;
+            Value::Text(format!("{}{}.{:02}", sign, cents / 100, cents % 100))
+        }
+        Function::Min | Function::Max => {
+            let (a, b) = (int(args.remove(0))?, int(args.remove(0))?);
+            Value::Int(if function == Function::Min { a.min(b) } else { a.max(b) })
+        }
+        Function::Abs => int(args.remove(0))?.checked_abs().map(Value::Int).ok_or_else(overflow)?,
+        Function::Concat => Value::Text(args.iter().map(ToString::to_string).collect()),
+        Function::If => unreachable!("if() is evaluated lazily"),
+    };
+    Ok(result)
+}
+
+/// One column of a custom report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateColumn {
+    pub header: String,
+    /// Expression computing the column's value for each employee
+    pub expression: String,
+}
+
+/// An employer-defined layout for the compliance report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportTemplate {
+    /// Name the template is saved under (letters, numbers, dashes, underscores)
+    pub name: String,
+    pub columns: Vec<TemplateColumn>,
+    /// Boolean expression; employees for which it is false are left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+/// A template's parsed expressions
+struct CompiledTemplate {
+    columns: Vec<Expr>,
+    filter: Option<Expr>,
+}
+
+impl ReportTemplate {
+    /// Check the name, the column count, and that every expression parses
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        self.compile().map(|_| ())
+    }
+
+    fn compile(&self) -> Result<CompiledTemplate, TemplateError> {
+        let valid_name = !self.name.is_empty()
+            && self.name.len() <= MAX_NAME_LEN
+            && self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(TemplateError::Invalid(format!("invalid template name '{}'", self.name)));
+        }
+        if self.columns.is_empty() || self.columns.len() > MAX_COLUMNS {
+            return Err(TemplateError::Invalid(format!("a template needs 1 to {} columns", MAX_COLUMNS)));
+        }
+
+        Ok(CompiledTemplate {
+            columns: self.columns.iter().map(|c| Parser::parse(&c.expression)).collect::<Result<_, _>>()?,
+            filter: self.filter.as_deref().map(Parser::parse).transpose()?,
+        })
+    }
+}
+
+/// One saved version of a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateVersion {
+    /// Version number, starting at 1 for each name
+    pub version: u32,
+    /// When this version was saved (ms since Unix epoch)
+    pub recorded_at: u64,
+    pub template: ReportTemplate,
+}
+
+/// A tenant's saved templates, every version of each kept
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReportTemplates {
+    by_name: BTreeMap<String, Vec<TemplateVersion>>,
+}
+
+impl ReportTemplates {
+    /// Validate a template and record it as the next version of its name
+    pub fn record(&mut self, template: ReportTemplate, recorded_at: u64) -> Result<&TemplateVersion, TemplateError> {
+        template.validate()?;
+
+        let versions = self.by_name.entry(template.name.clone()).or_default();
+        let version = versions.last().map_or(1, |v| v.version + 1);
+        versions.push(TemplateVersion { version, recorded_at, template });
+        Ok(versions.last().expect("version was just pushed"))
+    }
+
+    /// A specific version of a template, or its latest version
+    pub fn get(&self, name: &str, version: Option<u32>) -> Option<&TemplateVersion> {
+        let versions = self.by_name.get(name)?;
+        match version {
+            Some(version) => versions.iter().find(|v| v.version == version),
+            None => versions.last(),
+        }
+    }
+
+    /// The latest version of every template, sorted by name
+    pub fn latest(&self) -> impl Iterator<Item = &TemplateVersion> {
+        self.by_name.values().filter_map(|versions| versions.last())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+/// A compliance report laid out by a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomReport {
+    pub tenant_id: String,
+    pub year: i32,
+    /// When the underlying report was generated (ms since Unix epoch)
+    pub generated_at: u64,
+    pub template: String,
+    pub template_version: u32,
+    pub headers: Vec<String>,
+    /// One row per employee passing the filter, in employee ID order
+    pub rows: Vec<Vec<Value>>,
+    /// Fuel the template's expressions consumed
+    pub fuel_consumed: u64,
+}
+
+/// Lay out a compliance report with a template, spending at most `fuel`
+pub fn apply_template(
+    report: &ComplianceReport,
+    saved: &TemplateVersion,
+    fuel: u64,
+) -> Result<CustomReport, TemplateError> {
+    let compiled = saved.template.compile()?;
+    let mut budget = Fuel { limit: fuel, remaining: fuel };
+    let mut rows = Vec::new();
+
+    for employee in &report.employees {
+        if let Some(filter) = &compiled.filter {
+            if !boolean(eval(filter, employee, &mut budget)?)? {
+                continue;
+            }
+        }
+        let row = compiled.columns.iter().map(|c| eval(c, employee, &mut budget)).collect::<Result<_, _>>()?;
+        rows.push(row);
+    }
+
+    Ok(CustomReport {
+        tenant_id: report.tenant_id.clone(),
+        year: report.year,
+        generated_at: report.generated_at,
+        template: saved.template.name.clone(),
+        template_version: saved.version,
+        headers: saved.template.columns.iter().map(|c| c.header.clone()).collect(),
+        rows,
+        fuel_consumed: fuel - budget.remaining,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{ReportTotals, Violation, ViolationKind};
+
+    fn employee(employee_id: &str, accrued_minutes: u64, violations: usize) -> EmployeeSummary {
+        EmployeeSummary {
+            employee_id: employee_id.into(),
+            minutes_worked: accrued_minutes * 30,
+            accrued_minutes,
+            ending_balance_minutes: accrued_minutes as i64 - 30,
+            violations: (0..violations)
+                .map(|_| Violation {
+                    employee_id: employee_id.into(),
+                    kind: ViolationKind::UsageExceedsBalance,
+                    date: None,
+                    detail: String::new(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn evaluate(source: &str) -> Result<Value, TemplateError> {
+        let expr = Parser::parse(source)?;
+        eval(&expr, &employee("e1", 90, 1), &mut Fuel { limit: 1000, remaining: 1000 })
+    }
+
+    fn template(filter: Option<&str>, columns: &[(&str, &str)]) -> TemplateVersion {
+        let columns = columns
+            .iter()
+            .map(|(header, expression)| TemplateColumn { header: header.to_string(), expression: expression.to_string() })
+            .collect();
+        TemplateVersion {
+            version: 1,
+            recorded_at: 0,
+            template: ReportTemplate { name: "payroll".into(), columns, filter: filter.map(String::from) },
+        }
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(evaluate("1 + 2 * 3 - -4").unwrap(), Value::Int(11));
+        assert_eq!(evaluate("(1 + 2) * 3 % 5").unwrap(), Value::Int(4));
+        assert_eq!(evaluate("accrued_minutes >= 90 && !(violations == 0)").unwrap(), Value::Bool(true));
+        assert_eq!(evaluate("hours(ending_balance_minutes)").unwrap(), Value::Text("1.00".into()));
+        assert_eq!(evaluate("hours(-45)").unwrap(), Value::Text("-0.75".into()));
+        assert_eq!(evaluate("concat(employee_id, \": \", max(3, min(7, 5)))").unwrap(), Value::Text("e1: 5".into()));
+        assert_eq!(evaluate("if(employee_id == \"e2\", 1 / 0, abs(-2))").unwrap(), Value::Int(2));
+
+        assert!(matches!(evaluate("1 / (accrued_minutes - 90)"), Err(TemplateError::Arithmetic(_))));
+        assert!(matches!(evaluate("9223372036854775807 + 1"), Err(TemplateError::Arithmetic(_))));
+        assert!(matches!(evaluate("employee_id + 1"), Err(TemplateError::Type(_))));
+        assert!(matches!(evaluate("1 < \"2\""), Err(TemplateError::Type(_))));
+
+        for invalid in ["", "1 +", "(1", "salary", "exec(1)", "min(1)", "1 2", "\"open", "a = 1", &"(".repeat(40)] {
+            assert!(matches!(Parser::parse(invalid), Err(TemplateError::Syntax { .. })), "{:?} parsed", invalid);
+        }
+        assert!(Parser::parse(&format!("{}1", "!".repeat(40))).is_err());
+        assert!(Parser::parse(&"1+".repeat(MAX_EXPRESSION_LEN)).is_err());
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let report = ComplianceReport {
+            tenant_id: "acme".into(),
+            year: 2025,
+            period_start: "2025-01-01".parse().unwrap(),
+            period_end: "2025-12-31".parse().unwrap(),
+            generated_at: 7,
+            policies: Vec::new(),
+            employees: vec![employee("e1", 90, 0), employee("e2", 0, 0), employee("e3", 150, 2)],
+            totals: ReportTotals::default(),
+        };
+        let saved = template(
+            Some("minutes_worked > 0"),
+            &[("Employee", "employee_id"), ("Hours", "hours(accrued_minutes)"), ("Review", "violations > 0")],
+        );
+
+        let custom = apply_template(&report, &saved, 1000).unwrap();
+        assert_eq!(custom.headers, vec!["Employee", "Hours", "Review"]);
+        assert_eq!(
+            serde_json::to_value(&custom.rows).unwrap(),
+            serde_json::json!([["e1", "1.50", false], ["e3", "2.50", true]])
+        );
+        // Filter: 3 nodes per employee; columns: 1 + 2 + 3 nodes per included employee
+        assert_eq!(custom.fuel_consumed, 3 * 3 + 2 * 6);
+
+        assert_eq!(
+            apply_template(&report, &saved, 20).unwrap_err(),
+            TemplateError::OutOfFuel { limit: 20 }
+        );
+        let not_boolean = template(Some("minutes_worked"), &[("Employee", "employee_id")]);
+        assert!(matches!(apply_template(&report, &not_boolean, 1000), Err(TemplateError::Type(_))));
+    }
+
+    #[test]
+    fn test_templates_are_versioned() {
+        let mut templates = ReportTemplates::default();
+        let first = template(None, &[("Employee", "employee_id")]).template;
+        let mut second = first.clone();
+        second.filter = Some("violations > 0".into());
+
+        assert_eq!(templates.record(first.clone(), 1).unwrap().version, 1);
+        assert_eq!(templates.record(second.clone(), 2).unwrap().version, 2);
+        assert_eq!(templates.get("payroll", None).unwrap().template, second);
+        assert_eq!(templates.get("payroll", Some(1)).unwrap().template, first);
+        assert!(templates.get("payroll", Some(3)).is_none());
+        assert_eq!(templates.latest().count(), 1);
+
+        let mut invalid = first.clone();
+        invalid.name = "pay roll".into();
+        assert!(matches!(templates.record(invalid, 3), Err(TemplateError::Invalid(_))));
+        let mut empty = first;
+        empty.columns.clear();
+        assert!(templates.record(empty, 3).is_err());
+        assert_eq!(templates.get("payroll", None).unwrap().version, 2);
+    }
+}
@@ -0,0 +1,155 @@
+//! Module Resource Profiles
+//!
+//! Every successful invocation adds the fuel it consumed and the size its
+//! linear memory reached to its module's profile, a window of the most
+//! recent samples kept in `ModuleStats`. The profile's 99th percentiles are
+//! the module's normal range.
+//!
+//! With enforcement on, an invocation may use at most a multiple (10× by
+//! default) of those percentiles once enough samples are in. Fuel is capped
+//! up front, so a runaway call stops there; memory is checked when the call
+//! returns, its output discarded if the limit was passed. Either way the call
+//! fails with `KernelError::ResourceAnomaly` and is audited. This is defense
+//! in depth against compromised or regressed modules on top of the fixed
+//! `ExecutionConfig` limits, which still apply.
+//!
+//! A relaunched or upgraded module keeps its predecessor's profile, so a new
+//! version that suddenly needs far more than the old one is caught.
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+/// Samples kept per module
+pub const DEFAULT_PROFILE_WINDOW: usize = 1_000;
+
+/// When and how tightly profiles limit invocations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceProfileConfig {
+    /// Fail invocations far outside their module's profile (off by default)
+    pub enforce: bool,
+    /// Limits are this multiple of the profile's 99th percentiles
+    pub multiplier: u64,
+    /// Samples needed before a profile is enforced
+    pub min_samples: usize,
+    /// Most recent samples kept per module
+    pub window: usize,
+}
+
+impl Default for ResourceProfileConfig {
+    fn default() -> Self {
+        Self {
+            enforce: false,
+            multiplier: 10,
+            min_samples: 100,
+            window: DEFAULT_PROFILE_WINDOW,
+        }
+    }
+}
+
+/// Resources one invocation used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub fuel: u64,
+    /// Size of linear memory when the call returned; memory never shrinks,
+    /// so this is also its peak
+    pub memory_bytes: usize,
+}
+
+/// Most one invocation may use under a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+/// Recent resource usage of one module
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResourceProfile {
+    /// Samples in the window
+    pub samples: usize,
+    pub p99_fuel: u64,
+    pub p99_memory_bytes: usize,
+    #[serde(skip)]
+    fuel: VecDeque<u64>,
+    #[serde(skip)]
+    memory: VecDeque<usize>,
+}
+
+impl ResourceProfile {
+    /// Add a successful invocation, dropping the oldest sample past `window`
+    pub fn observe(&mut self, usage: ResourceUsage, window: usize) {
+        self.fuel.push_back(usage.fuel);
+        self.memory.push_back(usage.memory_bytes);
+        while self.fuel.len() > window.max(1) {
+            self.fuel.pop_front();
+            self.memory.pop_front();
+        }
+        self.samples = self.fuel.len();
+        self.p99_fuel = p99(&self.fuel);
+        self.p99_memory_bytes = p99(&self.memory);
+    }
+
+    /// Limits for the next invocation, once the profile has enough samples
+    pub fn limits(&self, config: &ResourceProfileConfig) -> Option<ResourceLimits> {
+        if !config.enforce || self.samples < config.min_samples.max(1) {
+            return None;
+        }
+        let multiplier = config.multiplier.max(1);
+        Some(ResourceLimits {
+            fuel: self.p99_fuel.max(1).saturating_mul(multiplier),
+            memory_bytes: self.p99_memory_bytes.saturating_mul(multiplier as usize),
+        })
+    }
+}
+
+/// Nearest-rank 99th percentile
+fn p99<T: Copy + Ord + Default>(samples: &VecDeque<T>) -> T {
+    let mut sorted: Vec<T> = samples.iter().copied().collect();
+    sorted.sort_unstable();
+    match sorted.len() {
+        0 => T::default(),
+        n => sorted[(n * 99).div_ceil(100) - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(fuel: u64, memory_bytes: usize) -> ResourceUsage {
+        ResourceUsage { fuel, memory_bytes }
+    }
+
+    #[test]
+    fn test_profile_tracks_recent_p99() {
+        let mut profile = ResourceProfile::default();
+        for fuel in 1..=100 {
+            profile.observe(usage(fuel, 65_536), 200);
+        }
+        assert_eq!(profile.samples, 100);
+        assert_eq!(profile.p99_fuel, 99);
+        assert_eq!(profile.p99_memory_bytes, 65_536);
+
+        // Old samples fall out of the window
+        for _ in 0..200 {
+            profile.observe(usage(5, 131_072), 200);
+        }
+        assert_eq!(profile.samples, 200);
+        assert_eq!(profile.p99_fuel, 5);
+        assert_eq!(profile.p99_memory_bytes, 131_072);
+    }
+
+    #[test]
+    fn test_limits_need_enforcement_and_samples() {
+        let mut profile = ResourceProfile::default();
+        let config = ResourceProfileConfig { enforce: true, min_samples: 3, ..Default::default() };
+        profile.observe(usage(1_000, 65_536), config.window);
+        profile.observe(usage(1_200, 65_536), config.window);
+        assert_eq!(profile.limits(&config), None);
+
+        profile.observe(usage(900, 65_536), config.window);
+        assert_eq!(profile.limits(&config), Some(ResourceLimits { fuel: 12_000, memory_bytes: 655_360 }));
+        assert_eq!(profile.limits(&ResourceProfileConfig { enforce: false, ..config }), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Execution Result Cache
+//!
+//! Modules are deterministic: the same module, function, and input give the
+//! same output. Validating the same ledger again costs the same fuel again,
+//! so the kernel can remember successful outputs and answer repeated calls
+//! without running the guest.
+//!
+//! An entry is keyed by the module's checksum, the function, and the SHA-256
+//! of the input. Guests also read their tenant's policy and module
+//! configuration through host functions, so the key includes the tenant and
+//! the policy generation, and a configuration change clears the cache. A
+//! relaunched module with new code has a new checksum and misses. Modules
+//! granted `wasi` can read files and are never cached.
+//!
+//! A hit skips the guest entirely: no fuel is used, module statistics and
+//! tenant usage are not updated, nothing is archived or recorded for replay,
+//! and anything the guest would have logged is not logged. The audit log gets
+//! an `ExecutionCacheHit` entry in place of `ExecutionCompleted`.
+//!
+//! The cache is off by default. Entries expire after the TTL and the least
+//! recently used entry is evicted when it is full; callers can bypass it per
+//! invocation with [`Invocation::bypass_cache`](crate::Invocation::bypass_cache).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Default number of outputs kept
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000;
+
+/// Default time an output stays cached
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Default largest output cached, in bytes
+pub const DEFAULT_MAX_ENTRY_BYTES: usize = 64 * 1024;
+
+/// Whether and how much the kernel caches invocation outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResultCacheConfig {
+    /// Answer repeated invocations from the cache (off by default)
+    pub enabled: bool,
+    /// Outputs kept; the least recently used is evicted past this
+    pub max_entries: usize,
+    /// How long an output is served after it was computed
+    pub ttl: Duration,
+    /// Larger outputs are not cached
+    pub max_entry_bytes: usize,
+}
+
+impl Default for ResultCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            ttl: DEFAULT_TTL,
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+        }
+    }
+}
+
+/// What an output is cached under
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Checksum of the module version that computed the output
+    pub checksum: String,
+    pub function: String,
+    /// SHA-256 of the caller's input (hex)
+    pub input_hash: String,
+    pub tenant_id: Option<String>,
+    /// Policy generation of the tenant registry when the call started
+    pub policy_generation: u64,
+}
+
+impl CacheKey {
+    pub fn new(checksum: &str, function: &str, input: &[u8], tenant_id: Option<&str>, policy_generation: u64) -> Self {
+        Self {
+            checksum: checksum.to_string(),
+            function: function.to_string(),
+            input_hash: hex::encode(Sha256::digest(input)),
+            tenant_id: tenant_id.map(str::to_string),
+            policy_generation,
+        }
+    }
+}
+
+/// Cache counters since the kernel started
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ResultCacheStats {
+    /// Invocations answered from the cache
+    pub hits: u64,
+    /// Cacheable invocations that ran the guest
+    pub misses: u64,
+    /// Entries dropped to make room
+    pub evictions: u64,
+    /// Entries dropped because their TTL passed
+    pub expired: u64,
+    /// Entries held now
+    pub entries: usize,
+}
+
+struct Entry {
+    output: Vec<u8>,
+    stored_at: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<CacheKey, Entry>,
+    /// Use counter ordering entries for eviction
+    clock: u64,
+    stats: ResultCacheStats,
+}
+
+/// Outputs of recent successful invocations
+///
+/// The configuration is passed to each call, so a reloaded configuration
+/// applies to the entries already held.
+#[derive(Default)]
+pub struct ResultCache {
+    state: std::sync::Mutex<State>,
+}
+
+impl ResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached output for a key, counting a hit or a miss
+    pub fn get(&self, key: &CacheKey, config: &ResultCacheConfig, now: u64) -> Option<Vec<u8>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        let ttl = config.ttl.as_millis() as u64;
+        let found = match state.entries.get_mut(key) {
+            Some(entry) if now.saturating_sub(entry.stored_at) < ttl => {
+                entry.last_used = clock;
+                Some(entry.output.clone())
+            }
+            Some(_) => {
+                state.entries.remove(key);
+                state.stats.expired += 1;
+                None
+            }
+            None => None,
+        };
+        match found {
+            Some(_) => state.stats.hits += 1,
+            None => state.stats.misses += 1,
+        }
+        found
+    }
+
+    /// Remember an output, evicting the least recently used entries to make room
+    pub fn insert(&self, key: CacheKey, output: &[u8], config: &ResultCacheConfig, now: u64) {
+        if output.len() > config.max_entry_bytes || config.max_entries == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let last_used = state.clock;
+        if !state.entries.contains_key(&key) {
+            while state.entries.len() >= config.max_entries {
+                let Some(oldest) = state.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone()) else {
+                    break;
+                };
+                state.entries.remove(&oldest);
+                state.stats.evictions += 1;
+            }
+        }
+        state.entries.insert(key, Entry { output: output.to_vec(), stored_at: now, last_used });
+    }
+
+    /// Drop every entry; returns how many were dropped
+    pub fn clear(&self) -> usize {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let cleared = state.entries.len();
+        state.entries.clear();
+        cleared
+    }
+
+    pub fn stats(&self) -> ResultCacheStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        ResultCacheStats { entries: state.entries.len(), ..state.stats.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(input: &str) -> CacheKey {
+        CacheKey::new("abc", "validate_json", input.as_bytes(), Some("acme"), 1)
+    }
+
+    #[test]
+    fn test_hits_expire_after_ttl() {
+        let cache = ResultCache::new();
+        let config = ResultCacheConfig { enabled: true, ttl: Duration::from_millis(100), ..Default::default() };
+
+        assert_eq!(cache.get(&key("{}"), &config, 0), None);
+        cache.insert(key("{}"), b"{\"valid\":true}", &config, 0);
+        assert_eq!(cache.get(&key("{}"), &config, 50).as_deref(), Some(&b"{\"valid\":true}"[..]));
+
+        // Another tenant or policy generation is another entry
+        let other_tenant = CacheKey::new("abc", "validate_json", b"{}", Some("globex"), 1);
+        assert_eq!(cache.get(&other_tenant, &config, 50), None);
+        let new_policy = CacheKey::new("abc", "validate_json", b"{}", Some("acme"), 2);
+        assert_eq!(cache.get(&new_policy, &config, 50), None);
+
+        assert_eq!(cache.get(&key("{}"), &config, 100), None);
+        assert_eq!(
+            cache.stats(),
+            ResultCacheStats { hits: 1, misses: 4, evictions: 0, expired: 1, entries: 0 }
+        );
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ResultCache::new();
+        let config = ResultCacheConfig { enabled: true, max_entries: 2, max_entry_bytes: 4, ..Default::default() };
+
+        cache.insert(key("a"), b"1", &config, 0);
+        cache.insert(key("b"), b"2", &config, 0);
+        assert!(cache.get(&key("a"), &config, 0).is_some());
+        cache.insert(key("c"), b"3", &config, 0);
+        assert!(cache.get(&key("b"), &config, 0).is_none());
+        assert!(cache.get(&key("a"), &config, 0).is_some());
+
+        // Outputs over the size limit are not kept
+        cache.insert(key("d"), b"12345", &config, 0);
+        assert!(cache.get(&key("d"), &config, 0).is_none());
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.clear(), 2);
+    }
+}
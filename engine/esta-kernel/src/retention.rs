@@ -0,0 +1,64 @@
+//! Record Retention
+//!
+//! A tenant's policy may set `retention_days`, the days its records keep
+//! employee data. [`crate::Kernel::apply_retention`] redacts what is older:
+//!
+//! - Ledger events worked before the cutoff date have their employee ID
+//!   replaced with a keyed hash of it, starting with
+//!   [`crate::ledger::REDACTED_EMPLOYEE_PREFIX`]. One employee's history stays
+//!   distinct from another's without saying whose it is, and hours and
+//!   accruals stay, so yearly totals still add up.
+//! - Audit entries written on the tenant's behalf before the cutoff have
+//!   their event replaced with a `Redacted` marker. Entry hashes commit to a
+//!   hash of the event rather than the event itself, so the chain still
+//!   verifies. Entries written before that was the case are left as they are.
+//!
+//! Retention can be no shorter than [`MIN_RETENTION_DAYS`], the three years
+//! employers must keep sick time records.
+
+use crate::calendar::Date;
+use serde::{Deserialize, Serialize};
+
+/// Shortest retention period a policy may set (three years of records)
+pub const MIN_RETENTION_DAYS: u32 = 3 * 365;
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// What applying a tenant's retention period redacted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub tenant_id: String,
+    /// Records from before this time were redacted (ms since Unix epoch)
+    pub cutoff: u64,
+    /// Ledger events whose employee ID was redacted
+    pub ledger_events: usize,
+    /// Audit entries whose event was redacted
+    pub audit_entries: usize,
+    /// Audit entries past the cutoff written before they could be redacted
+    pub audit_entries_skipped: usize,
+}
+
+/// Start of the day `retention_days` before `now` (ms since Unix epoch)
+pub fn cutoff(now: u64, retention_days: u32) -> u64 {
+    let today = now / MILLIS_PER_DAY * MILLIS_PER_DAY;
+    today.saturating_sub(u64::from(retention_days) * MILLIS_PER_DAY)
+}
+
+/// The first work date kept in full under a cutoff
+pub fn cutoff_date(cutoff: u64) -> Date {
+    Date::from_unix_millis(cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff_is_start_of_day() {
+        let now = "2028-03-15".parse::<Date>().unwrap().days_since_epoch() as u64 * MILLIS_PER_DAY + 3_600_000;
+        let cutoff = cutoff(now, MIN_RETENTION_DAYS);
+        assert_eq!(cutoff % MILLIS_PER_DAY, 0);
+        assert_eq!(cutoff_date(cutoff), "2025-03-16".parse().unwrap());
+        assert_eq!(super::cutoff(1_000, MIN_RETENTION_DAYS), 0);
+    }
+}
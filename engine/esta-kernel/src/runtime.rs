@@ -0,0 +1,479 @@
+//! WebAssembly Runtime Abstraction
+//!
+//! The kernel reaches modules through two traits: [`WasmRuntime`] compiles
+//! module bytes and instantiates them around per-instance host state, and
+//! [`WasmInstance`] calls exports, meters fuel, and reads and writes linear
+//! memory. wasmtime is the default backend and wasmi the `interpreter`
+//! backend; the guest JSON ABI ([`call_json`]) is written once, against the
+//! traits, for both.
+//!
+//! Guests report failures through a response envelope,
+//! `{"ok": bool, "error": {"code", "message"} | null, "data": ... | null}`.
+//! [`call_json`] returns the `data` of a successful envelope and fails with
+//! [`KernelError::ModuleReported`] otherwise, so a request the guest could not
+//! serve never reaches callers as an empty result. Output that is not an
+//! envelope (older modules) is returned as is.
+//!
+//! The host owns both buffers of a call once the guest returns: it releases
+//! the result with the guest's `free_result(ptr)` export and its input with
+//! `dealloc(ptr, len)`, on success and failure alike, so an instance serving
+//! many calls does not leak linear memory. Modules without those exports are
+//! still called.
+//!
+//! Modules declare the ABI they were built against by exporting
+//! `__abi_version() -> i32`. The kernel calls it once at load and refuses
+//! versions outside [`SUPPORTED_ABI_VERSIONS`]; the number is raised
+//! whenever the host functions or the calling convention change in a way an
+//! older module would misread. Modules built before the convention have no
+//! such export and are taken to speak version 1, unless the kernel requires
+//! the export.
+//!
+//! Nothing here depends on a runtime, so code written against the traits is
+//! unit tested with a scripted backend, without the `wasmtime` feature.
+
+use std::future::Future;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::error::KernelError;
+use crate::resource_profile::ResourceUsage;
+
+/// Export through which a module declares its ABI version
+pub const ABI_VERSION_EXPORT: &str = "__abi_version";
+
+/// Import module name of WASI preview 1, which only the wasmtime backend
+/// provides (see `crate::wasi`)
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+/// ABI version this host implements
+pub const ABI_VERSION: u32 = 1;
+
+/// ABI versions this host can run
+pub const SUPPORTED_ABI_VERSIONS: RangeInclusive<u32> = 1..=ABI_VERSION;
+
+/// Compiles modules and instantiates them in fresh stores
+pub trait WasmRuntime: Send + Sync {
+    /// State the host functions of one instance see
+    type Host: Send;
+    /// A compiled module, instantiated any number of times
+    type Module: Send + Sync;
+    type Instance: WasmInstance<Host = Self::Host>;
+
+    /// Validate and compile module bytes (binary or text format)
+    fn compile(&self, bytes: &[u8]) -> Result<Self::Module>;
+
+    /// Whether a compiled module exports anything under `name`
+    fn has_export(&self, module: &Self::Module, name: &str) -> bool;
+
+    /// Imports of a compiled module, as (module, name) pairs
+    fn imports(&self, module: &Self::Module) -> Vec<(String, String)>;
+
+    /// Instantiate a module in a new store holding `host`, with `fuel` to spend
+    fn instantiate(
+        &self,
+        module: &Self::Module,
+        host: Self::Host,
+        fuel: u64,
+    ) -> impl Future<Output = Result<Self::Instance>> + Send;
+}
+
+/// A module instance and the store it lives in
+///
+/// Only `i32` values cross the kernel's ABI, so calls take and return those.
+pub trait WasmInstance: Send {
+    type Host;
+
+    /// Host state of this instance
+    fn host(&self) -> &Self::Host;
+
+    /// Whether the module exports a function of this name
+    fn has_function(&self, name: &str) -> bool;
+
+    /// Call an exported function
+    fn call(&mut self, name: &str, args: &[i32]) -> impl Future<Output = Result<Vec<i32>>> + Send;
+
+    /// Fuel spent since instantiation
+    fn fuel_consumed(&self) -> u64;
+
+    /// Whether the module exports its linear memory as `memory`
+    fn has_memory(&self) -> bool;
+
+    /// Current size of linear memory in bytes (0 without one)
+    fn memory_size(&self) -> usize;
+
+    /// Copy bytes out of linear memory
+    fn read_memory(&self, offset: usize, buf: &mut [u8]) -> Result<()>;
+
+    /// Copy bytes into linear memory
+    fn write_memory(&mut self, offset: usize, bytes: &[u8]) -> Result<()>;
+
+    /// Fuel and memory used so far
+    fn usage(&self) -> ResourceUsage {
+        ResourceUsage {
+            fuel: self.fuel_consumed(),
+            memory_bytes: self.memory_size(),
+        }
+    }
+}
+
+/// An error a guest reported in its response envelope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleError {
+    /// Guest-defined code, e.g. `INVALID_INPUT`, `INPUT_TOO_LARGE`, `INTERNAL`
+    pub code: String,
+    pub message: String,
+}
+
+/// The envelope; all three keys are required, so other output never matches
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope<'a> {
+    ok: bool,
+    #[serde(borrow)]
+    error: &'a RawValue,
+    #[serde(borrow)]
+    data: &'a RawValue,
+}
+
+/// Run one call through the guest JSON ABI
+///
+/// The input is copied into a buffer from the guest's `alloc(len) -> ptr`,
+/// then `function(ptr, len)` returns a pointer to a little-endian `u32`
+/// length followed by that many output bytes, or 0 when it rejects the
+/// input. WASI reactors have their `_initialize` export run first. A
+/// response envelope is unwrapped to its `data` bytes, exactly as the guest
+/// wrote them, or to the error it reports.
+///
+/// Once the output is read it is released with `free_result(ptr)`, then the
+/// input with `dealloc(ptr, len)`, when the guest exports them. Output longer
+/// than `max_output_bytes` is not read; the call fails with
+/// `KernelError::OutputTooLarge`.
+pub async fn call_json<I: WasmInstance>(
+    instance: &mut I,
+    function_name: &str,
+    input: &[u8],
+    max_output_bytes: usize,
+) -> Result<Vec<u8>> {
+    if !instance.has_memory() {
+        return Err(KernelError::MissingMemoryExport.into());
+    }
+    // WASI reactors set up their runtime before any other export runs
+    if instance.has_function("_initialize") {
+        instance.call("_initialize", &[]).await?;
+    }
+
+    let input_len = i32::try_from(input.len())
+        .map_err(|_| KernelError::InputTooLarge(input.len()))?;
+    let input_ptr = single_result(instance.call("alloc", &[input_len]).await?, "alloc")?;
+    instance.write_memory(input_ptr as u32 as usize, input)?;
+
+    let output_ptr = single_result(instance.call(function_name, &[input_ptr, input_len]).await?, function_name)?;
+    let output = match output_ptr {
+        0 => Err(KernelError::InputRejected(function_name.to_string()).into()),
+        ptr => {
+            let output = read_output(instance, function_name, ptr as u32 as usize, max_output_bytes);
+            if instance.has_function("free_result") {
+                instance.call("free_result", &[ptr]).await?;
+            }
+            output
+        }
+    };
+    if instance.has_function("dealloc") {
+        instance.call("dealloc", &[input_ptr, input_len]).await?;
+    }
+
+    unwrap_envelope(function_name, output?)
+}
+
+/// Copy a length-prefixed result buffer out of linear memory
+fn read_output<I: WasmInstance>(instance: &I, function_name: &str, output_ptr: usize, max_len: usize) -> Result<Vec<u8>> {
+    let mut len_bytes = [0u8; 4];
+    instance.read_memory(output_ptr, &mut len_bytes)?;
+    let output_len = u32::from_le_bytes(len_bytes) as usize;
+    if output_len > max_len {
+        return Err(KernelError::OutputTooLarge {
+            function: function_name.to_string(),
+            size: output_len,
+            limit: max_len,
+        }
+        .into());
+    }
+
+    let mut output = vec![0u8; output_len];
+    instance.read_memory(output_ptr + 4, &mut output)?;
+    Ok(output)
+}
+
+/// The data of a response envelope, or the error it reports; other output
+/// passes through unchanged
+fn unwrap_envelope(function_name: &str, output: Vec<u8>) -> Result<Vec<u8>> {
+    let Ok(envelope) = serde_json::from_slice::<Envelope>(&output) else {
+        return Ok(output);
+    };
+    if envelope.ok {
+        return Ok(envelope.data.get().as_bytes().to_vec());
+    }
+    let error = serde_json::from_str(envelope.error.get()).unwrap_or_else(|_| ModuleError {
+        code: "INTERNAL".to_string(),
+        message: format!("malformed error {}", envelope.error.get()),
+    });
+    Err(KernelError::ModuleReported { function: function_name.to_string(), error }.into())
+}
+
+/// The ABI version an instance's module declares, if it exports one
+pub async fn abi_version<I: WasmInstance>(instance: &mut I) -> Result<Option<i32>> {
+    if !instance.has_function(ABI_VERSION_EXPORT) {
+        return Ok(None);
+    }
+    let results = instance.call(ABI_VERSION_EXPORT, &[]).await?;
+    single_result(results, ABI_VERSION_EXPORT).map(Some)
+}
+
+fn single_result(results: Vec<i32>, function_name: &str) -> Result<i32> {
+    match results[..] {
+        [value] => Ok(value),
+        _ => Err(anyhow!("{} returned {} values, expected one i32", function_name, results.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A guest export written in Rust
+    type Export = fn(&mut ScriptedInstance, &[i32]) -> Result<Vec<i32>>;
+
+    /// Backend whose "modules" are tables of Rust functions over a byte vector
+    struct ScriptedRuntime;
+
+    struct ScriptedInstance {
+        exports: HashMap<&'static str, Export>,
+        memory: Option<Vec<u8>>,
+        /// Next free byte for `alloc`
+        next: i32,
+        /// Buffers allocated and not yet freed; none left rewinds `next`
+        live: usize,
+        /// Exports called, in order
+        calls: Vec<String>,
+        fuel: u64,
+        consumed: u64,
+    }
+
+    impl WasmRuntime for ScriptedRuntime {
+        type Host = ();
+        type Module = HashMap<&'static str, Export>;
+        type Instance = ScriptedInstance;
+
+        fn compile(&self, _bytes: &[u8]) -> Result<Self::Module> {
+            Ok(HashMap::new())
+        }
+
+        fn has_export(&self, module: &Self::Module, name: &str) -> bool {
+            module.contains_key(name)
+        }
+
+        fn imports(&self, _module: &Self::Module) -> Vec<(String, String)> {
+            Vec::new()
+        }
+
+        async fn instantiate(&self, module: &Self::Module, _host: (), fuel: u64) -> Result<ScriptedInstance> {
+            Ok(ScriptedInstance {
+                exports: module.clone(),
+                memory: Some(vec![0; 256]),
+                next: 16,
+                live: 0,
+                calls: Vec::new(),
+                fuel,
+                consumed: 0,
+            })
+        }
+    }
+
+    impl WasmInstance for ScriptedInstance {
+        type Host = ();
+
+        fn host(&self) -> &() {
+            &()
+        }
+
+        fn has_function(&self, name: &str) -> bool {
+            self.exports.contains_key(name)
+        }
+
+        async fn call(&mut self, name: &str, args: &[i32]) -> Result<Vec<i32>> {
+            let export = *self.exports.get(name).ok_or_else(|| anyhow!("no export {}", name))?;
+            self.calls.push(name.to_string());
+            // Every call costs 10 fuel
+            self.consumed += 10;
+            if self.consumed > self.fuel {
+                return Err(anyhow!("all fuel consumed"));
+            }
+            export(self, args)
+        }
+
+        fn fuel_consumed(&self) -> u64 {
+            self.consumed
+        }
+
+        fn has_memory(&self) -> bool {
+            self.memory.is_some()
+        }
+
+        fn memory_size(&self) -> usize {
+            self.memory.as_ref().map_or(0, Vec::len)
+        }
+
+        fn read_memory(&self, offset: usize, buf: &mut [u8]) -> Result<()> {
+            let memory = self.memory.as_ref().ok_or_else(|| anyhow!("no memory"))?;
+            let bytes = memory.get(offset..offset + buf.len()).ok_or_else(|| anyhow!("out of bounds"))?;
+            buf.copy_from_slice(bytes);
+            Ok(())
+        }
+
+        fn write_memory(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+            let memory = self.memory.as_mut().ok_or_else(|| anyhow!("no memory"))?;
+            let target = memory.get_mut(offset..offset + bytes.len()).ok_or_else(|| anyhow!("out of bounds"))?;
+            target.copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn alloc(instance: &mut ScriptedInstance, args: &[i32]) -> Result<Vec<i32>> {
+        let ptr = instance.next;
+        instance.next += args[0];
+        instance.live += 1;
+        Ok(vec![ptr])
+    }
+
+    /// Serves `free_result` and `dealloc`
+    fn free(instance: &mut ScriptedInstance, _args: &[i32]) -> Result<Vec<i32>> {
+        instance.live -= 1;
+        if instance.live == 0 {
+            instance.next = 16;
+        }
+        Ok(vec![])
+    }
+
+    /// Returns its input reversed, behind a length prefix
+    fn reverse(instance: &mut ScriptedInstance, args: &[i32]) -> Result<Vec<i32>> {
+        let mut input = vec![0; args[1] as usize];
+        instance.read_memory(args[0] as usize, &mut input)?;
+        input.reverse();
+        let out = alloc(instance, &[4 + args[1]])?[0];
+        instance.write_memory(out as usize, &(args[1] as u32).to_le_bytes())?;
+        instance.write_memory(out as usize + 4, &input)?;
+        Ok(vec![out])
+    }
+
+    /// Answers with the envelope stored at its input
+    fn envelope(instance: &mut ScriptedInstance, args: &[i32]) -> Result<Vec<i32>> {
+        let mut input = vec![0; args[1] as usize];
+        instance.read_memory(args[0] as usize, &mut input)?;
+        let out = alloc(instance, &[4 + args[1]])?[0];
+        instance.write_memory(out as usize, &(args[1] as u32).to_le_bytes())?;
+        instance.write_memory(out as usize + 4, &input)?;
+        Ok(vec![out])
+    }
+
+    fn module() -> HashMap<&'static str, Export> {
+        let mut exports: HashMap<&'static str, Export> = HashMap::new();
+        exports.insert("alloc", alloc);
+        exports.insert("reverse_json", reverse);
+        exports.insert("echo_json", envelope);
+        exports.insert("reject_json", |_, _| Ok(vec![0]));
+        exports.insert("void_json", |_, _| Ok(vec![]));
+        exports.insert("_initialize", |_, _| Ok(vec![]));
+        exports
+    }
+
+    #[tokio::test]
+    async fn test_json_abi_round_trip() {
+        let runtime = ScriptedRuntime;
+        let module = module();
+        let mut instance = runtime.instantiate(&module, (), 1_000).await.unwrap();
+
+        let output = call_json(&mut instance, "reverse_json", b"[1,2]", usize::MAX).await.unwrap();
+        assert_eq!(output, b"]2,1[");
+        assert_eq!(instance.calls, ["_initialize", "alloc", "reverse_json"]);
+        assert_eq!(instance.fuel_consumed(), 30);
+
+        // Fuel runs out like any other guest failure
+        let mut starved = runtime.instantiate(&module, (), 15).await.unwrap();
+        assert!(call_json(&mut starved, "reverse_json", b"{}", usize::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_buffers_are_freed_after_every_call() {
+        let mut module = module();
+        module.insert("free_result", free);
+        module.insert("dealloc", free);
+        let mut instance = ScriptedRuntime.instantiate(&module, (), u64::MAX).await.unwrap();
+
+        // Leaked buffers would overrun the 256-byte memory within a few calls
+        for _ in 0..10_000 {
+            assert_eq!(call_json(&mut instance, "reverse_json", b"[1,2]", usize::MAX).await.unwrap(), b"]2,1[");
+        }
+        assert_eq!((instance.live, instance.next), (0, 16));
+        assert_eq!(instance.calls[1..5], ["alloc", "reverse_json", "free_result", "dealloc"]);
+
+        // Failures release them too
+        let err = call_json(&mut instance, "reject_json", b"{}", usize::MAX).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::InputRejected(_))));
+        let err = call_json(&mut instance, "echo_json", br#"{"ok":false,"error":null,"data":null}"#, usize::MAX).await;
+        assert!(err.is_err());
+        let err = call_json(&mut instance, "reverse_json", b"[1,2]", 4).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::OutputTooLarge { size: 5, limit: 4, .. })));
+        assert_eq!((instance.live, instance.next), (0, 16));
+    }
+
+    #[tokio::test]
+    async fn test_json_abi_errors() {
+        let runtime = ScriptedRuntime;
+        let mut instance = runtime.instantiate(&module(), (), 1_000).await.unwrap();
+
+        let err = call_json(&mut instance, "reject_json", b"{}", usize::MAX).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::InputRejected(f)) if f == "reject_json"));
+        let err = call_json(&mut instance, "void_json", b"{}", usize::MAX).await.unwrap_err();
+        assert!(err.to_string().contains("expected one i32"));
+        assert!(call_json(&mut instance, "missing_json", b"{}", usize::MAX).await.is_err());
+
+        instance.memory = None;
+        let err = call_json(&mut instance, "reverse_json", b"{}", usize::MAX).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::MissingMemoryExport)));
+    }
+
+    #[tokio::test]
+    async fn test_response_envelope() {
+        // A fresh instance per call, as the scripted memory is small
+        async fn call(output: &[u8]) -> Result<Vec<u8>> {
+            let mut instance = ScriptedRuntime.instantiate(&module(), (), 1_000).await.unwrap();
+            call_json(&mut instance, "echo_json", output, usize::MAX).await
+        }
+
+        // Data comes back byte for byte, key order and all
+        let data = call(br#"{"ok":true,"error":null,"data":{"z":1,"a":[2]}}"#).await.unwrap();
+        assert_eq!(data, br#"{"z":1,"a":[2]}"#);
+
+        let err = call(br#"{"ok":false,"error":{"code":"INVALID_INPUT","message":"missing field"},"data":null}"#)
+            .await
+            .unwrap_err();
+        match err.downcast_ref() {
+            Some(KernelError::ModuleReported { function, error }) => {
+                assert_eq!(function, "echo_json");
+                assert_eq!(error, &ModuleError { code: "INVALID_INPUT".into(), message: "missing field".into() });
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        let err = call(br#"{"ok":false,"error":"boom","data":null}"#).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(KernelError::ModuleReported { error, .. }) if error.code == "INTERNAL"));
+
+        // Anything else is not an envelope and passes through
+        for output in [&br#"{}"#[..], br#"{"ok":true}"#, br#"{"ok":true,"error":null,"data":1,"extra":2}"#, b"not json"] {
+            assert_eq!(call(output).await.unwrap(), output);
+        }
+    }
+}
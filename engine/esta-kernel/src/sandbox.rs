@@ -0,0 +1,154 @@
+//! Sandbox Profiles
+//!
+//! A sandbox profile names the limits modules run under, so a deployment or
+//! a module's manifest picks `strict` instead of tuning fuel, memory,
+//! timeouts, and signature checks one by one:
+//!
+//! | Profile    | Signatures | Fuel per call | Memory  | Call timeout | WASI and directories |
+//! |------------|------------|---------------|---------|--------------|----------------------|
+//! | `strict`   | required   | 5M            | 16 MiB  | 10 s         | refused              |
+//! | `standard` | optional   | 20M           | 32 MiB  | none         | allowed              |
+//! | `dev`      | optional   | 100M          | 64 MiB  | none         | allowed              |
+//!
+//! `standard` matches `ExecutionConfig::default()`. WASI and directory grants
+//! are a module's only way to reach files or the network, so a strict module
+//! can compute but not communicate.
+//!
+//! [`ExecutionConfig::sandboxed`](crate::ExecutionConfig::sandboxed) starts a
+//! kernel configuration from a profile. A manifest's `sandbox` field then
+//! tightens the limits for that module only: a module never runs with more
+//! than the kernel allows, so a manifest asking for `dev` on a strict kernel
+//! still runs strict. Memory limits apply to the whole kernel. The profile
+//! each module runs under is recorded in its `ModuleLoaded` audit entry.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+
+/// A named bundle of module limits, ordered from most relaxed to strictest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxProfile {
+    /// Generous limits for developing modules
+    Dev,
+    /// The kernel's default limits
+    Standard,
+    /// Signed modules with low limits and no file or network access
+    Strict,
+}
+
+/// An unrecognized sandbox profile name
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown sandbox profile {0} (expected strict, standard, or dev)")]
+pub struct UnknownSandbox(pub String);
+
+impl std::str::FromStr for SandboxProfile {
+    type Err = UnknownSandbox;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dev" | "development" => Ok(Self::Dev),
+            "standard" => Ok(Self::Standard),
+            "strict" => Ok(Self::Strict),
+            _ => Err(UnknownSandbox(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for SandboxProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Limits a module runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SandboxLimits {
+    /// The module must carry a signature by a trusted key
+    pub require_signatures: bool,
+    /// Fuel (instructions) per invocation
+    pub max_fuel: u64,
+    /// Linear memory in bytes
+    pub max_memory_bytes: usize,
+    /// Longest an invocation may run (enforced at `host_yield`)
+    pub call_timeout: Option<Duration>,
+    /// Whether the module may be granted `wasi`, `fs_read`, and `fs_write`
+    pub host_io: bool,
+}
+
+impl SandboxLimits {
+    /// The stricter of each limit in `self` and `other`
+    pub fn tightened(&self, other: &SandboxLimits) -> SandboxLimits {
+        SandboxLimits {
+            require_signatures: self.require_signatures || other.require_signatures,
+            max_fuel: self.max_fuel.min(other.max_fuel),
+            max_memory_bytes: self.max_memory_bytes.min(other.max_memory_bytes),
+            call_timeout: match (self.call_timeout, other.call_timeout) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            host_io: self.host_io && other.host_io,
+        }
+    }
+}
+
+impl SandboxProfile {
+    /// The profile's name, as accepted by `parse`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dev => "dev",
+            Self::Standard => "standard",
+            Self::Strict => "strict",
+        }
+    }
+
+    /// The limits this profile sets
+    pub fn limits(&self) -> SandboxLimits {
+        match self {
+            Self::Dev => SandboxLimits {
+                require_signatures: false,
+                max_fuel: 100_000_000,
+                max_memory_bytes: 64 * 1024 * 1024,
+                call_timeout: None,
+                host_io: true,
+            },
+            Self::Standard => SandboxLimits {
+                require_signatures: false,
+                max_fuel: 20_000_000,
+                max_memory_bytes: 32 * 1024 * 1024,
+                call_timeout: None,
+                host_io: true,
+            },
+            Self::Strict => SandboxLimits {
+                require_signatures: true,
+                max_fuel: 5_000_000,
+                max_memory_bytes: 16 * 1024 * 1024,
+                call_timeout: Some(Duration::from_secs(10)),
+                host_io: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_tighten() {
+        for profile in [SandboxProfile::Dev, SandboxProfile::Standard, SandboxProfile::Strict] {
+            assert_eq!(profile.as_str().parse(), Ok(profile));
+        }
+        assert_eq!(" Strict ".parse(), Ok(SandboxProfile::Strict));
+        assert_eq!("loose".parse::<SandboxProfile>(), Err(UnknownSandbox("loose".to_string())));
+        assert!(SandboxProfile::Strict > SandboxProfile::Dev);
+
+        // Tightening by a more relaxed profile changes nothing
+        let strict = SandboxProfile::Strict.limits();
+        assert_eq!(strict.tightened(&SandboxProfile::Dev.limits()), strict);
+        let custom = SandboxLimits { max_fuel: 1_000, call_timeout: Some(Duration::from_secs(60)), ..SandboxProfile::Dev.limits() };
+        let tightened = custom.tightened(&strict);
+        assert_eq!((tightened.max_fuel, tightened.call_timeout), (1_000, Some(Duration::from_secs(10))));
+        assert!(tightened.require_signatures && !tightened.host_io);
+    }
+}
@@ -0,0 +1,415 @@
+//! Audit Checkpoint Anchoring
+//!
+//! The hash chain shows an audit log was not altered after the fact, but not
+//! when it was written: whoever holds the log could rebuild the whole chain.
+//! Anchoring closes that gap. A checkpoint, the head entry's sequence number
+//! and hash, is handed to an [`AnchorProvider`] outside the kernel's control,
+//! and the [`AnchorReceipt`] it returns is written back into the log as a
+//! `CheckpointAnchored` entry. Because each entry hash commits to every entry
+//! before it, a receipt for entry N shows entries 1 to N existed by the time
+//! the provider recorded it.
+//!
+//! Two providers are built in:
+//!
+//! - [`FileAnchor`] appends checkpoints to a file, meant to be on storage the
+//!   kernel's host cannot rewrite (a WORM volume, a synced folder owned by
+//!   the auditor).
+//! - `HttpAnchor` (feature `anchor-http`) sends them to an RFC 3161 timestamp
+//!   authority, or POSTs them as JSON to an HTTPS endpoint. An RFC 3161
+//!   receipt keeps the authority's whole response, which an auditor checks
+//!   with `openssl ts -verify -digest <hash> -in <response>`. The kernel only
+//!   checks that the response grants the request and names the hash.
+//!
+//! A log given a checkpoint key ([`crate::AuditLog::with_checkpoint_signer`])
+//! signs its checkpoints, so a verifier holding the trusted keys can tell
+//! they were taken by the kernel and not made up afterwards.
+//!
+//! [`crate::AuditLog::schedule_anchoring`] anchors the head every interval
+//! when entries were added since the last checkpoint.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use super::audit::AuditEntry;
+use super::sig::{ModuleSigner, SignatureError, SignatureResult};
+use super::trust::{TrustStore, TrustedKey};
+use crate::clock::now_millis;
+
+/// Domain separator so a checkpoint signature cannot be mistaken for a module signature
+const CHECKPOINT_CONTEXT: &[u8] = b"esta-audit-checkpoint\0";
+
+/// Errors anchoring a checkpoint
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AnchorError {
+    #[error("Anchor I/O failed: {0}")]
+    Io(String),
+
+    #[error("Anchor request failed: {0}")]
+    Http(String),
+
+    #[error("Anchor refused checkpoint: {0}")]
+    Rejected(String),
+
+    #[error("Anchor response is invalid: {0}")]
+    InvalidResponse(String),
+}
+
+/// Result type for anchoring
+pub type AnchorResult<T> = Result<T, AnchorError>;
+
+/// The head of the audit chain at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditCheckpoint {
+    /// Sequence number of the head entry (0 for an empty log)
+    pub sequence: u64,
+    /// Hash of the head entry (hex)
+    pub hash: String,
+    /// When the checkpoint was taken (Unix millis)
+    pub taken_at: u64,
+    /// Ed25519 signature by the kernel's checkpoint key (hex), if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl AuditCheckpoint {
+    /// The bytes a checkpoint signature covers
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = CHECKPOINT_CONTEXT.to_vec();
+        bytes.extend(self.sequence.to_le_bytes());
+        bytes.extend(self.hash.as_bytes());
+        bytes.extend(self.taken_at.to_le_bytes());
+        bytes
+    }
+
+    /// Sign the checkpoint
+    pub fn sign(&mut self, signer: &ModuleSigner) {
+        self.signature = Some(signer.sign(&self.signed_bytes()));
+    }
+
+    /// Check the signature against trusted keys, returning the key that made it
+    ///
+    /// A key retired since is accepted if it was still trusted when the
+    /// checkpoint was taken.
+    pub fn verify_signature(&self, trust: &TrustStore) -> SignatureResult<TrustedKey> {
+        let signature = self.signature.as_deref().ok_or(SignatureError::MissingSignature)?;
+        match trust.verify(&self.signed_bytes(), signature) {
+            Err(SignatureError::KeyRetired(key_id)) => trust
+                .keys()
+                .into_iter()
+                .find(|key| key.key_id == key_id && key.is_trusted_at(self.taken_at))
+                .ok_or(SignatureError::KeyRetired(key_id)),
+            verified => verified,
+        }
+    }
+}
+
+/// A provider's record that a checkpoint existed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnchorReceipt {
+    pub checkpoint: AuditCheckpoint,
+    /// Provider that recorded it (see [`AnchorProvider::name`])
+    pub provider: String,
+    /// When the kernel received the receipt (Unix millis); an RFC 3161
+    /// proof carries the authority's own time
+    pub anchored_at: u64,
+    /// The provider's response (hex): a DER `TimeStampResp` from a timestamp
+    /// authority, the body returned by a JSON endpoint, empty for files
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub proof: String,
+}
+
+impl AnchorReceipt {
+    /// Whether this receipt is for `entry`
+    ///
+    /// An RFC 3161 proof must also grant the request and name the entry
+    /// hash; the timestamp authority's signature is not checked here.
+    pub fn covers(&self, entry: &AuditEntry) -> bool {
+        self.covers_head(entry.sequence, &entry.hash)
+    }
+
+    /// Whether this receipt is for the entry with `sequence` and `hash` (see [`Self::covers`])
+    pub fn covers_head(&self, sequence: u64, hash: &str) -> bool {
+        if self.checkpoint.sequence != sequence || self.checkpoint.hash != hash {
+            return false;
+        }
+        match (hex::decode(&self.proof), hex::decode(hash)) {
+            (Ok(proof), Ok(digest)) if rfc3161::is_response(&proof) => {
+                <[u8; 32]>::try_from(digest).is_ok_and(|digest| rfc3161::check_response(&proof, &digest).is_ok())
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Somewhere outside the kernel that records audit checkpoints
+///
+/// Anchoring blocks; the audit log calls it off the async runtime.
+pub trait AnchorProvider: Send + Sync {
+    /// Where checkpoints go, for receipts and logs (e.g. a path or URL)
+    fn name(&self) -> String;
+
+    /// Record a checkpoint
+    fn anchor(&self, checkpoint: &AuditCheckpoint) -> AnchorResult<AnchorReceipt>;
+}
+
+/// Appends checkpoints to a file, one JSON object per line
+pub struct FileAnchor {
+    path: PathBuf,
+}
+
+impl FileAnchor {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AnchorProvider for FileAnchor {
+    fn name(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+
+    fn anchor(&self, checkpoint: &AuditCheckpoint) -> AnchorResult<AnchorReceipt> {
+        let io = |e: std::io::Error| AnchorError::Io(format!("{}: {}", self.path.display(), e));
+        let mut line = serde_json::to_vec(checkpoint).map_err(|e| AnchorError::Io(e.to_string()))?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path).map_err(io)?;
+        file.write_all(&line).map_err(io)?;
+        file.sync_all().map_err(io)?;
+        Ok(AnchorReceipt {
+            checkpoint: checkpoint.clone(),
+            provider: self.name(),
+            anchored_at: now_millis(),
+            proof: String::new(),
+        })
+    }
+}
+
+#[cfg(feature = "anchor-http")]
+pub use http::{AnchorFormat, HttpAnchor};
+
+#[cfg(feature = "anchor-http")]
+mod http {
+    use super::*;
+    use std::io::Read;
+    use std::time::Duration;
+
+    /// Longest an anchor endpoint may take to answer
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Largest response kept as a proof
+    const MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+    /// What an [`HttpAnchor`] endpoint speaks
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AnchorFormat {
+        /// RFC 3161 time-stamp protocol over HTTP
+        Rfc3161,
+        /// The checkpoint POSTed as JSON; any 2xx answer is the receipt
+        Json,
+    }
+
+    /// Sends checkpoints to a timestamp authority or HTTPS endpoint
+    pub struct HttpAnchor {
+        url: String,
+        format: AnchorFormat,
+        agent: ureq::Agent,
+    }
+
+    impl HttpAnchor {
+        pub fn new(url: impl Into<String>, format: AnchorFormat) -> Self {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).redirects(0).build();
+            Self { url: url.into(), format, agent }
+        }
+
+        /// An RFC 3161 timestamp authority
+        pub fn rfc3161(url: impl Into<String>) -> Self {
+            Self::new(url, AnchorFormat::Rfc3161)
+        }
+
+        /// An endpoint taking checkpoints as JSON
+        pub fn json(url: impl Into<String>) -> Self {
+            Self::new(url, AnchorFormat::Json)
+        }
+
+        fn post(&self, content_type: &str, body: &[u8]) -> AnchorResult<Vec<u8>> {
+            let response = match self.agent.post(&self.url).set("Content-Type", content_type).send_bytes(body) {
+                Ok(response) => response,
+                Err(ureq::Error::Status(status, _)) => return Err(AnchorError::Rejected(format!("HTTP {}", status))),
+                Err(e) => return Err(AnchorError::Http(e.to_string())),
+            };
+            let mut body = Vec::new();
+            response
+                .into_reader()
+                .take(MAX_RESPONSE_BYTES + 1)
+                .read_to_end(&mut body)
+                .map_err(|e| AnchorError::Http(e.to_string()))?;
+            if body.len() as u64 > MAX_RESPONSE_BYTES {
+                return Err(AnchorError::InvalidResponse(format!("larger than {} bytes", MAX_RESPONSE_BYTES)));
+            }
+            Ok(body)
+        }
+    }
+
+    impl AnchorProvider for HttpAnchor {
+        fn name(&self) -> String {
+            self.url.clone()
+        }
+
+        fn anchor(&self, checkpoint: &AuditCheckpoint) -> AnchorResult<AnchorReceipt> {
+            let proof = match self.format {
+                AnchorFormat::Rfc3161 => {
+                    let digest = hex::decode(&checkpoint.hash)
+                        .ok()
+                        .and_then(|digest| <[u8; 32]>::try_from(digest).ok())
+                        .ok_or_else(|| AnchorError::Io(format!("checkpoint hash {} is not SHA-256", checkpoint.hash)))?;
+                    let response = self.post("application/timestamp-query", &rfc3161::request(&digest, nonce()))?;
+                    rfc3161::check_response(&response, &digest)?;
+                    response
+                }
+                AnchorFormat::Json => {
+                    let body = serde_json::to_vec(checkpoint).map_err(|e| AnchorError::Io(e.to_string()))?;
+                    self.post("application/json", &body)?
+                }
+            };
+            Ok(AnchorReceipt {
+                checkpoint: checkpoint.clone(),
+                provider: self.name(),
+                anchored_at: now_millis(),
+                proof: hex::encode(proof),
+            })
+        }
+    }
+
+    fn nonce() -> u64 {
+        use ring::rand::SecureRandom;
+        let mut bytes = [0u8; 8];
+        // A zero nonce still gets a valid timestamp
+        let _ = ring::rand::SystemRandom::new().fill(&mut bytes);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+/// Just enough DER for RFC 3161 requests and response status
+#[cfg_attr(not(feature = "anchor-http"), allow(dead_code))]
+mod rfc3161 {
+    use super::{AnchorError, AnchorResult};
+
+    /// AlgorithmIdentifier for SHA-256 (OID 2.16.840.1.101.3.4.2.1, NULL parameters)
+    const SHA256_ALGORITHM: [u8; 15] = [0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00];
+
+    const SEQUENCE: u8 = 0x30;
+    const INTEGER: u8 = 0x02;
+
+    /// A `TimeStampReq` for a SHA-256 digest, asking for the TSA's certificate
+    pub fn request(digest: &[u8; 32], nonce: u64) -> Vec<u8> {
+        let mut imprint = SHA256_ALGORITHM.to_vec();
+        imprint.extend(tlv(0x04, digest));
+
+        let mut body = tlv(INTEGER, &[1]);
+        body.extend(tlv(SEQUENCE, &imprint));
+        body.extend(tlv(INTEGER, &unsigned(nonce)));
+        // certReq TRUE
+        body.extend([0x01, 0x01, 0xff]);
+        tlv(SEQUENCE, &body)
+    }
+
+    /// Check a `TimeStampResp` grants the request and its token names `digest`
+    pub fn check_response(response: &[u8], digest: &[u8; 32]) -> AnchorResult<()> {
+        let invalid = |what: &str| AnchorError::InvalidResponse(what.to_string());
+        let (tag, resp) = read(response).ok_or_else(|| invalid("not DER"))?;
+        let (status_tag, status_info) = read(resp).ok_or_else(|| invalid("missing status"))?;
+        let (int_tag, status) = read(status_info).ok_or_else(|| invalid("missing status"))?;
+        if tag != SEQUENCE || status_tag != SEQUENCE || int_tag != INTEGER {
+            return Err(invalid("not a TimeStampResp"));
+        }
+        // 0 granted, 1 granted with modifications
+        if !matches!(status, [0] | [1]) {
+            return Err(AnchorError::Rejected(format!("PKIStatus {}", hex::encode(status))));
+        }
+        if !contains(resp, digest) {
+            return Err(invalid("token does not name the checkpoint hash"));
+        }
+        Ok(())
+    }
+
+    /// Whether `der` looks like a `TimeStampResp` rather than some other proof
+    pub fn is_response(der: &[u8]) -> bool {
+        read(der).is_some_and(|(tag, _)| tag == SEQUENCE)
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+            out.push(0x80 | bytes.len() as u8);
+            out.extend(bytes);
+        }
+        out.extend(content);
+        out
+    }
+
+    /// Minimal two's-complement encoding of a non-negative INTEGER
+    fn unsigned(value: u64) -> Vec<u8> {
+        let mut bytes: Vec<u8> = value.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        if bytes.first().is_none_or(|b| b & 0x80 != 0) {
+            bytes.insert(0, 0);
+        }
+        bytes
+    }
+
+    /// The tag and content of the first element in `der`
+    fn read(der: &[u8]) -> Option<(u8, &[u8])> {
+        let (&tag, rest) = der.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (usize::from(first), rest)
+        } else {
+            let count = usize::from(first & 0x7f);
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count].iter().fold(0usize, |len, b| len << 8 | usize::from(*b));
+            (len, &rest[count..])
+        };
+        Some((tag, rest.get(..len)?))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_request_and_response() {
+            let digest = [0xab; 32];
+            let request = request(&digest, 0x80);
+            let (tag, body) = read(&request).unwrap();
+            assert_eq!(tag, SEQUENCE);
+            assert_eq!(body.len(), request.len() - 2);
+            assert_eq!(&body[..3], &[INTEGER, 1, 1]);
+            // Nonce with its high bit set gets a leading zero
+            assert!(contains(body, &[INTEGER, 2, 0, 0x80]));
+            assert_eq!(unsigned(0), vec![0]);
+            assert_eq!(unsigned(0x0102), vec![1, 2]);
+
+            let response = |status: u8| {
+                let mut body = tlv(SEQUENCE, &tlv(INTEGER, &[status]));
+                // Stand-in for a token: anything naming the digest
+                body.extend(tlv(SEQUENCE, &tlv(0x04, &vec![0x11; 200].into_iter().chain(digest).collect::<Vec<_>>())));
+                tlv(SEQUENCE, &body)
+            };
+            assert_eq!(check_response(&response(0), &digest), Ok(()));
+            assert!(matches!(check_response(&response(2), &digest), Err(AnchorError::Rejected(_))));
+            assert!(matches!(check_response(&response(0), &[0xcd; 32]), Err(AnchorError::InvalidResponse(_))));
+            assert!(check_response(b"<html>", &digest).is_err());
+        }
+    }
+}
@@ -6,50 +6,383 @@
 //! - Tamper-evident: Each entry is cryptographically chained
 //! - Queryable: Efficient filtering and search
 //!
+//! The log can be persisted as segment files of JSON Lines in a directory, so
+//! the chain continues across restarts and survives trimming of the bounded
+//! in-memory log. Verification has two tiers: [`AuditLog::verify_incremental`]
+//! checks only the entries appended since its last call, cheap enough for
+//! frequent UI checks, while [`AuditLog::start_full_verification`] walks every
+//! persisted segment from the genesis entry in a background task, reporting
+//! progress as it goes.
+//!
+//! Queries reaching back past the in-memory log read the persisted segments
+//! through memory maps (see [`super::audit_reader`]).
+//!
+//! New entries are also broadcast to live subscribers
+//! ([`AuditLog::subscribe`]), so audit views need not poll.
+//!
+//! The one permitted change to a written entry is redaction under a tenant's
+//! retention period ([`AuditLog::redact_tenant`]). Entry hashes commit to a
+//! hash of the event rather than the event itself, so an event can be
+//! replaced by a [`AuditEventType::Redacted`] marker and the chain still
+//! verifies.
+//!
+//! A segment write that fails (disk full, permissions) is never skipped:
+//! the entry waits in a bounded in-memory buffer and every later write goes
+//! behind it, so segments stay in sequence order. [`AuditLog::health`]
+//! reports the failure, and the configured [`AuditFailurePolicy`] decides
+//! when privileged operations are refused ([`AuditLog::check_writable`])
+//! until [`AuditLog::retry_pending`] gets the buffer written.
+//!
 //! Reference: docs/abi/kernel_contract.md
 
+use super::anchor::{AnchorError, AnchorProvider, AnchorReceipt, AuditCheckpoint};
+use super::audit_reader::{ArchivedAuditStats, AuditSegmentReader};
+use super::capabilities::{CapabilityRight, ResourceType};
+use super::sig::ModuleSigner;
+use crate::backup::BackupSummary;
+use crate::clock::{Clock, SystemClock};
+use crate::error::StorageError;
+use crate::pagination::{Page, PageRequest};
+use crate::replay::{InvocationRecord, ReplayReport};
+use crate::retention::RetentionReport;
+use crate::sandbox::SandboxProfile;
+use crate::tenant::TenantPurgeReport;
+use crate::trap::{BacktraceFrame, TrapKind};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::VecDeque;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// Entries per segment file when persisting to a directory
+pub const DEFAULT_SEGMENT_ENTRIES: u64 = 10_000;
+
+/// Entries buffered for each live subscriber before it starts missing some
+const BROADCAST_CAPACITY: usize = 1_024;
+
+/// Unwritten entries kept in memory while segment writes fail, by default
+pub const DEFAULT_MAX_PENDING_ENTRIES: usize = 1_000;
 
 /// Types of audit events
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AuditEventType {
     // Module lifecycle events
-    ModuleLoaded { module_name: String, checksum: String },
-    ModuleUnloaded { module_name: String },
+    ModuleLoaded {
+        module_name: String,
+        checksum: String,
+        /// Sandbox profile the module runs under, if the kernel or its manifest named one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sandbox: Option<SandboxProfile>,
+        /// ABI version the module declared (none for modules built before `__abi_version`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        abi_version: Option<u32>,
+    },
+    ModuleUnloaded {
+        module_name: String,
+        /// Outcome of the module's `__shutdown` export ("completed", "failed", "not_exported")
+        #[serde(default)]
+        signal: String,
+        /// Invocations still running or queued when the drain timeout expired
+        #[serde(default)]
+        pending_invocations: usize,
+        #[serde(default)]
+        start_task_aborted: bool,
+        /// Capabilities bound to the instance, revoked with it
+        #[serde(default)]
+        capabilities_revoked: usize,
+        /// Whether its final statistics were written to the stats history
+        #[serde(default)]
+        stats_flushed: bool,
+    },
     ModuleStarted { module_name: String },
     ModuleStopped { module_name: String, exit_code: i32 },
-    ModuleCrashed { module_name: String, error: String },
+    ModuleCrashed {
+        module_name: String,
+        error: String,
+        /// Symbolized WASM frames, innermost first (empty if not captured)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        backtrace: Vec<BacktraceFrame>,
+        /// How the module failed, if it trapped
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trap: Option<TrapKind>,
+        /// ID of the crash dump saved for the trap (see [`crate::forensics`])
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        crash_dump: Option<String>,
+    },
     ModuleRestarted { module_name: String, attempt: u32 },
+    /// A module was refused before instantiation for an import it may not link
+    ModuleImportDenied {
+        module_name: String,
+        /// `module::name` of the import
+        import: String,
+        /// Capability the import needs (None: the host does not provide it)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        capability: Option<String>,
+    },
+    /// A module was loaded with host function imports its capabilities do
+    /// not grant; each is linked to a stub and calls to it trap
+    ModuleImportsStubbed {
+        module_name: String,
+        /// Capability each stubbed import (`module::name`) needs
+        imports: BTreeMap<String, String>,
+    },
+    /// A module called a host function its capabilities do not grant
+    HostCallDenied {
+        module_name: String,
+        /// Export whose call made the host call
+        function_name: String,
+        /// `module::name` of the host function
+        import: String,
+        /// Capability the host function needs
+        capability: String,
+        /// Right and resource the call would have exercised
+        right: String,
+        resource: ResourceType,
+    },
+    ModuleRolledBack {
+        module_name: String,
+        from_version: Option<String>,
+        to_version: String,
+        checksum: String,
+    },
 
     // Capability events
     CapabilityCreated { cap_id: String, owner: String, rights: Vec<String> },
-    CapabilityValidated { cap_id: String, operation: String },
-    CapabilityDenied { cap_id: String, reason: String },
-    CapabilityDelegated { parent_id: String, new_id: String, new_owner: String },
-    CapabilityRevoked { cap_id: String, cascade_count: usize },
+    CapabilityValidated {
+        cap_id: String,
+        /// Rights the operation required, comma-separated
+        operation: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        owner: String,
+        /// Rights the capability grants
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rights: Vec<String>,
+    },
+    CapabilityDenied {
+        cap_id: String,
+        reason: String,
+        /// Owner of the capability, if the token named a known one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        /// Rights the operation required
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rights: Vec<String>,
+    },
+    CapabilityDelegated {
+        parent_id: String,
+        new_id: String,
+        new_owner: String,
+        /// Rights granted to the new owner
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rights: Vec<String>,
+    },
+    /// A lease was extended (see [`super::capabilities::CapabilityManager::renew`])
+    CapabilityRenewed {
+        cap_id: String,
+        owner: String,
+        /// New expiry (Unix millis)
+        expires_at: u64,
+        /// Latest the lease can be renewed to (Unix millis)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lifetime_ceiling: Option<u64>,
+    },
+    CapabilityRevoked {
+        cap_id: String,
+        cascade_count: usize,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        owner: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        rights: Vec<String>,
+    },
 
     // Signature events
-    SignatureVerified { module_name: String },
+    SignatureVerified {
+        module_name: String,
+        /// Trusted key that made the signature
+        key_id: String,
+        /// The key is in its grace period after a rotation
+        retiring: bool,
+    },
+    SigningKeyRotated {
+        key_id: String,
+        retiring_key_ids: Vec<String>,
+        /// When the retiring keys stop being trusted (Unix millis)
+        retires_at: Option<u64>,
+    },
     SignatureFailed { module_name: String, error: String },
 
     // Execution events
     ExecutionStarted { module_name: String, function: String },
     ExecutionCompleted { module_name: String, function: String, fuel_used: u64 },
-    ExecutionFailed { module_name: String, function: String, error: String },
+    /// An invocation was answered from the result cache without running the guest
+    ExecutionCacheHit {
+        module_name: String,
+        function: String,
+        /// SHA-256 of the input (hex)
+        input_hash: String,
+    },
+    ExecutionFailed {
+        module_name: String,
+        function: String,
+        error: String,
+        /// How the call failed, if it trapped
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trap: Option<TrapKind>,
+    },
+    /// An invocation was cancelled by its caller before it finished
+    InvocationCancelled { module_name: String, function: String, invocation_id: String, fuel_used: u64 },
     FuelExhausted { module_name: String, fuel_limit: u64 },
-    MemoryLimitExceeded { module_name: String, limit: u64 },
+    MemoryLimitExceeded {
+        module_name: String,
+        limit: u64,
+        /// Function whose call went over the limit
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        function: Option<String>,
+        /// What was too large (`input` or `output`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resource: Option<String>,
+        /// Bytes it came to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+    /// An invocation went far past its module's resource profile (see
+    /// [`crate::resource_profile`])
+    ResourceAnomaly {
+        module_name: String,
+        function: String,
+        resource: String,
+        used: u64,
+        limit: u64,
+        p99: u64,
+    },
+    InvocationArchived { module_name: String, function: String, input_hash: String, output_hash: String },
+    InvocationRecorded(InvocationRecord),
+    /// A WASI module opened, or was refused, a file (see [`crate::wasi`])
+    WasiFileAccess {
+        module_name: String,
+        /// Path as the guest sees it, under its preopened directory
+        path: String,
+        write: bool,
+        allowed: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    ReplayCompleted {
+        first_sequence: u64,
+        last_sequence: u64,
+        invocations: usize,
+        matched: usize,
+        diverged: usize,
+    },
+
+    // Tenant events
+    TenantCreated {
+        tenant_id: String,
+        /// Resource namespace of the tenant's capabilities
+        namespace: String,
+    },
+    TenantArchived { tenant_id: String, capabilities_revoked: usize },
+    TenantPurged(TenantPurgeReport),
+    PolicyVersionRecorded { tenant_id: String, version: u32, effective_from: String },
+    ReportTemplateRecorded { tenant_id: String, name: String, version: u32 },
+    /// A tenant's configuration of a module was set, or removed
+    ModuleConfigChanged { tenant_id: String, module_name: String, removed: bool },
+    UsageInsightsChanged { tenant_id: String, enabled: bool, effective_from: String },
+    UsageInsightsComputed { tenant_id: String, employees_analyzed: usize, insights: usize },
+    /// Employee data past a tenant's retention period was redacted (see [`crate::retention`])
+    RetentionApplied {
+        tenant_id: String,
+        /// Records from before this time were redacted (Unix millis)
+        cutoff: u64,
+        ledger_events: usize,
+        audit_entries: usize,
+    },
+    DryRunExecuted {
+        module_name: String,
+        function_name: String,
+        tenant_id: Option<String>,
+        succeeded: bool,
+    },
 
     // System events
     KernelStarted { version: String },
     KernelShutdown { reason: String },
+    /// The execution configuration was replaced while running (see [`crate::Kernel::reload_config`])
+    ConfigReloaded {
+        /// Settings that took a new value
+        changed: Vec<String>,
+        /// Changed settings that keep their old value until restart
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        restart_required: Vec<String>,
+    },
+    CapabilitySecretRotated {
+        /// Generation in the secret store (None when the secret is not persisted)
+        generation: Option<u32>,
+        reissued: usize,
+        revoked: usize,
+    },
+    BackupCreated {
+        /// Public key the backup is signed with (hex)
+        signer: String,
+        tenants: usize,
+        ledger_events: usize,
+        audit_entries: usize,
+    },
+    BackupRestored {
+        signer: String,
+        /// When the backup was made (Unix millis)
+        created_at: u64,
+        tenants_added: usize,
+        ledger_events: usize,
+    },
+    DatabaseKeyRotated {
+        /// ID of the new key, as recorded in sealed values
+        key_id: String,
+        /// Stored values re-sealed under it
+        resealed: usize,
+    },
+    ModuleShutdown {
+        module_name: String,
+        /// Outcome of the module's `__shutdown` export ("completed", "failed", "not_exported")
+        signal: String,
+        /// Invocations cancelled because they outlasted the drain timeout
+        aborted_invocations: usize,
+        start_task_aborted: bool,
+    },
     SupervisorEscalation { module_name: String, level: u32 },
+    StorageVacuumed {
+        temp_files_removed: usize,
+        archived_blobs_removed: usize,
+        versions_pruned: usize,
+        bytes_reclaimed: u64,
+    },
+    StorageLimitExceeded { total_bytes: u64, limit_bytes: u64 },
+    /// A checkpoint of the chain was recorded outside the kernel (see [`super::anchor`])
+    CheckpointAnchored(AnchorReceipt),
+    AnchorFailed {
+        /// Head of the chain the checkpoint was for
+        sequence: u64,
+        provider: String,
+        error: String,
+    },
 
     // Custom events
     Custom { category: String, message: String },
+
+    /// An event removed under a tenant's retention period; the entry's
+    /// `payload_hash` still commits to the original
+    Redacted {
+        /// Variant name of the removed event
+        kind: String,
+        /// When it was redacted (Unix millis)
+        redacted_at: u64,
+    },
 }
 
 /// A single audit log entry
@@ -67,37 +400,101 @@ pub struct AuditEntry {
     pub prev_hash: String,
     /// Hash of this entry
     pub hash: String,
+    /// Correlation ID of the request that caused the entry (see [`crate::correlation`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Tenant the entry was written on behalf of (see [`crate::tenant::scope`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// SHA-256 of the serialized event (hex), committed to by the entry hash
+    /// in place of the event so the event can be redacted (absent on entries
+    /// written before redaction existed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_hash: Option<String>,
 }
 
 impl AuditEntry {
     /// Compute the hash of this entry
+    ///
+    /// Entries without a correlation ID, tenant, or payload hash hash as they
+    /// did before those existed, so older logs still verify.
+    #[allow(clippy::too_many_arguments)]
     fn compute_hash(
         sequence: u64,
         timestamp: u64,
         event: &AuditEventType,
+        payload_hash: Option<&str>,
         source: &str,
         prev_hash: &str,
+        correlation_id: Option<&str>,
+        tenant_id: Option<&str>,
     ) -> String {
         let mut hasher = Sha256::new();
         hasher.update(sequence.to_le_bytes());
         hasher.update(timestamp.to_le_bytes());
-        hasher.update(serde_json::to_string(event).unwrap_or_default().as_bytes());
+        match payload_hash {
+            Some(payload) => {
+                hasher.update(b"payload:");
+                hasher.update(payload.as_bytes());
+            }
+            None => hasher.update(serde_json::to_string(event).unwrap_or_default().as_bytes()),
+        }
         hasher.update(source.as_bytes());
         hasher.update(prev_hash.as_bytes());
+        if let Some(id) = correlation_id {
+            hasher.update(b"correlation:");
+            hasher.update(id.as_bytes());
+        }
+        if let Some(tenant) = tenant_id {
+            hasher.update(b"tenant:");
+            hasher.update(tenant.as_bytes());
+        }
         hex::encode(hasher.finalize())
     }
 
     /// Verify this entry's hash is correct
+    ///
+    /// A redacted event cannot be checked against its payload hash; the
+    /// rest of the entry still is.
     pub fn verify(&self) -> bool {
+        if let Some(payload) = &self.payload_hash {
+            if !self.is_redacted() && *payload != payload_hash(&self.event) {
+                return false;
+            }
+        }
         let computed = Self::compute_hash(
             self.sequence,
             self.timestamp,
             &self.event,
+            self.payload_hash.as_deref(),
             &self.source,
             &self.prev_hash,
+            self.correlation_id.as_deref(),
+            self.tenant_id.as_deref(),
         );
         computed == self.hash
     }
+
+    /// Whether the event has been redacted
+    pub fn is_redacted(&self) -> bool {
+        matches!(self.event, AuditEventType::Redacted { .. })
+    }
+}
+
+/// SHA-256 of a serialized event (hex)
+fn payload_hash(event: &AuditEventType) -> String {
+    hex::encode(Sha256::digest(serde_json::to_string(event).unwrap_or_default().as_bytes()))
+}
+
+impl AuditEventType {
+    /// The variant name (e.g. `ModuleLoaded`), as it appears in serialized entries
+    pub fn kind(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map.keys().next().cloned().unwrap_or_default(),
+            Ok(serde_json::Value::String(name)) => name,
+            _ => String::new(),
+        }
+    }
 }
 
 /// A single audit event before it's been logged
@@ -123,6 +520,10 @@ pub struct AuditLogConfig {
     pub max_entries: usize,
     /// Whether to enable verbose logging
     pub verbose: bool,
+    /// What to refuse while segment writes fail
+    pub failure_policy: AuditFailurePolicy,
+    /// Unwritten entries kept for retry; entries beyond this are lost
+    pub max_pending_entries: usize,
 }
 
 impl Default for AuditLogConfig {
@@ -130,10 +531,91 @@ impl Default for AuditLogConfig {
         Self {
             max_entries: 10_000,
             verbose: false,
+            failure_policy: AuditFailurePolicy::default(),
+            max_pending_entries: DEFAULT_MAX_PENDING_ENTRIES,
+        }
+    }
+}
+
+/// How the kernel degrades while audit entries cannot be persisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFailurePolicy {
+    /// Refuse privileged operations from the first failed write until the
+    /// buffered entries are written
+    #[default]
+    Block,
+    /// Keep going while unwritten entries fit in the buffer; refuse
+    /// privileged operations once it is full and entries start being lost
+    Buffer,
+}
+
+impl AuditFailurePolicy {
+    /// Name used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditFailurePolicy::Block => "block",
+            AuditFailurePolicy::Buffer => "buffer",
+        }
+    }
+}
+
+/// An unrecognized failure policy name
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Unknown audit failure policy {0} (expected block or buffer)")]
+pub struct UnknownFailurePolicy(pub String);
+
+impl std::str::FromStr for AuditFailurePolicy {
+    type Err = UnknownFailurePolicy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "block" => Ok(Self::Block),
+            "buffer" => Ok(Self::Buffer),
+            _ => Err(UnknownFailurePolicy(s.to_string())),
         }
     }
 }
 
+/// Whether entries are reaching the segment files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AuditHealth {
+    /// When segment writes started failing (Unix millis); `None` while healthy
+    pub failing_since: Option<u64>,
+    /// The latest write error while failing
+    pub last_error: Option<String>,
+    /// Entries waiting to be written
+    pub pending_entries: usize,
+    /// Entries lost to a full buffer since startup; full verification reports the gaps
+    pub lost_entries: u64,
+    /// Privileged operations are refused under the failure policy
+    pub blocks_privileged: bool,
+}
+
+impl AuditHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.failing_since.is_none()
+    }
+}
+
+/// A position in the chain: an entry's sequence number and hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChainPoint {
+    sequence: u64,
+    hash: String,
+}
+
+impl ChainPoint {
+    /// The point before the first entry
+    fn genesis() -> Self {
+        Self { sequence: 0, hash: genesis_hash() }
+    }
+}
+
+pub(crate) fn genesis_hash() -> String {
+    hex::encode(Sha256::digest(b"ESTA-KERNEL-GENESIS"))
+}
+
 /// The append-only audit log
 pub struct AuditLog {
     /// Log entries stored in memory (bounded)
@@ -142,22 +624,103 @@ pub struct AuditLog {
     sequence: Arc<RwLock<u64>>,
     /// Hash of the last entry
     last_hash: Arc<RwLock<String>>,
+    /// The entry just before the oldest one in memory (trimmed or persisted earlier)
+    anchor: Arc<RwLock<ChainPoint>>,
+    /// Last entry checked by incremental verification
+    verified: Arc<Mutex<ChainPoint>>,
+    /// Directory of persisted segment files
+    segment_dir: Option<PathBuf>,
+    segment_entries: u64,
+    /// Progress of the latest full verification
+    full_verification: Arc<watch::Sender<VerificationProgress>>,
+    /// New entries, for live subscribers
+    broadcast: broadcast::Sender<AuditEntry>,
+    /// Entries whose segment write failed, oldest first
+    pending: Arc<Mutex<VecDeque<AuditEntry>>>,
+    /// Segment write health
+    health: Arc<watch::Sender<AuditHealth>>,
+    /// Key checkpoints are signed with
+    checkpoint_signer: Option<Arc<ModuleSigner>>,
+    /// Time entries, checkpoints, and redactions are stamped with
+    clock: Arc<dyn Clock>,
     /// Configuration
     config: AuditLogConfig,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<crate::chaos::Chaos>>,
 }
 
 impl AuditLog {
     /// Create a new audit log with the given configuration
     pub fn new(config: AuditLogConfig) -> Self {
-        // Genesis hash - the starting point of the chain
-        let genesis_hash = hex::encode(Sha256::digest(b"ESTA-KERNEL-GENESIS"));
-
         Self {
             entries: Arc::new(RwLock::new(VecDeque::with_capacity(config.max_entries))),
             sequence: Arc::new(RwLock::new(0)),
-            last_hash: Arc::new(RwLock::new(genesis_hash)),
+            // Genesis hash - the starting point of the chain
+            last_hash: Arc::new(RwLock::new(genesis_hash())),
+            anchor: Arc::new(RwLock::new(ChainPoint::genesis())),
+            verified: Arc::new(Mutex::new(ChainPoint::genesis())),
+            segment_dir: None,
+            segment_entries: DEFAULT_SEGMENT_ENTRIES,
+            full_verification: Arc::new(watch::channel(VerificationProgress::default()).0),
+            broadcast: broadcast::channel(BROADCAST_CAPACITY).0,
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            health: Arc::new(watch::channel(AuditHealth::default()).0),
+            checkpoint_signer: None,
+            clock: Arc::new(SystemClock),
             config,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+
+    /// Persist entries to segment files in a directory, continuing its chain
+    ///
+    /// The sequence and hash chain resume from the last persisted entry; the
+    /// in-memory log starts empty. A partially written final line, left by a
+    /// crash mid-append, is cut off.
+    pub fn with_segments(config: AuditLogConfig, dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+
+        let mut log = Self::new(config);
+        if let Some(last) = list_segments(&dir)?.last() {
+            let tail = resume_segment(last)?;
+            log.sequence = Arc::new(RwLock::new(tail.sequence));
+            log.last_hash = Arc::new(RwLock::new(tail.hash.clone()));
+            log.anchor = Arc::new(RwLock::new(tail));
         }
+        log.segment_dir = Some(dir);
+        Ok(log)
+    }
+
+    /// Start a new segment file every `entries` entries
+    pub fn with_segment_entries(mut self, entries: u64) -> Self {
+        self.segment_entries = entries.max(1);
+        self
+    }
+
+    /// Sign checkpoints with `signer`, whose public key verifiers trust
+    pub fn with_checkpoint_signer(mut self, signer: ModuleSigner) -> Self {
+        self.checkpoint_signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Stamp entries with `clock` instead of the kernel wall clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Drop segment writes according to a chaos schedule
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Arc<crate::chaos::Chaos>) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Directory of persisted segment files, if any
+    pub fn segment_dir(&self) -> Option<&Path> {
+        self.segment_dir.as_deref()
     }
 
     /// Create with default configuration
@@ -165,65 +728,221 @@ impl AuditLog {
         Self::new(AuditLogConfig::default())
     }
 
-    fn current_timestamp() -> u64 {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_millis() as u64)
-            .unwrap_or(0)
+    fn current_timestamp(&self) -> u64 {
+        self.clock.now_millis()
     }
 
     /// Append a new event to the log
     ///
     /// This is the only way to add entries - existing entries cannot be modified.
     pub async fn append(&self, event: AuditEvent) -> AuditEntry {
+        self.append_batch(vec![event]).await.pop().expect("one entry per event")
+    }
+
+    /// Append several events as contiguous entries
+    ///
+    /// The log's locks are taken once for the whole batch, so no other entry
+    /// lands between them, and consecutive entries are written to their
+    /// segment file together. Entries are returned in the order given.
+    pub async fn append_batch(&self, events: Vec<AuditEvent>) -> Vec<AuditEntry> {
+        let correlation_id = crate::correlation::current();
+        let tenant_id = crate::tenant::current();
+        let events = events
+            .into_iter()
+            .map(|event| (event, correlation_id.clone(), tenant_id.clone()))
+            .collect();
+        self.append_scoped(events).await
+    }
+
+    /// Append events, each with the correlation and tenant it was raised under
+    async fn append_scoped(&self, events: Vec<(AuditEvent, Option<String>, Option<String>)>) -> Vec<AuditEntry> {
+        if events.is_empty() {
+            return Vec::new();
+        }
         let mut entries = self.entries.write().await;
         let mut seq = self.sequence.write().await;
         let mut last_hash = self.last_hash.write().await;
 
-        *seq += 1;
-        let sequence = *seq;
-        let timestamp = Self::current_timestamp();
-        let prev_hash = last_hash.clone();
-
-        let hash = AuditEntry::compute_hash(
-            sequence,
-            timestamp,
-            &event.event_type,
-            &event.source,
-            &prev_hash,
-        );
+        let timestamp = self.current_timestamp();
+        let batch: Vec<AuditEntry> = events
+            .into_iter()
+            .map(|(event, correlation_id, tenant_id)| {
+                *seq += 1;
+                let sequence = *seq;
+                let prev_hash = last_hash.clone();
+                let payload_hash = payload_hash(&event.event_type);
+
+                let hash = AuditEntry::compute_hash(
+                    sequence,
+                    timestamp,
+                    &event.event_type,
+                    Some(&payload_hash),
+                    &event.source,
+                    &prev_hash,
+                    correlation_id.as_deref(),
+                    tenant_id.as_deref(),
+                );
+                *last_hash = hash.clone();
+
+                AuditEntry {
+                    sequence,
+                    timestamp,
+                    event: event.event_type,
+                    source: event.source,
+                    prev_hash,
+                    hash,
+                    correlation_id,
+                    tenant_id,
+                    payload_hash: Some(payload_hash),
+                }
+            })
+            .collect();
+
+        if let Some(dir) = self.segment_dir.as_ref() {
+            #[cfg(feature = "chaos")]
+            let persisted: Vec<AuditEntry> = batch
+                .iter()
+                .filter(|entry| {
+                    !self.chaos.as_ref().is_some_and(|chaos| {
+                        chaos.inject(crate::chaos::Fault::DroppedAuditWrite, &entry.sequence.to_string())
+                    })
+                })
+                .cloned()
+                .collect();
+            #[cfg(not(feature = "chaos"))]
+            let persisted = batch.clone();
+            self.write_pending(dir, persisted).await;
+        }
 
-        let entry = AuditEntry {
-            sequence,
-            timestamp,
-            event: event.event_type,
-            source: event.source,
-            prev_hash,
-            hash: hash.clone(),
-        };
+        for entry in &batch {
+            // Trim if needed, remembering where the chain continues from
+            if entries.len() >= self.config.max_entries {
+                if let Some(trimmed) = entries.pop_front() {
+                    *self.anchor.write().await = ChainPoint { sequence: trimmed.sequence, hash: trimmed.hash };
+                }
+            }
 
-        *last_hash = hash;
+            entries.push_back(entry.clone());
+            // No subscribers is not an error
+            let _ = self.broadcast.send(entry.clone());
 
-        // Trim if needed
-        if entries.len() >= self.config.max_entries {
-            entries.pop_front();
+            if self.config.verbose {
+                log::info!("Audit: {:?}", entry.event);
+            }
         }
 
-        entries.push_back(entry.clone());
+        batch
+    }
 
-        if self.config.verbose {
-            log::info!("Audit: {:?}", entry.event);
-        }
+    /// A handle that buffers events and appends them in batches of `capacity`
+    pub fn writer(self: &Arc<Self>, capacity: usize) -> AuditWriter {
+        AuditWriter::new(self.clone(), capacity)
+    }
 
-        entry
+    /// Receive entries appended from now on that match `filter`
+    pub fn subscribe(&self, filter: AuditFilter) -> AuditSubscription {
+        AuditSubscription { receiver: self.broadcast.subscribe(), filter }
     }
 
     /// Log a module loaded event
     pub async fn log_module_loaded(&self, module_name: &str, checksum: &str, source: &str) -> AuditEntry {
+        self.log_module_launched(module_name, checksum, None, None, source).await
+    }
+
+    /// Log a module loaded event, with the sandbox profile it runs under and its ABI version
+    pub async fn log_module_launched(
+        &self,
+        module_name: &str,
+        checksum: &str,
+        sandbox: Option<SandboxProfile>,
+        abi_version: Option<u32>,
+        source: &str,
+    ) -> AuditEntry {
         self.append(AuditEvent::new(
             AuditEventType::ModuleLoaded {
                 module_name: module_name.into(),
                 checksum: checksum.into(),
+                sandbox,
+                abi_version,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a module refused for an import its capabilities do not allow
+    pub async fn log_module_import_denied(
+        &self,
+        module_name: &str,
+        import: &str,
+        capability: Option<&str>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleImportDenied {
+                module_name: module_name.into(),
+                import: import.into(),
+                capability: capability.map(Into::into),
+            },
+            source,
+        )).await
+    }
+
+    /// Log the host function imports a module was loaded without the capabilities for
+    pub async fn log_module_imports_stubbed(
+        &self,
+        module_name: &str,
+        imports: BTreeMap<String, String>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleImportsStubbed {
+                module_name: module_name.into(),
+                imports,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a host function call refused for a capability the module lacks
+    #[allow(clippy::too_many_arguments)]
+    pub async fn log_host_call_denied(
+        &self,
+        module_name: &str,
+        function_name: &str,
+        import: &str,
+        capability: &str,
+        right: CapabilityRight,
+        resource: ResourceType,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::HostCallDenied {
+                module_name: module_name.into(),
+                function_name: function_name.into(),
+                import: import.into(),
+                capability: capability.into(),
+                right: right.as_str().into(),
+                resource,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a module rolled back to a previously installed version
+    pub async fn log_module_rolled_back(
+        &self,
+        module_name: &str,
+        from_version: Option<&str>,
+        to_version: &str,
+        checksum: &str,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleRolledBack {
+                module_name: module_name.into(),
+                from_version: from_version.map(Into::into),
+                to_version: to_version.into(),
+                checksum: checksum.into(),
             },
             source,
         )).await
@@ -235,6 +954,31 @@ impl AuditLog {
             AuditEventType::ModuleCrashed {
                 module_name: module_name.into(),
                 error: error.into(),
+                backtrace: Vec::new(),
+                trap: None,
+                crash_dump: None,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a module trap with its kind, symbolized backtrace, and crash dump
+    pub async fn log_module_trapped(
+        &self,
+        module_name: &str,
+        error: &str,
+        trap: TrapKind,
+        backtrace: Vec<BacktraceFrame>,
+        crash_dump: Option<&str>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleCrashed {
+                module_name: module_name.into(),
+                error: error.into(),
+                backtrace,
+                trap: Some(trap),
+                crash_dump: crash_dump.map(str::to_string),
             },
             source,
         )).await
@@ -264,6 +1008,30 @@ impl AuditLog {
             AuditEventType::CapabilityDenied {
                 cap_id: cap_id.into(),
                 reason: reason.into(),
+                owner: None,
+                rights: Vec::new(),
+            },
+            source,
+        )).await
+    }
+
+    /// Log an invocation input or output over the kernel's size limit
+    pub async fn log_memory_limit_exceeded(
+        &self,
+        module_name: &str,
+        function: &str,
+        resource: &str,
+        size: u64,
+        limit: u64,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::MemoryLimitExceeded {
+                module_name: module_name.into(),
+                limit,
+                function: Some(function.into()),
+                resource: Some(resource.into()),
+                size: Some(size),
             },
             source,
         )).await
@@ -280,6 +1048,31 @@ impl AuditLog {
         )).await
     }
 
+    /// Log an invocation stopped for exceeding its module's resource profile
+    #[allow(clippy::too_many_arguments)]
+    pub async fn log_resource_anomaly(
+        &self,
+        module_name: &str,
+        function: &str,
+        resource: &str,
+        used: u64,
+        limit: u64,
+        p99: u64,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ResourceAnomaly {
+                module_name: module_name.into(),
+                function: function.into(),
+                resource: resource.into(),
+                used,
+                limit,
+                p99,
+            },
+            source,
+        )).await
+    }
+
     /// Log an execution completed event
     pub async fn log_execution_completed(
         &self,
@@ -298,95 +1091,915 @@ impl AuditLog {
         )).await
     }
 
-    /// Log a custom event
-    pub async fn log_custom(&self, category: &str, message: &str, source: &str) -> AuditEntry {
+    /// Log an invocation answered from the result cache
+    pub async fn log_execution_cache_hit(
+        &self,
+        module_name: &str,
+        function: &str,
+        input_hash: &str,
+        source: &str,
+    ) -> AuditEntry {
         self.append(AuditEvent::new(
-            AuditEventType::Custom {
-                category: category.into(),
-                message: message.into(),
+            AuditEventType::ExecutionCacheHit {
+                module_name: module_name.into(),
+                function: function.into(),
+                input_hash: input_hash.into(),
             },
             source,
         )).await
     }
 
-    /// Get all entries (for export or analysis)
-    pub async fn get_all_entries(&self) -> Vec<AuditEntry> {
-        let entries = self.entries.read().await;
-        entries.iter().cloned().collect()
+    /// Log an execution failed event
+    pub async fn log_execution_failed(
+        &self,
+        module_name: &str,
+        function: &str,
+        error: &str,
+        trap: Option<TrapKind>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ExecutionFailed {
+                module_name: module_name.into(),
+                function: function.into(),
+                error: error.into(),
+                trap,
+            },
+            source,
+        )).await
     }
 
-    /// Get entries after a specific sequence number
-    pub async fn get_entries_after(&self, after_sequence: u64) -> Vec<AuditEntry> {
-        let entries = self.entries.read().await;
-        entries
-            .iter()
-            .filter(|e| e.sequence > after_sequence)
-            .cloned()
-            .collect()
+    /// Log that an invocation was cancelled before it finished
+    pub async fn log_invocation_cancelled(
+        &self,
+        module_name: &str,
+        function: &str,
+        invocation_id: &str,
+        fuel_used: u64,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::InvocationCancelled {
+                module_name: module_name.into(),
+                function: function.into(),
+                invocation_id: invocation_id.into(),
+                fuel_used,
+            },
+            source,
+        )).await
     }
 
-    /// Get entries within a time range
-    pub async fn get_entries_in_range(&self, start: u64, end: u64) -> Vec<AuditEntry> {
-        let entries = self.entries.read().await;
-        entries
-            .iter()
-            .filter(|e| e.timestamp >= start && e.timestamp <= end)
-            .cloned()
-            .collect()
+    /// Log that an invocation's input and output were archived
+    pub async fn log_invocation_archived(
+        &self,
+        module_name: &str,
+        function: &str,
+        input_hash: &str,
+        output_hash: &str,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::InvocationArchived {
+                module_name: module_name.into(),
+                function: function.into(),
+                input_hash: input_hash.into(),
+                output_hash: output_hash.into(),
+            },
+            source,
+        )).await
     }
 
-    /// Get entries by source
-    pub async fn get_entries_by_source(&self, source: &str) -> Vec<AuditEntry> {
-        let entries = self.entries.read().await;
-        entries
-            .iter()
-            .filter(|e| e.source == source)
-            .cloned()
-            .collect()
+    /// Log a successful invocation so it can be replayed
+    pub async fn log_invocation_recorded(&self, record: InvocationRecord, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(AuditEventType::InvocationRecorded(record), source)).await
     }
 
-    /// Verify the integrity of the entire log chain
-    pub async fn verify_chain(&self) -> ChainVerification {
-        let entries = self.entries.read().await;
-        
-        if entries.is_empty() {
-            return ChainVerification {
-                valid: true,
-                entries_checked: 0,
-                first_invalid: None,
-            };
-        }
-
-        let genesis_hash = hex::encode(Sha256::digest(b"ESTA-KERNEL-GENESIS"));
-        let mut prev_hash = genesis_hash;
-
-        for entry in entries.iter() {
-            // Verify this entry's hash
-            if !entry.verify() {
-                return ChainVerification {
-                    valid: false,
-                    entries_checked: entry.sequence,
-                    first_invalid: Some(entry.sequence),
-                };
+    /// Log the outcome of replaying a range of recorded invocations
+    pub async fn log_replay_completed(&self, report: &ReplayReport, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ReplayCompleted {
+                first_sequence: report.first_sequence,
+                last_sequence: report.last_sequence,
+                invocations: report.invocations.len(),
+                matched: report.matched(),
+                diverged: report.diverged(),
+            },
+            source,
+        )).await
+    }
+
+    /// Log that a new tenant policy version was recorded
+    pub async fn log_tenant_created(&self, tenant_id: &str, namespace: &str, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::TenantCreated {
+                tenant_id: tenant_id.into(),
+                namespace: namespace.into(),
+            },
+            source,
+        )).await
+    }
+
+    pub async fn log_tenant_archived(&self, tenant_id: &str, capabilities_revoked: usize, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::TenantArchived {
+                tenant_id: tenant_id.into(),
+                capabilities_revoked,
+            },
+            source,
+        )).await
+    }
+
+    pub async fn log_tenant_purged(&self, report: &TenantPurgeReport, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(AuditEventType::TenantPurged(report.clone()), source)).await
+    }
+
+    pub async fn log_policy_version_recorded(
+        &self,
+        tenant_id: &str,
+        version: u32,
+        effective_from: &str,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::PolicyVersionRecorded {
+                tenant_id: tenant_id.into(),
+                version,
+                effective_from: effective_from.into(),
+            },
+            source,
+        )).await
+    }
+
+    /// Log a change to a tenant's configuration of a module
+    pub async fn log_module_config_changed(
+        &self,
+        tenant_id: &str,
+        module_name: &str,
+        removed: bool,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleConfigChanged {
+                tenant_id: tenant_id.into(),
+                module_name: module_name.into(),
+                removed,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a new version of a tenant's report template
+    pub async fn log_report_template_recorded(
+        &self,
+        tenant_id: &str,
+        name: &str,
+        version: u32,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ReportTemplateRecorded {
+                tenant_id: tenant_id.into(),
+                name: name.into(),
+                version,
+            },
+            source,
+        )).await
+    }
+
+    /// Log that a tenant opted in to or out of usage pattern insights
+    pub async fn log_usage_insights_changed(
+        &self,
+        tenant_id: &str,
+        enabled: bool,
+        effective_from: &str,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::UsageInsightsChanged {
+                tenant_id: tenant_id.into(),
+                enabled,
+                effective_from: effective_from.into(),
+            },
+            source,
+        )).await
+    }
+
+    /// Log a dry-run execution (the only audit trace a dry run leaves)
+    pub async fn log_dry_run_executed(
+        &self,
+        module_name: &str,
+        function_name: &str,
+        tenant_id: Option<&str>,
+        succeeded: bool,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::DryRunExecuted {
+                module_name: module_name.into(),
+                function_name: function_name.into(),
+                tenant_id: tenant_id.map(Into::into),
+                succeeded,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a usage pattern analysis run for a tenant
+    pub async fn log_usage_insights_computed(
+        &self,
+        tenant_id: &str,
+        employees_analyzed: usize,
+        insights: usize,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::UsageInsightsComputed {
+                tenant_id: tenant_id.into(),
+                employees_analyzed,
+                insights,
+            },
+            source,
+        )).await
+    }
+
+    /// Log the redaction of a tenant's employee data past its retention period
+    pub async fn log_retention_applied(&self, report: &RetentionReport, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::RetentionApplied {
+                tenant_id: report.tenant_id.clone(),
+                cutoff: report.cutoff,
+                ledger_events: report.ledger_events,
+                audit_entries: report.audit_entries,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a storage vacuum and the space it reclaimed
+    pub async fn log_storage_vacuumed(
+        &self,
+        temp_files_removed: usize,
+        archived_blobs_removed: usize,
+        versions_pruned: usize,
+        bytes_reclaimed: u64,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::StorageVacuumed {
+                temp_files_removed,
+                archived_blobs_removed,
+                versions_pruned,
+                bytes_reclaimed,
+            },
+            source,
+        )).await
+    }
+
+    /// Log that on-disk data has grown past the configured size limit
+    pub async fn log_storage_limit_exceeded(&self, total_bytes: u64, limit_bytes: u64, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::StorageLimitExceeded { total_bytes, limit_bytes },
+            source,
+        )).await
+    }
+
+    /// Log which trusted key verified a module's signature
+    pub async fn log_module_signature_verified(
+        &self,
+        module_name: &str,
+        key_id: &str,
+        retiring: bool,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::SignatureVerified {
+                module_name: module_name.into(),
+                key_id: key_id.into(),
+                retiring,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a module signing key rotation
+    pub async fn log_signing_key_rotated(
+        &self,
+        key_id: &str,
+        retiring_key_ids: Vec<String>,
+        retires_at: Option<u64>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::SigningKeyRotated { key_id: key_id.into(), retiring_key_ids, retires_at },
+            source,
+        )).await
+    }
+
+    /// Log a reload of the kernel's execution configuration
+    pub async fn log_config_reloaded(&self, changed: &[&str], restart_required: &[&str], source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ConfigReloaded {
+                changed: changed.iter().map(|name| name.to_string()).collect(),
+                restart_required: restart_required.iter().map(|name| name.to_string()).collect(),
+            },
+            source,
+        )).await
+    }
+
+    /// Log a capability secret rotation
+    pub async fn log_capability_secret_rotated(
+        &self,
+        generation: Option<u32>,
+        reissued: usize,
+        revoked: usize,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::CapabilitySecretRotated { generation, reissued, revoked },
+            source,
+        )).await
+    }
+
+    /// Log a backup written by [`crate::Kernel::create_backup`]
+    pub async fn log_backup_created(&self, summary: &BackupSummary, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::BackupCreated {
+                signer: summary.signer.clone(),
+                tenants: summary.tenants,
+                ledger_events: summary.ledger_events,
+                audit_entries: summary.audit_entries,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a backup restored by [`crate::Kernel::restore_backup`]
+    pub async fn log_backup_restored(
+        &self,
+        summary: &BackupSummary,
+        tenants_added: usize,
+        ledger_events: usize,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::BackupRestored {
+                signer: summary.signer.clone(),
+                created_at: summary.created_at,
+                tenants_added,
+                ledger_events,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a rotation of the database field key
+    pub async fn log_database_key_rotated(&self, key_id: &str, resealed: usize, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::DatabaseKeyRotated { key_id: key_id.into(), resealed },
+            source,
+        )).await
+    }
+
+    /// Log how a module was shut down
+    pub async fn log_module_shutdown(
+        &self,
+        module_name: &str,
+        signal: &str,
+        aborted_invocations: usize,
+        start_task_aborted: bool,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleShutdown {
+                module_name: module_name.into(),
+                signal: signal.into(),
+                aborted_invocations,
+                start_task_aborted,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a module unloaded on request
+    #[allow(clippy::too_many_arguments)]
+    pub async fn log_module_unloaded(
+        &self,
+        module_name: &str,
+        signal: &str,
+        pending_invocations: usize,
+        start_task_aborted: bool,
+        capabilities_revoked: usize,
+        stats_flushed: bool,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::ModuleUnloaded {
+                module_name: module_name.into(),
+                signal: signal.into(),
+                pending_invocations,
+                start_task_aborted,
+                capabilities_revoked,
+                stats_flushed,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a WASI file open, allowed or denied
+    pub async fn log_wasi_file_access(
+        &self,
+        module_name: &str,
+        path: &str,
+        write: bool,
+        allowed: bool,
+        reason: Option<String>,
+        source: &str,
+    ) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::WasiFileAccess {
+                module_name: module_name.into(),
+                path: path.into(),
+                write,
+                allowed,
+                reason,
+            },
+            source,
+        )).await
+    }
+
+    /// Log a custom event
+    pub async fn log_custom(&self, category: &str, message: &str, source: &str) -> AuditEntry {
+        self.append(AuditEvent::new(
+            AuditEventType::Custom {
+                category: category.into(),
+                message: message.into(),
+            },
+            source,
+        )).await
+    }
+
+    /// Redact the events of a tenant's entries written before `before` (Unix millis)
+    ///
+    /// Entry hashes are unchanged, so the chain still verifies. Persisted
+    /// segments are rewritten atomically. Entries written before payload
+    /// hashes were recorded cannot be redacted without breaking the chain;
+    /// they are left alone and counted as skipped.
+    pub async fn redact_tenant(&self, tenant_id: &str, before: u64) -> Result<AuditRedaction> {
+        // Holding the entries lock keeps appends out of the segments being rewritten
+        let mut entries = self.entries.write().await;
+        let redacted_at = self.current_timestamp();
+        let mut redacted = BTreeSet::new();
+        let mut skipped = BTreeSet::new();
+        let mut redact = |entry: &mut AuditEntry| {
+            if entry.tenant_id.as_deref() != Some(tenant_id) || entry.timestamp >= before || entry.is_redacted() {
+                return false;
+            }
+            if entry.payload_hash.is_none() {
+                skipped.insert(entry.sequence);
+                return false;
+            }
+            entry.event = AuditEventType::Redacted { kind: entry.event.kind(), redacted_at };
+            redacted.insert(entry.sequence);
+            true
+        };
+
+        entries.iter_mut().for_each(|entry| {
+            redact(entry);
+        });
+        self.pending.lock().await.iter_mut().for_each(|entry| {
+            redact(entry);
+        });
+        if let Some(dir) = &self.segment_dir {
+            for path in list_segments(dir)? {
+                let text = tokio::fs::read_to_string(&path).await.with_context(|| format!("reading {}", path.display()))?;
+                let mut rewritten = String::with_capacity(text.len());
+                let mut changed = false;
+                for line in text.lines() {
+                    // Unreadable lines are kept as they are, for verification to report
+                    let redacted = serde_json::from_str::<AuditEntry>(line)
+                        .ok()
+                        .and_then(|mut entry| redact(&mut entry).then_some(entry));
+                    match redacted {
+                        Some(entry) => {
+                            rewritten.push_str(&serde_json::to_string(&entry)?);
+                            changed = true;
+                        }
+                        None => rewritten.push_str(line),
+                    }
+                    rewritten.push('\n');
+                }
+                if changed {
+                    let tmp = path.with_extension("tmp");
+                    tokio::fs::write(&tmp, rewritten).await?;
+                    tokio::fs::rename(&tmp, &path).await.with_context(|| format!("rewriting {}", path.display()))?;
+                }
+            }
+        }
+
+        Ok(AuditRedaction { redacted: redacted.len(), skipped: skipped.len() })
+    }
+
+    /// Get all entries (for export or analysis)
+    pub async fn get_all_entries(&self) -> Vec<AuditEntry> {
+        let entries = self.entries.read().await;
+        entries.iter().cloned().collect()
+    }
+
+    /// Get entries after a specific sequence number
+    pub async fn get_entries_after(&self, after_sequence: u64) -> Vec<AuditEntry> {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .filter(|e| e.sequence > after_sequence)
+            .cloned()
+            .collect()
+    }
+
+    /// Get entries within a time range
+    pub async fn get_entries_in_range(&self, start: u64, end: u64) -> Vec<AuditEntry> {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .filter(|e| e.timestamp >= start && e.timestamp <= end)
+            .cloned()
+            .collect()
+    }
+
+    /// Get entries by source
+    pub async fn get_entries_by_source(&self, source: &str) -> Vec<AuditEntry> {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .filter(|e| e.source == source)
+            .cloned()
+            .collect()
+    }
+
+    /// Open a reader over the persisted segments, if the log is persisted
+    pub fn segment_reader(&self) -> Result<Option<AuditSegmentReader>> {
+        self.segment_dir.as_deref().map(AuditSegmentReader::open).transpose()
+    }
+
+    /// Size and extent of the persisted log; `None` when not persisted or empty
+    pub async fn archived_stats(&self) -> Result<Option<ArchivedAuditStats>> {
+        let Some(dir) = self.segment_dir.clone() else {
+            return Ok(None);
+        };
+        tokio::task::spawn_blocking(move || AuditSegmentReader::open(&dir)?.stats()).await?
+    }
+
+    /// Entries matching a query, in sequence order (oldest first), up to `limit`
+    ///
+    /// Served from memory when it reaches back far enough; older entries are
+    /// read from the persisted segments by binary search, without loading
+    /// whole segments.
+    pub async fn query(&self, query: &AuditQuery, limit: usize) -> Result<Vec<AuditEntry>> {
+        {
+            let entries = self.entries.read().await;
+            let anchor = self.anchor.read().await;
+            let in_memory = self.segment_dir.is_none()
+                || anchor.sequence == 0
+                || query.after_sequence.is_some_and(|after| after >= anchor.sequence)
+                || query.from_timestamp.is_some_and(|from| entries.front().is_some_and(|e| e.timestamp < from));
+            if in_memory {
+                return Ok(entries.iter().filter(|e| query.matches(e)).take(limit).cloned().collect());
+            }
+        }
+
+        let Some(dir) = self.segment_dir.clone() else {
+            return Ok(Vec::new());
+        };
+        let query = query.clone();
+        tokio::task::spawn_blocking(move || {
+            let reader = AuditSegmentReader::open(&dir)?;
+            let entries = match (query.after_sequence, query.from_timestamp) {
+                (Some(after), _) => reader.from_sequence(after.saturating_add(1))?,
+                (None, Some(from)) => reader.from_timestamp(from)?,
+                (None, None) => reader.from_sequence(0)?,
+            };
+            let mut found = Vec::new();
+            for entry in entries {
+                let entry = entry?;
+                if found.len() >= limit || query.to_timestamp.is_some_and(|to| entry.timestamp > to) {
+                    break;
+                }
+                if query.matches(&entry) {
+                    found.push(entry);
+                }
+            }
+            Ok(found)
+        })
+        .await?
+    }
+
+    /// One page of the entries matching a query, in sequence order
+    ///
+    /// The cursor is the sequence number of the previous page's last entry.
+    pub async fn query_page(&self, query: &AuditQuery, page: &PageRequest) -> Result<Page<AuditEntry>> {
+        let after = page.cursor_key::<u64>()?;
+        let query = AuditQuery {
+            after_sequence: query.after_sequence.max(after),
+            ..query.clone()
+        };
+        let limit = page.limit();
+        let entries = self.query(&query, limit + 1).await?;
+        Ok(Page::truncate(entries, limit, |entry| entry.sequence))
+    }
+
+    /// Entries appended since a previous export, for incremental archiving
+    ///
+    /// The export starts right after `cursor` (use [`ExportCursor::genesis`]
+    /// the first time) and reads back through persisted segments as needed.
+    /// It fails if the log no longer continues the chain the cursor ended,
+    /// or if entries after it were trimmed without being persisted.
+    pub async fn export_since(&self, cursor: &ExportCursor) -> Result<AuditExport, AuditExportError> {
+        let head = self.checkpoint().await;
+        if head.sequence < cursor.sequence {
+            return Err(AuditExportError::CursorAhead { cursor: cursor.sequence, head: head.sequence });
+        }
+        if head.sequence == cursor.sequence && head.hash != cursor.hash {
+            return Err(AuditExportError::Diverged(cursor.sequence));
+        }
+        let query = AuditQuery { after_sequence: Some(cursor.sequence.saturating_sub(1)), ..AuditQuery::default() };
+        let entries = self.query(&query, usize::MAX).await.map_err(|e| AuditExportError::Read(format!("{:#}", e)))?;
+        export_since(entries, cursor)
+    }
+
+    /// Verify the integrity of the in-memory log chain
+    ///
+    /// Entries trimmed from memory, or persisted by an earlier run, are
+    /// covered by [`AuditLog::start_full_verification`] instead.
+    pub async fn verify_chain(&self) -> ChainVerification {
+        let entries = self.entries.read().await;
+        let anchor = self.anchor.read().await;
+        verify_from(anchor.hash.clone(), entries.iter())
+    }
+
+    /// Verify only the entries appended since the last incremental verification
+    ///
+    /// `entries_checked` counts the newly verified entries. The read lock is
+    /// held only while copying them. After a failure, later calls keep
+    /// re-checking from the last good entry, so the failure is reported
+    /// until the log is repaired.
+    pub async fn verify_incremental(&self) -> ChainVerification {
+        let mut verified = self.verified.lock().await;
+        let (start, pending) = {
+            let entries = self.entries.read().await;
+            let anchor = self.anchor.read().await;
+            // Entries trimmed before they were checked are left to full verification
+            let start = if verified.sequence < anchor.sequence { anchor.clone() } else { verified.clone() };
+            let pending: Vec<AuditEntry> = entries.iter().filter(|e| e.sequence > start.sequence).cloned().collect();
+            (start, pending)
+        };
+
+        let verification = verify_from(start.hash, pending.iter());
+        if verification.valid {
+            if let Some(last) = pending.last() {
+                *verified = ChainPoint { sequence: last.sequence, hash: last.hash.clone() };
+            }
+        }
+        verification
+    }
+
+    /// Verify every persisted segment from the genesis entry in a background task
+    ///
+    /// Without a segment directory the in-memory log is verified instead.
+    /// Returns a receiver for progress updates; if a verification is already
+    /// running, it is not restarted. Must be called within a tokio runtime.
+    pub async fn start_full_verification(&self) -> watch::Receiver<VerificationProgress> {
+        let progress = self.full_verification.clone();
+        let receiver = progress.subscribe();
+        let started = progress.send_if_modified(|p| {
+            if p.running {
+                return false;
+            }
+            *p = VerificationProgress { running: true, ..Default::default() };
+            true
+        });
+        if !started {
+            return receiver;
+        }
+
+        // Check up to the entry that is last now; later appends are left to the next run
+        let target = ChainPoint { sequence: *self.sequence.read().await, hash: self.last_hash.read().await.clone() };
+        match self.segment_dir.clone() {
+            Some(dir) => {
+                tokio::spawn(async move {
+                    let outcome = verify_segments(&dir, &target, &progress).await;
+                    finish_full_verification(&progress, outcome);
+                });
+            }
+            None => {
+                let entries = self.get_all_entries().await;
+                let anchor = self.anchor.read().await.clone();
+                tokio::spawn(async move {
+                    let verification = verify_from(anchor.hash, entries.iter());
+                    progress.send_modify(|p| p.entries_checked = verification.entries_checked);
+                    finish_full_verification(&progress, Ok(verification));
+                });
+            }
+        }
+        receiver
+    }
+
+    /// Progress of the latest full verification
+    pub fn full_verification_progress(&self) -> VerificationProgress {
+        self.full_verification.borrow().clone()
+    }
+
+    /// Current segment write health
+    pub fn health(&self) -> AuditHealth {
+        self.health.borrow().clone()
+    }
+
+    /// Receive segment write health as it changes, for notifications
+    pub fn watch_health(&self) -> watch::Receiver<AuditHealth> {
+        self.health.subscribe()
+    }
+
+    /// Fail if the failure policy refuses privileged operations right now
+    pub fn check_writable(&self) -> Result<(), StorageError> {
+        let health = self.health.borrow();
+        if health.blocks_privileged {
+            let error = health.last_error.as_deref().unwrap_or("segment writes are failing");
+            return Err(StorageError::AuditUnavailable(error.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Try again to write entries whose segment write failed
+    pub async fn retry_pending(&self) -> AuditHealth {
+        // Holding the entries lock keeps appends from writing in between
+        let _entries = self.entries.write().await;
+        if let Some(dir) = self.segment_dir.as_ref() {
+            self.write_pending(dir, Vec::new()).await;
+        }
+        self.health()
+    }
+
+    /// Retry failed segment writes every `interval`
+    ///
+    /// Abort the returned handle to stop.
+    pub fn schedule_retries(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if !self.health().is_healthy() {
+                    self.retry_pending().await;
+                }
             }
+        })
+    }
+
+    /// The head of the chain now, signed if the log has a checkpoint key
+    pub async fn checkpoint(&self) -> AuditCheckpoint {
+        let sequence = self.sequence.read().await;
+        let hash = self.last_hash.read().await;
+        let mut checkpoint = AuditCheckpoint {
+            sequence: *sequence,
+            hash: hash.clone(),
+            taken_at: self.current_timestamp(),
+            signature: None,
+        };
+        if let Some(signer) = &self.checkpoint_signer {
+            checkpoint.sign(signer);
+        }
+        checkpoint
+    }
 
-            // Verify chain continuity
-            if entry.prev_hash != prev_hash {
-                return ChainVerification {
-                    valid: false,
-                    entries_checked: entry.sequence,
-                    first_invalid: Some(entry.sequence),
+    /// Anchor the head of the chain with `provider`
+    ///
+    /// The receipt, or the failure, is appended to the log.
+    pub async fn anchor(&self, provider: Arc<dyn AnchorProvider>) -> Result<AnchorReceipt, AnchorError> {
+        let checkpoint = self.checkpoint().await;
+        let name = provider.name();
+        let anchored = {
+            let checkpoint = checkpoint.clone();
+            tokio::task::spawn_blocking(move || provider.anchor(&checkpoint))
+                .await
+                .unwrap_or_else(|e| Err(AnchorError::Io(e.to_string())))
+        };
+        match &anchored {
+            Ok(receipt) => {
+                self.append(AuditEvent::new(AuditEventType::CheckpointAnchored(receipt.clone()), "audit")).await;
+            }
+            Err(e) => {
+                log::warn!("Anchoring audit checkpoint {} with {} failed: {}", checkpoint.sequence, name, e);
+                let event = AuditEventType::AnchorFailed {
+                    sequence: checkpoint.sequence,
+                    provider: name,
+                    error: e.to_string(),
                 };
+                self.append(AuditEvent::new(event, "audit")).await;
+            }
+        }
+        anchored
+    }
+
+    /// Anchor the head of the chain every `interval`, skipping intervals in
+    /// which nothing but the previous receipt was logged
+    ///
+    /// A failed anchor is retried at the next interval. Abort the returned
+    /// handle to stop.
+    pub fn schedule_anchoring(self: Arc<Self>, provider: Arc<dyn AnchorProvider>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut anchored_through = None;
+            loop {
+                ticker.tick().await;
+                if Some(*self.sequence.read().await) == anchored_through {
+                    continue;
+                }
+                if self.anchor(provider.clone()).await.is_ok() {
+                    anchored_through = Some(*self.sequence.read().await);
+                }
             }
+        })
+    }
+
+    /// Queue `entry` behind any unwritten entries, then write them in order
+    /// until one fails
+    ///
+    /// Callers hold the entries lock, so writes are never interleaved.
+    async fn write_pending(&self, dir: &Path, new_entries: Vec<AuditEntry>) {
+        let mut pending = self.pending.lock().await;
+        let mut lost = 0;
+        for entry in new_entries {
+            if pending.len() < self.config.max_pending_entries.max(1) {
+                pending.push_back(entry);
+            } else {
+                log::error!("Audit entry {} lost: {} entries are waiting to be written", entry.sequence, pending.len());
+                lost += 1;
+            }
+        }
 
-            prev_hash = entry.hash.clone();
+        let mut failure = None;
+        while let Some(next) = pending.front() {
+            // Entries bound for the same segment go in one write
+            let first = self.segment_first(next.sequence);
+            let run = pending.iter().take_while(|entry| self.segment_first(entry.sequence) == first).count();
+            match self.persist(dir, first, pending.range(..run)).await {
+                Ok(()) => {
+                    pending.drain(..run);
+                }
+                Err(e) => {
+                    failure = Some(format!("{:#}", e));
+                    break;
+                }
+            }
         }
 
-        ChainVerification {
-            valid: true,
-            entries_checked: entries.len() as u64,
-            first_invalid: None,
+        let pending_entries = pending.len();
+        let full = pending_entries >= self.config.max_pending_entries.max(1);
+        let policy = self.config.failure_policy;
+        self.health.send_if_modified(|health| {
+            let before = health.clone();
+            match failure {
+                Some(error) => {
+                    if health.failing_since.is_none() {
+                        log::error!("Audit entries cannot be persisted ({} policy): {}", policy.as_str(), error);
+                        health.failing_since = Some(self.current_timestamp());
+                    }
+                    health.last_error = Some(error);
+                }
+                None => {
+                    if health.failing_since.take().is_some() {
+                        log::info!("Audit segment writes recovered");
+                    }
+                    health.last_error = None;
+                }
+            }
+            health.pending_entries = pending_entries;
+            health.lost_entries += lost;
+            health.blocks_privileged = match policy {
+                AuditFailurePolicy::Block => health.failing_since.is_some(),
+                AuditFailurePolicy::Buffer => full,
+            };
+            *health != before
+        });
+    }
+
+    /// Sequence number of the first entry of the segment holding `sequence`
+    fn segment_first(&self, sequence: u64) -> u64 {
+        (sequence - 1) / self.segment_entries * self.segment_entries + 1
+    }
+
+    /// Append entries to the segment file starting at `first`
+    async fn persist(&self, dir: &Path, first: u64, entries: impl Iterator<Item = &AuditEntry>) -> Result<()> {
+        let mut lines = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut lines, entry)?;
+            lines.push(b'\n');
         }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(segment_name(first)))
+            .await?;
+        file.write_all(&lines).await?;
+        file.flush().await?;
+        Ok(())
     }
 
     /// Get statistics about the audit log
@@ -402,6 +2015,387 @@ impl AuditLog {
     }
 }
 
+/// Verify a chain of entries, oldest first, starting from the genesis hash
+///
+/// Used for the live log and for entries exported to a file.
+pub fn verify_entries<'a>(entries: impl IntoIterator<Item = &'a AuditEntry>) -> ChainVerification {
+    verify_from(genesis_hash(), entries)
+}
+
+/// Verify a chain of entries continuing from the entry with `prev_hash`
+pub(crate) fn verify_from<'a>(mut prev_hash: String, entries: impl IntoIterator<Item = &'a AuditEntry>) -> ChainVerification {
+    let mut checked = 0;
+
+    for entry in entries {
+        // Verify this entry's hash and chain continuity
+        if !entry.verify() || entry.prev_hash != prev_hash {
+            return ChainVerification {
+                valid: false,
+                entries_checked: entry.sequence,
+                first_invalid: Some(entry.sequence),
+            };
+        }
+
+        prev_hash = entry.hash.clone();
+        checked += 1;
+    }
+
+    ChainVerification {
+        valid: true,
+        entries_checked: checked,
+        first_invalid: None,
+    }
+}
+
+fn segment_name(first_sequence: u64) -> String {
+    format!("audit-{:020}.jsonl", first_sequence)
+}
+
+/// Segment files in a directory, oldest first
+pub(crate) fn list_segments(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut segments = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if name.starts_with("audit-") && name.ends_with(".jsonl") {
+            segments.push(path);
+        }
+    }
+    // Zero-padded sequence numbers sort by name
+    segments.sort();
+    Ok(segments)
+}
+
+/// Cut off a partially written final line and return the segment's last entry
+fn resume_segment(path: &Path) -> Result<ChainPoint> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    if complete < bytes.len() {
+        log::warn!("Discarding a partially written entry at the end of {}", path.display());
+        std::fs::OpenOptions::new().write(true).open(path)?.set_len(complete as u64)?;
+    }
+
+    let last = bytes[..complete]
+        .split(|&b| b == b'\n')
+        .rfind(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .with_context(|| format!("{} has no entries", path.display()))?;
+    let entry: AuditEntry =
+        serde_json::from_slice(last).with_context(|| format!("last entry of {}", path.display()))?;
+    Ok(ChainPoint { sequence: entry.sequence, hash: entry.hash })
+}
+
+/// Verify persisted segments up to `target`, updating progress after each one
+async fn verify_segments(
+    dir: &Path,
+    target: &ChainPoint,
+    progress: &watch::Sender<VerificationProgress>,
+) -> Result<ChainVerification> {
+    let segments = list_segments(dir)?;
+    progress.send_modify(|p| p.segments_total = segments.len());
+
+    let mut prev = ChainPoint::genesis();
+    for (index, path) in segments.iter().enumerate() {
+        let text = tokio::fs::read_to_string(path).await.with_context(|| format!("reading {}", path.display()))?;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let Ok(entry) = serde_json::from_str::<AuditEntry>(line) else {
+                // An unreadable line is as broken as a bad hash
+                return Ok(broken_at(prev.sequence + 1));
+            };
+            if entry.sequence > target.sequence {
+                break;
+            }
+            if entry.sequence != prev.sequence + 1 || !entry.verify() || entry.prev_hash != prev.hash {
+                return Ok(broken_at(prev.sequence + 1));
+            }
+            prev = ChainPoint { sequence: entry.sequence, hash: entry.hash };
+        }
+        progress.send_modify(|p| {
+            p.segments_checked = index + 1;
+            p.entries_checked = prev.sequence;
+        });
+    }
+
+    // Missing or truncated segments end the chain early
+    if prev != *target {
+        return Ok(broken_at(prev.sequence + 1));
+    }
+    Ok(ChainVerification { valid: true, entries_checked: prev.sequence, first_invalid: None })
+}
+
+fn broken_at(sequence: u64) -> ChainVerification {
+    ChainVerification { valid: false, entries_checked: sequence, first_invalid: Some(sequence) }
+}
+
+fn finish_full_verification(progress: &watch::Sender<VerificationProgress>, outcome: Result<ChainVerification>) {
+    match &outcome {
+        Ok(verification) if !verification.valid => {
+            log::error!("Audit chain broken at sequence {:?}", verification.first_invalid)
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("Full audit verification failed: {:#}", e),
+    }
+    progress.send_modify(|p| {
+        p.running = false;
+        match outcome {
+            Ok(verification) => p.result = Some(verification),
+            Err(e) => p.error = Some(format!("{:#}", e)),
+        }
+    });
+}
+
+/// Progress of a background full verification
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerificationProgress {
+    /// A verification is in progress
+    pub running: bool,
+    /// Segment files to check (0 when verifying the in-memory log)
+    pub segments_total: usize,
+    pub segments_checked: usize,
+    /// Entries verified so far
+    pub entries_checked: u64,
+    /// Outcome, once finished
+    pub result: Option<ChainVerification>,
+    /// Why the verification could not finish (e.g. an unreadable directory)
+    pub error: Option<String>,
+}
+
+/// Which audit entries to return from [`AuditLog::query`]
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Only entries after this sequence number
+    pub after_sequence: Option<u64>,
+    /// Only entries at or after this time (ms since Unix epoch)
+    pub from_timestamp: Option<u64>,
+    /// Only entries at or before this time (ms since Unix epoch)
+    pub to_timestamp: Option<u64>,
+    /// Only entries from this source
+    pub source: Option<String>,
+    /// Only entries written during this request
+    pub correlation_id: Option<String>,
+    /// Only entries written on behalf of this tenant
+    pub tenant_id: Option<String>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.after_sequence.is_none_or(|after| entry.sequence > after)
+            && self.from_timestamp.is_none_or(|from| entry.timestamp >= from)
+            && self.to_timestamp.is_none_or(|to| entry.timestamp <= to)
+            && self.source.as_deref().is_none_or(|source| entry.source == source)
+            && self.correlation_id.as_deref().is_none_or(|id| entry.correlation_id.as_deref() == Some(id))
+            && self.tenant_id.as_deref().is_none_or(|id| entry.tenant_id.as_deref() == Some(id))
+    }
+}
+
+/// Which live entries a subscriber receives; empty lists match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFilter {
+    /// Sources to receive (e.g. `kernel`, `ipc`)
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Event kinds to receive (e.g. `ModuleCrashed`; see [`AuditEventType::kind`])
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        (self.sources.is_empty() || self.sources.contains(&entry.source))
+            && (self.event_types.is_empty() || self.event_types.contains(&entry.event.kind()))
+    }
+}
+
+/// Buffers audit events and appends them to the log in batches
+///
+/// For paths that write many entries at once, such as bulk imports: each
+/// batch takes the log's locks once (see [`AuditLog::append_batch`]). Events
+/// keep the correlation and tenant they were logged under. Flush before
+/// relying on the entries being in the log; events still buffered when the
+/// writer is dropped are appended from a background task.
+pub struct AuditWriter {
+    log: Arc<AuditLog>,
+    buffer: Vec<(AuditEvent, Option<String>, Option<String>)>,
+    capacity: usize,
+}
+
+impl AuditWriter {
+    /// A writer appending once `capacity` events are buffered
+    pub fn new(log: Arc<AuditLog>, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { log, buffer: Vec::with_capacity(capacity), capacity }
+    }
+
+    /// Buffer an event, appending the batch if that fills the buffer
+    ///
+    /// Returns the entries appended, if any.
+    pub async fn log(&mut self, event: AuditEvent) -> Vec<AuditEntry> {
+        self.buffer.push((event, crate::correlation::current(), crate::tenant::current()));
+        if self.buffer.len() >= self.capacity {
+            self.flush().await
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Append every buffered event
+    pub async fn flush(&mut self) -> Vec<AuditEntry> {
+        let events = std::mem::replace(&mut self.buffer, Vec::with_capacity(self.capacity));
+        self.log.append_scoped(events).await
+    }
+
+    /// Events waiting to be appended
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl Drop for AuditWriter {
+    fn drop(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let events = std::mem::take(&mut self.buffer);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                let log = self.log.clone();
+                handle.spawn(async move {
+                    log.append_scoped(events).await;
+                });
+            }
+            Err(_) => log::error!("{} buffered audit events lost: writer dropped outside the runtime", events.len()),
+        }
+    }
+}
+
+/// A live feed of new audit entries
+pub struct AuditSubscription {
+    receiver: broadcast::Receiver<AuditEntry>,
+    filter: AuditFilter,
+}
+
+/// Entries a subscriber skipped because it fell too far behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissedEntries(pub u64);
+
+impl AuditSubscription {
+    /// The next matching entry, or `None` once the log is dropped
+    ///
+    /// A subscriber more than 1024 entries behind skips ahead and receives
+    /// [`MissedEntries`] first; read what it missed with [`AuditLog::query`].
+    pub async fn recv(&mut self) -> Option<Result<AuditEntry, MissedEntries>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(entry) if self.filter.matches(&entry) => return Some(Ok(entry)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => return Some(Err(MissedEntries(missed))),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Where an incremental export left off: the last entry it included
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportCursor {
+    pub sequence: u64,
+    pub hash: String,
+}
+
+impl ExportCursor {
+    /// The cursor before the first entry, for a first export
+    pub fn genesis() -> Self {
+        Self { sequence: 0, hash: genesis_hash() }
+    }
+}
+
+impl Default for ExportCursor {
+    fn default() -> Self {
+        Self::genesis()
+    }
+}
+
+/// Entries appended since a previous export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditExport {
+    /// The cursor the export continues from; the first entry's `prev_hash`
+    /// is its hash
+    pub from: ExportCursor,
+    /// New entries, oldest first
+    pub entries: Vec<AuditEntry>,
+    /// Cursor for the next export
+    pub cursor: ExportCursor,
+}
+
+impl AuditExport {
+    /// Verify the entries chain on from the previous export
+    pub fn verify(&self) -> ChainVerification {
+        verify_from(self.from.hash.clone(), &self.entries)
+    }
+}
+
+/// Why an incremental export could not continue from its cursor
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuditExportError {
+    #[error("Audit log ends at entry {head}, before entry {cursor} of the previous export")]
+    CursorAhead { cursor: u64, head: u64 },
+
+    #[error("Audit entry {0} does not match the previous export")]
+    Diverged(u64),
+
+    #[error("Audit entries from {expected} are no longer available; the log resumes at {found}")]
+    Gap { expected: u64, found: u64 },
+
+    #[error("Audit chain broken at sequence {0}")]
+    ChainBroken(u64),
+
+    #[error("Reading audit entries failed: {0}")]
+    Read(String),
+}
+
+/// Export the entries after `cursor`, checking they continue the chain the
+/// previous export ended
+///
+/// `entries` are oldest first and may start at or before the cursor's entry.
+pub fn export_since(
+    entries: impl IntoIterator<Item = AuditEntry>,
+    cursor: &ExportCursor,
+) -> Result<AuditExport, AuditExportError> {
+    let mut head = None;
+    let mut new = Vec::new();
+    for entry in entries {
+        head = Some(entry.sequence);
+        if entry.sequence == cursor.sequence && entry.hash != cursor.hash {
+            return Err(AuditExportError::Diverged(entry.sequence));
+        }
+        if entry.sequence > cursor.sequence {
+            new.push(entry);
+        }
+    }
+    if let Some(first) = new.first() {
+        if first.sequence != cursor.sequence + 1 {
+            return Err(AuditExportError::Gap { expected: cursor.sequence + 1, found: first.sequence });
+        }
+        if first.prev_hash != cursor.hash {
+            return Err(AuditExportError::Diverged(cursor.sequence));
+        }
+    } else if let Some(head) = head.filter(|head| *head < cursor.sequence) {
+        return Err(AuditExportError::CursorAhead { cursor: cursor.sequence, head });
+    }
+
+    let export = AuditExport {
+        from: cursor.clone(),
+        cursor: new
+            .last()
+            .map(|last| ExportCursor { sequence: last.sequence, hash: last.hash.clone() })
+            .unwrap_or_else(|| cursor.clone()),
+        entries: new,
+    };
+    if let Some(sequence) = export.verify().first_invalid {
+        return Err(AuditExportError::ChainBroken(sequence));
+    }
+    Ok(export)
+}
+
 /// Result of chain verification
 #[derive(Debug, Clone, Serialize)]
 pub struct ChainVerification {
@@ -410,6 +2404,15 @@ pub struct ChainVerification {
     pub first_invalid: Option<u64>,
 }
 
+/// What [`AuditLog::redact_tenant`] changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AuditRedaction {
+    /// Entries whose events were redacted
+    pub redacted: usize,
+    /// Entries too old to redact without breaking the chain
+    pub skipped: usize,
+}
+
 /// Statistics about the audit log
 #[derive(Debug, Clone, Serialize)]
 pub struct AuditStats {
@@ -421,6 +2424,7 @@ pub struct AuditStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::ManualClock;
 
     #[tokio::test]
     async fn test_append_and_retrieve() {
@@ -466,6 +2470,63 @@ mod tests {
         assert!(!verification.valid);
     }
 
+    #[tokio::test]
+    async fn test_redaction_keeps_chain_valid() {
+        let log = AuditLog::with_defaults();
+        crate::tenant::scope("acme".into(), log.log_custom("payroll", "e1 imported", "kernel")).await;
+        crate::tenant::scope("globex".into(), log.log_custom("payroll", "e7 imported", "kernel")).await;
+
+        // Changing an event without redacting it is still caught
+        let mut tampered = log.get_all_entries().await;
+        tampered[0].event = AuditEventType::Custom { category: "payroll".into(), message: "e2 imported".into() };
+        assert_eq!(verify_entries(&tampered).first_invalid, Some(1));
+
+        let redaction = log.redact_tenant("acme", u64::MAX).await.unwrap();
+        assert_eq!(redaction, AuditRedaction { redacted: 1, skipped: 0 });
+        let entries = log.get_all_entries().await;
+        assert!(entries[0].is_redacted() && !entries[1].is_redacted());
+        assert!(log.verify_chain().await.valid);
+
+        // Entries from before payload hashes verify as they always did
+        let mut legacy = entries[1].clone();
+        legacy.payload_hash = None;
+        legacy.prev_hash = genesis_hash();
+        legacy.hash = AuditEntry::compute_hash(
+            legacy.sequence,
+            legacy.timestamp,
+            &legacy.event,
+            None,
+            &legacy.source,
+            &legacy.prev_hash,
+            None,
+            legacy.tenant_id.as_deref(),
+        );
+        assert!(verify_entries([&legacy]).valid);
+    }
+
+    #[tokio::test]
+    async fn test_injected_clock_stamps_entries() {
+        const START: u64 = 1_700_000_000_000;
+        let clock = Arc::new(ManualClock::new(START));
+        let log = AuditLog::with_defaults().with_clock(clock.clone());
+        log.log_custom("payroll", "e1 imported", "kernel").await;
+        clock.advance(Duration::from_secs(60));
+        log.log_custom("payroll", "e2 imported", "kernel").await;
+
+        let recent = log.get_entries_in_range(START + 30_000, u64::MAX).await;
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].timestamp, START + 60_000);
+        assert_eq!(log.checkpoint().await.taken_at, START + 60_000);
+
+        // The same events at the same times replay to the same chain
+        let replay_clock = Arc::new(ManualClock::new(START));
+        let replay = AuditLog::with_defaults().with_clock(replay_clock.clone());
+        replay.log_custom("payroll", "e1 imported", "kernel").await;
+        replay_clock.set(START + 60_000);
+        replay.log_custom("payroll", "e2 imported", "kernel").await;
+        assert_eq!(replay.checkpoint().await.hash, log.checkpoint().await.hash);
+    }
+
     #[tokio::test]
     async fn test_query_by_source() {
         let log = AuditLog::with_defaults();
@@ -499,6 +2560,7 @@ mod tests {
         let config = AuditLogConfig {
             max_entries: 5,
             verbose: false,
+            ..AuditLogConfig::default()
         };
         let log = AuditLog::new(config);
 
@@ -510,6 +2572,360 @@ mod tests {
         assert_eq!(entries.len(), 5);
         // Should have entries 6-10 (most recent 5)
         assert_eq!(entries[0].sequence, 6);
+        // The chain is checked from the last trimmed entry
+        assert!(log.verify_chain().await.valid);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_verification() {
+        let log = AuditLog::with_defaults();
+        for i in 0..3 {
+            log.log_module_loaded(&format!("mod{}", i), "hash", "kernel").await;
+        }
+        assert_eq!(log.verify_incremental().await.entries_checked, 3);
+        assert_eq!(log.verify_incremental().await.entries_checked, 0);
+
+        log.log_module_loaded("mod3", "hash", "kernel").await;
+        log.log_module_loaded("mod4", "hash", "kernel").await;
+        {
+            let mut entries = log.entries.write().await;
+            entries.back_mut().unwrap().source = "tampered".into();
+        }
+        for _ in 0..2 {
+            let verification = log.verify_incremental().await;
+            assert!(!verification.valid);
+            assert_eq!(verification.first_invalid, Some(5));
+        }
+    }
+
+    async fn full_verification(log: &AuditLog) -> VerificationProgress {
+        let mut progress = log.start_full_verification().await;
+        let finished = progress.wait_for(|p| !p.running).await.unwrap().clone();
+        finished
+    }
+
+    #[tokio::test]
+    async fn test_segments_resume_and_full_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || AuditLog::with_segments(AuditLogConfig::default(), dir.path()).unwrap().with_segment_entries(2);
+
+        let log = open();
+        for i in 0..5 {
+            log.log_module_loaded(&format!("mod{}", i), "hash", "kernel").await;
+        }
+        drop(log);
+
+        // A crash mid-append leaves a torn line, which is cut off on reopening
+        let last = list_segments(dir.path()).unwrap().pop().unwrap();
+        let mut text = std::fs::read_to_string(&last).unwrap();
+        text.push_str(r#"{"sequence":6,"#);
+        std::fs::write(&last, text).unwrap();
+
+        let log = open();
+        assert_eq!(log.log_module_loaded("mod5", "hash", "kernel").await.sequence, 6);
+        assert!(log.verify_chain().await.valid);
+        assert_eq!(list_segments(dir.path()).unwrap().len(), 3);
+
+        let progress = full_verification(&log).await;
+        assert_eq!((progress.segments_total, progress.segments_checked), (3, 3));
+        let result = progress.result.unwrap();
+        assert!(result.valid);
+        assert_eq!(result.entries_checked, 6);
+
+        // Tampering with a persisted entry is caught even though it is no longer in memory
+        let first = &list_segments(dir.path()).unwrap()[1];
+        let text = std::fs::read_to_string(first).unwrap().replace("mod3", "modX");
+        std::fs::write(first, text).unwrap();
+        let result = full_verification(&log).await.result.unwrap();
+        assert_eq!(result.first_invalid, Some(4));
+
+        // So is a deleted segment
+        std::fs::write(first, std::fs::read_to_string(first).unwrap().replace("modX", "mod3")).unwrap();
+        std::fs::remove_file(list_segments(dir.path()).unwrap().pop().unwrap()).unwrap();
+        let result = full_verification(&log).await.result.unwrap();
+        assert_eq!(result.first_invalid, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_query_reads_persisted_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AuditLogConfig { max_entries: 5, ..AuditLogConfig::default() };
+        let log = AuditLog::with_segments(config, dir.path()).unwrap().with_segment_entries(4);
+        for i in 0..20 {
+            let source = if i % 2 == 0 { "kernel" } else { "supervisor" };
+            log.log_custom("test", &format!("entry {}", i), source).await;
+        }
+        let sequences = |entries: Vec<AuditEntry>| entries.iter().map(|e| e.sequence).collect::<Vec<_>>();
+
+        // Trimmed from memory, so read from the segments
+        let old = log.query(&AuditQuery { after_sequence: Some(2), ..Default::default() }, 3).await.unwrap();
+        assert_eq!(sequences(old), [3, 4, 5]);
+        let kernel = AuditQuery { source: Some("kernel".into()), ..Default::default() };
+        assert_eq!(sequences(log.query(&kernel, 4).await.unwrap()), [1, 3, 5, 7]);
+        let oldest = log.get_all_entries().await[0].timestamp;
+        let to_oldest = AuditQuery { to_timestamp: Some(oldest), ..Default::default() };
+        assert!(log.query(&to_oldest, 100).await.unwrap().iter().all(|e| e.timestamp <= oldest));
+
+        // Recent entries come from memory
+        let recent = log.query(&AuditQuery { after_sequence: Some(17), ..Default::default() }, 10).await.unwrap();
+        assert_eq!(sequences(recent), [18, 19, 20]);
+
+        let stats = log.archived_stats().await.unwrap().unwrap();
+        assert_eq!((stats.segments, stats.first_sequence, stats.last_sequence), (5, 1, 20));
+        assert!(AuditLog::with_defaults().archived_stats().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_query_pages_across_segments_and_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AuditLogConfig { max_entries: 5, ..AuditLogConfig::default() };
+        let log = AuditLog::with_segments(config, dir.path()).unwrap().with_segment_entries(4);
+        for i in 0..20 {
+            let source = if i % 2 == 0 { "kernel" } else { "supervisor" };
+            log.log_custom("test", &format!("entry {}", i), source).await;
+        }
+
+        let kernel = AuditQuery { source: Some("kernel".into()), ..Default::default() };
+        let mut request = PageRequest::first(4);
+        let mut pages = Vec::new();
+        loop {
+            let page = log.query_page(&kernel, &request).await.unwrap();
+            pages.push(page.items.iter().map(|e| e.sequence).collect::<Vec<_>>());
+            match page.next_cursor {
+                Some(cursor) => request = PageRequest::after(cursor, 4),
+                None => break,
+            }
+        }
+        assert_eq!(pages, [vec![1, 3, 5, 7], vec![9, 11, 13, 15], vec![17, 19]]);
+
+        // A cursor behind the query's own starting point does not rewind it
+        let recent = AuditQuery { after_sequence: Some(17), ..Default::default() };
+        let page = log.query_page(&recent, &PageRequest::after("2", 10)).await.unwrap();
+        assert_eq!(page.items.iter().map(|e| e.sequence).collect::<Vec<_>>(), [18, 19, 20]);
+        assert!(log.query_page(&recent, &PageRequest::after("-1", 10)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entries_carry_correlation_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::with_segments(AuditLogConfig::default(), dir.path()).unwrap();
+        log.log_custom("test", "outside", "kernel").await;
+        crate::correlation::scope("req-7".to_string(), async {
+            log.log_custom("test", "first", "kernel").await;
+            log.log_custom("test", "second", "supervisor").await;
+        })
+        .await;
+
+        let entries = log.get_all_entries().await;
+        assert_eq!(entries[0].correlation_id, None);
+        assert_eq!(entries[2].correlation_id.as_deref(), Some("req-7"));
+        assert!(log.verify_chain().await.valid);
+
+        // The ID is covered by the hash
+        let mut tampered = entries[1].clone();
+        tampered.correlation_id = Some("req-8".into());
+        assert!(!tampered.verify());
+
+        let request = AuditQuery { correlation_id: Some("req-7".into()), ..Default::default() };
+        assert_eq!(log.query(&request, 10).await.unwrap().len(), 2);
+        let persisted = log.segment_reader().unwrap().unwrap().entry(3).unwrap().unwrap();
+        assert_eq!(persisted.correlation_id.as_deref(), Some("req-7"));
+    }
+
+    #[tokio::test]
+    async fn test_batched_appends() {
+        let custom = |message: &str| {
+            AuditEvent::new(AuditEventType::Custom { category: "test".into(), message: message.into() }, "kernel")
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let log = Arc::new(AuditLog::with_segments(AuditLogConfig::default(), dir.path()).unwrap().with_segment_entries(3));
+        log.log_custom("test", "before", "kernel").await;
+
+        // A batch is contiguous, and split across segments where it crosses one
+        let batch = log.append_batch((0..4).map(|i| custom(&format!("batch {}", i))).collect()).await;
+        assert_eq!(batch.iter().map(|e| e.sequence).collect::<Vec<_>>(), [2, 3, 4, 5]);
+        assert!(batch.windows(2).all(|pair| pair[1].prev_hash == pair[0].hash));
+        assert!(log.append_batch(Vec::new()).await.is_empty());
+        assert_eq!(list_segments(dir.path()).unwrap().len(), 2);
+
+        // A writer appends once full, keeping the scope each event was logged under
+        let mut writer = log.writer(2);
+        crate::tenant::scope("acme".into(), async {
+            assert!(writer.log(custom("first")).await.is_empty());
+        })
+        .await;
+        assert_eq!(writer.buffered(), 1);
+        let written = writer.log(custom("second")).await;
+        assert_eq!(written.iter().map(|e| e.tenant_id.as_deref()).collect::<Vec<_>>(), [Some("acme"), None]);
+        assert_eq!(writer.buffered(), 0);
+        writer.log(custom("third")).await;
+        assert_eq!(writer.flush().await[0].sequence, 8);
+
+        let progress = full_verification(&log).await;
+        let result = progress.result.unwrap();
+        assert!(result.valid);
+        assert_eq!(result.entries_checked, 8);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_new_entries() {
+        let log = AuditLog::with_defaults();
+        log.log_custom("test", "before", "kernel").await;
+        let mut all = log.subscribe(AuditFilter::default());
+        let filter = AuditFilter { sources: vec!["supervisor".into()], event_types: vec!["ModuleCrashed".into()] };
+        let mut crashes = log.subscribe(filter);
+
+        log.log_custom("test", "after", "kernel").await;
+        log.log_module_crashed("accrual", "trap", "kernel").await;
+        log.log_module_crashed("accrual", "hung", "supervisor").await;
+
+        assert_eq!(all.recv().await.unwrap().unwrap().sequence, 2);
+        assert_eq!(crashes.recv().await.unwrap().unwrap().sequence, 4);
+        assert_eq!(log.get_all_entries().await[3].event.kind(), "ModuleCrashed");
+
+        // A subscriber that falls too far behind learns how much it missed
+        for i in 0..BROADCAST_CAPACITY + 10 {
+            log.log_custom("test", &i.to_string(), "kernel").await;
+        }
+        let mut lagging = all;
+        let mut missed = 0;
+        while let Some(Err(MissedEntries(n))) = lagging.recv().await {
+            missed += n;
+        }
+        assert!(missed > 0);
+        drop(log);
+        while lagging.recv().await.is_some() {}
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_buffered_and_retried() {
+        for policy in [AuditFailurePolicy::Block, AuditFailurePolicy::Buffer] {
+            let dir = tempfile::tempdir().unwrap();
+            let config = AuditLogConfig { failure_policy: policy, max_pending_entries: 2, ..AuditLogConfig::default() };
+            let log = AuditLog::with_segments(config, dir.path()).unwrap().with_segment_entries(1);
+            let mut health = log.watch_health();
+            log.log_custom("test", "1", "kernel").await;
+            assert!(log.health().is_healthy());
+
+            // A directory where entry 2's segment file belongs fails every write to it
+            let blocked = dir.path().join(segment_name(2));
+            std::fs::create_dir(&blocked).unwrap();
+            log.log_custom("test", "2", "kernel").await;
+            assert!(health.has_changed().unwrap());
+            let failing = health.borrow_and_update().clone();
+            assert!(failing.failing_since.is_some() && failing.last_error.is_some());
+            assert_eq!(failing.pending_entries, 1);
+            assert_eq!(log.check_writable().is_err(), policy == AuditFailurePolicy::Block);
+
+            // Entry 3 waits behind entry 2; entry 4 does not fit in the buffer
+            log.log_custom("test", "3", "kernel").await;
+            log.log_custom("test", "4", "kernel").await;
+            assert!(!dir.path().join(segment_name(3)).exists());
+            let full = log.health();
+            assert_eq!((full.pending_entries, full.lost_entries), (2, 1));
+            assert!(matches!(log.check_writable(), Err(StorageError::AuditUnavailable(_))));
+            assert!(log.retry_pending().await.blocks_privileged);
+
+            std::fs::remove_dir(&blocked).unwrap();
+            let recovered = log.retry_pending().await;
+            assert!(recovered.is_healthy() && !recovered.blocks_privileged);
+            assert_eq!((recovered.pending_entries, recovered.lost_entries), (0, 1));
+            assert!(log.check_writable().is_ok());
+            for sequence in [2, 3] {
+                assert!(dir.path().join(segment_name(sequence)).is_file());
+            }
+            assert!(!dir.path().join(segment_name(4)).exists());
+        }
+        assert_eq!("Buffer".parse(), Ok(AuditFailurePolicy::Buffer));
+        assert!("drop".parse::<AuditFailurePolicy>().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_checkpoints_anchored() {
+        use crate::security::anchor::FileAnchor;
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = Arc::new(AuditLog::with_defaults());
+        log.log_custom("test", "1", "kernel").await;
+        let head = log.log_custom("test", "2", "kernel").await;
+
+        let anchors = dir.path().join("anchors.jsonl");
+        let receipt = log.anchor(Arc::new(FileAnchor::new(&anchors))).await.unwrap();
+        assert_eq!((receipt.checkpoint.sequence, receipt.checkpoint.hash.as_str()), (2, head.hash.as_str()));
+        assert!(receipt.covers(&head));
+        let recorded: AuditCheckpoint = serde_json::from_str(std::fs::read_to_string(&anchors).unwrap().trim()).unwrap();
+        assert_eq!(recorded, receipt.checkpoint);
+        let entries = log.get_all_entries().await;
+        assert_eq!(entries[2].event, AuditEventType::CheckpointAnchored(receipt.clone()));
+        assert!(!receipt.covers(&entries[2]));
+
+        // A provider that cannot record the checkpoint is logged and retried
+        let failed = log.anchor(Arc::new(FileAnchor::new(dir.path()))).await;
+        assert!(matches!(failed, Err(AnchorError::Io(_))));
+        assert!(matches!(log.get_all_entries().await[3].event, AuditEventType::AnchorFailed { sequence: 3, .. }));
+
+        // Scheduled anchoring skips intervals with nothing new but its own receipt
+        let task = log.clone().schedule_anchoring(Arc::new(FileAnchor::new(&anchors)), Duration::from_secs(60));
+        let anchored = |lines: usize| {
+            let anchors = anchors.clone();
+            async move {
+                for _ in 0..100 {
+                    if std::fs::read_to_string(&anchors).unwrap().lines().count() >= lines {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                tokio::time::sleep(Duration::from_secs(600)).await;
+                std::fs::read_to_string(&anchors).unwrap().lines().count()
+            }
+        };
+        assert_eq!(anchored(2).await, 2);
+        log.log_custom("test", "3", "kernel").await;
+        assert_eq!(anchored(3).await, 3);
+        task.abort();
+        assert!(log.verify_chain().await.valid);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AuditLogConfig { max_entries: 2, ..AuditLogConfig::default() };
+        let log = AuditLog::with_segments(config, dir.path()).unwrap().with_segment_entries(2);
+        for i in 0..3 {
+            log.log_custom("test", &i.to_string(), "kernel").await;
+        }
+
+        // The first export reaches back past the in-memory log into segments
+        let first = log.export_since(&ExportCursor::genesis()).await.unwrap();
+        assert_eq!(first.entries.len(), 3);
+        assert_eq!(first.cursor.sequence, 3);
+        log.log_custom("test", "3", "kernel").await;
+        log.log_custom("test", "4", "kernel").await;
+        let second = log.export_since(&first.cursor).await.unwrap();
+        assert_eq!(second.entries.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![4, 5]);
+        assert_eq!(second.from, first.cursor);
+        assert!(second.verify().valid);
+        assert!(verify_entries(first.entries.iter().chain(&second.entries)).valid);
+
+        let unchanged = log.export_since(&second.cursor).await.unwrap();
+        assert!(unchanged.entries.is_empty());
+        assert_eq!(unchanged.cursor, second.cursor);
+
+        // The log no longer continues what was exported
+        let forged = ExportCursor { sequence: 4, hash: first.cursor.hash.clone() };
+        assert_eq!(log.export_since(&forged).await.unwrap_err(), AuditExportError::Diverged(4));
+        let ahead = ExportCursor { sequence: 9, hash: second.cursor.hash.clone() };
+        assert_eq!(log.export_since(&ahead).await.unwrap_err(), AuditExportError::CursorAhead { cursor: 9, head: 5 });
+
+        // Entries trimmed without being persisted cannot be exported
+        let memory = AuditLog::new(AuditLogConfig { max_entries: 2, ..AuditLogConfig::default() });
+        for i in 0..4 {
+            memory.log_custom("test", &i.to_string(), "kernel").await;
+        }
+        assert_eq!(
+            memory.export_since(&ExportCursor::genesis()).await.unwrap_err(),
+            AuditExportError::Gap { expected: 1, found: 3 }
+        );
     }
 
     #[tokio::test]
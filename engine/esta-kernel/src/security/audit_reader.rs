@@ -0,0 +1,382 @@
+//! Memory-Mapped Readers for Persisted Audit Segments
+//!
+//! Historical audit queries over millions of entries must not load whole
+//! segments into memory. A reader maps segment files read-only and locates
+//! entries by binary search: first over segments (named by their first
+//! sequence number), then over byte offsets within a segment, parsing only
+//! the line under each probe. A lookup touches a handful of pages, and
+//! iterating from the found position reads only the entries returned.
+//!
+//! Sequence numbers increase strictly through the segments. Timestamp
+//! searches assume timestamps never decrease, which holds unless the system
+//! clock is set back; entries written across such a step may be missed by a
+//! time range query, but never by a sequence query.
+//!
+//! Segments are append-only while the log runs, so a mapping stays valid; a
+//! reader sees the complete lines present when each segment is mapped.
+
+use super::audit::{list_segments, AuditEntry};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The fields a search compares, parsed without the rest of the entry
+#[derive(Deserialize)]
+struct EntryKey {
+    sequence: u64,
+    timestamp: u64,
+}
+
+/// Summary of the persisted segments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchivedAuditStats {
+    pub segments: usize,
+    pub bytes: u64,
+    pub first_sequence: u64,
+    pub last_sequence: u64,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+}
+
+/// Reads persisted audit segments through read-only memory maps
+///
+/// The segment list is fixed when the reader is opened; segments started
+/// later are seen by a new reader.
+pub struct AuditSegmentReader {
+    /// Segment files and the sequence number each starts at, oldest first
+    segments: Vec<(u64, PathBuf)>,
+}
+
+impl AuditSegmentReader {
+    /// Open the segments in a directory
+    pub fn open(dir: &Path) -> Result<Self> {
+        let segments = list_segments(dir)?
+            .into_iter()
+            .map(|path| {
+                let first = first_sequence(&path).with_context(|| format!("unrecognized segment {}", path.display()))?;
+                Ok((first, path))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { segments })
+    }
+
+    /// Number of segment files
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The entry with a sequence number, if persisted
+    pub fn entry(&self, sequence: u64) -> Result<Option<AuditEntry>> {
+        match self.from_sequence(sequence)?.next().transpose()? {
+            Some(entry) if entry.sequence == sequence => Ok(Some(entry)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Entries from the first with a sequence number of at least `sequence`, oldest first
+    pub fn from_sequence(&self, sequence: u64) -> Result<SegmentEntries<'_>> {
+        // The last segment starting at or before the sequence holds it
+        let index = self.segments.partition_point(|(first, _)| *first <= sequence).saturating_sub(1);
+        let Some(segment) = self.map(index)? else {
+            return Ok(SegmentEntries::empty(self));
+        };
+        let offset = segment.lower_bound(|key| key.sequence >= sequence)?;
+        Ok(SegmentEntries { reader: self, index, segment: Some(segment), offset })
+    }
+
+    /// Entries from the first with a timestamp of at least `timestamp` (ms), oldest first
+    pub fn from_timestamp(&self, timestamp: u64) -> Result<SegmentEntries<'_>> {
+        // The last segment whose first entry is earlier may still end with matching entries
+        let mut low = 0;
+        let mut high = self.segments.len();
+        while low < high {
+            let mid = (low + high) / 2;
+            let first = self.map(mid)?.and_then(|segment| segment.key_at(0).transpose()).transpose()?;
+            match first {
+                Some(key) if key.timestamp < timestamp => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        let index = low.saturating_sub(1);
+        let Some(segment) = self.map(index)? else {
+            return Ok(SegmentEntries::empty(self));
+        };
+        let offset = segment.lower_bound(|key| key.timestamp >= timestamp)?;
+        Ok(SegmentEntries { reader: self, index, segment: Some(segment), offset })
+    }
+
+    /// Size and extent of the persisted log; `None` when no entries are persisted
+    pub fn stats(&self) -> Result<Option<ArchivedAuditStats>> {
+        let Some(first) = self.from_sequence(0)?.next().transpose()? else {
+            return Ok(None);
+        };
+        let mut last = None;
+        for index in (0..self.segments.len()).rev() {
+            if let Some(segment) = self.map(index)? {
+                if let Some(key) = segment.last_key()? {
+                    last = Some(key);
+                    break;
+                }
+            }
+        }
+        let last = last.context("persisted audit entries disappeared")?;
+
+        let mut bytes = 0;
+        for (_, path) in &self.segments {
+            bytes += std::fs::metadata(path).with_context(|| format!("reading {}", path.display()))?.len();
+        }
+        Ok(Some(ArchivedAuditStats {
+            segments: self.segments.len(),
+            bytes,
+            first_sequence: first.sequence,
+            last_sequence: last.sequence,
+            first_timestamp: first.timestamp,
+            last_timestamp: last.timestamp,
+        }))
+    }
+
+    /// Map a segment; `None` past the last one
+    fn map(&self, index: usize) -> Result<Option<MappedSegment>> {
+        self.segments.get(index).map(|(_, path)| MappedSegment::open(path)).transpose()
+    }
+}
+
+/// Entries read in order through the segments, see [`AuditSegmentReader::from_sequence`]
+pub struct SegmentEntries<'a> {
+    reader: &'a AuditSegmentReader,
+    index: usize,
+    segment: Option<MappedSegment>,
+    offset: usize,
+}
+
+impl<'a> SegmentEntries<'a> {
+    fn empty(reader: &'a AuditSegmentReader) -> Self {
+        Self { reader, index: 0, segment: None, offset: 0 }
+    }
+}
+
+impl Iterator for SegmentEntries<'_> {
+    type Item = Result<AuditEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let segment = self.segment.as_ref()?;
+            if let Some((line, next)) = segment.line_at(self.offset) {
+                let entry = segment.parse(line, self.offset);
+                self.offset = next;
+                return Some(entry);
+            }
+            self.index += 1;
+            self.offset = 0;
+            match self.reader.map(self.index) {
+                Ok(segment) => self.segment = segment,
+                Err(e) => {
+                    self.segment = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// One segment file, mapped up to its last complete line
+struct MappedSegment {
+    path: PathBuf,
+    map: Mmap,
+    /// Length of the complete lines; a line still being appended is ignored
+    len: usize,
+}
+
+impl MappedSegment {
+    fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let size = file.metadata()?.len() as usize;
+        let map = Mmap::map(&file, size).with_context(|| format!("mapping {}", path.display()))?;
+        let len = map.as_slice().iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        Ok(Self { path: path.to_path_buf(), map, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.map.as_slice()[..self.len]
+    }
+
+    /// The line starting at `offset` (without its newline) and the offset after it
+    fn line_at(&self, offset: usize) -> Option<(&[u8], usize)> {
+        let rest = self.bytes().get(offset..).filter(|rest| !rest.is_empty())?;
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        Some((&rest[..end], offset + end + 1))
+    }
+
+    fn key_at(&self, offset: usize) -> Result<Option<EntryKey>> {
+        self.line_at(offset).map(|(line, _)| self.parse(line, offset)).transpose()
+    }
+
+    fn last_key(&self) -> Result<Option<EntryKey>> {
+        let bytes = self.bytes();
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        let start = bytes[..bytes.len() - 1].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        self.key_at(start)
+    }
+
+    fn parse<T: for<'de> Deserialize<'de>>(&self, line: &[u8], offset: usize) -> Result<T> {
+        serde_json::from_slice(line)
+            .with_context(|| format!("unreadable audit entry in {} at byte {}", self.path.display(), offset))
+    }
+
+    /// Offset of the first line whose key satisfies `pred`, or the end
+    ///
+    /// `pred` must be false for a prefix of the lines and true for the rest.
+    fn lower_bound(&self, pred: impl Fn(&EntryKey) -> bool) -> Result<usize> {
+        let bytes = self.bytes();
+        // Invariant: `low` is a line start, lines before it fail `pred`, lines from `high` satisfy it
+        let mut low = 0;
+        let mut high = bytes.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let start = bytes[low..mid].iter().rposition(|&b| b == b'\n').map_or(low, |i| low + i + 1);
+            let Some((line, next)) = self.line_at(start) else {
+                bail!("segment {} ended unexpectedly", self.path.display());
+            };
+            if pred(&self.parse::<EntryKey>(line, start)?) {
+                high = start;
+            } else {
+                low = next;
+            }
+        }
+        Ok(low)
+    }
+}
+
+/// Segment name `audit-<first sequence>.jsonl` to its first sequence
+fn first_sequence(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_prefix("audit-")?.strip_suffix(".jsonl")?.parse().ok()
+}
+
+/// A read-only private mapping of a whole file
+#[cfg(unix)]
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned, so it can be shared like a `&[u8]`
+#[cfg(unix)]
+unsafe impl Send for Mmap {}
+#[cfg(unix)]
+unsafe impl Sync for Mmap {}
+
+#[cfg(unix)]
+impl Mmap {
+    fn map(file: &std::fs::File, len: usize) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        if len == 0 {
+            return Ok(Self { ptr: std::ptr::null_mut(), len });
+        }
+        // SAFETY: a fresh read-only mapping of an open file; segments are
+        // only ever appended to while mapped, never truncated
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` maps `len` readable bytes until dropped
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: unmapping exactly the region mapped in `map`
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+/// Platforms without `mmap` read the file instead
+#[cfg(not(unix))]
+struct Mmap(Vec<u8>);
+
+#[cfg(not(unix))]
+impl Mmap {
+    fn map(file: &std::fs::File, len: usize) -> std::io::Result<Self> {
+        use std::io::Read;
+
+        let mut bytes = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::audit::{AuditLog, AuditLogConfig};
+
+    #[tokio::test]
+    async fn test_binary_search_across_segments() {
+        let time = crate::testing::TimeMachine::start();
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::with_segments(AuditLogConfig::default(), dir.path()).unwrap().with_segment_entries(10);
+        let start = crate::clock::now_millis();
+        for i in 0..95 {
+            log.log_custom("test", &format!("entry {}", i), "kernel").await;
+            // Ten entries per second, several sharing each timestamp
+            if i % 3 == 2 {
+                time.advance(std::time::Duration::from_millis(300)).await;
+            }
+        }
+        // A line still being appended is not returned
+        let last = list_segments(dir.path()).unwrap().pop().unwrap();
+        std::fs::write(&last, std::fs::read_to_string(&last).unwrap() + r#"{"sequence":96,"#).unwrap();
+
+        let reader = AuditSegmentReader::open(dir.path()).unwrap();
+        assert_eq!(reader.segment_count(), 10);
+        for sequence in [1, 10, 11, 50, 95] {
+            assert_eq!(reader.entry(sequence).unwrap().unwrap().sequence, sequence);
+        }
+        assert!(reader.entry(0).unwrap().is_none());
+        assert!(reader.entry(96).unwrap().is_none());
+
+        let run: Vec<u64> = reader.from_sequence(38).unwrap().take(5).map(|e| e.unwrap().sequence).collect();
+        assert_eq!(run, [38, 39, 40, 41, 42]);
+        assert_eq!(reader.from_sequence(90).unwrap().count(), 6);
+
+        // The first entry at or after a time, including one that ends a segment
+        for sequence in [1, 4, 7, 30, 31, 61, 94] {
+            let timestamp = reader.entry(sequence).unwrap().unwrap().timestamp;
+            let first = reader.from_timestamp(timestamp).unwrap().next().unwrap().unwrap();
+            assert_eq!(first.timestamp, timestamp);
+            assert_eq!(first.sequence, (sequence - 1) / 3 * 3 + 1, "first of the entries sharing a timestamp");
+        }
+        assert_eq!(reader.from_timestamp(start + 1).unwrap().next().unwrap().unwrap().sequence, 4);
+        assert!(reader.from_timestamp(u64::MAX).unwrap().next().is_none());
+
+        let stats = reader.stats().unwrap().unwrap();
+        assert_eq!((stats.segments, stats.first_sequence, stats.last_sequence), (10, 1, 95));
+        assert_eq!(stats.first_timestamp, start);
+        assert_eq!(stats.last_timestamp, start + 31 * 300);
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(AuditSegmentReader::open(empty.path()).unwrap().stats().unwrap().is_none());
+    }
+}
@@ -6,18 +6,47 @@
 //! Security Guarantees:
 //! - Capabilities are unforgeable tokens issued only by the kernel
 //! - Capabilities can be delegated but only with equal or fewer rights (monotonic attenuation)
-//! - Capabilities can be revoked at any time
+//! - Capabilities can be revoked at any time; revoking one revokes everything
+//!   delegated from it, at any depth
+//! - Capabilities can be short-lived leases, renewed by holders of the
+//!   `Renew` right up to a maximum lifetime
+//! - Capabilities can be bound to a module instance nonce, so a leaked token
+//!   is useless to any other module or external caller
 //! - All capability operations are logged for audit
 //!
+//! With [`CapabilityManager::with_audit_log`], every creation, validation,
+//! denial, delegation, and revocation is appended to the audit log with the
+//! capability's owner and rights. Tokens themselves are never logged; entries
+//! name capabilities by their numeric ID.
+//!
+//! Validation and denial rates are kept over a rolling window and reported
+//! by [`CapabilityManager::stats`]; a spike in denials can raise a security
+//! alert in the audit log (see [`super::capability_metrics`]).
+//!
+//! Validation runs on every capability-gated host call, so it takes no lock
+//! on the capability table: it reads the current snapshot of the table,
+//! which writers replace whole (copy, change, publish) one at a time. A
+//! validation that raced a revocation sees the table as it was when the
+//! validation began.
+//!
 //! Reference: docs/abi/kernel_contract.md
 
+use arc_swap::ArcSwap;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::RwLock;
+
+use super::audit::{AuditEvent, AuditEventType, AuditLog};
+use super::capability_metrics::{CapabilityMetrics, CapabilityMetricsConfig, SECURITY_ALERT_CATEGORY};
+use super::secrets::SecretError;
+use super::snapshot::CapabilitySnapshot;
+use crate::clock::{Clock, SystemClock};
+use crate::error::KernelError;
+use crate::pagination::{Page, PageRequest};
+use crate::tenant::{check_resource_scope, resource_tenant, tenant_resource_id, validate_tenant_id};
 
 /// Errors that can occur in capability operations
 #[derive(Error, Debug, Clone)]
@@ -45,13 +74,44 @@ pub enum CapabilityError {
     
     #[error("Process not authorized for this operation")]
     Unauthorized,
+
+    #[error("Capability does not expire, so it cannot be renewed")]
+    NotRenewable,
+
+    #[error("Capability resource is outside tenant {0}")]
+    CrossTenant(String),
+
+    #[error("Capability is bound to a different module instance")]
+    InstanceMismatch,
 }
 
+impl CapabilityError {
+    /// Short name for the kind of error, as counted in denial metrics
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CapabilityError::NotFound(_) => "not_found",
+            CapabilityError::Revoked => "revoked",
+            CapabilityError::Expired => "expired",
+            CapabilityError::InsufficientRights { .. } => "insufficient_rights",
+            CapabilityError::UsageLimitExceeded => "usage_limit_exceeded",
+            CapabilityError::DelegationNotAllowed => "delegation_not_allowed",
+            CapabilityError::InvalidToken => "invalid_token",
+            CapabilityError::Unauthorized => "unauthorized",
+            CapabilityError::NotRenewable => "not_renewable",
+            CapabilityError::CrossTenant(_) => "cross_tenant",
+            CapabilityError::InstanceMismatch => "instance_mismatch",
+        }
+    }
+}
+
+/// Duration of the leases the kernel issues to long-running modules (5 minutes)
+pub const DEFAULT_LEASE_MS: u64 = 5 * 60 * 1000;
+
 /// Result type for capability operations
 pub type CapabilityResult<T> = Result<T, CapabilityError>;
 
 /// Unique identifier for a capability
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct CapabilityId(u64);
 
 impl CapabilityId {
@@ -59,6 +119,11 @@ impl CapabilityId {
         // Combine counter and timestamp for uniqueness
         Self((timestamp << 32) | (counter & 0xFFFF_FFFF))
     }
+
+    /// The numeric ID, as it appears in tokens
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
 }
 
 /// Rights that can be granted by a capability
@@ -88,10 +153,13 @@ pub enum CapabilityRight {
     PersistenceWrite,
     /// Permission to log messages
     Log,
+    /// Permission to extend the capability's own expiry
+    Renew,
 }
 
 impl CapabilityRight {
     /// Parse a right from its string representation
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "read" => Some(Self::Read),
@@ -106,6 +174,7 @@ impl CapabilityRight {
             "persistence_read" => Some(Self::PersistenceRead),
             "persistence_write" => Some(Self::PersistenceWrite),
             "log" => Some(Self::Log),
+            "renew" => Some(Self::Renew),
             _ => None,
         }
     }
@@ -125,6 +194,7 @@ impl CapabilityRight {
             Self::PersistenceRead => "persistence_read",
             Self::PersistenceWrite => "persistence_write",
             Self::Log => "log",
+            Self::Renew => "renew",
         }
     }
 }
@@ -149,7 +219,7 @@ pub enum ResourceType {
 }
 
 /// Validity constraints for a capability
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CapabilityValidity {
     /// Expiration timestamp (Unix millis), None = never expires
     pub expires_at: Option<u64>,
@@ -157,28 +227,82 @@ pub struct CapabilityValidity {
     pub max_uses: Option<u64>,
     /// Current usage count
     pub use_count: u64,
+    /// Longest the capability may live (ms after creation), however often
+    /// it is renewed; None = no ceiling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lifetime_ms: Option<u64>,
 }
 
-impl Default for CapabilityValidity {
-    fn default() -> Self {
+impl CapabilityValidity {
+    /// A lease expiring `duration_ms` from now, renewable until
+    /// `max_lifetime_ms` after creation
+    ///
+    /// Measured on the kernel wall clock; for a manager given its own clock,
+    /// use [`CapabilityManager::lease`].
+    pub fn lease(duration_ms: u64, max_lifetime_ms: Option<u64>) -> Self {
+        Self::lease_from(crate::clock::now_millis(), duration_ms, max_lifetime_ms)
+    }
+
+    fn lease_from(now: u64, duration_ms: u64, max_lifetime_ms: Option<u64>) -> Self {
         Self {
-            expires_at: None,
-            max_uses: None,
-            use_count: 0,
+            expires_at: Some(now + duration_ms),
+            max_lifetime_ms,
+            ..Default::default()
         }
     }
 }
 
+/// Secret nonce identifying one module instance
+///
+/// Established by the kernel when a module is launched and held only by the
+/// stores of that instance. It is deliberately neither serializable nor
+/// printable, so it cannot leak through logs or persisted state the way a
+/// token can.
+#[derive(Clone, PartialEq, Eq)]
+pub struct InstanceNonce([u8; 32]);
+
+impl InstanceNonce {
+    /// Generate a fresh random nonce
+    ///
+    /// # Panics
+    /// Panics if the system RNG fails.
+    pub fn generate() -> Self {
+        let rng = ring::rand::SystemRandom::new();
+        let bytes: [u8; 32] = ring::rand::generate(&rng)
+            .expect("System RNG failed - cannot generate instance nonce")
+            .expose();
+        Self(bytes)
+    }
+
+    /// One-way fingerprint recorded on capabilities bound to this instance
+    fn fingerprint(&self) -> String {
+        let hash = hex::encode(Sha256::digest(self.0));
+        hash[..16].to_string()
+    }
+}
+
+impl std::fmt::Debug for InstanceNonce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("InstanceNonce(..)")
+    }
+}
+
 /// Opaque capability token for external use
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CapabilityToken(String);
 
 impl CapabilityToken {
     /// Create a new token from capability ID and HMAC
-    fn new(cap_id: CapabilityId, secret: &[u8]) -> Self {
+    ///
+    /// A bound token also covers the instance nonce, so it can only be
+    /// reproduced by a presenter holding that nonce.
+    fn new(cap_id: CapabilityId, secret: &[u8], instance: Option<&InstanceNonce>) -> Self {
         let mut hasher = Sha256::new();
         hasher.update(cap_id.0.to_le_bytes());
         hasher.update(secret);
+        if let Some(instance) = instance {
+            hasher.update(instance.0);
+        }
         let hash = hex::encode(hasher.finalize());
         Self(format!("cap_{}_{}", cap_id.0, &hash[..16]))
     }
@@ -193,6 +317,11 @@ impl CapabilityToken {
         }
     }
 
+    /// A token as presented by an external caller; validating it decides whether it is genuine
+    pub fn presented(token: &str) -> Self {
+        Self(token.to_string())
+    }
+
     /// Get the token as a string
     pub fn as_str(&self) -> &str {
         &self.0
@@ -208,7 +337,8 @@ pub struct Capability {
     pub resource_type: ResourceType,
     /// Specific resource identifier
     pub resource_id: String,
-    /// Rights granted by this capability
+    /// Rights granted by this capability, serialized in name order
+    #[serde(serialize_with = "serialize_rights")]
     pub rights: HashSet<CapabilityRight>,
     /// Owner process/module ID
     pub owner: String,
@@ -218,10 +348,30 @@ pub struct Capability {
     pub validity: CapabilityValidity,
     /// Whether this capability has been revoked
     pub revoked: bool,
+    /// Fingerprint of the module instance nonce this capability is bound to
+    #[serde(default)]
+    pub bound_instance: Option<String>,
     /// Creation timestamp (Unix millis)
     pub created_at: u64,
 }
 
+/// Serialize rights in name order rather than hash order
+fn serialize_rights<S: serde::Serializer>(rights: &HashSet<CapabilityRight>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut sorted: Vec<&CapabilityRight> = rights.iter().collect();
+    sorted.sort_by_key(|right| right.as_str());
+    serializer.collect_seq(sorted)
+}
+
+/// Names of rights in name order, as recorded in audit entries
+fn right_names<'a>(rights: impl IntoIterator<Item = &'a CapabilityRight>) -> Vec<String> {
+    let mut names: Vec<String> = rights.into_iter().map(|right| right.as_str().to_string()).collect();
+    names.sort();
+    names
+}
+
+/// Audit entry source for capability operations
+const AUDIT_SOURCE: &str = "capabilities";
+
 impl Capability {
     /// Check if the capability has a specific right
     pub fn has_right(&self, right: CapabilityRight) -> bool {
@@ -240,6 +390,12 @@ impl Capability {
             }
         }
 
+        if let Some(ceiling) = self.lifetime_ceiling() {
+            if now > ceiling {
+                return Err(CapabilityError::Expired);
+            }
+        }
+
         if let Some(max_uses) = self.validity.max_uses {
             if self.validity.use_count >= max_uses {
                 return Err(CapabilityError::UsageLimitExceeded);
@@ -248,20 +404,61 @@ impl Capability {
 
         Ok(())
     }
+
+    /// When the capability stops being valid however often it is renewed (Unix millis)
+    pub fn lifetime_ceiling(&self) -> Option<u64> {
+        self.validity.max_lifetime_ms.map(|ms| self.created_at.saturating_add(ms))
+    }
+}
+
+/// What validation reads: the capabilities, revocations, and token secret
+///
+/// Never changed in place; see [`CapabilityManager::update`].
+#[derive(Clone)]
+struct CapabilityTable {
+    /// All capabilities, revoked ones included
+    capabilities: HashMap<CapabilityId, Arc<Capability>>,
+    /// Revocation list for quick lookup
+    revocations: HashSet<CapabilityId>,
+    /// Secret for token generation
+    secret: Arc<Vec<u8>>,
+}
+
+impl CapabilityTable {
+    /// `roots` and every capability delegated from them, directly or not
+    fn subtree(&self, roots: impl IntoIterator<Item = CapabilityId>) -> HashSet<CapabilityId> {
+        let mut children: HashMap<CapabilityId, Vec<CapabilityId>> = HashMap::new();
+        for cap in self.capabilities.values() {
+            if let Some(parent) = cap.parent_id {
+                children.entry(parent).or_default().push(cap.id);
+            }
+        }
+
+        let mut reached = HashSet::new();
+        let mut pending: Vec<CapabilityId> = roots.into_iter().collect();
+        while let Some(id) = pending.pop() {
+            if reached.insert(id) {
+                pending.extend(children.get(&id).into_iter().flatten());
+            }
+        }
+        reached
+    }
 }
 
 /// Manages all capabilities in the system
 pub struct CapabilityManager {
-    /// All active capabilities
-    capabilities: Arc<RwLock<HashMap<CapabilityId, Capability>>>,
-    /// Token lookup table
-    tokens: Arc<RwLock<HashMap<CapabilityToken, CapabilityId>>>,
-    /// Revocation list for quick lookup
-    revocations: Arc<RwLock<HashSet<CapabilityId>>>,
+    /// Current capability table, loaded without locking
+    table: ArcSwap<CapabilityTable>,
+    /// Token lookup table; held by writers while they update the table
+    tokens: std::sync::Mutex<HashMap<CapabilityToken, CapabilityId>>,
     /// Next capability ID counter
     next_id: AtomicU64,
-    /// Secret for token generation
-    secret: Vec<u8>,
+    /// Where capability operations are recorded, if anywhere
+    audit_log: std::sync::RwLock<Option<Arc<AuditLog>>>,
+    /// Validation and denial counts over the rolling window
+    metrics: std::sync::Mutex<CapabilityMetrics>,
+    /// Time for creation stamps, expiry, and the metrics window
+    clock: Arc<dyn Clock>,
 }
 
 impl CapabilityManager {
@@ -271,32 +468,147 @@ impl CapabilityManager {
     /// * `secret` - Secret bytes for token generation (should be cryptographically random)
     pub fn new(secret: Vec<u8>) -> Self {
         Self {
-            capabilities: Arc::new(RwLock::new(HashMap::new())),
-            tokens: Arc::new(RwLock::new(HashMap::new())),
-            revocations: Arc::new(RwLock::new(HashSet::new())),
+            table: ArcSwap::from_pointee(CapabilityTable {
+                capabilities: HashMap::new(),
+                revocations: HashSet::new(),
+                secret: Arc::new(secret),
+            }),
+            tokens: std::sync::Mutex::new(HashMap::new()),
             next_id: AtomicU64::new(1),
-            secret,
+            audit_log: std::sync::RwLock::new(None),
+            metrics: std::sync::Mutex::new(CapabilityMetrics::new(CapabilityMetricsConfig::default())),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read time from `clock` instead of the kernel wall clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keep validation metrics over a different window, or alert on denial spikes
+    pub fn with_metrics(self, config: CapabilityMetricsConfig) -> Self {
+        *self.metrics.lock().unwrap_or_else(|e| e.into_inner()) = CapabilityMetrics::new(config);
+        self
+    }
+
+    fn metrics(&self) -> std::sync::MutexGuard<'_, CapabilityMetrics> {
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Change a copy of the capability table and publish it
+    ///
+    /// Writers take the token lock for the whole change, so none of them
+    /// loses another's. Readers holding the previous table are unaffected.
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut CapabilityTable, &mut HashMap<CapabilityToken, CapabilityId>) -> T,
+    ) -> T {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        let mut table = CapabilityTable::clone(&self.table.load());
+        let result = change(&mut table, &mut tokens);
+        self.table.store(Arc::new(table));
+        result
+    }
+
+    /// Record capability operations in an audit log
+    pub fn with_audit_log(self, audit_log: Arc<AuditLog>) -> Self {
+        self.attach_audit_log(audit_log);
+        self
+    }
+
+    /// Record capability operations in an audit log from now on
+    ///
+    /// For owners that replace their audit log after the manager is shared.
+    pub(crate) fn attach_audit_log(&self, audit_log: Arc<AuditLog>) {
+        *self.audit_log.write().unwrap_or_else(|e| e.into_inner()) = Some(audit_log);
+    }
+
+    /// Append an event to the audit log, if there is one
+    async fn audit(&self, event: AuditEventType) {
+        let audit_log = self.audit_log.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(audit_log) = audit_log {
+            audit_log.append(AuditEvent::new(event, AUDIT_SOURCE)).await;
+        }
+    }
+
+    /// Append events to the audit log as one batch, if there is one
+    async fn audit_batch(&self, events: Vec<AuditEventType>) {
+        let audit_log = self.audit_log.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(audit_log) = audit_log {
+            let events = events.into_iter().map(|event| AuditEvent::new(event, AUDIT_SOURCE)).collect();
+            audit_log.append_batch(events).await;
+        }
+    }
+
+    /// Record a refused operation, naming the capability's owner if the token names a known one
+    ///
+    /// Raises a security alert when the denial takes the window's denials to
+    /// the alert threshold.
+    async fn audit_denied(&self, token: &CapabilityToken, error: &CapabilityError, required: &[CapabilityRight]) {
+        let cap_id = token.capability_id();
+        let owner = match cap_id {
+            Some(id) => self.table.load().capabilities.get(&id).map(|cap| cap.owner.clone()),
+            None => None,
+        };
+        let alert = self.metrics().denied(self.current_timestamp(), error.reason(), owner.as_deref());
+        self.audit(AuditEventType::CapabilityDenied {
+            cap_id: cap_id.map(|id| id.0.to_string()).unwrap_or_else(|| "invalid".into()),
+            reason: error.to_string(),
+            owner,
+            rights: right_names(required),
+        }).await;
+        if let Some(message) = alert {
+            log::warn!("Capability denial spike: {}", message);
+            self.audit(AuditEventType::Custom { category: SECURITY_ALERT_CATEGORY.into(), message }).await;
+        }
+    }
+
+    /// Count an allowed operation in the metrics
+    fn record_allowed(&self) {
+        self.metrics().allowed(self.current_timestamp());
+    }
+
+    /// Record the outcome of a validation
+    async fn audit_validation(
+        &self,
+        token: &CapabilityToken,
+        required: &[CapabilityRight],
+        result: &CapabilityResult<Capability>,
+    ) {
+        match result {
+            Ok(cap) => {
+                self.record_allowed();
+                self.audit(AuditEventType::CapabilityValidated {
+                    cap_id: cap.id.0.to_string(),
+                    operation: right_names(required).join(","),
+                    owner: cap.owner.clone(),
+                    rights: right_names(&cap.rights),
+                }).await
+            }
+            Err(e) => self.audit_denied(token, e, required).await,
         }
     }
 
     /// Generate a cryptographically random secret
-    /// 
-    /// # Panics
-    /// Panics if the system RNG fails. This is a critical error that should
-    /// not be silently handled as it would compromise security.
-    pub fn generate_secret() -> Vec<u8> {
+    ///
+    /// Fails with [`SecretError::Rng`] if the system RNG does; there is no
+    /// weaker fallback.
+    pub fn generate_secret() -> Result<Vec<u8>, SecretError> {
         let rng = ring::rand::SystemRandom::new();
-        let random_bytes: [u8; 32] = ring::rand::generate(&rng)
-            .expect("System RNG failed - cannot generate secure secret")
-            .expose();
-        random_bytes.to_vec()
+        let random_bytes: [u8; 32] = ring::rand::generate(&rng).map_err(|_| SecretError::Rng)?.expose();
+        Ok(random_bytes.to_vec())
+    }
+
+    fn current_timestamp(&self) -> u64 {
+        self.clock.now_millis()
     }
 
-    fn current_timestamp() -> u64 {
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_millis() as u64)
-            .unwrap_or(0)
+    /// A lease expiring `duration_ms` from now on this manager's clock, as
+    /// [`CapabilityValidity::lease`]
+    pub fn lease(&self, duration_ms: u64, max_lifetime_ms: Option<u64>) -> CapabilityValidity {
+        CapabilityValidity::lease_from(self.current_timestamp(), duration_ms, max_lifetime_ms)
     }
 
     /// Create a new capability (kernel authority only)
@@ -315,10 +627,38 @@ impl CapabilityManager {
         owner: String,
         validity: CapabilityValidity,
     ) -> CapabilityResult<CapabilityToken> {
-        let id = CapabilityId::new(
-            self.next_id.fetch_add(1, Ordering::SeqCst),
-            Self::current_timestamp(),
-        );
+        self.issue(resource_type, resource_id, rights, owner, validity, None).await
+    }
+
+    /// Create a capability bound to a module instance (kernel authority only)
+    ///
+    /// The token only validates through
+    /// [`CapabilityManager::validate_from_instance`] with the same nonce; plain
+    /// [`CapabilityManager::validate`] rejects it. Bound capabilities cannot
+    /// be delegated.
+    pub async fn create_bound_capability(
+        &self,
+        resource_type: ResourceType,
+        resource_id: String,
+        rights: HashSet<CapabilityRight>,
+        owner: String,
+        validity: CapabilityValidity,
+        instance: &InstanceNonce,
+    ) -> CapabilityResult<CapabilityToken> {
+        self.issue(resource_type, resource_id, rights, owner, validity, Some(instance)).await
+    }
+
+    async fn issue(
+        &self,
+        resource_type: ResourceType,
+        resource_id: String,
+        rights: HashSet<CapabilityRight>,
+        owner: String,
+        validity: CapabilityValidity,
+        instance: Option<&InstanceNonce>,
+    ) -> CapabilityResult<CapabilityToken> {
+        let now = self.current_timestamp();
+        let id = CapabilityId::new(self.next_id.fetch_add(1, Ordering::SeqCst), now);
 
         let cap = Capability {
             id,
@@ -329,17 +669,24 @@ impl CapabilityManager {
             parent_id: None,
             validity,
             revoked: false,
-            created_at: Self::current_timestamp(),
+            bound_instance: instance.map(InstanceNonce::fingerprint),
+            created_at: now,
         };
 
-        let token = CapabilityToken::new(id, &self.secret);
-
-        let mut caps = self.capabilities.write().await;
-        caps.insert(id, cap);
+        let created = AuditEventType::CapabilityCreated {
+            cap_id: id.0.to_string(),
+            owner: cap.owner.clone(),
+            rights: right_names(&cap.rights),
+        };
 
-        let mut tokens = self.tokens.write().await;
-        tokens.insert(token.clone(), id);
+        let token = self.update(|table, tokens| {
+            let token = CapabilityToken::new(id, &table.secret, instance);
+            table.capabilities.insert(id, Arc::new(cap));
+            tokens.insert(token.clone(), id);
+            token
+        });
 
+        self.audit(created).await;
         Ok(token)
     }
 
@@ -352,26 +699,63 @@ impl CapabilityManager {
         &self,
         token: &CapabilityToken,
         required_rights: &[CapabilityRight],
+    ) -> CapabilityResult<Capability> {
+        let result = self.validate_presented(token, None, required_rights);
+        self.audit_validation(token, required_rights, &result).await;
+        result
+    }
+
+    /// Validate a token presented by a module instance
+    ///
+    /// Accepts unbound tokens as well as tokens bound to `instance`; tokens
+    /// bound to any other instance fail with
+    /// [`CapabilityError::InstanceMismatch`].
+    pub async fn validate_from_instance(
+        &self,
+        token: &CapabilityToken,
+        instance: &InstanceNonce,
+        required_rights: &[CapabilityRight],
+    ) -> CapabilityResult<Capability> {
+        let result = self.validate_presented(token, Some(instance), required_rights);
+        self.audit_validation(token, required_rights, &result).await;
+        result
+    }
+
+    fn validate_presented(
+        &self,
+        token: &CapabilityToken,
+        instance: Option<&InstanceNonce>,
+        required_rights: &[CapabilityRight],
     ) -> CapabilityResult<Capability> {
         let cap_id = token.capability_id()
             .ok_or(CapabilityError::InvalidToken)?;
+        let table = self.table.load();
 
         // Check revocation list first
-        {
-            let revocations = self.revocations.read().await;
-            if revocations.contains(&cap_id) {
-                return Err(CapabilityError::Revoked);
-            }
+        if table.revocations.contains(&cap_id) {
+            return Err(CapabilityError::Revoked);
         }
 
         // Get the capability
-        let caps = self.capabilities.read().await;
-        let cap = caps.get(&cap_id)
-            .ok_or_else(|| CapabilityError::NotFound(token.as_str().to_string()))?
-            .clone();
+        let cap = table.capabilities.get(&cap_id)
+            .ok_or_else(|| CapabilityError::NotFound(token.as_str().to_string()))?;
+
+        // Bound tokens are only honored when presented by their instance
+        let instance = match &cap.bound_instance {
+            Some(bound) if instance.map(InstanceNonce::fingerprint).as_ref() != Some(bound) => {
+                return Err(CapabilityError::InstanceMismatch);
+            }
+            Some(_) => instance,
+            None => None,
+        };
+
+        // The token must carry the kernel's MAC, not just a known capability ID
+        if CapabilityToken::new(cap.id, &table.secret, instance) != *token {
+            return Err(CapabilityError::InvalidToken);
+        }
 
         // Check validity
-        cap.is_valid(Self::current_timestamp())?;
+        cap.is_valid(self.current_timestamp())?;
 
         // Check rights
         let missing: Vec<String> = required_rights.iter()
@@ -386,7 +770,51 @@ impl CapabilityManager {
             });
         }
 
-        Ok(cap)
+        Ok(Capability::clone(cap))
+    }
+
+    /// Create a capability inside a tenant's namespace
+    ///
+    /// The resource ID is prefixed with the tenant namespace so the capability
+    /// can only ever pass [`CapabilityManager::validate_for_tenant`] for that tenant.
+    pub async fn create_tenant_capability(
+        &self,
+        tenant_id: &str,
+        resource_type: ResourceType,
+        resource_id: &str,
+        rights: HashSet<CapabilityRight>,
+        owner: String,
+        validity: CapabilityValidity,
+    ) -> CapabilityResult<CapabilityToken> {
+        validate_tenant_id(tenant_id)
+            .map_err(|_| CapabilityError::CrossTenant(tenant_id.to_string()))?;
+
+        self.create_capability(
+            resource_type,
+            tenant_resource_id(tenant_id, resource_id),
+            rights,
+            owner,
+            validity,
+        ).await
+    }
+
+    /// Validate a capability token for use by a specific tenant
+    ///
+    /// In addition to the checks in [`CapabilityManager::validate`], the
+    /// capability's resource must live in the tenant's namespace.
+    pub async fn validate_for_tenant(
+        &self,
+        token: &CapabilityToken,
+        tenant_id: &str,
+        required_rights: &[CapabilityRight],
+    ) -> CapabilityResult<Capability> {
+        let result = self.validate_presented(token, None, required_rights).and_then(|cap| {
+            check_resource_scope(tenant_id, &cap.resource_id)
+                .map_err(|_| CapabilityError::CrossTenant(tenant_id.to_string()))?;
+            Ok(cap)
+        });
+        self.audit_validation(token, required_rights, &result).await;
+        result
     }
 
     /// Record usage of a capability (increments use count)
@@ -394,12 +822,81 @@ impl CapabilityManager {
         let cap_id = token.capability_id()
             .ok_or(CapabilityError::InvalidToken)?;
 
-        let mut caps = self.capabilities.write().await;
-        let cap = caps.get_mut(&cap_id)
-            .ok_or_else(|| CapabilityError::NotFound(token.as_str().to_string()))?;
+        self.update(|table, _| {
+            let cap = table.capabilities.get_mut(&cap_id)
+                .ok_or_else(|| CapabilityError::NotFound(token.as_str().to_string()))?;
+            Arc::make_mut(cap).validity.use_count += 1;
+            Ok(())
+        })
+    }
 
-        cap.validity.use_count += 1;
-        Ok(())
+    /// Extend a lease to `extension_ms` from now
+    ///
+    /// The token needs the [`CapabilityRight::Renew`] right, and the
+    /// capability must already expire: permanent capabilities are not leases.
+    /// The new expiry never passes the capability's lifetime ceiling, nor
+    /// comes before its current expiry. Returns the new expiry (Unix millis).
+    pub async fn renew(&self, token: &CapabilityToken, extension_ms: u64) -> CapabilityResult<u64> {
+        self.renew_presented(token, None, extension_ms).await
+    }
+
+    /// Extend a lease presented by a module instance, as [`CapabilityManager::renew`]
+    ///
+    /// Tokens bound to `instance` are accepted here, as in
+    /// [`CapabilityManager::validate_from_instance`].
+    pub async fn renew_from_instance(
+        &self,
+        token: &CapabilityToken,
+        instance: &InstanceNonce,
+        extension_ms: u64,
+    ) -> CapabilityResult<u64> {
+        self.renew_presented(token, Some(instance), extension_ms).await
+    }
+
+    async fn renew_presented(
+        &self,
+        token: &CapabilityToken,
+        instance: Option<&InstanceNonce>,
+        extension_ms: u64,
+    ) -> CapabilityResult<u64> {
+        let required = [CapabilityRight::Renew];
+        let renewed = match self.validate_presented(token, instance, &required) {
+            Ok(cap) => self.extend(cap.id, extension_ms),
+            Err(e) => Err(e),
+        };
+        match renewed {
+            Ok((cap, expires_at)) => {
+                self.record_allowed();
+                self.audit(AuditEventType::CapabilityRenewed {
+                    cap_id: cap.id.0.to_string(),
+                    lifetime_ceiling: cap.lifetime_ceiling(),
+                    owner: cap.owner,
+                    expires_at,
+                }).await;
+                Ok(expires_at)
+            }
+            Err(e) => {
+                self.audit_denied(token, &e, &required).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Move a validated lease's expiry, returning the capability and its new expiry
+    fn extend(&self, id: CapabilityId, extension_ms: u64) -> CapabilityResult<(Capability, u64)> {
+        self.update(|table, _| {
+            let cap = table.capabilities.get_mut(&id)
+                .ok_or_else(|| CapabilityError::NotFound(id.0.to_string()))?;
+            let current = cap.validity.expires_at.ok_or(CapabilityError::NotRenewable)?;
+
+            let mut expires_at = self.current_timestamp().saturating_add(extension_ms).max(current);
+            if let Some(ceiling) = cap.lifetime_ceiling() {
+                expires_at = expires_at.min(ceiling);
+            }
+            let cap = Arc::make_mut(cap);
+            cap.validity.expires_at = Some(expires_at);
+            Ok((cap.clone(), expires_at))
+        })
     }
 
     /// Delegate a capability to another owner with potentially reduced rights
@@ -417,7 +914,15 @@ impl CapabilityManager {
         validity: CapabilityValidity,
     ) -> CapabilityResult<CapabilityToken> {
         // First validate the parent capability has delegate right
-        let parent_cap = self.validate(token, &[CapabilityRight::Delegate]).await?;
+        let parent_cap = match self.validate_presented(token, None, &[CapabilityRight::Delegate]) {
+            Ok(cap) => cap,
+            Err(e) => {
+                self.audit_denied(token, &e, &[CapabilityRight::Delegate]).await;
+                return Err(e);
+            }
+        };
+
+        self.record_allowed();
 
         // Ensure delegated rights are a subset of parent rights (monotonic attenuation)
         let invalid_rights: Vec<_> = rights.iter()
@@ -425,17 +930,17 @@ impl CapabilityManager {
             .collect();
 
         if !invalid_rights.is_empty() {
-            return Err(CapabilityError::InsufficientRights {
+            let error = CapabilityError::InsufficientRights {
                 required: invalid_rights.iter().map(|r| r.as_str().to_string()).collect(),
                 actual: parent_cap.rights.iter().map(|r| r.as_str().to_string()).collect(),
-            });
+            };
+            self.audit_denied(token, &error, &rights.iter().copied().collect::<Vec<_>>()).await;
+            return Err(error);
         }
 
         // Create the new delegated capability
-        let id = CapabilityId::new(
-            self.next_id.fetch_add(1, Ordering::SeqCst),
-            Self::current_timestamp(),
-        );
+        let now = self.current_timestamp();
+        let id = CapabilityId::new(self.next_id.fetch_add(1, Ordering::SeqCst), now);
 
         let cap = Capability {
             id,
@@ -446,21 +951,29 @@ impl CapabilityManager {
             parent_id: Some(parent_cap.id),
             validity,
             revoked: false,
-            created_at: Self::current_timestamp(),
+            bound_instance: None,
+            created_at: now,
         };
 
-        let new_token = CapabilityToken::new(id, &self.secret);
-
-        let mut caps = self.capabilities.write().await;
-        caps.insert(id, cap);
+        let delegated = AuditEventType::CapabilityDelegated {
+            parent_id: parent_cap.id.0.to_string(),
+            new_id: id.0.to_string(),
+            new_owner: cap.owner.clone(),
+            rights: right_names(&cap.rights),
+        };
 
-        let mut tokens = self.tokens.write().await;
-        tokens.insert(new_token.clone(), id);
+        let new_token = self.update(|table, tokens| {
+            let token = CapabilityToken::new(id, &table.secret, None);
+            table.capabilities.insert(id, Arc::new(cap));
+            tokens.insert(token.clone(), id);
+            token
+        });
 
+        self.audit(delegated).await;
         Ok(new_token)
     }
 
-    /// Revoke a capability and all its delegated children
+    /// Revoke a capability and everything delegated from it, at any depth
     ///
     /// # Arguments
     /// * `token` - The capability to revoke
@@ -471,64 +984,249 @@ impl CapabilityManager {
         let cap_id = token.capability_id()
             .ok_or(CapabilityError::InvalidToken)?;
 
-        let mut caps = self.capabilities.write().await;
-        let mut revocations = self.revocations.write().await;
-        
-        // Find all capabilities to revoke (the target and all its children)
-        let mut to_revoke: Vec<CapabilityId> = vec![cap_id];
-        let mut count = 0;
-
-        // Find all delegated children (cascade revocation)
-        for (id, cap) in caps.iter() {
-            if cap.parent_id == Some(cap_id) {
-                to_revoke.push(*id);
+        let (count, revoked) = self.update(|table, _| {
+            // The target, its delegated children, their children, and so on
+            let mut count = 0;
+            for id in table.subtree([cap_id]) {
+                if let Some(cap) = table.capabilities.get_mut(&id) {
+                    Arc::make_mut(cap).revoked = true;
+                    table.revocations.insert(id);
+                    count += 1;
+                }
             }
-        }
 
-        // Revoke all identified capabilities
-        for id in to_revoke {
-            if let Some(cap) = caps.get_mut(&id) {
-                cap.revoked = true;
-                revocations.insert(id);
-                count += 1;
-            }
+            let revoked = table.capabilities.get(&cap_id).map(|cap| AuditEventType::CapabilityRevoked {
+                cap_id: cap_id.0.to_string(),
+                cascade_count: count,
+                owner: cap.owner.clone(),
+                rights: right_names(&cap.rights),
+            });
+            (count, revoked)
+        });
+        if let Some(event) = revoked {
+            self.audit(event).await;
         }
 
         Ok(count)
     }
 
-    /// List all capabilities for a specific owner
+    /// Revoke every capability in a tenant's namespace
+    ///
+    /// Returns the number revoked. Capabilities outside the namespace are
+    /// untouched, even if owned by a module acting for the tenant.
+    pub async fn revoke_tenant(&self, tenant_id: &str) -> usize {
+        let mut revoked = self.update(|table, _| {
+            let mut revoked = Vec::new();
+            for (id, cap) in table.capabilities.iter_mut() {
+                if !cap.revoked && resource_tenant(&cap.resource_id) == Some(tenant_id) {
+                    Arc::make_mut(cap).revoked = true;
+                    table.revocations.insert(*id);
+                    revoked.push((*id, AuditEventType::CapabilityRevoked {
+                        cap_id: id.0.to_string(),
+                        cascade_count: 1,
+                        owner: cap.owner.clone(),
+                        rights: right_names(&cap.rights),
+                    }));
+                }
+            }
+            revoked
+        });
+        revoked.sort_by_key(|(id, _)| *id);
+        let count = revoked.len();
+        self.audit_batch(revoked.into_iter().map(|(_, event)| event).collect()).await;
+        count
+    }
+
+    /// Revoke every capability bound to a module instance, and everything
+    /// delegated from them
+    ///
+    /// Returns the number revoked. Used when the instance is unloaded, since
+    /// nothing could present its tokens anymore.
+    pub async fn revoke_instance(&self, instance: &InstanceNonce) -> usize {
+        let fingerprint = instance.fingerprint();
+        let mut revoked = self.update(|table, _| {
+            let bound: Vec<CapabilityId> = table.capabilities.values()
+                .filter(|cap| cap.bound_instance.as_ref() == Some(&fingerprint))
+                .map(|cap| cap.id)
+                .collect();
+            let reached = table.subtree(bound);
+            let mut revoked = Vec::new();
+            for (id, cap) in table.capabilities.iter_mut() {
+                if !cap.revoked && reached.contains(id) {
+                    Arc::make_mut(cap).revoked = true;
+                    table.revocations.insert(*id);
+                    revoked.push((*id, AuditEventType::CapabilityRevoked {
+                        cap_id: id.0.to_string(),
+                        cascade_count: 1,
+                        owner: cap.owner.clone(),
+                        rights: right_names(&cap.rights),
+                    }));
+                }
+            }
+            revoked
+        });
+        revoked.sort_by_key(|(id, _)| *id);
+        let count = revoked.len();
+        self.audit_batch(revoked.into_iter().map(|(_, event)| event).collect()).await;
+        count
+    }
+
+    /// Replace the token secret and re-issue tokens for live capabilities
+    ///
+    /// Tokens issued under the old secret stop working. A capability bound to
+    /// a module instance is re-issued only if that instance's nonce is in
+    /// `instances` (the instances still running); the others are revoked,
+    /// since nothing could present their token anymore. Revoked and expired
+    /// capabilities are not re-issued. Returns the re-issued tokens and the
+    /// number of capabilities revoked.
+    pub async fn rotate_secret(&self, secret: Vec<u8>, instances: &[InstanceNonce]) -> (Vec<ReissuedToken>, usize) {
+        self.update(|table, tokens| {
+            table.secret = Arc::new(secret);
+
+            let now = self.current_timestamp();
+            let mut reissued = Vec::new();
+            let mut revoked = 0;
+            for (previous, id) in std::mem::take(tokens) {
+                let Some(cap) = table.capabilities.get_mut(&id) else { continue };
+                if cap.is_valid(now).is_err() {
+                    continue;
+                }
+                let instance = match &cap.bound_instance {
+                    None => None,
+                    Some(bound) => match instances.iter().find(|nonce| nonce.fingerprint() == *bound) {
+                        Some(nonce) => Some(nonce),
+                        None => {
+                            Arc::make_mut(cap).revoked = true;
+                            table.revocations.insert(id);
+                            revoked += 1;
+                            continue;
+                        }
+                    },
+                };
+
+                let token = CapabilityToken::new(id, &table.secret, instance);
+                tokens.insert(token.clone(), id);
+                reissued.push(ReissuedToken { capability_id: id, previous, token });
+            }
+            reissued.sort_by_key(|r| r.capability_id.0);
+            (reissued, revoked)
+        })
+    }
+
+    /// List all capabilities for a specific owner, in ID order
     pub async fn list_capabilities(&self, owner: &str) -> Vec<Capability> {
-        let caps = self.capabilities.read().await;
-        caps.values()
+        let table = self.table.load();
+        let mut owned: Vec<Capability> = table.capabilities.values()
             .filter(|c| c.owner == owner && !c.revoked)
-            .cloned()
-            .collect()
+            .map(|c| Capability::clone(c))
+            .collect();
+        owned.sort_by_key(|c| c.id);
+        owned
+    }
+
+    /// One page of an owner's capabilities, in ID order
+    ///
+    /// The cursor is the numeric ID of the previous page's last capability.
+    pub async fn capabilities_page(&self, owner: &str, page: &PageRequest) -> Result<Page<Capability>, KernelError> {
+        Page::from_sorted(self.list_capabilities(owner).await, page, |c| c.id.as_u64())
+    }
+
+    /// Export a signed snapshot of the active capabilities for security review
+    ///
+    /// Revoked and expired capabilities are left out; tokens and the secret
+    /// are never included. See [`CapabilitySnapshot`].
+    pub async fn export_state(&self) -> CapabilitySnapshot {
+        let table = self.table.load();
+        let now = self.current_timestamp();
+        let active = table.capabilities.values().map(|cap| &**cap).filter(|cap| cap.is_valid(now).is_ok());
+        CapabilitySnapshot::new(active, now, &table.secret)
+    }
+
+    /// Whether a snapshot was exported under the current secret and not altered since
+    pub async fn verify_snapshot(&self, snapshot: &CapabilitySnapshot) -> bool {
+        snapshot.verify(&self.table.load().secret)
     }
 
     /// Get statistics about the capability system
+    ///
+    /// Rates and denial reasons cover the rolling metrics window ending now.
     pub async fn stats(&self) -> CapabilityStats {
-        let caps = self.capabilities.read().await;
-        let revocations = self.revocations.read().await;
+        let table = self.table.load();
+        let caps = &table.capabilities;
 
         let active_count = caps.values().filter(|c| !c.revoked).count();
         let total_count = caps.len();
-        let revoked_count = revocations.len();
+        let revoked_count = table.revocations.len();
+
+        let mut delegation_depths = BTreeMap::new();
+        for cap in caps.values().filter(|c| !c.revoked) {
+            *delegation_depths.entry(delegation_depth(caps, cap)).or_default() += 1;
+        }
+
+        let metrics = self.metrics();
+        let window = metrics.window();
+        let counts = metrics.counts(self.current_timestamp());
+        let (total_validations, total_denials) = metrics.totals();
 
         CapabilityStats {
             active_count,
             total_count,
             revoked_count,
+            window_secs: window.as_secs(),
+            validations: counts.validations,
+            denials: counts.denials,
+            validations_per_sec: counts.validations as f64 / window.as_secs_f64(),
+            denials_per_sec: counts.denials as f64 / window.as_secs_f64(),
+            denials_by_reason: counts.denials_by_reason,
+            total_validations,
+            total_denials,
+            delegation_depths,
         }
     }
 }
 
+/// Delegations between a capability and the one the kernel issued at the root of its chain
+fn delegation_depth(caps: &HashMap<CapabilityId, Arc<Capability>>, cap: &Capability) -> usize {
+    let mut depth = 0;
+    let mut parent = cap.parent_id;
+    // Bounded by the number of capabilities, in case of a cycle
+    while let Some(id) = parent.filter(|_| depth < caps.len()) {
+        depth += 1;
+        parent = caps.get(&id).and_then(|c| c.parent_id);
+    }
+    depth
+}
+
+/// A capability token re-issued under a rotated secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReissuedToken {
+    pub capability_id: CapabilityId,
+    /// The token that stopped working
+    pub previous: CapabilityToken,
+    /// The token to use instead
+    pub token: CapabilityToken,
+}
+
 /// Statistics about the capability system
 #[derive(Debug, Clone, Serialize)]
 pub struct CapabilityStats {
     pub active_count: usize,
     pub total_count: usize,
     pub revoked_count: usize,
+    /// Span of the rolling window the counts below cover
+    pub window_secs: u64,
+    /// Validations in the window, allowed or denied
+    pub validations: u64,
+    pub denials: u64,
+    pub validations_per_sec: f64,
+    pub denials_per_sec: f64,
+    /// Denials in the window by [`CapabilityError::reason`]
+    pub denials_by_reason: BTreeMap<String, u64>,
+    /// Validations and denials since the manager was created
+    pub total_validations: u64,
+    pub total_denials: u64,
+    /// Unrevoked capabilities by delegation depth (0 for those the kernel issued)
+    pub delegation_depths: BTreeMap<usize, usize>,
 }
 
 /// Quick capability creation helpers
@@ -609,7 +1307,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_create_and_validate() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         let token = manager.create_read_only(
             ResourceType::Module,
@@ -626,7 +1324,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_insufficient_rights() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         let token = manager.create_read_only(
             ResourceType::Module,
@@ -640,7 +1338,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_delegation() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         // Create a capability with delegate right
         let token = manager.create_full_access(
@@ -670,7 +1368,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_delegation_monotonic_attenuation() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         // Create a read-only capability
         let token = manager.create_read_only(
@@ -696,7 +1394,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_revocation() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         let token = manager.create_read_only(
             ResourceType::Module,
@@ -713,9 +1411,76 @@ mod tests {
         assert!(matches!(result, Err(CapabilityError::Revoked)));
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_injected_clock_drives_expiry_and_metrics() {
+        use crate::clock::ManualClock;
+        use std::time::Duration;
+
+        const START: u64 = 1_700_000_000_000;
+        let clock = Arc::new(ManualClock::new(START));
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap()).with_clock(clock.clone());
+        let rights = [CapabilityRight::Read, CapabilityRight::Renew].into_iter().collect();
+        let token = manager
+            .create_capability(ResourceType::Module, "ledger".into(), rights, "accrual".into(), manager.lease(1_000, Some(5_000)))
+            .await
+            .unwrap();
+        assert_eq!(manager.validate(&token, &[]).await.unwrap().created_at, START);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(manager.renew(&token, 1_000).await.unwrap(), START + 1_500);
+
+        // Valid through the expiry millisecond, expired just after
+        clock.set(START + 1_500);
+        manager.validate(&token, &[]).await.unwrap();
+        clock.advance(Duration::from_millis(1));
+        assert!(matches!(manager.validate(&token, &[]).await, Err(CapabilityError::Expired)));
+
+        // The denial leaves the rolling window once the window has passed
+        assert_eq!(manager.stats().await.denials, 1);
+        clock.advance(Duration::from_secs(61));
+        let stats = manager.stats().await;
+        assert_eq!((stats.denials, stats.total_denials), (0, 1));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_writers_and_readers() {
+        let manager = Arc::new(CapabilityManager::new(CapabilityManager::generate_secret().unwrap()));
+        let kept = manager.create_read_only(ResourceType::Module, "kept".into(), "reader".into()).await.unwrap();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let manager = manager.clone();
+                let kept = kept.clone();
+                tokio::spawn(async move {
+                    for j in 0..25 {
+                        let token = manager
+                            .create_read_only(ResourceType::Module, format!("m{}-{}", i, j), format!("owner{}", i))
+                            .await
+                            .unwrap();
+                        if j % 5 == 0 {
+                            manager.revoke(&token).await.unwrap();
+                        }
+                        manager.record_usage(&kept).await.unwrap();
+                        assert!(manager.validate(&kept, &[CapabilityRight::Read]).await.is_ok());
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // No writer lost another's change
+        let stats = manager.stats().await;
+        assert_eq!((stats.total_count, stats.revoked_count), (201, 40));
+        assert_eq!(manager.list_capabilities("owner3").await.len(), 20);
+        let kept = manager.validate(&kept, &[]).await.unwrap();
+        assert_eq!(kept.validity.use_count, 200);
+    }
+
     #[tokio::test]
     async fn test_usage_limit() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         let mut rights = HashSet::new();
         rights.insert(CapabilityRight::Read);
@@ -724,6 +1489,7 @@ mod tests {
             expires_at: None,
             max_uses: Some(2),
             use_count: 0,
+            max_lifetime_ms: None,
         };
 
         let token = manager.create_capability(
@@ -745,7 +1511,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_capabilities() {
-        let manager = CapabilityManager::new(CapabilityManager::generate_secret());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
 
         manager.create_read_only(ResourceType::Module, "mod1".into(), "owner1".into()).await.unwrap();
         manager.create_read_only(ResourceType::Module, "mod2".into(), "owner1".into()).await.unwrap();
@@ -756,5 +1522,506 @@ mod tests {
 
         let owner2_caps = manager.list_capabilities("owner2").await;
         assert_eq!(owner2_caps.len(), 1);
+
+        // Listed and paged in ID order, rights serialized by name
+        assert!(owner1_caps[0].id < owner1_caps[1].id);
+        let first = manager.capabilities_page("owner1", &PageRequest::first(1)).await.unwrap();
+        assert_eq!(first.items[0].resource_id, "mod1");
+        let cursor = first.next_cursor.unwrap();
+        let second = manager.capabilities_page("owner1", &PageRequest::after(cursor, 1)).await.unwrap();
+        assert_eq!(second.items[0].resource_id, "mod2");
+        assert_eq!(second.next_cursor, None);
+        let json = serde_json::to_value(&owner1_caps[0]).unwrap();
+        assert_eq!(json["rights"], serde_json::json!(["List", "Read"]));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_scoped_capability() {
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+
+        let token = manager.create_tenant_capability(
+            "acme",
+            ResourceType::Custom("ledger".into()),
+            "ledger",
+            [CapabilityRight::Read].into_iter().collect(),
+            "accrual".into(),
+            CapabilityValidity::default(),
+        ).await.expect("Should create capability");
+
+        let cap = manager.validate_for_tenant(&token, "acme", &[CapabilityRight::Read]).await
+            .expect("Should validate for owning tenant");
+        assert_eq!(cap.resource_id, "tenant:acme/ledger");
+
+        let result = manager.validate_for_tenant(&token, "globex", &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::CrossTenant(_))));
+
+        // Unscoped capabilities never satisfy a tenant check
+        let global = manager.create_read_only(
+            ResourceType::Module,
+            "ledger".into(),
+            "accrual".into(),
+        ).await.unwrap();
+        let result = manager.validate_for_tenant(&global, "acme", &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::CrossTenant(_))));
+
+        // Revoking a tenant reaches only its own namespace
+        let globex = manager.create_tenant_capability(
+            "globex",
+            ResourceType::Custom("ledger".into()),
+            "ledger",
+            [CapabilityRight::Read].into_iter().collect(),
+            "accrual".into(),
+            CapabilityValidity::default(),
+        ).await.unwrap();
+        assert_eq!(manager.revoke_tenant("acme").await, 1);
+        assert_eq!(manager.revoke_tenant("acme").await, 0);
+        assert!(manager.validate(&token, &[CapabilityRight::Read]).await.is_err());
+        assert!(manager.validate(&global, &[CapabilityRight::Read]).await.is_ok());
+        assert!(manager.validate_for_tenant(&globex, "globex", &[CapabilityRight::Read]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_instance_bound_capability() {
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let instance = InstanceNonce::generate();
+
+        let token = manager.create_bound_capability(
+            ResourceType::Module,
+            "ledger".into(),
+            [CapabilityRight::Read, CapabilityRight::Delegate].into_iter().collect(),
+            "accrual".into(),
+            CapabilityValidity::default(),
+            &instance,
+        ).await.expect("Should create capability");
+
+        manager.validate_from_instance(&token, &instance, &[CapabilityRight::Read]).await
+            .expect("Should validate for the owning instance");
+
+        // Replayed by an external caller or another instance
+        let result = manager.validate(&token, &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::InstanceMismatch)));
+        let other = InstanceNonce::generate();
+        let result = manager.validate_from_instance(&token, &other, &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::InstanceMismatch)));
+        let result = manager.delegate(&token, "other".into(), HashSet::new(), CapabilityValidity::default()).await;
+        assert!(matches!(result, Err(CapabilityError::InstanceMismatch)));
+
+        // Unbound tokens still validate when presented by an instance
+        let unbound = manager.create_read_only(ResourceType::Module, "ledger".into(), "accrual".into()).await.unwrap();
+        assert!(manager.validate_from_instance(&unbound, &instance, &[CapabilityRight::Read]).await.is_ok());
+
+        // A token naming a valid capability ID without the kernel's MAC is rejected
+        let forged = CapabilityToken(format!("cap_{}_0000000000000000", token.capability_id().unwrap().0));
+        let result = manager.validate_from_instance(&forged, &instance, &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::InvalidToken)));
+        assert_eq!(format!("{:?}", instance), "InstanceNonce(..)");
+
+        // Unloading the instance revokes only what is bound to it
+        assert_eq!(manager.revoke_instance(&other).await, 0);
+        assert_eq!(manager.revoke_instance(&instance).await, 1);
+        let result = manager.validate_from_instance(&token, &instance, &[CapabilityRight::Read]).await;
+        assert!(matches!(result, Err(CapabilityError::Revoked)));
+        assert!(manager.validate_from_instance(&unbound, &instance, &[CapabilityRight::Read]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_secret_reissues_live_tokens() {
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let running = InstanceNonce::generate();
+        let stopped = InstanceNonce::generate();
+        let read = [CapabilityRight::Read];
+
+        let unbound = manager.create_read_only(ResourceType::Module, "ledger".into(), "host".into()).await.unwrap();
+        let bound = manager.create_bound_capability(
+            ResourceType::Module, "ledger".into(), read.into_iter().collect(), "accrual".into(),
+            CapabilityValidity::default(), &running,
+        ).await.unwrap();
+        let orphaned = manager.create_bound_capability(
+            ResourceType::Module, "ledger".into(), read.into_iter().collect(), "accrual".into(),
+            CapabilityValidity::default(), &stopped,
+        ).await.unwrap();
+        let revoked = manager.create_read_only(ResourceType::Module, "ledger".into(), "host".into()).await.unwrap();
+        manager.revoke(&revoked).await.unwrap();
+
+        let (reissued, revoked_count) = manager
+            .rotate_secret(CapabilityManager::generate_secret().unwrap(), std::slice::from_ref(&running))
+            .await;
+        assert_eq!(reissued.len(), 2);
+        assert_eq!(revoked_count, 1);
+
+        // Old tokens stop working; their replacements work
+        assert!(matches!(manager.validate(&unbound, &read).await, Err(CapabilityError::InvalidToken)));
+        let new_unbound = &reissued.iter().find(|r| r.previous == unbound).unwrap().token;
+        assert!(manager.validate(new_unbound, &read).await.is_ok());
+        let new_bound = &reissued.iter().find(|r| r.previous == bound).unwrap().token;
+        assert!(manager.validate_from_instance(new_bound, &running, &read).await.is_ok());
+
+        // The capability bound to a stopped instance was revoked
+        assert!(matches!(
+            manager.validate_from_instance(&orphaned, &stopped, &read).await,
+            Err(CapabilityError::Revoked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_operations_are_audited() {
+        let audit_log = Arc::new(AuditLog::with_defaults());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap()).with_audit_log(audit_log.clone());
+
+        let token = manager.create_full_access(ResourceType::Module, "ledger".into(), "host".into()).await.unwrap();
+        manager.validate(&token, &[CapabilityRight::Read]).await.unwrap();
+        let delegated = manager.delegate(
+            &token,
+            "accrual".into(),
+            [CapabilityRight::Read].into_iter().collect(),
+            CapabilityValidity::default(),
+        ).await.unwrap();
+        assert!(manager.validate(&delegated, &[CapabilityRight::Write]).await.is_err());
+        assert_eq!(manager.revoke(&token).await.unwrap(), 2);
+
+        let events: Vec<_> = audit_log.get_all_entries().await.into_iter().map(|e| e.event).collect();
+        let parent = token.capability_id().unwrap().0.to_string();
+        let child = delegated.capability_id().unwrap().0.to_string();
+        assert_eq!(events.len(), 5);
+        assert!(matches!(&events[0], AuditEventType::CapabilityCreated { cap_id, owner, rights }
+            if *cap_id == parent && owner == "host" && rights.len() == 8));
+        assert_eq!(events[1], AuditEventType::CapabilityValidated {
+            cap_id: parent.clone(),
+            operation: "read".into(),
+            owner: "host".into(),
+            rights: ["create", "delegate", "delete", "execute", "list", "read", "revoke", "write"]
+                .map(String::from)
+                .to_vec(),
+        });
+        assert_eq!(events[2], AuditEventType::CapabilityDelegated {
+            parent_id: parent.clone(),
+            new_id: child.clone(),
+            new_owner: "accrual".into(),
+            rights: vec!["read".into()],
+        });
+        assert!(matches!(&events[3], AuditEventType::CapabilityDenied { cap_id, owner: Some(owner), rights, .. }
+            if *cap_id == child && owner == "accrual" && *rights == vec!["write".to_string()]));
+        assert!(matches!(&events[4], AuditEventType::CapabilityRevoked { cap_id, cascade_count: 2, owner, .. }
+            if *cap_id == parent && owner == "host"));
+
+        // Tokens never appear in the log
+        let json = serde_json::to_string(&events).unwrap();
+        assert!(!json.contains(token.as_str()) && !json.contains(delegated.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_and_denial_alert() {
+        let audit_log = Arc::new(AuditLog::with_defaults());
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap())
+            .with_audit_log(audit_log.clone())
+            .with_metrics(CapabilityMetricsConfig { denial_alert_threshold: Some(3), ..Default::default() });
+
+        let token = manager.create_full_access(ResourceType::Module, "ledger".into(), "host".into()).await.unwrap();
+        let read = [CapabilityRight::Read].into_iter().collect::<HashSet<_>>();
+        let child = manager.delegate(&token, "accrual".into(), read.clone(), CapabilityValidity::default()).await.unwrap();
+        manager.delegate(&child, "report".into(), read, CapabilityValidity::default()).await.unwrap_err();
+        manager.validate(&child, &[CapabilityRight::Read]).await.unwrap();
+        manager.validate(&child, &[CapabilityRight::Write]).await.unwrap_err();
+        manager.validate(&CapabilityToken::presented("forged"), &[]).await.unwrap_err();
+
+        let stats = manager.stats().await;
+        assert_eq!((stats.validations, stats.denials, stats.total_denials), (5, 3, 3));
+        assert_eq!(stats.denials_by_reason.get("insufficient_rights"), Some(&2));
+        assert_eq!(stats.denials_by_reason.get("invalid_token"), Some(&1));
+        assert_eq!(stats.window_secs, 60);
+        assert!((stats.denials_per_sec - 3.0 / 60.0).abs() < 1e-9);
+        assert_eq!(stats.delegation_depths, BTreeMap::from([(0, 1), (1, 1)]));
+
+        // The third denial reached the threshold; the fourth stays quiet
+        manager.validate(&child, &[CapabilityRight::Delete]).await.unwrap_err();
+        let alerts: Vec<_> = audit_log.get_all_entries().await.into_iter()
+            .filter_map(|e| match e.event {
+                AuditEventType::Custom { category, message } if category == SECURITY_ALERT_CATEGORY => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].starts_with("3 capability denials within 60 seconds (threshold 3); most for accrual (2)"), "{}", alerts[0]);
+    }
+
+    /// Property tests of the attenuation lattice: delegation never widens
+    /// rights, and revocation reaches every descendant
+    ///
+    /// They run on simulated time, so lease expiry and capability IDs are the
+    /// same whenever a failing case is shrunk or replayed.
+    mod lattice {
+        use super::*;
+        use crate::testing::TimeMachine;
+        use proptest::prelude::*;
+        use proptest_state_machine::{prop_state_machine, ReferenceStateMachine, StateMachineTest};
+        use std::time::Duration;
+
+        /// Rights the tests grant, as bits of a mask; few, so subsets are common
+        const RIGHTS: [CapabilityRight; 4] = [
+            CapabilityRight::Read,
+            CapabilityRight::Write,
+            CapabilityRight::Execute,
+            CapabilityRight::Delegate,
+        ];
+        const DELEGATE: u8 = 1 << 3;
+        const ALL: u8 = (1 << RIGHTS.len()) - 1;
+
+        fn rights(mask: u8) -> HashSet<CapabilityRight> {
+            RIGHTS.iter().enumerate().filter(|(bit, _)| mask & (1 << bit) != 0).map(|(_, right)| *right).collect()
+        }
+
+        fn validity(lease_ms: Option<u64>) -> CapabilityValidity {
+            lease_ms.map_or_else(CapabilityValidity::default, |ms| CapabilityValidity::lease(ms, None))
+        }
+
+        /// A current-thread runtime with simulated time started
+        fn simulated() -> (tokio::runtime::Runtime, TimeMachine) {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+            let time = runtime.block_on(async { TimeMachine::start_on("2025-01-01".parse().unwrap()) });
+            (runtime, time)
+        }
+
+        /// Stop simulating time; resuming tokio's clock needs its runtime
+        fn finish(runtime: tokio::runtime::Runtime, time: TimeMachine) {
+            let entered = runtime.enter();
+            drop(time);
+            drop(entered);
+        }
+
+        proptest! {
+            #[test]
+            fn delegation_chain_never_widens_rights(masks in prop::collection::vec(0..=ALL, 1..8), cut in any::<prop::sample::Index>()) {
+                let (runtime, time) = simulated();
+                runtime.block_on(async {
+                    let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+                    let root = manager
+                        .create_capability(ResourceType::Module, "ledger".into(), rights(ALL), "root".into(), CapabilityValidity::default())
+                        .await
+                        .unwrap();
+
+                    // Each link asks for an arbitrary set; only subsets of its parent are granted
+                    let mut chain = vec![(root, ALL)];
+                    for (depth, &mask) in masks.iter().enumerate() {
+                        let (parent, parent_mask) = chain.last().unwrap().clone();
+                        let granted = manager.delegate(&parent, format!("holder{}", depth), rights(mask), CapabilityValidity::default()).await;
+                        let allowed = parent_mask & DELEGATE != 0 && mask & !parent_mask == 0;
+                        prop_assert_eq!(granted.is_ok(), allowed, "mask {:#06b} under {:#06b}", mask, parent_mask);
+                        match granted {
+                            Ok(token) => chain.push((token, mask)),
+                            Err(_) => break,
+                        }
+                    }
+
+                    // Revoking any link revokes exactly the links below it
+                    let cut = cut.index(chain.len());
+                    prop_assert_eq!(manager.revoke(&chain[cut].0).await.unwrap(), chain.len() - cut);
+                    for (depth, (token, _)) in chain.iter().enumerate() {
+                        let result = manager.validate(token, &[]).await;
+                        prop_assert_eq!(result.is_err(), depth >= cut, "link {} after revoking link {}", depth, cut);
+                    }
+                    Ok(())
+                })?;
+                finish(runtime, time);
+            }
+        }
+
+        /// What the manager should hold: capabilities in creation order
+        #[derive(Debug, Clone, Default)]
+        struct Model {
+            caps: Vec<ModelCap>,
+            /// Milliseconds since the test started
+            now: u64,
+        }
+
+        #[derive(Debug, Clone)]
+        struct ModelCap {
+            parent: Option<usize>,
+            rights: u8,
+            expires_at: Option<u64>,
+            revoked: bool,
+        }
+
+        impl Model {
+            /// The denial reason validation should give, if any
+            fn check(&self, index: usize, required: u8) -> Result<(), &'static str> {
+                let cap = &self.caps[index];
+                if cap.revoked {
+                    Err("revoked")
+                } else if cap.expires_at.is_some_and(|at| self.now > at) {
+                    Err("expired")
+                } else if required & !cap.rights != 0 {
+                    Err("insufficient_rights")
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn descends_from(&self, index: usize, ancestor: usize) -> bool {
+                std::iter::successors(Some(index), |&i| self.caps[i].parent).any(|i| i == ancestor)
+            }
+        }
+
+        #[derive(Debug, Clone)]
+        enum Transition {
+            Create { rights: u8, lease_ms: Option<u64> },
+            Delegate { parent: usize, rights: u8, lease_ms: Option<u64> },
+            Revoke { target: usize },
+            Validate { target: usize, required: u8 },
+            Advance { ms: u64 },
+        }
+
+        struct CapabilityModel;
+
+        impl ReferenceStateMachine for CapabilityModel {
+            type State = Model;
+            type Transition = Transition;
+
+            fn init_state() -> BoxedStrategy<Model> {
+                Just(Model::default()).boxed()
+            }
+
+            fn transitions(state: &Model) -> BoxedStrategy<Transition> {
+                let lease = prop::option::of(1..100u64);
+                let create = (0..=ALL, lease.clone()).prop_map(|(rights, lease_ms)| Transition::Create { rights, lease_ms });
+                if state.caps.is_empty() {
+                    return create.boxed();
+                }
+
+                // Half the delegations ask for a subset of the parent's rights
+                let held: Vec<u8> = state.caps.iter().map(|cap| cap.rights).collect();
+                let count = held.len();
+                let delegate = (0..count, 0..=ALL, any::<bool>(), lease).prop_map(move |(parent, mask, subset, lease_ms)| {
+                    let rights = if subset { mask & held[parent] } else { mask };
+                    Transition::Delegate { parent, rights, lease_ms }
+                });
+                prop_oneof![
+                    2 => create,
+                    4 => delegate,
+                    1 => (0..count).prop_map(|target| Transition::Revoke { target }),
+                    3 => (0..count, 0..=ALL).prop_map(|(target, required)| Transition::Validate { target, required }),
+                    1 => (1..50u64).prop_map(|ms| Transition::Advance { ms }),
+                ]
+                .boxed()
+            }
+
+            fn preconditions(state: &Model, transition: &Transition) -> bool {
+                match *transition {
+                    Transition::Create { .. } | Transition::Advance { .. } => true,
+                    Transition::Delegate { parent: index, .. }
+                    | Transition::Revoke { target: index }
+                    | Transition::Validate { target: index, .. } => index < state.caps.len(),
+                }
+            }
+
+            fn apply(mut state: Model, transition: &Transition) -> Model {
+                match *transition {
+                    Transition::Create { rights, lease_ms } => {
+                        let expires_at = lease_ms.map(|ms| state.now + ms);
+                        state.caps.push(ModelCap { parent: None, rights, expires_at, revoked: false });
+                    }
+                    Transition::Delegate { parent, rights, lease_ms } => {
+                        if state.check(parent, DELEGATE).is_ok() && rights & !state.caps[parent].rights == 0 {
+                            let expires_at = lease_ms.map(|ms| state.now + ms);
+                            state.caps.push(ModelCap { parent: Some(parent), rights, expires_at, revoked: false });
+                        }
+                    }
+                    Transition::Revoke { target } => {
+                        for index in 0..state.caps.len() {
+                            if state.descends_from(index, target) {
+                                state.caps[index].revoked = true;
+                            }
+                        }
+                    }
+                    Transition::Validate { .. } => {}
+                    Transition::Advance { ms } => state.now += ms,
+                }
+                state
+            }
+        }
+
+        struct Subject {
+            runtime: tokio::runtime::Runtime,
+            time: TimeMachine,
+            manager: CapabilityManager,
+            /// Tokens in creation order, matching the model's capabilities
+            tokens: Vec<CapabilityToken>,
+        }
+
+        impl StateMachineTest for CapabilityModel {
+            type SystemUnderTest = Subject;
+            type Reference = Self;
+
+            fn init_test(_: &Model) -> Subject {
+                let (runtime, time) = simulated();
+                let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+                Subject { runtime, time, manager, tokens: Vec::new() }
+            }
+
+            fn apply(mut subject: Subject, model: &Model, transition: Transition) -> Subject {
+                let Subject { runtime, time, manager, tokens } = &mut subject;
+                runtime.block_on(async {
+                    match transition {
+                        Transition::Create { rights: mask, lease_ms } => {
+                            let token = manager
+                                .create_capability(ResourceType::Module, "ledger".into(), rights(mask), "root".into(), validity(lease_ms))
+                                .await
+                                .unwrap();
+                            tokens.push(token);
+                        }
+                        Transition::Delegate { parent, rights: mask, lease_ms } => {
+                            let owner = format!("holder{}", tokens.len());
+                            match manager.delegate(&tokens[parent], owner, rights(mask), validity(lease_ms)).await {
+                                Ok(token) => {
+                                    assert_eq!(model.caps.len(), tokens.len() + 1, "delegation from {} should be refused", parent);
+                                    tokens.push(token);
+                                }
+                                Err(e) => assert_eq!(model.caps.len(), tokens.len(), "delegation from {} refused: {}", parent, e),
+                            }
+                        }
+                        Transition::Revoke { target } => {
+                            let count = manager.revoke(&tokens[target]).await.unwrap();
+                            let subtree = (0..model.caps.len()).filter(|&i| model.descends_from(i, target)).count();
+                            assert_eq!(count, subtree, "revoking {}", target);
+                        }
+                        Transition::Validate { target, required } => {
+                            let needed: Vec<CapabilityRight> = rights(required).into_iter().collect();
+                            let result = manager.validate(&tokens[target], &needed).await;
+                            assert_eq!(result.map(|_| ()).map_err(|e| e.reason()), model.check(target, required), "validating {}", target);
+                        }
+                        Transition::Advance { ms } => time.advance(Duration::from_millis(ms)).await,
+                    }
+                });
+                subject
+            }
+
+            fn check_invariants(subject: &Subject, model: &Model) {
+                subject.runtime.block_on(async {
+                    let table = subject.manager.table.load();
+                    for (index, token) in subject.tokens.iter().enumerate() {
+                        let result = subject.manager.validate(token, &[]).await;
+                        assert_eq!(result.as_ref().map(|_| ()).map_err(|e| e.reason()), model.check(index, 0), "capability {}", index);
+
+                        // A valid capability has no revoked ancestor and no right an ancestor lacks
+                        let Ok(cap) = result else { continue };
+                        let mut parent = cap.parent_id;
+                        while let Some(id) = parent {
+                            let ancestor = &table.capabilities[&id];
+                            assert!(!ancestor.revoked, "capability {} is valid under revoked {:?}", index, id);
+                            assert!(cap.rights.is_subset(&ancestor.rights), "capability {} has rights {:?} lacks", index, id);
+                            parent = ancestor.parent_id;
+                        }
+                    }
+                });
+            }
+
+            fn teardown(subject: Subject, _: Model) {
+                finish(subject.runtime, subject.time);
+            }
+        }
+
+        prop_state_machine! {
+            #[test]
+            fn capability_manager_matches_model(sequential 1..40 => CapabilityModel);
+        }
     }
 }
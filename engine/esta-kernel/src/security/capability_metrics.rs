@@ -0,0 +1,220 @@
+//! Capability Validation Metrics
+//!
+//! The [`CapabilityManager`](super::CapabilityManager) counts validations and
+//! denials in one-second buckets covering a rolling window (a minute by
+//! default), so [`CapabilityManager::stats`](super::CapabilityManager::stats)
+//! can report current rates and why operations were refused.
+//!
+//! A module probing for capabilities it was not granted shows up as a spike
+//! in denials. With [`CapabilityMetricsConfig::denial_alert_threshold`] set,
+//! the manager records a `Custom` audit entry in category
+//! [`SECURITY_ALERT_CATEGORY`] once the denials within the window reach the
+//! threshold, naming the owner refused most often. No further alert is raised
+//! until a full window has passed.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// Default span of the rolling metrics window
+pub const DEFAULT_METRICS_WINDOW: Duration = Duration::from_secs(60);
+
+/// Category of the `Custom` audit entries raised for denial spikes
+pub const SECURITY_ALERT_CATEGORY: &str = "security";
+
+/// Owner recorded for denials of tokens that name no known capability
+const UNKNOWN_OWNER: &str = "unknown";
+
+/// How capability metrics are kept, and when denials raise an alert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityMetricsConfig {
+    /// Span of the rolling window rates are computed over
+    pub window: Duration,
+    /// Denials within the window that raise a security alert; none by default
+    pub denial_alert_threshold: Option<u64>,
+}
+
+impl Default for CapabilityMetricsConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_METRICS_WINDOW,
+            denial_alert_threshold: None,
+        }
+    }
+}
+
+/// Counts within one second
+#[derive(Debug, Default)]
+struct Bucket {
+    /// Seconds since the Unix epoch
+    second: u64,
+    validations: u64,
+    denials_by_reason: BTreeMap<&'static str, u64>,
+    denials_by_owner: BTreeMap<String, u64>,
+}
+
+/// Validations and denials within the current window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct WindowCounts {
+    pub validations: u64,
+    pub denials: u64,
+    pub denials_by_reason: BTreeMap<String, u64>,
+}
+
+/// Rolling validation and denial counts
+#[derive(Debug)]
+pub(crate) struct CapabilityMetrics {
+    config: CapabilityMetricsConfig,
+    /// Oldest first, one per second that saw a validation
+    buckets: VecDeque<Bucket>,
+    total_validations: u64,
+    total_denials: u64,
+    /// When the last denial alert was raised (ms since Unix epoch)
+    last_alert: Option<u64>,
+}
+
+impl CapabilityMetrics {
+    pub(crate) fn new(config: CapabilityMetricsConfig) -> Self {
+        Self {
+            config,
+            buckets: VecDeque::new(),
+            total_validations: 0,
+            total_denials: 0,
+            last_alert: None,
+        }
+    }
+
+    fn window_secs(&self) -> u64 {
+        self.config.window.as_secs().max(1)
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs())
+    }
+
+    /// Validations and denials since the metrics were created
+    pub(crate) fn totals(&self) -> (u64, u64) {
+        (self.total_validations, self.total_denials)
+    }
+
+    /// Count an allowed validation at `now` (Unix millis)
+    pub(crate) fn allowed(&mut self, now: u64) {
+        self.total_validations += 1;
+        self.bucket(now).validations += 1;
+    }
+
+    /// Count a denied validation at `now` (Unix millis)
+    ///
+    /// Returns the alert message when this denial takes the window to the
+    /// alert threshold.
+    pub(crate) fn denied(&mut self, now: u64, reason: &'static str, owner: Option<&str>) -> Option<String> {
+        self.total_validations += 1;
+        self.total_denials += 1;
+        let bucket = self.bucket(now);
+        bucket.validations += 1;
+        *bucket.denials_by_reason.entry(reason).or_default() += 1;
+        *bucket.denials_by_owner.entry(owner.unwrap_or(UNKNOWN_OWNER).to_string()).or_default() += 1;
+
+        let threshold = self.config.denial_alert_threshold?;
+        let window_ms = self.window_secs() * 1000;
+        if self.last_alert.is_some_and(|at| now < at.saturating_add(window_ms)) {
+            return None;
+        }
+        let counts = self.counts(now);
+        if counts.denials < threshold.max(1) {
+            return None;
+        }
+        self.last_alert = Some(now);
+        Some(self.alert_message(&counts))
+    }
+
+    /// Counts within the window ending at `now` (Unix millis)
+    pub(crate) fn counts(&self, now: u64) -> WindowCounts {
+        let mut counts = WindowCounts::default();
+        for bucket in self.current(now) {
+            counts.validations += bucket.validations;
+            for (reason, denials) in &bucket.denials_by_reason {
+                counts.denials += denials;
+                *counts.denials_by_reason.entry(reason.to_string()).or_default() += denials;
+            }
+        }
+        counts
+    }
+
+    fn alert_message(&self, counts: &WindowCounts) -> String {
+        let mut owners: BTreeMap<&str, u64> = BTreeMap::new();
+        for bucket in &self.buckets {
+            for (owner, denials) in &bucket.denials_by_owner {
+                *owners.entry(owner).or_default() += denials;
+            }
+        }
+        // Ties go to the first owner by name, so the message is deterministic
+        let (owner, owner_denials) = owners
+            .into_iter()
+            .fold((UNKNOWN_OWNER, 0), |top, (owner, n)| if n > top.1 { (owner, n) } else { top });
+        let reasons: Vec<String> = counts.denials_by_reason.iter().map(|(r, n)| format!("{}={}", r, n)).collect();
+        format!(
+            "{} capability denials within {} seconds (threshold {}); most for {} ({}); reasons: {}",
+            counts.denials,
+            self.window_secs(),
+            self.config.denial_alert_threshold.unwrap_or_default(),
+            owner,
+            owner_denials,
+            reasons.join(", "),
+        )
+    }
+
+    /// Buckets within the window ending at `now`
+    fn current(&self, now: u64) -> impl Iterator<Item = &Bucket> {
+        let oldest = (now / 1000).saturating_sub(self.window_secs() - 1);
+        self.buckets.iter().filter(move |b| b.second >= oldest)
+    }
+
+    /// The bucket for `now`, dropping buckets that have left the window
+    fn bucket(&mut self, now: u64) -> &mut Bucket {
+        let second = now / 1000;
+        let oldest = second.saturating_sub(self.window_secs() - 1);
+        while self.buckets.front().is_some_and(|b| b.second < oldest) {
+            self.buckets.pop_front();
+        }
+        // The wall clock can step backwards; count such readings in the newest bucket
+        if self.buckets.back().is_none_or(|b| b.second < second) {
+            self.buckets.push_back(Bucket { second, ..Bucket::default() });
+        }
+        self.buckets.back_mut().expect("bucket pushed above")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_rolls_and_alerts_once() {
+        let mut metrics = CapabilityMetrics::new(CapabilityMetricsConfig {
+            window: Duration::from_secs(10),
+            denial_alert_threshold: Some(3),
+        });
+        let start = 1_700_000_000_000;
+        metrics.allowed(start);
+        assert!(metrics.denied(start, "revoked", Some("accrual")).is_none());
+        assert!(metrics.denied(start + 1_000, "invalid_token", None).is_none());
+        let alert = metrics.denied(start + 2_000, "revoked", Some("accrual")).unwrap();
+        assert_eq!(
+            alert,
+            "3 capability denials within 10 seconds (threshold 3); most for accrual (2); reasons: invalid_token=1, revoked=2"
+        );
+        // Quiet until a full window has passed since the alert
+        assert!(metrics.denied(start + 3_000, "revoked", Some("accrual")).is_none());
+
+        let counts = metrics.counts(start + 3_000);
+        assert_eq!((counts.validations, counts.denials), (5, 4));
+        assert_eq!(counts.denials_by_reason["revoked"], 3);
+
+        // The first second's counts leave the window ten seconds on
+        let counts = metrics.counts(start + 10_000);
+        assert_eq!((counts.validations, counts.denials), (3, 3));
+        assert_eq!(metrics.totals(), (5, 4));
+        assert!(metrics.denied(start + 11_000, "expired", Some("payroll")).is_none());
+        assert!(metrics.denied(start + 12_500, "expired", Some("payroll")).is_some());
+    }
+}
@@ -0,0 +1,213 @@
+//! Field Encryption for Stored Records
+//!
+//! Employee IDs and hour records written to the database (see
+//! [`crate::database`]) are sealed with AES-256-GCM under a key kept in the
+//! [`SecretStore`]. Values are bound to a context (column and tenant) as
+//! associated data, so a sealed value cannot be moved to another tenant.
+//!
+//! The nonce is derived from the key, context, and plaintext (a synthetic
+//! IV), so equal values in the same context seal identically. That keeps
+//! roster uniqueness and lookups working on sealed columns; the cost is that
+//! equality of values is visible, never the values themselves.
+//!
+//! Each sealed value names the key that sealed it. Rotation keeps the
+//! previous key in the store until every record has been re-sealed
+//! ([`FieldCipher::begin_rotation`], [`FieldCipher::finish_rotation`]), so
+//! an interrupted rotation can be resumed without losing data.
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use std::sync::Arc;
+
+use super::secrets::{SecretError, SecretResult, SecretStore};
+
+/// Name of the database field key in the secret store
+pub const DATABASE_KEY: &str = "database-key";
+
+/// Name the replaced key is kept under while a rotation is in progress
+pub const PREVIOUS_DATABASE_KEY: &str = "database-key.previous";
+
+/// Prefix of sealed values
+const SEALED_PREFIX: &str = "enc:";
+
+/// A field key and the keys derived from it
+struct FieldKey {
+    id: String,
+    aead: LessSafeKey,
+    nonce: hmac::Key,
+}
+
+impl FieldKey {
+    fn new(secret: &[u8]) -> SecretResult<Self> {
+        if secret.len() != 32 {
+            return Err(SecretError::Corrupt("database key must be 32 bytes".into()));
+        }
+        let root = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        let derive = |label: &[u8]| hmac::sign(&root, label);
+        let aead = LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, derive(b"esta-field-aead").as_ref()).expect("32-byte key"),
+        );
+        Ok(Self {
+            id: hex::encode(&derive(b"esta-field-id").as_ref()[..8]),
+            aead,
+            nonce: hmac::Key::new(hmac::HMAC_SHA256, derive(b"esta-field-nonce").as_ref()),
+        })
+    }
+}
+
+/// Seals and opens stored field values
+///
+/// Holds the current key, which seals, and optionally the previous one,
+/// which still opens values sealed before a rotation.
+#[derive(Clone)]
+pub struct FieldCipher {
+    /// Current key first
+    keys: Vec<Arc<FieldKey>>,
+}
+
+impl FieldCipher {
+    /// A cipher sealing with a 32-byte key
+    pub fn new(key: &[u8]) -> SecretResult<Self> {
+        Ok(Self { keys: vec![Arc::new(FieldKey::new(key)?)] })
+    }
+
+    /// Also open values sealed with a previous key
+    pub fn with_previous(mut self, key: &[u8]) -> SecretResult<Self> {
+        self.keys.truncate(1);
+        self.keys.push(Arc::new(FieldKey::new(key)?));
+        Ok(self)
+    }
+
+    /// The cipher whose keys are in a secret store, generating the key on first use
+    pub fn from_store(store: &mut SecretStore) -> SecretResult<Self> {
+        let cipher = Self::new(store.get_or_generate(DATABASE_KEY, 32)?.expose())?;
+        match store.get(PREVIOUS_DATABASE_KEY)? {
+            Some(previous) => cipher.with_previous(previous.expose()),
+            None => Ok(cipher),
+        }
+    }
+
+    /// Replace the store's key, keeping the old one until [`Self::finish_rotation`]
+    ///
+    /// If a rotation is already in progress, the pending one is resumed instead of
+    /// starting another, which would discard the key older records still need.
+    /// Returns the cipher to re-seal records with.
+    pub fn begin_rotation(store: &mut SecretStore) -> SecretResult<Self> {
+        if store.get(PREVIOUS_DATABASE_KEY)?.is_none() {
+            store.get_or_generate(DATABASE_KEY, 32)?;
+            store.copy(DATABASE_KEY, PREVIOUS_DATABASE_KEY)?;
+            store.rotate(DATABASE_KEY, 32)?;
+        }
+        Self::from_store(store)
+    }
+
+    /// Drop the previous key once every record is sealed with the current one
+    pub fn finish_rotation(store: &mut SecretStore) -> SecretResult<()> {
+        store.remove(PREVIOUS_DATABASE_KEY)?;
+        Ok(())
+    }
+
+    /// ID of the current key, as recorded in sealed values
+    pub fn key_id(&self) -> &str {
+        &self.keys[0].id
+    }
+
+    /// Whether a stored value is sealed (rather than plaintext)
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(SEALED_PREFIX)
+    }
+
+    /// Seal a value under the current key
+    pub fn seal(&self, context: &str, plaintext: &str) -> String {
+        let key = &self.keys[0];
+        let tag = hmac::sign(&key.nonce, &[context.as_bytes(), &[0], plaintext.as_bytes()].concat());
+        let nonce: [u8; NONCE_LEN] = tag.as_ref()[..NONCE_LEN].try_into().expect("nonce length");
+        let mut buffer = plaintext.as_bytes().to_vec();
+        key.aead
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(context), &mut buffer)
+            .expect("AES-GCM sealing of an in-memory buffer");
+        format!("{}{}:{}{}", SEALED_PREFIX, key.id, hex::encode(nonce), hex::encode(buffer))
+    }
+
+    /// Open a sealed value
+    pub fn open(&self, context: &str, sealed: &str) -> SecretResult<String> {
+        let invalid = || SecretError::Corrupt("sealed value is malformed".into());
+        let (key_id, body) = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(invalid)?;
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| SecretError::Corrupt(format!("value is sealed with unknown key {}", key_id)))?;
+        let bytes = hex::decode(body).map_err(|_| invalid())?;
+        if bytes.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let mut buffer = ciphertext.to_vec();
+        let plaintext = key
+            .aead
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce.try_into().expect("nonce length")),
+                Aad::from(context),
+                &mut buffer,
+            )
+            .map_err(|_| SecretError::Corrupt(format!("sealed value does not open in context {}", context)))?;
+        String::from_utf8(plaintext.to_vec()).map_err(|_| invalid())
+    }
+}
+
+impl std::fmt::Debug for FieldCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FieldCipher").field("key_id", &self.key_id()).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::MasterKey;
+
+    #[test]
+    fn test_seal_open_and_rotation() {
+        let cipher = FieldCipher::new(&[1; 32]).unwrap();
+        let sealed = cipher.seal("employee_id:acme", "emp-42");
+        assert!(FieldCipher::is_sealed(&sealed));
+        assert!(!sealed.contains("emp-42"));
+        assert_eq!(cipher.seal("employee_id:acme", "emp-42"), sealed);
+        assert_ne!(cipher.seal("employee_id:globex", "emp-42"), sealed);
+        assert_eq!(cipher.open("employee_id:acme", &sealed).unwrap(), "emp-42");
+        // Bound to its context
+        assert!(cipher.open("employee_id:globex", &sealed).is_err());
+
+        let rotated = FieldCipher::new(&[2; 32]).unwrap();
+        assert!(rotated.open("employee_id:acme", &sealed).is_err());
+        let rotated = rotated.with_previous(&[1; 32]).unwrap();
+        assert_eq!(rotated.open("employee_id:acme", &sealed).unwrap(), "emp-42");
+        assert_ne!(rotated.key_id(), cipher.key_id());
+    }
+
+    #[test]
+    fn test_rotation_in_store_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SecretStore::open_with_key(dir.path().join("secrets.json"), MasterKey::from_bytes([3; 32])).unwrap();
+        let original = FieldCipher::from_store(&mut store).unwrap();
+        let sealed = original.seal("kind:acme", "480");
+
+        let next = FieldCipher::begin_rotation(&mut store).unwrap();
+        assert_ne!(next.key_id(), original.key_id());
+        assert_eq!(next.open("kind:acme", &sealed).unwrap(), "480");
+
+        // An interrupted rotation resumes with the same keys
+        let resumed = FieldCipher::begin_rotation(&mut store).unwrap();
+        assert_eq!(resumed.key_id(), next.key_id());
+        assert_eq!(resumed.open("kind:acme", &sealed).unwrap(), "480");
+
+        FieldCipher::finish_rotation(&mut store).unwrap();
+        let current = FieldCipher::from_store(&mut store).unwrap();
+        assert_eq!(current.key_id(), next.key_id());
+        assert!(current.open("kind:acme", &sealed).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+//! Capability Policy Fixtures
+//!
+//! A fixture states a security policy as data: the capabilities granted,
+//! delegated, and revoked, followed by the operations each owner should and
+//! should not be able to perform. [`CapabilityFixture::run`] replays the
+//! grants against a fresh [`CapabilityManager`] and checks every expectation,
+//! so a policy change is reviewed as a diff of the fixture file rather than of
+//! async test code. `esta-kernel-cli capabilities check` runs fixture files.
+//!
+//! ```json
+//! {
+//!   "name": "ledger isolation",
+//!   "grants": [
+//!     { "id": "ui", "owner": "ui", "resource_type": "Module", "resource": "ledger",
+//!       "rights": ["read", "delegate"] }
+//!   ],
+//!   "delegations": [{ "id": "report", "from": "ui", "owner": "report", "rights": ["read"] }],
+//!   "revocations": [],
+//!   "expect": [
+//!     { "owner": "report", "resource_type": "Module", "resource": "ledger", "rights": ["read"], "allow": true },
+//!     { "owner": "report", "resource_type": "Module", "resource": "ledger", "rights": ["write"], "allow": false }
+//!   ]
+//! }
+//! ```
+//!
+//! An owner may perform an operation when any capability it holds on the
+//! resource validates with the required rights. Grants with a `tenant` are
+//! created in that tenant's namespace, and expectations with a `tenant` are
+//! checked with [`CapabilityManager::validate_for_tenant`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+use super::capabilities::{
+    CapabilityError, CapabilityManager, CapabilityRight, CapabilityToken, CapabilityValidity, ResourceType,
+};
+use super::secrets::SecretError;
+use crate::tenant::tenant_resource_id;
+
+/// Errors in a fixture itself, as opposed to failed expectations
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    #[error("Invalid fixture: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("Unknown right {0:?}")]
+    UnknownRight(String),
+
+    #[error("Grant {0:?} is defined more than once")]
+    DuplicateGrant(String),
+
+    #[error("Unknown grant {0:?}")]
+    UnknownGrant(String),
+
+    #[error("Grant {id:?} could not be set up: {source}")]
+    Setup { id: String, source: CapabilityError },
+
+    #[error("Capability secret could not be generated: {0}")]
+    Secret(#[from] SecretError),
+}
+
+/// A capability issued directly by the kernel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureGrant {
+    /// Name used by delegations and revocations
+    pub id: String,
+    pub owner: String,
+    pub resource_type: ResourceType,
+    pub resource: String,
+    pub rights: Vec<String>,
+    /// Issue inside this tenant's namespace
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// Expiration timestamp (Unix millis); a past time makes an expired grant
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// A capability delegated from an earlier grant or delegation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureDelegation {
+    pub id: String,
+    /// Grant or delegation being delegated
+    pub from: String,
+    pub owner: String,
+    pub rights: Vec<String>,
+}
+
+/// An operation and whether it should be allowed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureExpectation {
+    pub owner: String,
+    pub resource_type: ResourceType,
+    pub resource: String,
+    pub rights: Vec<String>,
+    /// Perform the operation as this tenant
+    #[serde(default)]
+    pub tenant: Option<String>,
+    pub allow: bool,
+}
+
+/// A capability policy and its expected outcomes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityFixture {
+    pub name: String,
+    #[serde(default)]
+    pub grants: Vec<FixtureGrant>,
+    #[serde(default)]
+    pub delegations: Vec<FixtureDelegation>,
+    /// Grants or delegations revoked, with everything delegated from them
+    #[serde(default)]
+    pub revocations: Vec<String>,
+    pub expect: Vec<FixtureExpectation>,
+}
+
+/// Outcome of one expectation
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectationResult {
+    /// Position in the fixture's `expect` list
+    pub index: usize,
+    pub owner: String,
+    pub resource: String,
+    pub rights: Vec<String>,
+    pub expected_allow: bool,
+    pub allowed: bool,
+    /// Why the operation was denied, when it was
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub denial: Option<String>,
+}
+
+impl ExpectationResult {
+    pub fn passed(&self) -> bool {
+        self.expected_allow == self.allowed
+    }
+}
+
+/// Outcome of running a fixture
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureReport {
+    pub fixture: String,
+    pub results: Vec<ExpectationResult>,
+}
+
+impl FixtureReport {
+    /// Whether every expectation held
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(ExpectationResult::passed)
+    }
+
+    /// Expectations that did not hold
+    pub fn failures(&self) -> impl Iterator<Item = &ExpectationResult> {
+        self.results.iter().filter(|r| !r.passed())
+    }
+}
+
+/// Resource type and (namespaced) resource ID a token covers
+type Resource = (ResourceType, String);
+
+fn parse_rights(rights: &[String]) -> Result<HashSet<CapabilityRight>, FixtureError> {
+    rights
+        .iter()
+        .map(|r| CapabilityRight::from_str(r).ok_or_else(|| FixtureError::UnknownRight(r.clone())))
+        .collect()
+}
+
+impl CapabilityFixture {
+    /// Parse a fixture from JSON
+    pub fn from_json(json: &str) -> Result<Self, FixtureError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Set up the fixture's capabilities in a fresh manager and check every expectation
+    pub async fn run(&self) -> Result<FixtureReport, FixtureError> {
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret()?);
+        // Tokens by grant ID, and what each owner holds with the resource it covers
+        let mut tokens: HashMap<&str, (CapabilityToken, Resource)> = HashMap::new();
+        let mut held: HashMap<&str, Vec<(CapabilityToken, Resource)>> = HashMap::new();
+
+        for grant in &self.grants {
+            if tokens.contains_key(grant.id.as_str()) {
+                return Err(FixtureError::DuplicateGrant(grant.id.clone()));
+            }
+            let rights = parse_rights(&grant.rights)?;
+            let validity = CapabilityValidity { expires_at: grant.expires_at, ..Default::default() };
+            let issued = match &grant.tenant {
+                Some(tenant) => {
+                    manager
+                        .create_tenant_capability(
+                            tenant,
+                            grant.resource_type.clone(),
+                            &grant.resource,
+                            rights,
+                            grant.owner.clone(),
+                            validity,
+                        )
+                        .await
+                }
+                None => {
+                    manager
+                        .create_capability(
+                            grant.resource_type.clone(),
+                            grant.resource.clone(),
+                            rights,
+                            grant.owner.clone(),
+                            validity,
+                        )
+                        .await
+                }
+            };
+            let token = issued.map_err(|source| FixtureError::Setup { id: grant.id.clone(), source })?;
+            let resource_id = match &grant.tenant {
+                Some(tenant) => tenant_resource_id(tenant, &grant.resource),
+                None => grant.resource.clone(),
+            };
+            let held_token = (token, (grant.resource_type.clone(), resource_id));
+            held.entry(grant.owner.as_str()).or_default().push(held_token.clone());
+            tokens.insert(grant.id.as_str(), held_token);
+        }
+
+        for delegation in &self.delegations {
+            if tokens.contains_key(delegation.id.as_str()) {
+                return Err(FixtureError::DuplicateGrant(delegation.id.clone()));
+            }
+            let (parent, resource) = tokens
+                .get(delegation.from.as_str())
+                .ok_or_else(|| FixtureError::UnknownGrant(delegation.from.clone()))?;
+            let token = manager
+                .delegate(parent, delegation.owner.clone(), parse_rights(&delegation.rights)?, CapabilityValidity::default())
+                .await
+                .map_err(|source| FixtureError::Setup { id: delegation.id.clone(), source })?;
+            let held_token = (token, resource.clone());
+            held.entry(delegation.owner.as_str()).or_default().push(held_token.clone());
+            tokens.insert(delegation.id.as_str(), held_token);
+        }
+
+        for id in &self.revocations {
+            let (token, _) = tokens.get(id.as_str()).ok_or_else(|| FixtureError::UnknownGrant(id.clone()))?;
+            manager.revoke(token).await.map_err(|source| FixtureError::Setup { id: id.clone(), source })?;
+        }
+
+        let mut results = Vec::with_capacity(self.expect.len());
+        for (index, expectation) in self.expect.iter().enumerate() {
+            let rights: Vec<CapabilityRight> = parse_rights(&expectation.rights)?.into_iter().collect();
+            let resource = (
+                expectation.resource_type.clone(),
+                match &expectation.tenant {
+                    Some(tenant) => tenant_resource_id(tenant, &expectation.resource),
+                    None => expectation.resource.clone(),
+                },
+            );
+
+            let mut denial = None;
+            let mut allowed = false;
+            // Capabilities on other resources say nothing about this one
+            for (token, _) in held.get(expectation.owner.as_str()).into_iter().flatten().filter(|(_, r)| *r == resource) {
+                let validated = match &expectation.tenant {
+                    Some(tenant) => manager.validate_for_tenant(token, tenant, &rights).await,
+                    None => manager.validate(token, &rights).await,
+                };
+                match validated {
+                    Ok(_) => {
+                        allowed = true;
+                        break;
+                    }
+                    Err(e) => denial = Some(e.to_string()),
+                }
+            }
+
+            results.push(ExpectationResult {
+                index,
+                owner: expectation.owner.clone(),
+                resource: expectation.resource.clone(),
+                rights: expectation.rights.clone(),
+                expected_allow: expectation.allow,
+                allowed,
+                denial: if allowed {
+                    None
+                } else {
+                    Some(denial.unwrap_or_else(|| "no capability on this resource".to_string()))
+                },
+            });
+        }
+
+        Ok(FixtureReport { fixture: self.name.clone(), results })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bundled_fixture_holds() {
+        let fixture = CapabilityFixture::from_json(include_str!("../../fixtures/capabilities/module-isolation.json"))
+            .unwrap();
+        let report = fixture.run().await.unwrap();
+        assert!(report.passed(), "{:?}", report.failures().collect::<Vec<_>>());
+        assert_eq!(report.results[3].denial.as_deref(), Some("Capability has been revoked"));
+        assert_eq!(report.results[2].denial.as_deref(), Some("no capability on this resource"));
+
+        // Flipping an expectation is reported, not hidden
+        let mut loosened = fixture.clone();
+        loosened.expect[1].allow = true;
+        let report = loosened.run().await.unwrap();
+        assert_eq!(report.failures().map(|r| r.index).collect::<Vec<_>>(), [1]);
+
+        let mut broken = fixture;
+        broken.revocations.push("missing".to_string());
+        assert!(matches!(broken.run().await, Err(FixtureError::UnknownGrant(id)) if id == "missing"));
+        assert!(matches!(
+            CapabilityFixture::from_json(r#"{"name":"x","grants":[{"id":"a","owner":"o","resource_type":"Module",
+                "resource":"m","rights":["fly"]}],"expect":[]}"#).unwrap().run().await,
+            Err(FixtureError::UnknownRight(_))
+        ));
+    }
+}
@@ -3,12 +3,49 @@
 //! This module provides security primitives for the microkernel:
 //! - Ed25519 signature verification for WASM modules
 //! - Capability-based access control
+//! - Capability validation metrics and denial spike alerts
 //! - Audit logging for security events
+//! - Anchoring of audit checkpoints outside the kernel
+//! - Memory-mapped queries over persisted audit segments
+//! - Offline verification of exported audit logs
+//! - Encrypted storage for the capability secret and signing seeds
+//! - Field encryption of employee records at rest
+//! - Trusted signing keys with rotation and grace periods
+//! - Signed capability snapshots for security review
+//! - Declarative capability policy fixtures with expected outcomes
+//! - Pseudonymization of employee identifiers passed to modules
 
 pub mod sig;
 pub mod capabilities;
+pub mod capability_metrics;
 pub mod audit;
+pub mod anchor;
+pub mod audit_reader;
+pub mod audit_verify;
+pub mod field_cipher;
+pub mod fixtures;
+pub mod pseudonym;
+pub mod secrets;
+pub mod snapshot;
+pub mod trust;
 
 pub use sig::{SignatureVerifier, SignatureError};
-pub use capabilities::{Capability, CapabilityManager, CapabilityToken, CapabilityError};
-pub use audit::{AuditLog, AuditEvent, AuditEventType};
+pub use capabilities::{
+    Capability, CapabilityManager, CapabilityStats, CapabilityToken, CapabilityError, InstanceNonce, ReissuedToken,
+};
+pub use capability_metrics::CapabilityMetricsConfig;
+pub use audit::{
+    AuditEvent, AuditEventType, AuditExport, AuditExportError, AuditFailurePolicy, AuditFilter, AuditHealth, AuditLog,
+    AuditQuery, AuditRedaction, AuditSubscription, AuditWriter, ChainVerification, ExportCursor, MissedEntries, VerificationProgress,
+};
+#[cfg(feature = "anchor-http")]
+pub use anchor::{AnchorFormat, HttpAnchor};
+pub use anchor::{AnchorError, AnchorProvider, AnchorReceipt, AuditCheckpoint, FileAnchor};
+pub use audit_reader::{ArchivedAuditStats, AuditSegmentReader};
+pub use audit_verify::{audit_verify_export, ExportVerification};
+pub use field_cipher::FieldCipher;
+pub use fixtures::{CapabilityFixture, FixtureError, FixtureReport};
+pub use pseudonym::{PseudonymMap, Pseudonymizer};
+pub use secrets::{MasterKey, Secret, SecretError, SecretStore};
+pub use snapshot::{CapabilityChange, CapabilityRecord, CapabilitySnapshot, DelegationEdge, SnapshotDiff};
+pub use trust::{KeyRotation, TrustError, TrustStore, TrustedKey};
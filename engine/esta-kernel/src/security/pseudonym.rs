@@ -0,0 +1,196 @@
+//! Employee Identifier Pseudonymization
+//!
+//! Rule modules, including compiled third-party ones, never need to know who
+//! an employee is, only to tell employees apart. Before an invocation's input
+//! enters the guest, every `employee_id` (and each entry of `employee_ids`)
+//! is replaced with a pseudonym; after the call, pseudonyms in the output are
+//! mapped back to the real identifiers.
+//!
+//! Pseudonyms are an HMAC of the tenant and identifier under a kernel secret:
+//! stable across invocations and restarts (with a persisted secret), so
+//! modules can still correlate an employee's records, but different in every
+//! tenant and not reversible without the secret. The reverse mapping exists
+//! only for the duration of one invocation.
+//!
+//! The same secret also keys [`Pseudonymizer::redacted`], the identifier
+//! retention leaves on ledger events in place of an employee's: under its own
+//! domain separator, and never mapped back.
+
+use crate::ledger::REDACTED_EMPLOYEE_PREFIX;
+use ring::hmac;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Name of the pseudonymization secret in the secret store
+pub const PSEUDONYM_SECRET: &str = "pseudonym-secret";
+
+/// Domain separator so pseudonyms cannot be confused with other MACs
+const PSEUDONYM_CONTEXT: &[u8] = b"esta-employee-pseudonym\0";
+
+/// Prefix marking a pseudonym
+const PSEUDONYM_PREFIX: &str = "emp_";
+
+/// Domain separator for redacted identifiers, so they never equal a pseudonym
+const REDACTION_CONTEXT: &[u8] = b"esta-employee-redaction\0";
+
+/// Replaces employee identifiers with stable per-tenant pseudonyms
+pub struct Pseudonymizer {
+    key: hmac::Key,
+}
+
+impl Pseudonymizer {
+    pub fn new(secret: &[u8]) -> Self {
+        Self { key: hmac::Key::new(hmac::HMAC_SHA256, secret) }
+    }
+
+    /// The pseudonym of an employee of a tenant
+    pub fn pseudonym(&self, tenant_id: &str, employee_id: &str) -> String {
+        format!("{}{}", PSEUDONYM_PREFIX, self.tag(PSEUDONYM_CONTEXT, tenant_id, employee_id))
+    }
+
+    /// The identifier that replaces an employee's on records past retention
+    ///
+    /// One employee's redacted records still share an identifier, so their
+    /// history stays distinct from everyone else's, but nothing maps it back.
+    pub fn redacted(&self, tenant_id: &str, employee_id: &str) -> String {
+        format!("{}{}", REDACTED_EMPLOYEE_PREFIX, self.tag(REDACTION_CONTEXT, tenant_id, employee_id))
+    }
+
+    /// Hex of the truncated MAC of a tenant's employee identifier
+    fn tag(&self, context: &[u8], tenant_id: &str, employee_id: &str) -> String {
+        let mut message = context.to_vec();
+        message.extend_from_slice(tenant_id.as_bytes());
+        message.push(0);
+        message.extend_from_slice(employee_id.as_bytes());
+        let tag = hmac::sign(&self.key, &message);
+        hex::encode(&tag.as_ref()[..12])
+    }
+
+    /// Pseudonymize the employee identifiers in a JSON input
+    ///
+    /// Input that is not JSON is passed through unchanged (the module rejects
+    /// it). Returns the input for the guest and the mapping to restore its output.
+    pub fn pseudonymize(&self, tenant_id: &str, input: &[u8]) -> (Vec<u8>, PseudonymMap) {
+        let mut map = PseudonymMap::default();
+        let Ok(mut value) = serde_json::from_slice::<Value>(input) else {
+            return (input.to_vec(), map);
+        };
+        self.replace_identifiers(tenant_id, &mut value, &mut map);
+        if map.is_empty() {
+            return (input.to_vec(), map);
+        }
+        let bytes = serde_json::to_vec(&value).unwrap_or_else(|_| input.to_vec());
+        (bytes, map)
+    }
+
+    fn replace_identifiers(&self, tenant_id: &str, value: &mut Value, map: &mut PseudonymMap) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    match (name.as_str(), field) {
+                        ("employee_id", id) => self.replace_identifier(tenant_id, id, map),
+                        ("employee_ids", Value::Array(ids)) => {
+                            ids.iter_mut().for_each(|id| self.replace_identifier(tenant_id, id, map))
+                        }
+                        (_, nested) => self.replace_identifiers(tenant_id, nested, map),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.replace_identifiers(tenant_id, item, map)),
+            _ => {}
+        }
+    }
+
+    fn replace_identifier(&self, tenant_id: &str, id: &mut Value, map: &mut PseudonymMap) {
+        if let Value::String(employee_id) = id {
+            let pseudonym = self.pseudonym(tenant_id, employee_id);
+            map.reverse.insert(pseudonym.clone(), std::mem::replace(employee_id, pseudonym));
+        }
+    }
+}
+
+/// Pseudonyms issued for one invocation, and the identifiers they stand for
+#[derive(Debug, Default)]
+pub struct PseudonymMap {
+    reverse: HashMap<String, String>,
+}
+
+impl PseudonymMap {
+    /// Whether the input contained no employee identifiers
+    pub fn is_empty(&self) -> bool {
+        self.reverse.is_empty()
+    }
+
+    /// Replace every string in a JSON output that is one of this invocation's pseudonyms
+    ///
+    /// Pseudonyms embedded in longer strings are left as they are.
+    pub fn restore(&self, output: &[u8]) -> Vec<u8> {
+        if self.is_empty() {
+            return output.to_vec();
+        }
+        let Ok(mut value) = serde_json::from_slice::<Value>(output) else {
+            return output.to_vec();
+        };
+        self.restore_value(&mut value);
+        serde_json::to_vec(&value).unwrap_or_else(|_| output.to_vec())
+    }
+
+    fn restore_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                if let Some(employee_id) = self.reverse.get(s.as_str()) {
+                    *s = employee_id.clone();
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.restore_value(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.restore_value(field)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudonymize_and_restore() {
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let input = br#"{"employee_id":"maria.lopez","shifts":[{"employee_id":"j.smith","minutes":60}],"employee_ids":["maria.lopez"]}"#;
+
+        let (guest_input, map) = pseudonymizer.pseudonymize("acme", input);
+        let guest_text = String::from_utf8(guest_input.clone()).unwrap();
+        assert!(!guest_text.contains("maria.lopez") && !guest_text.contains("j.smith"), "{}", guest_text);
+        let guest: Value = serde_json::from_slice(&guest_input).unwrap();
+        assert_eq!(guest["employee_id"], guest["employee_ids"][0], "stable within a call");
+        assert_eq!(guest["shifts"][0]["minutes"], 60);
+
+        // Stable across calls, different across tenants and secrets
+        let maria = pseudonymizer.pseudonym("acme", "maria.lopez");
+        assert_eq!(guest["employee_id"], maria.as_str());
+        assert_ne!(pseudonymizer.pseudonym("globex", "maria.lopez"), maria);
+        assert_ne!(Pseudonymizer::new(b"other").pseudonym("acme", "maria.lopez"), maria);
+
+        let output = format!(r#"{{"employee_id":"{}","note":"seen {}","balance":30}}"#, maria, maria);
+        let restored: Value = serde_json::from_slice(&map.restore(output.as_bytes())).unwrap();
+        assert_eq!(restored["employee_id"], "maria.lopez");
+        assert_eq!(restored["note"], format!("seen {}", maria));
+
+        // Inputs without identifiers, or not JSON, pass through untouched
+        let (unchanged, map) = pseudonymizer.pseudonymize("acme", br#"{"minutes_worked": 60}"#);
+        assert_eq!(unchanged, br#"{"minutes_worked": 60}"#);
+        assert!(map.is_empty());
+        assert_eq!(pseudonymizer.pseudonymize("acme", b"not json").0, b"not json");
+    }
+
+    #[test]
+    fn test_redacted_identifiers() {
+        let pseudonymizer = Pseudonymizer::new(b"secret");
+        let maria = pseudonymizer.redacted("acme", "maria.lopez");
+        assert!(maria.starts_with(REDACTED_EMPLOYEE_PREFIX) && !maria.contains("maria"), "{}", maria);
+        assert_eq!(pseudonymizer.redacted("acme", "maria.lopez"), maria);
+        assert_ne!(pseudonymizer.redacted("acme", "j.smith"), maria);
+        assert_ne!(pseudonymizer.redacted("globex", "maria.lopez"), maria);
+        assert_ne!(pseudonymizer.pseudonym("acme", "maria.lopez")[4..], maria[REDACTED_EMPLOYEE_PREFIX.len()..]);
+    }
+}
@@ -0,0 +1,540 @@
+//! Encrypted Secret Storage
+//!
+//! The kernel's secrets and signing seeds must outlive the process without
+//! sitting on disk in the clear: what is derived from them (pseudonyms,
+//! capability snapshot and backup signatures, module cache MACs, encrypted
+//! database fields) has to verify or decrypt after a restart. Capability
+//! tokens do not survive a restart even so; the capability table lives in
+//! memory and modules are granted fresh capabilities when relaunched.
+//! `SecretStore` keeps the secrets in a JSON file, each value sealed with
+//! ChaCha20-Poly1305 under a master key:
+//!
+//! - Hosts with an OS keychain supply the 32-byte key directly
+//!   ([`MasterKey::from_bytes`]).
+//! - Otherwise the key is derived from a passphrase with Argon2id; the
+//!   algorithm, salt, and cost parameters are stored in the file. Stores
+//!   created with PBKDF2-HMAC-SHA256 still open, and are re-sealed under an
+//!   Argon2id key the first time they are unlocked.
+//!
+//! Each value is bound to its name (as associated data), so sealed values
+//! cannot be swapped between entries. A sealed check value detects a wrong
+//! key or passphrase at open time rather than at first use.
+//!
+//! Rotating a secret replaces its value and increments its generation. A
+//! secret can be copied aside first when the old value is still needed
+//! (see [`super::field_cipher`]).
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::sig::ModuleSigner;
+
+/// Name of the capability token secret
+pub const CAPABILITY_SECRET: &str = "capability-secret";
+
+/// Secret store file format version
+const STORE_VERSION: u32 = 1;
+
+/// Key derivation for new passphrase-protected stores
+const ARGON2ID: &str = "argon2id";
+
+/// Key derivation of stores created before Argon2id, migrated on unlock
+const PBKDF2_HMAC_SHA256: &str = "pbkdf2-hmac-sha256";
+
+/// Argon2id passes for new passphrase-protected stores
+const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+
+/// Argon2id memory for new passphrase-protected stores, in KiB (19 MiB)
+const DEFAULT_ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Argon2id lanes for new passphrase-protected stores
+const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
+
+/// Associated data of the check value
+const CHECK_AAD: &[u8] = b"esta-secret-store";
+
+/// Errors opening or using a secret store
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SecretError {
+    #[error("Secret store I/O failed: {0}")]
+    Io(String),
+
+    #[error("Secret store is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Secret store key or passphrase is incorrect")]
+    WrongKey,
+
+    #[error("System RNG failed")]
+    Rng,
+}
+
+/// Result type for secret store operations
+pub type SecretResult<T> = Result<T, SecretError>;
+
+/// Key that seals the values in a secret store
+pub struct MasterKey([u8; 32]);
+
+impl MasterKey {
+    /// Use a key held elsewhere (e.g. the OS keychain)
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Derive a key from a passphrase
+    fn derive(passphrase: &str, kdf: &KdfParams) -> SecretResult<Self> {
+        let salt = hex::decode(&kdf.salt).map_err(|e| SecretError::Corrupt(format!("salt: {}", e)))?;
+        let mut key = [0u8; 32];
+        match kdf.algorithm.as_str() {
+            ARGON2ID => {
+                let params = argon2::Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(key.len()))
+                    .map_err(|e| SecretError::Corrupt(format!("argon2id parameters: {}", e)))?;
+                argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                    .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+                    .map_err(|e| SecretError::Corrupt(format!("argon2id: {}", e)))?;
+            }
+            PBKDF2_HMAC_SHA256 => {
+                let iterations = NonZeroU32::new(kdf.iterations)
+                    .ok_or_else(|| SecretError::Corrupt("iterations must be positive".into()))?;
+                ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, passphrase.as_bytes(), &mut key);
+            }
+            other => return Err(SecretError::Corrupt(format!("unsupported key derivation {}", other))),
+        }
+        Ok(Self(key))
+    }
+
+    fn aead(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &self.0).expect("32-byte key"))
+    }
+}
+
+impl Drop for MasterKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterKey(..)")
+    }
+}
+
+/// A secret value, cleared from memory when dropped
+pub struct Secret(Vec<u8>);
+
+impl Secret {
+    /// The secret bytes
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// Passphrase key derivation settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    /// PBKDF2 iterations, or Argon2id passes
+    iterations: u32,
+    /// Argon2id memory in KiB; absent from PBKDF2 stores
+    #[serde(default, skip_serializing_if = "is_zero")]
+    memory_kib: u32,
+    /// Argon2id lanes; absent from PBKDF2 stores
+    #[serde(default, skip_serializing_if = "is_zero")]
+    parallelism: u32,
+}
+
+impl KdfParams {
+    /// Argon2id with the default costs and a fresh salt
+    fn argon2id() -> SecretResult<Self> {
+        Ok(Self {
+            algorithm: ARGON2ID.into(),
+            salt: hex::encode(random_bytes(16)?),
+            iterations: DEFAULT_ARGON2_ITERATIONS,
+            memory_kib: DEFAULT_ARGON2_MEMORY_KIB,
+            parallelism: DEFAULT_ARGON2_PARALLELISM,
+        })
+    }
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// A value sealed under the master key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// A named secret in the store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SecretEntry {
+    /// Incremented each time the secret is rotated
+    generation: u32,
+    /// When this generation was created (ms since Unix epoch)
+    created_at: u64,
+    #[serde(flatten)]
+    sealed: Sealed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    /// Present when the key is derived from a passphrase
+    kdf: Option<KdfParams>,
+    check: Sealed,
+    entries: BTreeMap<String, SecretEntry>,
+}
+
+/// Encrypted, file-backed store of named secrets
+#[derive(Debug)]
+pub struct SecretStore {
+    path: PathBuf,
+    key: MasterKey,
+    file: StoreFile,
+}
+
+impl SecretStore {
+    /// Open (or create) a store whose key is derived from a passphrase
+    ///
+    /// A store whose key was derived with an older algorithm is re-sealed
+    /// under an Argon2id key once unlocked. If that fails, it stays usable
+    /// under its old key and the migration is retried at the next unlock.
+    pub fn open_with_passphrase(path: impl Into<PathBuf>, passphrase: &str) -> SecretResult<Self> {
+        let path = path.into();
+        match Self::read_file(&path)? {
+            Some(file) => {
+                let kdf = file
+                    .kdf
+                    .clone()
+                    .ok_or_else(|| SecretError::Corrupt("store is not passphrase-protected".into()))?;
+                let mut store = Self::unlock(path, MasterKey::derive(passphrase, &kdf)?, file)?;
+                if kdf.algorithm != ARGON2ID {
+                    let migrated = KdfParams::argon2id()
+                        .and_then(|kdf| Ok((MasterKey::derive(passphrase, &kdf)?, kdf)))
+                        .and_then(|(key, kdf)| store.reseal(key, Some(kdf)));
+                    match migrated {
+                        Ok(()) => log::info!("Secret store {} migrated from {} to {}", store.path.display(), kdf.algorithm, ARGON2ID),
+                        Err(e) => log::warn!("Secret store {} still uses {}: {}", store.path.display(), kdf.algorithm, e),
+                    }
+                }
+                Ok(store)
+            }
+            None => {
+                let kdf = KdfParams::argon2id()?;
+                let key = MasterKey::derive(passphrase, &kdf)?;
+                Self::create(path, key, Some(kdf))
+            }
+        }
+    }
+
+    /// Open (or create) a store sealed with a key held by the host
+    pub fn open_with_key(path: impl Into<PathBuf>, key: MasterKey) -> SecretResult<Self> {
+        let path = path.into();
+        match Self::read_file(&path)? {
+            Some(file) => Self::unlock(path, key, file),
+            None => Self::create(path, key, None),
+        }
+    }
+
+    fn read_file(path: &Path) -> SecretResult<Option<StoreFile>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(SecretError::Io(e.to_string())),
+        };
+        let file: StoreFile = serde_json::from_slice(&bytes).map_err(|e| SecretError::Corrupt(e.to_string()))?;
+        if file.version != STORE_VERSION {
+            return Err(SecretError::Corrupt(format!("unsupported version {}", file.version)));
+        }
+        Ok(Some(file))
+    }
+
+    fn unlock(path: PathBuf, key: MasterKey, file: StoreFile) -> SecretResult<Self> {
+        open(&key, CHECK_AAD, &file.check).map_err(|_| SecretError::WrongKey)?;
+        Ok(Self { path, key, file })
+    }
+
+    fn create(path: PathBuf, key: MasterKey, kdf: Option<KdfParams>) -> SecretResult<Self> {
+        let check = seal(&key, CHECK_AAD, &[])?;
+        let store = Self {
+            path,
+            key,
+            file: StoreFile { version: STORE_VERSION, kdf, check, entries: BTreeMap::new() },
+        };
+        store.save()?;
+        Ok(store)
+    }
+
+    /// Seal the check value and every secret under `key` and save the store
+    ///
+    /// On failure the store is left as it was, on disk and in memory.
+    fn reseal(&mut self, key: MasterKey, kdf: Option<KdfParams>) -> SecretResult<()> {
+        let mut entries = BTreeMap::new();
+        for (name, entry) in &self.file.entries {
+            let secret = self.get(name)?.expect("entry exists");
+            let sealed = seal(&key, name.as_bytes(), secret.expose())?;
+            entries.insert(name.clone(), SecretEntry { sealed, ..entry.clone() });
+        }
+        let file = StoreFile { version: STORE_VERSION, kdf, check: seal(&key, CHECK_AAD, &[])?, entries };
+        let previous = std::mem::replace(&mut self.file, file);
+        if let Err(e) = self.save() {
+            self.file = previous;
+            return Err(e);
+        }
+        self.key = key;
+        Ok(())
+    }
+
+    /// The store file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Names of the stored secrets (sorted)
+    pub fn names(&self) -> Vec<String> {
+        self.file.entries.keys().cloned().collect()
+    }
+
+    /// The generation of a secret (1 when first created)
+    pub fn generation(&self, name: &str) -> Option<u32> {
+        self.file.entries.get(name).map(|entry| entry.generation)
+    }
+
+    /// Read a secret
+    pub fn get(&self, name: &str) -> SecretResult<Option<Secret>> {
+        self.file
+            .entries
+            .get(name)
+            .map(|entry| {
+                open(&self.key, name.as_bytes(), &entry.sealed)
+                    .map(Secret)
+                    .map_err(|_| SecretError::Corrupt(format!("secret {} does not decrypt", name)))
+            })
+            .transpose()
+    }
+
+    /// Read a secret, generating and storing `len` random bytes if absent
+    pub fn get_or_generate(&mut self, name: &str, len: usize) -> SecretResult<Secret> {
+        match self.get(name)? {
+            Some(secret) => Ok(secret),
+            None => self.put(name, random_bytes(len)?, 1),
+        }
+    }
+
+    /// Replace a secret with `len` new random bytes
+    pub fn rotate(&mut self, name: &str, len: usize) -> SecretResult<Secret> {
+        let generation = self.generation(name).map_or(1, |g| g + 1);
+        self.put(name, random_bytes(len)?, generation)
+    }
+
+    /// Store a copy of a secret under another name, replacing any secret there
+    ///
+    /// Returns false if `from` is absent.
+    pub fn copy(&mut self, from: &str, to: &str) -> SecretResult<bool> {
+        let Some(secret) = self.get(from)? else {
+            return Ok(false);
+        };
+        let generation = self.generation(from).unwrap_or(1);
+        self.put(to, secret.expose().to_vec(), generation)?;
+        Ok(true)
+    }
+
+    /// Delete a secret; returns false if it was absent
+    pub fn remove(&mut self, name: &str) -> SecretResult<bool> {
+        let Some(previous) = self.file.entries.remove(name) else {
+            return Ok(false);
+        };
+        if let Err(e) = self.save() {
+            self.file.entries.insert(name.to_string(), previous);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// The module signer whose seed is stored under `name`, creating it if absent
+    pub fn module_signer(&mut self, name: &str) -> SecretResult<ModuleSigner> {
+        let seed = self.get_or_generate(name, 32)?;
+        let seed: [u8; 32] = seed
+            .expose()
+            .try_into()
+            .map_err(|_| SecretError::Corrupt(format!("secret {} is not a 32-byte seed", name)))?;
+        ModuleSigner::from_seed(&seed).map_err(|e| SecretError::Corrupt(e.to_string()))
+    }
+
+    fn put(&mut self, name: &str, value: Vec<u8>, generation: u32) -> SecretResult<Secret> {
+        let secret = Secret(value);
+        let entry = SecretEntry {
+            generation,
+            created_at: crate::clock::now_millis(),
+            sealed: seal(&self.key, name.as_bytes(), secret.expose())?,
+        };
+        let previous = self.file.entries.insert(name.to_string(), entry);
+        if let Err(e) = self.save() {
+            // Keep memory consistent with what is on disk
+            match previous {
+                Some(previous) => self.file.entries.insert(name.to_string(), previous),
+                None => self.file.entries.remove(name),
+            };
+            return Err(e);
+        }
+        Ok(secret)
+    }
+
+    fn save(&self) -> SecretResult<()> {
+        let io = |e: std::io::Error| SecretError::Io(e.to_string());
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(io)?;
+        }
+        let json = serde_json::to_vec_pretty(&self.file).map_err(|e| SecretError::Corrupt(e.to_string()))?;
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, json).map_err(io)?;
+        std::fs::rename(&tmp, &self.path).map_err(io)
+    }
+}
+
+fn random_bytes(len: usize) -> SecretResult<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| SecretError::Rng)?;
+    Ok(bytes)
+}
+
+fn seal(key: &MasterKey, aad: &[u8], plaintext: &[u8]) -> SecretResult<Sealed> {
+    let nonce: [u8; NONCE_LEN] = random_bytes(NONCE_LEN)?.try_into().expect("nonce length");
+    let mut buffer = plaintext.to_vec();
+    key.aead()
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut buffer)
+        .map_err(|_| SecretError::Corrupt("sealing failed".into()))?;
+    Ok(Sealed { nonce: hex::encode(nonce), ciphertext: hex::encode(buffer) })
+}
+
+fn open(key: &MasterKey, aad: &[u8], sealed: &Sealed) -> Result<Vec<u8>, ()> {
+    let nonce: [u8; NONCE_LEN] = hex::decode(&sealed.nonce).map_err(|_| ())?.try_into().map_err(|_| ())?;
+    let mut buffer = hex::decode(&sealed.ciphertext).map_err(|_| ())?;
+    let plaintext = key
+        .aead()
+        .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut buffer)
+        .map_err(|_| ())?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_persist_encrypted_and_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_key(&path, MasterKey::from_bytes([1; 32])).unwrap();
+        let secret = store.get_or_generate(CAPABILITY_SECRET, 32).unwrap().expose().to_vec();
+        assert_eq!(store.generation(CAPABILITY_SECRET), Some(1));
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains(&hex::encode(&secret)));
+
+        let mut store = SecretStore::open_with_key(&path, MasterKey::from_bytes([1; 32])).unwrap();
+        assert_eq!(store.get_or_generate(CAPABILITY_SECRET, 32).unwrap().expose(), secret.as_slice());
+
+        let rotated = store.rotate(CAPABILITY_SECRET, 32).unwrap().expose().to_vec();
+        assert_ne!(rotated, secret);
+        assert_eq!(store.generation(CAPABILITY_SECRET), Some(2));
+
+        let signer = store.module_signer("signing-seed").unwrap();
+        let reopened = SecretStore::open_with_key(&path, MasterKey::from_bytes([1; 32]))
+            .unwrap()
+            .module_signer("signing-seed")
+            .unwrap();
+        assert_eq!(signer.public_key_hex(), reopened.public_key_hex());
+
+        let err = SecretStore::open_with_key(&path, MasterKey::from_bytes([2; 32])).unwrap_err();
+        assert_eq!(err, SecretError::WrongKey);
+    }
+
+    #[test]
+    fn test_passphrase_store_and_swapped_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_passphrase(&path, "correct horse").unwrap();
+        let secret = store.get_or_generate("a", 16).unwrap().expose().to_vec();
+        store.get_or_generate("b", 16).unwrap();
+        drop(store);
+
+        assert_eq!(
+            SecretStore::open_with_passphrase(&path, "wrong").unwrap_err(),
+            SecretError::WrongKey
+        );
+        let store = SecretStore::open_with_passphrase(&path, "correct horse").unwrap();
+        assert_eq!(store.get("a").unwrap().unwrap().expose(), secret.as_slice());
+        assert_eq!(store.names(), vec!["a".to_string(), "b".to_string()]);
+
+        // A value moved to another name no longer decrypts
+        let mut file: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        file["entries"]["b"] = file["entries"]["a"].clone();
+        std::fs::write(&path, file.to_string()).unwrap();
+        let store = SecretStore::open_with_passphrase(&path, "correct horse").unwrap();
+        assert!(matches!(store.get("b"), Err(SecretError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_pbkdf2_store_migrates_to_argon2id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.json");
+
+        // A store as created before Argon2id
+        let kdf = KdfParams {
+            algorithm: PBKDF2_HMAC_SHA256.into(),
+            salt: hex::encode([7u8; 16]),
+            iterations: 1000,
+            memory_kib: 0,
+            parallelism: 0,
+        };
+        let mut store = SecretStore::create(path.clone(), MasterKey::derive("correct horse", &kdf).unwrap(), Some(kdf)).unwrap();
+        let secret = store.get_or_generate(CAPABILITY_SECRET, 32).unwrap().expose().to_vec();
+        store.rotate(CAPABILITY_SECRET, 32).unwrap();
+        let rotated = store.get(CAPABILITY_SECRET).unwrap().unwrap().expose().to_vec();
+        assert_ne!(rotated, secret);
+        let old_file: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert!(old_file["kdf"].get("memory_kib").is_none());
+        drop(store);
+
+        let store = SecretStore::open_with_passphrase(&path, "correct horse").unwrap();
+        assert_eq!(store.get(CAPABILITY_SECRET).unwrap().unwrap().expose(), rotated.as_slice());
+
+        let file: StoreFile = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let kdf = file.kdf.unwrap();
+        assert_eq!(kdf.algorithm, ARGON2ID);
+        assert_eq!((kdf.memory_kib, kdf.parallelism), (DEFAULT_ARGON2_MEMORY_KIB, DEFAULT_ARGON2_PARALLELISM));
+        assert_ne!(file.entries[CAPABILITY_SECRET].sealed.ciphertext, old_file["entries"][CAPABILITY_SECRET]["ciphertext"]);
+        assert_eq!(file.entries[CAPABILITY_SECRET].generation, 2);
+
+        // Re-sealed under the Argon2id key, which the passphrase still derives
+        let store = SecretStore::open_with_passphrase(&path, "correct horse").unwrap();
+        assert_eq!(store.get(CAPABILITY_SECRET).unwrap().unwrap().expose(), rotated.as_slice());
+        assert_eq!(
+            SecretStore::open_with_passphrase(&path, "wrong").unwrap_err(),
+            SecretError::WrongKey
+        );
+    }
+}
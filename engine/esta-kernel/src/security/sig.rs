@@ -16,7 +16,7 @@ use std::sync::Arc;
 use thiserror::Error;
 
 /// Errors that can occur during signature verification
-#[derive(Error, Debug, Clone)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum SignatureError {
     #[error("Signature verification failed: invalid signature")]
     InvalidSignature,
@@ -32,6 +32,12 @@ pub enum SignatureError {
     
     #[error("Key generation failed: {0}")]
     KeyGenerationFailed(String),
+
+    #[error("Signed with key {0}, which is no longer trusted")]
+    KeyRetired(String),
+
+    #[error("Signed with key {0}, which is not trusted")]
+    UntrustedKey(String),
 }
 
 /// Result type for signature operations
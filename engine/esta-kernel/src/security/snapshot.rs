@@ -0,0 +1,265 @@
+//! Capability State Snapshots
+//!
+//! [`CapabilityManager::export_state`](super::CapabilityManager::export_state)
+//! captures every active capability, the delegation edges between them, and
+//! their validity windows, for periodic security reviews. Tokens and the
+//! token secret are never included.
+//!
+//! Snapshots are signed with an HMAC keyed by the capability secret, so the
+//! kernel that exported a snapshot can confirm it was not edited
+//! (a snapshot taken before a secret rotation no longer verifies).
+//! [`CapabilitySnapshot::diff`] compares two snapshots, e.g. last quarter's and
+//! this quarter's, and reports exactly what was granted, withdrawn, or changed.
+
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::capabilities::{Capability, ResourceType};
+
+/// Snapshot format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Domain separator so the snapshot MAC cannot be confused with a token MAC
+const SIGNATURE_CONTEXT: &[u8] = b"esta-capability-snapshot\0";
+
+/// A capability as recorded in a snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityRecord {
+    pub id: u64,
+    pub resource_type: ResourceType,
+    pub resource_id: String,
+    /// Granted rights, sorted
+    pub rights: Vec<String>,
+    pub owner: String,
+    /// Capability this one was delegated from
+    pub parent_id: Option<u64>,
+    /// Bound to a module instance (the nonce fingerprint is not exported)
+    pub instance_bound: bool,
+    /// Start of the validity window (Unix millis)
+    pub created_at: u64,
+    /// End of the validity window (Unix millis); `None` never expires
+    pub expires_at: Option<u64>,
+    pub max_uses: Option<u64>,
+    pub use_count: u64,
+}
+
+impl From<&Capability> for CapabilityRecord {
+    fn from(cap: &Capability) -> Self {
+        let mut rights: Vec<String> = cap.rights.iter().map(|r| r.as_str().to_string()).collect();
+        rights.sort();
+        Self {
+            id: cap.id.as_u64(),
+            resource_type: cap.resource_type.clone(),
+            resource_id: cap.resource_id.clone(),
+            rights,
+            owner: cap.owner.clone(),
+            parent_id: cap.parent_id.map(|id| id.as_u64()),
+            instance_bound: cap.bound_instance.is_some(),
+            created_at: cap.created_at,
+            expires_at: cap.validity.expires_at,
+            max_uses: cap.validity.max_uses,
+            use_count: cap.validity.use_count,
+        }
+    }
+}
+
+impl CapabilityRecord {
+    /// Names of the fields that differ from `other`
+    fn changed_fields(&self, other: &Self) -> Vec<&'static str> {
+        let checks = [
+            ("resource_type", self.resource_type != other.resource_type),
+            ("resource_id", self.resource_id != other.resource_id),
+            ("rights", self.rights != other.rights),
+            ("owner", self.owner != other.owner),
+            ("parent_id", self.parent_id != other.parent_id),
+            ("instance_bound", self.instance_bound != other.instance_bound),
+            ("created_at", self.created_at != other.created_at),
+            ("expires_at", self.expires_at != other.expires_at),
+            ("max_uses", self.max_uses != other.max_uses),
+            ("use_count", self.use_count != other.use_count),
+        ];
+        checks.into_iter().filter(|(_, changed)| *changed).map(|(name, _)| name).collect()
+    }
+}
+
+/// A delegation from one capability to another
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DelegationEdge {
+    pub parent_id: u64,
+    pub child_id: u64,
+}
+
+/// Signed record of the active capabilities at one point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilitySnapshot {
+    pub version: u32,
+    /// When the snapshot was taken (Unix millis)
+    pub exported_at: u64,
+    /// Active capabilities, by ID
+    pub capabilities: Vec<CapabilityRecord>,
+    /// Delegations between active capabilities, by parent then child
+    pub delegations: Vec<DelegationEdge>,
+    /// HMAC-SHA256 (hex) over the rest of the snapshot
+    #[serde(default)]
+    pub signature: String,
+}
+
+impl CapabilitySnapshot {
+    /// Build and sign a snapshot of the given (active) capabilities
+    pub(crate) fn new<'a>(
+        capabilities: impl IntoIterator<Item = &'a Capability>,
+        exported_at: u64,
+        secret: &[u8],
+    ) -> Self {
+        let mut capabilities: Vec<CapabilityRecord> = capabilities.into_iter().map(CapabilityRecord::from).collect();
+        capabilities.sort_by_key(|cap| cap.id);
+        let mut delegations: Vec<DelegationEdge> = capabilities
+            .iter()
+            .filter_map(|cap| cap.parent_id.map(|parent_id| DelegationEdge { parent_id, child_id: cap.id }))
+            .collect();
+        delegations.sort();
+
+        let mut snapshot = Self {
+            version: SNAPSHOT_VERSION,
+            exported_at,
+            capabilities,
+            delegations,
+            signature: String::new(),
+        };
+        snapshot.signature = snapshot.compute_signature(secret);
+        snapshot
+    }
+
+    fn compute_signature(&self, secret: &[u8]) -> String {
+        hex::encode(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, secret), &self.signed_message()).as_ref())
+    }
+
+    /// Whether the snapshot was signed with `secret` and has not been altered
+    pub(crate) fn verify(&self, secret: &[u8]) -> bool {
+        hex::decode(&self.signature).is_ok_and(|signature| {
+            hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, secret), &self.signed_message(), &signature).is_ok()
+        })
+    }
+
+    fn signed_message(&self) -> Vec<u8> {
+        let unsigned = Self { signature: String::new(), ..self.clone() };
+        let mut message = SIGNATURE_CONTEXT.to_vec();
+        message.extend(serde_json::to_vec(&unsigned).unwrap_or_default());
+        message
+    }
+
+    /// What changed between this snapshot and a later one
+    pub fn diff(&self, later: &CapabilitySnapshot) -> SnapshotDiff {
+        let before: BTreeMap<u64, &CapabilityRecord> = self.capabilities.iter().map(|cap| (cap.id, cap)).collect();
+        let after: BTreeMap<u64, &CapabilityRecord> = later.capabilities.iter().map(|cap| (cap.id, cap)).collect();
+
+        let added = after.iter().filter(|(id, _)| !before.contains_key(id)).map(|(_, cap)| (*cap).clone()).collect();
+        let removed = before.iter().filter(|(id, _)| !after.contains_key(id)).map(|(_, cap)| (*cap).clone()).collect();
+        let changed = before
+            .iter()
+            .filter_map(|(id, old)| {
+                let new = after.get(id)?;
+                let fields = old.changed_fields(new);
+                (!fields.is_empty()).then(|| CapabilityChange {
+                    id: *id,
+                    fields,
+                    before: (*old).clone(),
+                    after: (*new).clone(),
+                })
+            })
+            .collect();
+
+        SnapshotDiff {
+            from: self.exported_at,
+            to: later.exported_at,
+            added,
+            removed,
+            changed,
+            delegations_added: later.delegations.iter().filter(|e| !self.delegations.contains(e)).cloned().collect(),
+            delegations_removed: self.delegations.iter().filter(|e| !later.delegations.contains(e)).cloned().collect(),
+        }
+    }
+}
+
+/// A capability present in both snapshots with different fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityChange {
+    pub id: u64,
+    /// Names of the fields that differ
+    pub fields: Vec<&'static str>,
+    pub before: CapabilityRecord,
+    pub after: CapabilityRecord,
+}
+
+/// Differences between two capability snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotDiff {
+    /// Export times of the earlier and later snapshots
+    pub from: u64,
+    pub to: u64,
+    /// Capabilities granted since the earlier snapshot
+    pub added: Vec<CapabilityRecord>,
+    /// Capabilities revoked or expired since the earlier snapshot
+    pub removed: Vec<CapabilityRecord>,
+    pub changed: Vec<CapabilityChange>,
+    pub delegations_added: Vec<DelegationEdge>,
+    pub delegations_removed: Vec<DelegationEdge>,
+}
+
+impl SnapshotDiff {
+    /// Whether the snapshots describe the same capabilities
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.delegations_added.is_empty()
+            && self.delegations_removed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::capabilities::{CapabilityManager, CapabilityRight, CapabilityValidity};
+
+    #[tokio::test]
+    async fn test_export_and_diff() {
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let ledger = manager.create_full_access(ResourceType::Module, "ledger".into(), "kernel".into()).await.unwrap();
+        let rights = [CapabilityRight::Read].into_iter().collect();
+        let delegated = manager.delegate(&ledger, "reports".into(), rights, CapabilityValidity::default()).await.unwrap();
+        let config = manager.create_read_only(ResourceType::Config, "policy".into(), "ui".into()).await.unwrap();
+
+        let q1 = manager.export_state().await;
+        assert_eq!(q1.capabilities.len(), 3);
+        assert_eq!(q1.delegations.len(), 1);
+        let json = serde_json::to_string(&q1).unwrap();
+        assert!(!json.contains(ledger.as_str()) && !json.contains(delegated.as_str()), "tokens are never exported");
+        assert!(manager.verify_snapshot(&q1).await);
+
+        let mut edited = q1.clone();
+        edited.capabilities[0].rights.push("write".into());
+        assert!(!manager.verify_snapshot(&edited).await);
+
+        // Revoking the parent cascades to the delegation; a new grant and a use appear
+        manager.revoke(&ledger).await.unwrap();
+        manager.record_usage(&config).await.unwrap();
+        manager.create_read_only(ResourceType::Module, "archive".into(), "ui".into()).await.unwrap();
+        let q2 = manager.export_state().await;
+
+        let diff = q1.diff(&q2);
+        assert_eq!(diff.removed.len(), 2);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].resource_id, "archive");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields, ["use_count"]);
+        assert_eq!(diff.delegations_removed, q1.delegations);
+        assert!(diff.delegations_added.is_empty());
+        assert!(q2.diff(&q2).is_empty());
+
+        // A rotated secret no longer vouches for older snapshots
+        manager.rotate_secret(CapabilityManager::generate_secret().unwrap(), &[]).await;
+        assert!(!manager.verify_snapshot(&q2).await);
+    }
+}
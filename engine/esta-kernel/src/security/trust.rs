@@ -0,0 +1,307 @@
+//! Trusted Module Signing Keys
+//!
+//! Module and statute signatures are verified against a trust store instead
+//! of a single key, so the publisher's signing key can be rotated in the
+//! field. [`TrustStore::rotate`] makes a new key current and keeps the keys it
+//! replaces trusted for a grace period, during which modules signed by either
+//! are accepted. Once the grace period ends, signatures by a retired key are
+//! rejected with [`SignatureError::KeyRetired`].
+//!
+//! A store opened from a file saves every rotation, so a rotation survives
+//! restarts without reinstalling the application. Retired keys stay in the
+//! file as a record of what was once trusted.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use thiserror::Error;
+
+use super::sig::{SignatureError, SignatureResult, SignatureVerifier};
+use crate::clock::now_millis;
+
+/// Trust store file format version
+const TRUST_STORE_VERSION: u32 = 1;
+
+/// Errors opening a trust store or rotating its keys
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TrustError {
+    #[error("Trust store I/O failed: {0}")]
+    Io(String),
+
+    #[error("Trust store is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Invalid signing key: {0}")]
+    InvalidKey(String),
+
+    #[error("Key {0} is already trusted")]
+    AlreadyTrusted(String),
+}
+
+/// Result type for trust store operations
+pub type TrustResult<T> = Result<T, TrustError>;
+
+/// A public key trusted to sign modules
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Short identifier: the first 16 hex digits of the key's SHA-256
+    pub key_id: String,
+    /// Ed25519 public key (hex)
+    pub public_key: String,
+    /// When the key was added (Unix millis)
+    pub added_at: u64,
+    /// When the key stops being trusted (Unix millis); `None` while current
+    pub retires_at: Option<u64>,
+}
+
+impl TrustedKey {
+    fn new(public_key_hex: &str) -> TrustResult<Self> {
+        let public_key = public_key_hex.trim().to_ascii_lowercase();
+        SignatureVerifier::new(&public_key).map_err(|e| TrustError::InvalidKey(e.to_string()))?;
+        Ok(Self { key_id: key_id(&public_key), public_key, added_at: now_millis(), retires_at: None })
+    }
+
+    /// Whether signatures by this key are still accepted at `now`
+    pub fn is_trusted_at(&self, now: u64) -> bool {
+        self.retires_at.is_none_or(|retires_at| now < retires_at)
+    }
+
+    /// Whether the key is in its grace period after a rotation
+    pub fn is_retiring(&self) -> bool {
+        self.retires_at.is_some() && self.is_trusted_at(now_millis())
+    }
+
+    fn verifier(&self) -> SignatureVerifier {
+        SignatureVerifier::new(&self.public_key).expect("trusted keys are validated when added")
+    }
+}
+
+/// Outcome of a key rotation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyRotation {
+    /// The key that is now current
+    pub current: TrustedKey,
+    /// Keys accepted until `retires_at`, then rejected
+    pub retiring: Vec<TrustedKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TrustFile {
+    version: u32,
+    keys: Vec<TrustedKey>,
+}
+
+/// Set of module signing keys, with a current key and keys being retired
+pub struct TrustStore {
+    keys: RwLock<Vec<TrustedKey>>,
+    path: Option<PathBuf>,
+}
+
+impl TrustStore {
+    /// Trust a single key, kept in memory
+    pub fn new(public_key_hex: &str) -> TrustResult<Self> {
+        Ok(Self { keys: RwLock::new(vec![TrustedKey::new(public_key_hex)?]), path: None })
+    }
+
+    /// Trust several keys, kept in memory; the last is current
+    pub fn with_keys(public_keys_hex: &[impl AsRef<str>]) -> TrustResult<Self> {
+        Ok(Self { keys: RwLock::new(initial_keys(public_keys_hex)?), path: None })
+    }
+
+    /// Open a trust store file, creating it with `initial_key` if it is missing
+    ///
+    /// An existing file wins over `initial_key`: after a rotation the file
+    /// holds the current key, while the configured key may be the old one.
+    pub fn open(path: impl Into<PathBuf>, initial_key: &str) -> TrustResult<Self> {
+        Self::open_with_keys(path, &[initial_key])
+    }
+
+    /// Open a trust store file, creating it with several keys (the last
+    /// current) if it is missing; an existing file wins, as with [`TrustStore::open`]
+    pub fn open_with_keys(path: impl Into<PathBuf>, public_keys_hex: &[impl AsRef<str>]) -> TrustResult<Self> {
+        let path = path.into();
+        let keys = if path.exists() {
+            read(&path)?
+        } else {
+            let keys = initial_keys(public_keys_hex)?;
+            save(&path, &keys)?;
+            keys
+        };
+        Ok(Self { keys: RwLock::new(keys), path: Some(path) })
+    }
+
+    /// Open an existing trust store file
+    pub fn load(path: impl Into<PathBuf>) -> TrustResult<Self> {
+        let path = path.into();
+        Ok(Self { keys: RwLock::new(read(&path)?), path: Some(path) })
+    }
+
+    /// The file rotations are saved to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Every key ever trusted, oldest first
+    pub fn keys(&self) -> Vec<TrustedKey> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// The key new modules should be signed with
+    pub fn current(&self) -> Option<TrustedKey> {
+        self.keys().into_iter().rev().find(|key| key.retires_at.is_none())
+    }
+
+    /// Make `new_key` current, retiring the current key after `grace_period`
+    ///
+    /// Keys already retiring keep their earlier deadline. With a zero grace
+    /// period the replaced key is rejected immediately.
+    pub fn rotate(&self, new_key: &str, grace_period: Duration) -> TrustResult<KeyRotation> {
+        let new_key = TrustedKey::new(new_key)?;
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        if keys.iter().any(|key| key.public_key == new_key.public_key) {
+            return Err(TrustError::AlreadyTrusted(new_key.key_id));
+        }
+
+        let now = now_millis();
+        let deadline = now.saturating_add(grace_period.as_millis() as u64);
+        let mut rotated = keys.clone();
+        for key in rotated.iter_mut() {
+            key.retires_at = Some(key.retires_at.map_or(deadline, |at| at.min(deadline)));
+        }
+        rotated.push(new_key.clone());
+
+        if let Some(path) = &self.path {
+            save(path, &rotated)?;
+        }
+        let retiring = rotated.iter().filter(|key| key.retires_at.is_some_and(|at| at > now)).cloned().collect();
+        *keys = rotated;
+        Ok(KeyRotation { current: new_key, retiring })
+    }
+
+    /// Verify a signature, returning the key that made it
+    ///
+    /// A signature by a key whose grace period has ended fails with
+    /// [`SignatureError::KeyRetired`].
+    pub fn verify(&self, data: &[u8], signature_hex: &str) -> SignatureResult<TrustedKey> {
+        self.verify_with(|verifier| verifier.verify(data, signature_hex))
+    }
+
+    /// Verify a module signature (see [`SignatureVerifier::verify_module`])
+    pub fn verify_module(
+        &self,
+        module_bytes: &[u8],
+        checksum: &str,
+        signature_hex: &str,
+    ) -> SignatureResult<TrustedKey> {
+        self.verify_with(|verifier| verifier.verify_module(module_bytes, checksum, signature_hex))
+    }
+
+    fn verify_with(&self, verify: impl Fn(&SignatureVerifier) -> SignatureResult<()>) -> SignatureResult<TrustedKey> {
+        let now = now_millis();
+        let keys = self.keys();
+        // Newest first: the current key signs almost everything
+        let mut last_error = SignatureError::InvalidSignature;
+        for key in keys.iter().rev() {
+            match verify(&key.verifier()) {
+                Ok(()) if key.is_trusted_at(now) => return Ok(key.clone()),
+                Ok(()) => return Err(SignatureError::KeyRetired(key.key_id.clone())),
+                // Format and checksum errors do not depend on the key
+                Err(e @ SignatureError::InvalidFormat(_)) => return Err(e),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+/// The keys a new store trusts; none, or the same key twice, is an error
+fn initial_keys(public_keys_hex: &[impl AsRef<str>]) -> TrustResult<Vec<TrustedKey>> {
+    if public_keys_hex.is_empty() {
+        return Err(TrustError::InvalidKey("no signing key given".into()));
+    }
+    let mut keys: Vec<TrustedKey> = Vec::with_capacity(public_keys_hex.len());
+    for key in public_keys_hex {
+        let key = TrustedKey::new(key.as_ref())?;
+        if keys.iter().any(|trusted| trusted.public_key == key.public_key) {
+            return Err(TrustError::AlreadyTrusted(key.key_id));
+        }
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+fn key_id(public_key_hex: &str) -> String {
+    hex::encode(Sha256::digest(public_key_hex.as_bytes()))[..16].to_string()
+}
+
+fn read(path: &Path) -> TrustResult<Vec<TrustedKey>> {
+    let bytes = std::fs::read(path).map_err(|e| TrustError::Io(e.to_string()))?;
+    let file: TrustFile = serde_json::from_slice(&bytes).map_err(|e| TrustError::Corrupt(e.to_string()))?;
+    if file.version != TRUST_STORE_VERSION {
+        return Err(TrustError::Corrupt(format!("unsupported version {}", file.version)));
+    }
+    for key in &file.keys {
+        SignatureVerifier::new(&key.public_key).map_err(|e| TrustError::Corrupt(format!("key {}: {}", key.key_id, e)))?;
+    }
+    Ok(file.keys)
+}
+
+fn save(path: &Path, keys: &[TrustedKey]) -> TrustResult<()> {
+    let io = |e: std::io::Error| TrustError::Io(e.to_string());
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(io)?;
+    }
+    let file = TrustFile { version: TRUST_STORE_VERSION, keys: keys.to_vec() };
+    let json = serde_json::to_vec_pretty(&file).map_err(|e| TrustError::Corrupt(e.to_string()))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, json).map_err(io)?;
+    std::fs::rename(&tmp, path).map_err(io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::sig::ModuleSigner;
+    use crate::testing::TimeMachine;
+
+    #[tokio::test]
+    async fn test_rotation_grace_period() {
+        let time = TimeMachine::start();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trust.json");
+        let old = ModuleSigner::generate().unwrap();
+        let new = ModuleSigner::generate().unwrap();
+        let data = b"module";
+
+        let store = TrustStore::open(&path, &old.public_key_hex()).unwrap();
+        let rotation = store.rotate(&new.public_key_hex(), Duration::from_secs(7 * 86_400)).unwrap();
+        assert_eq!(rotation.current.public_key, new.public_key_hex());
+        assert_eq!(rotation.retiring.len(), 1);
+        assert_eq!(
+            store.rotate(&old.public_key_hex(), Duration::ZERO),
+            Err(TrustError::AlreadyTrusted(rotation.retiring[0].key_id.clone()))
+        );
+
+        // Both keys verify during the grace period, and the store reports which
+        let by_old = store.verify(data, &old.sign(data)).unwrap();
+        assert!(by_old.is_retiring());
+        assert_eq!(store.verify(data, &new.sign(data)).unwrap(), rotation.current);
+
+        // The rotation was saved; the configured (old) key does not override it
+        let reopened = TrustStore::open(&path, &old.public_key_hex()).unwrap();
+        assert_eq!(reopened.current(), Some(rotation.current.clone()));
+        assert_eq!(TrustStore::load(&path).unwrap().keys(), reopened.keys());
+        assert!(matches!(TrustStore::load(dir.path().join("missing.json")), Err(TrustError::Io(_))));
+
+        time.advance(Duration::from_secs(7 * 86_400)).await;
+        assert_eq!(
+            reopened.verify(data, &old.sign(data)),
+            Err(SignatureError::KeyRetired(by_old.key_id))
+        );
+        assert!(reopened.verify(data, &new.sign(data)).is_ok());
+        let stranger = ModuleSigner::generate().unwrap();
+        assert_eq!(reopened.verify(data, &stranger.sign(data)), Err(SignatureError::InvalidSignature));
+    }
+}
@@ -0,0 +1,706 @@
+//! HTTP Service Mode
+//!
+//! Exposes a kernel over an authenticated HTTP/JSON API for headless
+//! deployments (feature `server`; `esta-kernel-cli daemon run --listen`
+//! serves it). Operations mirror the desktop app's commands and go through
+//! the same kernel calls, so policy validation, tenant isolation, capability
+//! checks, and auditing behave as they do there.
+//!
+//! Callers authenticate with `Authorization: Bearer <token>`:
+//!
+//! - An administrator API key (configured as its SHA-256 digest, see
+//!   [`ServerConfig::with_admin_key`]) may call every operation.
+//! - A capability token, such as the root capability returned when a tenant
+//!   is created or one delegated from it, may call its tenant's operations
+//!   when it holds the rights they need. It is validated by the kernel's
+//!   capability manager, which audits refusals.
+//!
+//! Every call is recorded in the audit log as a `Custom` event of category
+//! `http` (source `http`) with the operation, caller, correlation ID,
+//! outcome, and duration; calls on a tenant's routes are tagged with it. A
+//! request's `X-Correlation-Id` is accepted if well formed and returned on
+//! the response. Failures are the kernel's [`UserError`], localized by
+//! `Accept-Language`, with an HTTP status chosen from its code.
+//!
+//! Administrator routes:
+//!
+//! - `GET /modules` - modules in the catalog with verification status
+//! - `POST /modules/load` `{"manifest_path"}`, `POST /modules/install`
+//!   `{"name", "version"?}`, `POST /modules/rollback` `{"name", "version"}`
+//! - `POST /modules/{module}/{function}` - run a function with the body as input
+//! - `POST /replay` `{"from", "to"}` - re-execute recorded invocations
+//! - `GET /audit` - a page of audit entries (`AuditQuery` fields, `cursor`, `limit`)
+//! - `POST /audit/verify?full=` - verify new entries, or start a full verification
+//! - `GET /storage`, `POST /storage/vacuum`
+//! - `POST /capability-secret/rotate`
+//! - `POST /tenants` `{"tenant_id"}`, `POST /tenants/{tenant_id}/archive`,
+//!   `DELETE /tenants/{tenant_id}`, `POST /tenants/{tenant_id}/retention`
+//! - `PUT /tenants/{tenant_id}/policy` `{"policy", "effective_from"?}`
+//! - `GET /alerts?tenant_id=`, `PUT /alerts?tenant_id=` - alert configuration
+//!
+//! Tenant routes (administrators, or a capability for the tenant with the right shown):
+//!
+//! - `GET /tenants/{tenant_id}/policy` (read) - policy history
+//! - `GET /tenants/{tenant_id}/usage` (read) - fuel used
+//! - `GET /tenants/{tenant_id}/employees` (list), `POST` `{"employee_ids"}` (write)
+//! - `POST /tenants/{tenant_id}/ledger` (write) - append ledger events for the tenant
+//! - `GET /tenants/{tenant_id}/reports/compliance?year=` (read)
+//! - `GET /tenants/{tenant_id}/insights?as_of=` (read)
+//! - `POST /tenants/{tenant_id}/execute/{module}/{function}` (execute)
+//!
+//! Any authenticated caller: `GET /status`, `GET /statutes?date=&jurisdiction=`.
+
+use crate::alerts::AlertConfig;
+use crate::calendar::Date;
+use crate::correlation;
+use crate::ledger::NewLedgerEvent;
+use crate::pagination::PageRequest;
+use crate::security::audit::AuditQuery;
+use crate::security::capabilities::{CapabilityRight, CapabilityToken};
+use crate::tenant::{self, TenantPolicy};
+use crate::user_errors::{from_anyhow, ErrorCode, Locale, UserError, UserFacing};
+use crate::Kernel;
+use anyhow::{anyhow, Result};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Audit category (and source) of recorded API calls
+pub const HTTP_CATEGORY: &str = "http";
+
+/// Header carrying a request's correlation ID
+pub const CORRELATION_HEADER: HeaderName = HeaderName::from_static("x-correlation-id");
+
+/// Largest request body accepted unless configured otherwise (1 MiB)
+pub const DEFAULT_MAX_BODY_BYTES: usize = 1_048_576;
+
+/// Where the API listens and who may call it
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub listen: SocketAddr,
+    /// SHA-256 digests (hex) of administrator API keys
+    pub admin_key_digests: Vec<String>,
+    pub max_body_bytes: usize,
+}
+
+impl ServerConfig {
+    /// Listen on `listen` with no administrator keys
+    pub fn new(listen: SocketAddr) -> Self {
+        Self { listen, admin_key_digests: Vec::new(), max_body_bytes: DEFAULT_MAX_BODY_BYTES }
+    }
+
+    /// Accept an administrator API key (only its digest is kept)
+    pub fn with_admin_key(mut self, key: &str) -> Self {
+        self.admin_key_digests.push(api_key_digest(key));
+        self
+    }
+
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+}
+
+/// SHA-256 digest (hex) of an API key, as kept in [`ServerConfig::admin_key_digests`]
+pub fn api_key_digest(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Serve the API until `shutdown` completes
+pub async fn serve(kernel: Arc<Kernel>, config: ServerConfig, shutdown: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(config.listen).await?;
+    log::info!("Serving the kernel API on {}", listener.local_addr()?);
+    axum::serve(listener, router(kernel, config)).with_graceful_shutdown(shutdown).await?;
+    Ok(())
+}
+
+/// The API over a kernel
+pub fn router(kernel: Arc<Kernel>, config: ServerConfig) -> Router {
+    let max_body_bytes = config.max_body_bytes;
+    Router::new()
+        .route("/status", get(status))
+        .route("/statutes", get(statute))
+        .route("/modules", get(available_modules))
+        .route("/modules/load", post(load_module))
+        .route("/modules/install", post(install_module))
+        .route("/modules/rollback", post(rollback_module))
+        .route("/modules/{module}/{function}", post(execute))
+        .route("/replay", post(replay))
+        .route("/audit", get(audit))
+        .route("/audit/verify", post(verify_audit))
+        .route("/storage", get(storage_usage))
+        .route("/storage/vacuum", post(storage_vacuum))
+        .route("/capability-secret/rotate", post(rotate_capability_secret))
+        .route("/alerts", get(alert_config).put(set_alert_config))
+        .route("/tenants", post(create_tenant))
+        .route("/tenants/{tenant_id}", axum::routing::delete(purge_tenant))
+        .route("/tenants/{tenant_id}/archive", post(archive_tenant))
+        .route("/tenants/{tenant_id}/retention", post(apply_retention))
+        .route("/tenants/{tenant_id}/policy", put(set_policy).get(policy_history))
+        .route("/tenants/{tenant_id}/usage", get(tenant_usage))
+        .route("/tenants/{tenant_id}/employees", get(list_employees).post(add_employees))
+        .route("/tenants/{tenant_id}/ledger", post(append_ledger))
+        .route("/tenants/{tenant_id}/reports/compliance", get(compliance_report))
+        .route("/tenants/{tenant_id}/insights", get(usage_insights))
+        .route("/tenants/{tenant_id}/execute/{module}/{function}", post(execute_for_tenant))
+        .layer(DefaultBodyLimit::max(max_body_bytes))
+        .with_state(Arc::new(Api { kernel, config }))
+}
+
+struct Api {
+    kernel: Arc<Kernel>,
+    config: ServerConfig,
+}
+
+/// Who may call an operation
+enum Access<'a> {
+    /// Any administrator or holder of a valid capability
+    Any,
+    Admin,
+    /// Administrators, or a capability for the tenant holding these rights
+    Tenant(&'a str, &'a [CapabilityRight]),
+}
+
+/// One recorded API call, serialized as the audit event's message
+#[derive(Serialize)]
+struct ApiCall<'a> {
+    operation: &'a str,
+    /// `admin`, the resource of the presented capability, or `anonymous`
+    caller: &'a str,
+    correlation_id: &'a str,
+    /// `ok`, or the failure's error code
+    outcome: &'a str,
+    duration_ms: u64,
+}
+
+impl Api {
+    /// Authorize, run, and record one operation
+    async fn traced<T: Serialize>(
+        &self,
+        headers: &HeaderMap,
+        operation: &str,
+        access: Access<'_>,
+        handler: impl Future<Output = Result<T>>,
+    ) -> Response {
+        let locale = headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map_or(Locale::English, Locale::from_tag);
+        let supplied = headers.get(&CORRELATION_HEADER).and_then(|value| value.to_str().ok());
+        let correlation_id = correlation::accept(supplied);
+        let tenant_id = match access {
+            Access::Tenant(tenant_id, _) => Some(tenant_id.to_string()),
+            _ => None,
+        };
+
+        let call = async {
+            let started = Instant::now();
+            let (caller, result) = match self.authorize(headers, &access, locale).await {
+                Ok(caller) => {
+                    let result = async { Ok(serde_json::to_value(handler.await?)?) };
+                    (caller, result.await.map_err(|e: anyhow::Error| from_anyhow(&e, locale)))
+                }
+                Err(refused) => ("anonymous".to_string(), Err(refused)),
+            };
+            let record = ApiCall {
+                operation,
+                caller: &caller,
+                correlation_id: &correlation_id,
+                outcome: result.as_ref().err().map_or("ok", |e| e.code),
+                duration_ms: started.elapsed().as_millis().min(u64::MAX as u128) as u64,
+            };
+            let message = serde_json::to_string(&record).unwrap_or_default();
+            self.kernel.audit_log().log_custom(HTTP_CATEGORY, &message, HTTP_CATEGORY).await;
+            match result {
+                Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+                Err(error) => {
+                    log::warn!("{} failed: {}", operation, error.detail.as_deref().unwrap_or(error.code));
+                    (status_for(error.code), Json(error)).into_response()
+                }
+            }
+        };
+        let call = correlation::scope(correlation_id.clone(), call);
+        let mut response = match tenant_id {
+            Some(tenant_id) => tenant::scope(tenant_id, call).await,
+            None => call.await,
+        };
+        if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+            response.headers_mut().insert(CORRELATION_HEADER, value);
+        }
+        response
+    }
+
+    /// The caller's name if the bearer token grants `access`
+    async fn authorize(&self, headers: &HeaderMap, access: &Access<'_>, locale: Locale) -> Result<String, UserError> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| UserError::new(ErrorCode::NotSignedIn, locale, Some("no bearer token".to_string())))?;
+
+        let digest = api_key_digest(token);
+        if self.config.admin_key_digests.iter().any(|d| d.eq_ignore_ascii_case(&digest)) {
+            return Ok("admin".to_string());
+        }
+
+        let capabilities = self.kernel.capability_manager();
+        let token = CapabilityToken::presented(token);
+        let validated = match access {
+            Access::Admin => {
+                let detail = "an administrator API key is required".to_string();
+                return Err(UserError::new(ErrorCode::PermissionDenied, locale, Some(detail)));
+            }
+            Access::Any => capabilities.validate(&token, &[]).await,
+            Access::Tenant(tenant_id, rights) => capabilities.validate_for_tenant(&token, tenant_id, rights).await,
+        };
+        validated.map(|capability| capability.resource_id).map_err(|e| e.to_user_error(locale))
+    }
+}
+
+/// HTTP status for a user error code
+fn status_for(code: &str) -> StatusCode {
+    match code {
+        "NOT_SIGNED_IN" => StatusCode::UNAUTHORIZED,
+        "PERMISSION_DENIED" | "TENANT_ISOLATION" | "CAPABILITY_DENIED" | "CAPABILITY_EXPIRED" | "PROFILE_RESTRICTED" => {
+            StatusCode::FORBIDDEN
+        }
+        "MODULE_NOT_LOADED" | "MODULE_NOT_AVAILABLE" | "TENANT_NOT_FOUND" | "EMPLOYEE_NOT_FOUND" | "TEMPLATE_NOT_FOUND" => {
+            StatusCode::NOT_FOUND
+        }
+        "TENANT_EXISTS" | "TENANT_ARCHIVED" | "TENANT_ACTIVE" => StatusCode::CONFLICT,
+        "INVALID_TENANT_ID" | "INVALID_POLICY" | "INVALID_REQUEST" | "INVALID_DATE" | "INPUT_REJECTED"
+        | "INSIGHTS_DISABLED" => StatusCode::BAD_REQUEST,
+        "INPUT_TOO_LARGE" => StatusCode::PAYLOAD_TOO_LARGE,
+        "BUSY" | "FUEL_CEILING" => StatusCode::TOO_MANY_REQUESTS,
+        "SHUTTING_DOWN" | "READ_ONLY" | "AUDIT_UNAVAILABLE" | "STORAGE_UNAVAILABLE" | "CATALOG_UNAVAILABLE" => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+type ApiState = State<Arc<Api>>;
+
+const READ: &[CapabilityRight] = &[CapabilityRight::Read];
+const LIST: &[CapabilityRight] = &[CapabilityRight::List];
+const WRITE: &[CapabilityRight] = &[CapabilityRight::Write];
+const EXECUTE: &[CapabilityRight] = &[CapabilityRight::Execute];
+
+/// Parse an optional date, defaulting to today
+fn date_or_today(date: Option<&str>) -> Result<Date> {
+    Ok(date.map(str::parse).transpose()?.unwrap_or_else(Date::today))
+}
+
+/// Run a module function with JSON input, returning its JSON output
+async fn execute_json(
+    kernel: &Kernel,
+    tenant_id: Option<&str>,
+    module: &str,
+    function: &str,
+    input: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let input = serde_json::to_vec(input)?;
+    let report = match tenant_id {
+        Some(tenant_id) => kernel.execute_for_tenant(tenant_id, module, function, &input).await?,
+        None => kernel.execute_function(module, function, &input).await?,
+    };
+    let output: serde_json::Value = serde_json::from_slice(&report.output)
+        .map_err(|e| anyhow!("Module '{}' returned invalid JSON: {}", module, e))?;
+    Ok(serde_json::json!({
+        "module": report.module_name,
+        "function": report.function_name,
+        "output": output,
+        "fuel_consumed": report.fuel_consumed,
+        "cached": report.cached,
+    }))
+}
+
+async fn status(State(api): ApiState, headers: HeaderMap) -> Response {
+    api.traced(&headers, "kernel_get_status", Access::Any, async { Ok(api.kernel.get_status().await) }).await
+}
+
+#[derive(Deserialize)]
+struct StatuteQuery {
+    date: Option<String>,
+    jurisdiction: Option<String>,
+}
+
+async fn statute(State(api): ApiState, headers: HeaderMap, Query(query): Query<StatuteQuery>) -> Response {
+    let handler = async {
+        let date = date_or_today(query.date.as_deref())?;
+        let jurisdiction = query.jurisdiction.as_deref().unwrap_or(api.kernel.jurisdiction());
+        Ok(api.kernel.statute_for(jurisdiction, date).await?)
+    };
+    api.traced(&headers, "statute_get_parameters", Access::Any, handler).await
+}
+
+async fn available_modules(State(api): ApiState, headers: HeaderMap) -> Response {
+    let handler = api.kernel.available_modules();
+    api.traced(&headers, "kernel_list_available_modules", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct LoadModule {
+    manifest_path: String,
+}
+
+async fn load_module(State(api): ApiState, headers: HeaderMap, Json(request): Json<LoadModule>) -> Response {
+    let handler = async {
+        api.kernel.launch_module(&request.manifest_path).await?;
+        Ok(serde_json::json!({ "loaded": request.manifest_path }))
+    };
+    api.traced(&headers, "kernel_load_module", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct InstallModule {
+    name: String,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+async fn install_module(State(api): ApiState, headers: HeaderMap, Json(request): Json<InstallModule>) -> Response {
+    let handler = api.kernel.install_module(&request.name, request.version.as_deref());
+    api.traced(&headers, "kernel_install_module", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct RollbackModule {
+    name: String,
+    version: String,
+}
+
+async fn rollback_module(State(api): ApiState, headers: HeaderMap, Json(request): Json<RollbackModule>) -> Response {
+    let handler = api.kernel.rollback_module(&request.name, &request.version);
+    api.traced(&headers, "kernel_rollback_module", Access::Admin, handler).await
+}
+
+async fn execute(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path((module, function)): Path<(String, String)>,
+    Json(input): Json<serde_json::Value>,
+) -> Response {
+    let handler = execute_json(&api.kernel, None, &module, &function, &input);
+    api.traced(&headers, "kernel_execute", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct ReplayRange {
+    from: u64,
+    to: u64,
+}
+
+async fn replay(State(api): ApiState, headers: HeaderMap, Json(range): Json<ReplayRange>) -> Response {
+    let handler = api.kernel.replay(range.from..=range.to);
+    api.traced(&headers, "kernel_replay", Access::Admin, handler).await
+}
+
+async fn audit(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+    Query(page): Query<PageRequest>,
+) -> Response {
+    let handler = async { api.kernel.audit_log().query_page(&query, &page).await };
+    api.traced(&headers, "kernel_get_logs", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct VerifyQuery {
+    #[serde(default)]
+    full: bool,
+}
+
+async fn verify_audit(State(api): ApiState, headers: HeaderMap, Query(query): Query<VerifyQuery>) -> Response {
+    let handler = async {
+        let audit_log = api.kernel.audit_log();
+        let incremental = audit_log.verify_incremental().await;
+        if query.full {
+            audit_log.start_full_verification().await;
+        }
+        Ok(serde_json::json!({ "incremental": incremental, "full": audit_log.full_verification_progress() }))
+    };
+    api.traced(&headers, "kernel_verify_audit", Access::Admin, handler).await
+}
+
+async fn storage_usage(State(api): ApiState, headers: HeaderMap) -> Response {
+    let handler = async { api.kernel.storage().usage_report().await };
+    api.traced(&headers, "storage_usage_report", Access::Admin, handler).await
+}
+
+async fn storage_vacuum(State(api): ApiState, headers: HeaderMap) -> Response {
+    let handler = async { api.kernel.storage().vacuum().await };
+    api.traced(&headers, "storage_vacuum", Access::Admin, handler).await
+}
+
+async fn rotate_capability_secret(State(api): ApiState, headers: HeaderMap) -> Response {
+    let handler = async {
+        let reissued = api.kernel.rotate_capability_secret().await?;
+        Ok(serde_json::json!({ "reissued": reissued.len() }))
+    };
+    api.traced(&headers, "kernel_rotate_capability_secret", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct AlertTenant {
+    tenant_id: Option<String>,
+}
+
+async fn alert_config(State(api): ApiState, headers: HeaderMap, Query(query): Query<AlertTenant>) -> Response {
+    let handler = async { Ok(api.kernel.alerts().config(query.tenant_id.as_deref())) };
+    api.traced(&headers, "alerts_get_config", Access::Admin, handler).await
+}
+
+async fn set_alert_config(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Query(query): Query<AlertTenant>,
+    Json(config): Json<AlertConfig>,
+) -> Response {
+    let handler = async {
+        api.kernel.alerts().set_config(query.tenant_id.as_deref(), config.clone())?;
+        Ok(config)
+    };
+    api.traced(&headers, "alerts_set_config", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct NewTenant {
+    tenant_id: String,
+}
+
+async fn create_tenant(State(api): ApiState, headers: HeaderMap, Json(request): Json<NewTenant>) -> Response {
+    let handler = api.kernel.create_tenant(&request.tenant_id);
+    api.traced(&headers, "tenant_create", Access::Admin, handler).await
+}
+
+async fn archive_tenant(State(api): ApiState, headers: HeaderMap, Path(tenant_id): Path<String>) -> Response {
+    let handler = async {
+        let revoked = api.kernel.archive_tenant(&tenant_id).await?;
+        Ok(serde_json::json!({ "tenant_id": tenant_id, "capabilities_revoked": revoked }))
+    };
+    api.traced(&headers, "tenant_archive", Access::Admin, handler).await
+}
+
+async fn purge_tenant(State(api): ApiState, headers: HeaderMap, Path(tenant_id): Path<String>) -> Response {
+    let handler = api.kernel.purge_tenant(&tenant_id);
+    api.traced(&headers, "tenant_purge", Access::Admin, handler).await
+}
+
+async fn apply_retention(State(api): ApiState, headers: HeaderMap, Path(tenant_id): Path<String>) -> Response {
+    let handler = api.kernel.apply_retention(&tenant_id);
+    api.traced(&headers, "tenant_apply_retention", Access::Admin, handler).await
+}
+
+#[derive(Deserialize)]
+struct SetPolicy {
+    policy: TenantPolicy,
+    /// First day the policy applies; today when omitted
+    #[serde(default)]
+    effective_from: Option<Date>,
+}
+
+async fn set_policy(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<SetPolicy>,
+) -> Response {
+    let handler = async {
+        let effective_from = request.effective_from.unwrap_or_else(Date::today);
+        Ok(api.kernel.set_tenant_policy(&tenant_id, request.policy, effective_from).await?)
+    };
+    api.traced(&headers, "tenant_set_policy", Access::Admin, handler).await
+}
+
+async fn policy_history(State(api): ApiState, headers: HeaderMap, Path(tenant_id): Path<String>) -> Response {
+    let handler = async { Ok(api.kernel.tenants().policy_history(&tenant_id).await?) };
+    api.traced(&headers, "tenant_get_policy_history", Access::Tenant(&tenant_id, READ), handler).await
+}
+
+async fn tenant_usage(State(api): ApiState, headers: HeaderMap, Path(tenant_id): Path<String>) -> Response {
+    let handler = async { Ok(api.kernel.tenant_usage(&tenant_id).await?) };
+    api.traced(&headers, "tenant_get_usage", Access::Tenant(&tenant_id, READ), handler).await
+}
+
+async fn list_employees(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Query(page): Query<PageRequest>,
+) -> Response {
+    let handler = async {
+        let employees = api.kernel.tenants().list_employees(&tenant_id).await?;
+        Ok(crate::Page::from_sorted(employees, &page, Clone::clone)?)
+    };
+    api.traced(&headers, "tenant_get_accruals", Access::Tenant(&tenant_id, LIST), handler).await
+}
+
+#[derive(Deserialize)]
+struct NewEmployees {
+    employee_ids: Vec<String>,
+}
+
+async fn add_employees(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Json(request): Json<NewEmployees>,
+) -> Response {
+    let handler = async {
+        api.kernel.add_employees(&tenant_id, &request.employee_ids).await?;
+        Ok(serde_json::json!({ "tenant_id": tenant_id, "added": request.employee_ids.len() }))
+    };
+    api.traced(&headers, "tenant_add_employees", Access::Tenant(&tenant_id, WRITE), handler).await
+}
+
+async fn append_ledger(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Json(events): Json<Vec<NewLedgerEvent>>,
+) -> Response {
+    let handler = async {
+        if let Some(other) = events.iter().find(|e| e.tenant_id != tenant_id) {
+            let resource_id = format!("ledger events of tenant {}", other.tenant_id);
+            let tenant_id = tenant_id.clone();
+            return Err(tenant::TenantError::CrossTenantAccess { tenant_id, resource_id }.into());
+        }
+        api.kernel.append_ledger_events(events).await
+    };
+    api.traced(&headers, "ledger_append", Access::Tenant(&tenant_id, WRITE), handler).await
+}
+
+#[derive(Deserialize)]
+struct ReportYear {
+    year: i32,
+}
+
+async fn compliance_report(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Query(query): Query<ReportYear>,
+) -> Response {
+    let handler = async { Ok(api.kernel.compliance_report(&tenant_id, query.year).await?) };
+    api.traced(&headers, "generate_compliance_report", Access::Tenant(&tenant_id, READ), handler).await
+}
+
+#[derive(Deserialize)]
+struct AsOf {
+    as_of: Option<String>,
+}
+
+async fn usage_insights(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+    Query(query): Query<AsOf>,
+) -> Response {
+    let handler = async {
+        let as_of = date_or_today(query.as_of.as_deref())?;
+        api.kernel.usage_insights(&tenant_id, as_of).await
+    };
+    api.traced(&headers, "tenant_usage_insights", Access::Tenant(&tenant_id, READ), handler).await
+}
+
+async fn execute_for_tenant(
+    State(api): ApiState,
+    headers: HeaderMap,
+    Path((tenant_id, module, function)): Path<(String, String, String)>,
+    Json(input): Json<serde_json::Value>,
+) -> Response {
+    let handler = execute_json(&api.kernel, Some(&tenant_id), &module, &function, &input);
+    api.traced(&headers, "kernel_execute", Access::Tenant(&tenant_id, EXECUTE), handler).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    const ADMIN_KEY: &str = "admin-key";
+
+    async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(&CORRELATION_HEADER, "req-42");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = request.body(body.map_or_else(Body::empty, |body| Body::from(body.to_string()))).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[&CORRELATION_HEADER], "req-42");
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_admin_keys_and_capability_tokens() {
+        let kernel = Arc::new(Kernel::new().unwrap());
+        let config = ServerConfig::new("127.0.0.1:0".parse().unwrap()).with_admin_key(ADMIN_KEY);
+        let app = router(kernel.clone(), config);
+
+        let (status, body) = send(&app, "GET", "/status", None, None).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::UNAUTHORIZED, Some("NOT_SIGNED_IN")));
+
+        let (status, acme) = send(&app, "POST", "/tenants", Some(ADMIN_KEY), Some(json!({ "tenant_id": "acme" }))).await;
+        assert_eq!(status, StatusCode::OK, "{}", acme);
+        send(&app, "POST", "/tenants", Some(ADMIN_KEY), Some(json!({ "tenant_id": "globex" }))).await;
+        let token = acme["root_capability"].as_str().unwrap();
+
+        // A tenant's capability reaches its own routes only
+        let (status, _) = send(&app, "GET", "/status", Some(token), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let employees = json!({ "employee_ids": ["e1"] });
+        let (status, _) = send(&app, "POST", "/tenants/acme/employees", Some(token), Some(employees)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, body) = send(&app, "GET", "/tenants/acme/employees", Some(token), None).await;
+        assert_eq!((status, &body["items"]), (StatusCode::OK, &json!(["e1"])));
+        let (status, body) = send(&app, "GET", "/tenants/globex/policy", Some(token), None).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("TENANT_ISOLATION")));
+        let (status, body) = send(&app, "POST", "/tenants/acme/archive", Some(token), None).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("PERMISSION_DENIED")));
+        let (status, _) = send(&app, "GET", "/tenants/acme/policy", Some("cap_1_forged"), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Ledger events must belong to the tenant in the path
+        let event = json!([{
+            "tenant_id": "globex",
+            "employee_id": "e1",
+            "work_date": "2025-03-03",
+            "kind": { "type": "used", "minutes": 60 },
+            "source": "test",
+        }]);
+        let (status, body) = send(&app, "POST", "/tenants/acme/ledger", Some(token), Some(event)).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::FORBIDDEN, Some("TENANT_ISOLATION")), "{}", body);
+        assert!(kernel.ledger().is_empty().await);
+
+        // Calls are audited under the request's correlation ID, tagged with their tenant
+        let entries = kernel.audit_log().query(&AuditQuery::default(), usize::MAX).await.unwrap();
+        let calls: Vec<_> = entries.iter().filter(|e| e.source == HTTP_CATEGORY).collect();
+        assert_eq!(calls.len(), 10);
+        assert!(calls.iter().all(|e| e.correlation_id.as_deref() == Some("req-42")));
+        let added = calls.iter().find(|e| format!("{:?}", e.event).contains("tenant_add_employees")).unwrap();
+        assert_eq!(added.tenant_id.as_deref(), Some("acme"));
+        assert!(format!("{:?}", added.event).contains("tenant:acme/"));
+    }
+}
@@ -0,0 +1,428 @@
+//! Module Statistics History
+//!
+//! `ModuleStats` count from a module's launch, so they start over on every
+//! restart. This module keeps their trend: each snapshot records what every
+//! module did since the previous one (invocations, errors, fuel) under the
+//! hour it was taken in, appended to a JSON Lines file so weeks of history
+//! survive restarts. Queries merge snapshots of the same module and hour, so
+//! snapshotting more often than hourly (or restarting mid-hour) still yields
+//! one point per module per hour.
+//!
+//! Records are hash-chained like the audit log: each record's hash covers its
+//! content and the previous record's hash, and the chain is checked whenever
+//! the file is loaded. An export carries whole records and a digest of their
+//! hashes, so exporting the same range twice gives identical bytes and an
+//! export can be verified away from the kernel.
+//!
+//! Records older than the retention period are dropped when a snapshot is
+//! taken; the oldest remaining record keeps its `prev_hash` as the anchor.
+
+use crate::error::StorageError;
+use crate::kernel::ModuleStats;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Length of one history bucket
+pub const HOUR_MS: u64 = 3_600_000;
+
+/// Default age after which records are dropped
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(90 * 86_400);
+
+/// What one module did in one hour
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSample {
+    pub module: String,
+    /// Start of the hour (ms since Unix epoch)
+    pub hour: u64,
+    pub invocations: u64,
+    pub errors: u64,
+    pub fuel_consumed: u64,
+    /// Peak linear memory since the module was launched
+    pub peak_memory_bytes: usize,
+}
+
+/// A persisted snapshot of one module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    /// Sequence number, increasing across all modules
+    pub sequence: u64,
+    /// When the snapshot was taken (ms since Unix epoch)
+    pub recorded_at: u64,
+    #[serde(flatten)]
+    pub sample: StatsSample,
+    /// Hash of the previous record (of the genesis value for the first)
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl StatsRecord {
+    fn compute_hash(sequence: u64, recorded_at: u64, sample: &StatsSample, prev_hash: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(sequence.to_le_bytes());
+        hasher.update(recorded_at.to_le_bytes());
+        hasher.update(serde_json::to_string(sample).unwrap_or_default().as_bytes());
+        hasher.update(prev_hash.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Whether the stored hash matches the record's content
+    pub fn hash_is_valid(&self) -> bool {
+        self.hash == Self::compute_hash(self.sequence, self.recorded_at, &self.sample, &self.prev_hash)
+    }
+}
+
+fn genesis_hash() -> String {
+    hex::encode(Sha256::digest(b"ESTA-STATS-GENESIS"))
+}
+
+/// Records in a time range, with a digest over their hashes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsExport {
+    /// Start of the range (ms since Unix epoch, inclusive)
+    pub from: u64,
+    /// End of the range (ms since Unix epoch, exclusive)
+    pub to: u64,
+    /// Records whose hour falls in the range, in sequence order
+    pub records: Vec<StatsRecord>,
+    /// SHA-256 over every record hash, in order (hex)
+    pub digest: String,
+}
+
+impl StatsExport {
+    fn digest(records: &[StatsRecord]) -> String {
+        let mut hasher = Sha256::new();
+        for record in records {
+            hasher.update(record.hash.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Check every record's hash, the links between consecutive records, and the digest
+    pub fn verify(&self) -> Result<(), StorageError> {
+        for (i, record) in self.records.iter().enumerate() {
+            if !record.hash_is_valid() {
+                return Err(StorageError::Corrupt(format!("stats record {} was altered", record.sequence)));
+            }
+            let previous = i.checked_sub(1).map(|i| &self.records[i]);
+            if let Some(previous) = previous.filter(|p| p.sequence + 1 == record.sequence) {
+                if record.prev_hash != previous.hash {
+                    return Err(StorageError::Corrupt(format!("stats record {} breaks the chain", record.sequence)));
+                }
+            }
+        }
+        if self.digest != Self::digest(&self.records) {
+            return Err(StorageError::Corrupt("stats export digest does not match its records".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Counters last seen for a module, to turn running totals into deltas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Totals {
+    invocations: u64,
+    errors: u64,
+    fuel_consumed: u64,
+}
+
+impl From<&ModuleStats> for Totals {
+    fn from(stats: &ModuleStats) -> Self {
+        Self { invocations: stats.invocation_count, errors: stats.error_count, fuel_consumed: stats.fuel_consumed }
+    }
+}
+
+#[derive(Default)]
+struct HistoryState {
+    records: Vec<StatsRecord>,
+    /// Hash of the last record ever written, kept when retention drops it
+    last_hash: Option<String>,
+    last_totals: HashMap<String, Totals>,
+}
+
+/// Hourly module statistics, optionally persisted to a JSON Lines file
+pub struct StatsHistory {
+    state: Mutex<HistoryState>,
+    file: Option<PathBuf>,
+    retention: Duration,
+}
+
+impl StatsHistory {
+    /// Keep history in memory only
+    pub fn new(retention: Duration) -> Self {
+        Self { state: Mutex::new(HistoryState::default()), file: None, retention }
+    }
+
+    /// Keep history in a JSON Lines file, loading and verifying what it holds
+    pub fn with_file(path: impl Into<PathBuf>, retention: Duration) -> Result<Self> {
+        let path = path.into();
+        let records = Self::load(&path)?;
+        let state = HistoryState {
+            last_hash: records.last().map(|r| r.hash.clone()),
+            records,
+            last_totals: HashMap::new(),
+        };
+        Ok(Self { state: Mutex::new(state), file: Some(path), retention })
+    }
+
+    /// The file backing this history, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    fn load(path: &Path) -> Result<Vec<StatsRecord>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut records: Vec<StatsRecord> = Vec::new();
+        for (i, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let corrupt = |detail: String| StorageError::Corrupt(format!("{}:{}: {}", path.display(), i + 1, detail));
+            let record: StatsRecord = serde_json::from_str(line).map_err(|e| corrupt(e.to_string()))?;
+            if !record.hash_is_valid() {
+                return Err(corrupt("record hash does not match its content".into()).into());
+            }
+            if records.last().is_some_and(|previous| previous.hash != record.prev_hash) {
+                return Err(corrupt("record does not follow the previous one".into()).into());
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Record what each module did since the previous snapshot
+    ///
+    /// `stats` are the modules' running totals. A module whose totals went
+    /// down was relaunched, so its totals are all new. Modules with no new
+    /// invocations are skipped. Records past the retention period are dropped.
+    pub async fn record(&self, stats: &[(String, ModuleStats)], now: u64) -> Result<Vec<StatsRecord>> {
+        self.append(stats, now, true).await
+    }
+
+    /// Record what one module did since the previous snapshot, as it is unloaded
+    ///
+    /// Other modules' totals are left for the next snapshot. The module's own
+    /// are forgotten, so if it is launched again everything it does is new.
+    pub async fn record_final(&self, module: &str, stats: &ModuleStats, now: u64) -> Result<Vec<StatsRecord>> {
+        self.append(&[(module.to_string(), stats.clone())], now, false).await
+    }
+
+    /// Record the given modules' new invocations; with `all`, `stats` covers
+    /// every module and replaces the remembered totals
+    async fn append(&self, stats: &[(String, ModuleStats)], now: u64, all: bool) -> Result<Vec<StatsRecord>> {
+        let mut state = self.state.lock().await;
+        let hour = now - now % HOUR_MS;
+
+        let mut samples = Vec::new();
+        let mut seen = HashMap::new();
+        for (module, stats) in stats {
+            let totals = Totals::from(stats);
+            let delta = match state.last_totals.get(module) {
+                Some(last) if last.invocations <= totals.invocations => Totals {
+                    invocations: totals.invocations - last.invocations,
+                    errors: totals.errors.saturating_sub(last.errors),
+                    fuel_consumed: totals.fuel_consumed.saturating_sub(last.fuel_consumed),
+                },
+                _ => totals,
+            };
+            seen.insert(module.clone(), totals);
+            if delta.invocations > 0 {
+                samples.push(StatsSample {
+                    module: module.clone(),
+                    hour,
+                    invocations: delta.invocations,
+                    errors: delta.errors,
+                    fuel_consumed: delta.fuel_consumed,
+                    peak_memory_bytes: stats.peak_memory_bytes,
+                });
+            }
+        }
+        samples.sort_by(|a, b| a.module.cmp(&b.module));
+
+        let mut prev_hash = state.last_hash.clone().unwrap_or_else(genesis_hash);
+        let mut sequence = state.records.last().map_or(0, |r| r.sequence + 1);
+        let recorded: Vec<StatsRecord> = samples
+            .into_iter()
+            .map(|sample| {
+                let hash = StatsRecord::compute_hash(sequence, now, &sample, &prev_hash);
+                let record = StatsRecord { sequence, recorded_at: now, sample, prev_hash: prev_hash.clone(), hash };
+                prev_hash = record.hash.clone();
+                sequence += 1;
+                record
+            })
+            .collect();
+
+        let cutoff = now.saturating_sub(self.retention.as_millis() as u64);
+        let expired = state.records.iter().take_while(|r| r.sample.hour < cutoff).count();
+
+        if let Some(path) = &self.file {
+            if expired > 0 {
+                let kept = state.records[expired..].iter().chain(&recorded);
+                let tmp = path.with_extension("tmp");
+                tokio::fs::write(&tmp, to_lines(kept)?).await?;
+                tokio::fs::rename(&tmp, path).await?;
+            } else if !recorded.is_empty() {
+                let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+                file.write_all(&to_lines(&recorded)?).await?;
+                file.sync_data().await?;
+            }
+        }
+
+        state.records.drain(..expired);
+        state.records.extend(recorded.iter().cloned());
+        state.last_hash = Some(prev_hash);
+        if all {
+            state.last_totals = seen;
+        } else {
+            seen.keys().for_each(|module| {
+                state.last_totals.remove(module);
+            });
+        }
+        Ok(recorded)
+    }
+
+    /// Hourly samples in `[from, to)`, optionally for one module, sorted by
+    /// hour and then module
+    pub async fn query(&self, module: Option<&str>, from: u64, to: u64) -> Vec<StatsSample> {
+        let state = self.state.lock().await;
+        let mut hours: BTreeMap<(u64, String), StatsSample> = BTreeMap::new();
+        for record in in_range(&state.records, from, to) {
+            let sample = &record.sample;
+            if module.is_some_and(|m| m != sample.module) {
+                continue;
+            }
+            let merged = hours
+                .entry((sample.hour, sample.module.clone()))
+                .or_insert_with(|| StatsSample { module: sample.module.clone(), hour: sample.hour, ..Default::default() });
+            merged.invocations += sample.invocations;
+            merged.errors += sample.errors;
+            merged.fuel_consumed += sample.fuel_consumed;
+            merged.peak_memory_bytes = merged.peak_memory_bytes.max(sample.peak_memory_bytes);
+        }
+        hours.into_values().collect()
+    }
+
+    /// Every record whose hour is in `[from, to)`, with a digest for verification
+    pub async fn export(&self, from: u64, to: u64) -> StatsExport {
+        let state = self.state.lock().await;
+        let records: Vec<StatsRecord> = in_range(&state.records, from, to).cloned().collect();
+        StatsExport { from, to, digest: StatsExport::digest(&records), records }
+    }
+
+    /// Number of records held
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.records.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.state.lock().await.records.is_empty()
+    }
+}
+
+fn in_range(records: &[StatsRecord], from: u64, to: u64) -> impl Iterator<Item = &StatsRecord> {
+    records.iter().filter(move |r| (from..to).contains(&r.sample.hour))
+}
+
+fn to_lines<'a>(records: impl IntoIterator<Item = &'a StatsRecord>) -> Result<Vec<u8>> {
+    let mut lines = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut lines, record)?;
+        lines.push(b'\n');
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(invocations: u64, errors: u64, fuel_consumed: u64) -> ModuleStats {
+        ModuleStats {
+            invocation_count: invocations,
+            error_count: errors,
+            fuel_consumed,
+            peak_memory_bytes: 65_536,
+            ..Default::default()
+        }
+    }
+
+    const DAY_MS: u64 = 24 * HOUR_MS;
+
+    #[tokio::test]
+    async fn test_snapshots_record_deltas_per_hour() {
+        let history = StatsHistory::new(DEFAULT_RETENTION);
+        let t0 = 100 * DAY_MS;
+
+        let first = history
+            .record(&[("accrual".into(), stats(10, 1, 500)), ("idle".into(), stats(0, 0, 0))], t0 + 60_000)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        history.record(&[("accrual".into(), stats(15, 1, 800))], t0 + 120_000).await.unwrap();
+        // Relaunched: totals started over
+        history.record(&[("accrual".into(), stats(4, 2, 100))], t0 + HOUR_MS).await.unwrap();
+
+        let samples = history.query(None, t0, t0 + 2 * HOUR_MS).await;
+        assert_eq!(
+            samples.iter().map(|s| (s.hour, s.invocations, s.errors, s.fuel_consumed)).collect::<Vec<_>>(),
+            vec![(t0, 15, 1, 800), (t0 + HOUR_MS, 4, 2, 100)]
+        );
+        assert!(history.query(Some("idle"), 0, u64::MAX).await.is_empty());
+        assert_eq!(history.query(Some("accrual"), t0 + HOUR_MS, u64::MAX).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_history_survives_restart_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let retention = Duration::from_millis(7 * DAY_MS);
+        let t0 = 100 * DAY_MS;
+
+        let history = StatsHistory::with_file(&path, retention).unwrap();
+        history.record(&[("accrual".into(), stats(3, 0, 30))], t0).await.unwrap();
+        history.record(&[("accrual".into(), stats(5, 0, 50))], t0 + DAY_MS).await.unwrap();
+        drop(history);
+
+        // A new process starts from zero totals and appends to the same chain
+        let history = StatsHistory::with_file(&path, retention).unwrap();
+        assert_eq!(history.len().await, 2);
+        history.record(&[("accrual".into(), stats(1, 1, 10))], t0 + 2 * DAY_MS).await.unwrap();
+        let export = history.export(0, u64::MAX).await;
+        assert_eq!(export.records.iter().map(|r| r.sample.invocations).collect::<Vec<_>>(), vec![3, 2, 1]);
+        export.verify().unwrap();
+
+        // Past the retention period the oldest record goes, and the rest still load
+        history.record(&[("accrual".into(), stats(2, 1, 20))], t0 + 7 * DAY_MS + HOUR_MS).await.unwrap();
+        assert_eq!(history.len().await, 3);
+        let reloaded = StatsHistory::with_file(&path, retention).unwrap();
+        assert_eq!(reloaded.export(0, u64::MAX).await, history.export(0, u64::MAX).await);
+    }
+
+    #[tokio::test]
+    async fn test_tampering_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let history = StatsHistory::with_file(&path, DEFAULT_RETENTION).unwrap();
+        history
+            .record(&[("a".into(), stats(1, 0, 10)), ("b".into(), stats(2, 0, 20))], 5 * HOUR_MS)
+            .await
+            .unwrap();
+
+        let mut export = history.export(0, u64::MAX).await;
+        export.verify().unwrap();
+        export.records[1].sample.fuel_consumed = 1;
+        assert!(export.verify().is_err());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace("\"fuel_consumed\":20", "\"fuel_consumed\":2")).unwrap();
+        let err = StatsHistory::with_file(&path, DEFAULT_RETENTION).err().unwrap();
+        assert!(matches!(err.downcast_ref(), Some(StorageError::Corrupt(_))));
+    }
+}
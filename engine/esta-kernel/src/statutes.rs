@@ -0,0 +1,481 @@
+//! Statutory Parameters
+//!
+//! The figures fixed by law (the accrual rate, the annual usage an employer
+//! must allow, the waiting period for new employees) are data, not code. Each
+//! jurisdiction has a statute file listing every version of its parameters
+//! with the date each took effect, so an amendment is shipped as a new file
+//! version rather than a new release, and calculations for a past date still
+//! use the law in force on that date.
+//!
+//! How the figures apply can differ between jurisdictions (Michigan tiers
+//! usage by employer size, Minnesota does not), so each supported
+//! jurisdiction also has [`StatuteRules`]. A tenant policy may name its
+//! jurisdiction; otherwise the kernel's applies.
+//!
+//! A baseline file for each supported jurisdiction is bundled with the kernel.
+//! Rule packs may ship newer files in a `statutes/` directory; the kernel
+//! verifies them like modules (see [`crate::Kernel::load_statutes`]) before
+//! they replace the bundled version. A file may never replace one with a
+//! higher latest version, so an old file cannot roll back an amendment.
+//!
+//! Files are strictly validated: unknown fields are rejected, and versions
+//! must be numbered and dated in increasing order.
+
+use crate::calendar::Date;
+use crate::tenant::TenantPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Statute file format understood by this kernel
+pub const STATUTE_SCHEMA_VERSION: u32 = 1;
+
+/// Jurisdiction used when none is configured
+pub const DEFAULT_JURISDICTION: &str = "US-MI";
+
+/// Statute files bundled with the kernel
+const BUNDLED: &[(&str, &str)] = &[
+    ("US-MI.json", include_str!("../statutes/US-MI.json")),
+    ("US-MN.json", include_str!("../statutes/US-MN.json")),
+];
+
+/// Errors loading or querying statute files
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StatuteError {
+    #[error("Statute file {file} is malformed: {reason}")]
+    Malformed { file: String, reason: String },
+
+    #[error("Statute file {file} is invalid: {reason}")]
+    Invalid { file: String, reason: String },
+
+    #[error("Statute file {file} (version {version}) is older than the loaded {jurisdiction} statute (version {loaded})")]
+    Outdated { file: String, jurisdiction: String, version: u32, loaded: u32 },
+
+    #[error("No statute loaded for jurisdiction {0}")]
+    UnknownJurisdiction(String),
+
+    #[error("No {jurisdiction} statute is in effect on {date}")]
+    NotInEffect { jurisdiction: String, date: Date },
+}
+
+/// Values fixed by statute for one version of the law
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatutoryParameters {
+    /// Hours worked per hour of sick time accrued (30 for a 1:30 rate)
+    pub accrual_hours_worked: u32,
+    /// Largest workforce that counts as a small employer
+    pub small_employer_max_employees: u32,
+    /// Annual usage a small employer must allow (hours)
+    pub small_employer_usage_hours: u32,
+    /// Annual usage a large employer must allow (hours)
+    pub large_employer_usage_hours: u32,
+    /// Days after hire an employer may make a new employee wait before use
+    pub waiting_period_days: u32,
+}
+
+/// Rules of one jurisdiction's law that are code rather than data
+///
+/// Statute files carry the figures; a jurisdiction's rules say how they
+/// apply, e.g. whether usage depends on employer size. Only jurisdictions
+/// with rules (see [`rules_for`]) can be named in a tenant policy.
+pub trait StatuteRules: Send + Sync {
+    /// Jurisdiction code, e.g. `US-MI`
+    fn jurisdiction(&self) -> &'static str;
+
+    /// Annual usage the statute requires for an employer size
+    fn usage_hours(&self, params: &StatutoryParameters, employer_size: &str) -> Option<u32>;
+
+    /// Carryover the statute requires for an employer size
+    fn carryover_hours(&self, params: &StatutoryParameters, employer_size: &str) -> Option<u32> {
+        self.usage_hours(params, employer_size)
+    }
+
+    /// Check that a tenant policy is at least as generous as the statute
+    ///
+    /// The policy's rate is compared as "one hour per N hours worked" with N
+    /// rounded, so a rate written as 0.0333 satisfies a 1:30 statute.
+    fn check_policy(&self, params: &StatutoryParameters, policy: &TenantPolicy) -> Result<(), String> {
+        let hours_worked = (1.0 / policy.accrual_rate).round();
+        if hours_worked > f64::from(params.accrual_hours_worked) {
+            return Err(format!(
+                "accrual_rate {} is below the statutory 1 hour per {} hours worked",
+                policy.accrual_rate, params.accrual_hours_worked
+            ));
+        }
+
+        if let Some(required) = self.usage_hours(params, &policy.employer_size) {
+            if policy.max_usage_hours < required {
+                return Err(format!(
+                    "max_usage_hours {} is below the statutory {} hours for {} employers",
+                    policy.max_usage_hours, required, policy.employer_size
+                ));
+            }
+        }
+        if let Some(required) = self.carryover_hours(params, &policy.employer_size) {
+            if policy.max_carryover_hours < required {
+                return Err(format!(
+                    "max_carryover_hours {} is below the statutory {} hours for {} employers",
+                    policy.max_carryover_hours, required, policy.employer_size
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parameters handed to guest modules as `employer_policy.statutory`
+    ///
+    /// Usage is given per employer size as these rules apply it, so guests
+    /// need no jurisdiction-specific code.
+    fn guest_parameters(&self, statute: &StatuteVersion) -> serde_json::Value {
+        let params = &statute.parameters;
+        serde_json::json!({
+            "jurisdiction": self.jurisdiction(),
+            "version": statute.version,
+            "accrual_hours_worked": params.accrual_hours_worked,
+            "small_employer_usage_hours": self.usage_hours(params, "small"),
+            "large_employer_usage_hours": self.usage_hours(params, "large"),
+            "waiting_period_days": params.waiting_period_days
+        })
+    }
+}
+
+/// Michigan Earned Sick Time Act: usage tiered by employer size
+pub struct MichiganEsta;
+
+impl StatuteRules for MichiganEsta {
+    fn jurisdiction(&self) -> &'static str {
+        "US-MI"
+    }
+
+    fn usage_hours(&self, params: &StatutoryParameters, employer_size: &str) -> Option<u32> {
+        match employer_size {
+            "small" => Some(params.small_employer_usage_hours),
+            "large" => Some(params.large_employer_usage_hours),
+            _ => None,
+        }
+    }
+}
+
+/// Minnesota Earned Sick and Safe Time: one usage figure for every employer
+///
+/// Employers may cap an employee's balance at 80 hours, so that much must
+/// carry over between years (Minn. Stat. 181.9446).
+pub struct MinnesotaEsst;
+
+/// Balance a Minnesota employer must let carry over (hours)
+const MINNESOTA_CARRYOVER_HOURS: u32 = 80;
+
+impl StatuteRules for MinnesotaEsst {
+    fn jurisdiction(&self) -> &'static str {
+        "US-MN"
+    }
+
+    fn usage_hours(&self, params: &StatutoryParameters, _employer_size: &str) -> Option<u32> {
+        Some(params.large_employer_usage_hours)
+    }
+
+    fn carryover_hours(&self, _params: &StatutoryParameters, _employer_size: &str) -> Option<u32> {
+        Some(MINNESOTA_CARRYOVER_HOURS)
+    }
+}
+
+/// Rules for every supported jurisdiction
+const RULES: &[&dyn StatuteRules] = &[&MichiganEsta, &MinnesotaEsst];
+
+/// The rules of a jurisdiction, if it is supported
+pub fn rules_for(jurisdiction: &str) -> Option<&'static dyn StatuteRules> {
+    RULES.iter().copied().find(|rules| rules.jurisdiction() == jurisdiction)
+}
+
+/// One version of a jurisdiction's statute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatuteVersion {
+    /// Version number, increasing with each amendment
+    pub version: u32,
+    /// First day this version applies
+    pub effective_from: Date,
+    /// Legal citation for the amendment
+    #[serde(default)]
+    pub citation: Option<String>,
+    pub parameters: StatutoryParameters,
+}
+
+/// Every version of one jurisdiction's statute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatuteFile {
+    pub schema_version: u32,
+    /// Jurisdiction code, e.g. `US-MI`
+    pub jurisdiction: String,
+    /// Human-readable name of the law
+    pub name: String,
+    /// Versions in increasing order
+    pub versions: Vec<StatuteVersion>,
+}
+
+impl StatuteFile {
+    /// Parse and validate a statute file
+    ///
+    /// `file` names the source in errors; if it is a `<jurisdiction>.json`
+    /// file name, it must match the jurisdiction inside.
+    pub fn parse(file: &str, bytes: &[u8]) -> Result<Self, StatuteError> {
+        let statute: StatuteFile = serde_json::from_slice(bytes).map_err(|e| StatuteError::Malformed {
+            file: file.to_string(),
+            reason: e.to_string(),
+        })?;
+        statute.validate(file)?;
+        Ok(statute)
+    }
+
+    fn validate(&self, file: &str) -> Result<(), StatuteError> {
+        let invalid = |reason: String| StatuteError::Invalid { file: file.to_string(), reason };
+
+        if self.schema_version != STATUTE_SCHEMA_VERSION {
+            return Err(invalid(format!(
+                "schema_version {} is not supported (expected {})",
+                self.schema_version, STATUTE_SCHEMA_VERSION
+            )));
+        }
+        let valid_code = !self.jurisdiction.is_empty()
+            && self.jurisdiction.len() <= 16
+            && self.jurisdiction.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-');
+        if !valid_code {
+            return Err(invalid(format!("invalid jurisdiction code {:?}", self.jurisdiction)));
+        }
+        if let Some(stem) = std::path::Path::new(file).file_stem().and_then(|s| s.to_str()) {
+            if file.ends_with(".json") && stem != self.jurisdiction {
+                return Err(invalid(format!("file is named {} but holds {}", stem, self.jurisdiction)));
+            }
+        }
+        if self.versions.is_empty() {
+            return Err(invalid("no versions".into()));
+        }
+
+        for (i, version) in self.versions.iter().enumerate() {
+            if version.parameters.accrual_hours_worked == 0 {
+                return Err(invalid(format!("version {}: accrual_hours_worked must be positive", version.version)));
+            }
+            if let Some(previous) = i.checked_sub(1).map(|p| &self.versions[p]) {
+                if version.version <= previous.version {
+                    return Err(invalid(format!("version {} follows version {}", version.version, previous.version)));
+                }
+                if version.effective_from <= previous.effective_from {
+                    return Err(invalid(format!(
+                        "version {} takes effect {}, not after version {} ({})",
+                        version.version, version.effective_from, previous.version, previous.effective_from
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The latest version number
+    pub fn latest_version(&self) -> u32 {
+        self.versions.last().map_or(0, |v| v.version)
+    }
+
+    /// The version in effect on a date
+    pub fn version_at(&self, date: Date) -> Option<&StatuteVersion> {
+        self.versions.iter().rev().find(|v| v.effective_from <= date)
+    }
+}
+
+/// Statute files for every known jurisdiction
+#[derive(Debug, Clone, Default)]
+pub struct StatuteBook {
+    statutes: BTreeMap<String, StatuteFile>,
+}
+
+impl StatuteBook {
+    /// The statute files bundled with the kernel
+    pub fn bundled() -> Self {
+        let mut book = Self::default();
+        for (file, contents) in BUNDLED {
+            let statute = StatuteFile::parse(file, contents.as_bytes()).expect("bundled statute file is valid");
+            book.statutes.insert(statute.jurisdiction.clone(), statute);
+        }
+        book
+    }
+
+    /// Add a statute file, replacing the jurisdiction's current file
+    ///
+    /// A file whose latest version is lower than the current file's is
+    /// rejected. `file` names the source in errors.
+    pub fn insert(&mut self, file: &str, statute: StatuteFile) -> Result<(), StatuteError> {
+        if let Some(loaded) = self.statutes.get(&statute.jurisdiction) {
+            if statute.latest_version() < loaded.latest_version() {
+                return Err(StatuteError::Outdated {
+                    file: file.to_string(),
+                    version: statute.latest_version(),
+                    jurisdiction: statute.jurisdiction,
+                    loaded: loaded.latest_version(),
+                });
+            }
+        }
+        self.statutes.insert(statute.jurisdiction.clone(), statute);
+        Ok(())
+    }
+
+    /// The statute file for a jurisdiction
+    pub fn get(&self, jurisdiction: &str) -> Option<&StatuteFile> {
+        self.statutes.get(jurisdiction)
+    }
+
+    /// Jurisdictions with a loaded statute (sorted)
+    pub fn jurisdictions(&self) -> Vec<String> {
+        self.statutes.keys().cloned().collect()
+    }
+
+    /// The statute version in effect in a jurisdiction on a date
+    pub fn version_at(&self, jurisdiction: &str, date: Date) -> Result<&StatuteVersion, StatuteError> {
+        let statute = self
+            .get(jurisdiction)
+            .ok_or_else(|| StatuteError::UnknownJurisdiction(jurisdiction.to_string()))?;
+        statute.version_at(date).ok_or_else(|| StatuteError::NotInEffect {
+            jurisdiction: jurisdiction.to_string(),
+            date,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amended() -> String {
+        serde_json::json!({
+            "schema_version": 1,
+            "jurisdiction": "US-MI",
+            "name": "Michigan Earned Sick Time Act",
+            "versions": [
+                {
+                    "version": 1,
+                    "effective_from": "2025-02-21",
+                    "parameters": {
+                        "accrual_hours_worked": 30,
+                        "small_employer_max_employees": 10,
+                        "small_employer_usage_hours": 40,
+                        "large_employer_usage_hours": 72,
+                        "waiting_period_days": 90
+                    }
+                },
+                {
+                    "version": 2,
+                    "effective_from": "2027-01-01",
+                    "citation": "hypothetical amendment",
+                    "parameters": {
+                        "accrual_hours_worked": 25,
+                        "small_employer_max_employees": 10,
+                        "small_employer_usage_hours": 48,
+                        "large_employer_usage_hours": 80,
+                        "waiting_period_days": 60
+                    }
+                }
+            ]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_bundled_statute_and_effective_dates() {
+        let mut book = StatuteBook::bundled();
+        let current = book.version_at(DEFAULT_JURISDICTION, Date::new(2026, 6, 1).unwrap()).unwrap();
+        assert_eq!(current.parameters.accrual_hours_worked, 30);
+        assert_eq!(MichiganEsta.usage_hours(&current.parameters, "large"), Some(72));
+        assert!(matches!(
+            book.version_at(DEFAULT_JURISDICTION, Date::new(2024, 6, 1).unwrap()),
+            Err(StatuteError::NotInEffect { .. })
+        ));
+        assert!(matches!(book.version_at("US-XX", Date::today()), Err(StatuteError::UnknownJurisdiction(_))));
+
+        let statute = StatuteFile::parse("US-MI.json", amended().as_bytes()).unwrap();
+        book.insert("US-MI.json", statute).unwrap();
+        let before = book.version_at("US-MI", Date::new(2026, 12, 31).unwrap()).unwrap();
+        let after = book.version_at("US-MI", Date::new(2027, 1, 1).unwrap()).unwrap();
+        assert_eq!((before.version, after.version), (1, 2));
+        assert_eq!(after.parameters.accrual_hours_worked, 25);
+
+        // The bundled baseline cannot roll back the amendment
+        let bundled = StatuteFile::parse("US-MI.json", BUNDLED[0].1.as_bytes()).unwrap();
+        assert!(matches!(book.insert("US-MI.json", bundled), Err(StatuteError::Outdated { loaded: 2, .. })));
+    }
+
+    #[test]
+    fn test_statute_files_are_validated() {
+        let mut unknown_field: serde_json::Value = serde_json::from_str(&amended()).unwrap();
+        unknown_field["versions"][0]["parameters"]["accrual_rate"] = serde_json::json!(0.0333);
+        let err = StatuteFile::parse("US-MI.json", unknown_field.to_string().as_bytes()).unwrap_err();
+        assert!(matches!(err, StatuteError::Malformed { .. }));
+
+        let mut out_of_order: serde_json::Value = serde_json::from_str(&amended()).unwrap();
+        out_of_order["versions"][1]["effective_from"] = serde_json::json!("2024-01-01");
+        let err = StatuteFile::parse("US-MI.json", out_of_order.to_string().as_bytes()).unwrap_err();
+        assert!(matches!(err, StatuteError::Invalid { .. }));
+
+        let err = StatuteFile::parse("US-OH.json", amended().as_bytes()).unwrap_err();
+        assert!(matches!(err, StatuteError::Invalid { .. }));
+    }
+
+    #[test]
+    fn test_policy_checked_against_statute() {
+        let params = StatuteBook::bundled()
+            .version_at(DEFAULT_JURISDICTION, Date::new(2026, 1, 1).unwrap())
+            .unwrap()
+            .parameters
+            .clone();
+        let rules = rules_for(DEFAULT_JURISDICTION).unwrap();
+        let mut policy = TenantPolicy {
+            employer_size: "large".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 72,
+            max_usage_hours: 72,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        };
+        assert!(rules.check_policy(&params, &policy).is_ok());
+
+        policy.max_usage_hours = 40;
+        assert!(rules.check_policy(&params, &policy).unwrap_err().contains("max_usage_hours"));
+
+        policy.max_usage_hours = 72;
+        policy.accrual_rate = 1.0 / 40.0;
+        assert!(rules.check_policy(&params, &policy).unwrap_err().contains("accrual_rate"));
+    }
+
+    #[test]
+    fn test_rules_differ_by_jurisdiction() {
+        let book = StatuteBook::bundled();
+        assert_eq!(book.jurisdictions(), vec!["US-MI".to_string(), "US-MN".to_string()]);
+        assert!(rules_for("US-XX").is_none());
+
+        let date = Date::new(2026, 1, 1).unwrap();
+        let minnesota = book.version_at("US-MN", date).unwrap();
+        let rules = rules_for("US-MN").unwrap();
+        assert_eq!(rules.usage_hours(&minnesota.parameters, "small"), Some(48));
+        assert_eq!(rules.usage_hours(&minnesota.parameters, "large"), Some(48));
+        assert_eq!(rules.guest_parameters(minnesota)["small_employer_usage_hours"], 48);
+
+        // A small employer's Michigan minimum is short of Minnesota's
+        let policy = TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: Some("US-MN".into()),
+            retention_days: None,
+        };
+        let michigan = book.version_at("US-MI", date).unwrap();
+        assert!(MichiganEsta.check_policy(&michigan.parameters, &policy).is_ok());
+        assert!(rules.check_policy(&minnesota.parameters, &policy).unwrap_err().contains("max_usage_hours"));
+
+        let policy = TenantPolicy { max_usage_hours: 48, ..policy };
+        assert!(rules.check_policy(&minnesota.parameters, &policy).unwrap_err().contains("max_carryover_hours"));
+        let policy = TenantPolicy { max_carryover_hours: 80, ..policy };
+        assert!(rules.check_policy(&minnesota.parameters, &policy).is_ok());
+    }
+}
@@ -0,0 +1,513 @@
+//! Storage Maintenance
+//!
+//! Reports how much disk the kernel's data uses and reclaims what is safe to
+//! remove:
+//!
+//! - temp files left behind by writes a crash interrupted
+//! - stored module versions beyond the catalog's retention limit
+//! - archived invocation blobs older than an opt-in retention period
+//!
+//! The ledger and policy files are the compliance record itself; they are
+//! measured but never compacted. When the total size passes a configured limit
+//! the usage report says so and a `StorageLimitExceeded` event is written to
+//! the audit log.
+//!
+//! `StorageMaintenance::schedule` runs a vacuum and a usage check periodically.
+
+use crate::archive::InvocationArchive;
+use crate::catalog::ModuleCatalog;
+use crate::error::StorageError;
+use crate::security::AuditLog;
+use crate::clock::now_millis;
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Temp files younger than this may belong to a write still in progress
+const TEMP_FILE_GRACE: Duration = Duration::from_secs(10 * 60);
+
+/// Size alert and retention settings
+#[derive(Debug, Clone, Default)]
+pub struct StorageLimits {
+    /// Total on-disk size above which usage reports raise an alert
+    pub alert_bytes: Option<u64>,
+    /// Remove archived invocations older than this
+    ///
+    /// Archived inputs are what makes old calculations replayable, so leave
+    /// this unset or at least as long as the employer's record retention
+    /// period (three years under ESTA).
+    pub archive_retention: Option<Duration>,
+}
+
+/// A kind of data the kernel keeps on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageArea {
+    /// The accrual ledger file
+    Ledger,
+    /// The tenant policy file
+    Policies,
+    /// The invocation archive's content store
+    Archive,
+    /// The module catalog directory, including its version store
+    Modules,
+    /// Precompiled modules
+    ModuleCache,
+    /// Hourly module statistics
+    StatsHistory,
+}
+
+/// Disk usage of one storage area
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub area: StorageArea,
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Disk usage across every configured storage area
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUsageReport {
+    /// When the report was generated (ms since Unix epoch)
+    pub generated_at: u64,
+    pub areas: Vec<StorageUsage>,
+    pub total_bytes: u64,
+    /// The configured alert threshold, if any
+    pub limit_bytes: Option<u64>,
+}
+
+impl StorageUsageReport {
+    /// Whether the total size is above the alert threshold
+    pub fn over_limit(&self) -> bool {
+        self.limit_bytes.is_some_and(|limit| self.total_bytes > limit)
+    }
+}
+
+/// What a vacuum removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VacuumReport {
+    pub temp_files_removed: usize,
+    pub archived_blobs_removed: usize,
+    /// Stored module versions removed, as `name@version`
+    pub versions_pruned: Vec<String>,
+    pub bytes_reclaimed: u64,
+}
+
+/// Usage reporting and space reclamation for a kernel's on-disk data
+///
+/// Obtained from `Kernel::storage`; cheap to clone.
+#[derive(Clone)]
+pub struct StorageMaintenance {
+    audit_log: Arc<AuditLog>,
+    limits: StorageLimits,
+    ledger_file: Option<PathBuf>,
+    policy_file: Option<PathBuf>,
+    archive: Option<Arc<InvocationArchive>>,
+    catalog: Option<Arc<ModuleCatalog>>,
+    module_cache_dir: Option<PathBuf>,
+    stats_file: Option<PathBuf>,
+    read_only: bool,
+}
+
+impl StorageMaintenance {
+    /// Create maintenance for no storage areas; add them with the `with_*` methods
+    pub fn new(audit_log: Arc<AuditLog>, limits: StorageLimits) -> Self {
+        Self {
+            audit_log,
+            limits,
+            ledger_file: None,
+            policy_file: None,
+            archive: None,
+            catalog: None,
+            module_cache_dir: None,
+            stats_file: None,
+            read_only: false,
+        }
+    }
+
+    pub fn with_ledger_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ledger_file = Some(path.into());
+        self
+    }
+
+    pub fn with_policy_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.policy_file = Some(path.into());
+        self
+    }
+
+    pub fn with_archive(mut self, archive: Arc<InvocationArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    pub fn with_catalog(mut self, catalog: Arc<ModuleCatalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    pub fn with_module_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.module_cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn with_stats_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stats_file = Some(path.into());
+        self
+    }
+
+    /// Report usage only; `vacuum` fails (for read-only snapshots)
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Measure every configured storage area
+    ///
+    /// When the total is over the alert threshold, a `StorageLimitExceeded`
+    /// event is written to the audit log.
+    pub async fn usage_report(&self) -> Result<StorageUsageReport> {
+        let mut areas = Vec::new();
+        if let Some(path) = &self.ledger_file {
+            areas.push(measure(StorageArea::Ledger, path)?);
+        }
+        if let Some(path) = &self.policy_file {
+            areas.push(measure(StorageArea::Policies, path)?);
+        }
+        if let Some(archive) = &self.archive {
+            areas.push(measure(StorageArea::Archive, archive.store().root())?);
+        }
+        if let Some(catalog) = &self.catalog {
+            areas.push(measure(StorageArea::Modules, catalog.dir())?);
+        }
+        if let Some(dir) = &self.module_cache_dir {
+            areas.push(measure(StorageArea::ModuleCache, dir)?);
+        }
+        if let Some(path) = &self.stats_file {
+            areas.push(measure(StorageArea::StatsHistory, path)?);
+        }
+
+        let report = StorageUsageReport {
+            generated_at: now_millis(),
+            total_bytes: areas.iter().map(|a| a.bytes).sum(),
+            areas,
+            limit_bytes: self.limits.alert_bytes,
+        };
+
+        if let (true, Some(limit)) = (report.over_limit(), report.limit_bytes) {
+            warn!("Storage uses {} bytes, over the {} byte limit", report.total_bytes, limit);
+            self.audit_log
+                .log_storage_limit_exceeded(report.total_bytes, limit, "storage")
+                .await;
+        }
+        Ok(report)
+    }
+
+    /// Reclaim space: stale temp files, excess module versions, and archived
+    /// invocations past the retention period
+    ///
+    /// The vacuum is recorded in the audit log.
+    pub async fn vacuum(&self) -> Result<VacuumReport> {
+        if self.read_only {
+            return Err(StorageError::ReadOnly("Storage".to_string()).into());
+        }
+
+        let mut report = VacuumReport::default();
+
+        if let Some(path) = &self.policy_file {
+            remove_stale_temp_files(&[path.with_extension("tmp")], &mut report)?;
+        }
+        if let Some(path) = &self.stats_file {
+            remove_stale_temp_files(&[path.with_extension("tmp")], &mut report)?;
+        }
+
+        if let Some(archive) = &self.archive {
+            let root = archive.store().root();
+            let mut temp_files = Vec::new();
+            let mut blobs = Vec::new();
+            for path in files_under(root)? {
+                if path.extension().is_some_and(|ext| ext == "tmp") {
+                    temp_files.push(path);
+                } else {
+                    blobs.push(path);
+                }
+            }
+            remove_stale_temp_files(&temp_files, &mut report)?;
+
+            if let Some(retention) = self.limits.archive_retention {
+                for blob in blobs {
+                    if older_than(&blob, retention)? {
+                        report.bytes_reclaimed += remove_file(&blob)?;
+                        report.archived_blobs_removed += 1;
+                    }
+                }
+            }
+            remove_empty_dirs(root)?;
+        }
+
+        if let Some(dir) = &self.module_cache_dir {
+            remove_stale_temp_files(&temp_files_in(dir)?, &mut report)?;
+        }
+
+        if let Some(catalog) = &self.catalog {
+            remove_stale_temp_files(&temp_files_in(catalog.dir())?, &mut report)?;
+
+            let before = directory_usage(catalog.dir())?.0;
+            for name in catalog.stored_modules()? {
+                for label in catalog.prune_versions(&name).await? {
+                    report.versions_pruned.push(format!("{}@{}", name, label));
+                }
+            }
+            report.bytes_reclaimed += before.saturating_sub(directory_usage(catalog.dir())?.0);
+        }
+
+        info!(
+            "Storage vacuum removed {} temp files, {} archived blobs, {} module versions ({} bytes)",
+            report.temp_files_removed,
+            report.archived_blobs_removed,
+            report.versions_pruned.len(),
+            report.bytes_reclaimed
+        );
+        self.audit_log
+            .log_storage_vacuumed(
+                report.temp_files_removed,
+                report.archived_blobs_removed,
+                report.versions_pruned.len(),
+                report.bytes_reclaimed,
+                "storage",
+            )
+            .await;
+        Ok(report)
+    }
+
+    /// Vacuum (unless read-only) and check usage now and then every `interval`
+    ///
+    /// Failures are logged and retried on the next run. Abort the returned
+    /// handle to stop.
+    pub fn schedule(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if !self.read_only {
+                    if let Err(e) = self.vacuum().await {
+                        warn!("Scheduled storage vacuum failed: {}", e);
+                    }
+                }
+                if let Err(e) = self.usage_report().await {
+                    warn!("Scheduled storage usage check failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+fn measure(area: StorageArea, path: &Path) -> Result<StorageUsage> {
+    let (bytes, files) = directory_usage(path)?;
+    Ok(StorageUsage {
+        area,
+        path: path.to_path_buf(),
+        bytes,
+        files,
+    })
+}
+
+/// Total size and file count of a file or directory tree (zero if missing)
+fn directory_usage(path: &Path) -> std::io::Result<(u64, u64)> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok((metadata.len(), 1));
+    }
+
+    let mut total = (0, 0);
+    for entry in std::fs::read_dir(path)? {
+        let (bytes, files) = directory_usage(&entry?.path())?;
+        total.0 += bytes;
+        total.1 += files;
+    }
+    Ok(total)
+}
+
+/// Every regular file in a directory tree (empty if missing)
+fn files_under(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files.extend(files_under(&entry.path())?);
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
+
+/// Temp files directly inside a directory (empty if missing)
+fn temp_files_in(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "tmp") && path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Remove empty subdirectories (e.g. archive shards whose blobs all expired)
+fn remove_empty_dirs(dir: &Path) -> std::io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dirs(&entry.path())?;
+            // Fails harmlessly if the directory is not empty
+            let _ = std::fs::remove_dir(entry.path());
+        }
+    }
+    Ok(())
+}
+
+fn remove_stale_temp_files(paths: &[PathBuf], report: &mut VacuumReport) -> std::io::Result<()> {
+    for path in paths {
+        if path.is_file() && older_than(path, TEMP_FILE_GRACE)? {
+            report.bytes_reclaimed += remove_file(path)?;
+            report.temp_files_removed += 1;
+        }
+    }
+    Ok(())
+}
+
+fn older_than(path: &Path, age: Duration) -> std::io::Result<bool> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(crate::clock::now()
+        .duration_since(modified)
+        .is_ok_and(|elapsed| elapsed > age))
+}
+
+/// Remove a file, returning its size
+fn remove_file(path: &Path) -> std::io::Result<u64> {
+    let bytes = std::fs::metadata(path)?.len();
+    std::fs::remove_file(path)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::ArchiveConfig;
+    use crate::security::audit::{AuditEventType, AuditLogConfig};
+    use std::time::SystemTime;
+
+    /// Backdate a file's modification time
+    fn age(path: &Path, by: Duration) {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - by).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_usage_report_and_size_alert() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = dir.path().join("ledger.jsonl");
+        std::fs::write(&ledger, vec![b'x'; 600]).unwrap();
+        let archive = Arc::new(InvocationArchive::new(ArchiveConfig::default(), dir.path().join("archive")));
+        archive.archive(&[b'i'; 300], &[b'o'; 200]).await.unwrap();
+
+        let audit_log = Arc::new(AuditLog::new(AuditLogConfig::default()));
+        let storage = StorageMaintenance::new(
+            audit_log.clone(),
+            StorageLimits { alert_bytes: Some(1000), ..Default::default() },
+        )
+        .with_ledger_file(&ledger)
+        .with_policy_file(dir.path().join("missing.json"))
+        .with_archive(archive);
+
+        let report = storage.usage_report().await.unwrap();
+        let areas: Vec<_> = report.areas.iter().map(|a| (a.area, a.bytes, a.files)).collect();
+        assert_eq!(
+            areas,
+            vec![
+                (StorageArea::Ledger, 600, 1),
+                (StorageArea::Policies, 0, 0),
+                (StorageArea::Archive, 500, 2),
+            ]
+        );
+        assert_eq!(report.total_bytes, 1100);
+        assert!(report.over_limit());
+
+        let alerts = audit_log.get_all_entries().await;
+        assert!(matches!(
+            alerts.last().unwrap().event,
+            AuditEventType::StorageLimitExceeded { total_bytes: 1100, limit_bytes: 1000 }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_vacuum_removes_stale_temp_files_and_expired_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = Arc::new(InvocationArchive::new(ArchiveConfig::default(), dir.path().join("archive")));
+        let old = archive.archive(b"old input", b"old output").await.unwrap();
+        let recent = archive.archive(b"new input", b"new output").await.unwrap();
+        let blob = |hash: &str| dir.path().join("archive").join(&hash[..2]).join(hash);
+        age(&blob(&old.input_hash), Duration::from_secs(40 * 86_400));
+        age(&blob(&old.output_hash), Duration::from_secs(40 * 86_400));
+
+        let stale_tmp = dir.path().join("archive").join("ab").join("abcd.tmp");
+        std::fs::create_dir_all(stale_tmp.parent().unwrap()).unwrap();
+        std::fs::write(&stale_tmp, b"partial").unwrap();
+        age(&stale_tmp, Duration::from_secs(3600));
+        let policies = dir.path().join("policies.json");
+        let fresh_tmp = policies.with_extension("tmp");
+        std::fs::write(&fresh_tmp, b"being written").unwrap();
+
+        let audit_log = Arc::new(AuditLog::new(AuditLogConfig::default()));
+        let limits = StorageLimits {
+            archive_retention: Some(Duration::from_secs(30 * 86_400)),
+            ..Default::default()
+        };
+        let storage = StorageMaintenance::new(audit_log.clone(), limits)
+            .with_policy_file(&policies)
+            .with_archive(archive.clone());
+
+        let report = storage.vacuum().await.unwrap();
+        assert_eq!(report.temp_files_removed, 1);
+        assert_eq!(report.archived_blobs_removed, 2);
+        assert_eq!(report.bytes_reclaimed, 7 + 9 + 10);
+        assert!(!stale_tmp.exists() && !stale_tmp.parent().unwrap().exists());
+        assert!(fresh_tmp.exists(), "a temp file still being written is kept");
+        assert!(archive.load(&old).await.is_err());
+        assert!(archive.load(&recent).await.is_ok());
+        assert!(matches!(
+            audit_log.get_all_entries().await.last().unwrap().event,
+            AuditEventType::StorageVacuumed { temp_files_removed: 1, archived_blobs_removed: 2, .. }
+        ));
+
+        assert!(storage.read_only().vacuum().await.is_err());
+    }
+}
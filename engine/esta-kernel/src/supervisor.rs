@@ -4,19 +4,53 @@
 //! The supervisor monitors running modules and handles:
 //! - Crash detection and restart
 //! - Escalation when restart limits are exceeded
-//! - Exponential backoff between restart attempts
+//! - Exponential or linear backoff between restart attempts, with jitter
+//! - Circuit breaking for crash-looping children
+//! - Hang detection for resident modules that report heartbeats
 //! - Graceful shutdown
 //!
+//! A module that runs its own loop can call `host_heartbeat` periodically.
+//! Children whose spec sets `heartbeat_timeout_ms` are treated as hung, and
+//! handled like a crash, when no heartbeat arrives within that time
+//! (see [`Supervisor::check_heartbeats`] and `Kernel::supervise_heartbeats`).
+//!
+//! Every change of a child's state is broadcast as a [`ChildTransition`] to
+//! [`Supervisor::subscribe`]rs, so status views can follow along instead of
+//! polling [`Supervisor::get_status`].
+//!
+//! With [`Supervisor::with_history_file`], each child's crash history (total
+//! crashes, escalation level, and restart window) is saved after every crash
+//! and restored when the child is registered again, so a module that was
+//! crash-looping does not get a fresh restart budget when the app restarts.
+//! History whose restart window has passed only keeps its crash total.
+//!
+//! Restart delays grow per [`BackoffPolicy`] and can be jittered, so
+//! children that crash together (say, on the same periodic trigger) do not
+//! all restart at the same moment. The random part is drawn from a
+//! [`JitterSource`], the system RNG unless [`Supervisor::with_jitter`] gives
+//! a seeded one; if the RNG fails, the delay is used un-jittered. A child with a [`CircuitBreaker`] that
+//! keeps crashing is left down for a cooldown and then restarted once, as a
+//! canary, instead of being restarted on every crash.
+//!
+//! A pending restart's remaining delay is reported in
+//! [`ChildStatus::restart_in_ms`], so status views can show "restarting in
+//! 12s". The supervisor reads time and waits out delays through a
+//! [`SupervisorTimer`]; the default is tokio's clock, which tests pause and
+//! advance instead of sleeping through backoff.
+//!
 //! Reference: docs/abi/kernel_contract.md
 
 use anyhow::{anyhow, Result};
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock};
-use tokio::time::{sleep, Instant};
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::time::Instant;
 
 /// Restart strategy for supervised modules
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -76,6 +110,20 @@ pub struct ChildSpec {
     pub max_restart_delay_ms: u64,
     /// Backoff multiplier for each restart
     pub backoff_factor: f64,
+    /// Longest a running child may go without a heartbeat (milliseconds);
+    /// heartbeats are not expected when unset
+    #[serde(default)]
+    pub heartbeat_timeout_ms: Option<u64>,
+    /// How the delay grows with each restart in the window
+    #[serde(default)]
+    pub backoff: BackoffPolicy,
+    /// Fraction (0.0-1.0) of each restart delay left to chance: the delay is
+    /// drawn between `1 - jitter` times the backoff delay and the full delay
+    #[serde(default)]
+    pub jitter: f64,
+    /// Leave a crash-looping child down for a cooldown; off when unset
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreaker>,
 }
 
 impl Default for ChildSpec {
@@ -89,12 +137,63 @@ impl Default for ChildSpec {
             base_restart_delay_ms: 1000,
             max_restart_delay_ms: 30000,
             backoff_factor: 2.0,
+            heartbeat_timeout_ms: None,
+            backoff: BackoffPolicy::Exponential,
+            jitter: 0.0,
+            circuit_breaker: None,
         }
     }
 }
 
+/// How the delay before a restart grows with the restarts in the window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackoffPolicy {
+    /// `base_restart_delay_ms * backoff_factor ^ restarts`
+    #[default]
+    Exponential,
+    /// `base_restart_delay_ms * restarts`
+    Linear,
+}
+
+/// Circuit breaker for a crash-looping child
+///
+/// After `failure_threshold` crashes in a row the circuit opens: the child is
+/// not restarted until `cooldown_ms` has passed, and then only once, as a
+/// canary (the circuit is half-open). A canary that crashes opens the circuit
+/// again. Crashes count as in a row unless the child ran for a full cooldown
+/// in between, which also closes the circuit.
+///
+/// Restarts made while the circuit is open do not count against
+/// `max_restarts`, so keep `failure_threshold` at or below it for the
+/// breaker to trip before the child escalates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreaker {
+    pub failure_threshold: u32,
+    pub cooldown_ms: u64,
+}
+
+/// State of a child's circuit breaker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Crashes are restarted with backoff
+    #[default]
+    Closed,
+    /// Waiting out the cooldown before a canary restart
+    Open,
+    /// The canary is running
+    HalfOpen,
+}
+
+/// Transitions buffered per subscriber before the oldest are dropped
+const TRANSITION_BUFFER: usize = 256;
+
 /// State of a supervised child
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Serialized with a `kind` tag, e.g. `{"kind":"restarting","attempt":2}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ChildState {
     /// Child is starting up
     Starting,
@@ -104,12 +203,105 @@ pub enum ChildState {
     Crashed { error: String },
     /// Child is in restart delay
     Restarting { attempt: u32 },
+    /// Child's circuit breaker is open; a canary restart follows the cooldown
+    CircuitOpen { cooldown_ms: u64 },
     /// Child has exceeded restart limits
     Stopped { reason: String },
     /// Child was gracefully shut down
     Terminated,
 }
 
+/// A child's crash history as saved between runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashHistory {
+    pub total_crashes: u64,
+    pub escalation_level: EscalationLevel,
+    /// Restarts in the current window
+    pub restart_count: u32,
+    /// Start of the current restart window (ms since Unix epoch)
+    pub window_start: Option<u64>,
+    /// ms since Unix epoch
+    pub last_crash: Option<u64>,
+}
+
+/// The instant `ms` (since Unix epoch) was, given the time now
+fn instant_at(ms: u64, now_ms: u64, now: Instant) -> Option<Instant> {
+    now.checked_sub(Duration::from_millis(now_ms.saturating_sub(ms)))
+}
+
+/// When `at` was, in ms since Unix epoch
+fn millis_at(at: Instant, now_ms: u64, now: Instant) -> u64 {
+    now_ms.saturating_sub(now.saturating_duration_since(at).as_millis() as u64)
+}
+
+/// Where the supervisor reads time and waits out restart delays
+pub trait SupervisorTimer: Send + Sync {
+    /// The current instant
+    fn now(&self) -> Instant;
+    /// Wait for `delay` to pass
+    fn sleep(&self, delay: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Tokio's clock, so `tokio::time::pause` and `advance` (or a
+/// `TimeMachine`) drive the supervisor in tests
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+impl SupervisorTimer for TokioTimer {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, delay: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(delay))
+    }
+}
+
+/// Where the supervisor draws the random part of jittered restart delays
+pub trait JitterSource: Send + Sync {
+    /// A fraction between 0 and 1
+    fn fraction(&self) -> Result<f64>;
+}
+
+/// The system RNG
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemJitter;
+
+impl JitterSource for SystemJitter {
+    fn fraction(&self) -> Result<f64> {
+        let sample: [u8; 4] = ring::rand::generate(&ring::rand::SystemRandom::new())
+            .map_err(|_| anyhow!("system RNG failed"))?
+            .expose();
+        Ok(u32::from_le_bytes(sample) as f64 / u32::MAX as f64)
+    }
+}
+
+/// Fractions from a sequence fixed by its seed, so tests see the same
+/// jittered delays on every run
+#[derive(Debug)]
+pub struct SeededJitter {
+    /// SplitMix64 state
+    state: std::sync::Mutex<u64>,
+}
+
+impl SeededJitter {
+    pub fn new(seed: u64) -> Self {
+        Self { state: std::sync::Mutex::new(seed) }
+    }
+}
+
+impl JitterSource for SeededJitter {
+    fn fraction(&self) -> Result<f64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Ok((z >> 11) as f64 / (1u64 << 53) as f64)
+    }
+}
+
 /// Information about a supervised child
 #[derive(Debug, Clone)]
 pub struct ChildInfo {
@@ -127,6 +319,15 @@ pub struct ChildInfo {
     pub escalation_level: EscalationLevel,
     /// Total crashes since start
     pub total_crashes: u64,
+    /// Last heartbeat, or when the child started if it has not sent one
+    pub last_heartbeat: Option<Instant>,
+    /// When the child last started running, until it crashes
+    pub started_at: Option<Instant>,
+    /// Crashes in a row, for the circuit breaker
+    pub consecutive_crashes: u32,
+    pub circuit: CircuitState,
+    /// When the scheduled restart is due, while restarting or with the circuit open
+    pub restart_at: Option<Instant>,
 }
 
 impl ChildInfo {
@@ -139,19 +340,62 @@ impl ChildInfo {
             last_crash: None,
             escalation_level: EscalationLevel::Level1RestartWithState,
             total_crashes: 0,
+            last_heartbeat: None,
+            started_at: None,
+            consecutive_crashes: 0,
+            circuit: CircuitState::Closed,
+            restart_at: None,
         }
     }
 
-    /// Calculate the delay before next restart with exponential backoff
-    fn calculate_restart_delay(&self) -> Duration {
+    /// How long the child has gone without a heartbeat, if that exceeds its timeout
+    fn heartbeat_overdue(&self, now: Instant) -> Option<Duration> {
+        let timeout = Duration::from_millis(self.spec.heartbeat_timeout_ms?);
+        let silent = now.duration_since(self.last_heartbeat?);
+        (self.state == ChildState::Running && silent > timeout).then_some(silent)
+    }
+
+    fn status(&self, now: Instant) -> ChildStatus {
+        ChildStatus {
+            id: self.spec.id.clone(),
+            state: self.state.clone(),
+            restart_count: self.restart_count,
+            total_crashes: self.total_crashes,
+            escalation_level: self.escalation_level,
+            heartbeat_age_ms: self.last_heartbeat.map(|at| now.saturating_duration_since(at).as_millis() as u64),
+            circuit: self.spec.circuit_breaker.map(|_| self.circuit),
+            restart_in_ms: self.restart_at.map(|at| at.saturating_duration_since(now).as_millis() as u64),
+        }
+    }
+
+    /// Calculate the delay before next restart with the spec's backoff and jitter
+    fn calculate_restart_delay(&self, source: &dyn JitterSource) -> Duration {
         let base = self.spec.base_restart_delay_ms as f64;
-        let factor = self.spec.backoff_factor;
         let attempt = self.restart_count as f64;
-        
-        let delay_ms = base * factor.powf(attempt);
+
+        let delay_ms = match self.spec.backoff {
+            BackoffPolicy::Exponential => base * self.spec.backoff_factor.powf(attempt),
+            BackoffPolicy::Linear => base * attempt,
+        };
         let delay_ms = delay_ms.min(self.spec.max_restart_delay_ms as f64) as u64;
-        
-        Duration::from_millis(delay_ms)
+
+        self.jittered(Duration::from_millis(delay_ms), source)
+    }
+
+    /// `delay` shortened by a random part of the spec's jitter, or as is if
+    /// no random fraction can be drawn
+    fn jittered(&self, delay: Duration, source: &dyn JitterSource) -> Duration {
+        let jitter = self.spec.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+        match source.fraction() {
+            Ok(fraction) => delay.mul_f64(1.0 - jitter * fraction.clamp(0.0, 1.0)),
+            Err(e) => {
+                warn!("Cannot jitter restart delay of child {}, using {:?}: {}", self.spec.id, delay, e);
+                delay
+            }
+        }
     }
 
     /// Check if restart limit has been exceeded
@@ -180,6 +424,66 @@ impl ChildInfo {
             }
         }
     }
+
+    fn crash_history(&self, now_ms: u64, now: Instant) -> CrashHistory {
+        CrashHistory {
+            total_crashes: self.total_crashes,
+            escalation_level: self.escalation_level,
+            restart_count: self.restart_count,
+            window_start: self.restart_window_start.map(|at| millis_at(at, now_ms, now)),
+            last_crash: self.last_crash.map(|at| millis_at(at, now_ms, now)),
+        }
+    }
+
+    /// Resume from saved history; a restart window that has passed starts over
+    fn restore(&mut self, history: &CrashHistory, now_ms: u64, now: Instant) {
+        self.total_crashes = history.total_crashes;
+        self.last_crash = history.last_crash.and_then(|at| instant_at(at, now_ms, now));
+        let window_ms = self.spec.restart_intensity_window as u64 * 1000;
+        if let Some(start) = history.window_start.filter(|&start| now_ms.saturating_sub(start) <= window_ms) {
+            self.restart_window_start = instant_at(start, now_ms, now);
+            self.restart_count = history.restart_count;
+            self.escalation_level = history.escalation_level;
+        }
+    }
+}
+
+/// JSON file of crash histories by child ID
+struct HistoryFile {
+    path: PathBuf,
+    children: std::sync::Mutex<BTreeMap<String, CrashHistory>>,
+}
+
+impl HistoryFile {
+    /// Load the file, starting empty if it does not exist
+    fn open(path: PathBuf) -> Result<Self> {
+        let children = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| anyhow!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(anyhow!("{}: {}", path.display(), e)),
+        };
+        Ok(Self { path, children: std::sync::Mutex::new(children) })
+    }
+
+    fn get(&self, id: &str) -> Option<CrashHistory> {
+        self.children.lock().unwrap_or_else(|e| e.into_inner()).get(id).cloned()
+    }
+
+    /// Record a child's history and save the file, replacing it atomically
+    async fn save(&self, id: &str, history: CrashHistory) -> Result<()> {
+        let json = {
+            let mut children = self.children.lock().unwrap_or_else(|e| e.into_inner());
+            children.insert(id.to_string(), history);
+            serde_json::to_vec_pretty(&*children)?
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
 }
 
 /// Supervisor event for the event loop
@@ -197,6 +501,21 @@ pub enum SupervisorEvent {
     Shutdown,
 }
 
+/// A change in a child's state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildTransition {
+    pub id: String,
+    /// `None` when the child was just registered
+    pub from: Option<ChildState>,
+    /// `None` when the child was unregistered
+    pub to: Option<ChildState>,
+    /// ms since Unix epoch
+    pub at: u64,
+}
+
+/// Callback invoked to (re)start a child: (child_id, manifest_path, escalation_level)
+type RestartCallback = dyn Fn(&str, &str, EscalationLevel) -> Result<()> + Send + Sync;
+
 /// Supervisor for managing WASM module lifecycles
 pub struct Supervisor {
     /// Children managed by this supervisor
@@ -204,11 +523,20 @@ pub struct Supervisor {
     /// Event sender for supervisor commands
     event_tx: mpsc::Sender<SupervisorEvent>,
     /// Event receiver for supervisor commands
+    #[allow(dead_code)]
     event_rx: Arc<RwLock<mpsc::Receiver<SupervisorEvent>>>,
     /// Whether supervisor is running
+    #[allow(dead_code)]
     running: Arc<RwLock<bool>>,
     /// Callback for module restart (actual kernel integration)
-    restart_callback: Arc<dyn Fn(&str, &str, EscalationLevel) -> Result<()> + Send + Sync>,
+    restart_callback: Arc<RestartCallback>,
+    transitions: broadcast::Sender<ChildTransition>,
+    /// Crash histories saved between runs
+    history: Option<HistoryFile>,
+    timer: Arc<dyn SupervisorTimer>,
+    jitter: Arc<dyn JitterSource>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<crate::chaos::Chaos>>,
 }
 
 impl Supervisor {
@@ -228,14 +556,75 @@ impl Supervisor {
             event_rx: Arc::new(RwLock::new(rx)),
             running: Arc::new(RwLock::new(false)),
             restart_callback: Arc::new(restart_callback),
+            transitions: broadcast::channel(TRANSITION_BUFFER).0,
+            history: None,
+            timer: Arc::new(TokioTimer),
+            jitter: Arc::new(SystemJitter),
+            #[cfg(feature = "chaos")]
+            chaos: None,
         }
     }
 
+    /// Read time and wait out restart delays with `timer` instead of tokio's clock
+    pub fn with_timer(mut self, timer: Arc<dyn SupervisorTimer>) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Draw the random part of jittered restart delays from `source`
+    /// instead of the system RNG
+    pub fn with_jitter(mut self, source: Arc<dyn JitterSource>) -> Self {
+        self.jitter = source;
+        self
+    }
+
+    /// Save crash histories to a JSON file and resume children from it
+    ///
+    /// The file is created after the first crash.
+    pub fn with_history_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        self.history = Some(HistoryFile::open(path.into())?);
+        Ok(self)
+    }
+
+    /// Drop heartbeat reports according to a chaos schedule
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Arc<crate::chaos::Chaos>) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
     /// Create a supervisor with a no-op callback (for testing)
     pub fn new_noop() -> Self {
         Self::new(|_, _, _| Ok(()))
     }
 
+    /// Receive every child state transition from now on
+    ///
+    /// A subscriber that falls more than 256 transitions behind skips the
+    /// oldest (`RecvError::Lagged`) and should refresh with `get_status`.
+    pub fn subscribe(&self) -> broadcast::Receiver<ChildTransition> {
+        self.transitions.subscribe()
+    }
+
+    fn publish(&self, id: &str, from: Option<ChildState>, to: Option<ChildState>) {
+        if from != to {
+            let transition = ChildTransition { id: id.to_string(), from, to, at: crate::clock::now_millis() };
+            // No subscribers is not an error
+            let _ = self.transitions.send(transition);
+        }
+    }
+
+    /// Move a child to `state`, publishing the transition
+    ///
+    /// A scheduled restart is forgotten when the child moves on from waiting for it.
+    fn set_state(&self, child: &mut ChildInfo, state: ChildState) {
+        if !matches!(state, ChildState::Restarting { .. } | ChildState::CircuitOpen { .. }) {
+            child.restart_at = None;
+        }
+        let from = std::mem::replace(&mut child.state, state.clone());
+        self.publish(&child.spec.id, Some(from), Some(state));
+    }
+
     /// Register a child module with the supervisor
     pub async fn register_child(&self, spec: ChildSpec) -> Result<()> {
         let id = spec.id.clone();
@@ -245,7 +634,13 @@ impl Supervisor {
             return Err(anyhow!("Child {} already registered", id));
         }
 
-        children.insert(id.clone(), ChildInfo::new(spec));
+        let mut child = ChildInfo::new(spec);
+        if let Some(history) = self.history.as_ref().and_then(|file| file.get(&id)) {
+            child.restore(&history, crate::clock::now_millis(), self.timer.now());
+            info!("Child {} resumes after {} crash(es) at {:?}", id, child.total_crashes, child.escalation_level);
+        }
+        self.publish(&id, None, Some(child.state.clone()));
+        children.insert(id.clone(), child);
         info!("Registered child: {}", id);
         Ok(())
     }
@@ -253,7 +648,8 @@ impl Supervisor {
     /// Unregister a child module
     pub async fn unregister_child(&self, id: &str) -> Result<()> {
         let mut children = self.children.write().await;
-        children.remove(id).ok_or_else(|| anyhow!("Child {} not found", id))?;
+        let child = children.remove(id).ok_or_else(|| anyhow!("Child {} not found", id))?;
+        self.publish(id, Some(child.state), None);
         info!("Unregistered child: {}", id);
         Ok(())
     }
@@ -267,7 +663,9 @@ impl Supervisor {
     pub async fn report_started(&self, id: &str) -> Result<()> {
         let mut children = self.children.write().await;
         if let Some(child) = children.get_mut(id) {
-            child.state = ChildState::Running;
+            self.set_state(child, ChildState::Running);
+            child.last_heartbeat = Some(self.timer.now());
+            child.started_at = Some(self.timer.now());
             info!("Child {} started", id);
             Ok(())
         } else {
@@ -275,33 +673,89 @@ impl Supervisor {
         }
     }
 
+    /// Record a heartbeat from a child
+    ///
+    /// A heartbeat from a child that is still starting shows it is running.
+    pub async fn report_heartbeat(&self, id: &str) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        if self.chaos.as_ref().is_some_and(|chaos| chaos.inject(crate::chaos::Fault::DroppedHeartbeat, id)) {
+            return Ok(());
+        }
+        let mut children = self.children.write().await;
+        let child = children.get_mut(id).ok_or_else(|| anyhow!("Child {} not found", id))?;
+        if child.state == ChildState::Starting {
+            self.set_state(child, ChildState::Running);
+            child.started_at = Some(self.timer.now());
+        }
+        child.last_heartbeat = Some(self.timer.now());
+        Ok(())
+    }
+
+    /// Treat running children whose heartbeat is overdue as crashed
+    ///
+    /// Returns the action decided for each hung child, as `report_crash` would.
+    pub async fn check_heartbeats(&self) -> Result<Vec<(String, SupervisorAction)>> {
+        let now = self.timer.now();
+        let hung: Vec<(String, Duration)> = self
+            .children
+            .read()
+            .await
+            .iter()
+            .filter_map(|(id, child)| Some((id.clone(), child.heartbeat_overdue(now)?)))
+            .collect();
+
+        let mut actions = Vec::with_capacity(hung.len());
+        for (id, silent) in hung {
+            let error = format!("hung: no heartbeat for {} ms", silent.as_millis());
+            warn!("Child {} {}", id, error);
+            let action = self.report_crash(&id, &error).await?;
+            actions.push((id, action));
+        }
+        Ok(actions)
+    }
+
     /// Report a child as crashed
+    ///
+    /// The child's crash history is saved when a history file is configured;
+    /// failing to save it is logged, not returned.
     pub async fn report_crash(&self, id: &str, error: &str) -> Result<SupervisorAction> {
-        let now = Instant::now();
         let mut children = self.children.write().await;
-        
         let child = children.get_mut(id)
             .ok_or_else(|| anyhow!("Child {} not found", id))?;
+        let action = self.crashed(child, error);
+        if let Some(file) = &self.history {
+            if let Err(e) = file.save(id, child.crash_history(crate::clock::now_millis(), self.timer.now())).await {
+                warn!("Failed to save crash history of {}: {}", id, e);
+            }
+        }
+        Ok(action)
+    }
 
-        child.state = ChildState::Crashed { error: error.to_string() };
+    /// Decide what to do about a crash
+    fn crashed(&self, child: &mut ChildInfo, error: &str) -> SupervisorAction {
+        let now = self.timer.now();
+        let id = child.spec.id.clone();
+        let ran_for = child.started_at.take().map(|at| now.duration_since(at));
+
+        self.set_state(child, ChildState::Crashed { error: error.to_string() });
         child.last_crash = Some(now);
         child.total_crashes += 1;
 
         // Check restart strategy
         match child.spec.restart {
             RestartStrategy::Temporary => {
-                child.state = ChildState::Stopped { 
-                    reason: "Temporary strategy - no restart".into() 
-                };
+                self.set_state(child, ChildState::Stopped {
+                    reason: "Temporary strategy - no restart".into()
+                });
                 warn!("Child {} crashed (temporary, no restart): {}", id, error);
-                return Ok(SupervisorAction::Stop);
+                return SupervisorAction::Stop;
             }
             RestartStrategy::Transient => {
                 // Only restart on abnormal termination
                 if error == "normal" || error == "shutdown" {
-                    child.state = ChildState::Terminated;
+                    self.set_state(child, ChildState::Terminated);
                     info!("Child {} terminated normally", id);
-                    return Ok(SupervisorAction::Stop);
+                    return SupervisorAction::Stop;
                 }
             }
             RestartStrategy::Permanent => {
@@ -317,17 +771,22 @@ impl Supervisor {
             child.restart_window_start = Some(now);
         }
 
+        if let Some(breaker) = child.spec.circuit_breaker {
+            if let Some(action) = self.trip_breaker(child, breaker, ran_for, now) {
+                return action;
+            }
+        }
+
         // Check restart limit
         if child.restart_limit_exceeded(now) {
             // Escalate
             child.escalation_level = child.escalation_level.next();
             
             if child.escalation_level >= EscalationLevel::Level4RestartSupervisor {
-                child.state = ChildState::Stopped { 
-                    reason: format!("Restart limit exceeded, escalated to {:?}", child.escalation_level)
-                };
+                let reason = format!("Restart limit exceeded, escalated to {:?}", child.escalation_level);
+                self.set_state(child, ChildState::Stopped { reason });
                 error!("Child {} exceeded restart limit, escalating to {:?}", id, child.escalation_level);
-                return Ok(SupervisorAction::Escalate(child.escalation_level));
+                return SupervisorAction::Escalate(child.escalation_level);
             }
 
             // Reset count at higher escalation level
@@ -335,30 +794,70 @@ impl Supervisor {
         }
 
         child.restart_count += 1;
-        let delay = child.calculate_restart_delay();
+        let delay = child.calculate_restart_delay(self.jitter.as_ref());
         let escalation = child.escalation_level;
         let manifest_path = child.spec.manifest_path.clone();
 
-        child.state = ChildState::Restarting { attempt: child.restart_count };
+        let attempt = child.restart_count;
+        self.set_state(child, ChildState::Restarting { attempt });
+        child.restart_at = Some(now + delay);
 
         info!(
             "Child {} will restart in {:?} (attempt {}, escalation {:?})",
             id, delay, child.restart_count, escalation
         );
 
-        Ok(SupervisorAction::Restart {
+        SupervisorAction::Restart {
             delay,
             manifest_path,
             escalation,
+        }
+    }
+
+    /// Count a crash against a child's circuit breaker
+    ///
+    /// Returns the canary restart if the circuit opens.
+    fn trip_breaker(
+        &self,
+        child: &mut ChildInfo,
+        breaker: CircuitBreaker,
+        ran_for: Option<Duration>,
+        now: Instant,
+    ) -> Option<SupervisorAction> {
+        let cooldown = Duration::from_millis(breaker.cooldown_ms);
+        // A child that stayed up for a cooldown was healthy; its crashes start over
+        if ran_for.is_some_and(|ran_for| ran_for >= cooldown) {
+            child.consecutive_crashes = 0;
+            child.circuit = CircuitState::Closed;
+        }
+        child.consecutive_crashes += 1;
+        if child.circuit != CircuitState::HalfOpen && child.consecutive_crashes < breaker.failure_threshold.max(1) {
+            return None;
+        }
+
+        child.circuit = CircuitState::Open;
+        self.set_state(child, ChildState::CircuitOpen { cooldown_ms: breaker.cooldown_ms });
+        let delay = child.jittered(cooldown, self.jitter.as_ref());
+        child.restart_at = Some(now + delay);
+        warn!(
+            "Child {} crashed {} times in a row; circuit open, canary restart in {:?}",
+            child.spec.id, child.consecutive_crashes, delay
+        );
+        Some(SupervisorAction::Restart {
+            delay,
+            manifest_path: child.spec.manifest_path.clone(),
+            escalation: child.escalation_level,
         })
     }
 
     /// Execute a restart action for a child
+    ///
+    /// Restarting a child whose circuit is open makes it the canary.
     pub async fn execute_restart(&self, id: &str, action: SupervisorAction) -> Result<()> {
         match action {
             SupervisorAction::Restart { delay, manifest_path, escalation } => {
                 // Wait for the delay
-                sleep(delay).await;
+                self.timer.sleep(delay).await;
 
                 // Execute the restart callback
                 (self.restart_callback)(id, &manifest_path, escalation)?;
@@ -366,7 +865,10 @@ impl Supervisor {
                 // Mark as starting
                 let mut children = self.children.write().await;
                 if let Some(child) = children.get_mut(id) {
-                    child.state = ChildState::Starting;
+                    if child.circuit == CircuitState::Open {
+                        child.circuit = CircuitState::HalfOpen;
+                    }
+                    self.set_state(child, ChildState::Starting);
                 }
 
                 Ok(())
@@ -379,28 +881,19 @@ impl Supervisor {
         }
     }
 
-    /// Get the status of all children
+    /// Get the status of all children, sorted by ID
     pub async fn get_status(&self) -> Vec<ChildStatus> {
         let children = self.children.read().await;
-        children.values().map(|c| ChildStatus {
-            id: c.spec.id.clone(),
-            state: format!("{:?}", c.state),
-            restart_count: c.restart_count,
-            total_crashes: c.total_crashes,
-            escalation_level: c.escalation_level,
-        }).collect()
+        let now = self.timer.now();
+        let mut status: Vec<ChildStatus> = children.values().map(|child| child.status(now)).collect();
+        status.sort_by(|a, b| a.id.cmp(&b.id));
+        status
     }
 
     /// Get the status of a specific child
     pub async fn get_child_status(&self, id: &str) -> Option<ChildStatus> {
         let children = self.children.read().await;
-        children.get(id).map(|c| ChildStatus {
-            id: c.spec.id.clone(),
-            state: format!("{:?}", c.state),
-            restart_count: c.restart_count,
-            total_crashes: c.total_crashes,
-            escalation_level: c.escalation_level,
-        })
+        children.get(id).map(|child| child.status(self.timer.now()))
     }
 
     /// Shutdown all children gracefully
@@ -408,7 +901,7 @@ impl Supervisor {
         let mut children = self.children.write().await;
         for (id, child) in children.iter_mut() {
             info!("Shutting down child: {}", id);
-            child.state = ChildState::Terminated;
+            self.set_state(child, ChildState::Terminated);
         }
     }
 }
@@ -432,10 +925,18 @@ pub enum SupervisorAction {
 #[derive(Debug, Clone, Serialize)]
 pub struct ChildStatus {
     pub id: String,
-    pub state: String,
+    pub state: ChildState,
     pub restart_count: u32,
     pub total_crashes: u64,
     pub escalation_level: EscalationLevel,
+    /// Time since the last heartbeat (or start) while the child has run
+    pub heartbeat_age_ms: Option<u64>,
+    /// Circuit breaker state, for children with a breaker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<CircuitState>,
+    /// Time left until a scheduled restart (or canary restart); 0 once due
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_in_ms: Option<u64>,
 }
 
 #[cfg(test)]
@@ -456,7 +957,7 @@ mod tests {
         supervisor.report_started("test-module").await.unwrap();
 
         let status = supervisor.get_child_status("test-module").await.unwrap();
-        assert!(status.state.contains("Running"));
+        assert_eq!(status.state, ChildState::Running);
     }
 
     #[tokio::test]
@@ -591,6 +1092,262 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_linear_backoff_with_jitter() {
+        let supervisor = Supervisor::new_noop();
+        let spec = ChildSpec {
+            id: "linear".into(),
+            max_restarts: 10,
+            base_restart_delay_ms: 1000,
+            backoff: BackoffPolicy::Linear,
+            jitter: 0.5,
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+
+        for attempt in 1..=3u64 {
+            let SupervisorAction::Restart { delay, .. } = supervisor.report_crash("linear", "trap").await.unwrap() else {
+                panic!("Expected Restart");
+            };
+            let full = Duration::from_millis(1000 * attempt);
+            assert!(delay <= full && delay >= full / 2, "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_jitter_source_leaves_delay_unjittered() {
+        struct Broken;
+
+        impl JitterSource for Broken {
+            fn fraction(&self) -> Result<f64> {
+                Err(anyhow!("no entropy"))
+            }
+        }
+
+        let supervisor = Supervisor::new_noop().with_jitter(Arc::new(Broken));
+        let spec = ChildSpec {
+            id: "steady".into(),
+            base_restart_delay_ms: 1000,
+            backoff: BackoffPolicy::Linear,
+            jitter: 0.5,
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+
+        let SupervisorAction::Restart { delay, .. } = supervisor.report_crash("steady", "trap").await.unwrap() else {
+            panic!("Expected Restart");
+        };
+        assert_eq!(delay, Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_probes_with_canary() {
+        let supervisor = Supervisor::new_noop();
+        let spec = ChildSpec {
+            id: "flapping".into(),
+            max_restarts: 10,
+            base_restart_delay_ms: 1,
+            circuit_breaker: Some(CircuitBreaker { failure_threshold: 3, cooldown_ms: 5_000 }),
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("flapping").await.unwrap();
+
+        for _ in 0..2 {
+            let action = supervisor.report_crash("flapping", "trap").await.unwrap();
+            assert!(matches!(action, SupervisorAction::Restart { delay, .. } if delay < Duration::from_secs(1)));
+        }
+        let status = supervisor.get_child_status("flapping").await.unwrap();
+        assert_eq!(status.circuit, Some(CircuitState::Closed));
+
+        // Third crash in a row opens the circuit for the cooldown
+        let action = supervisor.report_crash("flapping", "trap").await.unwrap();
+        assert!(matches!(action, SupervisorAction::Restart { delay, .. } if delay == Duration::from_secs(5)));
+        let status = supervisor.get_child_status("flapping").await.unwrap();
+        assert_eq!(status.state, ChildState::CircuitOpen { cooldown_ms: 5_000 });
+        assert_eq!(status.circuit, Some(CircuitState::Open));
+        assert_eq!(status.restart_count, 2);
+
+        // The restart after the cooldown is the canary
+        let canary = SupervisorAction::Restart {
+            delay: Duration::ZERO,
+            manifest_path: String::new(),
+            escalation: EscalationLevel::Level1RestartWithState,
+        };
+        supervisor.execute_restart("flapping", canary).await.unwrap();
+        supervisor.report_started("flapping").await.unwrap();
+        let status = supervisor.get_child_status("flapping").await.unwrap();
+        assert_eq!(status.circuit, Some(CircuitState::HalfOpen));
+
+        // A crashing canary opens the circuit again
+        let action = supervisor.report_crash("flapping", "trap").await.unwrap();
+        assert!(matches!(action, SupervisorAction::Restart { delay, .. } if delay == Duration::from_secs(5)));
+        let status = supervisor.get_child_status("flapping").await.unwrap();
+        assert_eq!(status.circuit, Some(CircuitState::Open));
+    }
+
+    #[tokio::test]
+    async fn test_crash_history_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("supervisor.json");
+        let spec = ChildSpec { id: "accrual".into(), max_restarts: 2, base_restart_delay_ms: 1, ..Default::default() };
+        {
+            let supervisor = Supervisor::new_noop().with_history_file(&path).unwrap();
+            supervisor.register_child(spec.clone()).await.unwrap();
+            for _ in 0..3 {
+                supervisor.report_crash("accrual", "trap").await.unwrap();
+            }
+        }
+
+        let supervisor = Supervisor::new_noop().with_history_file(&path).unwrap();
+        supervisor.register_child(spec.clone()).await.unwrap();
+        let status = supervisor.get_child_status("accrual").await.unwrap();
+        assert_eq!((status.total_crashes, status.restart_count), (3, 1));
+        assert_eq!(status.escalation_level, EscalationLevel::Level2RestartClean);
+
+        // The budget continues where it left off instead of starting over
+        supervisor.report_crash("accrual", "trap").await.unwrap();
+        let action = supervisor.report_crash("accrual", "trap").await.unwrap();
+        assert!(matches!(action, SupervisorAction::Restart { escalation: EscalationLevel::Level3ReloadModule, .. }));
+
+        // A window that has passed keeps only the crash total
+        let now = crate::clock::now_millis();
+        let expired = CrashHistory {
+            total_crashes: 9,
+            escalation_level: EscalationLevel::Level3ReloadModule,
+            restart_count: 2,
+            window_start: Some(now - 120_000),
+            last_crash: Some(now - 90_000),
+        };
+        std::fs::write(&path, serde_json::to_vec(&BTreeMap::from([("accrual", expired)])).unwrap()).unwrap();
+        let supervisor = Supervisor::new_noop().with_history_file(&path).unwrap();
+        supervisor.register_child(spec).await.unwrap();
+        let status = supervisor.get_child_status("accrual").await.unwrap();
+        assert_eq!((status.total_crashes, status.restart_count), (9, 0));
+        assert_eq!(status.escalation_level, EscalationLevel::Level1RestartWithState);
+    }
+
+    #[tokio::test]
+    async fn test_stale_heartbeat_is_a_hang() {
+        tokio::time::pause();
+        let supervisor = Supervisor::new_noop();
+        for (id, timeout) in [("resident", Some(5_000)), ("batch", None)] {
+            let spec = ChildSpec {
+                id: id.into(),
+                manifest_path: "/path/to/manifest.json".into(),
+                heartbeat_timeout_ms: timeout,
+                ..Default::default()
+            };
+            supervisor.register_child(spec).await.unwrap();
+            supervisor.report_started(id).await.unwrap();
+        }
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        supervisor.report_heartbeat("resident").await.unwrap();
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert!(supervisor.check_heartbeats().await.unwrap().is_empty());
+        assert_eq!(supervisor.get_child_status("resident").await.unwrap().heartbeat_age_ms, Some(4_000));
+
+        // Children without a heartbeat timeout are never considered hung
+        tokio::time::advance(Duration::from_secs(2)).await;
+        let hung = supervisor.check_heartbeats().await.unwrap();
+        assert_eq!(hung.len(), 1);
+        assert_eq!(hung[0].0, "resident");
+        assert!(matches!(hung[0].1, SupervisorAction::Restart { .. }));
+        let status = supervisor.get_child_status("resident").await.unwrap();
+        assert_eq!(status.total_crashes, 1);
+        assert_eq!(status.state, ChildState::Restarting { attempt: 1 });
+
+        // A restarting child is not checked again until it reports started
+        assert!(supervisor.check_heartbeats().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restart_countdown() {
+        tokio::time::pause();
+        let supervisor = Supervisor::new_noop();
+        let spec = ChildSpec { id: "accrual".into(), base_restart_delay_ms: 6_000, ..Default::default() };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("accrual").await.unwrap();
+        assert_eq!(supervisor.get_child_status("accrual").await.unwrap().restart_in_ms, None);
+
+        let action = supervisor.report_crash("accrual", "trap").await.unwrap();
+        assert_eq!(supervisor.get_child_status("accrual").await.unwrap().restart_in_ms, Some(12_000));
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let status = supervisor.get_child_status("accrual").await.unwrap();
+        assert_eq!(status.restart_in_ms, Some(7_000));
+        assert_eq!(serde_json::to_value(&status).unwrap()["restart_in_ms"], 7_000);
+
+        // The restart itself clears the countdown
+        supervisor.execute_restart("accrual", action).await.unwrap();
+        let status = supervisor.get_child_status("accrual").await.unwrap();
+        assert_eq!((status.state, status.restart_in_ms), (ChildState::Starting, None));
+    }
+
+    #[tokio::test]
+    async fn test_injected_timer() {
+        /// Sleeping moves time forward at once
+        struct SkipAhead(std::sync::Mutex<Instant>);
+
+        impl SupervisorTimer for SkipAhead {
+            fn now(&self) -> Instant {
+                *self.0.lock().unwrap()
+            }
+
+            fn sleep(&self, delay: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+                *self.0.lock().unwrap() += delay;
+                Box::pin(std::future::ready(()))
+            }
+        }
+
+        let timer = Arc::new(SkipAhead(std::sync::Mutex::new(Instant::now())));
+        let supervisor = Supervisor::new_noop().with_timer(timer.clone());
+        let spec = ChildSpec {
+            id: "flapping".into(),
+            circuit_breaker: Some(CircuitBreaker { failure_threshold: 1, cooldown_ms: 30_000 }),
+            ..Default::default()
+        };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("flapping").await.unwrap();
+
+        let start = timer.now();
+        let action = supervisor.report_crash("flapping", "trap").await.unwrap();
+        assert_eq!(supervisor.get_child_status("flapping").await.unwrap().restart_in_ms, Some(30_000));
+        supervisor.execute_restart("flapping", action).await.unwrap();
+        assert_eq!(timer.now() - start, Duration::from_secs(30));
+        assert_eq!(supervisor.get_child_status("flapping").await.unwrap().circuit, Some(CircuitState::HalfOpen));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_transitions() {
+        let supervisor = Supervisor::new_noop();
+        let mut transitions = supervisor.subscribe();
+        let spec = ChildSpec { id: "accrual".into(), restart: RestartStrategy::Temporary, ..Default::default() };
+        supervisor.register_child(spec).await.unwrap();
+        supervisor.report_started("accrual").await.unwrap();
+        supervisor.report_heartbeat("accrual").await.unwrap();
+        supervisor.report_crash("accrual", "trap").await.unwrap();
+        supervisor.unregister_child("accrual").await.unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(transition) = transitions.try_recv() {
+            assert_eq!(transition.id, "accrual");
+            seen.push((transition.from, transition.to));
+        }
+        let crashed = ChildState::Crashed { error: "trap".into() };
+        let stopped = ChildState::Stopped { reason: "Temporary strategy - no restart".into() };
+        assert_eq!(seen, vec![
+            (None, Some(ChildState::Starting)),
+            (Some(ChildState::Starting), Some(ChildState::Running)),
+            (Some(ChildState::Running), Some(crashed.clone())),
+            (Some(crashed), Some(stopped.clone())),
+            (Some(stopped), None),
+        ]);
+
+        let json = serde_json::to_value(ChildState::Restarting { attempt: 2 }).unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "restarting", "attempt": 2}));
+    }
+
     #[tokio::test]
     async fn test_shutdown_all() {
         let supervisor = Supervisor::new_noop();
@@ -609,7 +1366,7 @@ mod tests {
 
         let status = supervisor.get_status().await;
         for s in status {
-            assert!(s.state.contains("Terminated"));
+            assert_eq!(s.state, ChildState::Terminated);
         }
     }
 }
@@ -0,0 +1,1116 @@
+//! Tenant Isolation Layer
+//!
+//! This module implements the multi-tenancy boundary of the kernel. Every
+//! employer using the system is a tenant with its own policy, employee roster,
+//! and capability namespace.
+//!
+//! Isolation Guarantees:
+//! - Tenant IDs are validated before use and can never contain the namespace separator
+//! - Capability resource IDs are prefixed with the owning tenant's namespace
+//! - A resource is only reachable from the tenant whose namespace it lives in
+//! - Payloads that name a different tenant than the caller are rejected
+//!
+//! Policies are kept as a versioned history (see `policy`) and can be
+//! persisted so the rules in force on any past date remain available.
+//! A read-only snapshot of a persisted registry can be opened by a reporting
+//! replica while the primary keeps writing.
+//!
+//! Each tenant's history is also kept serialized in a policy cache that guest
+//! modules read through host imports. Every change bumps the tenant's
+//! revision, so guests can keep parsed policies and invalidate them exactly
+//! when an administrator updates one.
+//!
+//! Tenants have an explicit lifecycle. A tenant is created before anything
+//! runs on its behalf, archived when the employer leaves (its records stay
+//! readable but nothing new may be written), and finally purged, which drops
+//! it from the registry and the policy file. Purging only reaches archived
+//! tenants, so no tenant disappears in one step.
+//!
+//! Each tenant also keeps its saved report templates (see
+//! `report::template`), versioned and persisted with its policy history, and
+//! a JSON configuration object per module, which modules granted `config`
+//! read through `host_get_config` when they run on the tenant's behalf.
+//!
+//! Work done on behalf of a tenant runs inside [`scope`]; audit entries
+//! appended while it runs are tagged with the tenant.
+//!
+//! Reference: docs/abi/kernel_contract.md
+
+use crate::calendar::Date;
+use crate::clock::now_millis;
+use crate::policy::{PolicyFile, PolicyHistory, PolicyVersion, StoredTenant};
+use crate::report::template::{ReportTemplate, ReportTemplates, TemplateVersion};
+use crate::security::capabilities::CapabilityToken;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+/// Prefix for tenant-scoped resource identifiers
+const TENANT_NAMESPACE_PREFIX: &str = "tenant:";
+
+/// Maximum length of a tenant identifier
+const MAX_TENANT_ID_LEN: usize = 64;
+
+/// Largest module configuration a tenant may store, serialized
+pub const MAX_MODULE_CONFIG_BYTES: usize = 64 * 1024;
+
+tokio::task_local! {
+    static CURRENT_TENANT: String;
+}
+
+/// Run `future` on behalf of `tenant_id`
+pub async fn scope<F: Future>(tenant_id: String, future: F) -> F::Output {
+    CURRENT_TENANT.scope(tenant_id, future).await
+}
+
+/// The tenant the running task works on behalf of, if any
+pub fn current() -> Option<String> {
+    CURRENT_TENANT.try_with(Clone::clone).ok()
+}
+
+/// Errors that can occur in tenant operations
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TenantError {
+    #[error("Invalid tenant id: {0}")]
+    InvalidTenantId(String),
+
+    #[error("Tenant not found: {0}")]
+    UnknownTenant(String),
+
+    #[error("Tenant already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Tenant {0} is archived")]
+    Archived(String),
+
+    #[error("Tenant {0} must be archived before it is purged")]
+    NotArchived(String),
+
+    #[error("Employee {employee_id} not found in tenant {tenant_id}")]
+    UnknownEmployee { tenant_id: String, employee_id: String },
+
+    #[error("Tenant {tenant_id} may not access resource {resource_id}")]
+    CrossTenantAccess { tenant_id: String, resource_id: String },
+
+    #[error("Input for tenant {0} is not JSON, so the tenants it names cannot be checked")]
+    UncheckedPayload(String),
+
+    #[error("Invalid policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("Invalid report template: {0}")]
+    InvalidReportTemplate(String),
+
+    #[error("Report template not found: {0}")]
+    UnknownReportTemplate(String),
+
+    #[error("Invalid module configuration: {0}")]
+    InvalidModuleConfig(String),
+
+    #[error("Policy persistence failed: {0}")]
+    Persistence(String),
+
+    #[error("Tenant registry is a read-only snapshot")]
+    ReadOnly,
+}
+
+/// Result type for tenant operations
+pub type TenantResult<T> = Result<T, TenantError>;
+
+/// Check that a tenant ID is safe to embed in resource namespaces
+pub fn validate_tenant_id(tenant_id: &str) -> TenantResult<()> {
+    let valid = !tenant_id.is_empty()
+        && tenant_id.len() <= MAX_TENANT_ID_LEN
+        && tenant_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(TenantError::InvalidTenantId(tenant_id.to_string()))
+    }
+}
+
+/// Build the namespaced resource ID for a tenant-owned resource
+///
+/// e.g. `tenant_resource_id("acme", "ledger")` -> `tenant:acme/ledger`
+pub fn tenant_resource_id(tenant_id: &str, resource_id: &str) -> String {
+    format!("{}{}/{}", TENANT_NAMESPACE_PREFIX, tenant_id, resource_id)
+}
+
+/// Extract the owning tenant from a namespaced resource ID
+pub fn resource_tenant(resource_id: &str) -> Option<&str> {
+    resource_id
+        .strip_prefix(TENANT_NAMESPACE_PREFIX)
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(tenant, _)| tenant)
+}
+
+/// Verify a namespaced resource belongs to the given tenant
+pub fn check_resource_scope(tenant_id: &str, resource_id: &str) -> TenantResult<()> {
+    match resource_tenant(resource_id) {
+        Some(owner) if owner == tenant_id => Ok(()),
+        _ => Err(TenantError::CrossTenantAccess {
+            tenant_id: tenant_id.to_string(),
+            resource_id: resource_id.to_string(),
+        }),
+    }
+}
+
+/// Verify a JSON payload does not name a tenant other than the caller
+///
+/// Every `tenant_id` field, at any depth, must name the caller (or be
+/// null). Payloads that are not JSON cannot be checked and are refused.
+pub fn check_payload_scope(tenant_id: &str, payload: &[u8]) -> TenantResult<()> {
+    let value = serde_json::from_slice::<serde_json::Value>(payload)
+        .map_err(|_| TenantError::UncheckedPayload(tenant_id.to_string()))?;
+
+    match foreign_tenant(tenant_id, &value) {
+        Some(other) => Err(TenantError::CrossTenantAccess {
+            tenant_id: tenant_id.to_string(),
+            resource_id: tenant_resource_id(other.as_str().unwrap_or(&other.to_string()), "payload"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The first `tenant_id` in `value` naming a tenant other than `tenant_id`
+fn foreign_tenant<'a>(tenant_id: &str, value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+    match value {
+        serde_json::Value::Object(fields) => fields.iter().find_map(|(key, field)| match key.as_str() {
+            "tenant_id" if !field.is_null() && field.as_str() != Some(tenant_id) => Some(field),
+            _ => foreign_tenant(tenant_id, field),
+        }),
+        serde_json::Value::Array(items) => items.iter().find_map(|item| foreign_tenant(tenant_id, item)),
+        _ => None,
+    }
+}
+
+/// Employer policy configuration for a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantPolicy {
+    /// "small" (< 10 employees) or "large" (>= 10 employees)
+    pub employer_size: String,
+    /// Hours accrued per hour worked (default 1:30)
+    pub accrual_rate: f64,
+    pub max_carryover_hours: u32,
+    pub max_usage_hours: u32,
+    /// Opt in to informational usage pattern insights (see `insights`)
+    #[serde(default)]
+    pub usage_insights: bool,
+    /// Jurisdiction whose statute applies (defaults to the kernel's)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
+    /// Days ledger events and audit entries keep employee data before it is
+    /// redacted (see `retention`); kept indefinitely when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<u32>,
+}
+
+impl TenantPolicy {
+    /// Validate the policy values
+    pub fn validate(&self) -> TenantResult<()> {
+        if !["small", "large"].contains(&self.employer_size.as_str()) {
+            return Err(TenantError::InvalidPolicy(
+                "employer_size must be 'small' or 'large'".into(),
+            ));
+        }
+
+        if self.accrual_rate <= 0.0 || self.accrual_rate > 1.0 {
+            return Err(TenantError::InvalidPolicy(
+                "accrual_rate must be between 0 and 1".into(),
+            ));
+        }
+
+        if self.retention_days.is_some_and(|days| days < crate::retention::MIN_RETENTION_DAYS) {
+            return Err(TenantError::InvalidPolicy(format!(
+                "retention_days must be at least {} (three years of records)",
+                crate::retention::MIN_RETENTION_DAYS
+            )));
+        }
+
+        if let Some(jurisdiction) = &self.jurisdiction {
+            if crate::statutes::rules_for(jurisdiction).is_none() {
+                return Err(TenantError::InvalidPolicy(format!("unsupported jurisdiction {}", jurisdiction)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Where a tenant is in its lifecycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TenantStatus {
+    #[default]
+    Active,
+    /// Readable but closed to new work, awaiting purge
+    Archived,
+}
+
+/// When a tenant was created and archived
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantLifecycle {
+    pub status: TenantStatus,
+    /// ms since Unix epoch (0 for tenants stored before lifecycles were recorded)
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+}
+
+/// A newly created tenant and the root capability of its namespace
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantProvisioning {
+    pub tenant: Tenant,
+    /// Every right over the namespace; narrower capabilities are delegated from it
+    pub root_capability: CapabilityToken,
+}
+
+/// What purging a tenant removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantPurgeReport {
+    pub tenant_id: String,
+    pub ledger_events: usize,
+    pub policy_versions: usize,
+    pub employees: usize,
+    /// Capabilities issued in the namespace since the tenant was archived
+    pub capabilities_revoked: usize,
+}
+
+/// A tenant known to the kernel
+#[derive(Debug, Clone, Serialize)]
+pub struct Tenant {
+    /// Tenant identifier
+    pub id: String,
+    #[serde(flatten)]
+    pub lifecycle: TenantLifecycle,
+    /// Every policy version ever set for this tenant
+    pub policies: PolicyHistory,
+    /// Every version of every saved report template
+    pub report_templates: ReportTemplates,
+    /// Configuration object of each module, by module name
+    pub module_configs: BTreeMap<String, serde_json::Value>,
+    /// Employees belonging to this tenant (sorted)
+    pub employees: BTreeSet<String>,
+}
+
+impl Tenant {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            lifecycle: TenantLifecycle { created_at: now_millis(), ..Default::default() },
+            policies: PolicyHistory::default(),
+            report_templates: ReportTemplates::default(),
+            module_configs: BTreeMap::new(),
+            employees: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.lifecycle.status == TenantStatus::Archived
+    }
+
+    fn ensure_active(&self) -> TenantResult<()> {
+        if self.is_archived() {
+            Err(TenantError::Archived(self.id.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn stored(&self) -> StoredTenant {
+        StoredTenant {
+            lifecycle: self.lifecycle.clone(),
+            policies: self.policies.clone(),
+            report_templates: self.report_templates.clone(),
+            module_configs: self.module_configs.clone(),
+        }
+    }
+
+    /// Namespace under which this tenant's capability resources live
+    pub fn namespace(&self) -> String {
+        tenant_resource_id(&self.id, "")
+    }
+}
+
+/// A tenant's policy history as served to guest modules
+#[derive(Debug)]
+pub struct CachedPolicy {
+    /// Changes whenever the tenant's policy history does; never reused
+    pub revision: u64,
+    /// `{"tenant_id", "revision", "versions"}` as JSON
+    pub json: Vec<u8>,
+}
+
+/// Serialized policy histories, readable without awaiting the registry
+///
+/// Host imports run synchronously inside guest calls, so the registry keeps
+/// this copy current on every policy change instead.
+#[derive(Default)]
+struct PolicyCache {
+    entries: std::sync::RwLock<HashMap<String, (PolicyHistory, Arc<CachedPolicy>)>>,
+    generation: AtomicU64,
+}
+
+impl PolicyCache {
+    /// Cache a tenant's history, bumping its revision if the history changed
+    fn update(&self, tenant_id: &str, policies: &PolicyHistory) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if entries.get(tenant_id).is_some_and(|(cached, _)| cached == policies) {
+            return;
+        }
+
+        let revision = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let json = serde_json::json!({
+            "tenant_id": tenant_id,
+            "revision": revision,
+            "versions": policies,
+        });
+        let cached = CachedPolicy { revision, json: json.to_string().into_bytes() };
+        entries.insert(tenant_id.to_string(), (policies.clone(), Arc::new(cached)));
+    }
+
+    fn get(&self, tenant_id: &str) -> Option<Arc<CachedPolicy>> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries.get(tenant_id).map(|(_, cached)| cached.clone())
+    }
+
+    /// Forget a purged tenant's history
+    fn remove(&self, tenant_id: &str) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if entries.remove(tenant_id).is_some() {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Registry of all tenants and their isolated state
+pub struct TenantRegistry {
+    tenants: RwLock<HashMap<String, Tenant>>,
+    policy_file: Option<PolicyFile>,
+    read_only: bool,
+    policy_cache: PolicyCache,
+}
+
+impl TenantRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            tenants: RwLock::new(HashMap::new()),
+            policy_file: None,
+            read_only: false,
+            policy_cache: PolicyCache::default(),
+        }
+    }
+
+    /// Create a registry whose tenants and policy histories are persisted to a file
+    ///
+    /// Stored tenants are registered immediately.
+    pub fn with_policy_file(file: PolicyFile) -> TenantResult<Self> {
+        Self::open(file, false)
+    }
+
+    /// Open a read-only snapshot of a policy file written by another process
+    ///
+    /// Every mutation on a snapshot fails with `TenantError::ReadOnly`.
+    pub fn snapshot(file: PolicyFile) -> TenantResult<Self> {
+        Self::open(file, true)
+    }
+
+    fn open(file: PolicyFile, read_only: bool) -> TenantResult<Self> {
+        let tenants = Self::load_tenants(&file)?;
+        let policy_cache = PolicyCache::default();
+        for (id, tenant) in &tenants {
+            policy_cache.update(id, &tenant.policies);
+        }
+        Ok(Self {
+            tenants: RwLock::new(tenants),
+            policy_file: Some(file),
+            read_only,
+            policy_cache,
+        })
+    }
+
+    fn load_tenants(file: &PolicyFile) -> TenantResult<HashMap<String, Tenant>> {
+        file.load()?
+            .into_iter()
+            .map(|(id, stored)| {
+                validate_tenant_id(&id)?;
+                let mut tenant = Tenant::new(id.clone());
+                tenant.lifecycle = stored.lifecycle;
+                tenant.policies = stored.policies;
+                tenant.report_templates = stored.report_templates;
+                tenant.module_configs = stored.module_configs;
+                Ok((id, tenant))
+            })
+            .collect()
+    }
+
+    /// Reload a snapshot's tenants to pick up changes made by the primary
+    ///
+    /// Tenants the primary purged are dropped. Has no effect on a writable
+    /// registry, which is always current.
+    pub async fn refresh(&self) -> TenantResult<()> {
+        let (true, Some(file)) = (self.read_only, &self.policy_file) else {
+            return Ok(());
+        };
+
+        let loaded = Self::load_tenants(file)?;
+        let mut tenants = self.tenants.write().await;
+        tenants.retain(|id, _| {
+            let kept = loaded.contains_key(id);
+            if !kept {
+                self.policy_cache.remove(id);
+            }
+            kept
+        });
+        for (id, tenant) in loaded {
+            self.policy_cache.update(&id, &tenant.policies);
+            let entry = tenants.entry(id).or_insert_with(|| Tenant::new(tenant.id.clone()));
+            entry.lifecycle = tenant.lifecycle;
+            entry.policies = tenant.policies;
+            entry.report_templates = tenant.report_templates;
+            entry.module_configs = tenant.module_configs;
+        }
+        Ok(())
+    }
+
+    /// Save the tenants as they will be after a change, before it becomes visible
+    async fn persist<'a>(&self, tenants: impl Iterator<Item = &'a Tenant>) -> TenantResult<()> {
+        match &self.policy_file {
+            Some(file) => file.save(&tenants.map(|t| (t.id.clone(), t.stored())).collect()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// The file policy histories are persisted to, if any
+    pub fn policy_file(&self) -> Option<&PolicyFile> {
+        self.policy_file.as_ref()
+    }
+
+    /// Whether this registry is a read-only snapshot
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn ensure_writable(&self) -> TenantResult<()> {
+        if self.read_only {
+            Err(TenantError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create a tenant, failing if one with this ID exists (archived or not)
+    pub async fn create(&self, tenant_id: &str) -> TenantResult<Tenant> {
+        self.ensure_writable()?;
+        validate_tenant_id(tenant_id)?;
+        let mut tenants = self.tenants.write().await;
+        if tenants.contains_key(tenant_id) {
+            return Err(TenantError::AlreadyExists(tenant_id.to_string()));
+        }
+        let tenant = Tenant::new(tenant_id.to_string());
+        self.persist(tenants.values().chain([&tenant])).await?;
+        tenants.insert(tenant_id.to_string(), tenant.clone());
+        Ok(tenant)
+    }
+
+    /// Register a tenant, returning false if it already existed
+    pub async fn register(&self, tenant_id: &str) -> TenantResult<bool> {
+        match self.create(tenant_id).await {
+            Ok(_) => Ok(true),
+            Err(TenantError::AlreadyExists(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Archive an active tenant; its records stay readable but it accepts no new work
+    pub async fn archive(&self, tenant_id: &str) -> TenantResult<Tenant> {
+        self.ensure_writable()?;
+        let mut tenants = self.tenants.write().await;
+        let mut tenant = tenants
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.ensure_active()?;
+
+        tenant.lifecycle.status = TenantStatus::Archived;
+        tenant.lifecycle.archived_at = Some(now_millis());
+        self.persist(tenants.values().filter(|t| t.id != tenant_id).chain([&tenant])).await?;
+        tenants.insert(tenant_id.to_string(), tenant.clone());
+        Ok(tenant)
+    }
+
+    /// Remove an archived tenant for good, returning it as it was
+    pub async fn purge(&self, tenant_id: &str) -> TenantResult<Tenant> {
+        self.ensure_writable()?;
+        let mut tenants = self.tenants.write().await;
+        let tenant = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        if !tenant.is_archived() {
+            return Err(TenantError::NotArchived(tenant_id.to_string()));
+        }
+
+        self.persist(tenants.values().filter(|t| t.id != tenant_id)).await?;
+        self.policy_cache.remove(tenant_id);
+        Ok(tenants.remove(tenant_id).expect("tenant was just found"))
+    }
+
+    /// Check whether a tenant is registered
+    pub async fn contains(&self, tenant_id: &str) -> bool {
+        self.tenants.read().await.contains_key(tenant_id)
+    }
+
+    /// Fail unless the tenant is registered
+    pub async fn ensure_exists(&self, tenant_id: &str) -> TenantResult<()> {
+        if self.contains(tenant_id).await {
+            Ok(())
+        } else {
+            Err(TenantError::UnknownTenant(tenant_id.to_string()))
+        }
+    }
+
+    /// Fail unless the tenant is registered and not archived
+    pub async fn ensure_active(&self, tenant_id: &str) -> TenantResult<()> {
+        self.tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?
+            .ensure_active()
+    }
+
+    /// Get a snapshot of a tenant
+    pub async fn get(&self, tenant_id: &str) -> TenantResult<Tenant> {
+        self.tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))
+    }
+
+    /// Record a new policy version for a tenant, registering the tenant if needed
+    ///
+    /// The version applies from `effective_from` onwards and must start after
+    /// the tenant's latest version. When a policy file is configured the
+    /// history is saved before the new version becomes visible. Archived
+    /// tenants take no new versions.
+    pub async fn set_policy(
+        &self,
+        tenant_id: &str,
+        policy: TenantPolicy,
+        effective_from: Date,
+    ) -> TenantResult<PolicyVersion> {
+        self.ensure_writable()?;
+        validate_tenant_id(tenant_id)?;
+
+        let mut tenants = self.tenants.write().await;
+        let mut tenant = tenants
+            .get(tenant_id)
+            .cloned()
+            .unwrap_or_else(|| Tenant::new(tenant_id.to_string()));
+        tenant.ensure_active()?;
+        let version = tenant.policies.append(policy, effective_from, now_millis())?.clone();
+
+        self.persist(tenants.values().filter(|t| t.id != tenant_id).chain([&tenant])).await?;
+        self.policy_cache.update(tenant_id, &tenant.policies);
+        tenants.insert(tenant_id.to_string(), tenant);
+        Ok(version)
+    }
+
+    /// A tenant's cached policy history, without waiting on the registry
+    ///
+    /// `None` until a policy has been set for the tenant.
+    pub fn cached_policy(&self, tenant_id: &str) -> Option<Arc<CachedPolicy>> {
+        self.policy_cache.get(tenant_id)
+    }
+
+    /// Counter bumped by every policy change of any tenant
+    pub fn policy_generation(&self) -> u64 {
+        self.policy_cache.generation.load(Ordering::SeqCst)
+    }
+
+    /// Get the tenant policy in force today
+    pub async fn get_policy(&self, tenant_id: &str) -> TenantResult<Option<TenantPolicy>> {
+        Ok(self
+            .policy_at(tenant_id, Date::today())
+            .await?
+            .map(|v| v.policy))
+    }
+
+    /// Get the tenant policy version in force on a given date
+    pub async fn policy_at(&self, tenant_id: &str, date: Date) -> TenantResult<Option<PolicyVersion>> {
+        let tenants = self.tenants.read().await;
+        let tenant = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        Ok(tenant.policies.at(date).cloned())
+    }
+
+    /// Get every policy version recorded for a tenant, oldest first
+    pub async fn policy_history(&self, tenant_id: &str) -> TenantResult<Vec<PolicyVersion>> {
+        Ok(self.get(tenant_id).await?.policies.versions().to_vec())
+    }
+
+    /// Save a report template as the next version of its name
+    ///
+    /// The template's expressions must parse. Like policies, templates are
+    /// saved to the policy file before the new version becomes visible, and
+    /// archived tenants take no new versions.
+    pub async fn save_report_template(
+        &self,
+        tenant_id: &str,
+        template: ReportTemplate,
+    ) -> TenantResult<TemplateVersion> {
+        self.ensure_writable()?;
+        let mut tenants = self.tenants.write().await;
+        let mut tenant = tenants
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.ensure_active()?;
+        let version = tenant
+            .report_templates
+            .record(template, now_millis())
+            .map_err(|e| TenantError::InvalidReportTemplate(e.to_string()))?
+            .clone();
+
+        self.persist(tenants.values().filter(|t| t.id != tenant_id).chain([&tenant])).await?;
+        tenants.insert(tenant_id.to_string(), tenant);
+        Ok(version)
+    }
+
+    /// A saved report template, at a given version or its latest
+    pub async fn report_template(
+        &self,
+        tenant_id: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> TenantResult<TemplateVersion> {
+        let tenants = self.tenants.read().await;
+        let tenant = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.report_templates.get(name, version).cloned().ok_or_else(|| {
+            TenantError::UnknownReportTemplate(match version {
+                Some(version) => format!("{} version {}", name, version),
+                None => name.to_string(),
+            })
+        })
+    }
+
+    /// The latest version of each of a tenant's report templates, sorted by name
+    pub async fn list_report_templates(&self, tenant_id: &str) -> TenantResult<Vec<TemplateVersion>> {
+        Ok(self.get(tenant_id).await?.report_templates.latest().cloned().collect())
+    }
+
+    /// Set the configuration a module gets when it runs for a tenant
+    ///
+    /// The configuration must be a JSON object of at most
+    /// [`MAX_MODULE_CONFIG_BYTES`]; `null` removes it. Like report templates,
+    /// it is saved to the policy file before it becomes visible.
+    pub async fn set_module_config(
+        &self,
+        tenant_id: &str,
+        module_name: &str,
+        config: serde_json::Value,
+    ) -> TenantResult<()> {
+        self.ensure_writable()?;
+        match &config {
+            serde_json::Value::Null => {}
+            serde_json::Value::Object(_) if config.to_string().len() <= MAX_MODULE_CONFIG_BYTES => {}
+            serde_json::Value::Object(_) => {
+                return Err(TenantError::InvalidModuleConfig(format!(
+                    "configuration of {} is larger than {} bytes",
+                    module_name, MAX_MODULE_CONFIG_BYTES
+                )))
+            }
+            _ => {
+                return Err(TenantError::InvalidModuleConfig(format!(
+                    "configuration of {} is not a JSON object",
+                    module_name
+                )))
+            }
+        }
+
+        let mut tenants = self.tenants.write().await;
+        let mut tenant = tenants
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.ensure_active()?;
+        if config.is_null() {
+            tenant.module_configs.remove(module_name);
+        } else {
+            tenant.module_configs.insert(module_name.to_string(), config);
+        }
+
+        self.persist(tenants.values().filter(|t| t.id != tenant_id).chain([&tenant])).await?;
+        tenants.insert(tenant_id.to_string(), tenant);
+        Ok(())
+    }
+
+    /// The configuration a tenant set for a module, if any
+    pub async fn module_config(&self, tenant_id: &str, module_name: &str) -> TenantResult<Option<serde_json::Value>> {
+        let tenants = self.tenants.read().await;
+        let tenant = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        Ok(tenant.module_configs.get(module_name).cloned())
+    }
+
+    /// Add an employee to a tenant's roster
+    pub async fn add_employee(&self, tenant_id: &str, employee_id: &str) -> TenantResult<()> {
+        self.ensure_writable()?;
+        let mut tenants = self.tenants.write().await;
+        let tenant = tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+        tenant.ensure_active()?;
+        tenant.employees.insert(employee_id.to_string());
+        Ok(())
+    }
+
+    /// Fail unless the employee belongs to the tenant
+    pub async fn ensure_employee(&self, tenant_id: &str, employee_id: &str) -> TenantResult<()> {
+        let tenants = self.tenants.read().await;
+        let tenant = tenants
+            .get(tenant_id)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant_id.to_string()))?;
+
+        if tenant.employees.contains(employee_id) {
+            Ok(())
+        } else {
+            Err(TenantError::UnknownEmployee {
+                tenant_id: tenant_id.to_string(),
+                employee_id: employee_id.to_string(),
+            })
+        }
+    }
+
+    /// Bring back a tenant stored elsewhere (e.g. in a database)
+    ///
+    /// A tenant the registry lacks is added with its lifecycle and policy
+    /// history; the employees are added to the roster either way, archived or
+    /// not. Returns whether the tenant was added.
+    pub async fn restore(
+        &self,
+        tenant_id: &str,
+        lifecycle: TenantLifecycle,
+        policies: PolicyHistory,
+        employees: Vec<String>,
+    ) -> TenantResult<bool> {
+        self.ensure_writable()?;
+        validate_tenant_id(tenant_id)?;
+        let mut tenants = self.tenants.write().await;
+        let added = !tenants.contains_key(tenant_id);
+        if added {
+            let mut tenant = Tenant::new(tenant_id.to_string());
+            tenant.lifecycle = lifecycle;
+            tenant.policies = policies;
+            self.persist(tenants.values().chain([&tenant])).await?;
+            self.policy_cache.update(tenant_id, &tenant.policies);
+            tenants.insert(tenant_id.to_string(), tenant);
+        }
+        let tenant = tenants.get_mut(tenant_id).expect("tenant is registered");
+        tenant.employees.extend(employees);
+        Ok(added)
+    }
+
+    /// List a tenant's employees in sorted order
+    pub async fn list_employees(&self, tenant_id: &str) -> TenantResult<Vec<String>> {
+        Ok(self.get(tenant_id).await?.employees.into_iter().collect())
+    }
+
+    /// List all tenant IDs in sorted order
+    pub async fn list_tenants(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.tenants.read().await.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
+
+impl Default for TenantRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> TenantPolicy {
+        TenantPolicy {
+            employer_size: "small".into(),
+            accrual_rate: 0.0333,
+            max_carryover_hours: 40,
+            max_usage_hours: 40,
+            usage_insights: false,
+            jurisdiction: None,
+            retention_days: None,
+        }
+    }
+
+    #[test]
+    fn test_tenant_id_validation() {
+        assert!(validate_tenant_id("acme-01").is_ok());
+        assert!(validate_tenant_id("").is_err());
+        assert!(validate_tenant_id("acme/other").is_err());
+        assert!(validate_tenant_id("tenant:acme").is_err());
+        assert!(validate_tenant_id(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_resource_namespacing() {
+        let id = tenant_resource_id("acme", "ledger");
+        assert_eq!(id, "tenant:acme/ledger");
+        assert_eq!(resource_tenant(&id), Some("acme"));
+        assert_eq!(resource_tenant("ledger"), None);
+
+        assert!(check_resource_scope("acme", &id).is_ok());
+        assert!(matches!(
+            check_resource_scope("globex", &id),
+            Err(TenantError::CrossTenantAccess { .. })
+        ));
+        assert!(check_resource_scope("acme", "ledger").is_err());
+    }
+
+    #[test]
+    fn test_payload_scope() {
+        assert!(check_payload_scope("acme", br#"{"tenant_id":"acme"}"#).is_ok());
+        assert!(check_payload_scope("acme", br#"{"minutes_worked":60}"#).is_ok());
+        assert!(check_payload_scope("acme", br#"{"tenant_id":"globex"}"#).is_err());
+
+        // Nested fields count too, and whatever can't be read is refused
+        assert!(check_payload_scope("acme", br#"{"employees":[{"tenant_id":"acme"},{"tenant_id":null}]}"#).is_ok());
+        let nested = check_payload_scope("acme", br#"{"employees":[{"id":"e1","tenant_id":"globex"}]}"#);
+        assert!(matches!(nested, Err(TenantError::CrossTenantAccess { resource_id, .. }) if resource_id == "tenant:globex/payload"));
+        assert!(check_payload_scope("acme", br#"{"tenant_id":7}"#).is_err());
+        assert!(matches!(check_payload_scope("acme", b"\x00\x01"), Err(TenantError::UncheckedPayload(_))));
+    }
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_policy_and_employees() {
+        let registry = TenantRegistry::new();
+
+        registry.set_policy("acme", policy(), date("2024-01-01")).await.unwrap();
+        assert_eq!(registry.get_policy("acme").await.unwrap(), Some(policy()));
+
+        registry.add_employee("acme", "e2").await.unwrap();
+        registry.add_employee("acme", "e1").await.unwrap();
+        assert_eq!(registry.list_employees("acme").await.unwrap(), vec!["e1", "e2"]);
+
+        assert!(registry.ensure_employee("acme", "e1").await.is_ok());
+        assert!(registry.add_employee("globex", "e1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_isolation() {
+        let registry = TenantRegistry::new();
+        registry.register("acme").await.unwrap();
+        registry.register("globex").await.unwrap();
+        registry.add_employee("acme", "e1").await.unwrap();
+
+        assert!(matches!(
+            registry.ensure_employee("globex", "e1").await,
+            Err(TenantError::UnknownEmployee { .. })
+        ));
+        assert_eq!(registry.list_tenants().await, vec!["acme", "globex"]);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policies.json");
+        let registry = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+
+        let created = registry.create("acme").await.unwrap();
+        assert_eq!(created.lifecycle.status, TenantStatus::Active);
+        assert_eq!(registry.create("acme").await.unwrap_err(), TenantError::AlreadyExists("acme".into()));
+        assert!(!registry.register("acme").await.unwrap());
+        registry.add_employee("acme", "e1").await.unwrap();
+        assert_eq!(registry.purge("acme").await.unwrap_err(), TenantError::NotArchived("acme".into()));
+
+        let archived = registry.archive("acme").await.unwrap();
+        assert!(archived.is_archived() && archived.lifecycle.archived_at.is_some());
+        assert_eq!(registry.ensure_active("acme").await, Err(TenantError::Archived("acme".into())));
+        assert!(registry.ensure_exists("acme").await.is_ok());
+        assert!(registry.set_policy("acme", policy(), date("2024-01-01")).await.is_err());
+        assert!(registry.add_employee("acme", "e2").await.is_err());
+        assert!(registry.archive("acme").await.is_err());
+
+        // Tenants without a policy are persisted along with their lifecycle
+        let reopened = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+        assert!(reopened.get("acme").await.unwrap().is_archived());
+        let replica = TenantRegistry::snapshot(PolicyFile::new(&path)).unwrap();
+        assert_eq!(replica.purge("acme").await.unwrap_err(), TenantError::ReadOnly);
+
+        let purged = registry.purge("acme").await.unwrap();
+        assert_eq!(purged.employees.len(), 1);
+        assert!(!registry.contains("acme").await);
+        replica.refresh().await.unwrap();
+        assert!(!replica.contains("acme").await);
+
+        // A purged ID can be used again
+        assert!(registry.create("acme").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_report_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policies.json");
+        let registry = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+        let template = |expression: &str| ReportTemplate {
+            name: "payroll".into(),
+            columns: vec![crate::report::template::TemplateColumn {
+                header: "Hours".into(),
+                expression: expression.into(),
+            }],
+            filter: None,
+        };
+
+        assert!(matches!(
+            registry.save_report_template("acme", template("accrued_minutes")).await,
+            Err(TenantError::UnknownTenant(_))
+        ));
+        registry.create("acme").await.unwrap();
+        registry.save_report_template("acme", template("accrued_minutes")).await.unwrap();
+        let v2 = registry.save_report_template("acme", template("hours(accrued_minutes)")).await.unwrap();
+        assert_eq!(v2.version, 2);
+        assert!(matches!(
+            registry.save_report_template("acme", template("accrued_minutes +")).await,
+            Err(TenantError::InvalidReportTemplate(_))
+        ));
+
+        // Versions survive a restart and are visible to replicas
+        let replica = TenantRegistry::snapshot(PolicyFile::new(&path)).unwrap();
+        let v1 = replica.report_template("acme", "payroll", Some(1)).await.unwrap();
+        assert_eq!(v1.template.columns[0].expression, "accrued_minutes");
+        assert_eq!(replica.report_template("acme", "payroll", None).await.unwrap(), v2);
+        assert_eq!(replica.list_report_templates("acme").await.unwrap(), vec![v2]);
+        assert_eq!(
+            replica.report_template("acme", "payroll", Some(3)).await,
+            Err(TenantError::UnknownReportTemplate("payroll version 3".into()))
+        );
+        assert_eq!(replica.save_report_template("acme", template("1")).await, Err(TenantError::ReadOnly));
+
+        registry.archive("acme").await.unwrap();
+        assert_eq!(
+            registry.save_report_template("acme", template("1")).await,
+            Err(TenantError::Archived("acme".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scope_tags_current_tenant() {
+        assert_eq!(current(), None);
+        let inside = scope("acme".to_string(), async { current() }).await;
+        assert_eq!(inside.as_deref(), Some("acme"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_policy_rejected() {
+        let registry = TenantRegistry::new();
+        let mut bad = policy();
+        bad.employer_size = "medium".into();
+
+        assert!(matches!(
+            registry.set_policy("acme", bad, date("2024-01-01")).await,
+            Err(TenantError::InvalidPolicy(_))
+        ));
+        assert!(!registry.contains("acme").await);
+    }
+
+    #[tokio::test]
+    async fn test_policy_versions_by_work_date() {
+        let registry = TenantRegistry::new();
+        let mut large = policy();
+        large.employer_size = "large".into();
+
+        let v1 = registry.set_policy("acme", policy(), date("2024-01-01")).await.unwrap();
+        let v2 = registry.set_policy("acme", large, date("2025-02-21")).await.unwrap();
+        assert_eq!((v1.version, v2.version), (1, 2));
+
+        let on = |d| registry.policy_at("acme", date(d));
+        assert_eq!(on("2025-02-20").await.unwrap().unwrap().policy.employer_size, "small");
+        assert_eq!(on("2025-02-21").await.unwrap().unwrap().policy.employer_size, "large");
+        assert!(on("2023-01-01").await.unwrap().is_none());
+
+        let history = registry.policy_history("acme").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].effective_to, Some(date("2025-02-21")));
+
+        // Backdating before the latest version would rewrite history
+        assert!(registry.set_policy("acme", policy(), date("2024-06-01")).await.is_err());
+        assert_eq!(registry.policy_history("acme").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_policy_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policies.json");
+
+        let registry = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+        registry.set_policy("acme", policy(), date("2024-01-01")).await.unwrap();
+        registry.set_policy("acme", policy(), date("2025-01-01")).await.unwrap();
+        registry.set_policy("globex", policy(), date("2024-01-01")).await.unwrap();
+        drop(registry);
+
+        let reopened = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+        assert_eq!(reopened.list_tenants().await, vec!["acme", "globex"]);
+        assert_eq!(reopened.policy_history("acme").await.unwrap().len(), 2);
+        assert_eq!(
+            reopened.policy_at("acme", date("2024-06-01")).await.unwrap().unwrap().version,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_read_only_and_refreshes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policies.json");
+
+        let primary = TenantRegistry::with_policy_file(PolicyFile::new(&path)).unwrap();
+        primary.set_policy("acme", policy(), date("2024-01-01")).await.unwrap();
+
+        let replica = TenantRegistry::snapshot(PolicyFile::new(&path)).unwrap();
+        assert!(replica.is_read_only());
+        assert_eq!(
+            replica.set_policy("acme", policy(), date("2025-01-01")).await,
+            Err(TenantError::ReadOnly)
+        );
+        assert_eq!(replica.register("globex").await, Err(TenantError::ReadOnly));
+
+        primary.set_policy("acme", policy(), date("2025-01-01")).await.unwrap();
+        primary.set_policy("globex", policy(), date("2025-01-01")).await.unwrap();
+        assert_eq!(replica.policy_history("acme").await.unwrap().len(), 1);
+
+        let revision = replica.cached_policy("acme").unwrap().revision;
+        replica.refresh().await.unwrap();
+        assert_eq!(replica.policy_history("acme").await.unwrap().len(), 2);
+        assert_eq!(replica.list_tenants().await, vec!["acme", "globex"]);
+
+        // The guest-visible cache follows the refresh, and only changed tenants move
+        let acme = replica.cached_policy("acme").unwrap();
+        assert!(acme.revision > revision);
+        let generation = replica.policy_generation();
+        replica.refresh().await.unwrap();
+        assert_eq!(replica.policy_generation(), generation);
+        assert_eq!(replica.cached_policy("acme").unwrap().revision, acme.revision);
+        assert!(replica.cached_policy("globex").is_some());
+    }
+}
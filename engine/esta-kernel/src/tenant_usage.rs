@@ -0,0 +1,263 @@
+//! Per-Tenant Fuel Accounting
+//!
+//! Every invocation run on a tenant's behalf adds the fuel it consumed to
+//! that tenant's usage, broken down by module, whether the call succeeded or
+//! not. Dry runs are not counted. Usage is kept in memory from the kernel's
+//! start; the audit log holds the per-invocation record.
+//!
+//! Fuel can be priced in cents per billion units, so usage reports double as
+//! cost attribution in multi-tenant deployments.
+//!
+//! A ceiling caps the fuel a tenant may use in each fixed window (an hour by
+//! default), so one large employer cannot starve the others. A tenant at its
+//! ceiling has further invocations refused with
+//! `KernelError::FuelCeilingReached` until the next window starts. Calls
+//! already admitted run to completion, so a tenant may pass its ceiling by
+//! what those calls use.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::error::KernelError;
+
+/// Default length of a ceiling window
+pub const DEFAULT_FUEL_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Fuel ceilings and pricing for tenants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantFuelConfig {
+    /// Window ceilings apply to; windows start at multiples of it since the Unix epoch
+    pub window: Duration,
+    /// Fuel per window for tenants without their own ceiling (none by default)
+    pub default_ceiling: Option<u64>,
+    /// Fuel per window for individual tenants, overriding the default
+    pub ceilings: BTreeMap<String, u64>,
+    /// Price of a billion fuel units in cents (0 leaves usage unpriced)
+    pub price_cents_per_billion: u64,
+}
+
+impl Default for TenantFuelConfig {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_FUEL_WINDOW,
+            default_ceiling: None,
+            ceilings: BTreeMap::new(),
+            price_cents_per_billion: 0,
+        }
+    }
+}
+
+impl TenantFuelConfig {
+    /// Fuel a tenant may use per window, if limited
+    pub fn ceiling(&self, tenant_id: &str) -> Option<u64> {
+        self.ceilings.get(tenant_id).copied().or(self.default_ceiling)
+    }
+
+    /// Cost of `fuel` in cents, rounded up; `None` when fuel is unpriced
+    pub fn cost_cents(&self, fuel: u64) -> Option<u64> {
+        (self.price_cents_per_billion > 0)
+            .then(|| (fuel as u128 * self.price_cents_per_billion as u128).div_ceil(1_000_000_000) as u64)
+    }
+
+    fn window_ms(&self) -> u64 {
+        (self.window.as_millis() as u64).max(1)
+    }
+}
+
+/// A tenant's use of one module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModuleUsage {
+    pub module: String,
+    pub invocations: u64,
+    pub fuel_consumed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_cents: Option<u64>,
+}
+
+/// Fuel a tenant has used since the kernel started, and in the current window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub invocations: u64,
+    pub fuel_consumed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_cents: Option<u64>,
+    /// Per-module breakdown, sorted by module name
+    pub modules: Vec<ModuleUsage>,
+    /// Start of the current ceiling window (ms since Unix epoch)
+    pub window_start: u64,
+    /// Fuel used in the current window
+    pub window_fuel: u64,
+    /// Fuel allowed per window, if limited
+    pub ceiling: Option<u64>,
+}
+
+#[derive(Default)]
+struct Totals {
+    modules: BTreeMap<String, (u64, u64)>,
+    window_start: u64,
+    window_fuel: u64,
+}
+
+impl Totals {
+    /// Fuel used in the window starting at `window_start`
+    fn fuel_in(&self, window_start: u64) -> u64 {
+        if self.window_start == window_start { self.window_fuel } else { 0 }
+    }
+}
+
+/// Running fuel totals per tenant
+pub struct TenantMeter {
+    config: TenantFuelConfig,
+    tenants: std::sync::Mutex<HashMap<String, Totals>>,
+}
+
+impl TenantMeter {
+    pub fn new(config: TenantFuelConfig) -> Self {
+        Self { config, tenants: std::sync::Mutex::new(HashMap::new()) }
+    }
+
+    pub fn config(&self) -> &TenantFuelConfig {
+        &self.config
+    }
+
+    fn window_start(&self, now: u64) -> u64 {
+        now - now % self.config.window_ms()
+    }
+
+    /// Refuse a new invocation if the tenant has reached its ceiling
+    pub fn check(&self, tenant_id: &str, now: u64) -> Result<(), KernelError> {
+        let Some(ceiling) = self.config.ceiling(tenant_id) else {
+            return Ok(());
+        };
+        let window_start = self.window_start(now);
+        let used = self
+            .tenants
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(tenant_id)
+            .map_or(0, |totals| totals.fuel_in(window_start));
+        if used < ceiling {
+            return Ok(());
+        }
+        Err(KernelError::FuelCeilingReached {
+            tenant_id: tenant_id.to_string(),
+            used,
+            ceiling,
+            resets_at: window_start + self.config.window_ms(),
+        })
+    }
+
+    /// Add one invocation's fuel to the tenant's usage
+    pub fn record(&self, tenant_id: &str, module: &str, fuel: u64, now: u64) {
+        let window_start = self.window_start(now);
+        let mut tenants = self.tenants.lock().unwrap_or_else(|e| e.into_inner());
+        let totals = tenants.entry(tenant_id.to_string()).or_default();
+        let (invocations, fuel_consumed) = totals.modules.entry(module.to_string()).or_default();
+        *invocations += 1;
+        *fuel_consumed += fuel;
+        totals.window_fuel = totals.fuel_in(window_start) + fuel;
+        totals.window_start = window_start;
+    }
+
+    /// The tenant's usage as of `now`
+    pub fn usage(&self, tenant_id: &str, now: u64) -> TenantUsage {
+        let window_start = self.window_start(now);
+        let tenants = self.tenants.lock().unwrap_or_else(|e| e.into_inner());
+        let totals = tenants.get(tenant_id);
+        let modules: Vec<ModuleUsage> = totals
+            .into_iter()
+            .flat_map(|totals| &totals.modules)
+            .map(|(module, &(invocations, fuel_consumed))| ModuleUsage {
+                module: module.clone(),
+                invocations,
+                fuel_consumed,
+                cost_cents: self.config.cost_cents(fuel_consumed),
+            })
+            .collect();
+        let fuel_consumed = modules.iter().map(|m| m.fuel_consumed).sum();
+        TenantUsage {
+            tenant_id: tenant_id.to_string(),
+            invocations: modules.iter().map(|m| m.invocations).sum(),
+            fuel_consumed,
+            cost_cents: self.config.cost_cents(fuel_consumed),
+            modules,
+            window_start,
+            window_fuel: totals.map_or(0, |totals| totals.fuel_in(window_start)),
+            ceiling: self.config.ceiling(tenant_id),
+        }
+    }
+
+    /// Drop a tenant's usage, as when it is purged
+    pub fn forget(&self, tenant_id: &str) {
+        self.tenants.lock().unwrap_or_else(|e| e.into_inner()).remove(tenant_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3_600_000;
+
+    fn meter(config: TenantFuelConfig) -> TenantMeter {
+        TenantMeter::new(config)
+    }
+
+    #[test]
+    fn test_usage_is_broken_down_by_module() {
+        let meter = meter(TenantFuelConfig { price_cents_per_billion: 250, ..Default::default() });
+        meter.record("acme", "accrual", 600_000_000, HOUR);
+        meter.record("acme", "accrual", 400_000_000, HOUR + 1);
+        meter.record("acme", "validate", 1, HOUR + 2);
+        meter.record("other", "accrual", 5, HOUR + 3);
+
+        let usage = meter.usage("acme", HOUR + 10);
+        assert_eq!(usage.invocations, 3);
+        assert_eq!(usage.fuel_consumed, 1_000_000_001);
+        assert_eq!(usage.cost_cents, Some(251));
+        assert_eq!(usage.modules[0], ModuleUsage {
+            module: "accrual".into(),
+            invocations: 2,
+            fuel_consumed: 1_000_000_000,
+            cost_cents: Some(250),
+        });
+        assert_eq!(usage.modules[1].cost_cents, Some(1));
+        assert_eq!(usage.ceiling, None);
+
+        meter.forget("acme");
+        assert_eq!(meter.usage("acme", HOUR).invocations, 0);
+        assert_eq!(meter.usage("other", HOUR).fuel_consumed, 5);
+        assert_eq!(TenantFuelConfig::default().cost_cents(1_000), None);
+    }
+
+    #[test]
+    fn test_ceiling_resets_each_window() {
+        let meter = meter(TenantFuelConfig {
+            default_ceiling: Some(100),
+            ceilings: BTreeMap::from([("big".to_string(), 1_000)]),
+            ..Default::default()
+        });
+        meter.record("acme", "accrual", 60, HOUR);
+        assert!(meter.check("acme", HOUR + 1).is_ok());
+        meter.record("acme", "accrual", 60, HOUR + 2);
+
+        let err = meter.check("acme", HOUR + 3).unwrap_err();
+        assert!(matches!(
+            err,
+            KernelError::FuelCeilingReached { used: 120, ceiling: 100, resets_at, .. } if resets_at == 2 * HOUR
+        ));
+        // The tenant's own ceiling and other tenants are unaffected
+        meter.record("big", "accrual", 120, HOUR);
+        assert!(meter.check("big", HOUR + 3).is_ok());
+        assert!(meter.check("small", HOUR + 3).is_ok());
+
+        assert!(meter.check("acme", 2 * HOUR).is_ok());
+        let usage = meter.usage("acme", 2 * HOUR);
+        assert_eq!((usage.window_start, usage.window_fuel, usage.fuel_consumed), (2 * HOUR, 0, 120));
+        meter.record("acme", "accrual", 10, 2 * HOUR + 5);
+        assert_eq!(meter.usage("acme", 2 * HOUR + 6).window_fuel, 10);
+    }
+}
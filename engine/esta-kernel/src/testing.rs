@@ -0,0 +1,306 @@
+//! Test Support
+//!
+//! [`TimeMachine`] runs a test on simulated time: tokio's clock is paused and
+//! the kernel's wall clock ([`crate::clock`]) follows it. Supervisor backoff,
+//! capability expiry, scheduled storage jobs, and archive retention can then
+//! be exercised across hours or days of simulated time in milliseconds.
+//!
+//! Enabled by the `testing` feature. Tests must run on a current-thread
+//! runtime (the `#[tokio::test]` default).
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn token_expires() {
+//!     let time = TimeMachine::start();
+//!     // ... issue a capability that expires in an hour
+//!     time.advance(Duration::from_secs(3601)).await;
+//!     // ... validation now fails with CapabilityError::Expired
+//! }
+//! ```
+
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::calendar::Date;
+use crate::clock;
+
+/// Simulated time for the current test
+///
+/// Starting one pauses tokio time and freezes the wall clock; time only moves
+/// through [`TimeMachine::advance`], or when every task is idle and tokio
+/// jumps to the next timer (timers fire on millisecond boundaries). Dropping
+/// it returns both clocks to real time.
+#[must_use = "time is only simulated while the TimeMachine is alive"]
+pub struct TimeMachine {
+    started_at: tokio::time::Instant,
+    /// Tied to the thread whose clocks it controls
+    _thread: PhantomData<*const ()>,
+}
+
+impl TimeMachine {
+    /// Simulate time starting from the current system time
+    ///
+    /// # Panics
+    /// Outside a current-thread tokio runtime, or if time is already paused.
+    pub fn start() -> Self {
+        Self::start_at(SystemTime::now())
+    }
+
+    /// Simulate time starting from a given wall-clock time
+    pub fn start_at(wall: SystemTime) -> Self {
+        tokio::time::pause();
+        clock::simulate(Some(wall));
+        Self { started_at: tokio::time::Instant::now(), _thread: PhantomData }
+    }
+
+    /// Simulate time starting at midnight UTC of a date
+    pub fn start_on(date: Date) -> Self {
+        let days = date.days_since_epoch().max(0) as u64;
+        Self::start_at(UNIX_EPOCH + Duration::from_secs(days * 86_400))
+    }
+
+    /// Move both clocks forward, firing every timer that comes due on the way
+    pub async fn advance(&self, by: Duration) {
+        tokio::time::advance(by).await;
+    }
+
+    /// Move forward to a wall-clock time; does nothing if it has passed
+    pub async fn advance_to(&self, wall: SystemTime) {
+        if let Ok(by) = wall.duration_since(self.now()) {
+            self.advance(by).await;
+        }
+    }
+
+    /// Let spawned tasks catch up with timers that are due, without moving time
+    pub async fn settle(&self) {
+        for _ in 0..16 {
+            tokio::time::sleep(Duration::ZERO).await;
+        }
+    }
+
+    /// Current simulated wall-clock time
+    pub fn now(&self) -> SystemTime {
+        clock::now()
+    }
+
+    /// Current simulated wall-clock time as Unix milliseconds
+    pub fn now_millis(&self) -> u64 {
+        clock::now_millis()
+    }
+
+    /// Current simulated date (UTC)
+    pub fn today(&self) -> Date {
+        Date::today()
+    }
+
+    /// Simulated time elapsed since the machine started
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+impl Drop for TimeMachine {
+    fn drop(&mut self) {
+        clock::simulate(None);
+        // Resuming panics outside a runtime; never turn a failing test into an abort
+        if !std::thread::panicking() {
+            tokio::time::resume();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::capabilities::{
+        CapabilityError, CapabilityManager, CapabilityRight, CapabilityValidity, ResourceType, DEFAULT_LEASE_MS,
+    };
+    use crate::supervisor::{ChildSpec, SeededJitter, Supervisor, SupervisorAction};
+    use std::sync::Arc;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    #[tokio::test]
+    async fn test_wall_clock_follows_simulated_time() {
+        let time = TimeMachine::start_on("2025-03-01".parse().unwrap());
+        assert_eq!(time.today().to_string(), "2025-03-01");
+
+        time.advance(DAY * 30).await;
+        assert_eq!(time.today().to_string(), "2025-03-31");
+        assert_eq!(time.elapsed(), DAY * 30);
+
+        time.advance_to(UNIX_EPOCH).await;
+        assert_eq!(time.elapsed(), DAY * 30, "time never runs backwards");
+    }
+
+    #[tokio::test]
+    async fn test_capability_expiry() {
+        let time = TimeMachine::start();
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let validity = CapabilityValidity {
+            expires_at: Some(time.now_millis() + 3_600_000),
+            ..Default::default()
+        };
+        let rights = [CapabilityRight::Read].into_iter().collect();
+        let token = manager
+            .create_capability(ResourceType::Module, "ledger".to_string(), rights, "test".to_string(), validity)
+            .await
+            .unwrap();
+
+        time.advance(Duration::from_secs(3599)).await;
+        assert!(manager.validate(&token, &[CapabilityRight::Read]).await.is_ok());
+        time.advance(Duration::from_secs(2)).await;
+        assert!(matches!(
+            manager.validate(&token, &[CapabilityRight::Read]).await,
+            Err(CapabilityError::Expired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_capability_lease_renewal() {
+        let time = TimeMachine::start();
+        let manager = CapabilityManager::new(CapabilityManager::generate_secret().unwrap());
+        let lease = |rights: &[CapabilityRight]| {
+            manager.create_capability(
+                ResourceType::Module,
+                "ledger".to_string(),
+                rights.iter().copied().collect(),
+                "accrual".to_string(),
+                CapabilityValidity::lease(DEFAULT_LEASE_MS, Some(3 * DEFAULT_LEASE_MS)),
+            )
+        };
+        let token = lease(&[CapabilityRight::Read, CapabilityRight::Renew]).await.unwrap();
+        let start = time.now_millis();
+
+        // Renewed while live, the lease outlasts its first five minutes
+        time.advance(Duration::from_secs(240)).await;
+        assert_eq!(manager.renew(&token, DEFAULT_LEASE_MS).await.unwrap(), start + 240_000 + DEFAULT_LEASE_MS);
+        time.advance(Duration::from_secs(240)).await;
+        assert!(manager.validate(&token, &[CapabilityRight::Read]).await.is_ok());
+        manager.renew(&token, DEFAULT_LEASE_MS).await.unwrap();
+
+        // ...but never past its lifetime ceiling
+        time.advance(Duration::from_secs(240)).await;
+        assert_eq!(manager.renew(&token, DEFAULT_LEASE_MS).await.unwrap(), start + 3 * DEFAULT_LEASE_MS);
+        time.advance(Duration::from_secs(181)).await;
+        assert!(matches!(manager.validate(&token, &[CapabilityRight::Read]).await, Err(CapabilityError::Expired)));
+        assert!(matches!(manager.renew(&token, DEFAULT_LEASE_MS).await, Err(CapabilityError::Expired)));
+
+        // Renewal takes the Renew right, and a lease to renew
+        let unrenewable = lease(&[CapabilityRight::Read]).await.unwrap();
+        assert!(matches!(
+            manager.renew(&unrenewable, DEFAULT_LEASE_MS).await,
+            Err(CapabilityError::InsufficientRights { .. })
+        ));
+        let permanent = manager
+            .create_capability(
+                ResourceType::Module,
+                "ledger".to_string(),
+                [CapabilityRight::Renew].into_iter().collect(),
+                "accrual".to_string(),
+                CapabilityValidity::default(),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(manager.renew(&permanent, DEFAULT_LEASE_MS).await, Err(CapabilityError::NotRenewable)));
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_backoff() {
+        let time = TimeMachine::start();
+        let supervisor = Supervisor::new_noop();
+        let spec = ChildSpec { id: "accrual".to_string(), ..ChildSpec::default() };
+        supervisor.register_child(spec).await.unwrap();
+
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            let action = supervisor.report_crash("accrual", "trap").await.unwrap();
+            let SupervisorAction::Restart { delay, .. } = action.clone() else {
+                panic!("expected a restart, got {:?}", action);
+            };
+            let before = time.elapsed();
+            supervisor.execute_restart("accrual", action).await.unwrap();
+            // Timers fire on millisecond boundaries
+            let waited = time.elapsed() - before;
+            assert!(waited >= delay && waited <= delay + Duration::from_millis(1), "{:?}", waited);
+            delays.push(delay);
+        }
+        assert_eq!(delays, [2, 4, 8].map(Duration::from_secs));
+        assert!(time.elapsed() < Duration::from_secs(15));
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_jittered_backoff() {
+        /// Restart "accrual" three times, returning the delays waited out
+        async fn restarts(time: &TimeMachine, seed: u64) -> Vec<Duration> {
+            let supervisor = Supervisor::new_noop().with_jitter(Arc::new(SeededJitter::new(seed)));
+            let spec = ChildSpec { id: "accrual".to_string(), jitter: 0.5, ..ChildSpec::default() };
+            supervisor.register_child(spec).await.unwrap();
+
+            let mut delays = Vec::new();
+            for _ in 0..3 {
+                let action = supervisor.report_crash("accrual", "trap").await.unwrap();
+                let SupervisorAction::Restart { delay, .. } = action.clone() else {
+                    panic!("expected a restart, got {:?}", action);
+                };
+                let before = time.elapsed();
+                supervisor.execute_restart("accrual", action).await.unwrap();
+                let waited = time.elapsed() - before;
+                assert!(waited >= delay && waited <= delay + Duration::from_millis(1), "{:?}", waited);
+                delays.push(delay);
+            }
+            delays
+        }
+
+        let time = TimeMachine::start();
+        let delays = restarts(&time, 42).await;
+        for (delay, full) in delays.iter().zip([2, 4, 8].map(Duration::from_secs)) {
+            assert!(*delay <= full && *delay >= full / 2, "{:?} of {:?}", delay, full);
+        }
+        assert_ne!(delays, [2, 4, 8].map(Duration::from_secs));
+        assert_eq!(restarts(&time, 42).await, delays, "same seed, same delays");
+    }
+
+    #[cfg(feature = "kernel")]
+    #[tokio::test]
+    async fn test_scheduled_vacuum_applies_archive_retention() {
+        use crate::archive::{ArchiveConfig, InvocationArchive};
+        use crate::security::audit::{AuditEventType, AuditLog, AuditLogConfig};
+        use crate::storage::{StorageLimits, StorageMaintenance};
+
+        let dir = tempfile::tempdir().unwrap();
+        let time = TimeMachine::start();
+        let archive = Arc::new(InvocationArchive::new(ArchiveConfig::default(), dir.path().join("archive")));
+        let old = archive.archive(b"input", b"output").await.unwrap();
+
+        let audit_log = Arc::new(AuditLog::new(AuditLogConfig::default()));
+        let limits = StorageLimits { archive_retention: Some(DAY * 30), ..Default::default() };
+        let storage = StorageMaintenance::new(audit_log.clone(), limits).with_archive(archive.clone());
+        let vacuums = || async {
+            audit_log
+                .get_all_entries()
+                .await
+                .iter()
+                .filter_map(|e| match e.event {
+                    AuditEventType::StorageVacuumed { archived_blobs_removed, .. } => Some(archived_blobs_removed),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let job = storage.schedule(DAY);
+        time.settle().await;
+        assert_eq!(vacuums().await, [0]);
+
+        time.advance(DAY * 29).await;
+        time.settle().await;
+        assert_eq!(vacuums().await.len(), 30);
+        assert!(archive.load(&old).await.is_ok());
+
+        time.advance(DAY * 2).await;
+        time.settle().await;
+        assert_eq!(vacuums().await.iter().sum::<usize>(), 2);
+        assert!(archive.load(&old).await.is_err());
+        job.abort();
+    }
+}
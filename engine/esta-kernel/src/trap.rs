@@ -0,0 +1,161 @@
+//! Module Trap Diagnostics
+//!
+//! When a module traps, the kernel captures the WASM backtrace and resolves
+//! each frame's function index to a name using the module's `name` custom
+//! section. The symbolized frames are recorded in the `ModuleCrashed` audit
+//! event and carried on the error returned to the caller, so module authors
+//! can see where a crash happened without reproducing it locally.
+//!
+//! Failed calls are also classified by [`TrapKind`], which is counted in a
+//! module's statistics, recorded on crash and failure audit events, and
+//! passed on to callers with the user-facing error.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::KernelError;
+
+/// Why a module call failed
+///
+/// Derived from an error's typed causes by [`TrapKind::of`]; failures that
+/// are not traps, such as a module declining a request through its response
+/// envelope, have no kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, thiserror::Error)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapKind {
+    /// Ran out of fuel, under `max_fuel` or its resource profile's limit
+    #[error("fuel exhausted")]
+    FuelExhausted,
+    /// Was refused memory past its limit, or exhausted its call stack
+    #[error("memory limit reached")]
+    MemoryLimit,
+    /// Executed `unreachable` or another trapping instruction (out-of-bounds
+    /// access, division by zero, ...)
+    #[error("module trapped")]
+    Unreachable,
+    /// A host function it called failed
+    #[error("host function failed")]
+    HostError,
+    /// Did not finish within the call timeout
+    #[error("call timed out")]
+    Timeout,
+}
+
+impl TrapKind {
+    /// Classify a failed call's error
+    ///
+    /// Uses wasmtime's `Trap` code, the interpreter's
+    /// [`KernelError::Trapped`], the kernel's timeout and resource profile
+    /// errors, and the `MemoryLimit` mark a backend adds when its store
+    /// refused to grow memory before the call failed. Errors a host function
+    /// returned carry a WASM backtrace but no trap code (with the
+    /// interpreter, they are wasmi's host errors).
+    pub fn of(error: &anyhow::Error) -> Option<TrapKind> {
+        #[cfg(feature = "kernel")]
+        if let Some(trap) = error.downcast_ref::<crate::kernel::ModuleTrap>() {
+            return Some(trap.kind);
+        }
+        match error.downcast_ref::<KernelError>() {
+            Some(KernelError::CallTimedOut { .. }) => return Some(TrapKind::Timeout),
+            Some(KernelError::ResourceAnomaly { resource, .. }) if resource == "memory" => {
+                return Some(TrapKind::MemoryLimit)
+            }
+            Some(KernelError::ResourceAnomaly { .. }) => return Some(TrapKind::FuelExhausted),
+            Some(KernelError::CapabilityDenied { .. }) => return Some(TrapKind::HostError),
+            Some(KernelError::OutputTooLarge { .. }) => return Some(TrapKind::MemoryLimit),
+            Some(KernelError::Trapped { kind: TrapKind::FuelExhausted, .. }) => return Some(TrapKind::FuelExhausted),
+            _ => {}
+        }
+        #[cfg(feature = "wasmtime")]
+        match error.downcast_ref::<wasmtime::Trap>() {
+            Some(wasmtime::Trap::OutOfFuel) => return Some(TrapKind::FuelExhausted),
+            Some(wasmtime::Trap::Interrupt) => return Some(TrapKind::Timeout),
+            _ => {}
+        }
+        if let Some(kind) = error.downcast_ref::<TrapKind>() {
+            return Some(*kind);
+        }
+        if let Some(KernelError::Trapped { kind, .. }) = error.downcast_ref::<KernelError>() {
+            return Some(*kind);
+        }
+        #[cfg(feature = "wasmtime")]
+        match error.downcast_ref::<wasmtime::Trap>() {
+            Some(wasmtime::Trap::StackOverflow) => return Some(TrapKind::MemoryLimit),
+            Some(_) => return Some(TrapKind::Unreachable),
+            None if error.downcast_ref::<wasmtime::WasmBacktrace>().is_some() => return Some(TrapKind::HostError),
+            None => {}
+        }
+        #[cfg(feature = "interpreter")]
+        if error
+            .downcast_ref::<wasmi::Error>()
+            .is_some_and(|e| matches!(e.kind(), wasmi::errors::ErrorKind::Host(_) | wasmi::errors::ErrorKind::Message(_)))
+        {
+            return Some(TrapKind::HostError);
+        }
+        None
+    }
+}
+
+/// One frame of a symbolized WASM backtrace (innermost frame first)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacktraceFrame {
+    /// Index of the function in the module's function index space
+    pub func_index: u32,
+    /// Function name from the module's name section, if present
+    pub func_name: Option<String>,
+    /// Byte offset of the trapping instruction within the module
+    pub module_offset: Option<usize>,
+    /// Byte offset of the trapping instruction within the function body
+    pub func_offset: Option<usize>,
+}
+
+impl fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.func_name {
+            Some(name) => write!(f, "{} (func[{}])", name, self.func_index)?,
+            None => write!(f, "func[{}]", self.func_index)?,
+        }
+        if let Some(offset) = self.module_offset {
+            write!(f, " @ {:#x}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// Render frames one per line, numbered from the innermost frame
+pub fn format_backtrace(frames: &[BacktraceFrame]) -> String {
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| format!("{:>3}: {}", i, frame))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_display() {
+        let frames = vec![
+            BacktraceFrame {
+                func_index: 2,
+                func_name: Some("divide".into()),
+                module_offset: Some(0x4a),
+                func_offset: Some(3),
+            },
+            BacktraceFrame {
+                func_index: 0,
+                func_name: None,
+                module_offset: None,
+                func_offset: None,
+            },
+        ];
+
+        assert_eq!(
+            format_backtrace(&frames),
+            "  0: divide (func[2]) @ 0x4a\n  1: func[0]"
+        );
+    }
+}
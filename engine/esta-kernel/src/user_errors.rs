@@ -0,0 +1,890 @@
+//! User-Facing Error Messages
+//!
+//! Raw error strings ("Checksum mismatch: expected 3f2a..., got 9b1c...") are
+//! meaningful to developers but not to the employers and employees using the
+//! applications. This module maps every kernel, capability, signature,
+//! tenant, report template, and storage error to:
+//!
+//! - a stable error code that frontends and support can key on,
+//! - a short, non-technical message in the user's language, and
+//! - a suggested remediation.
+//!
+//! The original error text is kept in `detail` for logs and support requests;
+//! it should not be shown as the primary message.
+
+use serde::Serialize;
+
+use crate::alerts::AlertError;
+use crate::calendar::DateError;
+use crate::error::{KernelError, StorageError};
+use crate::output_root::OutputPathError;
+use crate::report::template::TemplateError;
+use crate::security::{CapabilityError, SecretError, SignatureError, TrustError};
+use crate::statutes::StatuteError;
+use crate::tenant::TenantError;
+use crate::trap::TrapKind;
+
+/// Languages user messages are available in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Locale {
+    /// Pick a locale from a language tag such as `es-MX`; unknown tags fall back to English
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match language.as_str() {
+            "es" => Locale::Spanish,
+            _ => Locale::English,
+        }
+    }
+}
+
+/// Stable error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    ModuleNotLoaded,
+    ModuleNotAvailable,
+    CatalogUnavailable,
+    ModuleIntegrity,
+    SignatureRequired,
+    SignatureInvalid,
+    SignatureConfig,
+    ProfileRestricted,
+    ModuleIncompatible,
+    ModuleCrashed,
+    ResourceLimit,
+    Busy,
+    FuelCeiling,
+    ShuttingDown,
+    Cancelled,
+    InputTooLarge,
+    InputRejected,
+    CapabilityDenied,
+    CapabilityExpired,
+    SecretsLocked,
+    InsightsDisabled,
+    TenantIsolation,
+    TenantNotFound,
+    TenantExists,
+    TenantArchived,
+    TenantActive,
+    EmployeeNotFound,
+    InvalidTenantId,
+    InvalidPolicy,
+    InvalidTemplate,
+    TemplateNotFound,
+    StatuteUnavailable,
+    InvalidDate,
+    InvalidRequest,
+    ReadOnly,
+    NotSignedIn,
+    PermissionDenied,
+    StorageCorrupt,
+    StorageUnavailable,
+    AuditUnavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Every code, in declaration order
+    pub const ALL: [ErrorCode; 41] = [
+        ErrorCode::ModuleNotLoaded,
+        ErrorCode::ModuleNotAvailable,
+        ErrorCode::CatalogUnavailable,
+        ErrorCode::ModuleIntegrity,
+        ErrorCode::SignatureRequired,
+        ErrorCode::SignatureInvalid,
+        ErrorCode::SignatureConfig,
+        ErrorCode::ProfileRestricted,
+        ErrorCode::ModuleIncompatible,
+        ErrorCode::ModuleCrashed,
+        ErrorCode::ResourceLimit,
+        ErrorCode::Busy,
+        ErrorCode::FuelCeiling,
+        ErrorCode::ShuttingDown,
+        ErrorCode::Cancelled,
+        ErrorCode::InputTooLarge,
+        ErrorCode::InputRejected,
+        ErrorCode::CapabilityDenied,
+        ErrorCode::CapabilityExpired,
+        ErrorCode::SecretsLocked,
+        ErrorCode::InsightsDisabled,
+        ErrorCode::TenantIsolation,
+        ErrorCode::TenantNotFound,
+        ErrorCode::TenantExists,
+        ErrorCode::TenantArchived,
+        ErrorCode::TenantActive,
+        ErrorCode::EmployeeNotFound,
+        ErrorCode::InvalidTenantId,
+        ErrorCode::InvalidPolicy,
+        ErrorCode::InvalidTemplate,
+        ErrorCode::TemplateNotFound,
+        ErrorCode::StatuteUnavailable,
+        ErrorCode::InvalidDate,
+        ErrorCode::InvalidRequest,
+        ErrorCode::ReadOnly,
+        ErrorCode::NotSignedIn,
+        ErrorCode::PermissionDenied,
+        ErrorCode::StorageCorrupt,
+        ErrorCode::StorageUnavailable,
+        ErrorCode::AuditUnavailable,
+        ErrorCode::Internal,
+    ];
+
+    /// The code as sent to frontends (never changes once released)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ModuleNotLoaded => "MODULE_NOT_LOADED",
+            ErrorCode::ModuleNotAvailable => "MODULE_NOT_AVAILABLE",
+            ErrorCode::CatalogUnavailable => "CATALOG_UNAVAILABLE",
+            ErrorCode::ModuleIntegrity => "MODULE_INTEGRITY",
+            ErrorCode::SignatureRequired => "SIGNATURE_REQUIRED",
+            ErrorCode::SignatureInvalid => "SIGNATURE_INVALID",
+            ErrorCode::SignatureConfig => "SIGNATURE_CONFIG",
+            ErrorCode::ProfileRestricted => "PROFILE_RESTRICTED",
+            ErrorCode::ModuleIncompatible => "MODULE_INCOMPATIBLE",
+            ErrorCode::ModuleCrashed => "MODULE_CRASHED",
+            ErrorCode::ResourceLimit => "RESOURCE_LIMIT",
+            ErrorCode::Busy => "BUSY",
+            ErrorCode::FuelCeiling => "FUEL_CEILING",
+            ErrorCode::ShuttingDown => "SHUTTING_DOWN",
+            ErrorCode::Cancelled => "CANCELLED",
+            ErrorCode::InputTooLarge => "INPUT_TOO_LARGE",
+            ErrorCode::InputRejected => "INPUT_REJECTED",
+            ErrorCode::CapabilityDenied => "CAPABILITY_DENIED",
+            ErrorCode::CapabilityExpired => "CAPABILITY_EXPIRED",
+            ErrorCode::SecretsLocked => "SECRETS_LOCKED",
+            ErrorCode::InsightsDisabled => "INSIGHTS_DISABLED",
+            ErrorCode::TenantIsolation => "TENANT_ISOLATION",
+            ErrorCode::TenantNotFound => "TENANT_NOT_FOUND",
+            ErrorCode::TenantExists => "TENANT_EXISTS",
+            ErrorCode::TenantArchived => "TENANT_ARCHIVED",
+            ErrorCode::TenantActive => "TENANT_ACTIVE",
+            ErrorCode::EmployeeNotFound => "EMPLOYEE_NOT_FOUND",
+            ErrorCode::InvalidTenantId => "INVALID_TENANT_ID",
+            ErrorCode::InvalidPolicy => "INVALID_POLICY",
+            ErrorCode::InvalidTemplate => "INVALID_TEMPLATE",
+            ErrorCode::TemplateNotFound => "TEMPLATE_NOT_FOUND",
+            ErrorCode::StatuteUnavailable => "STATUTE_UNAVAILABLE",
+            ErrorCode::InvalidDate => "INVALID_DATE",
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::ReadOnly => "READ_ONLY",
+            ErrorCode::NotSignedIn => "NOT_SIGNED_IN",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::StorageCorrupt => "STORAGE_CORRUPT",
+            ErrorCode::StorageUnavailable => "STORAGE_UNAVAILABLE",
+            ErrorCode::AuditUnavailable => "AUDIT_UNAVAILABLE",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    /// Message and remediation for this code
+    fn text(&self, locale: Locale) -> (&'static str, &'static str) {
+        match locale {
+            Locale::English => self.english(),
+            Locale::Spanish => self.spanish(),
+        }
+    }
+
+    fn english(&self) -> (&'static str, &'static str) {
+        match self {
+            ErrorCode::ModuleNotLoaded => (
+                "The calculation engine for this task is not installed.",
+                "Install the required rule module, then try again.",
+            ),
+            ErrorCode::ModuleNotAvailable => (
+                "The requested rule module was not found in the modules folder.",
+                "Copy the module and its manifest into the modules folder, then refresh the list.",
+            ),
+            ErrorCode::CatalogUnavailable => (
+                "No modules folder is set up.",
+                "Ask your administrator to configure the modules folder.",
+            ),
+            ErrorCode::ModuleIntegrity => (
+                "A rule module failed its integrity check and was not loaded.",
+                "Reinstall the module from a trusted source.",
+            ),
+            ErrorCode::SignatureRequired => (
+                "This rule module is not signed, so it cannot be used.",
+                "Install a signed copy of the module from its publisher.",
+            ),
+            ErrorCode::SignatureInvalid => (
+                "This rule module's signature could not be verified.",
+                "Reinstall the module from a trusted source. Contact support if this continues.",
+            ),
+            ErrorCode::SignatureConfig => (
+                "Module signature checking is not set up correctly.",
+                "Ask your administrator to check the trusted signing key configuration.",
+            ),
+            ErrorCode::ProfileRestricted => (
+                "This installation's security settings do not allow this module.",
+                "Install the module through the module catalog, or ask your administrator to review the security profile.",
+            ),
+            ErrorCode::ModuleIncompatible => (
+                "This rule module is not compatible with this version of the application.",
+                "Install a version of the module built for this application.",
+            ),
+            ErrorCode::ModuleCrashed => (
+                "The calculation stopped unexpectedly.",
+                "Try again. If it keeps happening, contact support so the module can be fixed.",
+            ),
+            ErrorCode::ResourceLimit => (
+                "The calculation took too much time or memory and was stopped.",
+                "Try a smaller batch of records, or contact support.",
+            ),
+            ErrorCode::Busy => (
+                "Too many calculations are waiting right now.",
+                "Wait a moment and try again.",
+            ),
+            ErrorCode::FuelCeiling => (
+                "This employer has used its calculation allowance for now.",
+                "Try again later, or ask your administrator to raise the employer's limit.",
+            ),
+            ErrorCode::ShuttingDown => (
+                "The application is closing and cannot start new calculations.",
+                "Restart the application, then try again.",
+            ),
+            ErrorCode::Cancelled => (
+                "The calculation was cancelled before it finished.",
+                "Run it again when you are ready.",
+            ),
+            ErrorCode::InputTooLarge => (
+                "Too much data was sent at once.",
+                "Split the data into smaller batches and try again.",
+            ),
+            ErrorCode::InputRejected => (
+                "Some of the information provided was not accepted.",
+                "Check the entered values and try again.",
+            ),
+            ErrorCode::CapabilityDenied => (
+                "You do not have permission to do this.",
+                "Ask your administrator for access.",
+            ),
+            ErrorCode::CapabilityExpired => (
+                "Your access for this action has expired.",
+                "Sign in again or ask your administrator to renew access.",
+            ),
+            ErrorCode::SecretsLocked => (
+                "The saved security keys could not be unlocked.",
+                "Check the secrets passphrase, then restart the application.",
+            ),
+            ErrorCode::InsightsDisabled => (
+                "Usage insights are turned off for this employer.",
+                "Turn on usage insights in the employer's sick time policy to use this feature.",
+            ),
+            ErrorCode::TenantIsolation => (
+                "This information belongs to a different employer.",
+                "Make sure you are signed in to the correct employer account.",
+            ),
+            ErrorCode::TenantNotFound => (
+                "The employer account could not be found.",
+                "Check the employer account, or set up its sick time policy first.",
+            ),
+            ErrorCode::TenantExists => (
+                "An employer account with this ID already exists.",
+                "Choose a different ID, or use the existing account.",
+            ),
+            ErrorCode::TenantArchived => (
+                "This employer account is archived.",
+                "Archived accounts can be viewed but not changed. Create a new account to continue tracking.",
+            ),
+            ErrorCode::TenantActive => (
+                "This employer account is still active.",
+                "Archive the account before permanently deleting it.",
+            ),
+            ErrorCode::EmployeeNotFound => (
+                "The employee could not be found for this employer.",
+                "Check the employee ID, or import the employee's hours first.",
+            ),
+            ErrorCode::InvalidTenantId => (
+                "The employer account ID is not valid.",
+                "Use only letters, numbers, dashes, and underscores (up to 64 characters).",
+            ),
+            ErrorCode::InvalidPolicy => (
+                "The sick time policy settings are not valid.",
+                "Review the policy settings and effective date, then save again.",
+            ),
+            ErrorCode::InvalidTemplate => (
+                "The report template could not be used.",
+                "Check the template's columns and filter for typing mistakes, then save again.",
+            ),
+            ErrorCode::TemplateNotFound => (
+                "The report template could not be found.",
+                "Check the template name and version, or save the template first.",
+            ),
+            ErrorCode::StatuteUnavailable => (
+                "The sick time law for this location or date could not be loaded.",
+                "Install the latest rule pack, or check the location and date.",
+            ),
+            ErrorCode::InvalidDate => (
+                "A date was not in a recognized format.",
+                "Enter dates as YYYY-MM-DD.",
+            ),
+            ErrorCode::InvalidRequest => (
+                "The request could not be processed.",
+                "Check the information provided and try again.",
+            ),
+            ErrorCode::ReadOnly => (
+                "Changes cannot be made in this reporting window.",
+                "Make changes in the main application window instead.",
+            ),
+            ErrorCode::NotSignedIn => (
+                "You need to sign in first.",
+                "Sign in with your user name and password, then try again.",
+            ),
+            ErrorCode::PermissionDenied => (
+                "Your account is not allowed to do this.",
+                "Ask your employer's administrator if you need access.",
+            ),
+            ErrorCode::StorageCorrupt => (
+                "Saved records appear to be damaged.",
+                "Restore from a backup and contact support.",
+            ),
+            ErrorCode::StorageUnavailable => (
+                "Records could not be read or saved.",
+                "Check that the data folder exists and there is free disk space, then try again.",
+            ),
+            ErrorCode::AuditUnavailable => (
+                "Changes are paused because the compliance log cannot be saved.",
+                "Free up disk space or fix the audit folder's permissions. Changes resume once the log is saved again.",
+            ),
+            ErrorCode::Internal => (
+                "Something went wrong.",
+                "Try again. If it keeps happening, contact support.",
+            ),
+        }
+    }
+
+    fn spanish(&self) -> (&'static str, &'static str) {
+        match self {
+            ErrorCode::ModuleNotLoaded => (
+                "El motor de cálculo para esta tarea no está instalado.",
+                "Instale el módulo de reglas requerido e inténtelo de nuevo.",
+            ),
+            ErrorCode::ModuleNotAvailable => (
+                "No se encontró el módulo de reglas solicitado en la carpeta de módulos.",
+                "Copie el módulo y su manifiesto en la carpeta de módulos y actualice la lista.",
+            ),
+            ErrorCode::CatalogUnavailable => (
+                "No hay una carpeta de módulos configurada.",
+                "Pida a su administrador que configure la carpeta de módulos.",
+            ),
+            ErrorCode::ModuleIntegrity => (
+                "Un módulo de reglas no pasó la verificación de integridad y no se cargó.",
+                "Vuelva a instalar el módulo desde una fuente confiable.",
+            ),
+            ErrorCode::SignatureRequired => (
+                "Este módulo de reglas no está firmado y no se puede usar.",
+                "Instale una copia firmada del módulo de su editor.",
+            ),
+            ErrorCode::SignatureInvalid => (
+                "No se pudo verificar la firma de este módulo de reglas.",
+                "Vuelva a instalar el módulo desde una fuente confiable. Si continúa, contacte a soporte.",
+            ),
+            ErrorCode::SignatureConfig => (
+                "La verificación de firmas de módulos no está configurada correctamente.",
+                "Pida a su administrador que revise la clave de firma de confianza.",
+            ),
+            ErrorCode::ProfileRestricted => (
+                "La configuración de seguridad de esta instalación no permite este módulo.",
+                "Instale el módulo desde el catálogo de módulos o pida a su administrador que revise el perfil de seguridad.",
+            ),
+            ErrorCode::ModuleIncompatible => (
+                "Este módulo de reglas no es compatible con esta versión de la aplicación.",
+                "Instale una versión del módulo creada para esta aplicación.",
+            ),
+            ErrorCode::ModuleCrashed => (
+                "El cálculo se detuvo inesperadamente.",
+                "Inténtelo de nuevo. Si sigue ocurriendo, contacte a soporte.",
+            ),
+            ErrorCode::ResourceLimit => (
+                "El cálculo usó demasiado tiempo o memoria y se detuvo.",
+                "Pruebe con un lote de registros más pequeño o contacte a soporte.",
+            ),
+            ErrorCode::Busy => (
+                "Hay demasiados cálculos en espera en este momento.",
+                "Espere un momento e inténtelo de nuevo.",
+            ),
+            ErrorCode::FuelCeiling => (
+                "Este empleador ya usó su límite de cálculos por ahora.",
+                "Inténtelo más tarde o pida a su administrador que aumente el límite del empleador.",
+            ),
+            ErrorCode::ShuttingDown => (
+                "La aplicación se está cerrando y no puede iniciar nuevos cálculos.",
+                "Reinicie la aplicación e inténtelo de nuevo.",
+            ),
+            ErrorCode::Cancelled => (
+                "El cálculo se canceló antes de terminar.",
+                "Vuelva a ejecutarlo cuando esté listo.",
+            ),
+            ErrorCode::InputTooLarge => (
+                "Se enviaron demasiados datos a la vez.",
+                "Divida los datos en lotes más pequeños e inténtelo de nuevo.",
+            ),
+            ErrorCode::InputRejected => (
+                "Parte de la información proporcionada no fue aceptada.",
+                "Revise los valores ingresados e inténtelo de nuevo.",
+            ),
+            ErrorCode::CapabilityDenied => (
+                "No tiene permiso para realizar esta acción.",
+                "Pida acceso a su administrador.",
+            ),
+            ErrorCode::CapabilityExpired => (
+                "Su acceso para esta acción ha vencido.",
+                "Inicie sesión de nuevo o pida a su administrador que renueve el acceso.",
+            ),
+            ErrorCode::SecretsLocked => (
+                "No se pudieron desbloquear las claves de seguridad guardadas.",
+                "Revise la frase de contraseña de los secretos y reinicie la aplicación.",
+            ),
+            ErrorCode::InsightsDisabled => (
+                "Los análisis de uso están desactivados para este empleador.",
+                "Active los análisis de uso en la política de licencia por enfermedad del empleador.",
+            ),
+            ErrorCode::TenantIsolation => (
+                "Esta información pertenece a otro empleador.",
+                "Asegúrese de haber iniciado sesión en la cuenta de empleador correcta.",
+            ),
+            ErrorCode::TenantNotFound => (
+                "No se encontró la cuenta del empleador.",
+                "Revise la cuenta del empleador o configure primero su política de licencia por enfermedad.",
+            ),
+            ErrorCode::TenantExists => (
+                "Ya existe una cuenta de empleador con este ID.",
+                "Elija otro ID o use la cuenta existente.",
+            ),
+            ErrorCode::TenantArchived => (
+                "Esta cuenta de empleador está archivada.",
+                "Las cuentas archivadas se pueden consultar pero no modificar. Cree una cuenta nueva para seguir llevando el registro.",
+            ),
+            ErrorCode::TenantActive => (
+                "Esta cuenta de empleador sigue activa.",
+                "Archive la cuenta antes de eliminarla definitivamente.",
+            ),
+            ErrorCode::EmployeeNotFound => (
+                "No se encontró al empleado para este empleador.",
+                "Revise el ID del empleado o importe primero sus horas.",
+            ),
+            ErrorCode::InvalidTenantId => (
+                "El ID de la cuenta del empleador no es válido.",
+                "Use solo letras, números, guiones y guiones bajos (hasta 64 caracteres).",
+            ),
+            ErrorCode::InvalidPolicy => (
+                "La configuración de la política de licencia por enfermedad no es válida.",
+                "Revise la configuración y la fecha de vigencia, y guarde de nuevo.",
+            ),
+            ErrorCode::InvalidTemplate => (
+                "No se pudo usar la plantilla del informe.",
+                "Revise las columnas y el filtro de la plantilla en busca de errores, y guarde de nuevo.",
+            ),
+            ErrorCode::TemplateNotFound => (
+                "No se encontró la plantilla del informe.",
+                "Revise el nombre y la versión de la plantilla, o guarde primero la plantilla.",
+            ),
+            ErrorCode::StatuteUnavailable => (
+                "No se pudo cargar la ley de licencia por enfermedad para esta ubicación o fecha.",
+                "Instale el paquete de reglas más reciente o revise la ubicación y la fecha.",
+            ),
+            ErrorCode::InvalidDate => (
+                "Una fecha no tiene un formato reconocido.",
+                "Ingrese las fechas como AAAA-MM-DD.",
+            ),
+            ErrorCode::InvalidRequest => (
+                "No se pudo procesar la solicitud.",
+                "Revise la información proporcionada e inténtelo de nuevo.",
+            ),
+            ErrorCode::ReadOnly => (
+                "No se pueden hacer cambios en esta ventana de informes.",
+                "Haga los cambios en la ventana principal de la aplicación.",
+            ),
+            ErrorCode::NotSignedIn => (
+                "Primero debe iniciar sesión.",
+                "Inicie sesión con su nombre de usuario y contraseña, e inténtelo de nuevo.",
+            ),
+            ErrorCode::PermissionDenied => (
+                "Su cuenta no tiene permiso para hacer esto.",
+                "Pida acceso al administrador de su empleador si lo necesita.",
+            ),
+            ErrorCode::StorageCorrupt => (
+                "Los registros guardados parecen estar dañados.",
+                "Restaure desde una copia de seguridad y contacte a soporte.",
+            ),
+            ErrorCode::StorageUnavailable => (
+                "No se pudieron leer ni guardar los registros.",
+                "Verifique que la carpeta de datos exista y que haya espacio libre, e inténtelo de nuevo.",
+            ),
+            ErrorCode::AuditUnavailable => (
+                "Los cambios están en pausa porque no se puede guardar el registro de cumplimiento.",
+                "Libere espacio en disco o corrija los permisos de la carpeta de auditoría. Los cambios se reanudan cuando el registro vuelva a guardarse.",
+            ),
+            ErrorCode::Internal => (
+                "Algo salió mal.",
+                "Inténtelo de nuevo. Si sigue ocurriendo, contacte a soporte.",
+            ),
+        }
+    }
+}
+
+/// An error as presented to users
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserError {
+    /// Stable error code
+    pub code: &'static str,
+    /// Short, non-technical message
+    pub message: String,
+    /// What the user can do about it
+    pub remediation: String,
+    /// Original error text, for logs and support
+    pub detail: Option<String>,
+    /// How the module failed, for errors from a module call that trapped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trap: Option<TrapKind>,
+}
+
+impl UserError {
+    /// Build a user error for a code, with optional technical detail
+    pub fn new(code: ErrorCode, locale: Locale, detail: Option<String>) -> Self {
+        let (message, remediation) = code.text(locale);
+        Self {
+            code: code.as_str(),
+            message: message.to_string(),
+            remediation: remediation.to_string(),
+            detail,
+            trap: None,
+        }
+    }
+}
+
+/// Errors that can be mapped to a stable user-facing code
+pub trait UserFacing: std::fmt::Display {
+    /// The error code for this error
+    fn error_code(&self) -> ErrorCode;
+
+    /// The localized user error, keeping the original text as detail
+    fn to_user_error(&self, locale: Locale) -> UserError {
+        UserError::new(self.error_code(), locale, Some(self.to_string()))
+    }
+}
+
+impl UserFacing for KernelError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            KernelError::ModuleNotLoaded(_) => ErrorCode::ModuleNotLoaded,
+            KernelError::ModuleNotInCatalog(_) => ErrorCode::ModuleNotAvailable,
+            KernelError::VersionNotStored { .. } => ErrorCode::ModuleNotAvailable,
+            KernelError::NoCatalogConfigured => ErrorCode::CatalogUnavailable,
+            KernelError::ChecksumMismatch { .. } => ErrorCode::ModuleIntegrity,
+            KernelError::SignatureRequired(_) => ErrorCode::SignatureRequired,
+            KernelError::NoVerifierConfigured => ErrorCode::SignatureConfig,
+            KernelError::SignatureInvalid { source, .. } => source.error_code(),
+            KernelError::CatalogOnly => ErrorCode::ProfileRestricted,
+            KernelError::UnknownCapability { .. } => ErrorCode::ProfileRestricted,
+            KernelError::SandboxDenied { .. } => ErrorCode::ProfileRestricted,
+            KernelError::MissingCapability { .. } => ErrorCode::ModuleIncompatible,
+            KernelError::UnknownImport { .. } => ErrorCode::ModuleIncompatible,
+            KernelError::CapabilityDenied { .. } => ErrorCode::CapabilityDenied,
+            KernelError::WasiNotConfigured(_) => ErrorCode::ModuleIncompatible,
+            KernelError::InterpreterUnsupported { .. } => ErrorCode::ModuleIncompatible,
+            KernelError::MissingMemoryExport => ErrorCode::ModuleIncompatible,
+            KernelError::AbiIncompatible { .. } => ErrorCode::ModuleIncompatible,
+            KernelError::AbiVersionMissing(_) => ErrorCode::ModuleIncompatible,
+            KernelError::InputTooLarge(_) => ErrorCode::InputTooLarge,
+            KernelError::OutputTooLarge { .. } => ErrorCode::ResourceLimit,
+            KernelError::InputRejected(_) => ErrorCode::InputRejected,
+            KernelError::ModuleReported { error, .. } => match error.code.as_str() {
+                "INVALID_INPUT" => ErrorCode::InputRejected,
+                "INPUT_TOO_LARGE" => ErrorCode::InputTooLarge,
+                _ => ErrorCode::ModuleCrashed,
+            },
+            KernelError::Trapped { kind: TrapKind::FuelExhausted | TrapKind::MemoryLimit | TrapKind::Timeout, .. } => {
+                ErrorCode::ResourceLimit
+            }
+            KernelError::Trapped { .. } => ErrorCode::ModuleCrashed,
+            KernelError::CallTimedOut { .. } => ErrorCode::ResourceLimit,
+            KernelError::ResourceAnomaly { .. } => ErrorCode::ResourceLimit,
+            KernelError::QueueFull { .. } => ErrorCode::Busy,
+            KernelError::FuelCeilingReached { .. } => ErrorCode::FuelCeiling,
+            KernelError::ShuttingDown => ErrorCode::ShuttingDown,
+            KernelError::Cancelled { .. } => ErrorCode::Cancelled,
+            KernelError::InsightsNotEnabled(_) => ErrorCode::InsightsDisabled,
+            KernelError::InvalidRequest(_) => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+impl UserFacing for SignatureError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            SignatureError::InvalidSignature
+            | SignatureError::InvalidFormat(_)
+            | SignatureError::KeyRetired(_)
+            | SignatureError::UntrustedKey(_) => ErrorCode::SignatureInvalid,
+            SignatureError::MissingSignature => ErrorCode::SignatureRequired,
+            SignatureError::InvalidPublicKey | SignatureError::KeyGenerationFailed(_) => {
+                ErrorCode::SignatureConfig
+            }
+        }
+    }
+}
+
+impl UserFacing for TrustError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            TrustError::Io(_) => ErrorCode::StorageUnavailable,
+            TrustError::Corrupt(_) => ErrorCode::StorageCorrupt,
+            TrustError::InvalidKey(_) | TrustError::AlreadyTrusted(_) => ErrorCode::SignatureConfig,
+        }
+    }
+}
+
+impl UserFacing for AlertError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            AlertError::Io(_) => ErrorCode::StorageUnavailable,
+            AlertError::Corrupt(_) => ErrorCode::StorageCorrupt,
+            AlertError::WebhookNotAllowed(..) => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+impl UserFacing for CapabilityError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            CapabilityError::NotFound(_)
+            | CapabilityError::InsufficientRights { .. }
+            | CapabilityError::DelegationNotAllowed
+            | CapabilityError::InvalidToken
+            | CapabilityError::InstanceMismatch
+            | CapabilityError::NotRenewable
+            | CapabilityError::Unauthorized => ErrorCode::CapabilityDenied,
+            CapabilityError::Revoked | CapabilityError::Expired | CapabilityError::UsageLimitExceeded => {
+                ErrorCode::CapabilityExpired
+            }
+            CapabilityError::CrossTenant(_) => ErrorCode::TenantIsolation,
+        }
+    }
+}
+
+impl UserFacing for TenantError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            TenantError::InvalidTenantId(_) => ErrorCode::InvalidTenantId,
+            TenantError::UnknownTenant(_) => ErrorCode::TenantNotFound,
+            TenantError::AlreadyExists(_) => ErrorCode::TenantExists,
+            TenantError::Archived(_) => ErrorCode::TenantArchived,
+            TenantError::NotArchived(_) => ErrorCode::TenantActive,
+            TenantError::UnknownEmployee { .. } => ErrorCode::EmployeeNotFound,
+            TenantError::CrossTenantAccess { .. } => ErrorCode::TenantIsolation,
+            TenantError::UncheckedPayload(_) => ErrorCode::InputRejected,
+            TenantError::InvalidPolicy(_) => ErrorCode::InvalidPolicy,
+            TenantError::InvalidReportTemplate(_) => ErrorCode::InvalidTemplate,
+            TenantError::UnknownReportTemplate(_) => ErrorCode::TemplateNotFound,
+            TenantError::InvalidModuleConfig(_) => ErrorCode::InvalidRequest,
+            TenantError::Persistence(_) => ErrorCode::StorageUnavailable,
+            TenantError::ReadOnly => ErrorCode::ReadOnly,
+        }
+    }
+}
+
+impl UserFacing for TemplateError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            TemplateError::OutOfFuel { .. } => ErrorCode::ResourceLimit,
+            TemplateError::Invalid(_)
+            | TemplateError::Syntax { .. }
+            | TemplateError::Type(_)
+            | TemplateError::Arithmetic(_) => ErrorCode::InvalidTemplate,
+        }
+    }
+}
+
+impl UserFacing for SecretError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            SecretError::WrongKey => ErrorCode::SecretsLocked,
+            SecretError::Corrupt(_) => ErrorCode::StorageCorrupt,
+            SecretError::Io(_) => ErrorCode::StorageUnavailable,
+            SecretError::Rng => ErrorCode::Internal,
+        }
+    }
+}
+
+impl UserFacing for OutputPathError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            OutputPathError::Io(_) => ErrorCode::StorageUnavailable,
+            _ => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+impl UserFacing for StatuteError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode::StatuteUnavailable
+    }
+}
+
+impl UserFacing for StorageError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            StorageError::ReadOnly(_) => ErrorCode::ReadOnly,
+            StorageError::Corrupt(_) => ErrorCode::StorageCorrupt,
+            StorageError::NotFound(_) => ErrorCode::StorageUnavailable,
+            StorageError::AuditUnavailable(_) => ErrorCode::AuditUnavailable,
+            StorageError::Encryption(_) => ErrorCode::StorageUnavailable,
+            StorageError::Conflict(_) => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+impl UserFacing for DateError {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode::InvalidDate
+    }
+}
+
+impl UserFacing for std::io::Error {
+    fn error_code(&self) -> ErrorCode {
+        ErrorCode::StorageUnavailable
+    }
+}
+
+/// Find the error code for a typed error anywhere in an error chain
+fn chain_code(error: &anyhow::Error) -> Option<ErrorCode> {
+    for cause in error.chain() {
+        #[cfg(feature = "kernel")]
+        if cause.downcast_ref::<crate::kernel::ModuleTrap>().is_some() {
+            return Some(ErrorCode::ModuleCrashed);
+        }
+        #[cfg(feature = "wasmtime")]
+        {
+            if let Some(trap) = cause.downcast_ref::<wasmtime::Trap>() {
+                return Some(match trap {
+                    wasmtime::Trap::OutOfFuel | wasmtime::Trap::StackOverflow => ErrorCode::ResourceLimit,
+                    _ => ErrorCode::ModuleCrashed,
+                });
+            }
+        }
+
+        let code = cause
+            .downcast_ref::<KernelError>()
+            .map(UserFacing::error_code)
+            .or_else(|| cause.downcast_ref::<SignatureError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<CapabilityError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<TenantError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<TemplateError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<SecretError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<TrustError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<AlertError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<StatuteError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<StorageError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<DateError>().map(UserFacing::error_code))
+            .or_else(|| cause.downcast_ref::<std::io::Error>().map(UserFacing::error_code));
+        if code.is_some() {
+            return code;
+        }
+    }
+    None
+}
+
+/// Map any kernel error to a user error
+///
+/// Errors without a typed cause map to `INTERNAL`. Failed module calls keep
+/// their [`TrapKind`]; a crash after memory ran out is a resource limit.
+pub fn from_anyhow(error: &anyhow::Error, locale: Locale) -> UserError {
+    let code = chain_code(error).unwrap_or(ErrorCode::Internal);
+    let (code, trap) = match (code, TrapKind::of(error)) {
+        (ErrorCode::ModuleCrashed, Some(TrapKind::MemoryLimit)) => (ErrorCode::ResourceLimit, Some(TrapKind::MemoryLimit)),
+        classified => classified,
+    };
+    UserError { trap, ..UserError::new(code, locale, Some(format!("{:#}", error))) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("es-MX"), Locale::Spanish);
+        assert_eq!(Locale::from_tag("ES"), Locale::Spanish);
+        assert_eq!(Locale::from_tag("en_US"), Locale::English);
+        assert_eq!(Locale::from_tag("fr"), Locale::English);
+    }
+
+    #[test]
+    fn test_frontend_knows_every_code() {
+        // `Internal` is declared last, so every variant has a place in `ALL`
+        assert_eq!(ErrorCode::ALL.len(), ErrorCode::Internal as usize + 1);
+        assert!(ErrorCode::ALL.iter().enumerate().all(|(i, code)| *code as usize == i));
+
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../apps/frontend/src/services/kernel.ts");
+        let source = std::fs::read_to_string(&path).unwrap();
+        let union = source.split("export type KernelErrorCode =").nth(1).unwrap();
+        let union = &union[..union.find(';').unwrap()];
+        let frontend: std::collections::BTreeSet<&str> = union.split('\'').skip(1).step_by(2).collect();
+        let kernel: std::collections::BTreeSet<&str> = ErrorCode::ALL.iter().map(ErrorCode::as_str).collect();
+        assert_eq!(frontend, kernel, "KernelErrorCode in kernel.ts is out of date");
+    }
+
+    #[test]
+    fn test_typed_errors_map_to_codes() {
+        let user = TenantError::UnknownTenant("acme".into()).to_user_error(Locale::English);
+        assert_eq!(user.code, "TENANT_NOT_FOUND");
+        assert!(!user.message.contains("acme"));
+        assert_eq!(user.detail.as_deref(), Some("Tenant not found: acme"));
+
+        assert_eq!(CapabilityError::Expired.error_code(), ErrorCode::CapabilityExpired);
+        assert_eq!(SignatureError::InvalidPublicKey.error_code(), ErrorCode::SignatureConfig);
+        assert_eq!(
+            KernelError::SignatureInvalid {
+                module: "accrual".into(),
+                source: SignatureError::InvalidSignature,
+            }
+            .error_code(),
+            ErrorCode::SignatureInvalid
+        );
+    }
+
+    #[test]
+    fn test_anyhow_chain_is_searched() {
+        let error = anyhow::Error::new(KernelError::ModuleNotLoaded("accrual".into()))
+            .context("while running legacy accrue");
+        let user = from_anyhow(&error, Locale::Spanish);
+        assert_eq!(user.code, "MODULE_NOT_LOADED");
+        assert!(user.message.starts_with("El motor"));
+        assert!(user.detail.unwrap().contains("Module accrual is not loaded"));
+
+        let raw = anyhow::anyhow!("something unexpected");
+        assert_eq!(from_anyhow(&raw, Locale::English).code, "INTERNAL");
+    }
+
+    #[test]
+    fn test_module_reported_errors_keep_their_cause() {
+        let reported = |code: &str| KernelError::ModuleReported {
+            function: "accrue_json".into(),
+            error: crate::runtime::ModuleError { code: code.into(), message: "missing field `minutes_worked`".into() },
+        };
+        let user = from_anyhow(&reported("INVALID_INPUT").into(), Locale::English);
+        assert_eq!(user.code, "INPUT_REJECTED");
+        assert!(user.detail.unwrap().contains("INVALID_INPUT: missing field"));
+        assert_eq!(reported("INPUT_TOO_LARGE").error_code(), ErrorCode::InputTooLarge);
+        assert_eq!(reported("INTERNAL").error_code(), ErrorCode::ModuleCrashed);
+    }
+
+    #[test]
+    fn test_every_code_has_text_in_every_locale() {
+        let mut seen = std::collections::HashSet::new();
+        for code in ErrorCode::ALL {
+            assert!(seen.insert(code.as_str()), "duplicate code {}", code.as_str());
+            for locale in [Locale::English, Locale::Spanish] {
+                let (message, remediation) = code.text(locale);
+                assert!(!message.is_empty() && !remediation.is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,607 @@
+//! WASI Preview 1
+//!
+//! Some candidate rule modules are compiled for `wasm32-wasi` and import
+//! `wasi_snapshot_preview1`. When the kernel has a WASI root
+//! ([`crate::ExecutionConfig::wasi_root`]), modules granted the `wasi`
+//! capability get those imports, implemented here on the kernel's own linker
+//! so every file access is checked against the module's capabilities and
+//! audited.
+//!
+//! What a module can reach follows from its capabilities:
+//! - `fs_read:<dir>` preopens `/<dir>`, the directory `<dir>` under the WASI
+//!   root, read-only; `fs_write:<dir>` preopens it read-write
+//! - stdout and stderr go to the kernel log with `log` and are closed
+//!   otherwise; stdin is always empty
+//! - there are no arguments, environment variables, or clocks, and
+//!   `random_get` returns a fixed sequence, so runs replay exactly
+//!
+//! Every `path_open`, allowed or denied, is recorded as a `WasiFileAccess`
+//! audit event. Paths must be relative, may not contain `..`, and may not
+//! resolve through a symlink to outside their preopened directory. Preview 1
+//! calls not listed in [`SUPPORTED`] return `ENOSYS`, and `proc_exit` ends
+//! the call with an error.
+
+use anyhow::Result;
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{Caller, ExternType, Linker, Memory, Module, Trap, Val, ValType};
+
+use crate::security::AuditLog;
+
+pub use crate::runtime::WASI_MODULE;
+
+/// Preview 1 calls the kernel implements
+pub const SUPPORTED: &[&str] = &[
+    "args_get",
+    "args_sizes_get",
+    "environ_get",
+    "environ_sizes_get",
+    "fd_close",
+    "fd_fdstat_get",
+    "fd_filestat_get",
+    "fd_prestat_dir_name",
+    "fd_prestat_get",
+    "fd_read",
+    "fd_seek",
+    "fd_write",
+    "path_open",
+    "proc_exit",
+    "random_get",
+];
+
+/// WASI error numbers used here
+mod errno {
+    pub const SUCCESS: i32 = 0;
+    pub const ACCES: i32 = 2;
+    pub const BADF: i32 = 8;
+    pub const EXIST: i32 = 20;
+    pub const FAULT: i32 = 21;
+    pub const INVAL: i32 = 28;
+    pub const IO: i32 = 29;
+    pub const ISDIR: i32 = 31;
+    pub const NOENT: i32 = 44;
+    pub const NOSYS: i32 = 52;
+    pub const NOTDIR: i32 = 54;
+    pub const NOTCAPABLE: i32 = 76;
+}
+
+/// `path_open` open flags
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_DIRECTORY: i32 = 2;
+const OFLAGS_EXCL: i32 = 4;
+const OFLAGS_TRUNC: i32 = 8;
+/// `path_open` descriptor flag for appending writes
+const FDFLAGS_APPEND: i32 = 1;
+/// `fd_write` right, requested by guests opening a file for writing
+const RIGHTS_FD_WRITE: i64 = 1 << 6;
+
+/// File types reported by `fd_fdstat_get` and `fd_filestat_get`
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+
+/// First descriptor after stdin, stdout, and stderr
+const FIRST_FD: u32 = 3;
+
+/// A WASI call failed with this error number
+type Errno = i32;
+
+/// Directory a module may open files under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preopen {
+    /// Name the guest sees, as `/<name>`
+    pub name: String,
+    pub writable: bool,
+}
+
+enum Descriptor {
+    Dir { name: String, path: PathBuf, writable: bool },
+    File { file: File, writable: bool },
+}
+
+/// The guest called `proc_exit`
+#[derive(Debug, thiserror::Error)]
+#[error("Module exited with status {0}")]
+pub struct WasiExit(pub i32);
+
+/// One store's WASI state: its descriptors and where file access is audited
+pub struct WasiCtx {
+    module_name: String,
+    audit_log: Arc<AuditLog>,
+    stdio_to_log: bool,
+    fds: BTreeMap<u32, Descriptor>,
+    next_fd: u32,
+    /// State of the fixed `random_get` sequence
+    random_state: u64,
+}
+
+impl WasiCtx {
+    /// Context preopening `preopens` under `root`
+    pub fn new(module_name: &str, root: &Path, preopens: &[Preopen], stdio_to_log: bool, audit_log: Arc<AuditLog>) -> Self {
+        let fds: BTreeMap<u32, Descriptor> = (FIRST_FD..)
+            .zip(preopens)
+            .map(|(fd, preopen)| {
+                let path = root.join(&preopen.name);
+                (fd, Descriptor::Dir { name: preopen.name.clone(), path, writable: preopen.writable })
+            })
+            .collect();
+        Self {
+            module_name: module_name.to_string(),
+            audit_log,
+            stdio_to_log,
+            next_fd: FIRST_FD + fds.len() as u32,
+            fds,
+            random_state: 0,
+        }
+    }
+
+    /// Next value of the `random_get` sequence (SplitMix64)
+    fn next_random(&mut self) -> u64 {
+        self.random_state = self.random_state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.random_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// The path the guest sees for `path` under a descriptor
+    fn guest_path(&self, dir_fd: u32, path: &str) -> String {
+        match self.fds.get(&dir_fd) {
+            Some(Descriptor::Dir { name, .. }) => format!("/{}/{}", name, path),
+            _ => path.to_string(),
+        }
+    }
+
+    /// Resolve a guest path under a preopened directory descriptor to a host path
+    fn resolve(&self, dir_fd: u32, path: &str, write: bool) -> std::result::Result<PathBuf, (Errno, String)> {
+        let (name, dir, writable) = match self.fds.get(&dir_fd) {
+            Some(Descriptor::Dir { name, path, writable }) => (name, path, *writable),
+            Some(Descriptor::File { .. }) => return Err((errno::NOTDIR, "not a directory".into())),
+            None => return Err((errno::BADF, "bad descriptor".into())),
+        };
+        if write && !writable {
+            return Err((errno::NOTCAPABLE, format!("/{} is read-only", name)));
+        }
+        let relative = Path::new(path);
+        if path.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err((errno::NOTCAPABLE, format!("{} escapes /{}", path, name)));
+        }
+
+        // A symlink may point outside the directory: the nearest existing
+        // ancestor must still canonicalize to somewhere inside it
+        let full = dir.join(relative);
+        let canonical_dir = dir.canonicalize().map_err(|e| (errno::NOENT, e.to_string()))?;
+        let mut existing = full.as_path();
+        while existing != dir && existing.symlink_metadata().is_err() {
+            existing = existing.parent().unwrap_or(dir);
+        }
+        match existing.canonicalize() {
+            Ok(canonical) if canonical.starts_with(&canonical_dir) => Ok(full),
+            Ok(_) => Err((errno::NOTCAPABLE, format!("{} escapes /{}", path, name))),
+            Err(e) => Err((errno::NOENT, e.to_string())),
+        }
+    }
+
+    fn open(
+        &mut self,
+        dir_fd: u32,
+        path: &str,
+        oflags: i32,
+        write: bool,
+        append: bool,
+    ) -> (std::result::Result<u32, Errno>, String, Option<String>) {
+        let guest_path = self.guest_path(dir_fd, path);
+        let host_path = match self.resolve(dir_fd, path, write) {
+            Ok(host_path) => host_path,
+            Err((code, reason)) => return (Err(code), guest_path, Some(reason)),
+        };
+        if oflags & OFLAGS_DIRECTORY != 0 || host_path.is_dir() {
+            return (Err(errno::ISDIR), guest_path, Some("directories cannot be opened".into()));
+        }
+
+        let mut options = OpenOptions::new();
+        options
+            .read(true)
+            .write(write && !append)
+            .append(append)
+            .create(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL == 0)
+            .create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+            .truncate(oflags & OFLAGS_TRUNC != 0);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_NOFOLLOW);
+        }
+        match options.open(&host_path) {
+            Ok(file) => {
+                let fd = self.next_fd;
+                self.next_fd += 1;
+                self.fds.insert(fd, Descriptor::File { file, writable: write });
+                (Ok(fd), guest_path, None)
+            }
+            Err(e) => {
+                let code = match e.kind() {
+                    std::io::ErrorKind::NotFound => errno::NOENT,
+                    std::io::ErrorKind::AlreadyExists => errno::EXIST,
+                    std::io::ErrorKind::PermissionDenied => errno::ACCES,
+                    _ => errno::IO,
+                };
+                (Err(code), guest_path, Some(e.to_string()))
+            }
+        }
+    }
+}
+
+/// Guest memory of the calling instance
+fn memory<T>(caller: &mut Caller<'_, T>) -> std::result::Result<Memory, Errno> {
+    caller.get_export("memory").and_then(|export| export.into_memory()).ok_or(errno::FAULT)
+}
+
+fn read_bytes<T>(caller: &mut Caller<'_, T>, ptr: i32, len: u32) -> std::result::Result<Vec<u8>, Errno> {
+    let memory = memory(caller)?;
+    let mut bytes = vec![0u8; len as usize];
+    memory.read(&*caller, ptr as u32 as usize, &mut bytes).map_err(|_| errno::FAULT)?;
+    Ok(bytes)
+}
+
+fn write_bytes<T>(caller: &mut Caller<'_, T>, ptr: i32, bytes: &[u8]) -> std::result::Result<(), Errno> {
+    let memory = memory(caller)?;
+    memory.write(&mut *caller, ptr as u32 as usize, bytes).map_err(|_| errno::FAULT)
+}
+
+/// The `(pointer, length)` pairs of an iovec array
+fn read_iovecs<T>(caller: &mut Caller<'_, T>, iovs: i32, count: i32) -> std::result::Result<Vec<(i32, u32)>, Errno> {
+    let count = u32::try_from(count).map_err(|_| errno::INVAL)?;
+    let raw = read_bytes(caller, iovs, count.checked_mul(8).ok_or(errno::INVAL)?)?;
+    Ok(raw
+        .chunks_exact(8)
+        .map(|iov| {
+            let ptr = u32::from_le_bytes([iov[0], iov[1], iov[2], iov[3]]) as i32;
+            (ptr, u32::from_le_bytes([iov[4], iov[5], iov[6], iov[7]]))
+        })
+        .collect())
+}
+
+/// Turn a call's outcome into its error number
+fn code(result: std::result::Result<(), Errno>) -> i32 {
+    result.err().unwrap_or(errno::SUCCESS)
+}
+
+/// Add `wasi_snapshot_preview1` to a linker for `module`
+///
+/// `ctx` finds the store's WASI state; calls from a store without one fail
+/// with `ENOSYS`. Preview 1 imports the module uses that are not in
+/// [`SUPPORTED`] are defined to return `ENOSYS` (or trap, if they return
+/// nothing), so such modules still instantiate.
+pub fn add_to_linker<T: Send + 'static>(
+    linker: &mut Linker<T>,
+    module: &Module,
+    ctx: fn(&mut T) -> Option<&mut WasiCtx>,
+) -> Result<()> {
+    // No arguments and no environment
+    for (sizes, values) in [("args_sizes_get", "args_get"), ("environ_sizes_get", "environ_get")] {
+        linker.func_wrap(WASI_MODULE, sizes, |mut caller: Caller<'_, T>, count_ptr: i32, size_ptr: i32| -> i32 {
+            code(write_bytes(&mut caller, count_ptr, &0u32.to_le_bytes())
+                .and_then(|()| write_bytes(&mut caller, size_ptr, &0u32.to_le_bytes())))
+        })?;
+        linker.func_wrap(WASI_MODULE, values, |_: Caller<'_, T>, _: i32, _: i32| -> i32 { errno::SUCCESS })?;
+    }
+
+    linker.func_wrap(WASI_MODULE, "proc_exit", |_: Caller<'_, T>, status: i32| -> Result<()> {
+        Err(WasiExit(status).into())
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "random_get", move |mut caller: Caller<'_, T>, buf: i32, len: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        let len = len.max(0) as usize;
+        let mut bytes = Vec::with_capacity(len + 8);
+        while bytes.len() < len {
+            bytes.extend_from_slice(&wasi.next_random().to_le_bytes());
+        }
+        bytes.truncate(len);
+        code(write_bytes(&mut caller, buf, &bytes))
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "fd_write", move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, written_ptr: i32| -> i32 {
+        let result = (|| {
+            let mut data = Vec::new();
+            for (ptr, len) in read_iovecs(&mut caller, iovs, iovs_len)? {
+                data.extend(read_bytes(&mut caller, ptr, len)?);
+            }
+            let wasi = ctx(caller.data_mut()).ok_or(errno::NOSYS)?;
+            match (fd as u32, wasi.fds.get_mut(&(fd as u32))) {
+                (1 | 2, _) if wasi.stdio_to_log => {
+                    let stream = if fd == 1 { "stdout" } else { "stderr" };
+                    info!("[{}] {}: {}", wasi.module_name, stream, String::from_utf8_lossy(&data).trim_end());
+                }
+                (_, Some(Descriptor::File { file, writable: true, .. })) => {
+                    file.write_all(&data).map_err(|_| errno::IO)?;
+                }
+                (_, Some(Descriptor::File { .. })) => return Err(errno::NOTCAPABLE),
+                (_, Some(Descriptor::Dir { .. })) => return Err(errno::ISDIR),
+                _ => return Err(errno::BADF),
+            }
+            write_bytes(&mut caller, written_ptr, &(data.len() as u32).to_le_bytes())
+        })();
+        code(result)
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "fd_read", move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, read_ptr: i32| -> i32 {
+        let result = (|| {
+            let iovecs = read_iovecs(&mut caller, iovs, iovs_len)?;
+            let wasi = ctx(caller.data_mut()).ok_or(errno::NOSYS)?;
+            let mut chunks = Vec::with_capacity(iovecs.len());
+            match wasi.fds.get_mut(&(fd as u32)) {
+                // stdin is always at end of file
+                None if fd == 0 => {}
+                Some(Descriptor::File { file, .. }) => {
+                    for (ptr, len) in iovecs {
+                        let mut chunk = vec![0u8; len as usize];
+                        let n = file.read(&mut chunk).map_err(|_| errno::IO)?;
+                        chunk.truncate(n);
+                        chunks.push((ptr, chunk));
+                        if n < len as usize {
+                            break;
+                        }
+                    }
+                }
+                Some(Descriptor::Dir { .. }) => return Err(errno::ISDIR),
+                None => return Err(errno::BADF),
+            }
+            let mut total = 0u32;
+            for (ptr, chunk) in chunks {
+                write_bytes(&mut caller, ptr, &chunk)?;
+                total += chunk.len() as u32;
+            }
+            write_bytes(&mut caller, read_ptr, &total.to_le_bytes())
+        })();
+        code(result)
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "fd_seek", move |mut caller: Caller<'_, T>, fd: i32, offset: i64, whence: i32, new_offset_ptr: i32| -> i32 {
+        let result = (|| {
+            let wasi = ctx(caller.data_mut()).ok_or(errno::NOSYS)?;
+            let Some(Descriptor::File { file, .. }) = wasi.fds.get_mut(&(fd as u32)) else {
+                return Err(errno::BADF);
+            };
+            let from = match whence {
+                0 => SeekFrom::Start(u64::try_from(offset).map_err(|_| errno::INVAL)?),
+                1 => SeekFrom::Current(offset),
+                2 => SeekFrom::End(offset),
+                _ => return Err(errno::INVAL),
+            };
+            let position = file.seek(from).map_err(|_| errno::INVAL)?;
+            write_bytes(&mut caller, new_offset_ptr, &position.to_le_bytes())
+        })();
+        code(result)
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "fd_close", move |mut caller: Caller<'_, T>, fd: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        match fd {
+            0..=2 => errno::SUCCESS,
+            _ if wasi.fds.remove(&(fd as u32)).is_some() => errno::SUCCESS,
+            _ => errno::BADF,
+        }
+    })?;
+
+    // Rights are checked when files are opened, so every descriptor reports all of them
+    linker.func_wrap(WASI_MODULE, "fd_fdstat_get", move |mut caller: Caller<'_, T>, fd: i32, stat_ptr: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        let filetype = match wasi.fds.get(&(fd as u32)) {
+            None if (0..=2).contains(&fd) => FILETYPE_CHARACTER_DEVICE,
+            Some(Descriptor::Dir { .. }) => FILETYPE_DIRECTORY,
+            Some(Descriptor::File { .. }) => FILETYPE_REGULAR_FILE,
+            None => return errno::BADF,
+        };
+        let mut stat = [0u8; 24];
+        stat[0] = filetype;
+        stat[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        stat[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        code(write_bytes(&mut caller, stat_ptr, &stat))
+    })?;
+
+    // Sizes only: timestamps are zero so they cannot make runs differ
+    linker.func_wrap(WASI_MODULE, "fd_filestat_get", move |mut caller: Caller<'_, T>, fd: i32, stat_ptr: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        let (filetype, size) = match wasi.fds.get(&(fd as u32)) {
+            Some(Descriptor::File { file, .. }) => match file.metadata() {
+                Ok(metadata) => (FILETYPE_REGULAR_FILE, metadata.len()),
+                Err(_) => return errno::IO,
+            },
+            Some(Descriptor::Dir { .. }) => (FILETYPE_DIRECTORY, 0),
+            None if (0..=2).contains(&fd) => (FILETYPE_CHARACTER_DEVICE, 0),
+            None => return errno::BADF,
+        };
+        let mut stat = [0u8; 64];
+        stat[16] = filetype;
+        stat[24..32].copy_from_slice(&1u64.to_le_bytes());
+        stat[32..40].copy_from_slice(&size.to_le_bytes());
+        code(write_bytes(&mut caller, stat_ptr, &stat))
+    })?;
+
+    // Preopened directories, enumerated by the guest's libc at startup
+    linker.func_wrap(WASI_MODULE, "fd_prestat_get", move |mut caller: Caller<'_, T>, fd: i32, prestat_ptr: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        let Some(Descriptor::Dir { name, .. }) = wasi.fds.get(&(fd as u32)) else { return errno::BADF };
+        let mut prestat = [0u8; 8];
+        prestat[4..8].copy_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+        code(write_bytes(&mut caller, prestat_ptr, &prestat))
+    })?;
+
+    linker.func_wrap(WASI_MODULE, "fd_prestat_dir_name", move |mut caller: Caller<'_, T>, fd: i32, path_ptr: i32, path_len: i32| -> i32 {
+        let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+        let Some(Descriptor::Dir { name, .. }) = wasi.fds.get(&(fd as u32)) else { return errno::BADF };
+        let path = format!("/{}", name);
+        if path.len() > path_len.max(0) as usize {
+            return errno::INVAL;
+        }
+        code(write_bytes(&mut caller, path_ptr, path.as_bytes()))
+    })?;
+
+    linker.func_wrap9_async(
+        WASI_MODULE,
+        "path_open",
+        move |mut caller: Caller<'_, T>,
+              dir_fd: i32,
+              _lookup_flags: i32,
+              path_ptr: i32,
+              path_len: i32,
+              oflags: i32,
+              rights_base: i64,
+              _rights_inheriting: i64,
+              fdflags: i32,
+              fd_ptr: i32| {
+            Box::new(async move {
+                let path = match read_bytes(&mut caller, path_ptr, path_len.max(0) as u32).map(String::from_utf8) {
+                    Ok(Ok(path)) => path,
+                    Ok(Err(_)) => return errno::INVAL,
+                    Err(code) => return code,
+                };
+                let Some(wasi) = ctx(caller.data_mut()) else { return errno::NOSYS };
+                let append = fdflags & FDFLAGS_APPEND != 0;
+                let write = rights_base & RIGHTS_FD_WRITE != 0 || append || oflags & (OFLAGS_CREAT | OFLAGS_TRUNC) != 0;
+                let (opened, guest_path, reason) = wasi.open(dir_fd as u32, &path, oflags, write, append);
+
+                let module_name = wasi.module_name.clone();
+                let audit_log = wasi.audit_log.clone();
+                if let Some(reason) = &reason {
+                    warn!("[{}] WASI open of {} denied: {}", module_name, guest_path, reason);
+                }
+                audit_log
+                    .log_wasi_file_access(&module_name, &guest_path, write, opened.is_ok(), reason, &module_name)
+                    .await;
+
+                match opened {
+                    Ok(fd) => code(write_bytes(&mut caller, fd_ptr, &fd.to_le_bytes())),
+                    Err(code) => code,
+                }
+            })
+        },
+    )?;
+
+    for import in module.imports().filter(|import| import.module() == WASI_MODULE) {
+        if SUPPORTED.contains(&import.name()) {
+            continue;
+        }
+        if let ExternType::Func(ty) = import.ty() {
+            let returns_errno = ty.results().eq([ValType::I32]);
+            linker.func_new(WASI_MODULE, import.name(), ty, move |_, _, results| {
+                if !returns_errno {
+                    return Err(Trap::UnreachableCodeReached.into());
+                }
+                results[0] = Val::I32(errno::NOSYS);
+                Ok(())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::audit::AuditEventType;
+    use wasmtime::{Config, Engine, Instance, Store};
+
+    /// Opens `path` in the first preopen and copies up to 64 bytes of it to
+    /// stdout; with a non-zero `write`, first writes `hello` into it
+    const CAT_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "path_open"
+            (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "clock_time_get" (func $clock (param i32 i64 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 200) "hello")
+          (func (export "cat") (param $path i32) (param $len i32) (param $write i32) (result i32)
+            (local $err i32)
+            (local.set $err (call $path_open (i32.const 3) (i32.const 0) (local.get $path) (local.get $len)
+              (select (i32.const 9) (i32.const 0) (local.get $write))
+              (select (i64.const 64) (i64.const 2) (local.get $write)) (i64.const 0) (i32.const 0) (i32.const 100)))
+            (if (local.get $err) (then (return (local.get $err))))
+            (if (local.get $write) (then
+              (i32.store (i32.const 104) (i32.const 200))
+              (i32.store (i32.const 108) (i32.const 5))
+              (return (call $fd_write (i32.load (i32.const 100)) (i32.const 104) (i32.const 1) (i32.const 112)))))
+            (i32.store (i32.const 104) (i32.const 300))
+            (i32.store (i32.const 108) (i32.const 64))
+            (local.set $err (call $fd_read (i32.load (i32.const 100)) (i32.const 104) (i32.const 1) (i32.const 112)))
+            (if (local.get $err) (then (return (local.get $err))))
+            (i32.store (i32.const 108) (i32.load (i32.const 112)))
+            (call $fd_write (i32.const 1) (i32.const 104) (i32.const 1) (i32.const 112)))
+          (func (export "clock") (result i32)
+            (call $clock (i32.const 0) (i64.const 0) (i32.const 400))))
+    "#;
+
+    async fn cat(store: &mut Store<WasiCtx>, instance: &Instance, path: &str, write: bool) -> i32 {
+        let memory = instance.get_memory(&mut *store, "memory").unwrap();
+        memory.write(&mut *store, 0, path.as_bytes()).unwrap();
+        let cat = instance.get_typed_func::<(i32, i32, i32), i32>(&mut *store, "cat").unwrap();
+        cat.call_async(&mut *store, (0, path.len() as i32, write as i32)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_file_access_follows_preopens() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("rules")).unwrap();
+        std::fs::write(root.path().join("rules/mi.json"), b"{\"rate\":30}").unwrap();
+        std::fs::write(root.path().join("secret.txt"), b"nope").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.path().join("secret.txt"), root.path().join("rules/link")).unwrap();
+
+        let mut config = Config::new();
+        config.async_support(true);
+        let engine = Engine::new(&config).unwrap();
+        let module = Module::new(&engine, CAT_WAT).unwrap();
+        let audit_log = Arc::new(AuditLog::with_defaults());
+        let preopens = [Preopen { name: "rules".into(), writable: false }];
+        let wasi = WasiCtx::new("rules", root.path(), &preopens, true, audit_log.clone());
+
+        let mut linker = Linker::new(&engine);
+        add_to_linker(&mut linker, &module, |wasi: &mut WasiCtx| Some(wasi)).unwrap();
+        let mut store = Store::new(&engine, wasi);
+        let instance = linker.instantiate_async(&mut store, &module).await.unwrap();
+
+        assert_eq!(cat(&mut store, &instance, "mi.json", false).await, errno::SUCCESS);
+        assert_eq!(cat(&mut store, &instance, "../secret.txt", false).await, errno::NOTCAPABLE);
+        assert_eq!(cat(&mut store, &instance, "/etc/passwd", false).await, errno::NOTCAPABLE);
+        assert_eq!(cat(&mut store, &instance, "missing.json", false).await, errno::NOENT);
+        assert_eq!(cat(&mut store, &instance, "out.json", true).await, errno::NOTCAPABLE);
+        #[cfg(unix)]
+        assert_eq!(cat(&mut store, &instance, "link", false).await, errno::NOTCAPABLE);
+        assert!(!root.path().join("rules/out.json").exists());
+
+        let clock = instance.get_typed_func::<(), i32>(&mut store, "clock").unwrap();
+        assert_eq!(clock.call_async(&mut store, ()).await.unwrap(), errno::NOSYS);
+
+        let accesses: Vec<(String, bool)> = audit_log
+            .get_all_entries()
+            .await
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                AuditEventType::WasiFileAccess { path, allowed, .. } => Some((path, allowed)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(accesses[0], ("/rules/mi.json".to_string(), true));
+        assert!(accesses[1..].iter().all(|(_, allowed)| !allowed));
+        assert_eq!(accesses[4], ("/rules/out.json".to_string(), false));
+
+        // A writable preopen accepts the same write
+        let preopens = [Preopen { name: "rules".into(), writable: true }];
+        let wasi = WasiCtx::new("rules", root.path(), &preopens, false, audit_log);
+        let mut store = Store::new(&engine, wasi);
+        let instance = linker.instantiate_async(&mut store, &module).await.unwrap();
+        assert_eq!(cat(&mut store, &instance, "out.json", true).await, errno::SUCCESS);
+        assert_eq!(std::fs::read(root.path().join("rules/out.json")).unwrap(), b"hello");
+        // Without the log capability stdout is closed
+        assert_eq!(cat(&mut store, &instance, "mi.json", false).await, errno::BADF);
+    }
+}
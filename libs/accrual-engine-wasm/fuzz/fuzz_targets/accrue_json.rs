@@ -0,0 +1,23 @@
+// Feeds arbitrary bytes through the JSON boundary of `accrue_json`:
+// malformed JSON, truncated requests, and oversized payloads must all be
+// answered without panicking, which would trap the guest.
+
+#![no_main]
+
+use accrual_engine_wasm::{accrue_json_slice, AccrualOutput, Envelope};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let output = accrue_json_slice(data);
+
+    // Every answer is an envelope holding either an accrual or an error
+    let envelope: Envelope<AccrualOutput> = serde_json::from_slice(&output).expect("output is an envelope");
+    match (envelope.ok, envelope.data, envelope.error) {
+        (true, Some(accrual), None) => assert!(accrual.metadata.contains_key("calc")),
+        (false, None, Some(error)) => {
+            let expected = if data.len() > 1_048_576 { "INPUT_TOO_LARGE" } else { "INVALID_INPUT" };
+            assert_eq!(error.code, expected);
+        }
+        _ => panic!("envelope must carry exactly one of data and error"),
+    }
+});
@@ -0,0 +1,143 @@
+// Deterministic bump allocator for the guest.
+//
+// Every allocation comes from one fixed arena in linear memory, so a run's
+// addresses depend only on its inputs: no `memory.grow`, no free lists shaped
+// by earlier calls. Freeing the newest allocation rolls the arena back, and
+// once nothing is live the arena starts over, so each host call (which frees
+// everything it allocated apart from its input and output buffers) reuses the
+// same space. Running out of arena is an allocation failure, which traps.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{Cell, UnsafeCell};
+use core::ptr;
+
+/// Arena storage, aligned for any type the guest allocates
+#[repr(C, align(16))]
+struct Heap<const SIZE: usize>([u8; SIZE]);
+
+/// Bump allocator over a `SIZE`-byte arena; the guest's global allocator on wasm32
+pub struct BumpAllocator<const SIZE: usize> {
+    heap: UnsafeCell<Heap<SIZE>>,
+    /// Offset of the first free byte
+    next: Cell<usize>,
+    /// Allocations not yet freed
+    live: Cell<usize>,
+}
+
+// The guest is single-threaded: wasm32-unknown-unknown has no threads
+#[cfg(target_arch = "wasm32")]
+unsafe impl<const SIZE: usize> Sync for BumpAllocator<SIZE> {}
+
+impl<const SIZE: usize> BumpAllocator<SIZE> {
+    /// An empty arena
+    pub const fn new() -> Self {
+        Self {
+            heap: UnsafeCell::new(Heap([0; SIZE])),
+            next: Cell::new(0),
+            live: Cell::new(0),
+        }
+    }
+
+    /// Bytes between the start of the arena and the first free byte
+    pub fn used(&self) -> usize {
+        self.next.get()
+    }
+
+    fn base(&self) -> *mut u8 {
+        self.heap.get().cast()
+    }
+
+    fn is_newest(&self, ptr: *mut u8, size: usize) -> bool {
+        ptr as usize + size == self.base() as usize + self.next.get()
+    }
+}
+
+impl<const SIZE: usize> Default for BumpAllocator<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<const SIZE: usize> GlobalAlloc for BumpAllocator<SIZE> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let base = self.base() as usize;
+        let start = match (base + self.next.get()).checked_next_multiple_of(layout.align()) {
+            Some(addr) => addr - base,
+            None => return ptr::null_mut(),
+        };
+        match start.checked_add(layout.size()) {
+            Some(end) if end <= SIZE => {
+                self.next.set(end);
+                self.live.set(self.live.get() + 1);
+                self.base().add(start)
+            }
+            _ => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let live = self.live.get().saturating_sub(1);
+        self.live.set(live);
+        if live == 0 {
+            self.next.set(0);
+        } else if self.is_newest(ptr, layout.size()) {
+            self.next.set(ptr as usize - self.base() as usize);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Grow or shrink the newest allocation in place; this is what keeps
+        // a growing Vec or String from leaving copies behind
+        if self.is_newest(ptr, layout.size()) {
+            let start = ptr as usize - self.base() as usize;
+            return match start.checked_add(new_size) {
+                Some(end) if end <= SIZE => {
+                    self.next.set(end);
+                    ptr
+                }
+                _ => ptr::null_mut(),
+            };
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_is_reused_deterministically() {
+        let heap = BumpAllocator::<256>::new();
+        let byte = Layout::from_size_align(1, 1).unwrap();
+        let word = Layout::from_size_align(8, 8).unwrap();
+
+        unsafe {
+            let a = heap.alloc(byte);
+            let b = heap.alloc(word);
+            assert_eq!(b as usize % 8, 0);
+            assert_eq!(heap.used(), 16);
+
+            // Growing the newest allocation stays in place
+            let grown = heap.realloc(b, word, 64);
+            assert_eq!(grown, b);
+            assert_eq!(heap.used(), 72);
+
+            // Freeing the newest rolls back; freeing everything starts over
+            heap.dealloc(grown, Layout::from_size_align(64, 8).unwrap());
+            assert_eq!(heap.used(), 8);
+            heap.dealloc(a, byte);
+            assert_eq!(heap.used(), 0);
+            assert_eq!(heap.alloc(byte), a);
+
+            assert!(heap.alloc(Layout::from_size_align(512, 1).unwrap()).is_null());
+        }
+    }
+}
@@ -1,25 +1,242 @@
 // Accrual engine compiled to WASM
 // Expose a minimal `accrue` export that accepts JSON input and returns JSON output.
 // Uses pure Rust types with deterministic serialization.
+//
+// The crate is no_std: the compiled guest links only `core` and `alloc`, with
+// the deterministic bump allocator below, so nothing it does depends on the
+// host. Outputs carry integers and strings only, never floats, so their bytes
+// never depend on float formatting.
+//
+// Every response is an envelope, `{"ok":true,"error":null,"data":{...}}` or
+// `{"ok":false,"error":{"code":...,"message":...},"data":null}`, so a request
+// the module cannot serve is never mistaken for an empty result. The kernel
+// unwraps it (see `runtime::call_json` in esta-kernel).
+//
+// Buffer ownership: the host owns the input buffer it gets from `alloc` and
+// returns it with `dealloc(ptr, len)` once the call has returned. The guest
+// hands ownership of each result buffer to the host, which returns it with
+// `free_result(ptr)` after reading it, whether or not the call succeeded.
+// With both freed, the bump allocator is empty again after every call, so an
+// instance serving many calls keeps using the same bytes of its arena.
+//
+// A request may split its time into typed buckets (`hours`: regular,
+// overtime, PTO, unpaid). Which buckets count toward accrual comes from
+// `employer_policy.counted_hours`; regular time always counts. Requests
+// without buckets use `minutes_worked` and produce exactly the bytes they
+// always did.
+//
+// With a `pay_period` (its dates and the minutes accrued earlier in the
+// year), accrual is clamped at the annual cap as it is calculated: the
+// output carries the capped `accrued_minutes` and the uncapped
+// `raw_accrued_minutes`. The cap is the statutory annual usage for the
+// employer's size (`employer_policy.statutory`), 40 hours for small
+// employers and 72 otherwise when the host supplies none.
+//
+// `accrue_json_slice` is the JSON boundary of `accrue_json` without the
+// pointers, for property tests and the cargo-fuzz target in `fuzz/`
+// (`cargo +nightly fuzz run accrue_json` from this directory).
 
+#![no_std]
+
+extern crate alloc;
+
+// Host builds (tests, native tooling) take std's allocator and panic handler
+#[cfg(not(target_arch = "wasm32"))]
+extern crate std;
+
+pub mod bump;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::BTreeMap;
+
+/// Size of the guest heap; inputs are capped at 1 MiB (see `MAX_INPUT_SIZE`)
+#[cfg(target_arch = "wasm32")]
+const HEAP_SIZE: usize = 8 * 1024 * 1024;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOCATOR: bump::BumpAllocator<HEAP_SIZE> = bump::BumpAllocator::new();
+
+/// Panics trap instead of unwinding into the host
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
 
 #[derive(Deserialize, Serialize)]
 pub struct AccrualInput {
     pub employee_id: String,
+    /// Time worked, when not split into `hours`
+    #[serde(default)]
     pub minutes_worked: u64,
     pub employer_policy: Value,
+    /// Time by kind; replaces `minutes_worked` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<HourBuckets>,
+    /// The pay period worked; enables the annual cap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pay_period: Option<PayPeriod>,
+}
+
+/// The pay period a request covers
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayPeriod {
+    /// First day, YYYY-MM-DD
+    pub start: String,
+    /// Last day, YYYY-MM-DD
+    pub end: String,
+    /// Minutes accrued earlier in the accrual year
+    pub ytd_accrued_minutes: u64,
+}
+
+impl PayPeriod {
+    /// Dates must be YYYY-MM-DD and in order
+    fn check(&self) -> Result<(), GuestError> {
+        let is_date = |date: &str| {
+            date.len() == 10
+                && date.bytes().enumerate().all(|(i, b)| if i == 4 || i == 7 { b == b'-' } else { b.is_ascii_digit() })
+        };
+        if !is_date(&self.start) || !is_date(&self.end) {
+            return Err(GuestError::new("INVALID_INPUT", "pay_period dates must be YYYY-MM-DD"));
+        }
+        if self.start > self.end {
+            return Err(GuestError::new(
+                "INVALID_INPUT",
+                format!("pay_period starts {} after it ends {}", self.start, self.end),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Minutes of each kind of time on a timesheet
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HourBuckets {
+    #[serde(default)]
+    pub regular_minutes: u64,
+    #[serde(default)]
+    pub overtime_minutes: u64,
+    #[serde(default)]
+    pub pto_minutes: u64,
+    #[serde(default)]
+    pub unpaid_minutes: u64,
+}
+
+/// Which buckets count toward accrual
+///
+/// Read from `employer_policy.counted_hours` (`{"overtime": true, ...}`).
+/// Regular time always counts; by default so does overtime, since it is
+/// time worked, while PTO and unpaid time do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountedHours {
+    pub overtime: bool,
+    pub pto: bool,
+    pub unpaid: bool,
+}
+
+impl CountedHours {
+    fn from_policy(policy: &Value) -> Self {
+        let rules = &policy["counted_hours"];
+        let rule = |bucket: &str, default: bool| rules[bucket].as_bool().unwrap_or(default);
+        Self {
+            overtime: rule("overtime", true),
+            pto: rule("pto", false),
+            unpaid: rule("unpaid", false),
+        }
+    }
+
+    /// Counted buckets and their minutes, in a fixed order
+    fn counted(&self, hours: &HourBuckets) -> Vec<(&'static str, u64)> {
+        [
+            ("regular", true, hours.regular_minutes),
+            ("overtime", self.overtime, hours.overtime_minutes),
+            ("pto", self.pto, hours.pto_minutes),
+            ("unpaid", self.unpaid, hours.unpaid_minutes),
+        ]
+        .into_iter()
+        .filter(|&(_, counts, _)| counts)
+        .map(|(bucket, _, minutes)| (bucket, minutes))
+        .collect()
+    }
 }
 
 /// Output with deterministic serialization using BTreeMap for consistent key ordering
 #[derive(Deserialize, Serialize)]
 pub struct AccrualOutput {
     pub employee_id: String,
+    /// Accrued this period, after the annual cap when a pay period is given
     pub accrued_minutes: u64,
+    /// Accrued this period before the annual cap; only with a pay period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_accrued_minutes: Option<u64>,
+    /// Accrued in the year through this period; only with a pay period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ytd_accrued_minutes: Option<u64>,
     /// Metadata with sorted keys for byte-level reproducibility
-    pub metadata: BTreeMap<String, Value>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ValidationInput {
+    pub employee_id: String,
+    pub accrued_minutes: u64,
+    pub used_minutes: u64,
+}
+
+/// Result of checking a balance; errors are listed in a fixed order
+#[derive(Deserialize, Serialize)]
+pub struct ValidationOutput {
+    pub employee_id: String,
+    pub valid: bool,
+    pub balance_minutes: u64,
+    pub validation_errors: Vec<String>,
+}
+
+/// Why a request could not be served
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GuestError {
+    /// `INVALID_INPUT`, `INPUT_TOO_LARGE`, or `INTERNAL`
+    pub code: String,
+    pub message: String,
+}
+
+impl GuestError {
+    fn new(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), message: message.into() }
+    }
+}
+
+/// Response written across the WASM boundary; exactly one of `error` and
+/// `data` is set, and both keys are always present
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Envelope<T> {
+    pub ok: bool,
+    pub error: Option<GuestError>,
+    pub data: Option<T>,
+}
+
+impl<T> From<Result<T, GuestError>> for Envelope<T> {
+    fn from(result: Result<T, GuestError>) -> Self {
+        match result {
+            Ok(data) => Self { ok: true, error: None, data: Some(data) },
+            Err(error) => Self { ok: false, error: Some(error), data: None },
+        }
+    }
+}
+
+/// Kernel ABI version this module is built against, read by the host at load.
+#[no_mangle]
+pub extern "C" fn __abi_version() -> i32 {
+    1
 }
 
 /// Memory allocation for WASM host communication.
@@ -34,7 +251,7 @@ pub extern "C" fn alloc(size: usize) -> *mut u8 {
     // Zero-initialize memory to prevent potential information leakage
     let mut buf = vec![0u8; size];
     let ptr = buf.as_mut_ptr();
-    std::mem::forget(buf);
+    core::mem::forget(buf);
     ptr
 }
 
@@ -49,37 +266,57 @@ pub unsafe extern "C" fn dealloc(ptr: *mut u8, size: usize) {
     }
 }
 
+/// Free a result buffer returned by `accrue_json` or `validate_json`.
+///
+/// # Safety
+/// The caller must pass a non-null pointer returned by one of those exports,
+/// exactly once, and not read the buffer afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn free_result(ptr: *mut u8) {
+    if !ptr.is_null() {
+        let len = u32::from_le_bytes(*(ptr as *const [u8; 4])) as usize;
+        dealloc(ptr, 4 + len);
+    }
+}
+
 /// Maximum allowed input size (1MB) to prevent resource exhaustion
 const MAX_INPUT_SIZE: usize = 1_048_576;
 
-/// Compute accrual based on input JSON.
-/// Returns JSON string for WASM boundary crossing.
-///
-/// # Arguments
-/// * `input_ptr` - Pointer to JSON input bytes
-/// * `input_len` - Length of input bytes
-///
-/// # Returns
-/// Pointer to JSON output string (caller must read length from first 4 bytes)
-/// Returns null pointer if input is invalid (null pointer or exceeds size limit)
-#[no_mangle]
-pub extern "C" fn accrue_json(input_ptr: *const u8, input_len: usize) -> *const u8 {
-    // Validate input pointer and size
-    if input_ptr.is_null() || input_len == 0 || input_len > MAX_INPUT_SIZE {
-        return std::ptr::null();
+/// Borrow a JSON request from guest memory, rejecting a null pointer.
+/// Oversized input is refused by `parse_request` before it is read.
+fn read_input<'a>(input_ptr: *const u8, input_len: usize) -> Option<&'a [u8]> {
+    if input_ptr.is_null() {
+        return None;
     }
 
-    // Safety: We've validated the pointer is non-null and size is reasonable
-    let input_slice = unsafe { std::slice::from_raw_parts(input_ptr, input_len) };
+    // Safety: the pointer is non-null and the host wrote `input_len` bytes there
+    Some(unsafe { core::slice::from_raw_parts(input_ptr, input_len) })
+}
 
-    let result = match serde_json::from_slice::<AccrualInput>(input_slice) {
-        Ok(input) => {
-            let output = accrue(input);
-            serde_json::to_vec(&output).unwrap_or_else(|_| b"{}".to_vec())
-        }
-        Err(_) => b"{}".to_vec(),
-    };
+/// Parse a request, refusing empty or oversized input
+fn parse_request<T: DeserializeOwned>(input: &[u8]) -> Result<T, GuestError> {
+    if input.is_empty() {
+        return Err(GuestError::new("INVALID_INPUT", "empty request"));
+    }
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(GuestError::new(
+            "INPUT_TOO_LARGE",
+            format!("request of {} bytes exceeds {} bytes", input.len(), MAX_INPUT_SIZE),
+        ));
+    }
+    serde_json::from_slice(input).map_err(|e| GuestError::new("INVALID_INPUT", e.to_string()))
+}
 
+/// Serialize a result as an envelope
+fn respond<T: Serialize>(result: Result<T, GuestError>) -> Vec<u8> {
+    serde_json::to_vec(&Envelope::from(result)).unwrap_or_else(|_| {
+        br#"{"ok":false,"error":{"code":"INTERNAL","message":"response could not be serialized"},"data":null}"#.to_vec()
+    })
+}
+
+/// Copy a JSON response into a freshly allocated, length-prefixed buffer,
+/// owned by the host until it calls `free_result`.
+fn write_output(result: &[u8]) -> *const u8 {
     // Allocate result with length prefix
     let len = result.len();
     let total_len = 4 + len;
@@ -87,40 +324,166 @@ pub extern "C" fn accrue_json(input_ptr: *const u8, input_len: usize) -> *const
 
     unsafe {
         // Write length as first 4 bytes (little-endian)
-        std::ptr::copy_nonoverlapping(
+        core::ptr::copy_nonoverlapping(
             (len as u32).to_le_bytes().as_ptr(),
             ptr,
             4,
         );
         // Write JSON data
-        std::ptr::copy_nonoverlapping(result.as_ptr(), ptr.add(4), len);
+        core::ptr::copy_nonoverlapping(result.as_ptr(), ptr.add(4), len);
     }
 
     ptr
 }
 
+/// Compute accrual for a JSON request held in a slice.
+/// Safe counterpart of `accrue_json`, producing the envelope it would write
+/// after the length prefix.
+pub fn accrue_json_slice(input: &[u8]) -> Vec<u8> {
+    respond(parse_request(input).and_then(check_accrual).map(accrue))
+}
+
+/// Validate a balance for a JSON request held in a slice.
+/// Safe counterpart of `validate_json`.
+pub fn validate_json_slice(input: &[u8]) -> Vec<u8> {
+    respond(parse_request(input).map(validate))
+}
+
+/// Compute accrual based on input JSON.
+/// Returns JSON string for WASM boundary crossing.
+///
+/// # Arguments
+/// * `input_ptr` - Pointer to JSON input bytes
+/// * `input_len` - Length of input bytes
+///
+/// # Returns
+/// Pointer to the JSON response envelope (caller must read length from first
+/// 4 bytes, then release it with `free_result`). Returns a null pointer only
+/// for a null input pointer; every other failure is reported in the envelope.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // FFI export; pointer is validated in read_input
+pub extern "C" fn accrue_json(input_ptr: *const u8, input_len: usize) -> *const u8 {
+    let Some(input_slice) = read_input(input_ptr, input_len) else {
+        return core::ptr::null();
+    };
+
+    write_output(&accrue_json_slice(input_slice))
+}
+
+/// Validate a balance based on input JSON.
+/// Uses the same pointer/length protocol as `accrue_json`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // FFI export; pointer is validated in read_input
+pub extern "C" fn validate_json(input_ptr: *const u8, input_len: usize) -> *const u8 {
+    let Some(input_slice) = read_input(input_ptr, input_len) else {
+        return core::ptr::null();
+    };
+
+    write_output(&validate_json_slice(input_slice))
+}
+
+/// Hours worked per hour accrued when the host supplies no statutory parameters
+const DEFAULT_ACCRUAL_HOURS_WORKED: u64 = 30;
+
+/// Annual cap (hours) for small employers without statutory parameters
+const DEFAULT_SMALL_EMPLOYER_CAP_HOURS: u64 = 40;
+
+/// Annual cap (hours) for other employers without statutory parameters
+const DEFAULT_LARGE_EMPLOYER_CAP_HOURS: u64 = 72;
+
+/// Reject requests `accrue` cannot serve
+fn check_accrual(input: AccrualInput) -> Result<AccrualInput, GuestError> {
+    if let Some(period) = &input.pay_period {
+        period.check()?;
+    }
+    Ok(input)
+}
+
+/// Annual accrual cap in minutes for the employer's size
+fn annual_cap_minutes(policy: &Value) -> u64 {
+    let statutory = &policy["statutory"];
+    let hours = match policy["employer_size"].as_str() {
+        Some("small") => statutory["small_employer_usage_hours"]
+            .as_u64()
+            .unwrap_or(DEFAULT_SMALL_EMPLOYER_CAP_HOURS),
+        _ => statutory["large_employer_usage_hours"]
+            .as_u64()
+            .unwrap_or(DEFAULT_LARGE_EMPLOYER_CAP_HOURS),
+    };
+    hours.saturating_mul(60)
+}
+
 /// Pure function for accrual calculation.
 /// Deterministic: identical inputs always produce identical outputs.
+///
+/// The rate comes from `employer_policy.statutory.accrual_hours_worked`, the
+/// statute data the host passes in; 1:30 is used when it is absent.
 pub fn accrue(input: AccrualInput) -> AccrualOutput {
-    // Deterministic calculation using 1:30 ratio (integer arithmetic only)
-    // 60 minutes worked yields 2 minutes accrued (60/30=2)
+    // Deterministic calculation using a 1:N ratio (integer arithmetic only)
+    // With N = 30, 60 minutes worked yields 2 minutes accrued (60/30=2)
     let rate_num = 1u64;
-    let rate_den = 30u64;
-    let accrued = input.minutes_worked * rate_num / rate_den;
-
+    let rate_den = input.employer_policy["statutory"]["accrual_hours_worked"]
+        .as_u64()
+        .filter(|&hours| hours > 0)
+        .unwrap_or(DEFAULT_ACCRUAL_HOURS_WORKED);
     // Use BTreeMap for deterministic key ordering in JSON serialization
     let mut metadata = BTreeMap::new();
-    metadata.insert("calc".to_string(), Value::String("1:30".to_string()));
-    metadata.insert("source".to_string(), Value::String("accrual.wasm".to_string()));
-    metadata.insert("version".to_string(), Value::String("0.1.0".to_string()));
+    let minutes_worked = match &input.hours {
+        Some(hours) => {
+            let counted = CountedHours::from_policy(&input.employer_policy).counted(hours);
+            let minutes = counted.iter().fold(0u64, |sum, &(_, minutes)| sum.saturating_add(minutes));
+            let buckets: Vec<&str> = counted.iter().map(|&(bucket, _)| bucket).collect();
+            metadata.insert("counted_hours".to_string(), buckets.join(","));
+            metadata.insert("counted_minutes".to_string(), minutes.to_string());
+            minutes
+        }
+        None => input.minutes_worked,
+    };
+    let raw_accrued = minutes_worked * rate_num / rate_den;
+
+    // Clamp at the annual cap, counting what the year has already accrued
+    let (accrued, raw_accrued_minutes, ytd_accrued_minutes) = match &input.pay_period {
+        Some(period) => {
+            let cap = annual_cap_minutes(&input.employer_policy);
+            let accrued = raw_accrued.min(cap.saturating_sub(period.ytd_accrued_minutes));
+            metadata.insert("annual_cap_minutes".to_string(), cap.to_string());
+            metadata.insert("pay_period".to_string(), format!("{}..{}", period.start, period.end));
+            (accrued, Some(raw_accrued), Some(period.ytd_accrued_minutes.saturating_add(accrued)))
+        }
+        None => (raw_accrued, None, None),
+    };
+
+    metadata.insert("calc".to_string(), format!("{}:{}", rate_num, rate_den));
+    metadata.insert("source".to_string(), "accrual.wasm".to_string());
+    metadata.insert("version".to_string(), "0.1.0".to_string());
 
     AccrualOutput {
         employee_id: input.employee_id,
         accrued_minutes: accrued,
+        raw_accrued_minutes,
+        ytd_accrued_minutes,
         metadata,
     }
 }
 
+/// Pure function for balance validation.
+/// Usage may never exceed accrued time; the balance saturates at zero.
+pub fn validate(input: ValidationInput) -> ValidationOutput {
+    let valid = input.accrued_minutes >= input.used_minutes;
+    let validation_errors = if valid {
+        Vec::new()
+    } else {
+        vec!["Used exceeds accrued".to_string()]
+    };
+
+    ValidationOutput {
+        employee_id: input.employee_id,
+        valid,
+        balance_minutes: input.accrued_minutes.saturating_sub(input.used_minutes),
+        validation_errors,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,6 +494,8 @@ mod tests {
             employee_id: "e1".into(),
             minutes_worked: 120,
             employer_policy: serde_json::json!({"cap": 480}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inpt);
         assert_eq!(out.accrued_minutes, 4); // 120/30 = 4
@@ -143,11 +508,15 @@ mod tests {
             employee_id: "e1".into(),
             minutes_worked: 60,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let inpt2 = AccrualInput {
             employee_id: "e1".into(),
             minutes_worked: 60,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
 
         let out1 = serde_json::to_string(&accrue(inpt1)).unwrap();
@@ -162,6 +531,8 @@ mod tests {
             employee_id: "e1".into(),
             minutes_worked: 0,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inpt);
         assert_eq!(out.accrued_minutes, 0);
@@ -173,8 +544,170 @@ mod tests {
             employee_id: "e1".into(),
             minutes_worked: 10_000,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inpt);
         assert_eq!(out.accrued_minutes, 333); // 10000/30 = 333
     }
+
+    #[test]
+    fn statutory_rate() {
+        let inpt = AccrualInput {
+            employee_id: "e1".into(),
+            minutes_worked: 10_000,
+            employer_policy: serde_json::json!({"statutory": {"accrual_hours_worked": 25}}),
+            hours: None,
+            pay_period: None,
+        };
+        let out = accrue(inpt);
+        assert_eq!(out.accrued_minutes, 400); // 10000/25 = 400
+        assert_eq!(out.metadata["calc"], "1:25");
+    }
+
+    #[test]
+    fn hour_buckets() {
+        let hours = HourBuckets {
+            regular_minutes: 2400,
+            overtime_minutes: 600,
+            pto_minutes: 480,
+            unpaid_minutes: 300,
+        };
+        let accrue_with = |policy: Value| {
+            accrue(AccrualInput {
+                employee_id: "e1".into(),
+                minutes_worked: 0,
+                employer_policy: policy,
+                hours: Some(hours.clone()),
+                pay_period: None,
+            })
+        };
+
+        // Regular and overtime by default
+        let out = accrue_with(serde_json::json!({}));
+        assert_eq!(out.accrued_minutes, 100); // 3000/30
+        assert_eq!(out.metadata["counted_hours"], "regular,overtime");
+        assert_eq!(out.metadata["counted_minutes"], "3000");
+
+        let out = accrue_with(serde_json::json!({"counted_hours": {"overtime": false, "pto": true}}));
+        assert_eq!(out.accrued_minutes, 96); // 2880/30
+        assert_eq!(out.metadata["counted_hours"], "regular,pto");
+
+        let input = br#"{"employee_id":"e1","employer_policy":{},"hours":{"regular_minutes":90,"overtime_minutes":30}}"#;
+        assert_eq!(
+            accrue_json_slice(input),
+            br#"{"ok":true,"error":null,"data":{"employee_id":"e1","accrued_minutes":4,"metadata":{"calc":"1:30","counted_hours":"regular,overtime","counted_minutes":"120","source":"accrual.wasm","version":"0.1.0"}}}"#
+        );
+        let unknown = br#"{"employee_id":"e1","employer_policy":{},"hours":{"holiday_minutes":60}}"#;
+        assert!(accrue_json_slice(unknown).starts_with(br#"{"ok":false,"error":{"code":"INVALID_INPUT""#));
+    }
+
+    #[test]
+    fn annual_cap_at_accrual_time() {
+        let accrue_in_period = |employer_size: &str, ytd_accrued_minutes: u64| {
+            accrue(AccrualInput {
+                employee_id: "e1".into(),
+                minutes_worked: 4800,
+                employer_policy: serde_json::json!({"employer_size": employer_size}),
+                hours: None,
+                pay_period: Some(PayPeriod {
+                    start: "2025-12-01".into(),
+                    end: "2025-12-14".into(),
+                    ytd_accrued_minutes,
+                }),
+            })
+        };
+
+        let out = accrue_in_period("large", 4000);
+        assert_eq!((out.accrued_minutes, out.raw_accrued_minutes), (160, Some(160)));
+        assert_eq!(out.ytd_accrued_minutes, Some(4160));
+
+        let out = accrue_in_period("large", 4300);
+        assert_eq!((out.accrued_minutes, out.raw_accrued_minutes), (20, Some(160)));
+        assert_eq!(out.ytd_accrued_minutes, Some(4320));
+        assert_eq!(out.metadata["annual_cap_minutes"], "4320");
+
+        let out = accrue_in_period("small", 2400);
+        assert_eq!((out.accrued_minutes, out.ytd_accrued_minutes), (0, Some(2400)));
+
+        // The statute the host supplies sets the cap
+        let input = br#"{"employee_id":"e1","minutes_worked":300,"employer_policy":{"employer_size":"small","statutory":{"small_employer_usage_hours":1}},"pay_period":{"start":"2025-01-01","end":"2025-01-14","ytd_accrued_minutes":55}}"#;
+        assert_eq!(
+            accrue_json_slice(input),
+            br#"{"ok":true,"error":null,"data":{"employee_id":"e1","accrued_minutes":5,"raw_accrued_minutes":10,"ytd_accrued_minutes":60,"metadata":{"annual_cap_minutes":"60","calc":"1:30","pay_period":"2025-01-01..2025-01-14","source":"accrual.wasm","version":"0.1.0"}}}"#
+        );
+
+        let backwards = br#"{"employee_id":"e1","minutes_worked":60,"employer_policy":{},"pay_period":{"start":"2025-01-14","end":"2025-01-01","ytd_accrued_minutes":0}}"#;
+        assert!(accrue_json_slice(backwards).starts_with(br#"{"ok":false,"error":{"code":"INVALID_INPUT""#));
+    }
+
+    #[test]
+    fn json_output_is_exact() {
+        let input = br#"{"employee_id":"e1","minutes_worked":90,"employer_policy":{"cap":40.5}}"#;
+        let ptr = accrue_json(input.as_ptr(), input.len());
+        let output = unsafe {
+            let len = u32::from_le_bytes(*(ptr as *const [u8; 4])) as usize;
+            core::slice::from_raw_parts(ptr.add(4), len)
+        };
+        assert_eq!(
+            output,
+            br#"{"ok":true,"error":null,"data":{"employee_id":"e1","accrued_minutes":3,"metadata":{"calc":"1:30","source":"accrual.wasm","version":"0.1.0"}}}"#
+        );
+    }
+
+    #[test]
+    fn slice_wrapper_matches_export() {
+        let input = br#"{"employee_id":"e1","minutes_worked":90,"employer_policy":{}}"#;
+        let ptr = accrue_json(input.as_ptr(), input.len());
+        let output = unsafe {
+            let len = u32::from_le_bytes(*(ptr as *const [u8; 4])) as usize;
+            core::slice::from_raw_parts(ptr.add(4), len)
+        };
+        assert_eq!(accrue_json_slice(input), output);
+
+        assert!(accrue_json(core::ptr::null(), 4).is_null());
+    }
+
+    #[test]
+    fn errors_are_reported_in_the_envelope() {
+        let code = |output: Vec<u8>| {
+            let envelope: Envelope<AccrualOutput> = serde_json::from_slice(&output).unwrap();
+            assert!(!envelope.ok && envelope.data.is_none());
+            envelope.error.unwrap().code
+        };
+        assert_eq!(code(accrue_json_slice(br#"{"employee_id":"e1""#)), "INVALID_INPUT");
+        assert_eq!(code(accrue_json_slice(br#"{"employee_id":"e1"}"#)), "INVALID_INPUT");
+        assert_eq!(code(accrue_json_slice(b"")), "INVALID_INPUT");
+        assert_eq!(code(accrue_json_slice(&vec![b' '; MAX_INPUT_SIZE + 1])), "INPUT_TOO_LARGE");
+        assert_eq!(code(validate_json_slice(b"[]")), "INVALID_INPUT");
+
+        let input = br#"{"employee_id":"e1","accrued_minutes":10,"used_minutes":4}"#;
+        let envelope: Envelope<ValidationOutput> = serde_json::from_slice(&validate_json_slice(input)).unwrap();
+        assert!(envelope.ok && envelope.error.is_none());
+        assert_eq!(envelope.data.unwrap().balance_minutes, 6);
+    }
+
+    #[test]
+    fn validate_balance() {
+        let out = validate(ValidationInput {
+            employee_id: "e1".into(),
+            accrued_minutes: 100,
+            used_minutes: 50,
+        });
+        assert!(out.valid);
+        assert_eq!(out.balance_minutes, 50);
+        assert!(out.validation_errors.is_empty());
+    }
+
+    #[test]
+    fn validate_overdrawn() {
+        let out = validate(ValidationInput {
+            employee_id: "e1".into(),
+            accrued_minutes: 30,
+            used_minutes: 50,
+        });
+        assert!(!out.valid);
+        assert_eq!(out.balance_minutes, 0);
+        assert_eq!(out.validation_errors, vec!["Used exceeds accrued".to_string()]);
+    }
 }
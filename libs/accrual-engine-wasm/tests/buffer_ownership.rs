@@ -0,0 +1,62 @@
+//! The host/guest buffer ownership protocol leaves no guest memory behind.
+//!
+//! Counts live heap bytes with a wrapping global allocator, so this file
+//! holds a single test: the harness allocates little while it runs.
+
+use accrual_engine_wasm::{accrue_json, alloc, dealloc, free_result};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// One call the way the kernel makes it, returning the response length
+fn call(input: &[u8]) -> usize {
+    let input_ptr = alloc(input.len());
+    unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), input_ptr, input.len()) };
+
+    let output_ptr = accrue_json(input_ptr, input.len()) as *mut u8;
+    let len = unsafe { u32::from_le_bytes(*(output_ptr as *const [u8; 4])) } as usize;
+
+    unsafe {
+        free_result(output_ptr);
+        dealloc(input_ptr, input.len());
+    }
+    len
+}
+
+#[test]
+fn repeated_calls_keep_memory_bounded() {
+    let input = br#"{"employee_id":"e1","minutes_worked":90,"employer_policy":{}}"#;
+    let invalid = br#"{"employee_id":"e1"}"#;
+
+    // Warm up anything allocated once
+    call(input);
+    let before = LIVE_BYTES.load(Ordering::SeqCst);
+
+    let mut response_bytes = 0;
+    for i in 0..10_000 {
+        response_bytes += call(if i % 10 == 0 { invalid } else { input });
+    }
+
+    // Without free_result every response would still be live
+    let growth = LIVE_BYTES.load(Ordering::SeqCst).saturating_sub(before);
+    assert!(response_bytes > 1_000_000);
+    assert!(growth < 4096, "{} bytes still allocated after 10k calls", growth);
+}
@@ -1,5 +1,5 @@
 use proptest::prelude::*;
-use accrual_engine_wasm::{AccrualInput, accrue};
+use accrual_engine_wasm::{AccrualInput, AccrualOutput, Envelope, accrue, accrue_json_slice};
 
 proptest! {
     #[test]
@@ -8,6 +8,8 @@ proptest! {
             employee_id: "test".into(),
             minutes_worked: minutes,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inp);
         prop_assert!(out.accrued_minutes <= minutes, "Accrued {} must be <= worked {}", out.accrued_minutes, minutes);
@@ -19,11 +21,15 @@ proptest! {
             employee_id: "test".into(),
             minutes_worked: minutes,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let inp2 = AccrualInput {
             employee_id: "test".into(),
             minutes_worked: minutes,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out1 = accrue(inp1);
         let out2 = accrue(inp2);
@@ -37,6 +43,8 @@ proptest! {
             employee_id: "test".into(),
             minutes_worked: minutes,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inp);
         let expected = minutes / 30;
@@ -49,6 +57,8 @@ proptest! {
             employee_id: "test".into(),
             minutes_worked: minutes,
             employer_policy: serde_json::json!({}),
+            hours: None,
+            pay_period: None,
         };
         let out = accrue(inp);
         prop_assert!(out.metadata.contains_key("source"));
@@ -56,3 +66,52 @@ proptest! {
         prop_assert!(out.metadata.contains_key("version"));
     }
 }
+
+/// Parse a response envelope, checking exactly one of `error` and `data` is set
+fn envelope(output: &[u8]) -> Envelope<AccrualOutput> {
+    let envelope: Envelope<AccrualOutput> = serde_json::from_slice(output).unwrap();
+    assert_eq!(envelope.ok, envelope.data.is_some());
+    assert_eq!(envelope.ok, envelope.error.is_none());
+    envelope
+}
+
+fn error_code(output: &[u8]) -> String {
+    envelope(output).error.map(|e| e.code).unwrap_or_default()
+}
+
+fn request(minutes: u64) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "employee_id": "test",
+        "minutes_worked": minutes,
+        "employer_policy": {},
+    }))
+    .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(input in proptest::collection::vec(any::<u8>(), 0..512)) {
+        let output = envelope(&accrue_json_slice(&input));
+        prop_assert!(output.ok || !output.error.unwrap().message.is_empty());
+    }
+
+    #[test]
+    fn truncated_requests_are_rejected(minutes in 0u64..10_000u64, cut in 1usize..40) {
+        let input = request(minutes);
+        let truncated = &input[..input.len().saturating_sub(cut).max(1)];
+        prop_assert_eq!(error_code(&accrue_json_slice(truncated)), "INVALID_INPUT");
+    }
+
+    #[test]
+    fn json_boundary_matches_accrue(minutes in any::<u64>()) {
+        let output = envelope(&accrue_json_slice(&request(minutes))).data.unwrap();
+        prop_assert_eq!(output.accrued_minutes, minutes / 30);
+    }
+
+    #[test]
+    fn giant_payloads_are_refused(extra in 1usize..4096) {
+        let mut input = request(60);
+        input.resize(1_048_576 + extra, b' ');
+        prop_assert_eq!(error_code(&accrue_json_slice(&input)), "INPUT_TOO_LARGE");
+    }
+}
@@ -0,0 +1,455 @@
+// Policy simulation compiled to WASM
+// Exposes `simulate_json`, which plays a year of projected hours through both
+// ways an employer may provide sick time under the Michigan Earned Sick Time
+// Act and returns the two outcomes side by side:
+//
+// - Accrual: one hour of sick time per `accrual_hours_worked` hours worked,
+//   usable as it is earned, with unused time carried into the next year up
+//   to the annual usage limit.
+// - Frontloading: the full annual usage limit granted on the first day, with
+//   nothing carried over.
+//
+// Planned use in each period is drawn from the balance available at the start
+// of that period and refused beyond the annual usage limit. The simulation is
+// informational: it records nothing and changes no balances. All arithmetic
+// is in whole minutes, so outputs never depend on float formatting.
+//
+// Responses use the same envelope and buffer ownership as the accrual engine
+// (`free_result` releases a result buffer).
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Statutory figures the simulation needs; Michigan's are the defaults
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Statutory {
+    /// Hours worked per hour of sick time accrued
+    pub accrual_hours_worked: u64,
+    /// Annual usage a small employer must allow (hours)
+    pub small_employer_usage_hours: u64,
+    /// Annual usage a large employer must allow (hours)
+    pub large_employer_usage_hours: u64,
+}
+
+impl Default for Statutory {
+    fn default() -> Self {
+        Self {
+            accrual_hours_worked: 30,
+            small_employer_usage_hours: 40,
+            large_employer_usage_hours: 72,
+        }
+    }
+}
+
+/// Projected work and planned sick time use for one pay period
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProjectedPeriod {
+    pub minutes_worked: u64,
+    #[serde(default)]
+    pub minutes_used: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SimulationInput {
+    /// `small` or `large`
+    pub employer_size: String,
+    /// The year's pay periods, in order
+    pub periods: Vec<ProjectedPeriod>,
+    /// Unused time carried in from last year; applies to accrual only
+    #[serde(default)]
+    pub carryover_minutes: u64,
+    #[serde(default)]
+    pub statutory: Statutory,
+}
+
+/// How the year plays out under one method
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MethodOutcome {
+    /// Balance at the end of each period
+    pub balances: Vec<u64>,
+    /// Time accrued or frontloaded during the year
+    pub granted_minutes: u64,
+    pub used_minutes: u64,
+    /// Planned use refused for lack of balance or past the usage limit
+    pub denied_minutes: u64,
+    pub ending_balance_minutes: u64,
+    /// Carried into next year
+    pub carryover_minutes: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SimulationOutput {
+    pub employer_size: String,
+    pub usage_limit_minutes: u64,
+    pub accrual: MethodOutcome,
+    pub frontload: MethodOutcome,
+    /// `accrual`, `frontload`, or `equal`: which method refuses less planned use
+    pub fewer_denials: String,
+    /// Each step of the simulation, in order
+    pub explain: Vec<String>,
+}
+
+/// Why a request could not be served
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GuestError {
+    /// `INVALID_INPUT`, `INPUT_TOO_LARGE`, or `INTERNAL`
+    pub code: String,
+    pub message: String,
+}
+
+impl GuestError {
+    fn new(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), message: message.into() }
+    }
+}
+
+/// Response written across the WASM boundary; exactly one of `error` and
+/// `data` is set, and both keys are always present
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Envelope<T> {
+    pub ok: bool,
+    pub error: Option<GuestError>,
+    pub data: Option<T>,
+}
+
+impl<T> From<Result<T, GuestError>> for Envelope<T> {
+    fn from(result: Result<T, GuestError>) -> Self {
+        match result {
+            Ok(data) => Self { ok: true, error: None, data: Some(data) },
+            Err(error) => Self { ok: false, error: Some(error), data: None },
+        }
+    }
+}
+
+/// Kernel ABI version this module is built against, read by the host at load.
+#[no_mangle]
+pub extern "C" fn __abi_version() -> i32 {
+    1
+}
+
+/// Memory allocation for WASM host communication.
+/// Memory is zero-initialized to prevent potential information leakage.
+#[no_mangle]
+pub extern "C" fn alloc(size: usize) -> *mut u8 {
+    let mut buf = vec![0u8; size];
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Free memory allocated by alloc.
+///
+/// # Safety
+/// The caller must ensure ptr was allocated by alloc with the given size.
+#[no_mangle]
+pub unsafe extern "C" fn dealloc(ptr: *mut u8, size: usize) {
+    if !ptr.is_null() {
+        let _ = Vec::from_raw_parts(ptr, 0, size);
+    }
+}
+
+/// Free a result buffer returned by `simulate_json`.
+///
+/// # Safety
+/// The caller must pass a non-null pointer returned by `simulate_json`,
+/// exactly once, and not read the buffer afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn free_result(ptr: *mut u8) {
+    if !ptr.is_null() {
+        let len = u32::from_le_bytes(*(ptr as *const [u8; 4])) as usize;
+        dealloc(ptr, 4 + len);
+    }
+}
+
+/// Maximum allowed input size (1MB) to prevent resource exhaustion
+const MAX_INPUT_SIZE: usize = 1_048_576;
+
+/// Most periods in a year (daily periods in a leap year)
+const MAX_PERIODS: usize = 366;
+
+/// Borrow a JSON request from guest memory, rejecting a null pointer.
+fn read_input<'a>(input_ptr: *const u8, input_len: usize) -> Option<&'a [u8]> {
+    if input_ptr.is_null() {
+        return None;
+    }
+
+    // Safety: the pointer is non-null and the host wrote `input_len` bytes there
+    Some(unsafe { std::slice::from_raw_parts(input_ptr, input_len) })
+}
+
+/// Parse a request, refusing empty or oversized input
+fn parse_request<T: DeserializeOwned>(input: &[u8]) -> Result<T, GuestError> {
+    if input.is_empty() {
+        return Err(GuestError::new("INVALID_INPUT", "empty request"));
+    }
+    if input.len() > MAX_INPUT_SIZE {
+        return Err(GuestError::new(
+            "INPUT_TOO_LARGE",
+            format!("request of {} bytes exceeds {} bytes", input.len(), MAX_INPUT_SIZE),
+        ));
+    }
+    serde_json::from_slice(input).map_err(|e| GuestError::new("INVALID_INPUT", e.to_string()))
+}
+
+/// Serialize a result as an envelope
+fn respond<T: Serialize>(result: Result<T, GuestError>) -> Vec<u8> {
+    serde_json::to_vec(&Envelope::from(result)).unwrap_or_else(|_| {
+        br#"{"ok":false,"error":{"code":"INTERNAL","message":"response could not be serialized"},"data":null}"#.to_vec()
+    })
+}
+
+/// Copy a JSON response into a freshly allocated, length-prefixed buffer,
+/// owned by the host until it calls `free_result`.
+fn write_output(result: &[u8]) -> *const u8 {
+    let len = result.len();
+    let ptr = alloc(4 + len);
+
+    unsafe {
+        // Write length as first 4 bytes (little-endian)
+        std::ptr::copy_nonoverlapping((len as u32).to_le_bytes().as_ptr(), ptr, 4);
+        std::ptr::copy_nonoverlapping(result.as_ptr(), ptr.add(4), len);
+    }
+
+    ptr
+}
+
+/// Run a simulation for a JSON request held in a slice.
+/// Safe counterpart of `simulate_json`.
+pub fn simulate_json_slice(input: &[u8]) -> Vec<u8> {
+    respond(parse_request(input).and_then(simulate))
+}
+
+/// Compare frontloading and accrual for a year of projected hours.
+/// Uses the same pointer/length protocol as the accrual engine; returns a
+/// null pointer only for a null input pointer.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // FFI export; pointer is validated in read_input
+pub extern "C" fn simulate_json(input_ptr: *const u8, input_len: usize) -> *const u8 {
+    let Some(input_slice) = read_input(input_ptr, input_len) else {
+        return std::ptr::null();
+    };
+
+    write_output(&simulate_json_slice(input_slice))
+}
+
+/// Hours to minutes, as used by the statute's hour figures
+fn minutes(hours: u64) -> u64 {
+    hours.saturating_mul(60)
+}
+
+/// Draw planned use from a balance within what is left of the usage limit
+///
+/// Returns the minutes allowed.
+fn draw(requested: u64, balance: &mut u64, used: &mut u64, usage_limit: u64) -> u64 {
+    let allowed = requested.min(*balance).min(usage_limit.saturating_sub(*used));
+    *balance -= allowed;
+    *used += allowed;
+    allowed
+}
+
+/// Play the year through the accrual method
+fn simulate_accrual(input: &SimulationInput, usage_limit: u64) -> MethodOutcome {
+    let rate = input.statutory.accrual_hours_worked;
+    let mut balance = input.carryover_minutes;
+    let (mut worked, mut granted, mut used, mut denied) = (0u64, 0u64, 0u64, 0u64);
+    let mut balances = Vec::with_capacity(input.periods.len());
+
+    for period in &input.periods {
+        // Use comes out of what was earned before the period
+        denied += period.minutes_used - draw(period.minutes_used, &mut balance, &mut used, usage_limit);
+
+        // Accrue on the running total so no fraction of a minute is lost
+        worked = worked.saturating_add(period.minutes_worked);
+        let earned = worked / rate - granted;
+        granted += earned;
+        balance = balance.saturating_add(earned);
+        balances.push(balance);
+    }
+
+    MethodOutcome {
+        balances,
+        granted_minutes: granted,
+        used_minutes: used,
+        denied_minutes: denied,
+        ending_balance_minutes: balance,
+        carryover_minutes: balance.min(usage_limit),
+    }
+}
+
+/// Play the year through the frontloading method
+fn simulate_frontload(input: &SimulationInput, usage_limit: u64) -> MethodOutcome {
+    let mut balance = usage_limit;
+    let (mut used, mut denied) = (0u64, 0u64);
+    let mut balances = Vec::with_capacity(input.periods.len());
+
+    for period in &input.periods {
+        denied += period.minutes_used - draw(period.minutes_used, &mut balance, &mut used, usage_limit);
+        balances.push(balance);
+    }
+
+    MethodOutcome {
+        balances,
+        granted_minutes: usage_limit,
+        used_minutes: used,
+        denied_minutes: denied,
+        ending_balance_minutes: balance,
+        carryover_minutes: 0,
+    }
+}
+
+/// Pure function comparing both methods over the projected year.
+/// Deterministic: identical inputs always produce identical outputs.
+pub fn simulate(input: SimulationInput) -> Result<SimulationOutput, GuestError> {
+    let usage_hours = match input.employer_size.as_str() {
+        "small" => input.statutory.small_employer_usage_hours,
+        "large" => input.statutory.large_employer_usage_hours,
+        other => {
+            return Err(GuestError::new(
+                "INVALID_INPUT",
+                format!("employer_size must be 'small' or 'large', not '{}'", other),
+            ))
+        }
+    };
+    if input.statutory.accrual_hours_worked == 0 {
+        return Err(GuestError::new("INVALID_INPUT", "accrual_hours_worked must be positive"));
+    }
+    if input.periods.is_empty() || input.periods.len() > MAX_PERIODS {
+        return Err(GuestError::new(
+            "INVALID_INPUT",
+            format!("between 1 and {} periods are required, got {}", MAX_PERIODS, input.periods.len()),
+        ));
+    }
+
+    let usage_limit = minutes(usage_hours);
+    let accrual = simulate_accrual(&input, usage_limit);
+    let frontload = simulate_frontload(&input, usage_limit);
+
+    let planned: u64 = input.periods.iter().map(|p| p.minutes_used).sum();
+    let worked: u64 = input.periods.iter().map(|p| p.minutes_worked).sum();
+    let mut explain = vec![
+        format!(
+            "{} employer: usage limited to {} hours a year",
+            input.employer_size, usage_hours
+        ),
+        format!(
+            "{} periods: {} minutes worked, {} minutes of planned use",
+            input.periods.len(),
+            worked,
+            planned
+        ),
+        format!(
+            "accrual at 1:{} from a {} minute carryover: {} minutes accrued, {} used, {} refused, {} carried over",
+            input.statutory.accrual_hours_worked,
+            input.carryover_minutes,
+            accrual.granted_minutes,
+            accrual.used_minutes,
+            accrual.denied_minutes,
+            accrual.carryover_minutes
+        ),
+        format!(
+            "frontloading {} minutes: {} used, {} refused, nothing carried over",
+            frontload.granted_minutes, frontload.used_minutes, frontload.denied_minutes
+        ),
+    ];
+
+    let fewer_denials = match accrual.denied_minutes.cmp(&frontload.denied_minutes) {
+        std::cmp::Ordering::Less => "accrual",
+        std::cmp::Ordering::Greater => "frontload",
+        std::cmp::Ordering::Equal => "equal",
+    };
+    explain.push(format!("fewer refusals: {}", fewer_denials));
+
+    Ok(SimulationOutput {
+        employer_size: input.employer_size,
+        usage_limit_minutes: usage_limit,
+        accrual,
+        frontload,
+        fewer_denials: fewer_denials.to_string(),
+        explain,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A year of 40-hour weeks with the given planned use per week
+    fn year(employer_size: &str, used: impl Fn(usize) -> u64) -> SimulationInput {
+        SimulationInput {
+            employer_size: employer_size.into(),
+            periods: (0..52)
+                .map(|week| ProjectedPeriod { minutes_worked: 40 * 60, minutes_used: used(week) })
+                .collect(),
+            carryover_minutes: 0,
+            statutory: Statutory::default(),
+        }
+    }
+
+    #[test]
+    fn full_time_year_without_use() {
+        let out = simulate(year("large", |_| 0)).unwrap();
+        // 2080 hours at 1:30 accrue 69h20m
+        assert_eq!(out.accrual.granted_minutes, 4160);
+        assert_eq!(out.accrual.carryover_minutes, 4160);
+        assert_eq!(out.frontload.granted_minutes, 72 * 60);
+        assert_eq!(out.frontload.carryover_minutes, 0);
+        assert_eq!(out.fewer_denials, "equal");
+    }
+
+    #[test]
+    fn early_use_is_refused_only_under_accrual() {
+        // A full day off in the second week
+        let out = simulate(year("small", |week| if week == 1 { 8 * 60 } else { 0 })).unwrap();
+        assert_eq!(out.usage_limit_minutes, 40 * 60);
+        assert_eq!(out.accrual.balances[0], 80);
+        assert_eq!((out.accrual.used_minutes, out.accrual.denied_minutes), (80, 400));
+        assert_eq!((out.frontload.used_minutes, out.frontload.denied_minutes), (480, 0));
+        assert_eq!(out.frontload.ending_balance_minutes, 40 * 60 - 480);
+        assert_eq!(out.fewer_denials, "frontload");
+    }
+
+    #[test]
+    fn usage_limit_applies_to_both_methods() {
+        let mut input = year("small", |week| if week >= 40 { 8 * 60 } else { 0 });
+        input.carryover_minutes = 40 * 60;
+        let out = simulate(input).unwrap();
+        assert_eq!(out.accrual.used_minutes, 40 * 60);
+        assert_eq!(out.frontload.used_minutes, 40 * 60);
+        assert_eq!(out.accrual.denied_minutes, out.frontload.denied_minutes);
+        // Accrual carries its unused balance over, capped at the limit
+        assert_eq!(out.accrual.carryover_minutes, 40 * 60);
+    }
+
+    #[test]
+    fn fractions_of_a_minute_are_not_lost() {
+        let input = SimulationInput {
+            employer_size: "large".into(),
+            periods: vec![ProjectedPeriod { minutes_worked: 45, minutes_used: 0 }; 2],
+            carryover_minutes: 0,
+            statutory: Statutory::default(),
+        };
+        let out = simulate(input).unwrap();
+        assert_eq!(out.accrual.balances, vec![1, 3]);
+    }
+
+    #[test]
+    fn json_round_trip_and_errors() {
+        let envelope: Envelope<SimulationOutput> = serde_json::from_slice(&simulate_json_slice(
+            br#"{"employer_size":"large","periods":[{"minutes_worked":600}]}"#,
+        ))
+        .unwrap();
+        assert!(envelope.ok);
+        assert_eq!(envelope.data.unwrap().accrual.granted_minutes, 20);
+
+        let code = |input: &[u8]| {
+            let envelope: Envelope<SimulationOutput> = serde_json::from_slice(&simulate_json_slice(input)).unwrap();
+            assert!(!envelope.ok && envelope.data.is_none());
+            envelope.error.unwrap().code
+        };
+        assert_eq!(code(br#"{"employer_size":"medium","periods":[{"minutes_worked":60}]}"#), "INVALID_INPUT");
+        assert_eq!(code(br#"{"employer_size":"small","periods":[]}"#), "INVALID_INPUT");
+        assert_eq!(code(b""), "INVALID_INPUT");
+        assert!(simulate_json(std::ptr::null(), 4).is_null());
+    }
+}
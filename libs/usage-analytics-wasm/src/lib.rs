@@ -0,0 +1,336 @@
+// Usage pattern analytics compiled to WASM
+// Exposes `analyze_usage_json`, which flags statistically unusual sick time
+// usage patterns for one employee. Results are informational insights only:
+// they never change balances or block usage. Every decision is recorded in an
+// explain trace so the reasoning can be reviewed.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Day of the week, as sent by the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    fn is_workday(self) -> bool {
+        !matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+
+    /// Monday or Friday: usage that extends a weekend
+    fn is_weekend_adjacent(self) -> bool {
+        matches!(self, Weekday::Monday | Weekday::Friday)
+    }
+}
+
+/// Sick time used on one day
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UsageDay {
+    /// Date as YYYY-MM-DD
+    pub date: String,
+    pub weekday: Weekday,
+    pub minutes: u64,
+}
+
+/// When a pattern is reported
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Thresholds {
+    /// Fewest workday usage days before any pattern is considered
+    pub min_usage_days: u32,
+    /// Largest p-value reported as unusual
+    pub max_p_value: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            min_usage_days: 6,
+            max_p_value: 0.01,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnalysisInput {
+    pub employee_id: String,
+    pub usage: Vec<UsageDay>,
+    #[serde(default)]
+    pub thresholds: Thresholds,
+}
+
+/// An unusual pattern; informational only
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Insight {
+    pub kind: String,
+    pub severity: String,
+    pub summary: String,
+    pub usage_days: u32,
+    pub matching_days: u32,
+    pub expected_share: f64,
+    pub observed_share: f64,
+    pub p_value: f64,
+}
+
+/// Output with deterministic serialization using BTreeMap for consistent key ordering
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnalysisOutput {
+    pub employee_id: String,
+    pub insights: Vec<Insight>,
+    /// Each step of the analysis, in order
+    pub explain: Vec<String>,
+    pub metadata: BTreeMap<String, Value>,
+}
+
+/// Kernel ABI version this module is built against, read by the host at load.
+#[no_mangle]
+pub extern "C" fn __abi_version() -> i32 {
+    1
+}
+
+/// Memory allocation for WASM host communication.
+/// Memory is zero-initialized to prevent potential information leakage.
+#[no_mangle]
+pub extern "C" fn alloc(size: usize) -> *mut u8 {
+    let mut buf = vec![0u8; size];
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Free memory allocated by alloc.
+///
+/// # Safety
+/// The caller must ensure ptr was allocated by alloc with the given size.
+#[no_mangle]
+pub unsafe extern "C" fn dealloc(ptr: *mut u8, size: usize) {
+    if !ptr.is_null() {
+        let _ = Vec::from_raw_parts(ptr, 0, size);
+    }
+}
+
+/// Maximum allowed input size (1MB) to prevent resource exhaustion
+const MAX_INPUT_SIZE: usize = 1_048_576;
+
+/// Read a JSON request from guest memory, rejecting null or oversized input.
+fn read_input<'a>(input_ptr: *const u8, input_len: usize) -> Option<&'a [u8]> {
+    if input_ptr.is_null() || input_len == 0 || input_len > MAX_INPUT_SIZE {
+        return None;
+    }
+
+    // Safety: We've validated the pointer is non-null and size is reasonable
+    Some(unsafe { std::slice::from_raw_parts(input_ptr, input_len) })
+}
+
+/// Copy a JSON response into a freshly allocated, length-prefixed buffer.
+fn write_output(result: &[u8]) -> *const u8 {
+    let len = result.len();
+    let ptr = alloc(4 + len);
+
+    unsafe {
+        // Write length as first 4 bytes (little-endian)
+        std::ptr::copy_nonoverlapping((len as u32).to_le_bytes().as_ptr(), ptr, 4);
+        std::ptr::copy_nonoverlapping(result.as_ptr(), ptr.add(4), len);
+    }
+
+    ptr
+}
+
+/// Analyze one employee's usage based on input JSON.
+/// Uses the same pointer/length protocol as the accrual engine.
+/// Returns a null pointer if the input is invalid.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)] // FFI export; pointer is validated in read_input
+pub extern "C" fn analyze_usage_json(input_ptr: *const u8, input_len: usize) -> *const u8 {
+    let Some(input_slice) = read_input(input_ptr, input_len) else {
+        return std::ptr::null();
+    };
+
+    let Ok(input) = serde_json::from_slice::<AnalysisInput>(input_slice) else {
+        return std::ptr::null();
+    };
+    let result = serde_json::to_vec(&analyze_usage(input)).unwrap_or_else(|_| b"{}".to_vec());
+    write_output(&result)
+}
+
+/// Probability of at least `k` successes in `n` trials with success probability `p`
+fn binomial_upper_tail(n: u32, k: u32, p: f64) -> f64 {
+    // pmf(i + 1) = pmf(i) * (n - i) / (i + 1) * p / (1 - p), starting from pmf(0) = (1 - p)^n
+    let mut pmf = (1.0 - p).powi(n as i32);
+    let mut tail = if k == 0 { pmf } else { 0.0 };
+    for i in 0..n {
+        pmf *= (n - i) as f64 / (i + 1) as f64 * p / (1.0 - p);
+        if i + 1 >= k {
+            tail += pmf;
+        }
+    }
+    tail.min(1.0)
+}
+
+/// Round for stable, readable output
+fn round4(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+/// Pure function for usage analysis.
+/// Deterministic: identical inputs always produce identical outputs.
+pub fn analyze_usage(input: AnalysisInput) -> AnalysisOutput {
+    let mut explain = Vec::new();
+    let thresholds = &input.thresholds;
+
+    // Several entries on one date count as a single usage day
+    let mut days: BTreeMap<&str, Weekday> = BTreeMap::new();
+    for day in input.usage.iter().filter(|d| d.minutes > 0) {
+        days.insert(day.date.as_str(), day.weekday);
+    }
+    let workdays: Vec<Weekday> = days.values().copied().filter(|w| w.is_workday()).collect();
+    explain.push(format!(
+        "{} distinct usage days, {} on workdays (weekend days are excluded)",
+        days.len(),
+        workdays.len()
+    ));
+
+    let mut by_weekday: BTreeMap<Weekday, u32> = BTreeMap::new();
+    for weekday in &workdays {
+        *by_weekday.entry(*weekday).or_default() += 1;
+    }
+    explain.push(format!(
+        "Usage days by weekday: {}",
+        by_weekday
+            .iter()
+            .map(|(weekday, count)| format!("{:?}={}", weekday, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+
+    let mut insights = Vec::new();
+    let n = workdays.len() as u32;
+    if n < thresholds.min_usage_days {
+        explain.push(format!(
+            "Fewer than {} workday usage days; no pattern is assessed",
+            thresholds.min_usage_days
+        ));
+    } else {
+        let k = workdays.iter().filter(|w| w.is_weekend_adjacent()).count() as u32;
+        let expected = 2.0 / 5.0;
+        let observed = k as f64 / n as f64;
+        let p_value = binomial_upper_tail(n, k, expected);
+        explain.push(format!(
+            "{} of {} workday usage days are Monday or Friday ({:.1}%); {:.1}% expected if usage were spread evenly",
+            k,
+            n,
+            observed * 100.0,
+            expected * 100.0
+        ));
+        explain.push(format!(
+            "Binomial test: probability of {} or more such days by chance is {:.6} (threshold {})",
+            k, p_value, thresholds.max_p_value
+        ));
+
+        if observed > expected && p_value <= thresholds.max_p_value {
+            explain.push("Pattern reported as an informational insight".to_string());
+            insights.push(Insight {
+                kind: "weekend_adjacent_usage".to_string(),
+                severity: "informational".to_string(),
+                summary: format!(
+                    "{} of {} sick days fell on a Monday or Friday, more than would be expected by chance",
+                    k, n
+                ),
+                usage_days: n,
+                matching_days: k,
+                expected_share: expected,
+                observed_share: round4(observed),
+                p_value: round4(p_value),
+            });
+        } else {
+            explain.push("Within the expected range; nothing reported".to_string());
+        }
+    }
+
+    let mut metadata = BTreeMap::new();
+    metadata.insert("source".to_string(), Value::String("usage-analytics.wasm".to_string()));
+    metadata.insert("version".to_string(), Value::String("0.1.0".to_string()));
+
+    AnalysisOutput {
+        employee_id: input.employee_id,
+        insights,
+        explain,
+        metadata,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(days: &[(&str, Weekday)]) -> Vec<UsageDay> {
+        days.iter()
+            .map(|(date, weekday)| UsageDay {
+                date: date.to_string(),
+                weekday: *weekday,
+                minutes: 480,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn binomial_tail() {
+        assert!((binomial_upper_tail(5, 0, 0.4) - 1.0).abs() < 1e-12);
+        assert!((binomial_upper_tail(1, 1, 0.4) - 0.4).abs() < 1e-12);
+        // P(X >= 8 | n = 8, p = 0.4) = 0.4^8
+        assert!((binomial_upper_tail(8, 8, 0.4) - 0.4f64.powi(8)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn flags_monday_friday_pattern() {
+        use Weekday::*;
+        let days = [
+            ("2025-01-03", Friday), ("2025-01-13", Monday), ("2025-01-24", Friday),
+            ("2025-02-03", Monday), ("2025-02-14", Friday), ("2025-02-24", Monday),
+            ("2025-03-07", Friday), ("2025-03-12", Wednesday),
+        ];
+        let out = analyze_usage(AnalysisInput {
+            employee_id: "e1".into(),
+            usage: usage(&days),
+            thresholds: Thresholds::default(),
+        });
+
+        assert_eq!(out.insights.len(), 1);
+        let insight = &out.insights[0];
+        assert_eq!((insight.usage_days, insight.matching_days), (8, 7));
+        assert_eq!(insight.severity, "informational");
+        assert!(insight.p_value <= 0.01);
+        assert!(out.explain.iter().any(|step| step.contains("Binomial test")));
+    }
+
+    #[test]
+    fn even_or_sparse_usage_is_not_flagged() {
+        use Weekday::*;
+        let even = [
+            ("2025-01-06", Monday), ("2025-01-14", Tuesday), ("2025-01-22", Wednesday),
+            ("2025-01-30", Thursday), ("2025-02-07", Friday), ("2025-02-11", Tuesday),
+        ];
+        let out = analyze_usage(AnalysisInput {
+            employee_id: "e1".into(),
+            usage: usage(&even),
+            thresholds: Thresholds::default(),
+        });
+        assert!(out.insights.is_empty());
+
+        let sparse = [("2025-01-03", Friday), ("2025-01-13", Monday), ("2025-01-13", Monday)];
+        let out = analyze_usage(AnalysisInput {
+            employee_id: "e1".into(),
+            usage: usage(&sparse),
+            thresholds: Thresholds::default(),
+        });
+        assert!(out.insights.is_empty());
+        assert!(out.explain[0].starts_with("2 distinct usage days"));
+    }
+}